@@ -40,14 +40,74 @@
 #define FLUXIO_HTTP_VERSION_2 20
 
 /*
- Sentinel value to return from a read or write callback that the operation
- is pending.
+ Decode `gzip` response bodies.
+ */
+#define FLUXIO_DECOMPRESS_GZIP 1
+
+/*
+ Decode `deflate` response bodies.
+ */
+#define FLUXIO_DECOMPRESS_DEFLATE 2
+
+/*
+ Decode `br` response bodies.
+ */
+#define FLUXIO_DECOMPRESS_BR 4
+
+/*
+ Decode `zstd` response bodies.
+ */
+#define FLUXIO_DECOMPRESS_ZSTD 8
+
+/*
+ Don't compress the request body.
+ */
+#define FLUXIO_COMPRESS_NONE 0
+
+/*
+ Compress the request body with `gzip`.
+ */
+#define FLUXIO_COMPRESS_GZIP 1
+
+/*
+ Compress the request body with `deflate`.
+ */
+#define FLUXIO_COMPRESS_DEFLATE 2
+
+/*
+ Compress the request body with `br`.
+ */
+#define FLUXIO_COMPRESS_BR 3
+
+/*
+ Compress the request body with `zstd`.
+ */
+#define FLUXIO_COMPRESS_ZSTD 4
+
+/*
+ Never add a `Host` header to requests.
+ */
+#define FLUXIO_AUTO_HOST_NEVER 0
+
+/*
+ Add a `Host` header to requests that don't have one.
+ */
+#define FLUXIO_AUTO_HOST_IF_MISSING 1
+
+/*
+ Always set the `Host` header of requests, replacing any they have.
+ */
+#define FLUXIO_AUTO_HOST_OVERRIDE 2
+
+/*
+ Sentinel value to return from a read, write, flush or shutdown callback
+ that the operation is pending.
  */
 #define FLUXIO_IO_PENDING 4294967295
 
 /*
- Sentinel value to return from a read or write callback that the operation
- has errored.
+ Sentinel value to return from a read, write, flush or shutdown callback
+ that the operation has errored.
  */
 #define FLUXIO_IO_ERROR 4294967294
 
@@ -105,8 +165,95 @@ typedef enum fluxio_code
    The peer sent an HTTP message that could not be parsed.
    */
   FLUXIO_INVALID_PEER_MESSAGE,
+  /*
+   The peer sent more informational (1xx) responses than allowed.
+   */
+  FLUXIO_TOO_MANY_INFORMATIONAL,
+  /*
+   The head of a response didn't arrive before the response header
+   timeout.
+   */
+  FLUXIO_RESPONSE_HEADER_TIMEOUT,
+  /*
+   A response body didn't receive data before the body idle timeout.
+   */
+  FLUXIO_BODY_IDLE_TIMEOUT,
+  /*
+   The connection was closed, such as after its idle timeout, so no more
+   requests can be sent on it.
+   */
+  FLUXIO_CONN_CLOSED,
+  /*
+   The peer sent a message with more headers than allowed.
+   */
+  FLUXIO_TOO_MANY_HEADERS,
+  /*
+   The peer sent a header value larger than allowed.
+   */
+  FLUXIO_HEADER_VALUE_TOO_LARGE,
+  /*
+   A body was larger than its maximum size.
+   */
+  FLUXIO_BODY_TOO_LARGE,
 } fluxio_code;
 
+/*
+ The kind of an event passed to a `fluxio_conn_event_callback`.
+ */
+typedef enum fluxio_conn_event
+{
+  /*
+   The connection was opened. The value is always `0`.
+   */
+  FLUXIO_CONN_EVENT_CONNECT,
+  /*
+   The connection was closed. The value is `1` if it was closed because
+   of an error, and `0` otherwise.
+   */
+  FLUXIO_CONN_EVENT_CLOSE,
+  /*
+   A request is about to be sent. The value is always `0`.
+   */
+  FLUXIO_CONN_EVENT_REQUEST_START,
+  /*
+   A response head was received. The value is its status code.
+   */
+  FLUXIO_CONN_EVENT_RESPONSE_HEADERS,
+  /*
+   A response body was fully received. The value is always `0`.
+   */
+  FLUXIO_CONN_EVENT_BODY_COMPLETE,
+} fluxio_conn_event;
+
+/*
+ The kind of a metric passed to a `fluxio_metrics_callback`.
+ */
+typedef enum fluxio_metric
+{
+  /*
+   Bytes were read from the connection. The value is how many.
+   */
+  FLUXIO_METRIC_BYTES_READ,
+  /*
+   Bytes were written to the connection. The value is how many.
+   */
+  FLUXIO_METRIC_BYTES_WRITTEN,
+  /*
+   A response head was received. The value is the time since its request
+   was sent, in microseconds.
+   */
+  FLUXIO_METRIC_REQUEST_LATENCY,
+  /*
+   The connection handshake completed. The value is how long it took, in
+   microseconds.
+   */
+  FLUXIO_METRIC_HANDSHAKE_DURATION,
+  /*
+   An HTTP/2 stream was reset. The value is always `1`.
+   */
+  FLUXIO_METRIC_STREAM_RESET,
+} fluxio_metric;
+
 /*
  A descriptor for what type a `fluxio_task` value is.
  */
@@ -132,6 +279,10 @@ typedef enum fluxio_task_return_type
    The value of this task is `fluxio_buf *`.
    */
   FLUXIO_TASK_BUF,
+  /*
+   The value of this task is `fluxio_upgraded *`.
+   */
+  FLUXIO_TASK_UPGRADED,
 } fluxio_task_return_type;
 
 /*
@@ -139,11 +290,26 @@ typedef enum fluxio_task_return_type
  */
 typedef struct fluxio_body fluxio_body;
 
+/*
+ A reader copying the data of a `fluxio_body` into buffers.
+ */
+typedef struct fluxio_body_reader fluxio_body_reader;
+
+/*
+ The sending half of a body created with `fluxio_body_channel`.
+ */
+typedef struct fluxio_body_sender fluxio_body_sender;
+
 /*
  A buffer of bytes that is sent or received on a `fluxio_body`.
  */
 typedef struct fluxio_buf fluxio_buf;
 
+/*
+ A growable buffer of bytes, finished into a `fluxio_buf`.
+ */
+typedef struct fluxio_buf_builder fluxio_buf_builder;
+
 /*
  An HTTP client connection handle.
 
@@ -173,6 +339,11 @@ typedef struct fluxio_error fluxio_error;
  */
 typedef struct fluxio_executor fluxio_executor;
 
+/*
+ A thread driving a `fluxio_executor`.
+ */
+typedef struct fluxio_executor_thread fluxio_executor_thread;
+
 /*
  An HTTP header map.
 
@@ -195,28 +366,134 @@ typedef struct fluxio_request fluxio_request;
  */
 typedef struct fluxio_response fluxio_response;
 
+/*
+ An event received on a Server-Sent Events body.
+ */
+typedef struct fluxio_sse_event fluxio_sse_event;
+
 /*
  An async task.
  */
 typedef struct fluxio_task fluxio_task;
 
+/*
+ An upgraded IO stream, such as after a CONNECT tunnel was established or
+ a `101 Switching Protocols` response was received.
+ */
+typedef struct fluxio_upgraded fluxio_upgraded;
+
 /*
  A waker that is saved and used to waken a pending task.
+
+ Wakers can be sent to, woken and freed on any thread, not only the one
+ polling the executor.
  */
 typedef struct fluxio_waker fluxio_waker;
 
+/*
+ A buffer passed to a `fluxio_io_writev_callback`.
+ */
+typedef struct fluxio_iovec
+{
+  /*
+   The start of the buffer.
+   */
+  const uint8_t *buf;
+  /*
+   The length of the buffer.
+   */
+  size_t len;
+} fluxio_iovec;
+
+/*
+ The tasks handled by `fluxio_executor_shutdown`.
+ */
+typedef struct fluxio_executor_shutdown_stats
+{
+  /*
+   The number of tasks that completed while shutting down.
+   */
+  size_t completed;
+  /*
+   The number of tasks that hadn't completed by the deadline, and were
+   canceled.
+   */
+  size_t canceled;
+} fluxio_executor_shutdown_stats;
+
+/*
+ How long sending a request took, as returned by `fluxio_response_timings`.
+
+ Durations are in microseconds, or `-1` if unknown.
+ */
+typedef struct fluxio_request_timings
+{
+  /*
+   How long resolving the destination took.
+   */
+  int64_t dns_us;
+  /*
+   How long establishing the connection took.
+   */
+  int64_t connect_us;
+  /*
+   How long the TLS handshake took.
+   */
+  int64_t tls_us;
+  /*
+   How long it took from sending the request to receiving the head of
+   the response.
+   */
+  int64_t ttfb_us;
+  /*
+   `1` if the request was sent on a connection that had been used before,
+   `0` otherwise.
+   */
+  int reused;
+} fluxio_request_timings;
+
 typedef int (*fluxio_body_foreach_callback)(void *, const struct fluxio_buf *);
 
+typedef int (*fluxio_sse_event_callback)(void *, const struct fluxio_sse_event *);
+
+typedef void (*fluxio_buf_drop_callback)(void *, const uint8_t *, size_t);
+
 typedef int (*fluxio_body_data_callback)(void *, struct fluxio_context *, struct fluxio_buf **);
 
+typedef void (*fluxio_metrics_callback)(void *, enum fluxio_metric, uint64_t);
+
+typedef void (*fluxio_conn_event_callback)(void *, enum fluxio_conn_event, uint64_t, int);
+
+typedef int (*fluxio_interceptor_request_callback)(void *, struct fluxio_request *);
+
+typedef int (*fluxio_interceptor_response_callback)(void *, struct fluxio_response *);
+
 typedef void (*fluxio_request_on_informational_callback)(void *, struct fluxio_response *);
 
+typedef int (*fluxio_request_on_head_callback)(void *, const uint8_t *, size_t);
+
+typedef void (*fluxio_extension_drop_callback)(void *);
+
 typedef int (*fluxio_headers_foreach_callback)(void *, const uint8_t *, size_t, const uint8_t *, size_t);
 
 typedef size_t (*fluxio_io_read_callback)(void *, struct fluxio_context *, uint8_t *, size_t);
 
 typedef size_t (*fluxio_io_write_callback)(void *, struct fluxio_context *, const uint8_t *, size_t);
 
+typedef void (*fluxio_io_cork_callback)(void *, int);
+
+typedef size_t (*fluxio_io_writev_callback)(void *, struct fluxio_context *, const struct fluxio_iovec *, size_t);
+
+typedef size_t (*fluxio_io_flush_callback)(void *, struct fluxio_context *);
+
+typedef size_t (*fluxio_io_shutdown_callback)(void *, struct fluxio_context *);
+
+typedef void (*fluxio_executor_task_callback)(void *, struct fluxio_task *);
+
+typedef void (*fluxio_task_on_complete_callback)(void *, struct fluxio_task *);
+
+typedef int (*fluxio_executor_dump_callback)(void *, uint64_t, const uint8_t *, size_t, uint64_t);
+
 #ifdef __cplusplus
 extern "C"
 {
@@ -253,6 +530,19 @@ extern "C"
    */
   struct fluxio_task *fluxio_body_data(struct fluxio_body *body);
 
+  /*
+   Return a task that will collect all data of the body into a single
+   buffer.
+
+   The task value is a `FLUXIO_TASK_BUF` with the data, or a
+   `FLUXIO_TASK_ERROR`. A body with more than `max_len` bytes of data
+   fails with the code `FLUXIO_BODY_TOO_LARGE`; pass `0` for no limit.
+   The buffer is preallocated from the length of the body, if known.
+
+   This will consume the `fluxio_body *`, you shouldn't use it anymore or free it.
+   */
+  struct fluxio_task *fluxio_body_collect(struct fluxio_body *body, size_t max_len);
+
   /*
    Return a task that will poll the body and execute the callback with each
    body chunk that is received.
@@ -269,6 +559,26 @@ extern "C"
                                           fluxio_body_foreach_callback func,
                                           void *userdata);
 
+  /*
+   Return a task that will poll the body as a Server-Sent Events stream,
+   and execute the callback with each event that is received.
+
+   The `fluxio_sse_event` pointer is only a borrowed reference, it cannot
+   live outside the execution of the callback. You must copy any data you
+   wish to persist.
+
+   The callback should return `FLUXIO_ITER_CONTINUE` to continue iterating
+   events as they are received, or `FLUXIO_ITER_BREAK` to cancel.
+
+   A line longer than 256 KiB, or an event with more than 1 MiB of data,
+   fails the task with an error.
+
+   This will consume the `fluxio_body *`, you shouldn't use it anymore or free it.
+   */
+  struct fluxio_task *fluxio_body_foreach_event(struct fluxio_body *body,
+                                                fluxio_sse_event_callback func,
+                                                void *userdata);
+
   /*
    Set userdata on this body, which will be passed to callback functions.
    */
@@ -297,6 +607,163 @@ extern "C"
    */
   void fluxio_body_set_data_func(struct fluxio_body *body, fluxio_body_data_callback func);
 
+  /*
+   Set the maximum size of the data of this body, in bytes.
+
+   Reading more data fails with the code `FLUXIO_BODY_TOO_LARGE`, as
+   does reading a body declaring a larger length, such as with
+   `Content-Length`, before any of it is read.
+   */
+  enum fluxio_code fluxio_body_set_max_size(struct fluxio_body *body, uint64_t max);
+
+  /*
+   Set the bounds of the length of the data of this body, in bytes.
+
+   An `upper` of `UINT64_MAX` means the length has no upper bound. If
+   both are equal, the body is sent with a `Content-Length` header
+   instead of chunked encoding, and must have exactly that much data.
+
+   Returns `FLUXIO_INVALID_ARG` if `upper` is less than `lower`.
+   */
+  enum fluxio_code fluxio_body_set_size_hint(struct fluxio_body *body,
+                                             uint64_t lower,
+                                             uint64_t upper);
+
+  /*
+   Create a body whose data is sent with a `fluxio_body_sender`.
+
+   The body buffers up to `capacity` buffers that were sent but not yet
+   received; a `capacity` of `0` is the same as `1`. The sending half is
+   written to `sender`, and must be freed with `fluxio_body_sender_free`
+   to end the body, or `fluxio_body_sender_abort` to abort it.
+
+   Returns `NULL` if `capacity` is larger than `SIZE_MAX / 4`.
+   */
+  struct fluxio_body *fluxio_body_channel(size_t capacity, struct fluxio_body_sender **sender);
+
+  /*
+   Set the watermarks of the data buffered in the channel, in bytes.
+
+   Once `high` bytes were sent but not yet received, the sender isn't
+   ready again until no more than `low` bytes remain buffered.
+   `fluxio_body_sender_try_send` only checks the capacity of the channel.
+
+   Setting `high` to 0 disables the watermarks, which is the default.
+   Returns `FLUXIO_INVALID_ARG` if `low` is larger than `high`.
+   */
+  enum fluxio_code fluxio_body_sender_set_watermarks(struct fluxio_body_sender *sender,
+                                                     size_t low,
+                                                     size_t high);
+
+  /*
+   Check whether the sender can send more data.
+
+   Returns `FLUXIO_POLL_READY` if it can, or `FLUXIO_POLL_ERROR` if the
+   body was dropped. Otherwise, the waker of the `fluxio_context *` is
+   registered to be woken once it can, and `FLUXIO_POLL_PENDING` is
+   returned.
+   */
+  int fluxio_body_sender_poll_ready(struct fluxio_body_sender *sender, struct fluxio_context *ctx);
+
+  /*
+   Try to send a buffer of data without waiting.
+
+   On success, this consumes the `fluxio_buf *`. If the channel is full
+   or the body was dropped, `FLUXIO_ERROR` is returned, and the buffer is
+   still owned by the caller.
+   */
+  enum fluxio_code fluxio_body_sender_try_send(struct fluxio_body_sender *sender,
+                                               struct fluxio_buf *buf);
+
+  /*
+   Return a task that sends a buffer of data once the sender is ready.
+
+   The task value is `FLUXIO_TASK_EMPTY` once the data is sent, or
+   `FLUXIO_TASK_ERROR` if the body was dropped.
+
+   This consumes the `fluxio_buf *`. It does not consume the
+   `fluxio_body_sender *`, but it MUST NOT be used or freed until the
+   related task completes.
+   */
+  struct fluxio_task *fluxio_body_sender_send(struct fluxio_body_sender *sender,
+                                              struct fluxio_buf *buf);
+
+  /*
+   Abort the body, so it ends with an error instead of normally.
+
+   This consumes the `fluxio_body_sender *`, you shouldn't use it anymore
+   or free it.
+   */
+  void fluxio_body_sender_abort(struct fluxio_body_sender *sender);
+
+  /*
+   Free a `fluxio_body_sender *`, ending the body normally.
+   */
+  void fluxio_body_sender_free(struct fluxio_body_sender *sender);
+
+  /*
+   Create a reader of the data of a body.
+
+   Instead of a `fluxio_buf *` per chunk, the reader copies the data into
+   buffers of your choosing, from within callbacks that are passed a
+   `fluxio_context *`, such as the data callback of another body.
+
+   This will consume the `fluxio_body *`, you shouldn't use it anymore or free it.
+   */
+  struct fluxio_body_reader *fluxio_body_reader_new(struct fluxio_body *body);
+
+  /*
+   Free a `fluxio_body_reader *`, and the body it reads.
+   */
+  void fluxio_body_reader_free(struct fluxio_body_reader *reader);
+
+  /*
+   Read data of the body into `buf`.
+
+   Returns the number of bytes copied into `buf`, which is `0` once the
+   body has no more data, or if `buf_len` is `0`.
+
+   If no data is available yet, the waker of the `fluxio_context *` is
+   registered to be woken once there is, and `FLUXIO_IO_PENDING` is
+   returned. If reading the body fails, or `buf` is `NULL` while `buf_len`
+   isn't `0`, `FLUXIO_IO_ERROR` is returned.
+   */
+  size_t fluxio_body_reader_read(struct fluxio_body_reader *reader,
+                                 struct fluxio_context *ctx,
+                                 uint8_t *buf,
+                                 size_t buf_len);
+
+  /*
+   Get the id of this event.
+
+   This is the last id sent on the stream, which may be the id of an
+   earlier event. The buffer is empty if no id was sent.
+
+   The `fluxio_buf` is borrowed from the event.
+   */
+  const struct fluxio_buf *fluxio_sse_event_id(const struct fluxio_sse_event *event);
+
+  /*
+   Get the type of this event, `message` if it wasn't set.
+
+   The `fluxio_buf` is borrowed from the event.
+   */
+  const struct fluxio_buf *fluxio_sse_event_type(const struct fluxio_sse_event *event);
+
+  /*
+   Get the data of this event.
+
+   The `fluxio_buf` is borrowed from the event.
+   */
+  const struct fluxio_buf *fluxio_sse_event_data(const struct fluxio_sse_event *event);
+
+  /*
+   Get the reconnection time sent along with this event, in milliseconds.
+
+   Returns `-1` if the event didn't have one.
+   */
+  int64_t fluxio_sse_event_retry(const struct fluxio_sse_event *event);
+
   /*
    Create a new `fluxio_buf *` by copying the provided bytes.
 
@@ -307,6 +774,34 @@ extern "C"
    */
   struct fluxio_buf *fluxio_buf_copy(const uint8_t *buf, size_t len);
 
+  /*
+   Create a new `fluxio_buf *` borrowing the provided bytes, without
+   copying them.
+
+   The bytes must stay valid and unchanged for as long as the program
+   runs, such as string literals or a file mapped for good.
+
+   This returns `NULL` if `buf` is `NULL` and `len` isn't 0.
+   */
+  struct fluxio_buf *fluxio_buf_from_static(const uint8_t *buf, size_t len);
+
+  /*
+   Create a new `fluxio_buf *` taking ownership of the provided bytes,
+   without copying them.
+
+   The bytes must stay valid and unchanged until fluxio releases them, by
+   calling `drop_fn` with `userdata`, `buf` and `len`. That happens once
+   this buffer, every buffer cloned or sliced from it, and every body
+   made from them were freed, from whichever thread frees the last one.
+
+   This returns `NULL` if `buf` is `NULL` and `len` isn't 0, without
+   calling `drop_fn`.
+   */
+  struct fluxio_buf *fluxio_buf_from_owned(const uint8_t *buf,
+                                           size_t len,
+                                           fluxio_buf_drop_callback drop_fn,
+                                           void *userdata);
+
   /*
    Get a pointer to the bytes in this buffer.
 
@@ -329,81 +824,523 @@ extern "C"
   void fluxio_buf_free(struct fluxio_buf *buf);
 
   /*
-   Starts an HTTP client connection handshake using the provided IO transport
-   and options.
+   Create a new `fluxio_buf *` sharing the bytes of this buffer.
+
+   The bytes aren't copied, only a reference count is incremented, so
+   either buffer can be freed independently of the other.
+   */
+  struct fluxio_buf *fluxio_buf_clone(const struct fluxio_buf *buf);
+
+  /*
+   Create a new `fluxio_buf *` of `len` bytes of this buffer, starting at
+   `start`.
+
+   The bytes aren't copied, so a part of a received chunk can be kept
+   after freeing the chunk, without keeping a copy of it.
+
+   This returns `NULL` if the range isn't within the buffer.
+   */
+  struct fluxio_buf *fluxio_buf_slice(const struct fluxio_buf *buf, size_t start, size_t len);
+
+  /*
+   Create a body whose data is the bytes of this buffer.
+
+   The bytes aren't copied, so a received chunk can be sent back out,
+   such as when proxying.
+
+   This consumes the `fluxio_buf *`, you shouldn't use it anymore or free it.
+   */
+  struct fluxio_body *fluxio_buf_into_body(struct fluxio_buf *buf);
+
+  /*
+   Create a new, empty `fluxio_buf_builder *` with room for at least
+   `capacity` bytes.
+
+   Bytes are appended with `fluxio_buf_builder_append`, and the builder is
+   turned into a `fluxio_buf *` with `fluxio_buf_builder_finish`, without
+   copying them again.
+
+   This returns `NULL` if allocating a new builder fails.
+   */
+  struct fluxio_buf_builder *fluxio_buf_builder_new(size_t capacity);
+
+  /*
+   Append a copy of the provided bytes to the builder.
+
+   The `buf` argument can be freed or changed afterwards.
+   */
+  enum fluxio_code fluxio_buf_builder_append(struct fluxio_buf_builder *builder,
+                                             const uint8_t *buf,
+                                             size_t len);
+
+  /*
+   Reserve room for at least `additional` more bytes in the builder.
+   */
+  enum fluxio_code fluxio_buf_builder_reserve(struct fluxio_buf_builder *builder,
+                                              size_t additional);
+
+  /*
+   Get the number of bytes appended to the builder so far.
+   */
+  size_t fluxio_buf_builder_len(const struct fluxio_buf_builder *builder);
+
+  /*
+   Turn the builder into a `fluxio_buf *` holding the appended bytes.
+
+   This consumes the `fluxio_buf_builder *`, you shouldn't use it anymore
+   or free it.
+   */
+  struct fluxio_buf *fluxio_buf_builder_finish(struct fluxio_buf_builder *builder);
+
+  /*
+   Free a builder, discarding the bytes appended to it.
+   */
+  void fluxio_buf_builder_free(struct fluxio_buf_builder *builder);
+
+  /*
+   Starts an HTTP client connection handshake using the provided IO transport
+   and options.
+
+   Both the `io` and the `options` are consumed in this function call.
+
+   The returned `fluxio_task *` must be polled with an executor until the
+   handshake completes, at which point the value can be taken.
+
+   If ALPN protocols were set with `fluxio_clientconn_options_alpn`, the
+   HTTP version is picked from the protocol the `io` negotiated.
+   */
+  struct fluxio_task *fluxio_clientconn_handshake(struct fluxio_io *io,
+                                                  struct fluxio_clientconn_options *options);
+
+  /*
+   Send a request on the client connection.
+
+   Returns a task that needs to be polled until it is ready. When ready, the
+   task yields a `fluxio_response *`.
+
+   If the connection is closed already, such as after its idle timeout,
+   the task yields a `fluxio_error *` with the `FLUXIO_CONN_CLOSED` code.
+
+   The interceptors added with `fluxio_clientconn_options_add_interceptor`
+   are called with the request right away.
+   */
+  struct fluxio_task *fluxio_clientconn_send(struct fluxio_clientconn *conn, struct fluxio_request *req);
+
+  /*
+   Establish a tunnel through the client connection with a `CONNECT`
+   request.
+
+   `authority` is the `host:port` to tunnel to, sent as the target and
+   `Host` header of the request, which has no body. The interceptors
+   added with `fluxio_clientconn_options_add_interceptor` are called as
+   with `fluxio_clientconn_send`.
+
+   Returns a task that needs to be polled until it is ready. When ready,
+   the task yields a `fluxio_upgraded *` to read and write through the
+   tunnel, once a `2xx` response was received. A response with any other
+   status yields a `fluxio_error *` instead, as does an error sending the
+   request.
+
+   Returns NULL if `authority` is not a valid authority.
+   */
+  struct fluxio_task *fluxio_clientconn_connect_tunnel(struct fluxio_clientconn *conn,
+                                                       const uint8_t *authority,
+                                                       size_t authority_len);
+
+  /*
+   Returns a task that completes once the initial HTTP/2 SETTINGS were
+   exchanged with the peer.
+
+   The handshake only sends the connection preface, so the peer's limits
+   may not be known yet when it completes. Awaiting this task before
+   sending a burst of requests makes sure they are.
+
+   The task yields `FLUXIO_TASK_EMPTY` when complete, or a `fluxio_error *`
+   if the connection closed before that. On HTTP/1 connections, it
+   completes immediately.
+
+   This does not consume the `fluxio_clientconn *`.
+   */
+  struct fluxio_task *fluxio_clientconn_settings_exchanged(struct fluxio_clientconn *conn);
+
+  /*
+   Returns a task that completes once the connection is ready to send
+   another request.
+
+   An HTTP/1 connection is ready once the previous response was
+   received, and an HTTP/2 connection once the peer's limit on
+   concurrent streams allows another one. Sending before that queues
+   the request, or fails if one is queued already.
+
+   The task yields `FLUXIO_TASK_EMPTY` when ready, or a `fluxio_error *`
+   if the connection closed.
+
+   This does not consume the `fluxio_clientconn *`.
+   */
+  struct fluxio_task *fluxio_clientconn_poll_ready(struct fluxio_clientconn *conn);
+
+  /*
+   Returns `1` if the connection is ready to send another request right
+   away, and `0` otherwise.
+
+   See `fluxio_clientconn_poll_ready` to wait until it is.
+   */
+  int fluxio_clientconn_is_ready(const struct fluxio_clientconn *conn);
+
+  /*
+   Returns `1` if the connection is closed, and no more requests can be
+   sent on it, and `0` otherwise.
+   */
+  int fluxio_clientconn_is_closed(const struct fluxio_clientconn *conn);
+
+  /*
+   Returns a task that sends a PING, and completes once the peer
+   acknowledged it, to check that an HTTP/2 connection is still
+   responsive.
+
+   If a PING is in flight already, such as one sent for keep-alive, this
+   waits for that one instead. The task doesn't time out on its own, so
+   it's typically raced against a `fluxio_timer_new` task.
+
+   The task yields `FLUXIO_TASK_EMPTY` once acknowledged, or a
+   `fluxio_error *` if the connection closed first. On HTTP/1
+   connections, which have no PINGs, it fails right away.
+
+   This does not consume the `fluxio_clientconn *`.
+   */
+  struct fluxio_task *fluxio_clientconn_ping(struct fluxio_clientconn *conn);
+
+  /*
+   Get the address of the peer of this connection, as set on its IO
+   transport with `fluxio_io_set_connected_info`.
+
+   The address is formatted as `ip:port`, in a new `fluxio_buf *` that
+   must be freed with `fluxio_buf_free`. Returns `NULL` if the address
+   is unknown.
+   */
+  struct fluxio_buf *fluxio_clientconn_peer_addr(const struct fluxio_clientconn *conn);
+
+  /*
+   Get the local address of this connection, as set on its IO transport
+   with `fluxio_io_set_connected_info`.
+
+   The address is formatted as `ip:port`, in a new `fluxio_buf *` that
+   must be freed with `fluxio_buf_free`. Returns `NULL` if the address
+   is unknown.
+   */
+  struct fluxio_buf *fluxio_clientconn_local_addr(const struct fluxio_clientconn *conn);
+
+  /*
+   Free a `fluxio_clientconn *`.
+   */
+  void fluxio_clientconn_free(struct fluxio_clientconn *conn);
+
+  /*
+   Creates a new set of HTTP clientconn options to be used in a handshake.
+   */
+  struct fluxio_clientconn_options *fluxio_clientconn_options_new(void);
+
+  /*
+   Set the whether or not header case is preserved.
+
+   Pass `0` to allow lowercase normalization (default), `1` to retain original case.
+   */
+  void fluxio_clientconn_options_set_preserve_header_case(struct fluxio_clientconn_options *opts,
+                                                          int enabled);
+
+  /*
+   Set the whether or not header order is preserved.
+
+   Pass `0` to allow reordering (default), `1` to retain original ordering.
+   */
+  void fluxio_clientconn_options_set_preserve_header_order(struct fluxio_clientconn_options *opts,
+                                                           int enabled);
+
+  /*
+   Free a `fluxio_clientconn_options *`.
+   */
+  void fluxio_clientconn_options_free(struct fluxio_clientconn_options *opts);
+
+  /*
+   Set the client background task executor.
+
+   This does not consume the `options` or the `exec`.
+   */
+  void fluxio_clientconn_options_exec(struct fluxio_clientconn_options *opts,
+                                      const struct fluxio_executor *exec);
+
+  /*
+   Set the whether to use HTTP2.
+
+   Pass `0` to disable, `1` to enable.
+   */
+  enum fluxio_code fluxio_clientconn_options_http2(struct fluxio_clientconn_options *opts, int enabled);
+
+  /*
+   Set the protocols offered with ALPN, so the HTTP version is picked
+   from the one the transport negotiated.
+
+   The `protos` are in the ALPN wire format, each protocol prefixed by
+   its length, as also passed to TLS libraries, such as
+   `"\x02h2\x08http/1.1"`. Only `h2`, `http/1.1` and `http/1.0` are
+   supported.
+
+   When handshaking, the connection uses HTTP/2 if the IO reported `h2`
+   with `fluxio_io_set_alpn`, and HTTP/1.1 otherwise, regardless of
+   `fluxio_clientconn_options_http2`.
+
+   Returns `FLUXIO_INVALID_ARG` if the list is malformed or contains an
+   unsupported protocol, and `FLUXIO_FEATURE_NOT_ENABLED` if it contains
+   `h2` without HTTP/2 support.
+   */
+  enum fluxio_code fluxio_clientconn_options_alpn(struct fluxio_clientconn_options *opts,
+                                                  const uint8_t *protos,
+                                                  size_t len);
+
+  /*
+   Set the whether to include a copy of the raw headers in responses
+   received on this connection.
+
+   Pass `0` to disable, `1` to enable.
+
+   If enabled, see `fluxio_response_headers_raw()` for usage.
+   */
+  enum fluxio_code fluxio_clientconn_options_headers_raw(struct fluxio_clientconn_options *opts,
+                                                         int enabled);
+
+  /*
+   Set the maximum number of informational (1xx) responses accepted
+   before the final response to a request.
+
+   A server sending more fails the request with the code
+   `FLUXIO_TOO_MANY_INFORMATIONAL`. Default is 10.
+   */
+  enum fluxio_code fluxio_clientconn_options_max_informational(struct fluxio_clientconn_options *opts,
+                                                               size_t max);
+
+  /*
+   Set the maximum number of headers of a response.
+
+   A server sending more fails the request with the code
+   `FLUXIO_TOO_MANY_HEADERS`. Default is 100.
+   */
+  enum fluxio_code fluxio_clientconn_options_max_headers(struct fluxio_clientconn_options *opts,
+                                                         size_t max);
+
+  /*
+   Set the maximum size of a response header value, in bytes.
+
+   A server sending a larger one fails the request with the code
+   `FLUXIO_HEADER_VALUE_TOO_LARGE`. Default is no limit other than the
+   size of the read buffer.
+   */
+  enum fluxio_code fluxio_clientconn_options_max_header_value_size(struct fluxio_clientconn_options *opts,
+                                                                   size_t max);
+
+  /*
+   Set whether to accept obsolete line folding in response headers.
+
+   Pass `0` to reject folded headers, `1` to accept them. If accepted,
+   each continuation line is unfolded into the header value, replacing
+   the line break with spaces, as RFC 7230 requires of user agents.
+
+   Default is to reject them.
+   */
+  enum fluxio_code fluxio_clientconn_options_allow_obsolete_multiline_headers(struct fluxio_clientconn_options *opts,
+                                                                             int enabled);
+
+  /*
+   Set a callback for the metrics measured on this connection.
+
+   The callback is called with the `userdata`, the kind of the metric,
+   and its value. It is called while the connection is being processed,
+   and so should return quickly.
+   */
+  enum fluxio_code fluxio_clientconn_options_metrics_callback(struct fluxio_clientconn_options *opts,
+                                                              fluxio_metrics_callback callback,
+                                                              void *userdata);
+
+  /*
+   Set a callback for the lifecycle events of this connection.
+
+   The callback is called with the `userdata`, the kind of the event,
+   the id of the connection, and a value depending on the event. It is
+   called while the connection is being processed, and so should return
+   quickly.
+   */
+  enum fluxio_code fluxio_clientconn_options_event_callback(struct fluxio_clientconn_options *opts,
+                                                            fluxio_conn_event_callback callback,
+                                                            void *userdata);
+
+  /*
+   Add an interceptor to the requests sent on this connection, and their
+   responses.
+
+   `before_send` is called with the `userdata` and each request right
+   before it is sent, and can change it like any other request, such as
+   setting headers or its URI. `after_response` is called with the
+   `userdata` and each response before the task sending its request
+   yields it. Either callback can be `NULL`.
+
+   The callbacks should return `FLUXIO_ITER_CONTINUE`, or
+   `FLUXIO_ITER_BREAK` to fail the request with
+   `FLUXIO_ABORTED_BY_CALLBACK` instead. Requests aborted before being
+   sent leave the connection usable.
+
+   Interceptors see requests in the order they were added, and responses
+   in the reverse order, so the first one added wraps all others.
+
+   NOTE: The `fluxio_request *` and `fluxio_response *` are just
+   borrowed, and will not be valid after the callback finishes.
+   */
+  enum fluxio_code fluxio_clientconn_options_add_interceptor(struct fluxio_clientconn_options *opts,
+                                                             fluxio_interceptor_request_callback before_send,
+                                                             fluxio_interceptor_response_callback after_response,
+                                                             void *userdata);
+
+  /*
+   Set which content codings of response bodies are decoded.
+
+   The `flags` are a combination of the `FLUXIO_DECOMPRESS_` constants,
+   or `0` to leave bodies alone, which is the default. The enabled
+   codings are advertised in `Accept-Encoding` when a request doesn't
+   have one already, and the `Content-Encoding` and `Content-Length`
+   headers are removed from decoded responses.
+   */
+  enum fluxio_code fluxio_clientconn_options_decompress(struct fluxio_clientconn_options *opts,
+                                                        int flags);
+
+  /*
+   Set whether a `Host` header is added to requests, derived from the
+   authority of their URI.
 
-   Both the `io` and the `options` are consumed in this function call.
+   The `mode` is one of the `FLUXIO_AUTO_HOST_` constants, and defaults
+   to `FLUXIO_AUTO_HOST_NEVER`. The host is kept as written, followed by
+   the port unless that is the default one of the scheme. Requests whose
+   URI has no authority, like `/path`, are left alone. Internationalized
+   domain names must be in their punycode form in the URI.
 
-   The returned `fluxio_task *` must be polled with an executor until the
-   handshake completes, at which point the value can be taken.
+   This is applied after the interceptors' `before_send` callbacks.
    */
-  struct fluxio_task *fluxio_clientconn_handshake(struct fluxio_io *io,
-                                                  struct fluxio_clientconn_options *options);
+  enum fluxio_code fluxio_clientconn_options_auto_host(struct fluxio_clientconn_options *opts,
+                                                       int mode);
 
   /*
-   Send a request on the client connection.
+   Set how long to wait for the head of a response, in milliseconds,
+   from when its request is sent.
 
-   Returns a task that needs to be polled until it is ready. When ready, the
-   task yields a `fluxio_response *`.
+   A request timing out fails with `FLUXIO_RESPONSE_HEADER_TIMEOUT`.
+   This doesn't limit how long the body takes to arrive, see
+   `fluxio_clientconn_options_body_idle_timeout`.
+
+   Pass `0` to disable the timeout, which is the default.
    */
-  struct fluxio_task *fluxio_clientconn_send(struct fluxio_clientconn *conn, struct fluxio_request *req);
+  enum fluxio_code fluxio_clientconn_options_response_header_timeout(struct fluxio_clientconn_options *opts,
+                                                                     uint64_t timeout_ms);
 
   /*
-   Free a `fluxio_clientconn *`.
+   Set how long a response body can be waited on without receiving
+   data, in milliseconds.
+
+   The timer starts each time the body is polled and no data is ready. If
+   it elapses, the body fails with `FLUXIO_BODY_IDLE_TIMEOUT`.
+
+   Pass `0` to disable the timeout, which is the default.
    */
-  void fluxio_clientconn_free(struct fluxio_clientconn *conn);
+  enum fluxio_code fluxio_clientconn_options_body_idle_timeout(struct fluxio_clientconn_options *opts,
+                                                               uint64_t timeout_ms);
 
   /*
-   Creates a new set of HTTP clientconn options to be used in a handshake.
+   Set a rate limit, in bytes per second, for sending request bodies.
+
+   Body chunks are paced with a token bucket holding a second worth of
+   bytes, so a large upload doesn't saturate a constrained uplink. Chunks
+   aren't split, so a chunk larger than the rate is sent at once, and
+   delays the next one instead. This only affects HTTP/1 connections.
+
+   Pass `0` to disable the limit, which is the default.
    */
-  struct fluxio_clientconn_options *fluxio_clientconn_options_new(void);
+  enum fluxio_code fluxio_clientconn_options_upload_rate_limit(struct fluxio_clientconn_options *opts,
+                                                               uint64_t bytes_per_sec);
 
   /*
-   Set the whether or not header case is preserved.
+   Set an interval, in milliseconds, for sending HTTP/2 PING frames to
+   keep the connection alive.
 
-   Pass `0` to allow lowercase normalization (default), `1` to retain original case.
+   A PING is sent once no frame was received for this long. If it isn't
+   acknowledged within the timeout set with
+   `fluxio_clientconn_options_http2_keep_alive_timeout`, the connection
+   is closed.
+
+   Pass `0` to disable keep-alive, which is the default.
    */
-  void fluxio_clientconn_options_set_preserve_header_case(struct fluxio_clientconn_options *opts,
-                                                          int enabled);
+  enum fluxio_code fluxio_clientconn_options_http2_keep_alive_interval(struct fluxio_clientconn_options *opts,
+                                                                       uint64_t interval_ms);
 
   /*
-   Set the whether or not header order is preserved.
+   Set how long to wait for a keep-alive PING to be acknowledged, in
+   milliseconds, before closing the connection.
 
-   Pass `0` to allow reordering (default), `1` to retain original ordering.
+   Does nothing unless an interval is set with
+   `fluxio_clientconn_options_http2_keep_alive_interval`. The default is
+   20 seconds, and `0` is an invalid argument.
    */
-  void fluxio_clientconn_options_set_preserve_header_order(struct fluxio_clientconn_options *opts,
-                                                           int enabled);
+  enum fluxio_code fluxio_clientconn_options_http2_keep_alive_timeout(struct fluxio_clientconn_options *opts,
+                                                                      uint64_t timeout_ms);
 
   /*
-   Free a `fluxio_clientconn_options *`.
+   Set whether HTTP/2 keep-alive PINGs are sent while no requests are in
+   flight.
+
+   Pass `0` to only send them while requests are in flight, which is the
+   default, or `1` to also send them while the connection is idle.
    */
-  void fluxio_clientconn_options_free(struct fluxio_clientconn_options *opts);
+  enum fluxio_code fluxio_clientconn_options_http2_keep_alive_while_idle(struct fluxio_clientconn_options *opts,
+                                                                         int enabled);
 
   /*
-   Set the client background task executor.
+   Set whether HTTP/1 connections are kept alive for more requests.
 
-   This does not consume the `options` or the `exec`.
+   If disabled, requests are sent with a `Connection: close` header, and
+   the connection closes once the first response was received.
+
+   Pass `1` to keep connections alive, which is the default, or `0` to
+   disable it.
    */
-  void fluxio_clientconn_options_exec(struct fluxio_clientconn_options *opts,
-                                      const struct fluxio_executor *exec);
+  enum fluxio_code fluxio_clientconn_options_http1_keepalive(struct fluxio_clientconn_options *opts,
+                                                             int enabled);
 
   /*
-   Set the whether to use HTTP2.
+   Set how long an HTTP/1 connection can go without a request in flight
+   before it is closed, in milliseconds.
 
-   Pass `0` to disable, `1` to enable.
+   The timer starts when the connection is established, and when a
+   response and its body were fully received. Once the connection is
+   closed, sending a request fails with `FLUXIO_CONN_CLOSED`. This only
+   affects HTTP/1 connections.
+
+   Pass `0` to disable the timeout, which is the default.
    */
-  enum fluxio_code fluxio_clientconn_options_http2(struct fluxio_clientconn_options *opts, int enabled);
+  enum fluxio_code fluxio_clientconn_options_idle_timeout_ms(struct fluxio_clientconn_options *opts,
+                                                             uint64_t timeout_ms);
 
   /*
-   Set the whether to include a copy of the raw headers in responses
-   received on this connection.
+   Configure connections with a versioned profile of hardened defaults,
+   with stricter parsing, smaller limits and a response header timeout.
 
-   Pass `0` to disable, `1` to enable.
+   Options set afterwards override the profile, and are counted by
+   `fluxio_clientconn_options_hardened_overridden`. Only version `1`
+   exists, others return `FLUXIO_INVALID_ARG`.
+   */
+  enum fluxio_code fluxio_clientconn_options_hardened(struct fluxio_clientconn_options *opts,
+                                                      uint32_t version);
 
-   If enabled, see `fluxio_response_headers_raw()` for usage.
+  /*
+   Returns how many options of the hardened profile were set again since
+   it was applied with `fluxio_clientconn_options_hardened`, or `-1` if
+   no profile was applied.
    */
-  enum fluxio_code fluxio_clientconn_options_headers_raw(struct fluxio_clientconn_options *opts,
-                                                         int enabled);
+  int fluxio_clientconn_options_hardened_overridden(const struct fluxio_clientconn_options *opts);
 
   /*
    Frees a `fluxio_error`.
@@ -489,6 +1426,21 @@ extern "C"
    */
   enum fluxio_code fluxio_request_set_version(struct fluxio_request *req, int version);
 
+  /*
+   Set whether to compress the body of this request when it is sent.
+
+   The encoding should be one of the `FLUXIO_COMPRESS_` constants, and
+   `FLUXIO_COMPRESS_NONE` turns compression back off. The level is
+   clamped to the range of the encoding, such as `0` to `9` for gzip.
+
+   The `Content-Encoding` header is set when the request is sent, and
+   any `Content-Length` header is removed. A request that already has a
+   `Content-Encoding` header is sent as it is.
+   */
+  enum fluxio_code fluxio_request_set_compression(struct fluxio_request *req,
+                                                  int encoding,
+                                                  int level);
+
   /*
    Gets a reference to the HTTP headers of this request
 
@@ -528,6 +1480,92 @@ extern "C"
                                                    fluxio_request_on_informational_callback callback,
                                                    void *data);
 
+  /*
+   Set a callback inspecting the head of the request right before it is
+   sent, and deciding whether to send it.
+
+   The callback is passed the `void *` data pointer, and the request
+   head exactly as it will be written to the connection, including the
+   request line and the headers added when sending it. This allows
+   auditing or signing what is sent.
+
+   The callback should return `FLUXIO_ITER_CONTINUE` to send the
+   request, or `FLUXIO_ITER_BREAK` to abort it. An aborted request
+   fails with `FLUXIO_ABORTED_BY_CALLBACK`, and closes the connection,
+   since nothing of it was written.
+
+   This is only called on HTTP/1 connections, since HTTP/2 doesn't send
+   the head as text.
+
+   NOTE: The head is just borrowed data, and will not be valid after the
+   callback finishes. You must copy any data you wish to persist.
+   */
+  enum fluxio_code fluxio_request_on_head(struct fluxio_request *req,
+                                          fluxio_request_on_head_callback callback,
+                                          void *data);
+
+  /*
+   Make a deep copy of this request.
+
+   The method, URI, version, and headers (including their original
+   casing and order) are copied, as well as the body if its data is
+   fully buffered. The copy shares nothing with the original, so it can
+   be handed to another thread, and both can be sent or freed
+   independently.
+
+   The informational (1xx) response and request head callbacks are not
+   copied, and the copy gets its own tracing span.
+
+   Returns `NULL` if the body is a streaming body (such as one using
+   `fluxio_body_set_data_func`), since its data cannot be copied.
+   */
+  struct fluxio_request *fluxio_request_clone(const struct fluxio_request *req);
+
+  /*
+   Get the id of the tracing span of this request.
+
+   Each request gets a `request` span, entered by fluxio while sending
+   it. Its id can be added to logs written by the application, to
+   correlate them with fluxio's own logs. Responses to this request,
+   including informational (1xx) ones passed to callbacks, carry the same
+   id, see `fluxio_response_span_id`.
+
+   Returns 0 if there is no span, such as when no tracing subscriber is
+   installed, or it isn't interested in this span.
+   */
+  uint64_t fluxio_request_span_id(const struct fluxio_request *req);
+
+  /*
+   Attach a value to this request under a key.
+
+   The value is an opaque pointer, owned by the request from now on:
+   `drop_fn` is called with it once the request or the response it is
+   moved to is freed, or when another value is set under the same key.
+   This allows middleware to pass data along with a request, without
+   keeping it in a global map.
+
+   When the request is sent with `fluxio_clientconn_send`, its values
+   are moved to the response, see `fluxio_response_extension`. They are
+   not copied by `fluxio_request_clone`.
+
+   Keys are compared byte by byte.
+   */
+  enum fluxio_code fluxio_request_set_extension(struct fluxio_request *req,
+                                                const uint8_t *key,
+                                                size_t key_len,
+                                                void *value,
+                                                fluxio_extension_drop_callback drop_fn);
+
+  /*
+   Get the value attached to this request under a key.
+
+   Returns `NULL` if no value was set under the key. The value is still
+   owned by the request.
+   */
+  void *fluxio_request_extension(const struct fluxio_request *req,
+                                 const uint8_t *key,
+                                 size_t key_len);
+
   /*
    Free an HTTP response after using it.
    */
@@ -560,6 +1598,20 @@ extern "C"
    */
   size_t fluxio_response_reason_phrase_len(const struct fluxio_response *resp);
 
+  /*
+   Set the reason-phrase of this response.
+
+   The phrase is copied, and replaces the canonical reason of the status
+   code when the response is written on an HTTP/1 connection.
+
+   Returns `FLUXIO_INVALID_ARG` if the phrase contains a byte not
+   allowed in a reason-phrase, such as a CR or LF. Tabs, spaces, visible
+   ASCII characters, and bytes above 0x7F are allowed.
+   */
+  enum fluxio_code fluxio_response_set_reason_phrase(struct fluxio_response *resp,
+                                                     const uint8_t *reason,
+                                                     size_t reason_len);
+
   /*
    Get a reference to the full raw headers of this response.
 
@@ -602,6 +1654,48 @@ extern "C"
    */
   struct fluxio_body *fluxio_response_body(struct fluxio_response *resp);
 
+  /*
+   Get the id of the tracing span of the request this response is for.
+
+   This is the same id returned by `fluxio_request_span_id` for the
+   request, or 0 if it had no span.
+   */
+  uint64_t fluxio_response_span_id(const struct fluxio_response *resp);
+
+  /*
+   Attach a value to this response under a key.
+
+   This works like `fluxio_request_set_extension`: the response owns the
+   value, and `drop_fn` is called with it once the response is freed, or
+   when another value is set under the same key.
+   */
+  enum fluxio_code fluxio_response_set_extension(struct fluxio_response *resp,
+                                                 const uint8_t *key,
+                                                 size_t key_len,
+                                                 void *value,
+                                                 fluxio_extension_drop_callback drop_fn);
+
+  /*
+   Get the value attached to this response, or to the request it is
+   for, under a key.
+
+   Returns `NULL` if no value was set under the key. The value is still
+   owned by the response.
+   */
+  void *fluxio_response_extension(const struct fluxio_response *resp,
+                                  const uint8_t *key,
+                                  size_t key_len);
+
+  /*
+   Get how long sending the request of this response took.
+
+   The durations of establishing the connection are never known for
+   connections handshaked with `fluxio_clientconn_handshake`, as the IO
+   is established by the caller. The durations are all `-1` if the
+   response wasn't received on a client connection.
+   */
+  struct fluxio_request_timings fluxio_response_timings(const struct fluxio_response *resp);
+
   /*
    Iterates the headers passing each name and value pair to the callback.
 
@@ -645,6 +1739,54 @@ extern "C"
    */
   struct fluxio_io *fluxio_io_new(void);
 
+  /*
+   Create a pair of IO transports connected to each other in memory.
+
+   Bytes written to one transport can be read from the other, so that
+   tests can drive full request and response cycles without sockets,
+   such as by handshaking a client connection on `client` and serving
+   `server`. Each direction buffers up to `capacity` bytes, after which
+   writes are pending until the other end reads.
+
+   The transports come with their user data and callbacks set, which
+   must not be changed. Once one end is freed, reads from the other end
+   reach the end of the stream, and writes to it error.
+
+   Returns `FLUXIO_INVALID_ARG` if `capacity` is 0 or an out pointer is
+   `NULL`.
+   */
+  enum fluxio_code fluxio_io_duplex_pair(size_t capacity,
+                                         struct fluxio_io **client,
+                                         struct fluxio_io **server);
+
+  /*
+   Wrap an IO transport to inject faults into its reads and writes, to
+   exercise how connections handle them.
+
+   The faults are picked with a pseudo-random generator seeded with
+   `seed`, so a failing schedule can be replayed by reusing its seed:
+
+   - `partial_writes` is the probability that a write only writes part
+     of its buffer,
+   - `delayed_reads` is the probability that a read is first pending,
+     waking the task right away,
+   - `spurious_wakeups` is the probability that the task is woken when
+     the transport is pending, although it is not ready,
+   - `eof_after` ends the stream once that many bytes were read. Pass
+     `UINT64_MAX` to never end it early.
+
+   Returns a new transport taking ownership of `io`, with the addresses
+   and ALPN protocol of `io`. Its user data and callbacks must not be
+   changed. Returns `NULL` if a probability is not between 0 and 1, in
+   which case `io` is not taken.
+   */
+  struct fluxio_io *fluxio_io_chaos_wrap(struct fluxio_io *io,
+                                         uint64_t seed,
+                                         double partial_writes,
+                                         double delayed_reads,
+                                         double spurious_wakeups,
+                                         uint64_t eof_after);
+
   /*
    Free an unused `fluxio_io *`.
 
@@ -697,6 +1839,111 @@ extern "C"
    */
   void fluxio_io_set_write(struct fluxio_io *io, fluxio_io_write_callback func);
 
+  /*
+   Set the write watermarks of this IO transport, so small writes are
+   coalesced before reaching the write callback.
+
+   When enabled, written data is buffered by fluxio until either `high`
+   bytes are buffered, or fluxio flushes the transport, such as at the
+   end of a message or a batch of HTTP/2 frames. Writes that are at least
+   `high` bytes long skip the buffer.
+
+   Once the buffer is full, fluxio writes it out to the write callback,
+   and won't buffer more data until no more than `low` bytes remain.
+
+   Setting `high` to 0 disables buffering, which is the default. Returns
+   `FLUXIO_INVALID_ARG` if `low` is larger than `high`.
+   */
+  enum fluxio_code fluxio_io_set_write_watermarks(struct fluxio_io *io, size_t low, size_t high);
+
+  /*
+   Set the cork function for this IO transport.
+
+   The function is called with `1` before fluxio starts writing a batch
+   of data, and with `0` once the batch is complete and the transport is
+   flushed. This lets the transport hold back small writes, such as with
+   `TCP_CORK` or by collecting them into a single TLS record, and send
+   them out together when uncorked.
+   */
+  void fluxio_io_set_cork(struct fluxio_io *io, fluxio_io_cork_callback func);
+
+  /*
+   Set the vectored write function for this IO transport.
+
+   The function is called with an array of buffers and their count,
+   which should be written to the transport in order, like with `writev`.
+   The number of bytes written, across all buffers, should be the
+   return value, or `FLUXIO_IO_PENDING` and `FLUXIO_IO_ERROR` as with
+   the write function.
+
+   When set, HTTP/1 connections queue large body chunks and pass them
+   along with the message head, instead of first copying them into a
+   single buffer for the write function. It is not used while write
+   watermarks are set, and must be set before the connection is
+   handshaked.
+   */
+  void fluxio_io_set_writev(struct fluxio_io *io, fluxio_io_writev_callback func);
+
+  /*
+   Set the flush function for this IO transport.
+
+   The function is called once fluxio has written out a batch of data,
+   such as a message or a set of HTTP/2 frames, so the transport can
+   send along anything it has buffered. It should return `0` once the
+   transport is flushed.
+
+   If the flush can't complete yet, a waker should be claimed from the
+   `ctx` and registered, and the return value should be
+   `FLUXIO_IO_PENDING`. The function is then called again once woken.
+   If there is an irrecoverable error, `FLUXIO_IO_ERROR` should be the
+   return value.
+
+   Without a flush function, flushing succeeds once fluxio's own write
+   buffer is written out.
+   */
+  void fluxio_io_set_flush(struct fluxio_io *io, fluxio_io_flush_callback func);
+
+  /*
+   Set the shutdown function for this IO transport.
+
+   The function is called when fluxio is done writing to the transport,
+   after it has been flushed, so the transport can close its write
+   side, such as sending a TLS `close_notify` alert. It should return
+   `0` once the shutdown is complete, or `FLUXIO_IO_PENDING` and
+   `FLUXIO_IO_ERROR` as with the flush function.
+
+   Without a shutdown function, shutting down only flushes the
+   transport.
+   */
+  void fluxio_io_set_shutdown(struct fluxio_io *io, fluxio_io_shutdown_callback func);
+
+  /*
+   Set the addresses of the connection this IO transport represents.
+
+   Each address is text in the form `ip:port`, such as `192.0.2.7:443`
+   or `[2001:db8::7]:443`, and is not NUL-terminated. A `NULL` address
+   is left unknown. Once handshaked, the addresses can be read back
+   with `fluxio_clientconn_peer_addr` and `fluxio_clientconn_local_addr`,
+   like the info a Rust connector reports when connecting.
+
+   Returns `FLUXIO_INVALID_ARG` if an address can't be parsed, in which
+   case neither address is changed.
+   */
+  enum fluxio_code fluxio_io_set_connected_info(struct fluxio_io *io,
+                                                const uint8_t *remote,
+                                                size_t remote_len,
+                                                const uint8_t *local,
+                                                size_t local_len);
+
+  /*
+   Set the protocol this IO transport negotiated with ALPN, such as
+   `h2`, without a length prefix.
+
+   Pass `NULL` if no protocol was agreed on, which is the default. This
+   is used by handshakes with `fluxio_clientconn_options_alpn` set.
+   */
+  void fluxio_io_set_alpn(struct fluxio_io *io, const uint8_t *proto, size_t len);
+
   /*
    Creates a new task executor.
    */
@@ -707,11 +1954,37 @@ extern "C"
    */
   void fluxio_executor_free(const struct fluxio_executor *exec);
 
+  /*
+   Shuts an executor down, giving its tasks until a deadline to complete,
+   and frees it.
+
+   New tasks, including those pushed by the executor's tasks, are freed
+   instead of being pushed. The pending tasks are polled until all of
+   them completed, or `deadline_ms` milliseconds passed, after which the
+   rest are canceled and freed. A `deadline_ms` of `0` waits for all of
+   them. Completed tasks are freed, or passed to the callback set with
+   `fluxio_task_on_complete`, while canceled tasks never are.
+
+   The calling thread sleeps while no task can make progress, so tasks
+   waiting on IO only complete if their wakers are called from other
+   threads meanwhile. If the executor is driven by
+   `fluxio_executor_run_in_thread`, the tasks aren't polled and are
+   canceled right away; stop the thread first.
+
+   Returns how many tasks completed and were canceled. This is the
+   recommended way to free an executor, over `fluxio_executor_free`.
+   */
+  struct fluxio_executor_shutdown_stats fluxio_executor_shutdown(const struct fluxio_executor *exec,
+                                                                 uint64_t deadline_ms);
+
   /*
    Push a task onto the executor.
 
    The executor takes ownership of the task, it should not be accessed
    again unless returned back to the user with `fluxio_executor_poll`.
+
+   Returns `FLUXIO_INVALID_ARG` if the executor is being shut down with
+   `fluxio_executor_shutdown`, in which case the task is freed.
    */
   enum fluxio_code fluxio_executor_push(const struct fluxio_executor *exec, struct fluxio_task *task);
 
@@ -725,6 +1998,164 @@ extern "C"
    */
   struct fluxio_task *fluxio_executor_poll(const struct fluxio_executor *exec);
 
+  /*
+   Print the tasks of the executor that haven't completed yet to a buffer.
+
+   Each task gets a line with its id, from `fluxio_task_id`, its label,
+   set with `fluxio_task_set_label`, and how long ago it was pushed onto
+   the executor. Connection tasks
+   spawned by fluxio itself are labeled `fluxio background task`. This is
+   meant for diagnosing tasks that never complete, the format may change.
+
+   If called from a callback of a task the executor is polling, only the
+   tasks pushed since the executor was last polled are listed.
+
+   The `dst_len` value must be the maximum length that the buffer can
+   store.
+
+   The return value is number of bytes that were written to `dst`, which
+   is `0` if `dst` is `NULL`.
+   */
+  size_t fluxio_executor_dump(const struct fluxio_executor *exec, uint8_t *dst, size_t dst_len);
+
+  /*
+   Calls `func` for each task of the executor that hasn't completed yet.
+
+   The callback is passed `userdata`, the id of the task, its label and
+   the length of the label, and how many milliseconds ago the task was
+   pushed onto the executor. A task without a label is passed a `NULL`
+   label of length `0`. The label is only valid during the call.
+
+   The callback should return `FLUXIO_ITER_CONTINUE` to keep iterating, or
+   `FLUXIO_ITER_BREAK` to stop. It may push new tasks, which aren't
+   listed.
+
+   If called from a callback of a task the executor is polling, only the
+   tasks pushed since the executor was last polled are listed.
+   */
+  void fluxio_executor_dump_tasks(const struct fluxio_executor *exec,
+                                  fluxio_executor_dump_callback func,
+                                  void *userdata);
+
+  /*
+   Returns the number of tasks pushed onto the executor that haven't
+   completed yet.
+
+   This includes the connection tasks spawned by fluxio itself. A count
+   that keeps growing in a long-running program usually means tasks are
+   never woken, or bodies are never consumed.
+   */
+  size_t fluxio_executor_task_count(const struct fluxio_executor *exec);
+
+  /*
+   Pushes a task onto the executor, and blocks the calling thread until
+   it completes.
+
+   The executor drives all of its tasks meanwhile, such as the
+   connection tasks the pushed task depends on. The thread sleeps while
+   no task can make progress, instead of polling in a loop. Other tasks
+   that complete meanwhile are returned by the next calls to
+   `fluxio_executor_poll`.
+
+   Returns the completed task, to be inspected with `fluxio_task_type`
+   and `fluxio_task_value`, and freed with `fluxio_task_free`. A
+   callback set with `fluxio_task_on_complete` is not called.
+
+   If `timeout_ms` isn't `0` and the task hasn't completed once that many
+   milliseconds passed, this returns `NULL`. The task stays on the
+   executor, and is returned by `fluxio_executor_poll` once it completes.
+
+   If the executor is driven by `fluxio_executor_run_in_thread`, or
+   blocked on by another thread, this returns `NULL` and the task is not
+   pushed.
+   */
+  struct fluxio_task *fluxio_executor_block_on(const struct fluxio_executor *exec,
+                                               struct fluxio_task *task,
+                                               uint64_t timeout_ms);
+
+  /*
+   Returns how many milliseconds are left until the next timer created
+   with `fluxio_timer_new` is due, or `-1` if there are no timers.
+
+   A loop polling the executor with `fluxio_executor_poll` should poll it
+   again at the latest after this timeout, such as by passing it to
+   `poll()` or `epoll_wait()`. A timer that is already due returns `0`.
+   */
+  int fluxio_executor_next_timer(const struct fluxio_executor *exec);
+
+  /*
+   Wakes the executor from any thread.
+
+   The thread spawned with `fluxio_executor_run_in_thread` polls the
+   tasks again, and a `fluxio_executor_poll` that is in progress on
+   another thread polls them again before returning. This can be used
+   to interrupt an executor after changing state that tasks depend on
+   without holding their wakers, such as when integrating with a loop
+   waiting on a condition variable.
+   */
+  void fluxio_executor_wake_external(const struct fluxio_executor *exec);
+
+  /*
+   Returns a file descriptor that is readable when the executor has tasks
+   to poll, so it can be added to an existing `poll()`, `epoll` or
+   `kqueue` loop.
+
+   Once it is readable, call `fluxio_executor_fd_ready`, and then
+   `fluxio_executor_poll` until it returns `NULL`. Timers don't signal the
+   descriptor, so the loop should also wait at most
+   `fluxio_executor_next_timer` milliseconds.
+
+   The descriptor is owned by the executor, and closed when it is freed.
+   It is created on the first call, readable right away, and the same
+   descriptor is returned by later calls. Returns `-1` if it couldn't be
+   created, or on platforms other than Unix.
+   */
+  int fluxio_executor_fd(const struct fluxio_executor *exec);
+
+  /*
+   Clears the readiness of the descriptor returned by
+   `fluxio_executor_fd`, once it was readable.
+
+   Returns `1` if the descriptor was signaled, meaning the executor
+   should be polled with `fluxio_executor_poll` until it returns `NULL`,
+   and `0` otherwise. Tasks woken from now on signal it again.
+   */
+  int fluxio_executor_fd_ready(const struct fluxio_executor *exec);
+
+  /*
+   Spawns a thread that drives the executor, so it doesn't have to be
+   polled with `fluxio_executor_poll`.
+
+   Each task that completes is passed to `func`, on the spawned thread,
+   along with `userdata`. The callback takes ownership of the task, and
+   must free it with `fluxio_task_free` once its value was taken. The
+   thread sleeps while no task can make progress, and is woken when a
+   task is woken or pushed with `fluxio_executor_push`.
+
+   While the thread runs, the executor must not be polled with
+   `fluxio_executor_poll`. Only one thread can drive an executor.
+
+   Returns `NULL` if the thread could not be spawned, or the executor
+   is already driven by a thread. Stop the thread with
+   `fluxio_executor_thread_stop`.
+   */
+  struct fluxio_executor_thread *fluxio_executor_run_in_thread(const struct fluxio_executor *exec,
+                                                               fluxio_executor_task_callback func,
+                                                               void *userdata);
+
+  /*
+   Stops a thread spawned with `fluxio_executor_run_in_thread`, and frees
+   it.
+
+   This waits for a completion callback that is running to return, and
+   for the thread to exit. Tasks that haven't completed stay on the
+   executor, and it can be polled again afterwards.
+
+   If called from a completion callback, the thread exits once the
+   callback returns, without waiting for it.
+   */
+  void fluxio_executor_thread_stop(struct fluxio_executor_thread *thread);
+
   /*
    Free a task.
    */
@@ -745,6 +2176,15 @@ extern "C"
    */
   enum fluxio_task_return_type fluxio_task_type(struct fluxio_task *task);
 
+  /*
+   Returns the id of this task.
+
+   Every task gets a different id when created, which stays the same
+   until it is freed. Ids start at `1`, and `0` is returned for a `NULL`
+   task. They match the ids listed by `fluxio_executor_dump`.
+   */
+  uint64_t fluxio_task_id(const struct fluxio_task *task);
+
   /*
    Set a user data pointer to be associated with this task.
 
@@ -758,11 +2198,53 @@ extern "C"
    */
   void *fluxio_task_userdata(struct fluxio_task *task);
 
+  /*
+   Set a label for this task, listed by `fluxio_executor_dump`.
+
+   The label is copied, and invalid UTF-8 replaced. Passing a `len` of `0`
+   removes the label.
+   */
+  void fluxio_task_set_label(struct fluxio_task *task, const uint8_t *label, size_t len);
+
+  /*
+   Set a callback called when this task completes, instead of the task
+   being returned by `fluxio_executor_poll`.
+
+   The callback is called with `userdata` and the completed task, on the
+   thread polling the executor, once that thread isn't polling other
+   tasks anymore. It takes ownership of the task: the value can be taken
+   with `fluxio_task_value`, and the task must be freed with
+   `fluxio_task_free`. The callback may push new tasks onto the executor.
+   */
+  void fluxio_task_on_complete(struct fluxio_task *task,
+                               fluxio_task_on_complete_callback func,
+                               void *userdata);
+
+  /*
+   Creates a task that completes once `ms` milliseconds passed.
+
+   The task is driven by the executor it's pushed onto, and its value is
+   `FLUXIO_TASK_EMPTY`. This can be used to build timeouts, or to do
+   something periodically, without a separate event loop. An executor
+   driven by `fluxio_executor_run_in_thread` or blocked on with
+   `fluxio_executor_block_on` wakes up for timers by itself. Loops
+   calling `fluxio_executor_poll` should use `fluxio_executor_next_timer`
+   to know when to poll again.
+   */
+  struct fluxio_task *fluxio_timer_new(uint64_t ms);
+
   /*
    Copies a waker out of the task context.
    */
   struct fluxio_waker *fluxio_context_waker(struct fluxio_context *cx);
 
+  /*
+   Clones a waker, such as to hand it to another thread.
+
+   Both wakers wake the same task, and each must be woken or freed.
+   */
+  struct fluxio_waker *fluxio_waker_clone(const struct fluxio_waker *waker);
+
   /*
    Free a waker that hasn't been woken.
    */
@@ -775,6 +2257,58 @@ extern "C"
    */
   void fluxio_waker_wake(struct fluxio_waker *waker);
 
+  /*
+   Take the upgraded connection of this response.
+
+   This should be called on a response with a `101 Switching Protocols`
+   status, or a successful response to a `CONNECT` request.
+
+   Returns a task that needs to be polled until it is ready. When ready,
+   the task yields a `fluxio_upgraded *`. If the connection could not be
+   upgraded, the task yields a `fluxio_error *` instead.
+
+   This does not consume the `fluxio_response *`, but it can only be
+   called once per response.
+   */
+  struct fluxio_task *fluxio_response_upgrade(struct fluxio_response *resp);
+
+  /*
+   Return a task that reads up to `max_len` bytes from the upgraded
+   connection.
+
+   The task value may have different types depending on the outcome:
+
+   - `FLUXIO_TASK_BUF`: Success, and some data was read.
+   - `FLUXIO_TASK_ERROR`: An error reading the data.
+   - `FLUXIO_TASK_EMPTY`: The connection has reached EOF.
+
+   This does not consume the `fluxio_upgraded *`, so it may be used again.
+   However, it MUST NOT be used or freed until the related task completes.
+   */
+  struct fluxio_task *fluxio_upgraded_read(struct fluxio_upgraded *upgraded, size_t max_len);
+
+  /*
+   Return a task that writes all of the provided bytes to the upgraded
+   connection, and then flushes it.
+
+   The bytes are copied, so the `buf` argument can be freed or changed
+   after this call returns.
+
+   The task yields `FLUXIO_TASK_EMPTY` once all bytes were written, or a
+   `fluxio_error *` if the write failed.
+
+   This does not consume the `fluxio_upgraded *`, so it may be used again.
+   However, it MUST NOT be used or freed until the related task completes.
+   */
+  struct fluxio_task *fluxio_upgraded_write(struct fluxio_upgraded *upgraded,
+                                            const uint8_t *buf,
+                                            size_t len);
+
+  /*
+   Free a `fluxio_upgraded *`, closing the connection.
+   */
+  void fluxio_upgraded_free(struct fluxio_upgraded *upgraded);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif // __cplusplus
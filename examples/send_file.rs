@@ -2,8 +2,6 @@
 
 use tokio::fs::File;
 
-use tokio_util::codec::{BytesCodec, FramedRead};
-
 use fluxio::service::{make_service_fn, service_fn};
 use fluxio::{Body, Method, Request, Response, Result, Server, StatusCode};
 
@@ -48,12 +46,12 @@ fn not_found() -> Response<Body> {
 }
 
 async fn simple_file_send(filename: &str) -> Result<Response<Body>> {
-    // Serve a file by asynchronously reading it by chunks using tokio-util crate.
+    // Serve a file by reading it in large chunks, with its length known up front.
 
     if let Ok(file) = File::open(filename).await {
-        let stream = FramedRead::new(file, BytesCodec::new());
-        let body = Body::wrap_stream(stream);
-        return Ok(Response::new(body));
+        if let Ok(body) = Body::from_file(file.into_std().await) {
+            return Ok(Response::new(body));
+        }
     }
 
     Ok(not_found())
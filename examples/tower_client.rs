@@ -3,14 +3,22 @@
 use fluxio::client::conn::Builder;
 use fluxio::client::connect::HttpConnector;
 use fluxio::client::service::Connect;
+use fluxio::header::{HeaderValue, USER_AGENT};
 use fluxio::service::Service;
 use fluxio::{Body, Request};
+use tower::util::MapRequestLayer;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     pretty_env_logger::init();
 
-    let mut mk_svc = Connect::new(HttpConnector::new(), Builder::new());
+    let mut mk_svc = Connect::new(HttpConnector::new(), Builder::new()).with_layer(
+        MapRequestLayer::new(|mut req: Request<Body>| {
+            req.headers_mut()
+                .insert(USER_AGENT, HeaderValue::from_static("tower-client"));
+            req
+        }),
+    );
 
     let uri = "http://127.0.0.1:8080".parse::<http::Uri>()?;
 
@@ -12,7 +12,8 @@ use crate::common::buf::BufList;
 ///
 /// Care needs to be taken if the remote is untrusted. The function doesn't implement any length
 /// checks and an malicious peer might make it consume arbitrary amounts of memory. Checking the
-/// `Content-Length` is a possibility, but it is not strictly mandated to be present.
+/// `Content-Length` is a possibility, but it is not strictly mandated to be present. Wrap the
+/// body in [`Limited`](crate::body::Limited) to bound how much of it is aggregated.
 pub async fn aggregate<T>(body: T) -> Result<impl Buf, T::Error>
 where
     T: HttpBody,
@@ -1,17 +1,47 @@
 use std::borrow::Cow;
-#[cfg(feature = "stream")]
 use std::error::Error as StdError;
 use std::fmt;
-
-use bytes::Bytes;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(all(
+    feature = "client",
+    feature = "runtime",
+    any(feature = "http1", feature = "http2")
+))]
+use std::time::Duration;
+
+use bytes::{Buf, Bytes};
 use futures_channel::mpsc;
 use futures_channel::oneshot;
 use futures_core::Stream; // for mpsc::Receiver
+#[cfg(feature = "sink")]
+use futures_sink::Sink;
+use futures_util::future;
+use futures_util::task::AtomicWaker;
 #[cfg(feature = "stream")]
 use futures_util::TryStreamExt;
 use http::HeaderMap;
 use http_body::{Body as HttpBody, SizeHint};
-
+use tokio::io::AsyncWrite;
+#[cfg(all(
+    feature = "client",
+    feature = "runtime",
+    any(feature = "http1", feature = "http2")
+))]
+use tokio::time::Sleep;
+
+#[cfg(feature = "compress")]
+use super::compress::Encoder;
+#[cfg(feature = "decompress")]
+use super::decompress::Decoder;
+#[cfg(feature = "runtime")]
+use super::file::FileBody;
+use super::limited::Limit;
+#[cfg(feature = "tokio-util")]
+use super::BodyReader;
+#[cfg(feature = "compress")]
+use super::ContentEncoding;
 use super::DecodedLength;
 #[cfg(feature = "stream")]
 use crate::common::sync_wrapper::SyncWrapper;
@@ -19,10 +49,12 @@ use crate::common::Future;
 #[cfg(all(feature = "client", any(feature = "http1", feature = "http2")))]
 use crate::common::Never;
 use crate::common::{task, watch, Pin, Poll};
+#[cfg(feature = "http1")]
+use crate::ext::ChunkExtensions;
 #[cfg(all(feature = "http2", any(feature = "client", feature = "server")))]
 use crate::proto::h2::ping;
 
-type BodySender = mpsc::Sender<Result<Bytes, crate::Error>>;
+type BodySender = mpsc::Sender<Result<Chunk, crate::Error>>;
 type TrailersSender = oneshot::Sender<HeaderMap>;
 
 /// A stream of `Bytes`, used when receiving bodies.
@@ -45,7 +77,7 @@ enum Kind {
     Chan {
         content_length: DecodedLength,
         want_tx: watch::Sender,
-        data_rx: mpsc::Receiver<Result<Bytes, crate::Error>>,
+        data_rx: mpsc::Receiver<Result<Chunk, crate::Error>>,
         trailers_rx: oneshot::Receiver<HeaderMap>,
     },
     #[cfg(all(feature = "http2", any(feature = "client", feature = "server")))]
@@ -56,6 +88,12 @@ enum Kind {
     },
     #[cfg(feature = "ffi")]
     Ffi(crate::ffi::UserBody),
+    #[cfg(feature = "decompress")]
+    Decoded(Box<Decoder>),
+    #[cfg(feature = "compress")]
+    Encoded(Box<Encoder>),
+    #[cfg(feature = "runtime")]
+    File(Box<FileBody>),
     #[cfg(feature = "stream")]
     Wrapped(
         SyncWrapper<
@@ -74,6 +112,30 @@ struct Extra {
     /// a brand new connection, since the pool didn't know about the idle
     /// connection yet.
     delayed_eof: Option<DelayEof>,
+    /// The data buffered in a channel created by `Body::channel_with_capacity`.
+    buffered: Option<Arc<Buffered>>,
+    /// Fails a response body waiting too long for its next chunk.
+    #[cfg(all(
+        feature = "client",
+        feature = "runtime",
+        any(feature = "http1", feature = "http2")
+    ))]
+    idle_timeout: Option<IdleTimeout>,
+    /// Fails the body once it read more data than allowed.
+    limit: Option<Limit>,
+    /// The size hint set with `Body::with_size_hint`, less the data read.
+    size_hint: Option<SizeHint>,
+    /// The extensions of the chunk last returned, if it had some.
+    #[cfg(feature = "http1")]
+    chunk_extensions: Option<ChunkExtensions>,
+}
+
+/// Data sent on a channel, with the extensions of the HTTP/1 chunk it was
+/// received in.
+struct Chunk {
+    data: Bytes,
+    #[cfg(feature = "http1")]
+    extensions: Option<ChunkExtensions>,
 }
 
 #[cfg(all(feature = "client", any(feature = "http1", feature = "http2")))]
@@ -92,6 +154,31 @@ enum DelayEof {
     Eof(DelayEofUntil),
 }
 
+/// The timer of a body's idle timeout.
+#[cfg(all(
+    feature = "client",
+    feature = "runtime",
+    any(feature = "http1", feature = "http2")
+))]
+struct IdleTimeout {
+    timeout: Duration,
+    sleep: Option<Pin<Box<Sleep>>>,
+    /// Whether `sleep` was reset since the body last returned a chunk.
+    armed: bool,
+}
+
+/// The data sent on a channel but not yet received, for its watermarks.
+///
+/// The watermarks are only used by the `Sender`, but are kept here so that
+/// a `Sender` stays small.
+struct Buffered {
+    bytes: AtomicUsize,
+    waker: AtomicWaker,
+    low_watermark: AtomicUsize,
+    high_watermark: AtomicUsize,
+    draining: AtomicBool,
+}
+
 /// A sender half created through [`Body::channel()`].
 ///
 /// Useful when wanting to stream chunks from another thread.
@@ -110,11 +197,16 @@ pub struct Sender {
     want_rx: watch::Receiver,
     data_tx: BodySender,
     trailers_tx: Option<TrailersSender>,
+    buffered: Option<Arc<Buffered>>,
 }
 
 const WANT_PENDING: usize = 1;
 const WANT_READY: usize = 2;
 
+/// The largest capacity of `Body::channel_with_capacity`, the `mpsc`
+/// channel asserting its buffer is smaller.
+pub(crate) const MAX_CHANNEL_CAPACITY: usize = usize::MAX >> 2;
+
 impl Body {
     /// Create an empty `Body` stream.
     ///
@@ -139,8 +231,81 @@ impl Body {
         Self::new_channel(DecodedLength::CHUNKED, /*wanter =*/ false)
     }
 
+    /// Create a `Body` stream with an associated sender half, buffering up
+    /// to `capacity` chunks.
+    ///
+    /// [`Sender::poll_ready()`] and [`Sender::send_data()`] wait once this
+    /// many chunks were sent but not yet received, and
+    /// [`Sender::try_send_data()`] fails. A `capacity` of `0` is the same as
+    /// `1`, the capacity of [`Body::channel()`].
+    ///
+    /// To also bound the buffered bytes, see [`Sender::set_watermarks()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is larger than `usize::MAX / 4`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::body::Bytes;
+    /// use fluxio::Body;
+    ///
+    /// let (mut tx, body) = Body::channel_with_capacity(2);
+    /// tx.try_send_data(Bytes::from("hello")).unwrap();
+    /// tx.try_send_data(Bytes::from(" world")).unwrap();
+    /// assert!(tx.try_send_data(Bytes::from("!")).is_err());
+    /// # drop(body);
+    /// ```
+    pub fn channel_with_capacity(capacity: usize) -> (Sender, Body) {
+        assert!(
+            capacity <= MAX_CHANNEL_CAPACITY,
+            "channel capacity must not be larger than usize::MAX / 4"
+        );
+        Self::new_buffered_channel(DecodedLength::CHUNKED, capacity)
+    }
+
+    /// Create a channel that the connection fills with up to about `max`
+    /// bytes of a response body before it is polled.
+    #[cfg(all(feature = "client", feature = "http1"))]
+    pub(crate) fn new_prefetch_channel(
+        content_length: DecodedLength,
+        max: usize,
+    ) -> (Sender, Body) {
+        let (mut tx, rx) = Self::new_buffered_channel(content_length, max);
+        tx.set_watermarks(max / 2, max);
+        (tx, rx)
+    }
+
+    fn new_buffered_channel(content_length: DecodedLength, capacity: usize) -> (Sender, Body) {
+        let buffered = Arc::new(Buffered {
+            bytes: AtomicUsize::new(0),
+            waker: AtomicWaker::new(),
+            low_watermark: AtomicUsize::new(0),
+            high_watermark: AtomicUsize::new(0),
+            draining: AtomicBool::new(false),
+        });
+        // The `mpsc` channel reserves one more slot for each sender.
+        Self::new_channel_with(
+            content_length,
+            /*wanter =*/ false,
+            capacity.saturating_sub(1),
+            Some(buffered),
+        )
+    }
+
+    #[cfg_attr(not(feature = "http1"), allow(dead_code))]
     pub(crate) fn new_channel(content_length: DecodedLength, wanter: bool) -> (Sender, Body) {
-        let (data_tx, data_rx) = mpsc::channel(0);
+        Self::new_channel_with(content_length, wanter, 0, None)
+    }
+
+    fn new_channel_with(
+        content_length: DecodedLength,
+        wanter: bool,
+        buffer: usize,
+        buffered: Option<Arc<Buffered>>,
+    ) -> (Sender, Body) {
+        let (data_tx, data_rx) = mpsc::channel(buffer);
         let (trailers_tx, trailers_rx) = oneshot::channel();
 
         // If wanter is true, `Sender::poll_ready()` won't becoming ready
@@ -153,13 +318,30 @@ impl Body {
             want_rx,
             data_tx,
             trailers_tx: Some(trailers_tx),
+            buffered: buffered.clone(),
         };
-        let rx = Body::new(Kind::Chan {
+        let mut rx = Body::new(Kind::Chan {
             content_length,
             want_tx,
             data_rx,
             trailers_rx,
         });
+        if buffered.is_some() {
+            rx.extra = Some(Box::new(Extra {
+                delayed_eof: None,
+                buffered,
+                #[cfg(all(
+                    feature = "client",
+                    feature = "runtime",
+                    any(feature = "http1", feature = "http2")
+                ))]
+                idle_timeout: None,
+                limit: None,
+                size_hint: None,
+                #[cfg(feature = "http1")]
+                chunk_extensions: None,
+            }));
+        }
 
         (tx, rx)
     }
@@ -197,10 +379,241 @@ impl Body {
         Body::new(Kind::Wrapped(SyncWrapper::new(Box::pin(mapped))))
     }
 
+    /// Compress a body with some content coding.
+    ///
+    /// The `level` is clamped to the range of the coding: up to `9` for
+    /// `gzip` and `deflate`, `11` for `br`, and `22` for `zstd`. The length
+    /// of the compressed body is unknown, so it is sent chunked over HTTP/1.
+    ///
+    /// This only changes the body, see [`compress_request`] to also update
+    /// the `Content-Encoding` and `Content-Length` headers of a request.
+    ///
+    /// [`compress_request`]: crate::body::compress_request
+    #[cfg(feature = "compress")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compress")))]
+    pub fn compressed(body: Body, encoding: ContentEncoding, level: u32) -> Body {
+        Body::new(Kind::Encoded(Box::new(Encoder::new(body, encoding, level))))
+    }
+
+    /// Create a body streaming the contents of a file.
+    ///
+    /// The body is the rest of the file from its current position, and its
+    /// exact length is known up front, so responses get a `Content-Length`
+    /// instead of being sent chunked. The file is read on the blocking
+    /// thread pool of the Tokio runtime, in large chunks that HTTP/1
+    /// connections write out as they are with vectored writes, without
+    /// copying them again into the write buffer.
+    ///
+    /// The body ends with an error if the file turns out to be shorter than
+    /// it was when the body was created.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the length or position of the file can't be
+    /// read.
+    ///
+    /// # Optional
+    ///
+    /// This function requires enabling the `runtime` feature in your
+    /// `Cargo.toml`, and the body must be polled from within a Tokio
+    /// runtime.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn from_file(file: std::fs::File) -> std::io::Result<Body> {
+        FileBody::new(file).map(|file| Body::new(Kind::File(Box::new(file))))
+    }
+
+    /// Limits this body to at most `max` bytes of data.
+    ///
+    /// Reading more than the limit fails with an error for which
+    /// [`Error::is_body_too_large`](crate::Error::is_body_too_large) is
+    /// true. A body declaring a larger length, such as with
+    /// `Content-Length`, fails before any of it is read. See
+    /// [`Limited`](crate::body::Limited) to limit other bodies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc() {
+    /// use fluxio::Body;
+    ///
+    /// let body = Body::from("hello world").limit(5);
+    /// let err = fluxio::body::to_bytes(body).await.unwrap_err();
+    /// assert!(err.is_body_too_large());
+    /// # }
+    /// ```
+    pub fn limit(mut self, max: u64) -> Body {
+        self.extra_mut().limit = Some(Limit::new(max));
+        self
+    }
+
+    /// Sets the exact length of the data of this body, in bytes.
+    ///
+    /// A body with an exact length is sent with a `Content-Length` header
+    /// instead of chunked encoding, which is useful for a body created with
+    /// [`Body::channel`] or from a stream when the length is known up front.
+    ///
+    /// The body must then have exactly `len` bytes of data, otherwise
+    /// sending it fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::body::HttpBody;
+    /// use fluxio::Body;
+    ///
+    /// let (_tx, body) = Body::channel();
+    /// let body = body.with_exact_size(11);
+    /// assert_eq!(body.size_hint().exact(), Some(11));
+    /// ```
+    pub fn with_exact_size(self, len: u64) -> Body {
+        self.with_size_hint(len, Some(len))
+    }
+
+    /// Sets the bounds of the length of the data of this body, in bytes.
+    ///
+    /// These replace what [`HttpBody::size_hint`] would otherwise return,
+    /// and are lowered as data is read. If both bounds are equal, this is
+    /// the same as [`Body::with_exact_size`].
+    ///
+    /// # Panics
+    ///
+    /// This panics if `upper` is less than `lower`.
+    pub fn with_size_hint(mut self, lower: u64, upper: Option<u64>) -> Body {
+        let mut hint = SizeHint::new();
+        hint.set_lower(lower);
+        if let Some(upper) = upper {
+            hint.set_upper(upper);
+        }
+        self.extra_mut().size_hint = Some(hint);
+        self
+    }
+
+    /// Returns the extensions of the chunk last returned by this body, if
+    /// it is an HTTP/1 chunked body and that chunk had some.
+    ///
+    /// If the last chunk, which has no data, has extensions, it is returned
+    /// as an empty chunk of data so that they can be read.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc(mut body: fluxio::Body) {
+    /// use fluxio::body::HttpBody;
+    ///
+    /// while let Some(chunk) = body.data().await {
+    ///     let chunk = chunk.unwrap();
+    ///     if let Some(extensions) = body.chunk_extensions() {
+    ///         println!("{} bytes with {:?}", chunk.len(), extensions);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn chunk_extensions(&self) -> Option<&ChunkExtensions> {
+        self.extra.as_ref()?.chunk_extensions.as_ref()
+    }
+
+    #[cfg(feature = "http1")]
+    fn set_chunk_extensions(&mut self, extensions: Option<ChunkExtensions>) {
+        match extensions {
+            Some(extensions) => self.extra_mut().chunk_extensions = Some(extensions),
+            None => {
+                if let Some(ref mut extra) = self.extra {
+                    extra.chunk_extensions = None;
+                }
+            }
+        }
+    }
+
+    /// Converts this body into an [`AsyncRead`] of its data.
+    ///
+    /// This allows piping a body into files, codecs, or anything else
+    /// taking an `AsyncRead`, without polling its chunks by hand.
+    ///
+    /// [`AsyncRead`]: tokio::io::AsyncRead
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc() -> std::io::Result<()> {
+    /// use fluxio::Body;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// let mut reader = Body::from("hello world").into_async_read();
+    /// let mut hello = String::new();
+    /// reader.read_to_string(&mut hello).await?;
+    /// assert_eq!(hello, "hello world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Optional
+    ///
+    /// This function requires enabling the `tokio-util` feature in your
+    /// `Cargo.toml`.
+    #[cfg(feature = "tokio-util")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+    pub fn into_async_read(self) -> BodyReader {
+        BodyReader::new(self)
+    }
+
+    /// Writes the data of this body to an [`AsyncWrite`], such as a file.
+    ///
+    /// Each chunk is written out before the next one is read, so a slow
+    /// writer slows down reading the body instead of buffering it in
+    /// memory. Trailers are ignored. The writer is flushed once the body
+    /// ended, but not shut down.
+    ///
+    /// Returns the number of bytes written. An error of the body is
+    /// returned as an `io::Error` wrapping it.
+    ///
+    /// [`AsyncWrite`]: tokio::io::AsyncWrite
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc() -> std::io::Result<()> {
+    /// use fluxio::Body;
+    ///
+    /// let mut out = Vec::new();
+    /// let copied = Body::from("hello world").copy_to(&mut out).await?;
+    /// assert_eq!(copied, 11);
+    /// assert_eq!(out, b"hello world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn copy_to<W>(mut self, dst: &mut W) -> io::Result<u64>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let mut copied = 0;
+        while let Some(chunk) = self.data().await {
+            let mut chunk = chunk.map_err(io::Error::other)?;
+            while chunk.has_remaining() {
+                let n =
+                    future::poll_fn(|cx| Pin::new(&mut *dst).poll_write(cx, chunk.chunk())).await?;
+                if n == 0 {
+                    return Err(io::ErrorKind::WriteZero.into());
+                }
+                chunk.advance(n);
+                copied += n as u64;
+            }
+        }
+        future::poll_fn(|cx| Pin::new(&mut *dst).poll_flush(cx)).await?;
+        Ok(copied)
+    }
+
     fn new(kind: Kind) -> Body {
         Body { kind, extra: None }
     }
 
+    #[cfg(feature = "decompress")]
+    pub(super) fn decoded(decoder: Decoder) -> Body {
+        Body::new(Kind::Decoded(Box::new(decoder)))
+    }
+
     #[cfg(all(feature = "http2", any(feature = "client", feature = "server")))]
     pub(crate) fn h2(
         recv: h2::RecvStream,
@@ -227,16 +640,42 @@ impl Body {
         self.extra_mut().delayed_eof = Some(DelayEof::NotEof(fut));
     }
 
+    #[cfg(all(
+        feature = "client",
+        feature = "runtime",
+        any(feature = "http1", feature = "http2")
+    ))]
+    pub(crate) fn idle_timeout(&mut self, timeout: Duration) {
+        self.extra_mut().idle_timeout = Some(IdleTimeout {
+            timeout,
+            sleep: None,
+            armed: false,
+        });
+    }
+
     fn take_delayed_eof(&mut self) -> Option<DelayEof> {
         self.extra
             .as_mut()
             .and_then(|extra| extra.delayed_eof.take())
     }
 
-    #[cfg(any(feature = "http1", feature = "http2"))]
     fn extra_mut(&mut self) -> &mut Extra {
-        self.extra
-            .get_or_insert_with(|| Box::new(Extra { delayed_eof: None }))
+        self.extra.get_or_insert_with(|| {
+            Box::new(Extra {
+                delayed_eof: None,
+                buffered: None,
+                #[cfg(all(
+                    feature = "client",
+                    feature = "runtime",
+                    any(feature = "http1", feature = "http2")
+                ))]
+                idle_timeout: None,
+                limit: None,
+                size_hint: None,
+                #[cfg(feature = "http1")]
+                chunk_extensions: None,
+            })
+        })
     }
 
     fn poll_eof(&mut self, cx: &mut task::Context<'_>) -> Poll<Option<crate::Result<Bytes>>> {
@@ -292,6 +731,19 @@ impl Body {
         }
     }
 
+    /// Copies this body, if all of its data is already buffered in memory.
+    #[cfg(any(
+        feature = "ffi",
+        all(feature = "client", any(feature = "http1", feature = "http2"))
+    ))]
+    pub(crate) fn try_clone_buffered(&self) -> Option<Body> {
+        match self.kind {
+            Kind::Once(None) => Some(Body::empty()),
+            Kind::Once(Some(ref bytes)) => Some(Body::from(bytes.clone())),
+            _ => None,
+        }
+    }
+
     fn poll_inner(&mut self, cx: &mut task::Context<'_>) -> Poll<Option<crate::Result<Bytes>>> {
         match self.kind {
             Kind::Once(ref mut val) => Poll::Ready(val.take().map(Ok)),
@@ -303,13 +755,19 @@ impl Body {
             } => {
                 want_tx.send(WANT_READY);
 
-                match ready!(Pin::new(data_rx).poll_next(cx)?) {
-                    Some(chunk) => {
-                        len.sub_if(chunk.len() as u64);
-                        Poll::Ready(Some(Ok(chunk)))
-                    }
-                    None => Poll::Ready(None),
+                let chunk = match ready!(Pin::new(data_rx).poll_next(cx)?) {
+                    Some(chunk) => chunk,
+                    None => return Poll::Ready(None),
+                };
+                let buffered = self.extra.as_ref().and_then(|e| e.buffered.as_ref());
+                if let Some(buffered) = buffered {
+                    buffered.bytes.fetch_sub(chunk.data.len(), Ordering::SeqCst);
+                    buffered.waker.wake();
                 }
+                len.sub_if(chunk.data.len() as u64);
+                #[cfg(feature = "http1")]
+                self.set_chunk_extensions(chunk.extensions);
+                Poll::Ready(Some(Ok(chunk.data)))
             }
             #[cfg(all(feature = "http2", any(feature = "client", feature = "server")))]
             Kind::H2 {
@@ -330,6 +788,13 @@ impl Body {
             #[cfg(feature = "ffi")]
             Kind::Ffi(ref mut body) => body.poll_data(cx),
 
+            #[cfg(feature = "decompress")]
+            Kind::Decoded(ref mut decoder) => decoder.poll_data(cx),
+            #[cfg(feature = "compress")]
+            Kind::Encoded(ref mut encoder) => encoder.poll_data(cx),
+            #[cfg(feature = "runtime")]
+            Kind::File(ref mut file) => file.poll_data(cx),
+
             #[cfg(feature = "stream")]
             Kind::Wrapped(ref mut s) => match ready!(s.get_mut().as_mut().poll_next(cx)) {
                 Some(res) => Poll::Ready(Some(res.map_err(crate::Error::new_body))),
@@ -356,6 +821,36 @@ impl Default for Body {
     }
 }
 
+#[cfg(all(
+    feature = "client",
+    feature = "runtime",
+    any(feature = "http1", feature = "http2")
+))]
+impl IdleTimeout {
+    fn poll(
+        &mut self,
+        cx: &mut task::Context<'_>,
+        res: Poll<Option<crate::Result<Bytes>>>,
+    ) -> Poll<Option<crate::Result<Bytes>>> {
+        if res.is_ready() {
+            self.armed = false;
+            return res;
+        }
+
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let sleep = self
+            .sleep
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(deadline)));
+        if !self.armed {
+            sleep.as_mut().reset(deadline);
+            self.armed = true;
+        }
+        ready!(sleep.as_mut().poll(cx));
+        self.armed = false;
+        Poll::Ready(Some(Err(crate::Error::new_body_idle_timeout())))
+    }
+}
+
 impl HttpBody for Body {
     type Data = Bytes;
     type Error = crate::Error;
@@ -364,7 +859,36 @@ impl HttpBody for Body {
         mut self: Pin<&mut Self>,
         cx: &mut task::Context<'_>,
     ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
-        self.poll_eof(cx)
+        if let Some(limit) = self.extra.as_ref().and_then(|extra| extra.limit) {
+            limit.check_hint(&HttpBody::size_hint(&*self))?;
+        }
+        let res = self.poll_eof(cx);
+        #[cfg(all(
+            feature = "client",
+            feature = "runtime",
+            any(feature = "http1", feature = "http2")
+        ))]
+        let res = match self
+            .extra
+            .as_mut()
+            .and_then(|extra| extra.idle_timeout.as_mut())
+        {
+            Some(idle) => idle.poll(cx, res),
+            None => res,
+        };
+        if let Poll::Ready(Some(Ok(ref chunk))) = res {
+            if let Some(limit) = self.extra.as_mut().and_then(|extra| extra.limit.as_mut()) {
+                limit.take(chunk.len())?;
+            }
+            if let Some(hint) = self
+                .extra
+                .as_mut()
+                .and_then(|extra| extra.size_hint.as_mut())
+            {
+                consume_size_hint(hint, chunk.len() as u64);
+            }
+        }
+        res
     }
 
     fn poll_trailers(
@@ -393,6 +917,10 @@ impl HttpBody for Body {
             },
             #[cfg(feature = "ffi")]
             Kind::Ffi(ref mut body) => body.poll_trailers(cx),
+            #[cfg(feature = "decompress")]
+            Kind::Decoded(ref mut decoder) => decoder.poll_trailers(cx),
+            #[cfg(feature = "compress")]
+            Kind::Encoded(ref mut encoder) => encoder.poll_trailers(cx),
             _ => Poll::Ready(Ok(None)),
         }
     }
@@ -405,12 +933,26 @@ impl HttpBody for Body {
             Kind::H2 { recv: ref h2, .. } => h2.is_end_stream(),
             #[cfg(feature = "ffi")]
             Kind::Ffi(..) => false,
+            #[cfg(feature = "decompress")]
+            Kind::Decoded(ref decoder) => decoder.is_end_stream(),
+            #[cfg(feature = "compress")]
+            Kind::Encoded(ref encoder) => encoder.is_end_stream(),
+            #[cfg(feature = "runtime")]
+            Kind::File(ref file) => file.is_end_stream(),
             #[cfg(feature = "stream")]
             Kind::Wrapped(..) => false,
         }
     }
 
     fn size_hint(&self) -> SizeHint {
+        if let Some(hint) = self
+            .extra
+            .as_ref()
+            .and_then(|extra| extra.size_hint.as_ref())
+        {
+            return hint.clone();
+        }
+
         macro_rules! opt_len {
             ($content_length:expr) => {{
                 let mut hint = SizeHint::default();
@@ -433,10 +975,27 @@ impl HttpBody for Body {
             Kind::H2 { content_length, .. } => opt_len!(content_length),
             #[cfg(feature = "ffi")]
             Kind::Ffi(..) => SizeHint::default(),
+            #[cfg(feature = "decompress")]
+            Kind::Decoded(..) => SizeHint::default(),
+            #[cfg(feature = "compress")]
+            Kind::Encoded(..) => SizeHint::default(),
+            #[cfg(feature = "runtime")]
+            Kind::File(ref file) => SizeHint::with_exact(file.len()),
         }
     }
 }
 
+/// Lowers both bounds of `hint` by `len` bytes that were read.
+fn consume_size_hint(hint: &mut SizeHint, len: u64) {
+    let upper = hint.upper().map(|upper| upper.saturating_sub(len));
+    let mut consumed = SizeHint::new();
+    consumed.set_lower(hint.lower().saturating_sub(len));
+    if let Some(upper) = upper {
+        consumed.set_upper(upper);
+    }
+    *hint = consumed;
+}
+
 #[allow(dead_code)]
 impl fmt::Debug for Body {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -549,11 +1108,57 @@ impl Sender {
     pub fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
         // Check if the receiver end has tried polling for the body yet
         ready!(self.poll_want(cx)?);
+        ready!(self.poll_watermarks(cx));
         self.data_tx
             .poll_ready(cx)
             .map_err(|_| crate::Error::new_closed())
     }
 
+    /// Set the watermarks of the data buffered in this channel.
+    ///
+    /// Once `high` bytes were sent but not yet received, `poll_ready()` and
+    /// `send_data()` wait until no more than `low` bytes remain buffered.
+    /// `try_send_data()` only checks the capacity of the channel, in chunks.
+    ///
+    /// Setting `high` to `0` disables the watermarks, which is the default.
+    /// Only channels created with [`Body::channel_with_capacity()`] have
+    /// watermarks; for others, this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is larger than `high`.
+    pub fn set_watermarks(&mut self, low: usize, high: usize) {
+        assert!(low <= high, "low watermark must not be larger than high");
+        if let Some(ref buffered) = self.buffered {
+            buffered.low_watermark.store(low, Ordering::Relaxed);
+            buffered.high_watermark.store(high, Ordering::Relaxed);
+            buffered.draining.store(false, Ordering::Relaxed);
+        }
+    }
+
+    fn poll_watermarks(&mut self, cx: &mut task::Context<'_>) -> Poll<()> {
+        let buffered = match self.buffered {
+            Some(ref buffered) => buffered,
+            None => return Poll::Ready(()),
+        };
+        let high = buffered.high_watermark.load(Ordering::Relaxed);
+        if high == 0 {
+            return Poll::Ready(());
+        }
+        if !buffered.draining.load(Ordering::Relaxed)
+            && buffered.bytes.load(Ordering::SeqCst) < high
+        {
+            return Poll::Ready(());
+        }
+        buffered.draining.store(true, Ordering::Relaxed);
+        buffered.waker.register(cx.waker());
+        if buffered.bytes.load(Ordering::SeqCst) > buffered.low_watermark.load(Ordering::Relaxed) {
+            return Poll::Pending;
+        }
+        buffered.draining.store(false, Ordering::Relaxed);
+        Poll::Ready(())
+    }
+
     fn poll_want(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
         match self.want_rx.load(cx) {
             WANT_READY => Poll::Ready(Ok(())),
@@ -570,8 +1175,7 @@ impl Sender {
     /// Send data on data channel when it is ready.
     pub async fn send_data(&mut self, chunk: Bytes) -> crate::Result<()> {
         self.ready().await?;
-        self.data_tx
-            .try_send(Ok(chunk))
+        self.send_chunk(chunk)
             .map_err(|_| crate::Error::new_closed())
     }
 
@@ -597,9 +1201,40 @@ impl Sender {
     /// that doesn't have an async context. If in an async context, prefer
     /// `send_data()` instead.
     pub fn try_send_data(&mut self, chunk: Bytes) -> Result<(), Bytes> {
-        self.data_tx
-            .try_send(Ok(chunk))
-            .map_err(|err| err.into_inner().expect("just sent Ok"))
+        self.send_chunk(chunk)
+    }
+
+    /// Tries to send data with the extensions of the chunk it was received
+    /// in.
+    #[cfg(feature = "http1")]
+    pub(crate) fn try_send_chunk(
+        &mut self,
+        data: Bytes,
+        extensions: Option<ChunkExtensions>,
+    ) -> Result<(), Bytes> {
+        self.push(Chunk { data, extensions })
+    }
+
+    fn send_chunk(&mut self, data: Bytes) -> Result<(), Bytes> {
+        self.push(Chunk {
+            data,
+            #[cfg(feature = "http1")]
+            extensions: None,
+        })
+    }
+
+    fn push(&mut self, chunk: Chunk) -> Result<(), Bytes> {
+        let len = chunk.data.len();
+        // Counted before sending, so the receiver never subtracts first.
+        if let Some(ref buffered) = self.buffered {
+            buffered.bytes.fetch_add(len, Ordering::SeqCst);
+        }
+        self.data_tx.try_send(Ok(chunk)).map_err(|err| {
+            if let Some(ref buffered) = self.buffered {
+                buffered.bytes.fetch_sub(len, Ordering::SeqCst);
+            }
+            err.into_inner().expect("just sent Ok").data
+        })
     }
 
     /// Aborts the body in an abnormal fashion.
@@ -611,12 +1246,63 @@ impl Sender {
             .try_send(Err(crate::Error::new_body_write_aborted()));
     }
 
+    /// Aborts the body with an error.
+    ///
+    /// Like [`Sender::abort()`], but the error of the body has `err` as its
+    /// cause, so the receiver can tell why it was aborted.
+    pub fn abort_with<E>(self, err: E)
+    where
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        let _ = self
+            .data_tx
+            // clone so the send works even if buffer is full
+            .clone()
+            .try_send(Err(crate::Error::new_body_write_aborted().with(err)));
+    }
+
     #[cfg(feature = "http1")]
     pub(crate) fn send_error(&mut self, err: crate::Error) {
         let _ = self.data_tx.try_send(Err(err));
     }
 }
 
+/// A `Sender` is also a `Sink` of data chunks, so encoders and other
+/// writers can stream into a body with the usual `Sink` combinators.
+///
+/// Closing the sink ends the body normally, without trailers.
+///
+/// # Optional
+///
+/// This implementation requires enabling the `sink` feature in your
+/// `Cargo.toml`.
+#[cfg(feature = "sink")]
+impl Sink<Bytes> for Sender {
+    type Error = crate::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
+        Sender::poll_ready(self.get_mut(), cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, chunk: Bytes) -> crate::Result<()> {
+        self.get_mut()
+            .send_chunk(chunk)
+            .map_err(|_| crate::Error::new_closed())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
+        // Chunks are handed to the body as soon as they are sent.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
+        let me = self.get_mut();
+        me.data_tx.close_channel();
+        me.trailers_tx = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
 impl fmt::Debug for Sender {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         #[derive(Debug)]
@@ -639,7 +1325,7 @@ mod tests {
     use std::mem;
     use std::task::Poll;
 
-    use super::{Body, DecodedLength, HttpBody, Sender, SizeHint};
+    use super::{Body, DecodedLength, HttpBody, Sender, SizeHint, MAX_CHANNEL_CAPACITY};
 
     #[test]
     fn test_size_of() {
@@ -659,7 +1345,7 @@ mod tests {
 
         assert_eq!(
             mem::size_of::<Sender>(),
-            mem::size_of::<usize>() * 5,
+            mem::size_of::<usize>() * 6,
             "Sender"
         );
 
@@ -727,6 +1413,103 @@ mod tests {
         assert_eq!(chunk2, "chunk 2");
     }
 
+    #[cfg(feature = "sink")]
+    #[tokio::test]
+    async fn channel_sink() {
+        use bytes::Bytes;
+        use futures_util::SinkExt;
+
+        let (mut tx, body) = Body::channel();
+        let send = tokio::spawn(async move {
+            for row in ["a,b\n", "1,2\n"] {
+                tx.feed(Bytes::from(row)).await?;
+            }
+            tx.close().await?;
+            tx.send("too late".into()).await.expect_err("closed");
+            Ok::<_, crate::Error>(())
+        });
+
+        assert_eq!(crate::body::to_bytes(body).await.unwrap(), "a,b\n1,2\n");
+        send.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn channel_with_capacity() {
+        let (mut tx, _rx) = Body::channel_with_capacity(3);
+
+        for i in 0..3 {
+            tx.try_send_data(format!("chunk {}", i).into())
+                .expect("send within capacity");
+        }
+        tx.try_send_data("chunk 3".into())
+            .expect_err("send over capacity");
+    }
+
+    #[test]
+    #[should_panic(expected = "channel capacity must not be larger")]
+    fn channel_capacity_too_large() {
+        let _ = Body::channel_with_capacity(usize::MAX);
+    }
+
+    #[test]
+    fn channel_max_capacity() {
+        let (mut tx, _rx) = Body::channel_with_capacity(MAX_CHANNEL_CAPACITY);
+        tx.try_send_data("chunk".into()).unwrap();
+    }
+
+    #[test]
+    fn channel_watermarks() {
+        let (mut tx, mut rx) = Body::channel_with_capacity(8);
+        tx.set_watermarks(5, 10);
+
+        tx.try_send_data("0123456".into()).unwrap();
+        assert!(tokio_test::task::spawn(tx.ready()).poll().is_ready());
+        tx.try_send_data("789".into()).unwrap();
+
+        let mut tx_ready = tokio_test::task::spawn(tx.ready());
+        assert!(tx_ready.poll().is_pending(), "10 bytes buffered");
+
+        let mut rx_data = tokio_test::task::spawn(rx.data());
+        assert!(rx_data.poll().is_ready());
+        drop(rx_data);
+        assert!(tx_ready.is_woken(), "rx poll wakes tx");
+        assert!(tx_ready.poll().is_ready(), "3 bytes buffered");
+    }
+
+    #[tokio::test]
+    async fn channel_abort_with() {
+        let (tx, mut rx) = Body::channel();
+
+        tx.abort_with("upstream failed");
+
+        let err = rx.data().await.unwrap().unwrap_err();
+        assert!(err.is_body_write_aborted(), "{:?}", err);
+        let cause = std::error::Error::source(&err).expect("cause");
+        assert_eq!(cause.to_string(), "upstream failed");
+    }
+
+    #[tokio::test]
+    async fn channel_with_exact_size() {
+        let (mut tx, rx) = Body::channel();
+        let mut rx = rx.with_exact_size(11);
+        assert_eq!(rx.size_hint().exact(), Some(11));
+
+        tx.send_data("hello ".into()).await.unwrap();
+        rx.data().await.unwrap().unwrap();
+        assert_eq!(rx.size_hint().exact(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn with_size_hint_bounds() {
+        let mut body = Body::from("hello").with_size_hint(2, Some(8));
+        assert_eq!(body.size_hint().lower(), 2);
+        assert_eq!(body.size_hint().upper(), Some(8));
+
+        body.data().await.unwrap().unwrap();
+        assert_eq!(body.size_hint().lower(), 0);
+        assert_eq!(body.size_hint().upper(), Some(3));
+    }
+
     #[tokio::test]
     async fn channel_empty() {
         let (_, mut rx) = Body::channel();
@@ -0,0 +1,254 @@
+use std::io::{self, Write};
+use std::mem;
+
+use bytes::Bytes;
+use http::header::{HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH};
+use http::{HeaderMap, Request};
+use http_body::Body as HttpBody;
+use tracing::trace;
+
+use super::{Body, ContentEncoding};
+use crate::common::{task, Pin, Poll};
+
+/// Compresses the body of a request, and updates its headers to match.
+///
+/// The body is wrapped with [`Body::compressed`], the `Content-Encoding`
+/// header is set to `encoding`, and any `Content-Length` is removed, since
+/// the compressed length isn't known in advance.
+///
+/// A request that already has a `Content-Encoding` is returned unchanged,
+/// since its body is already encoded.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::body::{compress_request, ContentEncoding};
+/// use fluxio::{Body, Request};
+///
+/// let req = Request::post("http://example.com/upload")
+///     .body(Body::from("a lot of very compressible text"))
+///     .unwrap();
+/// let req = compress_request(req, ContentEncoding::Gzip, 6);
+/// assert_eq!(req.headers()["content-encoding"], "gzip");
+/// ```
+pub fn compress_request(
+    req: Request<Body>,
+    encoding: ContentEncoding,
+    level: u32,
+) -> Request<Body> {
+    if req.headers().contains_key(CONTENT_ENCODING) {
+        trace!("request already has a content-encoding, not compressing it");
+        return req;
+    }
+    let (mut parts, body) = req.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(
+        CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    Request::from_parts(parts, Body::compressed(body, encoding, level))
+}
+
+/// A `Body` encoded with some content coding.
+pub(super) struct Encoder {
+    inner: Body,
+    codec: Codec,
+    done: bool,
+}
+
+enum Codec {
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibEncoder<Vec<u8>>),
+    // Brotli only finishes a stream when its writer is consumed.
+    Brotli(Option<Box<brotli::CompressorWriter<Vec<u8>>>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl Encoder {
+    /// The `level` is clamped to the range supported by the coding.
+    pub(super) fn new(inner: Body, encoding: ContentEncoding, level: u32) -> Encoder {
+        let codec = match encoding {
+            ContentEncoding::Gzip => Codec::Gzip(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::new(level.min(9)),
+            )),
+            ContentEncoding::Deflate => Codec::Deflate(flate2::write::ZlibEncoder::new(
+                Vec::new(),
+                flate2::Compression::new(level.min(9)),
+            )),
+            ContentEncoding::Brotli => Codec::Brotli(Some(Box::new(
+                brotli::CompressorWriter::new(Vec::new(), 4096, level.min(11), 22),
+            ))),
+            ContentEncoding::Zstd => Codec::Zstd(
+                zstd::stream::write::Encoder::new(Vec::new(), level.min(22) as i32)
+                    .expect("zstd encoder context"),
+            ),
+        };
+        Encoder {
+            inner,
+            codec,
+            done: false,
+        }
+    }
+
+    pub(super) fn poll_data(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<crate::Result<Bytes>>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            let encoded = match ready!(Pin::new(&mut self.inner).poll_data(cx)) {
+                Some(Ok(chunk)) => self.codec.encode(&chunk),
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => {
+                    self.done = true;
+                    self.codec.finish()
+                }
+            };
+            match encoded {
+                Ok(chunk) if !chunk.is_empty() => return Poll::Ready(Some(Ok(chunk))),
+                Ok(_) if self.done => return Poll::Ready(None),
+                Ok(_) => trace!("no encoded bytes yet"),
+                Err(err) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(crate::Error::new_body(err))));
+                }
+            }
+        }
+    }
+
+    pub(super) fn poll_trailers(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<crate::Result<Option<HeaderMap>>> {
+        Pin::new(&mut self.inner).poll_trailers(cx)
+    }
+
+    pub(super) fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+impl Codec {
+    /// Returns the bytes the encoder produced so far, which may be none.
+    fn encode(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        match self {
+            Codec::Gzip(ref mut codec) => {
+                codec.write_all(chunk)?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Deflate(ref mut codec) => {
+                codec.write_all(chunk)?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Brotli(Some(ref mut codec)) => {
+                codec.write_all(chunk)?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Brotli(None) => Ok(Bytes::new()),
+            Codec::Zstd(ref mut codec) => {
+                codec.write_all(chunk)?;
+                Ok(take(codec.get_mut()))
+            }
+        }
+    }
+
+    /// Ends the compressed stream, returning the last bytes.
+    fn finish(&mut self) -> io::Result<Bytes> {
+        match self {
+            Codec::Gzip(ref mut codec) => {
+                codec.try_finish()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Deflate(ref mut codec) => {
+                codec.try_finish()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Brotli(ref mut codec) => match codec.take() {
+                Some(codec) => Ok(Bytes::from(codec.into_inner())),
+                None => Ok(Bytes::new()),
+            },
+            Codec::Zstd(ref mut codec) => {
+                codec.do_finish()?;
+                Ok(take(codec.get_mut()))
+            }
+        }
+    }
+}
+
+fn take(buf: &mut Vec<u8>) -> Bytes {
+    Bytes::from(mem::take(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn compresses_request_bodies() {
+        let hello = "hello world, hello world, hello world".repeat(100);
+
+        for encoding in ContentEncoding::ALL {
+            let req = Request::post("/")
+                .header(CONTENT_LENGTH, hello.len())
+                .body(Body::from(hello.clone()))
+                .unwrap();
+            let req = compress_request(req, encoding, 6);
+            assert_eq!(req.headers()[CONTENT_ENCODING], encoding.as_str());
+            assert!(req.headers().get(CONTENT_LENGTH).is_none());
+            assert!(req.body().size_hint().exact().is_none());
+
+            let compressed = crate::body::to_bytes(req.into_body()).await.unwrap();
+            assert!(compressed.len() < hello.len(), "{} compresses", encoding);
+
+            let mut decoded = String::new();
+            match encoding {
+                ContentEncoding::Gzip => {
+                    flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut decoded)
+                }
+                ContentEncoding::Deflate => {
+                    flate2::read::ZlibDecoder::new(&compressed[..]).read_to_string(&mut decoded)
+                }
+                ContentEncoding::Brotli => {
+                    brotli::Decompressor::new(&compressed[..], 4096).read_to_string(&mut decoded)
+                }
+                ContentEncoding::Zstd => zstd::stream::read::Decoder::new(&compressed[..])
+                    .unwrap()
+                    .read_to_string(&mut decoded),
+            }
+            .unwrap();
+            assert_eq!(decoded, hello, "{} roundtrip", encoding);
+        }
+    }
+
+    #[tokio::test]
+    async fn leaves_encoded_requests_alone() {
+        let req = Request::post("/")
+            .header(CONTENT_ENCODING, "br")
+            .header(CONTENT_LENGTH, 5)
+            .body(Body::from("hello"))
+            .unwrap();
+        let req = compress_request(req, ContentEncoding::Gzip, 6);
+        assert_eq!(req.headers()[CONTENT_ENCODING], "br");
+        assert_eq!(req.headers()[CONTENT_LENGTH], "5");
+
+        let body = crate::body::to_bytes(req.into_body()).await.unwrap();
+        assert_eq!(body, "hello");
+    }
+
+    #[tokio::test]
+    async fn empty_body_is_still_encoded() {
+        let body = Body::compressed(Body::empty(), ContentEncoding::Gzip, 6);
+        let compressed = crate::body::to_bytes(body).await.unwrap();
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert!(decoded.is_empty());
+    }
+}
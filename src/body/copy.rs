@@ -0,0 +1,113 @@
+use std::error::Error as StdError;
+
+use bytes::Buf;
+use futures_util::StreamExt;
+
+use super::{Frames, HttpBody, Sender};
+
+/// Forward a body into the sending half of another one.
+///
+/// The data chunks are passed on as they are received, without buffering
+/// or copying them, followed by the trailers if there are any. The next
+/// chunk isn't read from `src` until `dst` has accepted the previous one,
+/// so a slow receiver slows down reading `src`, with at most one chunk held
+/// while waiting, instead of buffering the body in memory. This is the data
+/// path of a proxy, such as forwarding a client response to a server
+/// response.
+///
+/// Returns the number of bytes forwarded. If reading `src` fails, `dst` is
+/// aborted, so the other side sees an error instead of a truncated body.
+///
+/// # Example
+///
+/// ```
+/// # async fn doc() -> fluxio::Result<()> {
+/// use fluxio::body::copy_body;
+/// use fluxio::Body;
+///
+/// let upstream = Body::from("hello world");
+/// let (tx, body) = Body::channel();
+///
+/// tokio::spawn(copy_body(upstream, tx));
+/// assert_eq!(fluxio::body::to_bytes(body).await?, "hello world");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn copy_body<B>(src: B, mut dst: Sender) -> crate::Result<u64>
+where
+    B: HttpBody,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    let frames = Frames::new(src);
+    futures_util::pin_mut!(frames);
+
+    let mut copied = 0;
+    while let Some(frame) = frames.next().await {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(err) => {
+                dst.abort();
+                return Err(crate::Error::new_body(err));
+            }
+        };
+        let mut chunk = match frame.into_data() {
+            Ok(chunk) => chunk,
+            Err(frame) => {
+                if let Ok(trailers) = frame.into_trailers() {
+                    dst.send_trailers(trailers).await?;
+                }
+                continue;
+            }
+        };
+        let len = chunk.remaining();
+        if len == 0 {
+            continue;
+        }
+        // `Bytes` is passed through as it is.
+        dst.send_data(chunk.copy_to_bytes(len)).await?;
+        copied += len as u64;
+    }
+
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http::HeaderMap;
+
+    use super::*;
+    use crate::Body;
+
+    #[tokio::test]
+    async fn forwards_data_and_trailers() {
+        let (mut src_tx, src) = Body::channel();
+        let (dst_tx, mut dst) = Body::channel();
+        let copy = tokio::spawn(copy_body(src, dst_tx));
+
+        src_tx.send_data(Bytes::from("hello ")).await.unwrap();
+        src_tx.send_data(Bytes::from("world")).await.unwrap();
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        src_tx.send_trailers(trailers).await.unwrap();
+        drop(src_tx);
+
+        assert_eq!(dst.data().await.unwrap().unwrap(), "hello ");
+        assert_eq!(dst.data().await.unwrap().unwrap(), "world");
+        assert!(dst.data().await.is_none());
+        let trailers = dst.trailers().await.unwrap().unwrap();
+        assert_eq!(trailers["grpc-status"], "0");
+        assert_eq!(copy.await.unwrap().unwrap(), 11);
+    }
+
+    #[tokio::test]
+    async fn src_error_aborts_dst() {
+        let (src_tx, src) = Body::channel();
+        let (dst_tx, dst) = Body::channel();
+        let copy = tokio::spawn(copy_body(src, dst_tx));
+
+        src_tx.abort();
+        crate::body::to_bytes(dst).await.expect_err("dst aborted");
+        copy.await.unwrap().expect_err("src error");
+    }
+}
@@ -0,0 +1,377 @@
+// Only clients decode responses.
+#![cfg_attr(not(feature = "client"), allow(dead_code))]
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+
+use bytes::Bytes;
+use http::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH};
+use http::{HeaderMap, Request, Response};
+use http_body::Body as HttpBody;
+use tracing::{debug, trace};
+
+use super::{Body, ContentEncoding};
+use crate::common::{task, Pin, Poll};
+
+/// The set of content codings a client decodes in responses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Decompress {
+    codings: u8,
+    max_size: Option<u64>,
+}
+
+impl Decompress {
+    pub(crate) fn set_encodings<I>(&mut self, encodings: I)
+    where
+        I: IntoIterator<Item = ContentEncoding>,
+    {
+        self.codings = encodings.into_iter().fold(0, |bits, enc| bits | enc.bit());
+    }
+
+    /// Limits decoded bodies to at most `max` bytes.
+    pub(crate) fn set_max_size(&mut self, max: u64) {
+        self.max_size = Some(max);
+    }
+
+    fn contains(&self, encoding: ContentEncoding) -> bool {
+        self.codings & encoding.bit() != 0
+    }
+
+    fn encodings(self) -> impl Iterator<Item = ContentEncoding> {
+        ContentEncoding::ALL
+            .into_iter()
+            .filter(move |enc| self.contains(*enc))
+    }
+
+    /// Advertises the enabled codings, unless the request already has an
+    /// `Accept-Encoding`.
+    pub(crate) fn prepare_request<B>(&self, req: &mut Request<B>) {
+        if self.codings == 0 || req.headers().contains_key(ACCEPT_ENCODING) {
+            return;
+        }
+        let accept = self
+            .encodings()
+            .map(|enc| enc.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        req.headers_mut().insert(
+            ACCEPT_ENCODING,
+            HeaderValue::from_str(&accept).expect("valid encodings"),
+        );
+    }
+
+    /// Decodes the body of a response with an enabled `Content-Encoding`.
+    ///
+    /// The `Content-Encoding` and `Content-Length` headers are removed, as
+    /// they no longer describe the body. Bodies with several codings, or
+    /// unknown ones, are left alone. Decoding more than the maximum size
+    /// fails the body with a "body too large" error.
+    pub(crate) fn response(&self, res: Response<Body>) -> Response<Body> {
+        if self.codings == 0 || res.body().is_end_stream() {
+            return res;
+        }
+        let mut codings = res.headers().get_all(CONTENT_ENCODING).iter();
+        let encoding = match (codings.next(), codings.next()) {
+            (Some(value), None) => ContentEncoding::from_header(value),
+            _ => None,
+        };
+        let encoding = match encoding {
+            Some(encoding) if self.contains(encoding) => encoding,
+            _ => return res,
+        };
+
+        debug!("decoding {} response body", encoding);
+        let (mut parts, body) = res.into_parts();
+        parts.headers.remove(CONTENT_ENCODING);
+        parts.headers.remove(CONTENT_LENGTH);
+        let decoder = Decoder::new(body, encoding, self.max_size);
+        Response::from_parts(parts, Body::decoded(decoder))
+    }
+}
+
+/// A `Body` decoded with some content coding.
+pub(super) struct Decoder {
+    inner: Body,
+    codec: Codec,
+    done: bool,
+}
+
+enum Codec {
+    Gzip(flate2::write::GzDecoder<Output>),
+    Deflate(flate2::write::ZlibDecoder<Output>),
+    Brotli(Box<brotli::DecompressorWriter<Output>>),
+    // The zio writer tells when the input ended mid-frame, which
+    // `zstd::stream::write::Decoder` doesn't.
+    Zstd(zstd::stream::zio::Writer<Output, zstd::stream::raw::Decoder<'static>>),
+}
+
+/// Where a codec writes decoded bytes, refusing to hold more than the
+/// maximum size in total.
+struct Output {
+    buf: Vec<u8>,
+    remaining: Option<u64>,
+}
+
+#[derive(Debug)]
+struct TooLarge;
+
+impl Decoder {
+    fn new(inner: Body, encoding: ContentEncoding, max_size: Option<u64>) -> Decoder {
+        let output = Output {
+            buf: Vec::new(),
+            remaining: max_size,
+        };
+        let codec = match encoding {
+            ContentEncoding::Gzip => Codec::Gzip(flate2::write::GzDecoder::new(output)),
+            ContentEncoding::Deflate => Codec::Deflate(flate2::write::ZlibDecoder::new(output)),
+            ContentEncoding::Brotli => {
+                Codec::Brotli(Box::new(brotli::DecompressorWriter::new(output, 4096)))
+            }
+            ContentEncoding::Zstd => Codec::Zstd(zstd::stream::zio::Writer::new(
+                output,
+                zstd::stream::raw::Decoder::new().expect("zstd decoder context"),
+            )),
+        };
+        Decoder {
+            inner,
+            codec,
+            done: false,
+        }
+    }
+
+    pub(super) fn poll_data(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<crate::Result<Bytes>>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            let decoded = match ready!(Pin::new(&mut self.inner).poll_data(cx)) {
+                Some(Ok(chunk)) => self.codec.decode(&chunk),
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => {
+                    self.done = true;
+                    self.codec.finish()
+                }
+            };
+            match decoded {
+                Ok(chunk) if !chunk.is_empty() => return Poll::Ready(Some(Ok(chunk))),
+                Ok(_) if self.done => return Poll::Ready(None),
+                Ok(_) => trace!("no decoded bytes yet"),
+                Err(err) => {
+                    self.done = true;
+                    let err = match err.get_ref() {
+                        Some(inner) if inner.is::<TooLarge>() => crate::Error::new_body_too_large(),
+                        _ => crate::Error::new_body(err),
+                    };
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+        }
+    }
+
+    pub(super) fn poll_trailers(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<crate::Result<Option<HeaderMap>>> {
+        Pin::new(&mut self.inner).poll_trailers(cx)
+    }
+
+    pub(super) fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+impl Codec {
+    fn decode(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        match self {
+            Codec::Gzip(ref mut codec) => {
+                codec.write_all(chunk)?;
+                codec.flush()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Deflate(ref mut codec) => {
+                codec.write_all(chunk)?;
+                codec.flush()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Brotli(ref mut codec) => {
+                codec.write_all(chunk)?;
+                codec.flush()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Zstd(ref mut codec) => {
+                codec.write_all(chunk)?;
+                codec.flush()?;
+                Ok(take(codec.writer_mut()))
+            }
+        }
+    }
+
+    /// Checks the compressed stream is complete, returning the last bytes.
+    fn finish(&mut self) -> io::Result<Bytes> {
+        match self {
+            Codec::Gzip(ref mut codec) => {
+                codec.try_finish()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Deflate(ref mut codec) => {
+                codec.try_finish()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Brotli(ref mut codec) => {
+                codec.close()?;
+                Ok(take(codec.get_mut()))
+            }
+            Codec::Zstd(ref mut codec) => {
+                codec.finish()?;
+                Ok(take(codec.writer_mut()))
+            }
+        }
+    }
+}
+
+fn take(output: &mut Output) -> Bytes {
+    Bytes::from(mem::take(&mut output.buf))
+}
+
+impl Write for Output {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if let Some(ref mut remaining) = self.remaining {
+            if data.len() as u64 > *remaining {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, TooLarge));
+            }
+            *remaining -= data.len() as u64;
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("decoded body is too large")
+    }
+}
+
+impl StdError for TooLarge {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress<I>(encodings: I) -> Decompress
+    where
+        I: IntoIterator<Item = ContentEncoding>,
+    {
+        let mut decompress = Decompress::default();
+        decompress.set_encodings(encodings);
+        decompress
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    fn response(encoding: &'static str, body: Body) -> Response<Body> {
+        Response::builder()
+            .header(CONTENT_ENCODING, encoding)
+            .header(CONTENT_LENGTH, "100")
+            .body(body)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn decodes_enabled_encodings() {
+        let decompress = decompress(ContentEncoding::ALL);
+        let hello = b"hello world, hello world, hello world";
+
+        let compressed = gzip(hello);
+        let (mut tx, body) = Body::channel();
+        let res = decompress.response(response("gzip", body));
+        assert!(res.headers().get(CONTENT_ENCODING).is_none());
+        assert!(res.headers().get(CONTENT_LENGTH).is_none());
+
+        // split in two chunks, to decode across them
+        tokio::spawn(async move {
+            let (a, b) = compressed.split_at(compressed.len() / 2);
+            tx.send_data(Bytes::copy_from_slice(a)).await.unwrap();
+            tx.send_data(Bytes::copy_from_slice(b)).await.unwrap();
+        });
+        let decoded = crate::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(decoded, &hello[..]);
+
+        let compressed = zstd::stream::encode_all(&hello[..], 0).unwrap();
+        let res = decompress.response(response("zstd", Body::from(compressed)));
+        let decoded = crate::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(decoded, &hello[..]);
+
+        // truncated
+        let compressed = gzip(hello);
+        let truncated = Body::from(compressed[..compressed.len() - 4].to_vec());
+        let res = decompress.response(response("gzip", truncated));
+        crate::body::to_bytes(res.into_body())
+            .await
+            .expect_err("truncated gzip");
+
+        let compressed = zstd::stream::encode_all(&hello[..], 0).unwrap();
+        let truncated = Body::from(compressed[..compressed.len() - 4].to_vec());
+        let res = decompress.response(response("zstd", truncated));
+        crate::body::to_bytes(res.into_body())
+            .await
+            .expect_err("truncated zstd");
+    }
+
+    #[tokio::test]
+    async fn limits_decoded_size() {
+        let zeros = vec![0; 64 * 1024];
+        let mut deflate =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        deflate.write_all(&zeros).unwrap();
+        let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        brotli.write_all(&zeros).unwrap();
+        let encoded = [
+            ("gzip", gzip(&zeros)),
+            ("deflate", deflate.finish().unwrap()),
+            ("br", brotli.into_inner()),
+            ("zstd", zstd::stream::encode_all(&zeros[..], 0).unwrap()),
+        ];
+
+        for (encoding, compressed) in encoded {
+            let mut decompress = decompress(ContentEncoding::ALL);
+            decompress.set_max_size(zeros.len() as u64);
+            let res = decompress.response(response(encoding, Body::from(compressed.clone())));
+            let decoded = crate::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(decoded.len(), zeros.len(), "{}", encoding);
+
+            decompress.set_max_size(zeros.len() as u64 - 1);
+            let res = decompress.response(response(encoding, Body::from(compressed)));
+            let err = crate::body::to_bytes(res.into_body()).await.unwrap_err();
+            assert!(err.is_body_too_large(), "{}: {:?}", encoding, err);
+        }
+    }
+
+    #[test]
+    fn leaves_other_encodings_alone() {
+        let gzip_only = decompress([ContentEncoding::Gzip]);
+
+        let res = gzip_only.response(response("br", Body::from("x")));
+        assert_eq!(res.headers()[CONTENT_ENCODING], "br");
+
+        let res = gzip_only.response(response("gzip, gzip", Body::from("x")));
+        assert_eq!(res.headers()[CONTENT_LENGTH], "100");
+
+        let mut req = Request::new(());
+        decompress([ContentEncoding::Gzip, ContentEncoding::Brotli]).prepare_request(&mut req);
+        assert_eq!(req.headers()[ACCEPT_ENCODING], "gzip, br");
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+#[cfg(feature = "decompress")]
+use http::header::HeaderValue;
+
+/// A content coding of compressed bodies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContentEncoding {
+    /// `gzip`
+    Gzip,
+    /// `deflate`, the zlib format.
+    Deflate,
+    /// `br`, Brotli.
+    Brotli,
+    /// `zstd`, Zstandard.
+    Zstd,
+}
+
+impl ContentEncoding {
+    /// All the supported content codings.
+    pub const ALL: [ContentEncoding; 4] = [
+        ContentEncoding::Gzip,
+        ContentEncoding::Deflate,
+        ContentEncoding::Brotli,
+        ContentEncoding::Zstd,
+    ];
+
+    /// The name of this coding, as used in `Content-Encoding`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Zstd => "zstd",
+        }
+    }
+
+    #[cfg(feature = "decompress")]
+    pub(super) fn from_header(value: &HeaderValue) -> Option<ContentEncoding> {
+        let value = value.to_str().ok()?.trim();
+        if value.eq_ignore_ascii_case("gzip") || value.eq_ignore_ascii_case("x-gzip") {
+            Some(ContentEncoding::Gzip)
+        } else if value.eq_ignore_ascii_case("deflate") {
+            Some(ContentEncoding::Deflate)
+        } else if value.eq_ignore_ascii_case("br") {
+            Some(ContentEncoding::Brotli)
+        } else if value.eq_ignore_ascii_case("zstd") {
+            Some(ContentEncoding::Zstd)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "decompress")]
+    pub(super) fn bit(self) -> u8 {
+        match self {
+            ContentEncoding::Gzip => 1,
+            ContentEncoding::Deflate => 1 << 1,
+            ContentEncoding::Brotli => 1 << 2,
+            ContentEncoding::Zstd => 1 << 3,
+        }
+    }
+}
+
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
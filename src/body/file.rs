@@ -0,0 +1,150 @@
+use std::fs::File;
+use std::io::{self, Read, Seek};
+
+use bytes::Bytes;
+use tokio::task::JoinHandle;
+
+use crate::common::{task, Future, Pin, Poll};
+
+/// The most read from the file for a single chunk of the body.
+///
+/// Chunks this large are queued as they are by HTTP/1 connections using
+/// vectored writes, instead of being copied into the write buffer.
+const CHUNK_SIZE: usize = 128 * 1024;
+
+/// A `Body` streaming the contents of a file.
+pub(super) struct FileBody {
+    /// The file, unless a read of it is in flight.
+    file: Option<File>,
+    /// The length of the file not read yet.
+    remaining: u64,
+    read: Option<JoinHandle<io::Result<(File, Bytes)>>>,
+}
+
+impl FileBody {
+    pub(super) fn new(mut file: File) -> io::Result<FileBody> {
+        let len = file.metadata()?.len();
+        let pos = file.stream_position()?;
+        Ok(FileBody {
+            file: Some(file),
+            remaining: len.saturating_sub(pos),
+            read: None,
+        })
+    }
+
+    pub(super) fn poll_data(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<crate::Result<Bytes>>> {
+        loop {
+            if let Some(ref mut read) = self.read {
+                let res = ready!(Pin::new(read).poll(cx));
+                self.read = None;
+                let (file, chunk) = match res.map_err(io::Error::other).and_then(|res| res) {
+                    Ok((_, chunk)) if chunk.is_empty() => {
+                        self.remaining = 0;
+                        let err = io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "file is shorter than its length",
+                        );
+                        return Poll::Ready(Some(Err(crate::Error::new_body(err))));
+                    }
+                    Ok(read) => read,
+                    Err(err) => {
+                        self.remaining = 0;
+                        return Poll::Ready(Some(Err(crate::Error::new_body(err))));
+                    }
+                };
+                self.file = Some(file);
+                self.remaining -= chunk.len() as u64;
+                return Poll::Ready(Some(Ok(chunk)));
+            }
+
+            if self.remaining == 0 {
+                return Poll::Ready(None);
+            }
+            let mut file = match self.file.take() {
+                Some(file) => file,
+                None => return Poll::Ready(None),
+            };
+            let len = self.remaining.min(CHUNK_SIZE as u64);
+            self.read = Some(tokio::task::spawn_blocking(move || {
+                // Reading straight into the chunk, which is then handed to
+                // the connection as it is.
+                let mut buf = Vec::with_capacity(len as usize);
+                (&mut file).take(len).read_to_end(&mut buf)?;
+                Ok((file, Bytes::from(buf)))
+            }));
+        }
+    }
+
+    pub(super) fn is_end_stream(&self) -> bool {
+        self.remaining == 0
+    }
+
+    pub(super) fn len(&self) -> u64 {
+        self.remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{SeekFrom, Write};
+
+    use http_body::Body as HttpBody;
+
+    use super::*;
+    use crate::Body;
+
+    fn temp_file(name: &str, data: &[u8]) -> File {
+        let path =
+            std::env::temp_dir().join(format!("fluxio-body-file-{}-{}", name, std::process::id()));
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        file.write_all(data).unwrap();
+        file.rewind().unwrap();
+        file
+    }
+
+    #[tokio::test]
+    async fn reads_file_in_chunks() {
+        let data = (0..CHUNK_SIZE * 2 + 10)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let mut body = Body::from_file(temp_file("chunks", &data)).unwrap();
+        assert_eq!(body.size_hint().exact(), Some(data.len() as u64));
+
+        let mut read = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= CHUNK_SIZE);
+            read.extend_from_slice(&chunk);
+        }
+        assert_eq!(read, data);
+        assert!(body.is_end_stream());
+        assert_eq!(body.size_hint().exact(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn reads_from_position() {
+        let mut file = temp_file("position", b"hello world");
+        file.seek(SeekFrom::Start(6)).unwrap();
+        let body = Body::from_file(file).unwrap();
+        assert_eq!(body.size_hint().exact(), Some(5));
+        assert_eq!(crate::body::to_bytes(body).await.unwrap(), "world");
+    }
+
+    #[tokio::test]
+    async fn errors_when_truncated() {
+        let file = temp_file("truncated", b"hello world");
+        let body = Body::from_file(file.try_clone().unwrap()).unwrap();
+        file.set_len(5).unwrap();
+        crate::body::to_bytes(body).await.unwrap_err();
+    }
+}
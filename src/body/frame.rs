@@ -0,0 +1,404 @@
+use std::fmt;
+
+use bytes::Buf;
+use futures_core::Stream;
+use http::HeaderMap;
+use http_body::SizeHint;
+use pin_project_lite::pin_project;
+
+use super::HttpBody;
+use crate::common::{task, Pin, Poll};
+
+/// A frame of a body: either a chunk of data or the trailers.
+///
+/// Reading a body as frames, such as with [`Frames`], returns data and
+/// trailers from a single poll, so they don't need to be polled separately.
+/// Other kinds of frames may be added later, so code matching on frames
+/// should ignore the ones it doesn't know.
+pub struct Frame<T> {
+    kind: Kind<T>,
+}
+
+enum Kind<T> {
+    Data(T),
+    Trailers(HeaderMap),
+}
+
+impl<T> Frame<T> {
+    /// Creates a data frame.
+    pub fn data(buf: T) -> Frame<T> {
+        Frame {
+            kind: Kind::Data(buf),
+        }
+    }
+
+    /// Creates a trailers frame.
+    pub fn trailers(map: HeaderMap) -> Frame<T> {
+        Frame {
+            kind: Kind::Trailers(map),
+        }
+    }
+
+    /// Maps the data of this frame, if it is a data frame.
+    pub fn map_data<F, D>(self, f: F) -> Frame<D>
+    where
+        F: FnOnce(T) -> D,
+    {
+        match self.kind {
+            Kind::Data(data) => Frame::data(f(data)),
+            Kind::Trailers(trailers) => Frame::trailers(trailers),
+        }
+    }
+
+    /// Returns whether this is a data frame.
+    pub fn is_data(&self) -> bool {
+        matches!(self.kind, Kind::Data(..))
+    }
+
+    /// Returns the data of this frame, or the frame if it isn't a data frame.
+    pub fn into_data(self) -> Result<T, Frame<T>> {
+        match self.kind {
+            Kind::Data(data) => Ok(data),
+            _ => Err(self),
+        }
+    }
+
+    /// Returns a reference to the data of this frame, if it is a data frame.
+    pub fn data_ref(&self) -> Option<&T> {
+        match self.kind {
+            Kind::Data(ref data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the data of this frame, if it is a
+    /// data frame.
+    pub fn data_mut(&mut self) -> Option<&mut T> {
+        match self.kind {
+            Kind::Data(ref mut data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns whether this is a trailers frame.
+    pub fn is_trailers(&self) -> bool {
+        matches!(self.kind, Kind::Trailers(..))
+    }
+
+    /// Returns the trailers of this frame, or the frame if it isn't a
+    /// trailers frame.
+    pub fn into_trailers(self) -> Result<HeaderMap, Frame<T>> {
+        match self.kind {
+            Kind::Trailers(trailers) => Ok(trailers),
+            _ => Err(self),
+        }
+    }
+
+    /// Returns a reference to the trailers of this frame, if it is a
+    /// trailers frame.
+    pub fn trailers_ref(&self) -> Option<&HeaderMap> {
+        match self.kind {
+            Kind::Trailers(ref trailers) => Some(trailers),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the trailers of this frame, if it is a
+    /// trailers frame.
+    pub fn trailers_mut(&mut self) -> Option<&mut HeaderMap> {
+        match self.kind {
+            Kind::Trailers(ref mut trailers) => Some(trailers),
+            _ => None,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Frame<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Data(ref data) => f.debug_tuple("Data").field(data).finish(),
+            Kind::Trailers(ref trailers) => f.debug_tuple("Trailers").field(trailers).finish(),
+        }
+    }
+}
+
+pin_project! {
+    /// A stream of the frames of an [`HttpBody`].
+    ///
+    /// This returns a [`Frame`] for each chunk of data of the body, followed
+    /// by one for its trailers if it has any.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc() -> fluxio::Result<()> {
+    /// use fluxio::body::Frames;
+    /// use fluxio::Body;
+    /// use futures_util::StreamExt;
+    ///
+    /// let mut frames = Frames::new(Body::from("hello"));
+    /// while let Some(frame) = frames.next().await {
+    ///     let frame = frame?;
+    ///     if let Some(data) = frame.data_ref() {
+    ///         assert_eq!(data, "hello");
+    ///     } else if let Some(trailers) = frame.trailers_ref() {
+    ///         println!("trailers: {:?}", trailers);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[derive(Debug)]
+    pub struct Frames<B> {
+        #[pin]
+        body: B,
+        state: State,
+    }
+}
+
+type FrameResult<B> = Result<Frame<<B as HttpBody>::Data>, <B as HttpBody>::Error>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Data,
+    Trailers,
+    Done,
+}
+
+impl<B> Frames<B> {
+    /// Reads the frames of `body`.
+    pub fn new(body: B) -> Frames<B> {
+        Frames {
+            body,
+            state: State::Data,
+        }
+    }
+
+    /// Returns the wrapped body.
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+impl<B: HttpBody> Frames<B> {
+    /// Attempts to read the next frame of the body.
+    pub fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<FrameResult<B>>> {
+        let mut this = self.project();
+        loop {
+            match *this.state {
+                State::Data => match ready!(this.body.as_mut().poll_data(cx)) {
+                    Some(Ok(data)) => return Poll::Ready(Some(Ok(Frame::data(data)))),
+                    Some(Err(err)) => {
+                        *this.state = State::Done;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    None => *this.state = State::Trailers,
+                },
+                State::Trailers => {
+                    let trailers = ready!(this.body.as_mut().poll_trailers(cx));
+                    *this.state = State::Done;
+                    return Poll::Ready(trailers.map(|t| t.map(Frame::trailers)).transpose());
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<B: HttpBody> Stream for Frames<B> {
+    type Item = FrameResult<B>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_frame(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.state {
+            State::Done => (0, Some(0)),
+            _ => (0, None),
+        }
+    }
+}
+
+pin_project! {
+    /// An [`HttpBody`] reading its data and trailers from a stream of frames.
+    ///
+    /// This is the other way around from [`Frames`]: data frames are
+    /// returned by `poll_data`, and a trailers frame ends the data and is
+    /// returned by `poll_trailers`. Anything after the trailers is ignored,
+    /// as is any data not read before polling the trailers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc() {
+    /// use fluxio::body::{Frame, FrameBody, HttpBody};
+    /// use fluxio::http::HeaderMap;
+    /// use fluxio::body::Bytes;
+    ///
+    /// let frames = futures_util::stream::iter(vec![
+    ///     Ok::<_, std::convert::Infallible>(Frame::data(Bytes::from("hello"))),
+    ///     Ok(Frame::trailers(HeaderMap::new())),
+    /// ]);
+    /// let mut body = FrameBody::new(frames);
+    /// assert_eq!(body.data().await.unwrap().unwrap(), "hello");
+    /// assert!(body.data().await.is_none());
+    /// assert!(body.trailers().await.unwrap().is_some());
+    /// # }
+    /// ```
+    #[derive(Debug)]
+    pub struct FrameBody<S> {
+        #[pin]
+        frames: S,
+        trailers: Option<HeaderMap>,
+        state: State,
+    }
+}
+
+impl<S> FrameBody<S> {
+    /// Creates a body from a stream of frames.
+    pub fn new(frames: S) -> FrameBody<S> {
+        FrameBody {
+            frames,
+            trailers: None,
+            state: State::Data,
+        }
+    }
+
+    /// Returns the wrapped stream.
+    pub fn into_inner(self) -> S {
+        self.frames
+    }
+}
+
+impl<S, D, E> HttpBody for FrameBody<S>
+where
+    S: Stream<Item = Result<Frame<D>, E>>,
+    D: Buf,
+{
+    type Data = D;
+    type Error = E;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let mut this = self.project();
+        while *this.state == State::Data {
+            match ready!(this.frames.as_mut().poll_next(cx)) {
+                Some(Ok(frame)) => match frame.kind {
+                    Kind::Data(data) => return Poll::Ready(Some(Ok(data))),
+                    Kind::Trailers(trailers) => {
+                        *this.trailers = Some(trailers);
+                        *this.state = State::Done;
+                    }
+                },
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => *this.state = State::Done,
+            }
+        }
+        Poll::Ready(None)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        let mut this = self.project();
+        while *this.state == State::Data {
+            match ready!(this.frames.as_mut().poll_next(cx)) {
+                Some(Ok(frame)) => {
+                    if let Kind::Trailers(trailers) = frame.kind {
+                        *this.trailers = Some(trailers);
+                        *this.state = State::Done;
+                    }
+                }
+                Some(Err(err)) => return Poll::Ready(Err(err)),
+                None => *this.state = State::Done,
+            }
+        }
+        Poll::Ready(Ok(this.trailers.take()))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.state == State::Done && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        if self.is_end_stream() {
+            SizeHint::with_exact(0)
+        } else {
+            SizeHint::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::Body;
+
+    #[tokio::test]
+    async fn frames_of_body() {
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(async move {
+            tx.send_data(Bytes::from("hello")).await.unwrap();
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", "0".parse().unwrap());
+            tx.send_trailers(trailers).await.unwrap();
+        });
+
+        let frames = Frames::new(body)
+            .map(|frame| frame.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].data_ref().unwrap(), "hello");
+        assert_eq!(frames[1].trailers_ref().unwrap()["grpc-status"], "0");
+    }
+
+    #[tokio::test]
+    async fn body_of_frames_round_trip() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        let frames = futures_util::stream::iter(vec![
+            Ok::<_, Infallible>(Frame::data(Bytes::from("hello "))),
+            Ok(Frame::data(Bytes::from("world"))),
+            Ok(Frame::trailers(trailers)),
+        ]);
+
+        let mut frames = Frames::new(FrameBody::new(frames));
+        let mut data = Vec::new();
+        let mut trailers = None;
+        while let Some(frame) = frames.next().await {
+            match frame.unwrap().into_data() {
+                Ok(chunk) => data.extend_from_slice(&chunk),
+                Err(frame) => trailers = frame.into_trailers().ok(),
+            }
+        }
+        assert_eq!(data, b"hello world");
+        assert_eq!(trailers.unwrap()["grpc-status"], "0");
+        assert!(frames.into_inner().is_end_stream());
+    }
+
+    #[tokio::test]
+    async fn trailers_before_end_of_data() {
+        let frames = futures_util::stream::iter(vec![
+            Ok::<_, Infallible>(Frame::data(Bytes::from("skipped"))),
+            Ok(Frame::trailers(HeaderMap::new())),
+            Ok(Frame::data(Bytes::from("ignored"))),
+        ]);
+        let mut body = FrameBody::new(frames);
+        assert!(body.trailers().await.unwrap().is_some());
+        assert!(body.data().await.is_none());
+        assert!(body.is_end_stream());
+    }
+}
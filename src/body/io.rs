@@ -0,0 +1,226 @@
+use std::io;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use http_body::Body as HttpBody;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::io::StreamReader;
+
+use super::{Body, Sender};
+use crate::common::{task, Pin, Poll};
+
+/// Creates a body written to through an [`AsyncWrite`].
+///
+/// Everything written to the returned [`BodyWriter`] becomes the data of
+/// the returned `Body`, so encoders and other writers can produce a body
+/// directly. Each write waits for the body to be polled, so a slow reader
+/// slows down the writer instead of buffering in memory.
+///
+/// The body ends when the writer is shut down or dropped.
+///
+/// # Example
+///
+/// ```
+/// # async fn doc() -> fluxio::Result<()> {
+/// use tokio::io::AsyncWriteExt;
+///
+/// let (mut writer, body) = fluxio::body::sink();
+/// tokio::spawn(async move {
+///     writer.write_all(b"hello world").await?;
+///     writer.shutdown().await
+/// });
+/// assert_eq!(fluxio::body::to_bytes(body).await?, "hello world");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Optional
+///
+/// This function requires enabling the `tokio-util` feature in your
+/// `Cargo.toml`.
+pub fn sink() -> (BodyWriter, Body) {
+    let (tx, body) = Body::channel();
+    (BodyWriter { tx: Some(tx) }, body)
+}
+
+/// An [`AsyncRead`] of the data of a [`Body`].
+///
+/// Created with [`Body::into_async_read`]. Trailers are ignored, and an
+/// error of the body is returned as an `io::Error` wrapping it.
+///
+/// # Optional
+///
+/// This type requires enabling the `tokio-util` feature in your
+/// `Cargo.toml`.
+#[derive(Debug)]
+pub struct BodyReader {
+    inner: StreamReader<DataStream, Bytes>,
+}
+
+/// The [`AsyncWrite`] half of a body created with [`sink`].
+///
+/// # Optional
+///
+/// This type requires enabling the `tokio-util` feature in your
+/// `Cargo.toml`.
+#[derive(Debug)]
+pub struct BodyWriter {
+    tx: Option<Sender>,
+}
+
+#[derive(Debug)]
+struct DataStream(Body);
+
+impl BodyReader {
+    pub(super) fn new(body: Body) -> BodyReader {
+        BodyReader {
+            inner: StreamReader::new(DataStream(body)),
+        }
+    }
+
+    /// Returns the body being read.
+    ///
+    /// Any data already read from the body, but not yet from this reader,
+    /// is lost.
+    pub fn into_inner(self) -> Body {
+        self.inner.into_inner().0
+    }
+}
+
+impl AsyncRead for BodyReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncBufRead for BodyReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+impl Stream for DataStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0)
+            .poll_data(cx)
+            .map(|opt| opt.map(|res| res.map_err(io::Error::other)))
+    }
+}
+
+impl BodyWriter {
+    /// Aborts the body, so it ends with an error instead of normally.
+    pub fn abort(mut self) {
+        if let Some(tx) = self.tx.take() {
+            tx.abort();
+        }
+    }
+}
+
+impl AsyncWrite for BodyWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let tx = match self.tx {
+            Some(ref mut tx) => tx,
+            None => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        };
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        ready!(tx.poll_ready(cx)).map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))?;
+        match tx.try_send_data(Bytes::copy_from_slice(buf)) {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(_) => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        // Chunks are handed to the body as soon as they are written.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        _cx: &mut task::Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.tx = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn read_body() {
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(async move {
+            tx.send_data(Bytes::from("hello\n")).await.unwrap();
+            tx.send_data(Bytes::from("wor")).await.unwrap();
+            tx.send_data(Bytes::from("ld\n")).await.unwrap();
+        });
+
+        let mut reader = body.into_async_read();
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "hello\n");
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "world\n");
+    }
+
+    #[tokio::test]
+    async fn read_body_error() {
+        let (tx, body) = Body::channel();
+        tx.abort();
+
+        let mut buf = Vec::new();
+        let err = body
+            .into_async_read()
+            .read_to_end(&mut buf)
+            .await
+            .expect_err("aborted body");
+        let err = err.into_inner().expect("wraps the body error");
+        assert!(err.downcast_ref::<crate::Error>().is_some());
+    }
+
+    #[tokio::test]
+    async fn write_body() {
+        let (mut writer, body) = sink();
+        let write = tokio::spawn(async move {
+            writer.write_all(b"hello ").await.unwrap();
+            writer.write_all(b"world").await.unwrap();
+            writer.shutdown().await.unwrap();
+            writer.write_all(b"!").await.expect_err("shut down");
+        });
+
+        assert_eq!(crate::body::to_bytes(body).await.unwrap(), "hello world");
+        write.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn write_to_dropped_body() {
+        let (mut writer, body) = sink();
+        drop(body);
+        writer.write_all(b"hello").await.expect_err("body dropped");
+
+        let (writer, body) = sink();
+        writer.abort();
+        crate::body::to_bytes(body).await.expect_err("aborted");
+    }
+}
@@ -15,7 +15,7 @@ impl From<Option<u64>> for DecodedLength {
 }
 
 #[cfg(any(feature = "http1", feature = "http2", test))]
-const MAX_LEN: u64 = std::u64::MAX - 2;
+const MAX_LEN: u64 = u64::MAX - 2;
 
 #[allow(clippy::legacy_numeric_constants)]
 impl DecodedLength {
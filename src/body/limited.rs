@@ -0,0 +1,159 @@
+use std::error::Error as StdError;
+
+use bytes::Buf;
+use http::HeaderMap;
+use http_body::SizeHint;
+use pin_project_lite::pin_project;
+
+use super::HttpBody;
+use crate::common::{task, Pin, Poll};
+
+pin_project! {
+    /// A body failing once more data than a limit was read from it.
+    ///
+    /// This bounds how much of a body from an untrusted peer is read, such
+    /// as before collecting it with [`to_bytes`](crate::body::to_bytes).
+    /// Reading more than the limit fails with an error for which
+    /// [`Error::is_body_too_large`](crate::Error::is_body_too_large) is
+    /// true. A body declaring a larger length, such as with
+    /// `Content-Length`, fails before any of it is read.
+    ///
+    /// [`Body::limit`](crate::Body::limit) limits a `Body` without changing
+    /// its type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # async fn doc() {
+    /// use fluxio::body::Limited;
+    /// use fluxio::Body;
+    ///
+    /// let body = Limited::new(Body::from("hello world"), 5);
+    /// let err = fluxio::body::to_bytes(body).await.unwrap_err();
+    /// assert!(err.is_body_too_large());
+    /// # }
+    /// ```
+    #[derive(Debug)]
+    pub struct Limited<B> {
+        #[pin]
+        inner: B,
+        limit: Limit,
+    }
+}
+
+/// The bytes of a body that can still be read.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Limit {
+    remaining: u64,
+}
+
+impl<B> Limited<B> {
+    /// Limits `inner` to at most `max` bytes of data.
+    pub fn new(inner: B, max: u64) -> Limited<B> {
+        Limited {
+            inner,
+            limit: Limit::new(max),
+        }
+    }
+
+    /// Returns the wrapped body.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B> HttpBody for Limited<B>
+where
+    B: HttpBody,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Data = B::Data;
+    type Error = crate::Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let this = self.project();
+        this.limit.check_hint(&this.inner.size_hint())?;
+        match ready!(this.inner.poll_data(cx)) {
+            Some(Ok(data)) => {
+                this.limit.take(data.remaining())?;
+                Poll::Ready(Some(Ok(data)))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(crate::Error::new_body(err)))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        self.project()
+            .inner
+            .poll_trailers(cx)
+            .map_err(crate::Error::new_body)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Limit {
+    pub(super) fn new(max: u64) -> Limit {
+        Limit { remaining: max }
+    }
+
+    /// Fails if the body declares more data than is left of the limit.
+    pub(super) fn check_hint(&self, hint: &SizeHint) -> crate::Result<()> {
+        if hint.lower() > self.remaining {
+            return Err(crate::Error::new_body_too_large());
+        }
+        Ok(())
+    }
+
+    /// Takes `len` bytes of data off the limit, failing if it is exceeded.
+    pub(super) fn take(&mut self, len: usize) -> crate::Result<()> {
+        match self.remaining.checked_sub(len as u64) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Ok(())
+            }
+            None => Err(crate::Error::new_body_too_large()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+
+    #[tokio::test]
+    async fn fails_past_limit() {
+        let (mut tx, body) = Body::channel();
+        let mut body = Limited::new(body, 8);
+        tx.send_data("hello".into()).await.unwrap();
+        assert_eq!(body.data().await.unwrap().unwrap(), "hello");
+
+        tx.send_data("world".into()).await.unwrap();
+        let err = body.data().await.unwrap().unwrap_err();
+        assert!(err.is_body_too_large(), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn fails_on_declared_length() {
+        let mut body = Limited::new(Body::from("hello world"), 10);
+        let err = body.data().await.unwrap().unwrap_err();
+        assert!(err.is_body_too_large(), "{:?}", err);
+
+        let body = Limited::new(Body::from("hello world"), 11);
+        assert_eq!(crate::body::to_bytes(body).await.unwrap(), "hello world");
+    }
+}
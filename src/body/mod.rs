@@ -14,6 +14,9 @@
 //!   `HttpBody`, and returned by fluxio as a "receive stream" (so, for server
 //!   requests and client responses). It is also a decent default implementation
 //!   if you don't have very custom needs of your send streams.
+//!
+//! Bodies can also be read and built as a sequence of [`Frame`]s, each either
+//! data or trailers, with the [`Frames`] and [`FrameBody`] adapters.
 
 pub use bytes::{Buf, Bytes};
 pub use http_body::Body as HttpBody;
@@ -21,14 +24,42 @@ pub use http_body::SizeHint;
 
 pub use self::aggregate::aggregate;
 // pub use self::body::{Body, Sender};
+#[cfg(feature = "ffi")]
+pub(crate) use self::body::MAX_CHANNEL_CAPACITY;
 pub use self::body::{Body, Sender};
+#[cfg(feature = "compress")]
+pub use self::compress::compress_request;
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub use self::copy::copy_body;
+#[cfg(all(feature = "decompress", feature = "client"))]
+pub(crate) use self::decompress::Decompress;
+#[cfg(any(feature = "compress", feature = "decompress"))]
+pub use self::encoding::ContentEncoding;
+pub use self::frame::{Frame, FrameBody, Frames};
+#[cfg(feature = "tokio-util")]
+pub use self::io::{sink, BodyReader, BodyWriter};
 pub(crate) use self::length::DecodedLength;
-pub use self::to_bytes::to_bytes;
+pub use self::limited::Limited;
+pub use self::to_bytes::{to_bytes, to_bytes_limited};
 
 mod aggregate;
 #[allow(clippy::module_inception)]
 mod body;
+#[cfg(feature = "compress")]
+mod compress;
+#[cfg(any(feature = "http1", feature = "http2"))]
+mod copy;
+#[cfg(feature = "decompress")]
+mod decompress;
+#[cfg(any(feature = "compress", feature = "decompress"))]
+mod encoding;
+#[cfg(feature = "runtime")]
+mod file;
+mod frame;
+#[cfg(feature = "tokio-util")]
+mod io;
 mod length;
+mod limited;
 mod to_bytes;
 
 /// An optimization to try to take a full body if immediately available.
@@ -1,6 +1,15 @@
+use std::error::Error as StdError;
+
 use bytes::{Buf, BufMut, Bytes};
 
-use super::HttpBody;
+use super::{HttpBody, Limited};
+
+/// The most bytes preallocated for a body from its size hint, unless it is
+/// limited to more.
+///
+/// The size hint can come from the peer, such as a `Content-Length`, so it
+/// is only trusted up to this before that much data actually arrived.
+const MAX_PREALLOCATE: usize = 64 * 1024;
 
 /// Concatenate the buffers from a body into a single `Bytes` asynchronously.
 ///
@@ -12,7 +21,8 @@ use super::HttpBody;
 ///
 /// Care needs to be taken if the remote is untrusted. The function doesn't implement any length
 /// checks and an malicious peer might make it consume arbitrary amounts of memory. Checking the
-/// `Content-Length` is a possibility, but it is not strictly mandated to be present.
+/// `Content-Length` is a possibility, but it is not strictly mandated to be present. Prefer
+/// [`to_bytes_limited`] then.
 ///
 /// # Example
 ///
@@ -45,6 +55,50 @@ use super::HttpBody;
 /// # }
 /// ```
 pub async fn to_bytes<T>(body: T) -> Result<Bytes, T::Error>
+where
+    T: HttpBody,
+{
+    collect(body, MAX_PREALLOCATE).await
+}
+
+/// Concatenate the buffers from a body into a single `Bytes`, failing if
+/// it has more than `max` bytes of data.
+///
+/// Reading more than `max` bytes fails with an error for which
+/// [`Error::is_body_too_large`](crate::Error::is_body_too_large) is true,
+/// as does a body declaring a larger length, such as with
+/// `Content-Length`, before any of it is read. Up to `max` bytes are
+/// preallocated from the size hint of the body, so collecting a body of a
+/// known length copies it once.
+///
+/// # Example
+///
+/// ```
+/// # async fn doc() -> fluxio::Result<()> {
+/// use fluxio::Body;
+///
+/// let body = fluxio::body::to_bytes_limited(Body::from("hello"), 1024).await?;
+/// assert_eq!(body, "hello");
+///
+/// let err = fluxio::body::to_bytes_limited(Body::from("hello"), 4)
+///     .await
+///     .unwrap_err();
+/// assert!(err.is_body_too_large());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn to_bytes_limited<T>(body: T, max: u64) -> crate::Result<Bytes>
+where
+    T: HttpBody,
+    T::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    let preallocate = usize::try_from(max).unwrap_or(usize::MAX);
+    collect(Limited::new(body, max), preallocate).await
+}
+
+/// Collects the data of `body`, preallocating at most `preallocate` bytes
+/// from its size hint.
+async fn collect<T>(body: T, preallocate: usize) -> Result<Bytes, T::Error>
 where
     T: HttpBody,
 {
@@ -64,8 +118,9 @@ where
     };
 
     // With more than 1 buf, we gotta flatten into a Vec first.
-    let cap = first.remaining() + second.remaining() + body.size_hint().lower() as usize;
-    let mut vec = Vec::with_capacity(cap);
+    let received = first.remaining() + second.remaining();
+    let hint = usize::try_from(body.size_hint().lower()).unwrap_or(usize::MAX);
+    let mut vec = Vec::with_capacity(received + hint.min(preallocate.saturating_sub(received)));
     vec.put(first);
     vec.put(second);
 
@@ -75,3 +130,31 @@ where
 
     Ok(vec.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+
+    #[tokio::test]
+    async fn preallocates_from_size_hint() {
+        let (mut tx, body) = Body::new_channel(crate::body::DecodedLength::new(10), false);
+        tokio::spawn(async move {
+            for chunk in ["ab", "cd", "efghij"] {
+                tx.send_data(chunk.into()).await.unwrap();
+            }
+        });
+        let bytes = collect(body, MAX_PREALLOCATE).await.unwrap();
+        assert_eq!(bytes, "abcdefghij");
+        // copied into a buffer of the hinted length, without growing it
+        assert_eq!(Vec::from(bytes).capacity(), 10);
+    }
+
+    #[tokio::test]
+    async fn limited_fails_past_max() {
+        let err = to_bytes_limited(Body::from("hello world"), 5)
+            .await
+            .unwrap_err();
+        assert!(err.is_body_too_large(), "{:?}", err);
+    }
+}
@@ -0,0 +1,244 @@
+//! Caching accepted credentials, to send them preemptively.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use http::header::{HeaderMap, HeaderValue, AUTHORIZATION, PROXY_AUTHORIZATION};
+use http::StatusCode;
+
+use super::pool::Key;
+
+/// The credentials accepted by each destination.
+///
+/// These are shared by every request the `Client` makes, whoever made them.
+#[derive(Clone, Default)]
+pub(super) struct AuthCache {
+    inner: Arc<Mutex<HashMap<Key, Credentials>>>,
+}
+
+#[derive(Default)]
+struct Credentials {
+    authorization: Option<HeaderValue>,
+    proxy_authorization: Option<HeaderValue>,
+}
+
+/// The cacheable credentials a request was sent with.
+pub(super) struct Sent {
+    authorization: Option<HeaderValue>,
+    proxy_authorization: Option<HeaderValue>,
+}
+
+impl AuthCache {
+    pub(super) fn new() -> AuthCache {
+        AuthCache::default()
+    }
+
+    /// Adds the cached credentials a request to `key` is missing, and returns
+    /// the ones it is sent with.
+    ///
+    /// `Proxy-Authorization` is only used for `proxied` requests, since it
+    /// would otherwise reach the origin.
+    pub(super) fn prepare(&self, key: &Key, headers: &mut HeaderMap, proxied: bool) -> Sent {
+        let cache = self.inner.lock().unwrap();
+        if let Some(creds) = cache.get(key) {
+            if let Some(ref value) = creds.authorization {
+                if !headers.contains_key(AUTHORIZATION) {
+                    headers.insert(AUTHORIZATION, value.clone());
+                }
+            }
+            if let Some(ref value) = creds.proxy_authorization {
+                if proxied && !headers.contains_key(PROXY_AUTHORIZATION) {
+                    headers.insert(PROXY_AUTHORIZATION, value.clone());
+                }
+            }
+        }
+
+        Sent {
+            // Other schemes, like `Digest`, can't be replayed.
+            authorization: headers.get(AUTHORIZATION).filter(|v| is_basic(v)).cloned(),
+            proxy_authorization: headers
+                .get(PROXY_AUTHORIZATION)
+                .filter(|_| proxied)
+                .cloned(),
+        }
+    }
+
+    /// Caches the credentials of a request to `key` if its response was
+    /// successful or a redirect, or drops them if they were challenged by
+    /// it. Other responses, like `403` or `500`, don't say whether they were
+    /// accepted, and leave the cache alone.
+    pub(super) fn update(&self, key: &Key, sent: Sent, status: StatusCode) {
+        if sent.authorization.is_none() && sent.proxy_authorization.is_none() {
+            return;
+        }
+
+        let accepted = status.is_success() || status.is_redirection();
+
+        let mut cache = self.inner.lock().unwrap();
+        let creds = cache.entry(key.clone()).or_default();
+        if let Some(value) = sent.proxy_authorization {
+            remember(
+                &mut creds.proxy_authorization,
+                value,
+                accepted,
+                status == StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+            );
+        }
+        if let Some(value) = sent.authorization {
+            remember(
+                &mut creds.authorization,
+                value,
+                accepted,
+                status == StatusCode::UNAUTHORIZED,
+            );
+        }
+        if creds.authorization.is_none() && creds.proxy_authorization.is_none() {
+            cache.remove(key);
+        }
+    }
+}
+
+fn remember(
+    cached: &mut Option<HeaderValue>,
+    mut value: HeaderValue,
+    accepted: bool,
+    challenged: bool,
+) {
+    if accepted {
+        value.set_sensitive(true);
+        *cached = Some(value);
+    } else if challenged && cached.as_ref() == Some(&value) {
+        *cached = None;
+    }
+}
+
+fn is_basic(value: &HeaderValue) -> bool {
+    let value = value.as_bytes();
+    value.len() > 6 && value[..6].eq_ignore_ascii_case(b"basic ")
+}
+
+#[cfg(test)]
+mod tests {
+    use http::uri::{Authority, Scheme};
+
+    use super::*;
+
+    fn key(host: &'static str) -> Key {
+        (Scheme::HTTP, Authority::from_static(host))
+    }
+
+    fn headers(name: http::header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn caches_accepted_basic_auth() {
+        let cache = AuthCache::new();
+        let mut sent = headers(AUTHORIZATION, "Basic dXNlcjpwYXNz");
+        let creds = cache.prepare(&key("example.com"), &mut sent, false);
+        cache.update(&key("example.com"), creds, StatusCode::OK);
+
+        let mut next = HeaderMap::new();
+        cache.prepare(&key("example.com"), &mut next, false);
+        assert_eq!(next[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+        assert!(next[AUTHORIZATION].is_sensitive());
+
+        // other destinations, and requests with their own, are left alone
+        let mut other = HeaderMap::new();
+        cache.prepare(&key("example.org"), &mut other, false);
+        assert!(other.is_empty());
+        let mut own = headers(AUTHORIZATION, "Bearer token");
+        cache.prepare(&key("example.com"), &mut own, false);
+        assert_eq!(own[AUTHORIZATION], "Bearer token");
+    }
+
+    #[test]
+    fn skips_other_schemes() {
+        let cache = AuthCache::new();
+        let mut sent = headers(AUTHORIZATION, "Digest username=\"user\"");
+        let creds = cache.prepare(&key("example.com"), &mut sent, false);
+        cache.update(&key("example.com"), creds, StatusCode::OK);
+
+        let mut next = HeaderMap::new();
+        cache.prepare(&key("example.com"), &mut next, false);
+        assert!(next.is_empty());
+    }
+
+    #[test]
+    fn drops_challenged_auth() {
+        let cache = AuthCache::new();
+        let mut sent = headers(AUTHORIZATION, "Basic dXNlcjpwYXNz");
+        let creds = cache.prepare(&key("example.com"), &mut sent, false);
+        cache.update(&key("example.com"), creds, StatusCode::OK);
+
+        let mut next = HeaderMap::new();
+        let creds = cache.prepare(&key("example.com"), &mut next, false);
+        cache.update(&key("example.com"), creds, StatusCode::UNAUTHORIZED);
+
+        let mut next = HeaderMap::new();
+        cache.prepare(&key("example.com"), &mut next, false);
+        assert!(next.is_empty());
+    }
+
+    #[test]
+    fn caches_only_on_success_or_redirect() {
+        let cache = AuthCache::new();
+        for status in [
+            StatusCode::FORBIDDEN,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+        ] {
+            let mut sent = headers(AUTHORIZATION, "Basic dXNlcjpwYXNz");
+            let creds = cache.prepare(&key("example.com"), &mut sent, false);
+            cache.update(&key("example.com"), creds, status);
+
+            let mut next = HeaderMap::new();
+            cache.prepare(&key("example.com"), &mut next, false);
+            assert!(next.is_empty(), "cached on {}", status);
+        }
+
+        let mut sent = headers(AUTHORIZATION, "Basic dXNlcjpwYXNz");
+        let creds = cache.prepare(&key("example.com"), &mut sent, false);
+        cache.update(&key("example.com"), creds, StatusCode::FOUND);
+        let mut next = HeaderMap::new();
+        let creds = cache.prepare(&key("example.com"), &mut next, false);
+        assert_eq!(next[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+
+        // nor are cached ones dropped on them
+        cache.update(
+            &key("example.com"),
+            creds,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+        let mut next = HeaderMap::new();
+        cache.prepare(&key("example.com"), &mut next, false);
+        assert_eq!(next[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+    }
+
+    #[test]
+    fn proxy_auth_only_when_proxied() {
+        let cache = AuthCache::new();
+        let mut sent = headers(PROXY_AUTHORIZATION, "Basic cHJveHk6cGFzcw==");
+        let creds = cache.prepare(&key("example.com"), &mut sent, true);
+        cache.update(&key("example.com"), creds, StatusCode::OK);
+
+        let mut direct = HeaderMap::new();
+        cache.prepare(&key("example.com"), &mut direct, false);
+        assert!(direct.is_empty());
+
+        let mut proxied = HeaderMap::new();
+        let creds = cache.prepare(&key("example.com"), &mut proxied, true);
+        assert_eq!(proxied[PROXY_AUTHORIZATION], "Basic cHJveHk6cGFzcw==");
+
+        cache.update(
+            &key("example.com"),
+            creds,
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+        );
+        let mut proxied = HeaderMap::new();
+        cache.prepare(&key("example.com"), &mut proxied, true);
+        assert!(proxied.is_empty());
+    }
+}
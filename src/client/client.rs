@@ -1,28 +1,41 @@
+use std::any::Any;
 use std::error::Error as StdError;
 use std::fmt;
 use std::mem;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use futures_channel::oneshot;
 use futures_util::future::{self, Either, FutureExt as _, TryFutureExt as _};
-use http::header::{HeaderValue, HOST};
-use http::uri::{Port, Scheme};
-use http::{Method, Request, Response, Uri, Version};
+use http::uri::{Authority, Port, Scheme};
+use http::{Method, Request, Response, StatusCode, Uri, Version};
 use tracing::{debug, trace, warn};
 
+use super::auth::AuthCache;
 use super::conn;
+use super::connect::timing::{ConnectTimes, Timed};
 use super::connect::{self, sealed::Connect, Alpn, Connected, Connection};
+use super::host::HostPolicy;
+use super::idempotency::IdempotencyKey;
 use super::pool::{
-    self, CheckoutIsClosedError, Key as PoolKey, Pool, Poolable, Pooled, Reservation,
+    self, CheckoutIsClosedError, ConnectingLimitError, EvictionReason, Key as PoolKey, Pool,
+    PoolStats, Poolable, Pooled, QueueOrder, Reservation,
 };
+#[cfg(feature = "http1")]
+use super::validate::ResponseViolation;
 #[cfg(feature = "tcp")]
 use super::HttpConnector;
 use crate::body::{Body, HttpBody};
+use crate::metrics::MetricsSink;
+use crate::observer::ConnectionObserver;
 use crate::common::{
     exec::BoxSendFuture, lazy as fluxio_lazy, sync_wrapper::SyncWrapper, task, Future, Lazy, Pin,
     Poll,
 };
+use crate::ext::{RequestTargetForm, RequestTimings};
 use crate::rt::Executor;
+#[cfg(feature = "runtime")]
+use crate::rt::{Throttle, Throttled};
 
 /// A Client to make outgoing HTTP requests.
 ///
@@ -34,12 +47,18 @@ pub struct Client<C, B = Body> {
     conn_builder: conn::Builder,
     connector: C,
     pool: Pool<PoolClient<B>>,
+    auth_cache: Option<AuthCache>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 struct Config {
     retry_canceled_requests: bool,
-    set_host: bool,
+    retry_misdirected_requests: bool,
+    idempotency_key: Option<IdempotencyKey>,
+    preemptive_auth: bool,
+    host: HostPolicy,
+    #[cfg(feature = "runtime")]
+    throttle: Option<Throttle>,
     ver: Ver,
 }
 
@@ -146,6 +165,16 @@ where
 
     /// Send a constructed `Request` using this `Client`.
     ///
+    /// # CONNECT
+    ///
+    /// A `CONNECT` request is sent with the authority of its URI, like
+    /// `example.com:443`, as its target, and without a body. The connection
+    /// is asked of the connector as for any other request, so a connector
+    /// connecting to a proxy sends it to that proxy. A `2xx` response means
+    /// the tunnel was established: the connection is then taken out of the
+    /// pool, and [`upgrade::on`](crate::upgrade::on) yields it as the raw
+    /// tunnel. After any other response, the connection can be reused.
+    ///
     /// # Example
     ///
     /// ```
@@ -199,10 +228,28 @@ where
     ) -> crate::Result<Response<Body>> {
         let uri = req.uri().clone();
 
+        if self.config.retry_canceled_requests {
+            if let Some(ref key) = self.config.idempotency_key {
+                key.attach(&mut req);
+            }
+        }
+
+        // A misdirected request is only retried once, on a new connection.
+        let mut retry_misdirected = self.config.retry_misdirected_requests;
+
         loop {
-            req = match self.send_request(req, pool_key.clone()).await {
+            req = match self
+                .send_request(req, pool_key.clone(), retry_misdirected)
+                .await
+            {
                 Ok(resp) => return Ok(resp),
                 Err(ClientError::Normal(err)) => return Err(err),
+                Err(ClientError::Misdirected { mut req }) => {
+                    trace!("request misdirected on a reused connection, trying again");
+                    retry_misdirected = false;
+                    *req.uri_mut() = uri.clone();
+                    req
+                }
                 Err(ClientError::Canceled {
                     connection_reused,
                     mut req,
@@ -229,8 +276,15 @@ where
         &self,
         mut req: Request<B>,
         pool_key: PoolKey,
+        retry_misdirected: bool,
     ) -> Result<Response<Body>, ClientError<B>> {
-        let mut pooled = match self.connection_for(pool_key).await {
+        let permit = self
+            .pool
+            .acquire(&pool_key)
+            .await
+            .map_err(ClientError::Normal)?;
+
+        let mut pooled = match self.connection_for(pool_key.clone()).await {
             Ok(pooled) => pooled,
             Err(ClientConnectError::Normal(err)) => return Err(ClientError::Normal(err)),
             Err(ClientConnectError::H2CheckoutIsClosed(reason)) => {
@@ -241,6 +295,9 @@ where
                 })
             }
         };
+        if let Some(ref metrics) = self.conn_builder.metrics {
+            metrics.pool_checkout(pooled.is_reused());
+        }
 
         if pooled.is_http1() {
             if req.version() == Version::HTTP_2 {
@@ -250,25 +307,25 @@ where
                 ));
             }
 
-            if self.config.set_host {
-                let uri = req.uri().clone();
-                req.headers_mut().entry(HOST).or_insert_with(|| {
-                    let hostname = uri.host().expect("authority implies host");
-                    if let Some(port) = get_non_default_port(&uri) {
-                        let s = format!("{}:{}", hostname, port);
-                        HeaderValue::from_str(&s)
-                    } else {
-                        HeaderValue::from_str(hostname)
-                    }
-                    .expect("uri host is valid header value")
-                });
-            }
+            self.config.host.apply(&mut req);
 
             // CONNECT always sends authority-form, so check it first...
             if req.method() == Method::CONNECT {
                 authority_form(req.uri_mut());
+            } else if let Some(&form) = req.extensions().get::<RequestTargetForm>() {
+                match form {
+                    RequestTargetForm::Origin => origin_form(req.uri_mut()),
+                    RequestTargetForm::Absolute => {}
+                }
             } else if pooled.conn_info.is_proxied {
                 absolute_form(req.uri_mut());
+                if let Some(ref headers) = pooled.conn_info.proxy_headers {
+                    for (name, value) in headers {
+                        if !req.headers().contains_key(name) {
+                            req.headers_mut().insert(name, value.clone());
+                        }
+                    }
+                }
             } else {
                 origin_form(req.uri_mut());
             }
@@ -276,9 +333,36 @@ where
             authority_form(req.uri_mut());
         }
 
+        let sent_auth = self
+            .auth_cache
+            .as_ref()
+            .map(|cache| cache.prepare(&pool_key, req.headers_mut(), pooled.conn_info.is_proxied));
+
+        // A reused HTTP/2 connection may not be authoritative for this
+        // request's host, in which case the server answers with `421`, and
+        // the request can be sent again elsewhere (RFC 9110, Section 15.5.20).
+        // That needs a copy of the request, taken before it is sent.
+        let replay = if retry_misdirected && pooled.is_http2() && pooled.is_reused() {
+            try_clone_request(&req)
+        } else {
+            None
+        };
+
+        // Establishing the connection only counts for its first request.
+        let reused = pooled.is_reused();
+        let connect_times = (!reused).then(|| {
+            let times = pooled.connect_times;
+            (
+                pooled.conn_info.dns_duration.or(times.dns),
+                times.connect,
+                pooled.conn_info.tls_duration,
+            )
+        });
+        let sent_at = Instant::now();
+
         let fut = pooled
             .send_request_retryable(req)
-            .map_err(ClientError::map_with_reused(pooled.is_reused()));
+            .map_err(ClientError::map_with_reused(reused));
 
         // If the Connector included 'extra' info, add to Response...
         let extra_info = pooled.conn_info.extra.clone();
@@ -286,6 +370,11 @@ where
             if let Some(extra) = extra_info {
                 extra.set(res.extensions_mut());
             }
+            let mut timings = RequestTimings::new(sent_at.elapsed(), reused);
+            if let Some((dns, connect, tls)) = connect_times {
+                timings = timings.with_connect(dns, connect, tls);
+            }
+            res.extensions_mut().insert(timings);
             res
         });
 
@@ -302,6 +391,21 @@ where
 
         let mut res = fut.await?;
 
+        if let (Some(cache), Some(sent)) = (&self.auth_cache, sent_auth) {
+            cache.update(&pool_key, sent, res.status());
+        }
+
+        if res.status() == StatusCode::MISDIRECTED_REQUEST {
+            if let Some(req) = replay {
+                debug!(
+                    "misdirected request, evicting connection for {:?}",
+                    pool_key
+                );
+                self.pool.evict(&pool_key, EvictionReason::Misdirected);
+                return Err(ClientError::Misdirected { req });
+            }
+        }
+
         // If pooled is HTTP/2, we can toss this reference immediately.
         //
         // when pooled is dropped, it will try to insert back into the
@@ -312,14 +416,18 @@ where
         // for a new request to start.
         //
         // It won't be ready if there is a body to stream.
+        //
+        // The request's slot, if requests are limited, is freed along with it.
         if pooled.is_http2() || !pooled.is_pool_enabled() || pooled.is_ready() {
             drop(pooled);
+            drop(permit);
         } else if !res.body().is_end_stream() {
             let (delayed_tx, delayed_rx) = oneshot::channel();
             res.body_mut().delayed_eof(delayed_rx);
             let on_idle = future::poll_fn(move |cx| pooled.poll_ready(cx)).map(move |_| {
                 // At this point, `pooled` is dropped, and had a chance
                 // to insert into the pool (if conn was idle)
+                drop(permit);
                 drop(delayed_tx);
             });
 
@@ -327,7 +435,8 @@ where
         } else {
             // There's no body to delay, but the connection isn't
             // ready yet. Only re-insert when it's ready
-            let on_idle = future::poll_fn(move |cx| pooled.poll_ready(cx)).map(|_| ());
+            let on_idle =
+                future::poll_fn(move |cx| pooled.poll_ready(cx)).map(move |_| drop(permit));
 
             self.conn_builder.exec.execute(on_idle);
         }
@@ -352,73 +461,85 @@ where
         //   (an idle connection became available first), the started
         //   connection future is spawned into the runtime to complete,
         //   and then be inserted into the pool as an idle connection.
-        let checkout = self.pool.checkout(pool_key.clone());
-        let connect = self.connect_to(pool_key);
-        let is_ver_h2 = self.config.ver == Ver::Http2;
+        loop {
+            let checkout = self.pool.checkout(pool_key.clone());
+            let connect = self.connect_to(pool_key.clone());
+            let is_ver_h2 = self.config.ver == Ver::Http2;
 
-        // The order of the `select` is depended on below...
+            // The order of the `select` is depended on below...
 
-        match future::select(checkout, connect).await {
-            // Checkout won, connect future may have been started or not.
-            //
-            // If it has, let it finish and insert back into the pool,
-            // so as to not waste the socket...
-            Either::Left((Ok(checked_out), connecting)) => {
-                // This depends on the `select` above having the correct
-                // order, such that if the checkout future were ready
-                // immediately, the connect future will never have been
-                // started.
+            return match future::select(checkout, connect).await {
+                // Checkout won, connect future may have been started or not.
                 //
-                // If it *wasn't* ready yet, then the connect future will
-                // have been started...
-                if connecting.started() {
-                    let bg = connecting
-                        .map_err(|err| {
-                            trace!("background connect error: {}", err);
-                        })
-                        .map(|_pooled| {
-                            // dropping here should just place it in
-                            // the Pool for us...
-                        });
-                    // An execute error here isn't important, we're just trying
-                    // to prevent a waste of a socket...
-                    self.conn_builder.exec.execute(bg);
+                // If it has, let it finish and insert back into the pool,
+                // so as to not waste the socket...
+                Either::Left((Ok(checked_out), connecting)) => {
+                    // This depends on the `select` above having the correct
+                    // order, such that if the checkout future were ready
+                    // immediately, the connect future will never have been
+                    // started.
+                    //
+                    // If it *wasn't* ready yet, then the connect future will
+                    // have been started...
+                    if connecting.started() {
+                        let bg = connecting
+                            .map_err(|err| {
+                                trace!("background connect error: {}", err);
+                            })
+                            .map(|_pooled| {
+                                // dropping here should just place it in
+                                // the Pool for us...
+                            });
+                        // An execute error here isn't important, we're just trying
+                        // to prevent a waste of a socket...
+                        self.conn_builder.exec.execute(bg);
+                    }
+                    Ok(checked_out)
                 }
-                Ok(checked_out)
-            }
-            // Connect won, checkout can just be dropped.
-            Either::Right((Ok(connected), _checkout)) => Ok(connected),
-            // Either checkout or connect could get canceled:
-            //
-            // 1. Connect is canceled if this is HTTP/2 and there is
-            //    an outstanding HTTP/2 connecting task.
-            // 2. Checkout is canceled if the pool cannot deliver an
-            //    idle connection reliably.
-            //
-            // In both cases, we should just wait for the other future.
-            Either::Left((Err(err), connecting)) => {
-                if err.is_canceled() {
-                    connecting.await.map_err(ClientConnectError::Normal)
-                } else {
-                    Err(ClientConnectError::Normal(err))
+                // Connect won, checkout can just be dropped.
+                Either::Right((Ok(connected), _checkout)) => Ok(connected),
+                // Either checkout or connect could get canceled:
+                //
+                // 1. Connect is canceled if this is HTTP/2 and there is
+                //    an outstanding HTTP/2 connecting task.
+                // 2. Checkout is canceled if the pool cannot deliver an
+                //    idle connection reliably.
+                //
+                // In both cases, we should just wait for the other future.
+                Either::Left((Err(err), connecting)) => {
+                    if err.is_canceled() {
+                        connecting.await.map_err(ClientConnectError::Normal)
+                    } else {
+                        Err(ClientConnectError::Normal(err))
+                    }
                 }
-            }
-            Either::Right((Err(err), checkout)) => {
-                if err.is_canceled() {
-                    checkout.await.map_err(move |err| {
-                        if is_ver_h2
-                            && err.is_canceled()
-                            && err.find_source::<CheckoutIsClosedError>().is_some()
-                        {
-                            ClientConnectError::H2CheckoutIsClosed(err)
-                        } else {
-                            ClientConnectError::Normal(err)
+                Either::Right((Err(err), checkout)) => {
+                    if err.is_canceled() {
+                        let limited = err.find_source::<ConnectingLimitError>().is_some();
+                        match checkout.await {
+                            Ok(checked_out) => Ok(checked_out),
+                            // The connects that were waited for failed instead,
+                            // so try again, maybe connecting this time.
+                            Err(err) if limited && err.is_canceled() => {
+                                trace!("connects in progress failed, trying again");
+                                continue;
+                            }
+                            Err(err) => {
+                                if is_ver_h2
+                                    && err.is_canceled()
+                                    && err.find_source::<CheckoutIsClosedError>().is_some()
+                                {
+                                    Err(ClientConnectError::H2CheckoutIsClosed(err))
+                                } else {
+                                    Err(ClientConnectError::Normal(err))
+                                }
+                            }
                         }
-                    })
-                } else {
-                    Err(ClientConnectError::Normal(err))
+                    } else {
+                        Err(ClientConnectError::Normal(err))
+                    }
                 }
-            }
+            };
         }
     }
 
@@ -433,6 +554,8 @@ where
         #[cfg(feature = "http2")]
         let mut conn_builder = self.conn_builder.clone();
         let ver = self.config.ver;
+        #[cfg(feature = "runtime")]
+        let throttle = self.config.throttle;
         let is_ver_h2 = ver == Ver::Http2;
         let connector = self.connector.clone();
         let dst = domain_as_uri(pool_key.clone());
@@ -442,19 +565,25 @@ where
             // If the pool_key is for HTTP/2, and there is already a
             // connection being established, then this can't take a
             // second lock. The "connect_to" future is Canceled.
+            //
+            // The same goes for HTTP/1 when the limit of connections in
+            // progress for the pool_key is reached.
             let connecting = match pool.connecting(&pool_key, ver) {
                 Some(lock) => lock,
-                None => {
+                None if ver == Ver::Http2 => {
                     let canceled =
                         crate::Error::new_canceled().with("HTTP/2 connection in progress");
                     return Either::Right(future::err(canceled));
                 }
+                None => {
+                    let canceled = crate::Error::new_canceled().with(ConnectingLimitError);
+                    return Either::Right(future::err(canceled));
+                }
             };
             Either::Left(
-                connector
-                    .connect(connect::sealed::Internal, dst)
+                Timed::new(connector.connect(connect::sealed::Internal, dst))
                     .map_err(crate::Error::new_connect)
-                    .and_then(move |io| {
+                    .and_then(move |(io, connect_times)| {
                         let connected = io.connected();
                         // If ALPN is h2 and we aren't http2_only already,
                         // then we need to convert our pool checkout into
@@ -485,13 +614,37 @@ where
                         }
 
                         Either::Left(Box::pin(async move {
+                            #[cfg(feature = "runtime")]
+                            let (tx, conn) = match throttle {
+                                Some(throttle) => {
+                                    let io = Throttled::new(io, throttle);
+                                    let (tx, conn) = conn_builder.handshake(io).await?;
+                                    (tx, Either::Left(conn))
+                                }
+                                None => {
+                                    let (tx, conn) = conn_builder.handshake(io).await?;
+                                    (tx, Either::Right(conn))
+                                }
+                            };
+                            #[cfg(not(feature = "runtime"))]
                             let (tx, conn) = conn_builder.handshake(io).await?;
 
                             trace!("handshake complete, spawning background dispatcher task");
-                            executor.execute(
-                                conn.map_err(|e| debug!("client connection error: {}", e))
-                                    .map(|_| ()),
-                            );
+                            let close_reason = Arc::new(Mutex::new(None));
+                            let conn_close_reason = close_reason.clone();
+                            executor.execute(conn.map(move |res| {
+                                let reason = match res {
+                                    // An HTTP/2 connection only shuts down cleanly
+                                    // on its own after a GOAWAY.
+                                    Ok(()) if is_h2 => EvictionReason::GoAway,
+                                    Ok(()) => EvictionReason::ServerClosed,
+                                    Err(e) => {
+                                        debug!("client connection error: {}", e);
+                                        EvictionReason::Error
+                                    }
+                                };
+                                *conn_close_reason.lock().unwrap() = Some(reason);
+                            }));
 
                             // Wait for 'conn' to ready up before we
                             // declare this tx as usable
@@ -514,7 +667,9 @@ where
                                 connecting,
                                 PoolClient {
                                     conn_info: connected,
+                                    connect_times,
                                     tx,
+                                    close_reason,
                                 },
                             ))
                         }))
@@ -524,6 +679,81 @@ where
     }
 }
 
+impl<C> Client<C, Body>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// Send a `Request` whose body is written while the `Response` is
+    /// received.
+    ///
+    /// Returns a [`Sender`](crate::body::Sender) to write the request body
+    /// with, and a future yielding the `Response`, whose body can be read
+    /// while the request body is still being written. This allows
+    /// interleaving both, such as for bidirectional streaming RPCs over
+    /// HTTP/2.
+    ///
+    /// See [`SendRequest::send_duplex`](crate::client::conn::SendRequest::send_duplex)
+    /// for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature  = "runtime")]
+    /// # fn run () {
+    /// use fluxio::{Client, Method, Request};
+    ///
+    /// let client = Client::new();
+    ///
+    /// let req = Request::builder()
+    ///     .method(Method::POST)
+    ///     .uri("http://example.com/chat")
+    ///     .body(())
+    ///     .expect("request builder");
+    ///
+    /// let (body, future) = client.send_duplex(req);
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn send_duplex(&self, req: Request<()>) -> (crate::body::Sender, ResponseFuture) {
+        let (tx, body) = Body::channel();
+        (tx, self.request(req.map(|()| body)))
+    }
+
+    /// Returns a snapshot of the connections in the pool.
+    ///
+    /// This is empty if pooling is disabled. How long idle connections are
+    /// kept, and how many, is set with
+    /// [`Builder::pool_idle_timeout`](Builder::pool_idle_timeout) and
+    /// [`Builder::pool_max_idle_per_host`](Builder::pool_max_idle_per_host),
+    /// and [`Builder::pool_eviction_callback`](Builder::pool_eviction_callback)
+    /// is told whenever one is removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "runtime")]
+    /// # fn run () {
+    /// use fluxio::Client;
+    ///
+    /// let client = Client::new();
+    ///
+    /// for host in client.pool_stats().hosts() {
+    ///     println!(
+    ///         "{}://{}: {} idle, {} active",
+    ///         host.scheme(),
+    ///         host.authority(),
+    ///         host.idle(),
+    ///         host.active()
+    ///     );
+    /// }
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+}
+
 impl<C, B> tower_service::Service<Request<B>> for Client<C, B>
 where
     C: Connect + Clone + Send + Sync + 'static,
@@ -571,6 +801,7 @@ impl<C: Clone, B> Clone for Client<C, B> {
             conn_builder: self.conn_builder.clone(),
             connector: self.connector.clone(),
             pool: self.pool.clone(),
+            auth_cache: self.auth_cache.clone(),
         }
     }
 }
@@ -621,7 +852,10 @@ impl Future for ResponseFuture {
 #[allow(missing_debug_implementations)]
 struct PoolClient<B> {
     conn_info: Connected,
+    connect_times: ConnectTimes,
     tx: PoolTx<B>,
+    // Set by the connection task once the connection is done.
+    close_reason: Arc<Mutex<Option<EvictionReason>>>,
 }
 
 enum PoolTx<B> {
@@ -703,17 +937,23 @@ where
         match self.tx {
             PoolTx::Http1(tx) => Reservation::Unique(PoolClient {
                 conn_info: self.conn_info,
+                connect_times: self.connect_times,
                 tx: PoolTx::Http1(tx),
+                close_reason: self.close_reason,
             }),
             #[cfg(feature = "http2")]
             PoolTx::Http2(tx) => {
                 let b = PoolClient {
                     conn_info: self.conn_info.clone(),
+                    connect_times: self.connect_times,
                     tx: PoolTx::Http2(tx.clone()),
+                    close_reason: self.close_reason.clone(),
                 };
                 let a = PoolClient {
                     conn_info: self.conn_info,
+                    connect_times: self.connect_times,
                     tx: PoolTx::Http2(tx),
+                    close_reason: self.close_reason,
                 };
                 Reservation::Shared(a, b)
             }
@@ -723,6 +963,15 @@ where
     fn can_share(&self) -> bool {
         self.is_http2()
     }
+
+    fn close_reason(&self) -> EvictionReason {
+        // The task may not have finished yet, even though the connection
+        // can no longer be used.
+        self.close_reason
+            .lock()
+            .unwrap()
+            .unwrap_or(EvictionReason::ServerClosed)
+    }
 }
 
 // ===== impl ClientError =====
@@ -736,6 +985,9 @@ enum ClientError<B> {
         req: Request<B>,
         reason: crate::Error,
     },
+    Misdirected {
+        req: Request<B>,
+    },
 }
 
 impl<B> ClientError<B> {
@@ -766,6 +1018,23 @@ pub(super) enum Ver {
     Http2,
 }
 
+/// Copies a request, if its body is a fully buffered `Body`.
+///
+/// Extensions are not copied, as they can't be cloned.
+fn try_clone_request<B: 'static>(req: &Request<B>) -> Option<Request<B>> {
+    let body = (req.body() as &dyn Any)
+        .downcast_ref::<Body>()?
+        .try_clone_buffered()?;
+    let body = *(Box::new(body) as Box<dyn Any>).downcast::<B>().ok()?;
+
+    let mut clone = Request::new(body);
+    *clone.method_mut() = req.method().clone();
+    *clone.uri_mut() = req.uri().clone();
+    *clone.version_mut() = req.version();
+    *clone.headers_mut() = req.headers().clone();
+    Some(clone)
+}
+
 fn origin_form(uri: &mut Uri) {
     let path = match uri.path_and_query() {
         Some(path) if path.as_str() != "/" => {
@@ -860,7 +1129,7 @@ fn set_scheme(uri: &mut Uri, scheme: Scheme) {
     *uri = Uri::from_parts(parts).expect("scheme is valid");
 }
 
-fn get_non_default_port(uri: &Uri) -> Option<Port<&str>> {
+pub(super) fn get_non_default_port(uri: &Uri) -> Option<Port<&str>> {
     match (uri.port().map(|p| p.as_u16()), is_schema_secure(uri)) {
         (Some(443), true) => None,
         (Some(80), false) => None,
@@ -906,13 +1175,25 @@ impl Default for Builder {
         Self {
             client_config: Config {
                 retry_canceled_requests: true,
-                set_host: true,
+                retry_misdirected_requests: true,
+                idempotency_key: None,
+                preemptive_auth: false,
+                host: HostPolicy::SetIfMissing,
+                #[cfg(feature = "runtime")]
+                throttle: None,
                 ver: Ver::Auto,
             },
             conn_builder: conn::Builder::new(),
             pool_config: pool::Config {
                 idle_timeout: Some(Duration::from_secs(90)),
                 max_idle_per_host: std::usize::MAX,
+                max_connecting_per_host: usize::MAX,
+                max_requests_per_host: usize::MAX,
+                max_requests: usize::MAX,
+                queue_order: QueueOrder::Fifo,
+                #[cfg(feature = "runtime")]
+                wait_timeout: None,
+                on_evict: None,
             },
         }
     }
@@ -973,6 +1254,123 @@ impl Builder {
         self
     }
 
+    /// Sets the maximum number of HTTP/1 connections being established to
+    /// a host at the same time.
+    ///
+    /// When this many connects to a host are in progress, further requests
+    /// to it don't start their own, and instead wait for one of those
+    /// connections to become idle. This keeps a burst of requests to a host
+    /// with no pooled connection from opening a socket each. There is only
+    /// ever one connect in progress for HTTP/2, which is shared.
+    ///
+    /// This has no effect if pooling is disabled.
+    ///
+    /// Default is `usize::MAX` (no limit).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max` is 0.
+    pub fn pool_max_connecting_per_host(&mut self, max: usize) -> &mut Self {
+        assert!(max > 0, "pool_max_connecting_per_host must be at least 1");
+        self.pool_config.max_connecting_per_host = max;
+        self
+    }
+
+    /// Sets the maximum number of requests to a host in flight at the same
+    /// time.
+    ///
+    /// Requests over the limit wait until one of the host's requests is
+    /// done, in the order set with [`pool_queue_order`](Builder::pool_queue_order),
+    /// and for at most [`pool_wait_timeout`](Builder::pool_wait_timeout). With
+    /// pooling enabled, an HTTP/1 request is done once its connection is
+    /// ready for another one, after its response body has been read. Since
+    /// such a connection is only used for one request at a time, this also
+    /// bounds the number of connections to the host. Other requests are done
+    /// once their response head arrived.
+    ///
+    /// Default is `usize::MAX` (no limit).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max` is 0.
+    pub fn pool_max_requests_per_host(&mut self, max: usize) -> &mut Self {
+        assert!(max > 0, "pool_max_requests_per_host must be at least 1");
+        self.pool_config.max_requests_per_host = max;
+        self
+    }
+
+    /// Sets the maximum number of requests in flight at the same time, to
+    /// all hosts.
+    ///
+    /// Requests over the limit wait like those over
+    /// [`pool_max_requests_per_host`](Builder::pool_max_requests_per_host).
+    ///
+    /// Default is `usize::MAX` (no limit).
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max` is 0.
+    pub fn pool_max_requests(&mut self, max: usize) -> &mut Self {
+        assert!(max > 0, "pool_max_requests must be at least 1");
+        self.pool_config.max_requests = max;
+        self
+    }
+
+    /// Sets the order in which requests waiting because of the request
+    /// limits are let through.
+    ///
+    /// Default is [`QueueOrder::Fifo`].
+    pub fn pool_queue_order(&mut self, order: QueueOrder) -> &mut Self {
+        self.pool_config.queue_order = order;
+        self
+    }
+
+    /// Sets how long a request waits because of the request limits before
+    /// failing.
+    ///
+    /// A request that waited this long fails with an error for which
+    /// [`Error::is_pool_exhausted`](crate::Error::is_pool_exhausted) returns
+    /// `true`. Pass `None` to wait as long as it takes.
+    ///
+    /// Default is `None`.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn pool_wait_timeout(&mut self, timeout: impl Into<Option<Duration>>) -> &mut Self {
+        self.pool_config.wait_timeout = timeout.into();
+        self
+    }
+
+    /// Sets a callback called whenever an idle connection is removed from
+    /// the pool, with the host it was connected to and the reason it was
+    /// removed.
+    ///
+    /// The callback is called with the pool locked, and so should return
+    /// quickly, and must not make requests with this `Client`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "runtime")]
+    /// # fn run () {
+    /// use fluxio::Client;
+    ///
+    /// let client = Client::builder()
+    ///     .pool_eviction_callback(|host, reason| {
+    ///         println!("evicted connection to {}: {}", host, reason);
+    ///     })
+    ///     .build_http::<fluxio::Body>();
+    /// # drop(client);
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn pool_eviction_callback<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(&Authority, EvictionReason) + Send + Sync + 'static,
+    {
+        self.pool_config.on_evict = Some(pool::OnEvict::new(callback));
+        self
+    }
+
     // HTTP/1 options
 
     /// Sets the exact size of the read buffer to *always* use.
@@ -1001,6 +1399,161 @@ impl Builder {
         self
     }
 
+    /// Set the minimum buffer size for the connection.
+    ///
+    /// Read buffers start at this size, and ones grown beyond it are released
+    /// while connections wait for a new response, such as idle pooled
+    /// connections.
+    ///
+    /// Default is 8kb.
+    ///
+    /// Note that setting this option unsets the `http1_read_exact_buf_size` option.
+    ///
+    /// # Panics
+    ///
+    /// The minimum value allowed is 64. This method panics if the passed `min` is less than the minimum.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_min_buf_size(&mut self, min: usize) -> &mut Self {
+        self.conn_builder.http1_min_buf_size(min);
+        self
+    }
+
+    /// Set the maximum number of informational (1xx) responses accepted
+    /// before the final response to a request.
+    ///
+    /// A server sending more fails the request with an error for which
+    /// [`Error::is_parse_too_many_informational`] returns true.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is 10.
+    ///
+    /// [`Error::is_parse_too_many_informational`]: crate::Error::is_parse_too_many_informational
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_informational_responses(&mut self, max: usize) -> &mut Self {
+        self.conn_builder.http1_max_informational_responses(max);
+        self
+    }
+
+    /// Set the maximum number of headers of a response.
+    ///
+    /// A server sending more fails the request with an error for which
+    /// [`Error::is_parse_too_many_headers`] returns true.
+    ///
+    /// Up to 100 headers are parsed into memory on the stack, and more into
+    /// memory allocated for each response.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is 100.
+    ///
+    /// [`Error::is_parse_too_many_headers`]: crate::Error::is_parse_too_many_headers
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_headers(&mut self, max: usize) -> &mut Self {
+        self.conn_builder.http1_max_headers(max);
+        self
+    }
+
+    /// Set the maximum size of a response header value, in bytes.
+    ///
+    /// A server sending a larger one fails the request with an error for
+    /// which [`Error::is_parse_header_value_too_large`] returns true.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is no limit other than the size of the read buffer.
+    ///
+    /// [`Error::is_parse_header_value_too_large`]: crate::Error::is_parse_header_value_too_large
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_header_value_size(&mut self, max: usize) -> &mut Self {
+        self.conn_builder.http1_max_header_value_size(max);
+        self
+    }
+
+    /// Set how responses with multiple `Content-Length` values are read.
+    ///
+    /// See [`ContentLengthPolicy`](crate::ext::ContentLengthPolicy) for the
+    /// choices.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is `ContentLengthPolicy::MergeIfEqual`.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_content_length_policy(
+        &mut self,
+        policy: crate::ext::ContentLengthPolicy,
+    ) -> &mut Self {
+        self.conn_builder.http1_content_length_policy(policy);
+        self
+    }
+
+    /// Set how many bytes of a response body are read ahead, as soon as the
+    /// response head arrived and before the body is polled.
+    ///
+    /// Small responses are then usually complete by the time they are
+    /// polled. Reading pauses once about `max` bytes are buffered, and
+    /// resumes once half of them were polled, so a large body is never
+    /// buffered entirely. A chunk read off the connection may exceed
+    /// `max`.
+    ///
+    /// Note that this setting does not affect HTTP/2, where the stream
+    /// window bounds the data received ahead.
+    ///
+    /// Default is `0`, reading a single chunk ahead.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_response_body_prefetch(&mut self, max: usize) -> &mut Self {
+        self.conn_builder.http1_response_body_prefetch(max);
+        self
+    }
+
+    /// Set a callback choosing how request bodies of unknown length are
+    /// framed.
+    ///
+    /// The callback is called with the size hint of each such body. See
+    /// [`BodyFraming`](crate::ext::BodyFraming) for the choices; buffering small bodies sends them
+    /// with a `Content-Length`, for servers that can't read chunked
+    /// requests.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is `BodyFraming::Chunked`.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_body_framing<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&crate::body::SizeHint) -> crate::ext::BodyFraming + Send + Sync + 'static,
+    {
+        self.conn_builder.http1_body_framing(f);
+        self
+    }
+
+    /// Check received responses against MUST-level requirements of RFC 9110
+    /// and RFC 9112, and call `callback` with each violation found.
+    ///
+    /// The checks include a valid status code range, no `Content-Length` or
+    /// `Transfer-Encoding` on 1xx and 204 responses, no content after a 204
+    /// or 304 response, and a consistent `Content-Length`. This is meant for
+    /// tooling testing servers for compliance: responses are still handled
+    /// as leniently as without validation, and the callback is called before
+    /// a response that can't be handled fails the request.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_strict_response_validation<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(StatusCode, ResponseViolation) + Send + Sync + 'static,
+    {
+        self.conn_builder.http1_strict_response_validation(callback);
+        self
+    }
+
     /// Set whether HTTP/1 connections will accept spaces between header names
     /// and the colon that follow them in responses.
     ///
@@ -1066,6 +1619,22 @@ impl Builder {
         self
     }
 
+    /// Set whether HTTP/1 connections will skip invalid header lines in
+    /// responses, instead of failing the request.
+    ///
+    /// Each skipped line is reported as [`InvalidHeader`](crate::client::ResponseViolation::InvalidHeader)
+    /// to the callback set with
+    /// [`http1_strict_response_validation`](Builder::http1_strict_response_validation).
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    pub fn http1_ignore_invalid_headers_in_responses(&mut self, val: bool) -> &mut Self {
+        self.conn_builder
+            .http1_ignore_invalid_headers_in_responses(val);
+        self
+    }
+
     /// Set whether HTTP/1 connections should try to use vectored writes,
     /// or always flatten into a single buffer.
     ///
@@ -1298,18 +1867,233 @@ impl Builder {
         self
     }
 
+    /// Set whether to retry requests answered with `421 Misdirected Request`
+    /// on a reused HTTP/2 connection.
+    ///
+    /// A server can send a `421` when a shared connection was reused for a
+    /// host it is not authoritative for. When enabled, the connection is no
+    /// longer used for that host, and the request is sent once more on a
+    /// fresh connection.
+    ///
+    /// Only requests with a [`Body`](crate::Body) that is empty or fully
+    /// buffered, such as one created from `Bytes` or a `String`, can be
+    /// retried. Extensions of the original request are not copied to the
+    /// retried one.
+    ///
+    /// Default is `true`.
+    #[inline]
+    pub fn retry_misdirected_requests(&mut self, val: bool) -> &mut Self {
+        self.client_config.retry_misdirected_requests = val;
+        self
+    }
+
+    /// Set an idempotency key to attach to replay-safe requests.
+    ///
+    /// When retries are enabled (see
+    /// [`retry_canceled_requests`](Builder::retry_canceled_requests)), any
+    /// non-idempotent request carrying the [`ReplaySafe`](super::ReplaySafe)
+    /// extension, and not already including the configured header, gets a
+    /// freshly generated key. The same key is sent again on every retry of
+    /// that request, letting servers that support idempotency keys
+    /// deduplicate it.
+    ///
+    /// Default is `None`.
+    #[inline]
+    pub fn idempotency_key(&mut self, key: IdempotencyKey) -> &mut Self {
+        self.client_config.idempotency_key = Some(key);
+        self
+    }
+
+    /// Set whether to cache accepted credentials, and send them preemptively.
+    ///
+    /// When enabled, the `Proxy-Authorization` header of a request forwarded
+    /// through a proxy, and an `Authorization` header using the `Basic`
+    /// scheme, are remembered for the scheme and authority of the request,
+    /// once its response is successful (`2xx`) or a redirect (`3xx`). Later
+    /// requests to the same destination without the header get the cached
+    /// one, saving the round trip of being challenged first. A `407 Proxy
+    /// Authentication Required` or `401 Unauthorized` challenge of cached
+    /// credentials drops them again.
+    ///
+    /// `Proxy-Authorization` is only added to requests forwarded to a proxy,
+    /// never to ones tunneled through it, which would send it on to the
+    /// origin. `Authorization` is added either way. The cache is shared by
+    /// clones of the `Client`.
+    ///
+    /// # Note
+    ///
+    /// The cache is keyed only by destination, not by who made the request.
+    /// If one `Client` is shared by several users, such as the tenants of a
+    /// service, the credentials of one of them are sent with the requests
+    /// of all others to the same destination. Only enable this for clients
+    /// that act on behalf of a single user.
+    ///
+    /// Default is `false`.
+    #[inline]
+    pub fn preemptive_auth(&mut self, enabled: bool) -> &mut Self {
+        self.client_config.preemptive_auth = enabled;
+        self
+    }
+
     /// Set whether to automatically add the `Host` header to requests.
     ///
     /// If true, and a request does not include a `Host` header, one will be
-    /// added automatically, derived from the authority of the `Uri`.
+    /// added automatically, derived from the authority of the `Uri`. This
+    /// is the same as `host_policy(HostPolicy::SetIfMissing)`, and false as
+    /// `host_policy(HostPolicy::Never)`.
     ///
     /// Default is `true`.
     #[inline]
     pub fn set_host(&mut self, val: bool) -> &mut Self {
-        self.client_config.set_host = val;
+        self.client_config.host = if val {
+            HostPolicy::SetIfMissing
+        } else {
+            HostPolicy::Never
+        };
+        self
+    }
+
+    /// Set how the `Host` header of HTTP/1 requests is derived from their
+    /// `Uri`.
+    ///
+    /// See [`HostPolicy`] for the choices.
+    ///
+    /// Default is `HostPolicy::SetIfMissing`.
+    #[inline]
+    pub fn host_policy(&mut self, policy: HostPolicy) -> &mut Self {
+        self.client_config.host = policy;
+        self
+    }
+
+    /// Set rate limits applied to the transport of every connection.
+    ///
+    /// Each connection is limited on its own, so the limits add up over
+    /// connections to several hosts, or several connections to one host.
+    /// The IO of an upgraded connection is the connector's, wrapped in a
+    /// [`Throttled`].
+    ///
+    /// Default is no limits.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn throttle(&mut self, throttle: Throttle) -> &mut Self {
+        self.client_config.throttle = Some(throttle);
+        self
+    }
+
+    /// Set a sink for the metrics of this client's connections and requests.
+    ///
+    /// See the [`metrics`](crate::metrics) module for more.
+    pub fn metrics(&mut self, sink: Arc<dyn MetricsSink>) -> &mut Self {
+        self.conn_builder.metrics(sink);
+        self
+    }
+
+    /// Set an observer for the lifecycle events of this client's
+    /// connections.
+    ///
+    /// Note that HTTP/2 connections are not observed yet.
+    ///
+    /// See the [`observer`](crate::observer) module for more.
+    pub fn observer(&mut self, observer: Arc<dyn ConnectionObserver>) -> &mut Self {
+        self.conn_builder.observer(observer);
+        self
+    }
+
+    /// Set the content codings to transparently decode in responses.
+    ///
+    /// See [`conn::Builder::decompress`](super::conn::Builder::decompress)
+    /// for more.
+    #[cfg(feature = "decompress")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "decompress")))]
+    pub fn decompress<I>(&mut self, encodings: I) -> &mut Self
+    where
+        I: IntoIterator<Item = crate::body::ContentEncoding>,
+    {
+        self.conn_builder.decompress(encodings);
+        self
+    }
+
+    /// Set the maximum size of decoded response bodies, in bytes.
+    ///
+    /// See
+    /// [`conn::Builder::decompress_max_size`](super::conn::Builder::decompress_max_size)
+    /// for more.
+    #[cfg(feature = "decompress")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "decompress")))]
+    pub fn decompress_max_size(&mut self, max: u64) -> &mut Self {
+        self.conn_builder.decompress_max_size(max);
+        self
+    }
+
+    /// Set how long to wait for the head of a response.
+    ///
+    /// See [`conn::Builder::response_header_timeout`](super::conn::Builder::response_header_timeout)
+    /// for more.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn response_header_timeout(&mut self, timeout: impl Into<Option<Duration>>) -> &mut Self {
+        self.conn_builder.response_header_timeout(timeout);
+        self
+    }
+
+    /// Set how long a response body can be waited on without receiving data.
+    ///
+    /// See [`conn::Builder::body_idle_timeout`](super::conn::Builder::body_idle_timeout)
+    /// for more.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn body_idle_timeout(&mut self, timeout: impl Into<Option<Duration>>) -> &mut Self {
+        self.conn_builder.body_idle_timeout(timeout);
+        self
+    }
+
+    /// Set a rate limit, in bytes per second, for sending request bodies.
+    ///
+    /// See [`conn::Builder::http1_upload_rate_limit`](super::conn::Builder::http1_upload_rate_limit)
+    /// for more.
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "runtime"))))]
+    pub fn http1_upload_rate_limit(&mut self, bytes_per_sec: impl Into<Option<u64>>) -> &mut Self {
+        self.conn_builder.http1_upload_rate_limit(bytes_per_sec);
+        self
+    }
+
+    /// Configure connections for the upstream side of a proxy.
+    ///
+    /// See [`conn::Builder::preset_lenient_proxy`](super::conn::Builder::preset_lenient_proxy)
+    /// for the options set.
+    pub fn preset_lenient_proxy(&mut self) -> &mut Self {
+        self.conn_builder.preset_lenient_proxy();
+        self
+    }
+
+    /// Configure connections to use as little memory as possible.
+    ///
+    /// See [`conn::Builder::preset_low_memory`](super::conn::Builder::preset_low_memory)
+    /// for the options set. This also keeps at most one idle connection per
+    /// host in the pool.
+    pub fn preset_low_memory(&mut self) -> &mut Self {
+        self.conn_builder.preset_low_memory();
+        self.pool_max_idle_per_host(1);
         self
     }
 
+    /// Configure connections with a [hardened profile](crate::hardened).
+    ///
+    /// See [`conn::Builder::preset_hardened`](super::conn::Builder::preset_hardened)
+    /// for more.
+    pub fn preset_hardened(&mut self, profile: crate::hardened::Profile) -> &mut Self {
+        self.conn_builder.preset_hardened(profile);
+        self
+    }
+
+    /// Returns the hardened profile applied with
+    /// [`preset_hardened`](Builder::preset_hardened), and the options of it
+    /// overridden since.
+    pub fn hardened_posture(&self) -> Option<&crate::hardened::Posture> {
+        self.conn_builder.hardened_posture()
+    }
+
     /// Provide an executor to execute background `Connection` tasks.
     pub fn executor<E>(&mut self, exec: E) -> &mut Self
     where
@@ -1341,10 +2125,15 @@ impl Builder {
         B::Data: Send,
     {
         Client {
-            config: self.client_config,
+            config: self.client_config.clone(),
             conn_builder: self.conn_builder.clone(),
             connector,
-            pool: Pool::new(self.pool_config, &self.conn_builder.exec),
+            pool: Pool::new(self.pool_config.clone(), &self.conn_builder.exec),
+            auth_cache: if self.client_config.preemptive_auth {
+                Some(AuthCache::new())
+            } else {
+                None
+            },
         }
     }
 }
@@ -1411,6 +2200,25 @@ mod unit_tests {
         assert_eq!(uri.to_string(), "hyper.rs");
     }
 
+    #[test]
+    fn test_try_clone_request() {
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("https://hyper.rs/guides")
+            .header("content-type", "text/plain")
+            .body(Body::from("hello"))
+            .unwrap();
+        let clone = try_clone_request(&req).expect("buffered body");
+        assert_eq!(clone.method(), Method::POST);
+        assert_eq!(clone.uri(), req.uri());
+        assert_eq!(clone.headers(), req.headers());
+
+        // streaming bodies, and other body types, can't be replayed
+        let (_tx, body) = Body::channel();
+        assert!(try_clone_request(&Request::new(body)).is_none());
+        assert!(try_clone_request(&Request::new(String::from("hello"))).is_none());
+    }
+
     #[test]
     fn test_extract_domain_connect_no_port() {
         let mut uri = "hyper.rs".parse().unwrap();
@@ -1462,4 +2270,26 @@ mod unit_tests {
             123
         );
     }
+
+    #[test]
+    fn presets_configure_connections() {
+        let mut builder = Client::builder();
+        builder.preset_lenient_proxy();
+        let mut expected = conn::Builder::new();
+        expected.preset_lenient_proxy();
+        assert_eq!(
+            format!("{:?}", builder.conn_builder),
+            format!("{:?}", expected)
+        );
+
+        let mut builder = Client::builder();
+        builder.preset_low_memory();
+        let mut expected = conn::Builder::new();
+        expected.preset_low_memory();
+        assert_eq!(
+            format!("{:?}", builder.conn_builder),
+            format!("{:?}", expected)
+        );
+        assert_eq!(builder.pool_config.max_idle_per_host, 1);
+    }
 }
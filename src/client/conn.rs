@@ -59,31 +59,51 @@ use std::fmt;
 #[cfg(not(all(feature = "http1", feature = "http2")))]
 use std::marker::PhantomData;
 use std::sync::Arc;
-#[cfg(all(feature = "runtime", feature = "http2"))]
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use futures_util::future::{self, Either, FutureExt as _};
+#[cfg(feature = "http1")]
+use http::StatusCode;
 use httparse::ParserConfig;
 use pin_project_lite::pin_project;
 use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "http2")]
+use tokio::sync::watch;
 use tower_service::Service;
-use tracing::{debug, trace};
+use tracing::{debug, debug_span, trace, Instrument, Span};
 
 use super::dispatch;
+use super::timeout::{ResponseHead, Timeouts};
+use super::validate::OnViolation;
+#[cfg(feature = "http1")]
+use super::validate::ResponseViolation;
 use crate::body::HttpBody;
+#[cfg(feature = "http1")]
+use crate::body::SizeHint;
+#[cfg(feature = "decompress")]
+use crate::body::{ContentEncoding, Decompress};
 #[cfg(not(all(feature = "http1", feature = "http2")))]
 use crate::common::Never;
 use crate::common::{
     exec::{BoxSendFuture, Exec},
     task, Future, Pin, Poll,
 };
+#[cfg(feature = "http1")]
+use crate::ext::{BodyFraming, BodyFramingFn, ContentLengthPolicy};
+use crate::hardened::{Posture, Profile};
+use crate::metrics::{BandwidthEstimate, MetricsSink, Recorder};
+use crate::observer::{ConnectionObserver, ObserverRef};
 use crate::proto;
 use crate::rt::Executor;
 #[cfg(feature = "http1")]
 use crate::upgrade::Upgraded;
 use crate::{Body, Request, Response};
 
+pub use self::context::ConnContext;
+
+mod context;
+
 #[cfg(feature = "http1")]
 type Http1Dispatcher<T, B> =
     proto::dispatch::Dispatcher<proto::dispatch::Client<B>, B, T, proto::h1::ClientTransaction>;
@@ -130,6 +150,15 @@ where
 /// The sender side of an established connection.
 pub struct SendRequest<B> {
     dispatch: dispatch::Sender<Request<B>, Response<Body>>,
+    metrics: Option<Recorder>,
+    context: Option<ConnContext>,
+    #[cfg(feature = "decompress")]
+    decompress: Decompress,
+    timeouts: Timeouts,
+    #[cfg(feature = "http2")]
+    settings: Option<watch::Receiver<bool>>,
+    #[cfg(feature = "http2")]
+    pinger: Option<proto::h2::ping::Pinger>,
 }
 
 /// A future that processes all HTTP state for the IO object.
@@ -143,6 +172,8 @@ where
     B: HttpBody + 'static,
 {
     inner: Option<ProtoClient<T, B>>,
+    context: Option<ConnContext>,
+    span: Span,
 }
 
 /// A builder to configure an HTTP connection.
@@ -151,6 +182,12 @@ where
 #[derive(Clone, Debug)]
 pub struct Builder {
     pub(super) exec: Exec,
+    pub(super) metrics: Option<Recorder>,
+    context: Option<ConnContext>,
+    observer: Option<ObserverRef>,
+    #[cfg(feature = "decompress")]
+    decompress: Decompress,
+    timeouts: Timeouts,
     h09_responses: bool,
     h1_parser_config: ParserConfig,
     h1_writev: Option<bool>,
@@ -160,11 +197,27 @@ pub struct Builder {
     h1_preserve_header_order: bool,
     h1_read_buf_exact_size: Option<usize>,
     h1_max_buf_size: Option<usize>,
+    h1_min_buf_size: Option<usize>,
+    h1_max_informational: Option<usize>,
+    h1_max_headers: Option<usize>,
+    h1_max_header_value_size: Option<usize>,
+    #[cfg(feature = "http1")]
+    h1_content_length_policy: ContentLengthPolicy,
+    h1_body_prefetch: usize,
+    #[cfg(feature = "http1")]
+    h1_body_framing: Option<BodyFramingFn>,
+    h1_on_violation: Option<OnViolation>,
     #[cfg(feature = "ffi")]
     h1_headers_raw: bool,
+    #[cfg(feature = "runtime")]
+    h1_upload_rate_limit: Option<u64>,
+    h1_keep_alive: bool,
+    #[cfg(feature = "runtime")]
+    h1_idle_timeout: Option<Duration>,
     #[cfg(feature = "http2")]
     h2_builder: proto::h2::client::Config,
     version: Proto,
+    hardened: Option<Posture>,
 }
 
 #[derive(Clone, Debug)]
@@ -181,10 +234,31 @@ enum Proto {
 #[must_use = "futures do nothing unless polled"]
 pub struct ResponseFuture {
     inner: ResponseFutureState,
+    metrics: Option<(Recorder, Instant)>,
+    context: Option<ConnContext>,
+    #[cfg(feature = "decompress")]
+    decompress: Decompress,
+    timeouts: Timeouts,
+}
+
+/// A future returned by `SendRequest::settings_exchanged`.
+///
+/// Yields `Ok(())` once the initial SETTINGS were exchanged.
+#[must_use = "futures do nothing unless polled"]
+pub struct SettingsExchanged {
+    inner: Pin<Box<dyn Future<Output = crate::Result<()>> + Send>>,
+}
+
+/// A future returned by `SendRequest::ping`.
+///
+/// Yields the round trip time once the peer acknowledged the PING.
+#[must_use = "futures do nothing unless polled"]
+pub struct Pong {
+    inner: Pin<Box<dyn Future<Output = crate::Result<Duration>> + Send>>,
 }
 
 enum ResponseFutureState {
-    Waiting(dispatch::Promise<Response<Body>>),
+    Waiting(ResponseHead<dispatch::Promise<Response<Body>>>),
     // Option is to be able to `take()` it in `poll`
     Error(Option<crate::Error>),
 }
@@ -217,6 +291,10 @@ pub struct Parts<T> {
 #[cfg(feature = "http2")]
 pub(super) struct Http2SendRequest<B> {
     dispatch: dispatch::UnboundedSender<Request<B>, Response<Body>>,
+    metrics: Option<Recorder>,
+    #[cfg(feature = "decompress")]
+    decompress: Decompress,
+    timeouts: Timeouts,
 }
 
 // ===== impl SendRequest
@@ -238,18 +316,106 @@ impl<B> SendRequest<B> {
         .await
     }
 
-    pub(super) fn is_ready(&self) -> bool {
+    pub(crate) fn is_ready(&self) -> bool {
         self.dispatch.is_ready()
     }
 
-    pub(super) fn is_closed(&self) -> bool {
+    pub(crate) fn is_closed(&self) -> bool {
         self.dispatch.is_closed()
     }
 
+    /// Returns a future that resolves once the initial SETTINGS were
+    /// exchanged with the peer of an HTTP/2 connection.
+    ///
+    /// The handshake completes when the connection preface was sent. This
+    /// waits until the peer's SETTINGS were received too, so its limits,
+    /// such as the maximum number of concurrent streams, are known before
+    /// sending a burst of requests. To tell when that happened, a PING is sent
+    /// right after the preface.
+    ///
+    /// The future yields an error if the connection closed before that. For
+    /// HTTP/1 connections, it resolves immediately.
+    pub fn settings_exchanged(&self) -> SettingsExchanged {
+        #[cfg(feature = "http2")]
+        {
+            if let Some(mut settings) = self.settings.clone() {
+                return SettingsExchanged {
+                    inner: Box::pin(async move {
+                        settings.wait_for(|exchanged| *exchanged).await.map_err(|_| {
+                            crate::Error::new_canceled()
+                                .with("connection closed before SETTINGS were exchanged")
+                        })?;
+                        Ok(())
+                    }),
+                };
+            }
+        }
+
+        SettingsExchanged {
+            inner: Box::pin(future::ok(())),
+        }
+    }
+
+    /// Sends a PING to the peer of an HTTP/2 connection, to check that it is
+    /// still responsive.
+    ///
+    /// The returned future yields the round trip time once the peer
+    /// acknowledged it. If a PING is in flight already, such as one sent for
+    /// keep-alive, this waits for that one instead of sending another.
+    ///
+    /// The future yields an error if the connection closed first, and right
+    /// away for HTTP/1 connections, which have no PINGs. It doesn't time
+    /// out on its own, so it's typically raced against a timer.
+    pub fn ping(&self) -> Pong {
+        #[cfg(feature = "http2")]
+        {
+            if let Some(ref pinger) = self.pinger {
+                let pong = pinger.ping();
+                return Pong {
+                    inner: Box::pin(async move {
+                        pong.await.map_err(|_| {
+                            crate::Error::new_canceled()
+                                .with("connection closed before PING was acknowledged")
+                        })
+                    }),
+                };
+            }
+        }
+
+        Pong {
+            inner: Box::pin(future::err(
+                crate::Error::new_user_unsupported_version()
+                    .with("PING requires an HTTP/2 connection"),
+            )),
+        }
+    }
+
+    /// Returns the latest estimate of the bandwidth and round trip time of an
+    /// HTTP/2 connection.
+    ///
+    /// Estimates are made from the data received between PINGs, and only
+    /// with [`http2_adaptive_window`](Builder::http2_adaptive_window)
+    /// enabled. This returns `None` before the first one, and always for
+    /// HTTP/1 connections. A [`MetricsSink`] is told about each new estimate.
+    pub fn bandwidth_estimate(&self) -> Option<BandwidthEstimate> {
+        #[cfg(feature = "http2")]
+        {
+            if let Some(ref pinger) = self.pinger {
+                return pinger.bandwidth_estimate();
+            }
+        }
+
+        None
+    }
+
     #[cfg(feature = "http2")]
     pub(super) fn into_http2(self) -> Http2SendRequest<B> {
         Http2SendRequest {
             dispatch: self.dispatch.unbound(),
+            metrics: self.metrics,
+            #[cfg(feature = "decompress")]
+            decompress: self.decompress,
+            timeouts: self.timeouts,
         }
     }
 }
@@ -299,34 +465,63 @@ where
     /// # }
     /// # fn main() {}
     /// ```
-    pub fn send_request(&mut self, req: Request<B>) -> ResponseFuture {
+    pub fn send_request(
+        &mut self,
+        #[cfg_attr(not(feature = "decompress"), allow(unused_mut))] mut req: Request<B>,
+    ) -> ResponseFuture {
+        #[cfg(feature = "decompress")]
+        self.decompress.prepare_request(&mut req);
         let inner = match self.dispatch.send(req) {
-            Ok(rx) => ResponseFutureState::Waiting(rx),
+            Ok(rx) => ResponseFutureState::Waiting(self.timeouts.response_head(rx)),
             Err(_req) => {
                 debug!("connection was not ready");
                 let err = crate::Error::new_canceled().with("connection was not ready");
                 ResponseFutureState::Error(Some(err))
             }
         };
-
-        ResponseFuture { inner }
+        let metrics = self.metrics.clone().map(|metrics| (metrics, Instant::now()));
+
+        ResponseFuture {
+            inner,
+            metrics,
+            context: self.context.clone(),
+            #[cfg(feature = "decompress")]
+            decompress: self.decompress,
+            timeouts: self.timeouts,
+        }
     }
 
     pub(super) fn send_request_retryable(
         &mut self,
-        req: Request<B>,
+        #[cfg_attr(not(feature = "decompress"), allow(unused_mut))] mut req: Request<B>,
     ) -> impl Future<Output = Result<Response<Body>, (crate::Error, Option<Request<B>>)>> + Unpin
     where
         B: Send,
     {
+        #[cfg(feature = "decompress")]
+        self.decompress.prepare_request(&mut req);
+        #[cfg(feature = "decompress")]
+        let decompress = self.decompress;
+        let timeouts = self.timeouts;
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
         match self.dispatch.try_send(req) {
             Ok(rx) => {
-                Either::Left(rx.then(move |res| {
+                Either::Left(timeouts.response_head(rx).then(move |res| {
                     match res {
-                        Ok(Ok(res)) => future::ok(res),
-                        Ok(Err(err)) => future::err(err),
+                        Ok(Ok(Ok(res))) => {
+                            if let Some(metrics) = metrics {
+                                metrics.request_latency(start.elapsed());
+                            }
+                            let res = timeouts.response(res);
+                            #[cfg(feature = "decompress")]
+                            let res = decompress.response(res);
+                            future::ok(res)
+                        }
+                        Ok(Ok(Err(err))) => future::err(err),
                         // this is definite bug if it happens, but it shouldn't happen!
-                        Err(_) => panic!("dispatch dropped without returning error"),
+                        Ok(Err(_)) => panic!("dispatch dropped without returning error"),
+                        Err(timeout) => future::err((timeout, None)),
                     }
                 }))
             }
@@ -339,6 +534,48 @@ where
     }
 }
 
+impl SendRequest<Body> {
+    /// Sends a `Request` whose body is written while the `Response` is
+    /// received.
+    ///
+    /// Returns a [`Sender`](crate::body::Sender) to write the request body
+    /// with, and a future yielding the `Response` once its head arrived.
+    /// The response body can be read while the request body is still being
+    /// written, so both can be interleaved, such as for bidirectional
+    /// streaming RPCs. The request body ends once the `Sender` is dropped.
+    ///
+    /// Over HTTP/1, the request body is sent with chunked encoding, unless
+    /// `req` has a `Content-Length`. Note that many HTTP/1 servers only
+    /// respond once they received the whole request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use fluxio::client::conn::SendRequest;
+    /// # use fluxio::body::HttpBody;
+    /// # use fluxio::Body;
+    /// use fluxio::Request;
+    ///
+    /// # async fn doc(mut tx: SendRequest<Body>) -> fluxio::Result<()> {
+    /// let req = Request::post("/chat").body(()).unwrap();
+    /// let (mut body, res) = tx.send_duplex(req);
+    ///
+    /// body.send_data("hello".into()).await?;
+    /// let mut res = res.await?.into_body();
+    /// while let Some(chunk) = res.data().await {
+    ///     let reply = chunk?;
+    ///     body.send_data(reply).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// # fn main() {}
+    /// ```
+    pub fn send_duplex(&mut self, req: Request<()>) -> (crate::body::Sender, ResponseFuture) {
+        let (tx, body) = Body::channel();
+        (tx, self.send_request(req.map(|()| body)))
+    }
+}
+
 impl<B> Service<Request<B>> for SendRequest<B>
 where
     B: HttpBody + 'static,
@@ -382,19 +619,35 @@ where
 {
     pub(super) fn send_request_retryable(
         &mut self,
-        req: Request<B>,
+        #[cfg_attr(not(feature = "decompress"), allow(unused_mut))] mut req: Request<B>,
     ) -> impl Future<Output = Result<Response<Body>, (crate::Error, Option<Request<B>>)>>
     where
         B: Send,
     {
+        #[cfg(feature = "decompress")]
+        self.decompress.prepare_request(&mut req);
+        #[cfg(feature = "decompress")]
+        let decompress = self.decompress;
+        let timeouts = self.timeouts;
+        let metrics = self.metrics.clone();
+        let start = Instant::now();
         match self.dispatch.try_send(req) {
             Ok(rx) => {
-                Either::Left(rx.then(move |res| {
+                Either::Left(timeouts.response_head(rx).then(move |res| {
                     match res {
-                        Ok(Ok(res)) => future::ok(res),
-                        Ok(Err(err)) => future::err(err),
+                        Ok(Ok(Ok(res))) => {
+                            if let Some(metrics) = metrics {
+                                metrics.request_latency(start.elapsed());
+                            }
+                            let res = timeouts.response(res);
+                            #[cfg(feature = "decompress")]
+                            let res = decompress.response(res);
+                            future::ok(res)
+                        }
+                        Ok(Ok(Err(err))) => future::err(err),
                         // this is definite bug if it happens, but it shouldn't happen!
-                        Err(_) => panic!("dispatch dropped without returning error"),
+                        Ok(Err(_)) => panic!("dispatch dropped without returning error"),
+                        Err(timeout) => future::err((timeout, None)),
                     }
                 }))
             }
@@ -419,6 +672,10 @@ impl<B> Clone for Http2SendRequest<B> {
     fn clone(&self) -> Self {
         Http2SendRequest {
             dispatch: self.dispatch.clone(),
+            metrics: self.metrics.clone(),
+            #[cfg(feature = "decompress")]
+            decompress: self.decompress,
+            timeouts: self.timeouts,
         }
     }
 }
@@ -467,7 +724,8 @@ where
     /// and [`try_ready!`](https://docs.rs/futures/0.1.25/futures/macro.try_ready.html)
     /// to work with this function; or use the `without_shutdown` wrapper.
     pub fn poll_without_shutdown(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
-        match *self.inner.as_mut().expect("already upgraded") {
+        let _entered = self.span.enter();
+        let res = match *self.inner.as_mut().expect("already upgraded") {
             #[cfg(feature = "http1")]
             ProtoClient::H1 { ref mut h1 } => h1.poll_without_shutdown(cx),
             #[cfg(feature = "http2")]
@@ -477,7 +735,8 @@ where
             ProtoClient::H1 { ref mut h1 } => match h1.0 {},
             #[cfg(not(feature = "http2"))]
             ProtoClient::H2 { ref mut h2, .. } => match h2.0 {},
-        }
+        };
+        res.map_err(|err| err.with_context(self.context.as_ref()))
     }
 
     /// Prevent shutdown of the underlying IO object at the end of service the request,
@@ -518,10 +777,14 @@ where
     type Output = crate::Result<()>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        match ready!(Pin::new(self.inner.as_mut().unwrap()).poll(cx))? {
+        let this = &mut *self;
+        let _entered = this.span.enter();
+        let dispatched = ready!(Pin::new(this.inner.as_mut().unwrap()).poll(cx))
+            .map_err(|err| err.with_context(this.context.as_ref()))?;
+        match dispatched {
             proto::Dispatched::Shutdown => Poll::Ready(Ok(())),
             #[cfg(feature = "http1")]
-            proto::Dispatched::Upgrade(pending) => match self.inner.take() {
+            proto::Dispatched::Upgrade(pending) => match this.inner.take() {
                 Some(ProtoClient::H1 { h1 }) => {
                     let (io, buf, _) = h1.into_inner();
                     pending.fulfill(Upgraded::new(io, buf));
@@ -554,6 +817,12 @@ impl Builder {
     pub fn new() -> Builder {
         Builder {
             exec: Exec::Default,
+            metrics: None,
+            context: None,
+            observer: None,
+            #[cfg(feature = "decompress")]
+            decompress: Decompress::default(),
+            timeouts: Timeouts::default(),
             h09_responses: false,
             h1_writev: None,
             h1_read_buf_exact_size: None,
@@ -563,14 +832,30 @@ impl Builder {
             #[cfg(feature = "ffi")]
             h1_preserve_header_order: false,
             h1_max_buf_size: None,
+            h1_min_buf_size: None,
+            h1_max_informational: None,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            #[cfg(feature = "http1")]
+            h1_content_length_policy: ContentLengthPolicy::default(),
+            h1_body_prefetch: 0,
+            #[cfg(feature = "http1")]
+            h1_body_framing: None,
+            h1_on_violation: None,
             #[cfg(feature = "ffi")]
             h1_headers_raw: false,
+            #[cfg(feature = "runtime")]
+            h1_upload_rate_limit: None,
+            h1_keep_alive: true,
+            #[cfg(feature = "runtime")]
+            h1_idle_timeout: None,
             #[cfg(feature = "http2")]
             h2_builder: Default::default(),
             #[cfg(feature = "http1")]
             version: Proto::Http1,
             #[cfg(not(feature = "http1"))]
             version: Proto::Http2,
+            hardened: None,
         }
     }
 
@@ -583,10 +868,182 @@ impl Builder {
         self
     }
 
+    /// Set a sink for the metrics of this connection.
+    ///
+    /// See the [`metrics`](crate::metrics) module for more.
+    pub fn metrics(&mut self, sink: Arc<dyn MetricsSink>) -> &mut Builder {
+        let metrics = Recorder::new(sink);
+        #[cfg(feature = "http2")]
+        {
+            self.h2_builder.metrics = Some(metrics.clone());
+        }
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Set a context identifying this connection, such as the upstream it is
+    /// made to.
+    ///
+    /// The context is included in the tracing events, metrics and errors of
+    /// the connection. See [`ConnContext`] for more.
+    pub fn context(&mut self, context: ConnContext) -> &mut Builder {
+        self.context = Some(context);
+        self
+    }
+
+    /// Set an observer for the lifecycle events of this connection.
+    ///
+    /// Note that HTTP/2 connections are not observed yet.
+    ///
+    /// See the [`observer`](crate::observer) module for more.
+    pub fn observer(&mut self, observer: Arc<dyn ConnectionObserver>) -> &mut Builder {
+        self.observer = Some(ObserverRef::new(observer));
+        self
+    }
+
+    /// Set the content codings to transparently decode in responses.
+    ///
+    /// Requests without an `Accept-Encoding` header are sent with one
+    /// listing these codings. Responses with one of them as their
+    /// `Content-Encoding` get their body decoded, and their
+    /// `Content-Encoding` and `Content-Length` headers removed.
+    ///
+    /// Default is to not decode any response.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::body::ContentEncoding;
+    /// use fluxio::client::conn::Builder;
+    ///
+    /// let mut builder = Builder::new();
+    /// builder.decompress(ContentEncoding::ALL);
+    /// ```
+    #[cfg(feature = "decompress")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "decompress")))]
+    pub fn decompress<I>(&mut self, encodings: I) -> &mut Builder
+    where
+        I: IntoIterator<Item = ContentEncoding>,
+    {
+        self.decompress.set_encodings(encodings);
+        self
+    }
+
+    /// Set the maximum size of decoded response bodies, in bytes.
+    ///
+    /// Decoding more of a body than this fails with an error for which
+    /// [`Error::is_body_too_large`](crate::Error::is_body_too_large) is
+    /// true, so a small compressed response can't decode into an
+    /// arbitrarily large one. Bodies that aren't decoded aren't limited.
+    ///
+    /// Default is no limit.
+    #[cfg(feature = "decompress")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "decompress")))]
+    pub fn decompress_max_size(&mut self, max: u64) -> &mut Builder {
+        self.decompress.set_max_size(max);
+        self
+    }
+
+    /// Set how long to wait for the head of a response, from when its
+    /// request is sent on the connection.
+    ///
+    /// If the timeout elapses first, the request fails with an error for
+    /// which [`Error::is_response_header_timeout`] returns true. This doesn't
+    /// limit how long the body takes to arrive, see
+    /// [`body_idle_timeout`](Builder::body_idle_timeout).
+    ///
+    /// Pass `None` to disable the timeout, which is the default.
+    ///
+    /// [`Error::is_response_header_timeout`]: crate::Error::is_response_header_timeout
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn response_header_timeout(
+        &mut self,
+        timeout: impl Into<Option<Duration>>,
+    ) -> &mut Builder {
+        Posture::record(&mut self.hardened, "response_header_timeout");
+        self.timeouts.header = timeout.into();
+        self
+    }
+
+    /// Set how long a response body can be waited on without receiving data.
+    ///
+    /// The timer starts each time the body is polled and no data is ready,
+    /// so a slow reader doesn't cause a timeout. If it elapses, polling the
+    /// body returns an error for which [`Error::is_body_idle_timeout`]
+    /// returns true.
+    ///
+    /// Pass `None` to disable the timeout, which is the default.
+    ///
+    /// [`Error::is_body_idle_timeout`]: crate::Error::is_body_idle_timeout
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn body_idle_timeout(&mut self, timeout: impl Into<Option<Duration>>) -> &mut Builder {
+        self.timeouts.body_idle = timeout.into();
+        self
+    }
+
+    /// Set a rate limit, in bytes per second, for sending request bodies.
+    ///
+    /// Body chunks are paced with a token bucket holding a second worth of
+    /// bytes, so a large upload doesn't saturate a constrained uplink. Chunks
+    /// aren't split, so bodies of a single chunk larger than the rate are
+    /// sent all at once, and delay the next body instead.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Pass `None` to disable the limit, which is the default.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the passed rate is 0.
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "runtime"))))]
+    pub fn http1_upload_rate_limit(
+        &mut self,
+        bytes_per_sec: impl Into<Option<u64>>,
+    ) -> &mut Builder {
+        let bytes_per_sec = bytes_per_sec.into();
+        assert_ne!(bytes_per_sec, Some(0), "the upload rate limit cannot be 0");
+        self.h1_upload_rate_limit = bytes_per_sec;
+        self
+    }
+
+    /// Set whether HTTP/1 connections are kept alive for more requests.
+    ///
+    /// If disabled, requests are sent with a `Connection: close` header, and
+    /// the connection closes once the first response was received.
+    ///
+    /// Default is true.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_keep_alive(&mut self, enabled: bool) -> &mut Builder {
+        self.h1_keep_alive = enabled;
+        self
+    }
+
+    /// Set how long an HTTP/1 connection can go without a request in flight
+    /// before it is closed.
+    ///
+    /// The timer starts when a response and its body were fully received,
+    /// and when the connection is established. Requests sent once it closed
+    /// fail, and [`SendRequest::poll_ready`] returns an error.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Pass `None` to disable the timeout, which is the default.
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "runtime"))))]
+    pub fn http1_idle_timeout(&mut self, timeout: impl Into<Option<Duration>>) -> &mut Builder {
+        self.h1_idle_timeout = timeout.into();
+        self
+    }
+
     /// Set whether HTTP/0.9 responses should be tolerated.
     ///
     /// Default is false.
     pub fn http09_responses(&mut self, enabled: bool) -> &mut Builder {
+        Posture::record(&mut self.hardened, "http09_responses");
         self.h09_responses = enabled;
         self
     }
@@ -614,6 +1071,10 @@ impl Builder {
         &mut self,
         enabled: bool,
     ) -> &mut Builder {
+        Posture::record(
+            &mut self.hardened,
+            "http1_allow_spaces_after_header_name_in_responses",
+        );
         self.h1_parser_config
             .allow_spaces_after_header_name_in_responses(enabled);
         self
@@ -657,11 +1118,33 @@ impl Builder {
         &mut self,
         enabled: bool,
     ) -> &mut Builder {
+        Posture::record(
+            &mut self.hardened,
+            "http1_allow_obsolete_multiline_headers_in_responses",
+        );
         self.h1_parser_config
             .allow_obsolete_multiline_headers_in_responses(enabled);
         self
     }
 
+    /// Set whether HTTP/1 connections will skip invalid header lines in
+    /// responses, instead of failing the request.
+    ///
+    /// This lets responses from buggy devices, such as some IP cameras and
+    /// embedded firmware, still be read. Each skipped line is reported as
+    /// [`InvalidHeader`](crate::client::ResponseViolation::InvalidHeader) to the
+    /// callback set with
+    /// [`http1_strict_response_validation`](Builder::http1_strict_response_validation).
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    pub fn http1_ignore_invalid_headers_in_responses(&mut self, enabled: bool) -> &mut Builder {
+        self.h1_parser_config
+            .ignore_invalid_headers_in_responses(enabled);
+        self
+    }
+
     /// Set whether HTTP/1 connections should try to use vectored writes,
     /// or always flatten into a single buffer.
     ///
@@ -725,12 +1208,14 @@ impl Builder {
 
     /// Sets the exact size of the read buffer to *always* use.
     ///
-    /// Note that setting this option unsets the `http1_max_buf_size` option.
+    /// Note that setting this option unsets the `http1_max_buf_size` and
+    /// `http1_min_buf_size` options.
     ///
     /// Default is an adaptive read buffer.
     pub fn http1_read_buf_exact_size(&mut self, sz: Option<usize>) -> &mut Builder {
         self.h1_read_buf_exact_size = sz;
         self.h1_max_buf_size = None;
+        self.h1_min_buf_size = None;
         self
     }
 
@@ -751,17 +1236,194 @@ impl Builder {
             "the max_buf_size cannot be smaller than the minimum that h1 specifies."
         );
 
+        Posture::record(&mut self.hardened, "http1_max_buf_size");
         self.h1_max_buf_size = Some(max);
         self.h1_read_buf_exact_size = None;
         self
     }
 
+    /// Set the minimum buffer size for the connection.
+    ///
+    /// The read buffer starts at this size, grows with larger responses up
+    /// to the maximum buffer size, and shrinks back to it with smaller ones.
+    /// Once a connection waits for a new response, such as an idle pooled
+    /// connection, a read buffer grown beyond this size is released.
+    ///
+    /// Default is 8kb.
+    ///
+    /// Note that setting this option unsets the `http1_read_exact_buf_size` option.
+    ///
+    /// # Panics
+    ///
+    /// The minimum value allowed is 64. This method panics if the passed `min` is less than the minimum.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_min_buf_size(&mut self, min: usize) -> &mut Self {
+        assert!(
+            min >= proto::h1::MINIMUM_MIN_BUFFER_SIZE,
+            "the min_buf_size cannot be smaller than the minimum that h1 specifies."
+        );
+
+        self.h1_min_buf_size = Some(min);
+        self.h1_read_buf_exact_size = None;
+        self
+    }
+
+    /// Set the maximum number of informational (1xx) responses accepted
+    /// before the final response to a request.
+    ///
+    /// A peer sending more fails the request with an error for which
+    /// [`Error::is_parse_too_many_informational`] returns true.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is 10.
+    ///
+    /// [`Error::is_parse_too_many_informational`]: crate::Error::is_parse_too_many_informational
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_informational_responses(&mut self, max: usize) -> &mut Self {
+        Posture::record(&mut self.hardened, "http1_max_informational_responses");
+        self.h1_max_informational = Some(max);
+        self
+    }
+
+    /// Set the maximum number of headers of a response.
+    ///
+    /// A peer sending more fails the request with an error for which
+    /// [`Error::is_parse_too_many_headers`] returns true.
+    ///
+    /// Up to 100 headers are parsed into memory on the stack, and more into
+    /// memory allocated for each response.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is 100.
+    ///
+    /// [`Error::is_parse_too_many_headers`]: crate::Error::is_parse_too_many_headers
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_headers(&mut self, max: usize) -> &mut Self {
+        self.h1_max_headers = Some(max);
+        self
+    }
+
+    /// Set the maximum size of a response header value, in bytes.
+    ///
+    /// A peer sending a larger one fails the request with an error for
+    /// which [`Error::is_parse_header_value_too_large`] returns true.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is no limit other than the size of the read buffer.
+    ///
+    /// [`Error::is_parse_header_value_too_large`]: crate::Error::is_parse_header_value_too_large
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_header_value_size(&mut self, max: usize) -> &mut Self {
+        self.h1_max_header_value_size = Some(max);
+        self
+    }
+
+    /// Set how responses with multiple `Content-Length` values are read.
+    ///
+    /// See [`ContentLengthPolicy`] for the choices.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is [`ContentLengthPolicy::MergeIfEqual`].
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_content_length_policy(&mut self, policy: ContentLengthPolicy) -> &mut Self {
+        self.h1_content_length_policy = policy;
+        self
+    }
+
+    /// Set how many bytes of a response body are read ahead, as soon as the
+    /// response head arrived and before the body is polled.
+    ///
+    /// Small responses are then usually complete by the time they are
+    /// polled. Reading pauses once about `max` bytes are buffered, and
+    /// resumes once half of them were polled, so a large body is never
+    /// buffered entirely. A chunk read off the connection may exceed
+    /// `max`.
+    ///
+    /// Note that this setting does not affect HTTP/2, where the stream
+    /// window bounds the data received ahead.
+    ///
+    /// Default is `0`, reading a single chunk ahead.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_response_body_prefetch(&mut self, max: usize) -> &mut Self {
+        self.h1_body_prefetch = max;
+        self
+    }
+
+    /// Set a callback choosing how request bodies of unknown length are
+    /// framed.
+    ///
+    /// The callback is called with the size hint of each such body. See
+    /// [`BodyFraming`] for the choices; buffering small bodies sends them
+    /// with a `Content-Length`, for servers that can't read chunked
+    /// requests.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is [`BodyFraming::Chunked`].
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_body_framing<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&SizeHint) -> BodyFraming + Send + Sync + 'static,
+    {
+        self.h1_body_framing = Some(BodyFramingFn::new(f));
+        self
+    }
+
+    /// Check received responses against MUST-level requirements of RFC 9110
+    /// and RFC 9112, and call `callback` with each violation found.
+    ///
+    /// The checks include a valid status code range, no `Content-Length` or
+    /// `Transfer-Encoding` on 1xx and 204 responses, no content after a 204
+    /// or 304 response, and a consistent `Content-Length`. This is meant for
+    /// tooling testing servers for compliance: responses are still handled
+    /// as leniently as without validation, and the callback is called before
+    /// a response that can't be handled fails the request.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::client::conn::Builder;
+    ///
+    /// Builder::new().http1_strict_response_validation(|status, violation| {
+    ///     eprintln!("{} response violates {}", status, violation);
+    /// });
+    /// ```
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_strict_response_validation<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(StatusCode, ResponseViolation) + Send + Sync + 'static,
+    {
+        self.h1_on_violation = Some(OnViolation::new(callback));
+        self
+    }
+
     #[cfg(feature = "ffi")]
     pub(crate) fn http1_headers_raw(&mut self, enabled: bool) -> &mut Self {
         self.h1_headers_raw = enabled;
         self
     }
 
+    /// Picks the HTTP version from the protocol negotiated with ALPN.
+    #[cfg(all(feature = "ffi", feature = "http1", feature = "http2"))]
+    pub(crate) fn http2_negotiated(&mut self, h2: bool) -> &mut Self {
+        self.version = if h2 { Proto::Http2 } else { Proto::Http1 };
+        self
+    }
+
     /// Sets whether HTTP2 is required.
     ///
     /// Default is false.
@@ -931,6 +1593,97 @@ impl Builder {
         self
     }
 
+    /// Configure connections for the upstream side of a proxy, accepting
+    /// responses from legacy servers and forwarding them as received.
+    ///
+    /// This allows spaces after HTTP/1 header names and obsolete multiline
+    /// headers in responses, and preserves the original case of HTTP/1
+    /// header names. Responses that are ambiguous to frame, such as with
+    /// conflicting `Content-Length` headers, are still rejected, since
+    /// forwarding them could enable request smuggling.
+    ///
+    /// Options set afterwards override these.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::client::conn::Builder;
+    ///
+    /// let mut builder = Builder::new();
+    /// builder.preset_lenient_proxy();
+    /// ```
+    pub fn preset_lenient_proxy(&mut self) -> &mut Builder {
+        #[cfg(feature = "http1")]
+        self.http1_allow_spaces_after_header_name_in_responses(true)
+            .http1_allow_obsolete_multiline_headers_in_responses(true)
+            .http1_preserve_header_case(true);
+        self
+    }
+
+    /// Configure connections to use as little memory as possible, such as on
+    /// embedded devices, at the cost of throughput.
+    ///
+    /// This sets:
+    ///
+    /// - HTTP/1 read buffers of at most 8 KiB, the smallest allowed, which
+    ///   also bounds the size of response heads,
+    /// - HTTP/2 flow control windows of 64 KiB per stream and 128 KiB per
+    ///   connection, instead of an adaptive window,
+    /// - HTTP/2 frames of at most 16 KiB, and write buffers of at most 16 KiB
+    ///   per stream.
+    ///
+    /// Options set afterwards override these.
+    pub fn preset_low_memory(&mut self) -> &mut Builder {
+        #[cfg(feature = "http1")]
+        self.http1_max_buf_size(proto::h1::MINIMUM_MAX_BUFFER_SIZE);
+        #[cfg(feature = "http2")]
+        self.http2_initial_stream_window_size(64 * 1024)
+            .http2_initial_connection_window_size(128 * 1024)
+            .http2_max_frame_size(16 * 1024)
+            .http2_max_send_buf_size(16 * 1024);
+        self
+    }
+
+    /// Configure connections with a [hardened profile](crate::hardened).
+    ///
+    /// See [`Profile`] for the options set by each profile. Setting one of
+    /// them again afterwards overrides it, and is listed by
+    /// [`hardened_posture`](Builder::hardened_posture).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::client::conn::Builder;
+    /// use fluxio::hardened::Profile;
+    ///
+    /// let mut builder = Builder::new();
+    /// builder.preset_hardened(Profile::V1);
+    /// assert!(builder.hardened_posture().unwrap().is_hardened());
+    /// ```
+    pub fn preset_hardened(&mut self, profile: Profile) -> &mut Builder {
+        match profile {
+            Profile::V1 => {
+                self.http09_responses(false);
+                #[cfg(feature = "http1")]
+                self.http1_allow_spaces_after_header_name_in_responses(false)
+                    .http1_allow_obsolete_multiline_headers_in_responses(false)
+                    .http1_max_buf_size(64 * 1024)
+                    .http1_max_informational_responses(5);
+                #[cfg(feature = "runtime")]
+                self.response_header_timeout(Duration::from_secs(30));
+            }
+        }
+        self.hardened = Some(Posture::new(profile));
+        self
+    }
+
+    /// Returns the hardened profile applied with
+    /// [`preset_hardened`](Builder::preset_hardened), and the options of it
+    /// overridden since.
+    pub fn hardened_posture(&self) -> Option<&Posture> {
+        self.hardened.as_ref()
+    }
+
     /// Constructs a connection with the configured options and IO.
     /// See [`client::conn`](crate::client::conn) for more.
     ///
@@ -946,12 +1699,32 @@ impl Builder {
         B::Data: Send,
         B::Error: Into<Box<dyn StdError + Send + Sync>>,
     {
-        let opts = self.clone();
+        let mut opts = self.clone();
+        let span = match opts.context {
+            Some(ref context) => debug_span!("fluxio::conn", context = %context),
+            None => Span::none(),
+        };
+        if let Some(ref context) = opts.context {
+            if let Some(sink) = opts.metrics.as_ref().and_then(|m| m.for_context(context)) {
+                let metrics = Recorder::new(sink);
+                #[cfg(feature = "http2")]
+                {
+                    opts.h2_builder.metrics = Some(metrics.clone());
+                }
+                opts.metrics = Some(metrics);
+            }
+        }
 
-        async move {
+        let context = opts.context.clone();
+        let handshake = async move {
             trace!("client handshake {:?}", opts.version);
+            let start = Instant::now();
 
             let (tx, rx) = dispatch::channel();
+            #[cfg(feature = "http2")]
+            let mut settings = None;
+            #[cfg(feature = "http2")]
+            let mut pinger = None;
             let proto = match opts.version {
                 #[cfg(feature = "http1")]
                 Proto::Http1 => {
@@ -987,8 +1760,46 @@ impl Builder {
                     if let Some(max) = opts.h1_max_buf_size {
                         conn.set_max_buf_size(max);
                     }
-                    let cd = proto::h1::dispatch::Client::new(rx);
-                    let dispatch = proto::h1::Dispatcher::new(cd, conn);
+                    if let Some(min) = opts.h1_min_buf_size {
+                        conn.set_min_buf_size(min);
+                    }
+                    if let Some(max) = opts.h1_max_informational {
+                        conn.set_max_informational(max);
+                    }
+                    if let Some(max) = opts.h1_max_headers {
+                        conn.set_http1_max_headers(max);
+                    }
+                    if let Some(max) = opts.h1_max_header_value_size {
+                        conn.set_http1_max_header_value_size(max);
+                    }
+                    conn.set_content_length_policy(opts.h1_content_length_policy);
+                    conn.set_body_prefetch(opts.h1_body_prefetch);
+                    if let Some(body_framing) = opts.h1_body_framing {
+                        conn.set_body_framing(body_framing);
+                    }
+                    if !opts.h1_keep_alive {
+                        conn.disable_keep_alive();
+                    }
+                    if let Some(on_violation) = opts.h1_on_violation {
+                        conn.set_on_violation(on_violation);
+                    }
+                    if let Some(ref metrics) = opts.metrics {
+                        conn.set_metrics(metrics.clone());
+                    }
+                    let mut cd = proto::h1::dispatch::Client::new(rx);
+                    if let Some(ref observer) = opts.observer {
+                        cd.set_observed(observer.connect(http::Version::HTTP_11, false));
+                    }
+                    #[cfg_attr(not(feature = "runtime"), allow(unused_mut))]
+                    let mut dispatch = proto::h1::Dispatcher::new(cd, conn);
+                    #[cfg(feature = "runtime")]
+                    if let Some(rate) = opts.h1_upload_rate_limit {
+                        dispatch.set_body_rate_limit(rate);
+                    }
+                    #[cfg(feature = "runtime")]
+                    if let Some(timeout) = opts.h1_idle_timeout {
+                        dispatch.set_idle_timeout(timeout);
+                    }
                     ProtoClient::H1 { h1: dispatch }
                 }
                 #[cfg(feature = "http2")]
@@ -996,14 +1807,44 @@ impl Builder {
                     let h2 =
                         proto::h2::client::handshake(io, rx, &opts.h2_builder, opts.exec.clone())
                             .await?;
+                    settings = Some(h2.settings_exchanged());
+                    pinger = Some(h2.pinger());
                     ProtoClient::H2 { h2 }
                 }
             };
 
-            Ok((
-                SendRequest { dispatch: tx },
-                Connection { inner: Some(proto) },
+            if let Some(ref metrics) = opts.metrics {
+                metrics.handshake_duration(start.elapsed());
+            }
+
+            Ok::<_, crate::Error>((
+                SendRequest {
+                    dispatch: tx,
+                    metrics: opts.metrics,
+                    context: opts.context.clone(),
+                    #[cfg(feature = "decompress")]
+                    decompress: opts.decompress,
+                    timeouts: opts.timeouts,
+                    #[cfg(feature = "http2")]
+                    settings,
+                    #[cfg(feature = "http2")]
+                    pinger,
+                },
+                Connection {
+                    inner: Some(proto),
+                    context: opts.context,
+                    span: Span::none(),
+                },
             ))
+        };
+
+        async move {
+            let (tx, mut conn) = handshake
+                .instrument(span.clone())
+                .await
+                .map_err(|err| err.with_context(context.as_ref()))?;
+            conn.span = span;
+            Ok((tx, conn))
         }
     }
 }
@@ -1014,19 +1855,34 @@ impl Future for ResponseFuture {
     type Output = crate::Result<Response<Body>>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        match self.inner {
+        let this = &mut *self;
+        let metrics = &this.metrics;
+        #[cfg(feature = "decompress")]
+        let decompress = this.decompress;
+        let timeouts = this.timeouts;
+        let res = match this.inner {
             ResponseFutureState::Waiting(ref mut rx) => {
                 Pin::new(rx).poll(cx).map(|res| match res {
-                    Ok(Ok(resp)) => Ok(resp),
-                    Ok(Err(err)) => Err(err),
+                    Ok(Ok(Ok(resp))) => {
+                        if let Some((ref metrics, start)) = *metrics {
+                            metrics.request_latency(start.elapsed());
+                        }
+                        let resp = timeouts.response(resp);
+                        #[cfg(feature = "decompress")]
+                        let resp = decompress.response(resp);
+                        Ok(resp)
+                    }
+                    Ok(Ok(Err(err))) => Err(err),
                     // this is definite bug if it happens, but it shouldn't happen!
-                    Err(_canceled) => panic!("dispatch dropped without returning error"),
+                    Ok(Err(_canceled)) => panic!("dispatch dropped without returning error"),
+                    Err(timeout) => Err(timeout),
                 })
             }
             ResponseFutureState::Error(ref mut err) => {
                 Poll::Ready(Err(err.take().expect("polled after ready")))
             }
-        }
+        };
+        res.map_err(|err| err.with_context(this.context.as_ref()))
     }
 }
 
@@ -1036,6 +1892,38 @@ impl fmt::Debug for ResponseFuture {
     }
 }
 
+// ===== impl SettingsExchanged
+
+impl Future for SettingsExchanged {
+    type Output = crate::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for SettingsExchanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SettingsExchanged").finish()
+    }
+}
+
+// ===== impl Pong
+
+impl Future for Pong {
+    type Output = crate::Result<Duration>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for Pong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pong").finish()
+    }
+}
+
 // ===== impl ProtoClient
 
 impl<T, B> Future for ProtoClient<T, B>
@@ -1093,3 +1981,132 @@ impl AssertSendSync for Builder {}
 
 #[doc(hidden)]
 impl AssertSend for ResponseFuture {}
+
+#[doc(hidden)]
+impl AssertSend for SettingsExchanged {}
+
+#[doc(hidden)]
+impl AssertSend for Pong {}
+
+#[cfg(all(test, feature = "http1"))]
+mod tests {
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    async fn read_until<T: AsyncRead + Unpin>(io: &mut T, needle: &str) -> String {
+        let mut read = Vec::new();
+        let mut buf = [0; 1024];
+        while !String::from_utf8_lossy(&read).contains(needle) {
+            let n = io.read(&mut buf).await.unwrap();
+            assert_ne!(n, 0, "closed before {:?}: {:?}", needle, read);
+            read.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8(read).unwrap()
+    }
+
+    #[tokio::test]
+    async fn send_duplex_interleaves_bodies() {
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (mut tx, conn) = Builder::new().handshake::<_, Body>(io).await.unwrap();
+        tokio::spawn(conn);
+
+        let (mut body, res) = tx.send_duplex(Request::post("/echo").body(()).unwrap());
+        body.send_data(Bytes::from("ping")).await.unwrap();
+        let head = read_until(&mut server, "4\r\nping\r\n").await;
+        assert!(head.starts_with("POST /echo HTTP/1.1\r\n"), "{:?}", head);
+        assert!(
+            head.contains("transfer-encoding: chunked\r\n"),
+            "{:?}",
+            head
+        );
+
+        // the response arrives while the request body is still open
+        server
+            .write_all(b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n4\r\npong\r\n")
+            .await
+            .unwrap();
+        let mut res = res.await.unwrap().into_body();
+        assert_eq!(res.data().await.unwrap().unwrap(), "pong");
+
+        body.send_data(Bytes::from("bye")).await.unwrap();
+        drop(body);
+        read_until(&mut server, "3\r\nbye\r\n0\r\n\r\n").await;
+
+        server.write_all(b"0\r\n\r\n").await.unwrap();
+        assert!(res.data().await.is_none());
+    }
+    #[tokio::test]
+    async fn errors_carry_context() {
+        let (io, server) = tokio::io::duplex(1024);
+        let mut builder = Builder::new();
+        builder.context(ConnContext::new("billing").label("region", "eu-west-1"));
+        let (mut tx, conn) = builder.handshake::<_, Body>(io).await.unwrap();
+        let conn = tokio::spawn(conn);
+
+        let res = tx.send_request(Request::new(Body::empty()));
+        drop(server);
+        let err = res.await.unwrap_err();
+        assert_eq!(err.connection_context().unwrap().name(), "billing");
+        assert!(
+            err.to_string()
+                .ends_with(" (connection billing{region=eu-west-1})"),
+            "{}",
+            err
+        );
+
+        conn.await.unwrap().unwrap();
+
+        // errors of the connection itself
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (_tx, conn) = builder.handshake::<_, Body>(io).await.unwrap();
+        server.write_all(b"unexpected").await.unwrap();
+        let err = conn.await.unwrap_err();
+        assert_eq!(err.connection_context().unwrap().name(), "billing");
+    }
+
+    /// Whether the HTTP/1 parser of `builder` accepts a response head.
+    fn parses_response(builder: &Builder, head: &[u8]) -> bool {
+        let mut headers = [httparse::EMPTY_HEADER; 4];
+        let mut res = httparse::Response::new(&mut headers);
+        builder
+            .h1_parser_config
+            .parse_response(&mut res, head)
+            .is_ok()
+    }
+
+    #[test]
+    fn preset_lenient_proxy() {
+        let space_after_name = b"HTTP/1.1 200 OK\r\nfoo : bar\r\n\r\n";
+        let obs_fold = b"HTTP/1.1 200 OK\r\nfoo: bar\r\n baz\r\n\r\n";
+        let mut builder = Builder::new();
+        assert!(!parses_response(&builder, space_after_name));
+        assert!(!parses_response(&builder, obs_fold));
+
+        builder.preset_lenient_proxy();
+        assert!(parses_response(&builder, space_after_name));
+        assert!(parses_response(&builder, obs_fold));
+        assert!(builder.h1_preserve_header_case);
+    }
+
+    #[test]
+    fn preset_low_memory() {
+        let mut builder = Builder::new();
+        builder.http1_read_buf_exact_size(Some(1024 * 1024));
+        builder.preset_low_memory();
+
+        assert_eq!(
+            builder.h1_max_buf_size,
+            Some(proto::h1::MINIMUM_MAX_BUFFER_SIZE)
+        );
+        assert_eq!(builder.h1_read_buf_exact_size, None);
+        #[cfg(feature = "http2")]
+        {
+            assert!(!builder.h2_builder.adaptive_window);
+            assert_eq!(builder.h2_builder.initial_stream_window_size, 64 * 1024);
+            assert_eq!(builder.h2_builder.initial_conn_window_size, 128 * 1024);
+            assert_eq!(builder.h2_builder.max_frame_size, 16 * 1024);
+            assert_eq!(builder.h2_builder.max_send_buffer_size, 16 * 1024);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// A name and labels identifying a connection, such as the upstream it was
+/// made to.
+///
+/// Set on a connection with [`Builder::context`](super::Builder::context),
+/// the context is:
+///
+/// - a field of a `fluxio::conn` tracing span entered while the connection
+///   is polled, so that the events logged by it can be told apart,
+/// - passed to [`MetricsSink::for_context`], so that metrics can be labeled,
+/// - attached to the errors of the connection and of its requests, returned
+///   by [`Error::connection_context`] and shown with them.
+///
+/// Cloning a context is cheap.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::client::conn::{Builder, ConnContext};
+///
+/// let mut builder = Builder::new();
+/// builder.context(ConnContext::new("billing").label("region", "eu-west-1"));
+/// ```
+///
+/// [`MetricsSink::for_context`]: crate::metrics::MetricsSink::for_context
+/// [`Error::connection_context`]: crate::Error::connection_context
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ConnContext {
+    inner: Arc<Inner>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Inner {
+    name: String,
+    labels: Vec<(String, String)>,
+}
+
+impl ConnContext {
+    /// Creates a context with a name, such as of the upstream connected to.
+    pub fn new(name: impl Into<String>) -> ConnContext {
+        ConnContext {
+            inner: Arc::new(Inner {
+                name: name.into(),
+                labels: Vec::new(),
+            }),
+        }
+    }
+
+    /// Adds a label, such as the region or the address of the upstream.
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> ConnContext {
+        Arc::make_mut(&mut self.inner)
+            .labels
+            .push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the name of the context.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the labels of the context, in the order they were added.
+    pub fn labels(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.inner
+            .labels
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+impl fmt::Debug for ConnContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnContext")
+            .field("name", &self.inner.name)
+            .field("labels", &self.inner.labels)
+            .finish()
+    }
+}
+
+/// Formats as the name, followed by the labels in braces if there are any,
+/// such as `billing{region=eu-west-1}`.
+impl fmt::Display for ConnContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.inner.name)?;
+        for (i, (key, value)) in self.labels().enumerate() {
+            let sep = if i == 0 { "{" } else { "," };
+            write!(f, "{}{}={}", sep, key, value)?;
+        }
+        if !self.inner.labels.is_empty() {
+            f.write_str("}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_includes_labels() {
+        let context = ConnContext::new("billing");
+        assert_eq!(context.to_string(), "billing");
+
+        let labeled = context
+            .clone()
+            .label("region", "eu-west-1")
+            .label("pool", "2");
+        assert_eq!(labeled.to_string(), "billing{region=eu-west-1,pool=2}");
+        assert_eq!(
+            labeled.labels().collect::<Vec<_>>(),
+            [("region", "eu-west-1"), ("pool", "2")]
+        );
+        // labels are added to a copy
+        assert_eq!(context.labels().count(), 0);
+    }
+}
@@ -4,7 +4,10 @@
 //!
 //! - A [`GaiResolver`](GaiResolver) that is the default resolver for the
 //!   `HttpConnector`.
+//! - A [`FixedTtlResolver`](FixedTtlResolver) that caches the results of
+//!   another resolver for a fixed time.
 //! - The `Name` type used as an argument to custom resolvers.
+//! - The [`Resolve`](Resolve) trait implemented by all resolvers.
 //!
 //! # Resolvers are `Service`s
 //!
@@ -21,19 +24,27 @@
 //!     Ok::<_, Infallible>(iter::once(SocketAddr::from(([127, 0, 0, 1], 8080))))
 //! });
 //! ```
+//!
+//! Any such `Service` implements [`Resolve`](Resolve), which can be used to
+//! write code that is generic over resolvers.
+use std::collections::HashMap;
 use std::error::Error;
 use std::future::Future;
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs};
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 use std::{fmt, io, vec};
 
 use tokio::task::JoinHandle;
 use tower_service::Service;
 use tracing::debug;
 
-pub(super) use self::sealed::Resolve;
+pub use self::sealed::Resolve;
+
+type BoxError = Box<dyn Error + Send + Sync>;
 
 /// A domain name to resolve into IP addresses.
 #[derive(Clone, Hash, Eq, PartialEq)]
@@ -57,6 +68,62 @@ pub struct GaiFuture {
     inner: JoinHandle<Result<SocketAddrs, io::Error>>,
 }
 
+/// A resolver that caches the results of another resolver for a fixed time.
+///
+/// Successful lookups are cached for a fixed time to live, and failed
+/// lookups for a (shorter) negative time to live. Resolvers only return
+/// addresses, so the TTLs of the DNS records are not known, and a record
+/// with a shorter TTL is still cached for the whole fixed time. Once the
+/// maximum number of entries is reached, expired entries are removed first,
+/// and then the entries closest to expiring.
+///
+/// Clones of a `FixedTtlResolver` share the same cache.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "runtime")]
+/// # fn rt() {
+/// use std::time::Duration;
+/// use fluxio::client::connect::dns::{FixedTtlResolver, GaiResolver};
+/// use fluxio::client::HttpConnector;
+///
+/// let mut resolver = FixedTtlResolver::new(GaiResolver::new());
+/// resolver.set_ttl(Duration::from_secs(30));
+///
+/// let connector = HttpConnector::new_with_resolver(resolver);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct FixedTtlResolver<R> {
+    inner: R,
+    config: CacheConfig,
+    cache: Arc<Mutex<HashMap<Name, CacheEntry>>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct CacheConfig {
+    ttl: Duration,
+    negative_ttl: Duration,
+    max_entries: usize,
+}
+
+struct CacheEntry {
+    // Failures are kept as their message, since errors can't be cloned.
+    result: Result<Vec<SocketAddr>, Arc<str>>,
+    expires_at: Instant,
+}
+
+/// An iterator of IP addresses returned by a `FixedTtlResolver`.
+pub struct CachedAddrs {
+    inner: SocketAddrs,
+}
+
+/// A future to resolve a name returned by `FixedTtlResolver`.
+pub struct FixedTtlFuture {
+    inner: Pin<Box<dyn Future<Output = Result<CachedAddrs, BoxError>> + Send>>,
+}
+
 impl Name {
     pub(super) fn new(host: Box<str>) -> Name {
         Name { host }
@@ -165,6 +232,187 @@ impl Drop for GaiFuture {
     }
 }
 
+impl<R> FixedTtlResolver<R> {
+    /// Construct a new `FixedTtlResolver` around another resolver.
+    pub fn new(inner: R) -> Self {
+        FixedTtlResolver {
+            inner,
+            config: CacheConfig {
+                ttl: Duration::from_secs(60),
+                negative_ttl: Duration::from_secs(5),
+                max_entries: 1024,
+            },
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set how long successful lookups are cached.
+    ///
+    /// This applies to every name, whatever the TTL of its DNS records.
+    ///
+    /// Default is 60 seconds.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.config.ttl = ttl;
+    }
+
+    /// Set how long failed lookups are cached.
+    ///
+    /// A zero duration disables negative caching.
+    ///
+    /// Default is 5 seconds.
+    pub fn set_negative_ttl(&mut self, ttl: Duration) {
+        self.config.negative_ttl = ttl;
+    }
+
+    /// Set the maximum number of names kept in the cache.
+    ///
+    /// Default is 1024.
+    pub fn set_max_entries(&mut self, max: usize) {
+        self.config.max_entries = max;
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn lookup(&self, name: &Name) -> Option<Result<CachedAddrs, BoxError>> {
+        let mut cache = self.cache.lock().unwrap();
+        let entry = cache.get(name)?;
+        if entry.expires_at <= Instant::now() {
+            cache.remove(name);
+            return None;
+        }
+        debug!("resolved host={:?} from cache", name.host);
+        Some(match entry.result {
+            Ok(ref addrs) => Ok(CachedAddrs {
+                inner: SocketAddrs::new(addrs.clone()),
+            }),
+            Err(ref msg) => Err(io::Error::other(msg.to_string()).into()),
+        })
+    }
+}
+
+impl CacheConfig {
+    fn store(
+        &self,
+        cache: &Mutex<HashMap<Name, CacheEntry>>,
+        name: Name,
+        result: Result<Vec<SocketAddr>, Arc<str>>,
+    ) {
+        let ttl = if result.is_ok() {
+            self.ttl
+        } else {
+            self.negative_ttl
+        };
+        if ttl == Duration::ZERO || self.max_entries == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut cache = cache.lock().unwrap();
+        if cache.len() >= self.max_entries && !cache.contains_key(&name) {
+            cache.retain(|_, entry| entry.expires_at > now);
+        }
+        while cache.len() >= self.max_entries && !cache.contains_key(&name) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(name, _)| name.clone())
+                .expect("cache is not empty");
+            cache.remove(&oldest);
+        }
+        cache.insert(
+            name,
+            CacheEntry {
+                result,
+                expires_at: now + ttl,
+            },
+        );
+    }
+}
+
+impl<R> Service<Name> for FixedTtlResolver<R>
+where
+    R: Resolve,
+    R::Future: Send + 'static,
+{
+    type Response = CachedAddrs;
+    type Error = BoxError;
+    type Future = FixedTtlFuture;
+
+    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        if let Some(cached) = self.lookup(&name) {
+            return FixedTtlFuture {
+                inner: Box::pin(futures_util::future::ready(cached)),
+            };
+        }
+
+        let resolving = self.inner.resolve(name.clone());
+        let config = self.config;
+        let cache = self.cache.clone();
+        FixedTtlFuture {
+            inner: Box::pin(async move {
+                match resolving.await {
+                    Ok(addrs) => {
+                        let addrs = addrs.collect::<Vec<_>>();
+                        config.store(&cache, name, Ok(addrs.clone()));
+                        Ok(CachedAddrs {
+                            inner: SocketAddrs::new(addrs),
+                        })
+                    }
+                    Err(err) => {
+                        let err = err.into();
+                        config.store(&cache, name, Err(err.to_string().into()));
+                        Err(err)
+                    }
+                }
+            }),
+        }
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for FixedTtlResolver<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixedTtlResolver")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl Future for FixedTtlFuture {
+    type Output = Result<CachedAddrs, BoxError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for FixedTtlFuture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("FixedTtlFuture")
+    }
+}
+
+impl Iterator for CachedAddrs {
+    type Item = SocketAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl fmt::Debug for CachedAddrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("CachedAddrs")
+    }
+}
+
 impl Iterator for GaiAddrs {
     type Item = SocketAddr;
 
@@ -322,13 +570,22 @@ mod sealed {
     use crate::common::{task, Future, Poll};
     use tower_service::Service;
 
-    // "Trait alias" for `Service<Name, Response = Addrs>`
+    /// A resolver of domain names into socket addresses.
+    ///
+    /// This is a "trait alias" for `Service<Name>` with a `Response` that is
+    /// an iterator of `SocketAddr`s, and is implemented for all such
+    /// services. Implement `Service` to write a custom resolver.
     pub trait Resolve {
+        /// The addresses a name resolves to.
         type Addrs: Iterator<Item = SocketAddr>;
+        /// The error returned if resolving fails.
         type Error: Into<Box<dyn std::error::Error + Send + Sync>>;
+        /// The future resolving a name.
         type Future: Future<Output = Result<Self::Addrs, Self::Error>>;
 
+        /// Returns `Ready` when the resolver is able to resolve a name.
         fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>>;
+        /// Resolves a name.
         fn resolve(&mut self, name: Name) -> Self::Future;
     }
 
@@ -415,6 +672,90 @@ mod tests {
         assert!(fallback.is_empty());
     }
 
+    #[derive(Clone)]
+    struct Counting {
+        calls: Arc<Mutex<usize>>,
+        fail: bool,
+    }
+
+    impl Service<Name> for Counting {
+        type Response = vec::IntoIter<SocketAddr>;
+        type Error = io::Error;
+        type Future = futures_util::future::Ready<Result<Self::Response, io::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut task::Context<'_>) -> Poll<Result<(), io::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _name: Name) -> Self::Future {
+            *self.calls.lock().unwrap() += 1;
+            futures_util::future::ready(if self.fail {
+                Err(io::Error::other("no such host"))
+            } else {
+                Ok(vec![SocketAddr::from(([127, 0, 0, 1], 0))].into_iter())
+            })
+        }
+    }
+
+    fn counting(fail: bool) -> (FixedTtlResolver<Counting>, Arc<Mutex<usize>>) {
+        let calls = Arc::new(Mutex::new(0));
+        let inner = Counting {
+            calls: calls.clone(),
+            fail,
+        };
+        (FixedTtlResolver::new(inner), calls)
+    }
+
+    #[tokio::test]
+    async fn test_caching_resolver_hits_and_expires() {
+        let (mut resolver, calls) = counting(false);
+        let name = Name::from_str("example.com").unwrap();
+
+        for _ in 0..3 {
+            let addrs = resolve(&mut resolver, name.clone()).await.unwrap();
+            assert_eq!(addrs.count(), 1);
+        }
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        resolver.set_ttl(Duration::ZERO);
+        resolver.clear();
+        resolve(&mut resolver, name.clone()).await.unwrap();
+        resolve(&mut resolver, name).await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_caching_resolver_negative() {
+        let (mut resolver, calls) = counting(true);
+        let name = Name::from_str("missing.example").unwrap();
+
+        let err = resolve(&mut resolver, name.clone()).await.unwrap_err();
+        assert_eq!(err.to_string(), "no such host");
+        let err = resolve(&mut resolver, name).await.unwrap_err();
+        assert_eq!(err.to_string(), "no such host");
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_caching_resolver_max_entries() {
+        let (mut resolver, calls) = counting(false);
+        resolver.set_max_entries(2);
+
+        for (secs, host) in [(30, "a.example"), (60, "b.example"), (10, "c.example")] {
+            resolver.set_ttl(Duration::from_secs(secs));
+            resolve(&mut resolver, Name::from_str(host).unwrap())
+                .await
+                .unwrap();
+        }
+        assert_eq!(resolver.cache.lock().unwrap().len(), 2);
+
+        // "a.example" was closest to expiring when "c.example" was added
+        resolve(&mut resolver, Name::from_str("a.example").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), 4);
+    }
+
     #[test]
     fn test_name_from_str() {
         const DOMAIN: &str = "test.example.com";
@@ -80,8 +80,11 @@
 //! [`AsyncWrite`]: tokio::io::AsyncWrite
 //! [`Connection`]: Connection
 use std::fmt;
+use std::time::Duration;
 
-use ::http::Extensions;
+use ::http::{Extensions, HeaderMap};
+
+pub(crate) mod timing;
 
 cfg_feature! {
     #![feature = "tcp"]
@@ -96,6 +99,8 @@ cfg_feature! {
     #![any(feature = "http1", feature = "http2")]
 
     pub use self::sealed::Connect;
+
+    pub mod proxy;
 }
 
 /// Describes a type returned by a connector.
@@ -112,7 +117,10 @@ pub trait Connection {
 pub struct Connected {
     pub(super) alpn: Alpn,
     pub(super) is_proxied: bool,
+    pub(super) proxy_headers: Option<HeaderMap>,
     pub(super) extra: Option<Extra>,
+    pub(super) dns_duration: Option<Duration>,
+    pub(super) tls_duration: Option<Duration>,
 }
 
 pub(super) struct Extra(Box<dyn ExtraInner>);
@@ -129,7 +137,10 @@ impl Connected {
         Connected {
             alpn: Alpn::None,
             is_proxied: false,
+            proxy_headers: None,
             extra: None,
+            dns_duration: None,
+            tls_duration: None,
         }
     }
 
@@ -161,6 +172,17 @@ impl Connected {
         self.is_proxied
     }
 
+    /// Set headers the proxy needs in every request forwarded through it,
+    /// such as `Proxy-Authorization`.
+    ///
+    /// The `Client` adds them to requests sent in absolute-form over this
+    /// transport, unless the request already has them. This only applies
+    /// if [`proxy(true)`](Connected::proxy) is set.
+    pub fn proxy_headers(mut self, headers: HeaderMap) -> Connected {
+        self.proxy_headers = Some(headers);
+        self
+    }
+
     /// Set extra connection information to be set in the extensions of every `Response`.
     pub fn extra<T: Clone + Send + Sync + 'static>(mut self, extra: T) -> Connected {
         if let Some(prev) = self.extra {
@@ -189,6 +211,24 @@ impl Connected {
         self.alpn == Alpn::H2
     }
 
+    /// Set how long resolving the destination took.
+    ///
+    /// This is reported in the [`RequestTimings`](crate::ext::RequestTimings)
+    /// of responses. The `HttpConnector` reports it on its own.
+    pub fn dns_duration(mut self, duration: Duration) -> Connected {
+        self.dns_duration = Some(duration);
+        self
+    }
+
+    /// Set how long the TLS handshake of the connected transport took.
+    ///
+    /// This is reported in the [`RequestTimings`](crate::ext::RequestTimings)
+    /// of responses.
+    pub fn tls_duration(mut self, duration: Duration) -> Connected {
+        self.tls_duration = Some(duration);
+        self
+    }
+
     // Don't public expose that `Connected` is `Clone`, unsure if we want to
     // keep that contract...
     #[cfg(feature = "http2")]
@@ -196,7 +236,10 @@ impl Connected {
         Connected {
             alpn: self.alpn.clone(),
             is_proxied: self.is_proxied,
+            proxy_headers: self.proxy_headers.clone(),
             extra: self.extra.clone(),
+            dns_duration: self.dns_duration,
+            tls_duration: self.tls_duration,
         }
     }
 }
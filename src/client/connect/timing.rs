@@ -0,0 +1,102 @@
+//! Timing of the connectors' work.
+//!
+//! Connectors can't hand anything but the IO back to the client, so the
+//! `HttpConnector` records how long it resolved names for into a
+//! thread-local, which is set around each poll of a `Timed` future.
+
+use std::cell::Cell;
+use std::time::Duration;
+
+#[cfg(any(feature = "http1", feature = "http2"))]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+thread_local! {
+    static DNS: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Records how long resolving the destination took, if the connector is
+/// polled by a `Timed` future.
+#[cfg_attr(not(feature = "tcp"), allow(dead_code))]
+pub(crate) fn record_dns(duration: Duration) {
+    DNS.with(|dns| dns.set(Some(duration)));
+}
+
+/// How long a connector took.
+#[cfg(any(feature = "http1", feature = "http2"))]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ConnectTimes {
+    pub(crate) dns: Option<Duration>,
+    pub(crate) connect: Duration,
+}
+
+/// A connector future, timed.
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub(crate) struct Timed<F> {
+    inner: F,
+    start: Instant,
+    dns: Option<Duration>,
+}
+
+#[cfg(any(feature = "http1", feature = "http2"))]
+impl<F> Timed<F> {
+    pub(crate) fn new(inner: F) -> Timed<F> {
+        Timed {
+            inner,
+            start: Instant::now(),
+            dns: None,
+        }
+    }
+}
+
+#[cfg(any(feature = "http1", feature = "http2"))]
+impl<F, T, E> Future for Timed<F>
+where
+    F: Future<Output = Result<T, E>> + Unpin,
+{
+    type Output = Result<(T, ConnectTimes), E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let prev = DNS.with(|dns| dns.replace(self.dns));
+        let res = Pin::new(&mut self.inner).poll(cx);
+        self.dns = DNS.with(|dns| dns.replace(prev));
+
+        res.map(|res| {
+            res.map(|io| {
+                let times = ConnectTimes {
+                    dns: self.dns,
+                    connect: self.start.elapsed(),
+                };
+                (io, times)
+            })
+        })
+    }
+}
+
+#[cfg(all(test, any(feature = "http1", feature = "http2")))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_dns_of_polled_connector() {
+        let connecting = async {
+            tokio::task::yield_now().await;
+            record_dns(Duration::from_millis(5));
+            Ok::<_, ()>("io")
+        };
+        let (io, times) = Timed::new(Box::pin(connecting)).await.unwrap();
+        assert_eq!(io, "io");
+        assert_eq!(times.dns, Some(Duration::from_millis(5)));
+        // not leaked outside of the polls
+        assert_eq!(DNS.with(|dns| dns.get()), None);
+
+        let (_, times) = Timed::new(Box::pin(async { Ok::<_, ()>(()) }))
+            .await
+            .unwrap();
+        assert_eq!(times.dns, None);
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use bytes::Bytes;
+
+use crate::body::HttpBody;
+use crate::Body;
+
+type OnProgress = Box<dyn FnMut(u64, Option<u64>) + Send>;
+
+/// Saves a response body to a file.
+///
+/// The body is written out chunk by chunk, each one before the next is
+/// read, so a slow disk slows down receiving the body instead of buffering
+/// it in memory. The file is written on the blocking thread pool of the
+/// Tokio runtime.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn doc() -> Result<(), Box<dyn std::error::Error>> {
+/// use fluxio::client::{Download, SyncPolicy};
+/// use fluxio::Client;
+///
+/// let client = Client::new();
+/// let res = client.get("http://example.com/archive.tar".parse()?).await?;
+///
+/// let saved = Download::new("archive.tar")
+///     .sync(SyncPolicy::OnComplete)
+///     .on_progress(|saved, total| match total {
+///         Some(total) => println!("{}/{} bytes", saved, total),
+///         None => println!("{} bytes", saved),
+///     })
+///     .save(res.into_body())
+///     .await?;
+/// # let _ = saved;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Optional
+///
+/// This type requires enabling the `runtime` feature in your `Cargo.toml`.
+pub struct Download {
+    path: PathBuf,
+    sync: SyncPolicy,
+    on_progress: Option<OnProgress>,
+}
+
+/// When a [`Download`] makes sure the data it wrote reached the disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Leave it to the operating system, which is the fastest.
+    #[default]
+    Never,
+    /// Sync the file once the whole body was written.
+    OnComplete,
+    /// Sync the data written each time this many more bytes were written,
+    /// and the file once the whole body was.
+    ///
+    /// This bounds how much of a partial download can be lost on a crash,
+    /// so it can be resumed from the length of the file.
+    Every(u64),
+}
+
+impl Download {
+    /// Creates a download to the file at `path`.
+    ///
+    /// The file is created if it doesn't exist, and truncated otherwise.
+    pub fn new(path: impl Into<PathBuf>) -> Download {
+        Download {
+            path: path.into(),
+            sync: SyncPolicy::Never,
+            on_progress: None,
+        }
+    }
+
+    /// Sets when the written data is synced to the disk.
+    ///
+    /// Default is [`SyncPolicy::Never`].
+    pub fn sync(mut self, policy: SyncPolicy) -> Self {
+        self.sync = policy;
+        self
+    }
+
+    /// Sets a callback called after each chunk is written.
+    ///
+    /// It is passed the number of bytes written so far, and the length of
+    /// the whole body if it is known, such as from a `Content-Length`.
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: FnMut(u64, Option<u64>) + Send + 'static,
+    {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Writes `body` to the file, returning the number of bytes written.
+    ///
+    /// On error, the file is left with the data written so far. An error of
+    /// the body is returned as an `io::Error` wrapping it.
+    pub async fn save(mut self, mut body: Body) -> io::Result<u64> {
+        let total = body.size_hint().exact();
+        let path = self.path;
+        let mut file = blocking(move || File::create(path)).await?;
+
+        let mut saved = 0;
+        let mut unsynced = 0;
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(io::Error::other)?;
+            let len = chunk.len() as u64;
+            unsynced += len;
+            let sync_data = matches!(self.sync, SyncPolicy::Every(every) if unsynced >= every);
+            if sync_data {
+                unsynced = 0;
+            }
+            file = blocking(move || write_chunk(file, chunk, sync_data)).await?;
+
+            saved += len;
+            if let Some(ref mut on_progress) = self.on_progress {
+                on_progress(saved, total);
+            }
+        }
+
+        if self.sync != SyncPolicy::Never {
+            blocking(move || file.sync_all()).await?;
+        }
+        Ok(saved)
+    }
+}
+
+impl fmt::Debug for Download {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Download")
+            .field("path", &self.path)
+            .field("sync", &self.sync)
+            .finish()
+    }
+}
+
+fn write_chunk(mut file: File, chunk: Bytes, sync_data: bool) -> io::Result<File> {
+    file.write_all(&chunk)?;
+    if sync_data {
+        file.sync_data()?;
+    }
+    Ok(file)
+}
+
+async fn blocking<F, T>(f: F) -> io::Result<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(io::Error::other)
+        .and_then(|res| res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn saves_body_with_progress() {
+        let path = std::env::temp_dir().join(format!("fluxio-download-{}", std::process::id()));
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(async move {
+            tx.send_data(Bytes::from("hello ")).await.unwrap();
+            tx.send_data(Bytes::from("world")).await.unwrap();
+        });
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let seen = progress.clone();
+        let saved = Download::new(&path)
+            .sync(SyncPolicy::Every(4))
+            .on_progress(move |saved, total| seen.lock().unwrap().push((saved, total)))
+            .save(body)
+            .await
+            .unwrap();
+
+        assert_eq!(saved, 11);
+        assert_eq!(*progress.lock().unwrap(), [(6, None), (11, None)]);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn body_error_fails() {
+        let path = std::env::temp_dir().join(format!("fluxio-download-err-{}", std::process::id()));
+        let (tx, body) = Body::channel();
+        tx.abort();
+
+        let err = Download::new(&path).save(body).await.unwrap_err();
+        assert!(err.get_ref().unwrap().is::<crate::Error>(), "{:?}", err);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+//! Automatic `Host` headers.
+
+use http::header::{HeaderValue, HOST};
+use http::{Request, Uri};
+
+use super::client::get_non_default_port;
+
+/// Whether a `Host` header is added to requests, derived from their URI.
+///
+/// The value is the host of the URI's authority, as written, followed by
+/// its port unless that is the default one of the scheme, like
+/// `example.com:8080`. Hosts in a `Uri` are always ASCII, so internationalized
+/// domain names must be given in their punycode form (`xn--...`), which is
+/// then what the `Host` header holds. Userinfo is never included.
+///
+/// Requests whose URI has no authority, such as origin-form ones sent on a
+/// [`SendRequest`](super::conn::SendRequest), are left alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HostPolicy {
+    /// Add a `Host` header if the request doesn't have one.
+    #[default]
+    SetIfMissing,
+    /// Always set the `Host` header, replacing any the request has.
+    Override,
+    /// Never add a `Host` header.
+    Never,
+}
+
+impl HostPolicy {
+    pub(crate) fn apply<B>(self, req: &mut Request<B>) {
+        let set = match self {
+            HostPolicy::SetIfMissing => !req.headers().contains_key(HOST),
+            HostPolicy::Override => true,
+            HostPolicy::Never => false,
+        };
+        if set {
+            if let Some(host) = host_header(req.uri()) {
+                req.headers_mut().insert(HOST, host);
+            }
+        }
+    }
+}
+
+fn host_header(uri: &Uri) -> Option<HeaderValue> {
+    let host = uri.host()?;
+    let value = match get_non_default_port(uri) {
+        Some(port) => HeaderValue::from_str(&format!("{}:{}", host, port)),
+        None => HeaderValue::from_str(host),
+    };
+    Some(value.expect("uri host is valid header value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(
+        policy: HostPolicy,
+        uri: &'static str,
+        existing: Option<&'static str>,
+    ) -> Option<String> {
+        let mut req = Request::new(());
+        *req.uri_mut() = Uri::from_static(uri);
+        if let Some(existing) = existing {
+            req.headers_mut()
+                .insert(HOST, HeaderValue::from_static(existing));
+        }
+        policy.apply(&mut req);
+        req.headers()
+            .get(HOST)
+            .map(|host| host.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn host_policies() {
+        let uri = "http://Example.COM:8080/path";
+        assert_eq!(
+            host(HostPolicy::SetIfMissing, uri, None).as_deref(),
+            Some("Example.COM:8080")
+        );
+        assert_eq!(
+            host(HostPolicy::SetIfMissing, uri, Some("other")).as_deref(),
+            Some("other")
+        );
+        assert_eq!(
+            host(HostPolicy::Override, uri, Some("other")).as_deref(),
+            Some("Example.COM:8080")
+        );
+        assert_eq!(host(HostPolicy::Never, uri, None), None);
+        assert_eq!(
+            host(HostPolicy::Override, "/path", Some("other")).as_deref(),
+            Some("other")
+        );
+    }
+
+    #[test]
+    fn host_normalization() {
+        let set = |uri| host(HostPolicy::SetIfMissing, uri, None);
+        assert_eq!(
+            set("https://example.com:443/").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            set("http://example.com:80/").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            set("https://example.com:80/").as_deref(),
+            Some("example.com:80")
+        );
+        assert_eq!(
+            set("http://user:pw@xn--bcher-kva.example/").as_deref(),
+            Some("xn--bcher-kva.example")
+        );
+        assert_eq!(set("http://[::1]:3000/").as_deref(), Some("[::1]:3000"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Idempotency keys for retried requests.
+
+use std::fmt;
+use std::sync::Arc;
+
+use http::header::{HeaderName, HeaderValue};
+use http::Request;
+
+/// A marker extension allowing a non-idempotent request to be replayed.
+///
+/// Insert this into the extensions of a `POST` (or other non-idempotent)
+/// request to tell the [`Client`](super::Client) the server supports
+/// idempotency keys for it. When the client was configured with
+/// [`Builder::idempotency_key`](super::Builder::idempotency_key), such
+/// requests get a key attached, which stays the same across any retries.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::client::ReplaySafe;
+/// use fluxio::{Body, Method, Request};
+///
+/// let mut req = Request::builder()
+///     .method(Method::POST)
+///     .uri("http://example.com/payments")
+///     .body(Body::from("{}"))
+///     .unwrap();
+/// req.extensions_mut().insert(ReplaySafe);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReplaySafe;
+
+/// Configuration of the idempotency key attached to replay-safe requests.
+///
+/// By default, a random UUID (version 4) is sent in the `Idempotency-Key`
+/// header.
+#[derive(Clone)]
+pub struct IdempotencyKey {
+    header: HeaderName,
+    generate: Arc<dyn Fn() -> HeaderValue + Send + Sync>,
+}
+
+impl IdempotencyKey {
+    /// Create the default configuration.
+    pub fn new() -> IdempotencyKey {
+        IdempotencyKey {
+            header: HeaderName::from_static("idempotency-key"),
+            generate: Arc::new(random_uuid),
+        }
+    }
+
+    /// Set the name of the header the key is sent in.
+    ///
+    /// Default is `Idempotency-Key`.
+    pub fn header_name(mut self, name: HeaderName) -> IdempotencyKey {
+        self.header = name;
+        self
+    }
+
+    /// Set the function used to generate a new key.
+    ///
+    /// It is called once per request, never per retry.
+    pub fn generator<F>(mut self, generate: F) -> IdempotencyKey
+    where
+        F: Fn() -> HeaderValue + Send + Sync + 'static,
+    {
+        self.generate = Arc::new(generate);
+        self
+    }
+
+    /// Attaches a key if the request is non-idempotent, marked replay-safe,
+    /// and doesn't already carry one.
+    pub(super) fn attach<B>(&self, req: &mut Request<B>) {
+        if req.method().is_idempotent()
+            || req.extensions().get::<ReplaySafe>().is_none()
+            || req.headers().contains_key(&self.header)
+        {
+            return;
+        }
+        req.headers_mut()
+            .insert(self.header.clone(), (self.generate)());
+    }
+}
+
+impl Default for IdempotencyKey {
+    fn default() -> IdempotencyKey {
+        IdempotencyKey::new()
+    }
+}
+
+impl fmt::Debug for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdempotencyKey")
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+fn random_uuid() -> HeaderValue {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("failed to get random bytes for an idempotency key");
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let mut s = String::with_capacity(36);
+    for (i, b) in bytes.iter().enumerate() {
+        if i == 4 || i == 6 || i == 8 || i == 10 {
+            s.push('-');
+        }
+        s.push_str(&format!("{:02x}", b));
+    }
+    HeaderValue::from_str(&s).expect("uuid is a valid header value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::Method;
+
+    fn req(method: Method, replay_safe: bool) -> Request<()> {
+        let mut req = Request::builder().method(method).body(()).unwrap();
+        if replay_safe {
+            req.extensions_mut().insert(ReplaySafe);
+        }
+        req
+    }
+
+    #[test]
+    fn attaches_to_replay_safe_post() {
+        let key = IdempotencyKey::new();
+
+        let mut post = req(Method::POST, true);
+        key.attach(&mut post);
+        let value = post.headers()["idempotency-key"].to_str().unwrap();
+        assert_eq!(value.len(), 36);
+        assert_eq!(&value[14..15], "4");
+        assert!(matches!(&value[19..20], "8" | "9" | "a" | "b"));
+
+        // every request gets a key of its own
+        let mut other = req(Method::POST, true);
+        key.attach(&mut other);
+        assert_ne!(other.headers()["idempotency-key"], value);
+
+        // retries keep the same key
+        let first = post.headers()["idempotency-key"].clone();
+        key.attach(&mut post);
+        assert_eq!(post.headers()["idempotency-key"], first);
+    }
+
+    #[test]
+    fn skips_unmarked_or_idempotent() {
+        let key = IdempotencyKey::new();
+
+        let mut post = req(Method::POST, false);
+        key.attach(&mut post);
+        assert!(post.headers().is_empty());
+
+        let mut put = req(Method::PUT, true);
+        key.attach(&mut put);
+        assert!(put.headers().is_empty());
+    }
+
+    #[test]
+    fn custom_name_and_generator() {
+        let key = IdempotencyKey::new()
+            .header_name(HeaderName::from_static("x-request-key"))
+            .generator(|| HeaderValue::from_static("abc"));
+
+        let mut post = req(Method::PATCH, true);
+        key.attach(&mut post);
+        assert_eq!(post.headers()["x-request-key"], "abc");
+    }
+}
@@ -59,10 +59,23 @@ cfg_feature! {
     #![any(feature = "http1", feature = "http2")]
 
     pub use self::client::{Builder, Client, ResponseFuture};
+    #[cfg(feature = "runtime")]
+    pub use self::download::{Download, SyncPolicy};
+    pub use self::host::HostPolicy;
+    pub use self::pool::{EvictionReason, HostStats, PoolStats, QueueOrder};
+    pub use self::idempotency::{IdempotencyKey, ReplaySafe};
+    pub use self::validate::ResponseViolation;
 
+    mod auth;
     mod client;
     pub mod conn;
     pub(super) mod dispatch;
+    #[cfg(feature = "runtime")]
+    mod download;
+    mod host;
+    mod idempotency;
     mod pool;
     pub mod service;
+    mod timeout;
+    pub(crate) mod validate;
 }
@@ -8,6 +8,7 @@ use std::sync::{Arc, Mutex, Weak};
 use std::time::{Duration, Instant};
 
 use futures_channel::oneshot;
+use http::uri::{Authority, Scheme};
 #[cfg(feature = "runtime")]
 use tokio::time::{Duration, Instant, Interval};
 use tracing::{debug, trace};
@@ -20,6 +21,8 @@ use crate::common::{exec::Exec, task, Future, Pin, Poll, Unpin};
 pub(super) struct Pool<T> {
     // If the pool is disabled, this is None.
     inner: Option<Arc<Mutex<PoolInner<T>>>>,
+    // If requests aren't limited, this is None.
+    limiter: Option<Arc<Limiter>>,
 }
 
 // Before using a pooled connection, make sure the sender is not dead.
@@ -34,6 +37,158 @@ pub(super) trait Poolable: Unpin + Send + Sized + 'static {
     /// Allows for HTTP/2 to return a shared reservation.
     fn reserve(self) -> Reservation<Self>;
     fn can_share(&self) -> bool;
+    /// Why a connection that is no longer open was closed.
+    fn close_reason(&self) -> EvictionReason {
+        EvictionReason::ServerClosed
+    }
+}
+
+/// The reason an idle connection was removed from a `Client`'s pool.
+///
+/// See [`Builder::pool_eviction_callback`](super::Builder::pool_eviction_callback).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EvictionReason {
+    /// The connection was idle for longer than the pool's idle timeout.
+    IdleTimeout,
+    /// The server closed the connection.
+    ServerClosed,
+    /// The server sent an HTTP/2 `GOAWAY` and the connection shut down.
+    GoAway,
+    /// The connection failed with an error.
+    Error,
+    /// The pool already held the maximum number of idle connections for the
+    /// host.
+    MaxIdlePerHost,
+    /// The server responded with `421 Misdirected Request`, and all idle
+    /// connections for the host were removed.
+    Misdirected,
+    /// The pool was dropped with the connection still idle.
+    PoolCleared,
+}
+
+impl EvictionReason {
+    /// A short, machine-readable name of this reason, like `"idle_timeout"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvictionReason::IdleTimeout => "idle_timeout",
+            EvictionReason::ServerClosed => "server_closed",
+            EvictionReason::GoAway => "goaway",
+            EvictionReason::Error => "error",
+            EvictionReason::MaxIdlePerHost => "max_idle_per_host",
+            EvictionReason::Misdirected => "misdirected",
+            EvictionReason::PoolCleared => "pool_cleared",
+        }
+    }
+}
+
+impl fmt::Display for EvictionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The order in which requests waiting for a request slot in a `Client`'s
+/// pool are given one.
+///
+/// See [`Builder::pool_queue_order`](super::Builder::pool_queue_order).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueueOrder {
+    /// The request that has waited the longest goes first.
+    #[default]
+    Fifo,
+    /// The request that started waiting last goes first.
+    ///
+    /// Under sustained overload, this keeps serving the newest requests
+    /// quickly while the oldest ones run into the wait timeout, instead of
+    /// every request waiting almost as long.
+    Lifo,
+}
+
+/// A snapshot of the connections in a `Client`'s pool.
+///
+/// See [`Client::pool_stats`](super::Client::pool_stats).
+#[derive(Clone, Debug, Default)]
+pub struct PoolStats {
+    hosts: Vec<HostStats>,
+}
+
+/// The pooled connections to a single host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostStats {
+    scheme: Scheme,
+    authority: Authority,
+    idle: usize,
+    active: usize,
+}
+
+impl PoolStats {
+    /// The hosts the pool has connections to, sorted by authority.
+    pub fn hosts(&self) -> &[HostStats] {
+        &self.hosts
+    }
+
+    /// The number of idle connections, to all hosts.
+    pub fn idle(&self) -> usize {
+        self.hosts.iter().map(HostStats::idle).sum()
+    }
+
+    /// The number of active connections, to all hosts.
+    pub fn active(&self) -> usize {
+        self.hosts.iter().map(HostStats::active).sum()
+    }
+}
+
+impl HostStats {
+    /// The scheme of the connections, `http` or `https`.
+    pub fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+
+    /// The host and port the connections are to.
+    pub fn authority(&self) -> &Authority {
+        &self.authority
+    }
+
+    /// The number of connections waiting in the pool to be used.
+    ///
+    /// An HTTP/2 connection is shared by all requests to its host, and
+    /// stays in the pool while in use, so it always counts as idle.
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+
+    /// The number of HTTP/1 connections checked out of the pool for a
+    /// request, which return to it once the request is done.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+}
+
+type EvictionCallback = dyn Fn(&Authority, EvictionReason) + Send + Sync;
+
+/// The callback told about evicted connections.
+#[derive(Clone)]
+pub(super) struct OnEvict(Arc<EvictionCallback>);
+
+impl OnEvict {
+    pub(super) fn new<F>(f: F) -> OnEvict
+    where
+        F: Fn(&Authority, EvictionReason) + Send + Sync + 'static,
+    {
+        OnEvict(Arc::new(f))
+    }
+
+    fn call(&self, key: &Key, reason: EvictionReason) {
+        debug!("evicting connection for {:?}: {}", key, reason);
+        (self.0)(&key.1, reason);
+    }
+}
+
+impl fmt::Debug for OnEvict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnEvict").finish()
+    }
 }
 
 /// When checking out a pooled connection, it might be that the connection
@@ -62,10 +217,18 @@ struct PoolInner<T> {
     // should be shared. This prevents making multiple HTTP/2 connections
     // to the same host.
     connecting: HashSet<Key>,
+    // The number of HTTP/1 connections being established to each host,
+    // only counted when `max_connecting_per_host` is set. Requests over the
+    // limit wait for one of these connections instead of making their own.
+    connecting_h1: HashMap<Key, usize>,
+    max_connecting_per_host: usize,
     // These are internal Conns sitting in the event loop in the KeepAlive
     // state, waiting to receive a new Request to send on the socket.
     idle: HashMap<Key, Vec<Idle<T>>>,
     max_idle_per_host: usize,
+    // The number of HTTP/1 connections to each host that are checked out of
+    // the pool, and will be reinserted once their request is done.
+    active: HashMap<Key, usize>,
     // These are outstanding Checkouts that are waiting for a socket to be
     // able to send a Request one. This is used when "racing" for a new
     // connection.
@@ -83,16 +246,24 @@ struct PoolInner<T> {
     #[cfg(feature = "runtime")]
     exec: Exec,
     timeout: Option<Duration>,
+    on_evict: Option<OnEvict>,
 }
 
 // This is because `Weak::new()` *allocates* space for `T`, even if it
 // doesn't need it!
 struct WeakOpt<T>(Option<Weak<T>>);
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub(super) struct Config {
     pub(super) idle_timeout: Option<Duration>,
     pub(super) max_idle_per_host: usize,
+    pub(super) max_connecting_per_host: usize,
+    pub(super) max_requests_per_host: usize,
+    pub(super) max_requests: usize,
+    pub(super) queue_order: QueueOrder,
+    #[cfg(feature = "runtime")]
+    pub(super) wait_timeout: Option<Duration>,
+    pub(super) on_evict: Option<OnEvict>,
 }
 
 impl Config {
@@ -106,7 +277,10 @@ impl<T> Pool<T> {
         let inner = if config.is_enabled() {
             Some(Arc::new(Mutex::new(PoolInner {
                 connecting: HashSet::new(),
+                connecting_h1: HashMap::new(),
+                max_connecting_per_host: config.max_connecting_per_host,
                 idle: HashMap::new(),
+                active: HashMap::new(),
                 #[cfg(feature = "runtime")]
                 idle_interval_ref: None,
                 max_idle_per_host: config.max_idle_per_host,
@@ -114,18 +288,96 @@ impl<T> Pool<T> {
                 #[cfg(feature = "runtime")]
                 exec: __exec.clone(),
                 timeout: config.idle_timeout,
+                on_evict: config.on_evict,
             })))
         } else {
             None
         };
+        let limiter =
+            if config.max_requests_per_host != usize::MAX || config.max_requests != usize::MAX {
+                Some(Arc::new(Limiter {
+                    state: Mutex::new(LimiterState {
+                        total: 0,
+                        per_host: HashMap::new(),
+                        waiters: VecDeque::new(),
+                    }),
+                    max_per_host: config.max_requests_per_host,
+                    max_total: config.max_requests,
+                    order: config.queue_order,
+                    #[cfg(feature = "runtime")]
+                    wait_timeout: config.wait_timeout,
+                }))
+            } else {
+                None
+            };
 
-        Pool { inner }
+        Pool { inner, limiter }
     }
 
     fn is_enabled(&self) -> bool {
         self.inner.is_some()
     }
 
+    /// Counts the idle and active connections to each host.
+    pub(super) fn stats(&self) -> PoolStats {
+        let inner = match self.inner {
+            Some(ref enabled) => enabled.lock().unwrap(),
+            None => return PoolStats::default(),
+        };
+
+        let mut hosts = inner
+            .idle
+            .keys()
+            .chain(
+                inner
+                    .active
+                    .keys()
+                    .filter(|key| !inner.idle.contains_key(key)),
+            )
+            .map(|key| HostStats {
+                scheme: key.0.clone(),
+                authority: key.1.clone(),
+                idle: inner.idle.get(key).map_or(0, Vec::len),
+                active: inner.active.get(key).copied().unwrap_or(0),
+            })
+            .collect::<Vec<_>>();
+        hosts.sort_by(|a, b| {
+            (a.authority.as_str(), a.scheme.as_str())
+                .cmp(&(b.authority.as_str(), b.scheme.as_str()))
+        });
+        PoolStats { hosts }
+    }
+
+    /// Waits for a request slot for `key`, if requests are limited.
+    ///
+    /// The slot is freed when the returned `Permit` is dropped.
+    pub(super) fn acquire(&self, key: &Key) -> impl Future<Output = crate::Result<Option<Permit>>> {
+        let waiting = self
+            .limiter
+            .as_ref()
+            .map(|limiter| limiter.try_acquire(key).map_err(|rx| (limiter.clone(), rx)));
+        async move {
+            let (_limiter, rx) = match waiting {
+                None => return Ok(None),
+                Some(Ok(permit)) => return Ok(Some(permit)),
+                Some(Err(waiting)) => waiting,
+            };
+
+            #[cfg(feature = "runtime")]
+            if let Some(dur) = _limiter.wait_timeout {
+                return match tokio::time::timeout(dur, rx).await {
+                    Ok(Ok(permit)) => Ok(Some(permit)),
+                    Ok(Err(_canceled)) => Err(crate::Error::new_canceled()),
+                    Err(_elapsed) => Err(crate::Error::new_pool_exhausted()),
+                };
+            }
+
+            rx.await
+                .map(Some)
+                .map_err(|_canceled| crate::Error::new_canceled())
+        }
+    }
+
     #[cfg(test)]
     pub(super) fn no_timer(&self) {
         // Prevent an actual interval from being created for this pool...
@@ -151,7 +403,7 @@ impl<T: Poolable> Pool<T> {
     }
 
     /// Ensure that there is only ever 1 connecting task for HTTP/2
-    /// connections. This does nothing for HTTP/1.
+    /// connections, and no more than `max_connecting_per_host` for HTTP/1.
     pub(super) fn connecting(&self, key: &Key, ver: Ver) -> Option<Connecting<T>> {
         if ver == Ver::Http2 {
             if let Some(ref enabled) = self.inner {
@@ -160,6 +412,7 @@ impl<T: Poolable> Pool<T> {
                     let connecting = Connecting {
                         key: key.clone(),
                         pool: WeakOpt::downgrade(enabled),
+                        h1_slot: false,
                     };
                     Some(connecting)
                 } else {
@@ -167,14 +420,31 @@ impl<T: Poolable> Pool<T> {
                     None
                 };
             }
+        } else if let Some(ref enabled) = self.inner {
+            let mut inner = enabled.lock().unwrap();
+            let max = inner.max_connecting_per_host;
+            if max != usize::MAX {
+                let count = inner.connecting_h1.entry(key.clone()).or_insert(0);
+                if *count >= max {
+                    trace!("HTTP/1 connecting limit reached for {:?}", key);
+                    return None;
+                }
+                *count += 1;
+                return Some(Connecting {
+                    key: key.clone(),
+                    pool: WeakOpt::downgrade(enabled),
+                    h1_slot: true,
+                });
+            }
         }
 
         // else
         Some(Connecting {
             key: key.clone(),
-            // in HTTP/1's case, there is never a lock, so we don't
+            // in HTTP/1's case, there is usually no lock, so we don't
             // need to do anything in Drop.
             pool: WeakOpt::none(),
+            h1_slot: false,
         })
     }
 
@@ -202,11 +472,7 @@ impl<T: Poolable> Pool<T> {
     }
     */
 
-    pub(super) fn pooled(
-        &self,
-        #[cfg_attr(not(feature = "http2"), allow(unused_mut))] mut connecting: Connecting<T>,
-        value: T,
-    ) -> Pooled<T> {
+    pub(super) fn pooled(&self, mut connecting: Connecting<T>, value: T) -> Pooled<T> {
         let (value, pool_ref) = if let Some(ref enabled) = self.inner {
             match value.reserve() {
                 #[cfg(feature = "http2")]
@@ -224,6 +490,13 @@ impl<T: Poolable> Pool<T> {
                     (to_return, WeakOpt::none())
                 }
                 Reservation::Unique(value) => {
+                    let mut inner = enabled.lock().unwrap();
+                    if connecting.h1_slot {
+                        inner.connected_h1(&connecting.key, false);
+                        connecting.pool = WeakOpt::none();
+                    }
+                    inner.checked_out(&connecting.key);
+                    drop(inner);
                     // Unique reservations must take a reference to the pool
                     // since they hope to reinsert once the reservation is
                     // completed
@@ -246,6 +519,22 @@ impl<T: Poolable> Pool<T> {
         }
     }
 
+    /// Removes all idle connections for a key, so new checkouts don't use
+    /// them.
+    ///
+    /// Connections that are in use are not closed.
+    pub(super) fn evict(&self, key: &Key, reason: EvictionReason) {
+        if let Some(ref enabled) = self.inner {
+            let mut inner = enabled.lock().unwrap();
+            if let Some(idle) = inner.idle.remove(key) {
+                debug!("evicted {} idle connection(s) for {:?}", idle.len(), key);
+                for _ in &idle {
+                    inner.evicted(key, reason);
+                }
+            }
+        }
+    }
+
     fn reuse(&self, key: &Key, value: T) -> Pooled<T> {
         debug!("reuse idle connection for {:?}", key);
         // TODO: unhack this
@@ -259,6 +548,7 @@ impl<T: Poolable> Pool<T> {
         let mut pool_ref = WeakOpt::none();
         if !value.can_share() {
             if let Some(ref enabled) = self.inner {
+                enabled.lock().unwrap().checked_out(key);
                 pool_ref = WeakOpt::downgrade(enabled);
             }
         }
@@ -276,6 +566,7 @@ impl<T: Poolable> Pool<T> {
 struct IdlePopper<'a, T> {
     key: &'a Key,
     list: &'a mut Vec<Idle<T>>,
+    on_evict: Option<&'a OnEvict>,
 }
 
 impl<'a, T: Poolable + 'a> IdlePopper<'a, T> {
@@ -285,6 +576,7 @@ impl<'a, T: Poolable + 'a> IdlePopper<'a, T> {
             // timeout, simply drop it and keep looking...
             if !entry.value.is_open() {
                 trace!("removing closed connection for {:?}", self.key);
+                self.evicted(entry.value.close_reason());
                 continue;
             }
             // TODO: Actually, since the `idle` list is pushed to the end always,
@@ -295,6 +587,7 @@ impl<'a, T: Poolable + 'a> IdlePopper<'a, T> {
             // whole list...
             if expiration.expires(entry.idle_at) {
                 trace!("removing expired connection for {:?}", self.key);
+                self.evicted(EvictionReason::IdleTimeout);
                 continue;
             }
 
@@ -318,6 +611,12 @@ impl<'a, T: Poolable + 'a> IdlePopper<'a, T> {
 
         None
     }
+
+    fn evicted(&self, reason: EvictionReason) {
+        if let Some(on_evict) = self.on_evict {
+            on_evict.call(self.key, reason);
+        }
+    }
 }
 
 impl<T: Poolable> PoolInner<T> {
@@ -370,6 +669,7 @@ impl<T: Poolable> PoolInner<T> {
                     let idle_list = self.idle.entry(key.clone()).or_insert_with(Vec::new);
                     if self.max_idle_per_host <= idle_list.len() {
                         trace!("max idle per host for {:?}, dropping connection", key);
+                        self.evicted(&key, EvictionReason::MaxIdlePerHost);
                         return;
                     }
 
@@ -400,6 +700,22 @@ impl<T: Poolable> PoolInner<T> {
         self.waiters.remove(key);
     }
 
+    /// An HTTP/1 `Connecting` task is complete, freeing its slot.
+    ///
+    /// If it `failed`, the waiters that were not allowed to connect on their
+    /// own are canceled, so they can try again.
+    fn connected_h1(&mut self, key: &Key, failed: bool) {
+        if let Some(count) = self.connecting_h1.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.connecting_h1.remove(key);
+            }
+        }
+        if failed {
+            self.waiters.remove(key);
+        }
+    }
+
     #[cfg(feature = "runtime")]
     fn spawn_idle_interval(&mut self, pool_ref: &Arc<Mutex<PoolInner<T>>>) {
         let (dur, rx) = {
@@ -427,6 +743,25 @@ impl<T: Poolable> PoolInner<T> {
 }
 
 impl<T> PoolInner<T> {
+    fn evicted(&self, key: &Key, reason: EvictionReason) {
+        if let Some(ref on_evict) = self.on_evict {
+            on_evict.call(key, reason);
+        }
+    }
+
+    fn checked_out(&mut self, key: &Key) {
+        *self.active.entry(key.clone()).or_insert(0) += 1;
+    }
+
+    fn checked_in(&mut self, key: &Key) {
+        if let Some(count) = self.active.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.active.remove(key);
+            }
+        }
+    }
+
     /// Any `FutureResponse`s that were created will have made a `Checkout`,
     /// and possibly inserted into the pool that it is waiting for an idle
     /// connection. If a user ever dropped that future, we need to clean out
@@ -452,16 +787,25 @@ impl<T: Poolable> PoolInner<T> {
         let now = Instant::now();
         //self.last_idle_check_at = now;
 
+        let on_evict = self.on_evict.as_ref();
+        let evicted = |key: &Key, reason| {
+            if let Some(on_evict) = on_evict {
+                on_evict.call(key, reason);
+            }
+        };
+
         self.idle.retain(|key, values| {
             values.retain(|entry| {
                 if !entry.value.is_open() {
                     trace!("idle interval evicting closed for {:?}", key);
+                    evicted(key, entry.value.close_reason());
                     return false;
                 }
 
                 // Avoid `Instant::sub` to avoid issues like rust-lang/rust#86470.
                 if now.saturating_duration_since(entry.idle_at) > dur {
                     trace!("idle interval evicting expired for {:?}", key);
+                    evicted(key, EvictionReason::IdleTimeout);
                     return false;
                 }
 
@@ -475,10 +819,23 @@ impl<T: Poolable> PoolInner<T> {
     }
 }
 
+impl<T> Drop for PoolInner<T> {
+    fn drop(&mut self) {
+        if let Some(ref on_evict) = self.on_evict {
+            for (key, values) in &self.idle {
+                for _ in values {
+                    on_evict.call(key, EvictionReason::PoolCleared);
+                }
+            }
+        }
+    }
+}
+
 impl<T> Clone for Pool<T> {
     fn clone(&self) -> Pool<T> {
         Pool {
             inner: self.inner.clone(),
+            limiter: self.limiter.clone(),
         }
     }
 }
@@ -529,11 +886,18 @@ impl<T: Poolable> Drop for Pooled<T> {
             if !value.is_open() {
                 // If we *already* know the connection is done here,
                 // it shouldn't be re-inserted back into the pool.
+                if let Some(pool) = self.pool.upgrade() {
+                    if let Ok(mut inner) = pool.lock() {
+                        inner.checked_in(&self.key);
+                        inner.evicted(&self.key, value.close_reason());
+                    }
+                }
                 return;
             }
 
             if let Some(pool) = self.pool.upgrade() {
                 if let Ok(mut inner) = pool.lock() {
+                    inner.checked_in(&self.key);
                     inner.put(self.key.clone(), value, &pool);
                 }
             } else if !value.can_share() {
@@ -575,6 +939,18 @@ impl fmt::Display for CheckoutIsClosedError {
     }
 }
 
+/// A connect was skipped, since enough to the same host are in progress.
+#[derive(Debug)]
+pub(super) struct ConnectingLimitError;
+
+impl StdError for ConnectingLimitError {}
+
+impl fmt::Display for ConnectingLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("connections to host already in progress")
+    }
+}
+
 impl<T: Poolable> Checkout<T> {
     fn poll_waiter(
         &mut self,
@@ -606,7 +982,8 @@ impl<T: Poolable> Checkout<T> {
 
     fn checkout(&mut self, cx: &mut task::Context<'_>) -> Option<Pooled<T>> {
         let entry = {
-            let mut inner = self.pool.inner.as_ref()?.lock().unwrap();
+            let mut guard = self.pool.inner.as_ref()?.lock().unwrap();
+            let inner = &mut *guard;
             let expiration = Expiration::new(inner.timeout);
             let maybe_entry = inner.idle.get_mut(&self.key).and_then(|list| {
                 trace!("take? {:?}: expiration = {:?}", self.key, expiration.0);
@@ -616,6 +993,7 @@ impl<T: Poolable> Checkout<T> {
                     let popper = IdlePopper {
                         key: &self.key,
                         list,
+                        on_evict: inner.on_evict.as_ref(),
                     };
                     popper.pop(&expiration)
                 }
@@ -690,10 +1068,22 @@ impl<T> Drop for Checkout<T> {
 pub(super) struct Connecting<T: Poolable> {
     key: Key,
     pool: WeakOpt<Mutex<PoolInner<T>>>,
+    /// Whether this holds one of the limited HTTP/1 connecting slots,
+    /// instead of the HTTP/2 lock.
+    h1_slot: bool,
 }
 
 impl<T: Poolable> Connecting<T> {
-    pub(super) fn alpn_h2(self, pool: &Pool<T>) -> Option<Self> {
+    pub(super) fn alpn_h2(mut self, pool: &Pool<T>) -> Option<Self> {
+        if self.h1_slot {
+            // Waiters are served by the shared HTTP/2 connection instead.
+            if let Some(inner) = self.pool.upgrade() {
+                if let Ok(mut inner) = inner.lock() {
+                    inner.connected_h1(&self.key, false);
+                }
+            }
+            self.pool = WeakOpt::none();
+        }
         debug_assert!(
             self.pool.0.is_none(),
             "Connecting::alpn_h2 but already Http2"
@@ -708,12 +1098,128 @@ impl<T: Poolable> Drop for Connecting<T> {
         if let Some(pool) = self.pool.upgrade() {
             // No need to panic on drop, that could abort!
             if let Ok(mut inner) = pool.lock() {
-                inner.connected(&self.key);
+                if self.h1_slot {
+                    inner.connected_h1(&self.key, true);
+                } else {
+                    inner.connected(&self.key);
+                }
             }
         }
     }
 }
 
+/// Limits on the number of requests in flight, per host and in total.
+struct Limiter {
+    state: Mutex<LimiterState>,
+    max_per_host: usize,
+    max_total: usize,
+    order: QueueOrder,
+    #[cfg(feature = "runtime")]
+    wait_timeout: Option<Duration>,
+}
+
+struct LimiterState {
+    total: usize,
+    per_host: HashMap<Key, usize>,
+    // Requests waiting for a slot, in the order they started waiting. A
+    // waiter is sent its `Permit` once there is room for its host.
+    waiters: VecDeque<(Key, oneshot::Sender<Permit>)>,
+}
+
+/// A request slot, freed on drop.
+pub(super) struct Permit {
+    key: Key,
+    // None once the slot has been freed.
+    limiter: Option<Arc<Limiter>>,
+}
+
+impl Limiter {
+    fn try_acquire(self: &Arc<Self>, key: &Key) -> Result<Permit, oneshot::Receiver<Permit>> {
+        let mut state = self.state.lock().unwrap();
+        if self.has_room(&state, key) {
+            return Ok(self.take(&mut state, key.clone()));
+        }
+
+        trace!("request limit reached for {:?}, waiting", key);
+        // Drop any waiters that gave up, so they don't pile up.
+        state.waiters.retain(|(_, tx)| !tx.is_canceled());
+        let (tx, rx) = oneshot::channel();
+        state.waiters.push_back((key.clone(), tx));
+        Err(rx)
+    }
+
+    fn has_room(&self, state: &LimiterState, key: &Key) -> bool {
+        state.total < self.max_total
+            && state.per_host.get(key).copied().unwrap_or(0) < self.max_per_host
+    }
+
+    fn take(self: &Arc<Self>, state: &mut LimiterState, key: Key) -> Permit {
+        state.total += 1;
+        *state.per_host.entry(key.clone()).or_insert(0) += 1;
+        Permit {
+            key,
+            limiter: Some(self.clone()),
+        }
+    }
+
+    fn release(self: &Arc<Self>, key: &Key) {
+        let mut state = self.state.lock().unwrap();
+        state.free(key);
+
+        loop {
+            let next = {
+                let mut eligible = state
+                    .waiters
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (key, tx))| !tx.is_canceled() && self.has_room(&state, key))
+                    .map(|(idx, _)| idx);
+                match self.order {
+                    QueueOrder::Fifo => eligible.next(),
+                    QueueOrder::Lifo => eligible.last(),
+                }
+            };
+            let (key, tx) = match next.and_then(|idx| state.waiters.remove(idx)) {
+                Some(waiter) => waiter,
+                None => return,
+            };
+            let permit = self.take(&mut state, key);
+            if let Err(mut permit) = tx.send(permit) {
+                // The waiter gave up in the meantime. Free the slot here,
+                // since dropping the permit would lock again.
+                permit.limiter = None;
+                state.free(&permit.key);
+            }
+        }
+    }
+}
+
+impl LimiterState {
+    fn free(&mut self, key: &Key) {
+        self.total -= 1;
+        if let Some(count) = self.per_host.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.per_host.remove(key);
+            }
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.limiter.take() {
+            limiter.release(&self.key);
+        }
+    }
+}
+
+impl fmt::Debug for Permit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Permit").field("key", &self.key).finish()
+    }
+}
+
 struct Expiration(Option<Duration>);
 
 impl Expiration {
@@ -793,7 +1299,10 @@ mod tests {
     use std::task::Poll;
     use std::time::Duration;
 
-    use super::{Connecting, Key, Pool, Poolable, Reservation, WeakOpt};
+    use super::{
+        Connecting, EvictionReason, Key, OnEvict, Pool, Poolable, QueueOrder, Reservation, Ver,
+        WeakOpt,
+    };
     use crate::common::{exec::Exec, task, Future, Pin};
 
     /// Test unique reservations.
@@ -818,6 +1327,7 @@ mod tests {
         Connecting {
             key,
             pool: WeakOpt::none(),
+            h1_slot: false,
         }
     }
 
@@ -834,6 +1344,13 @@ mod tests {
             super::Config {
                 idle_timeout: Some(Duration::from_millis(100)),
                 max_idle_per_host: max_idle,
+                max_connecting_per_host: usize::MAX,
+                max_requests_per_host: usize::MAX,
+                max_requests: usize::MAX,
+                queue_order: QueueOrder::Fifo,
+                #[cfg(feature = "runtime")]
+                wait_timeout: None,
+                on_evict: None,
             },
             &Exec::Default,
         );
@@ -926,6 +1443,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pool_evict() {
+        let pool = pool_no_timer();
+        let key = host_key("foo");
+        let other = host_key("bar");
+
+        pool.pooled(c(key.clone()), Uniq(41));
+        pool.pooled(c(other.clone()), Uniq(5));
+        pool.evict(&key, EvictionReason::Misdirected);
+
+        assert!(!pool.locked().idle.contains_key(&key));
+        assert_eq!(
+            pool.locked().idle.get(&other).map(|entries| entries.len()),
+            Some(1)
+        );
+    }
+
     #[cfg(feature = "runtime")]
     #[tokio::test]
     async fn test_pool_timer_removes_expired() {
@@ -936,6 +1470,13 @@ mod tests {
             super::Config {
                 idle_timeout: Some(Duration::from_millis(10)),
                 max_idle_per_host: std::usize::MAX,
+                max_connecting_per_host: usize::MAX,
+                max_requests_per_host: usize::MAX,
+                max_requests: usize::MAX,
+                queue_order: QueueOrder::Fifo,
+                #[cfg(feature = "runtime")]
+                wait_timeout: None,
+                on_evict: None,
             },
             &Exec::Default,
         );
@@ -981,6 +1522,53 @@ mod tests {
         assert_eq!(*checkout.await.unwrap(), Uniq(41));
     }
 
+    #[tokio::test]
+    async fn test_pool_max_connecting_per_host() {
+        let pool = Pool::<Uniq<i32>>::new(
+            super::Config {
+                idle_timeout: Some(Duration::from_millis(100)),
+                max_idle_per_host: usize::MAX,
+                max_connecting_per_host: 2,
+                max_requests_per_host: usize::MAX,
+                max_requests: usize::MAX,
+                queue_order: QueueOrder::Fifo,
+                #[cfg(feature = "runtime")]
+                wait_timeout: None,
+                on_evict: None,
+            },
+            &Exec::Default,
+        );
+        pool.no_timer();
+        let key = host_key("foo");
+
+        let first = pool.connecting(&key, Ver::Auto).expect("first slot");
+        let second = pool.connecting(&key, Ver::Auto).expect("second slot");
+        assert!(pool.connecting(&key, Ver::Auto).is_none());
+        assert!(pool.connecting(&host_key("bar"), Ver::Auto).is_some());
+
+        // a request over the limit waits for a connection instead
+        let mut checkout = pool.checkout(key.clone());
+        PollOnce(&mut checkout).await;
+        assert_eq!(pool.locked().waiters.get(&key).unwrap().len(), 1);
+
+        // a failed connect frees its slot, and cancels the waiters
+        drop(second);
+        assert!(checkout.await.unwrap_err().is_canceled());
+        let third = pool.connecting(&key, Ver::Auto).expect("freed slot");
+
+        // a successful one frees its slot, and keeps them waiting
+        let mut checkout = pool.checkout(key.clone());
+        PollOnce(&mut checkout).await;
+        let pooled = pool.pooled(first, Uniq(41));
+        assert_eq!(pool.locked().waiters.get(&key).unwrap().len(), 1);
+        assert_eq!(pool.locked().connecting_h1[&key], 1);
+
+        drop(pooled);
+        assert_eq!(*checkout.await.unwrap(), Uniq(41));
+        drop(third);
+        assert!(pool.locked().connecting_h1.is_empty());
+    }
+
     #[tokio::test]
     async fn test_pool_checkout_drop_cleans_up_waiters() {
         let pool = pool_no_timer::<Uniq<i32>>();
@@ -1041,4 +1629,192 @@ mod tests {
 
         assert!(!pool.locked().idle.contains_key(&key));
     }
+
+    #[test]
+    fn eviction_callback_reasons() {
+        use std::sync::{Arc, Mutex};
+
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let record = evicted.clone();
+        let pool = Pool::new(
+            super::Config {
+                idle_timeout: Some(Duration::from_millis(100)),
+                max_idle_per_host: 1,
+                max_connecting_per_host: usize::MAX,
+                max_requests_per_host: usize::MAX,
+                max_requests: usize::MAX,
+                queue_order: QueueOrder::Fifo,
+                #[cfg(feature = "runtime")]
+                wait_timeout: None,
+                on_evict: Some(OnEvict::new(move |host, reason| {
+                    record.lock().unwrap().push((host.to_string(), reason));
+                })),
+            },
+            &Exec::Default,
+        );
+        pool.no_timer();
+        let key = host_key("foo");
+        let conn = |closed| CanClose { val: 1, closed };
+
+        pool.pooled(c(key.clone()), conn(false));
+        pool.pooled(c(key.clone()), conn(false));
+        pool.pooled(c(key.clone()), conn(true));
+        pool.evict(&key, EvictionReason::Misdirected);
+        pool.pooled(c(key.clone()), conn(false));
+        drop(pool);
+
+        assert_eq!(
+            *evicted.lock().unwrap(),
+            [
+                ("foo".to_owned(), EvictionReason::MaxIdlePerHost),
+                ("foo".to_owned(), EvictionReason::ServerClosed),
+                ("foo".to_owned(), EvictionReason::Misdirected),
+                ("foo".to_owned(), EvictionReason::PoolCleared),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pool_stats_counts_idle_and_active() {
+        let pool = pool_no_timer();
+        let (foo, bar) = (host_key("foo"), host_key("bar"));
+        let counts = |pool: &Pool<Uniq<i32>>| {
+            pool.stats()
+                .hosts()
+                .iter()
+                .map(|host| (host.authority().to_string(), host.idle(), host.active()))
+                .collect::<Vec<_>>()
+        };
+
+        let foo1 = pool.pooled(c(foo.clone()), Uniq(1));
+        let foo2 = pool.pooled(c(foo.clone()), Uniq(2));
+        drop(pool.pooled(c(bar.clone()), Uniq(3)));
+        assert_eq!(
+            counts(&pool),
+            [("bar".to_owned(), 1, 0), ("foo".to_owned(), 0, 2)]
+        );
+
+        drop(foo1);
+        let bar1 = pool.checkout(bar).await.unwrap();
+        assert_eq!(
+            counts(&pool),
+            [("bar".to_owned(), 0, 1), ("foo".to_owned(), 1, 1)]
+        );
+        let stats = pool.stats();
+        assert_eq!((stats.idle(), stats.active()), (1, 2));
+
+        drop((foo2, bar1));
+        assert_eq!(
+            counts(&pool),
+            [("bar".to_owned(), 1, 0), ("foo".to_owned(), 2, 0)]
+        );
+    }
+
+    fn pool_limited(per_host: usize, total: usize, order: QueueOrder) -> Pool<Uniq<i32>> {
+        let pool = Pool::new(
+            super::Config {
+                idle_timeout: Some(Duration::from_millis(100)),
+                max_idle_per_host: usize::MAX,
+                max_connecting_per_host: usize::MAX,
+                max_requests_per_host: per_host,
+                max_requests: total,
+                queue_order: order,
+                #[cfg(feature = "runtime")]
+                wait_timeout: Some(Duration::from_millis(10)),
+                on_evict: None,
+            },
+            &Exec::Default,
+        );
+        pool.no_timer();
+        pool
+    }
+
+    #[tokio::test]
+    async fn request_limits_per_host_and_total() {
+        let pool = pool_limited(1, 2, QueueOrder::Fifo);
+        let (foo, bar, baz) = (host_key("foo"), host_key("bar"), host_key("baz"));
+
+        let foo1 = pool.acquire(&foo).await.unwrap().expect("limited");
+        let bar1 = pool.acquire(&bar).await.unwrap().expect("limited");
+
+        // both limits are reached
+        let mut foo2 = Box::pin(pool.acquire(&foo));
+        let mut baz1 = Box::pin(pool.acquire(&baz));
+        futures_util::future::poll_fn(|cx| {
+            assert!(foo2.as_mut().poll(cx).is_pending());
+            assert!(baz1.as_mut().poll(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+
+        // freeing a slot of bar lets the next request through that fits,
+        // skipping foo which is still at its limit
+        drop(bar1);
+        let baz1 = baz1.await.unwrap().expect("limited");
+        drop(foo1);
+        let foo2 = foo2.await.unwrap().expect("limited");
+        drop((baz1, foo2));
+
+        let inner = pool.limiter.as_ref().unwrap().state.lock().unwrap();
+        assert_eq!(inner.total, 0);
+        assert!(inner.per_host.is_empty());
+    }
+
+    #[tokio::test]
+    async fn request_limit_queue_order() {
+        async fn order(order: QueueOrder) -> Vec<&'static str> {
+            let pool = pool_limited(usize::MAX, 1, order);
+            let first = pool.acquire(&host_key("first")).await.unwrap();
+
+            let mut waiting = Vec::new();
+            for name in ["a", "b", "c"] {
+                let mut acquire = Box::pin(pool.acquire(&host_key(name)));
+                futures_util::future::poll_fn(|cx| {
+                    assert!(acquire.as_mut().poll(cx).is_pending());
+                    Poll::Ready(())
+                })
+                .await;
+                waiting.push((name, acquire));
+            }
+
+            drop(first);
+            let mut served = Vec::new();
+            while !waiting.is_empty() {
+                let mut ready = None;
+                futures_util::future::poll_fn(|cx| {
+                    for (idx, (_, acquire)) in waiting.iter_mut().enumerate() {
+                        if let Poll::Ready(permit) = acquire.as_mut().poll(cx) {
+                            ready = Some((idx, permit.unwrap()));
+                            break;
+                        }
+                    }
+                    Poll::Ready(())
+                })
+                .await;
+                let (idx, permit) = ready.expect("one waiter is served");
+                served.push(waiting.remove(idx).0);
+                drop(permit);
+            }
+            served
+        }
+
+        assert_eq!(order(QueueOrder::Fifo).await, ["a", "b", "c"]);
+        assert_eq!(order(QueueOrder::Lifo).await, ["c", "b", "a"]);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn request_limit_wait_timeout() {
+        let pool = pool_limited(1, usize::MAX, QueueOrder::Fifo);
+        let key = host_key("foo");
+
+        let permit = pool.acquire(&key).await.unwrap();
+        let err = pool.acquire(&key).await.unwrap_err();
+        assert!(err.is_pool_exhausted());
+        assert!(err.is_timeout());
+
+        // the waiter that gave up doesn't hold on to the freed slot
+        drop(permit);
+        assert!(pool.acquire(&key).await.unwrap().is_some());
+    }
 }
@@ -6,6 +6,7 @@ use std::error::Error as StdError;
 use std::future::Future;
 use std::marker::PhantomData;
 
+use tower_layer::{Identity, Layer, Stack};
 use tracing::debug;
 
 use super::conn::{Builder, SendRequest};
@@ -20,10 +21,15 @@ use crate::{
 /// This accepts a `fluxio::client::conn::Builder` and provides
 /// a `MakeService` implementation to create connections from some
 /// target `T`.
+///
+/// The `SendRequest` of each connection is wrapped with the layers added
+/// with [`with_layer`](Connect::with_layer), such as for timeouts, retries
+/// or authentication.
 #[derive(Debug)]
-pub struct Connect<C, B, T> {
+pub struct Connect<C, B, T, L = Identity> {
     inner: C,
     builder: Builder,
+    layer: L,
     _pd: PhantomData<fn(T, B)>,
 }
 
@@ -34,12 +40,48 @@ impl<C, B, T> Connect<C, B, T> {
         Self {
             inner,
             builder,
+            layer: Identity::new(),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<C, B, T, L> Connect<C, B, T, L> {
+    /// Wrap the service of each connection with a [`Layer`].
+    ///
+    /// Layers added later wrap the ones added before, so they see requests
+    /// first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "tcp")]
+    /// # fn doc() {
+    /// use fluxio::client::conn::Builder;
+    /// use fluxio::client::connect::HttpConnector;
+    /// use fluxio::client::service::Connect;
+    /// use fluxio::{Body, Request};
+    /// use tower::util::MapRequestLayer;
+    ///
+    /// let connect = Connect::<_, Body, http::Uri>::new(HttpConnector::new(), Builder::new())
+    ///     .with_layer(MapRequestLayer::new(|mut req: Request<Body>| {
+    ///         req.headers_mut()
+    ///             .insert("authorization", "Bearer token".parse().unwrap());
+    ///         req
+    ///     }));
+    /// # }
+    /// ```
+    pub fn with_layer<L2>(self, layer: L2) -> Connect<C, B, T, Stack<L, L2>> {
+        Connect {
+            inner: self.inner,
+            builder: self.builder,
+            layer: Stack::new(self.layer, layer),
             _pd: PhantomData,
         }
     }
 }
 
-impl<C, B, T> Service<T> for Connect<C, B, T>
+impl<C, B, T, L> Service<T> for Connect<C, B, T, L>
 where
     C: MakeConnection<T>,
     C::Connection: Unpin + Send + 'static,
@@ -48,8 +90,9 @@ where
     B: HttpBody + Unpin + Send + 'static,
     B::Data: Send + Unpin,
     B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    L: Layer<SendRequest<B>> + Clone + Send + 'static,
 {
-    type Response = SendRequest<B>;
+    type Response = L::Service;
     type Error = crate::Error;
     type Future =
         Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
@@ -62,6 +105,7 @@ where
 
     fn call(&mut self, req: T) -> Self::Future {
         let builder = self.builder.clone();
+        let layer = self.layer.clone();
         let io = self.inner.make_connection(req);
 
         let fut = async move {
@@ -73,7 +117,7 @@ where
                                 debug!("connection error: {:?}", e);
                             }
                         });
-                        Ok(sr)
+                        Ok(layer.layer(sr))
                     }
                     Err(e) => Err(e),
                 },
@@ -87,3 +131,59 @@ where
         Box::pin(fut)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::rt::Executor;
+    use crate::Body;
+
+    #[derive(Clone)]
+    struct TokioExecutor;
+
+    impl<F> Executor<F> for TokioExecutor
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        fn execute(&self, fut: F) {
+            tokio::spawn(fut);
+        }
+    }
+
+    #[derive(Clone)]
+    struct CountLayer(Arc<AtomicUsize>);
+
+    struct Counted<S>(S);
+
+    impl<S> Layer<S> for CountLayer {
+        type Service = Counted<S>;
+
+        fn layer(&self, inner: S) -> Counted<S> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Counted(inner)
+        }
+    }
+
+    #[tokio::test]
+    async fn with_layer_wraps_each_connection() {
+        let connector = tower::service_fn(|_: ()| async {
+            let (io, _server) = tokio::io::duplex(64);
+            Ok::<_, std::io::Error>(io)
+        });
+        let mut builder = Builder::new();
+        builder.executor(TokioExecutor);
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut connect = Connect::<_, Body, ()>::new(connector, builder)
+            .with_layer(CountLayer(count.clone()))
+            .with_layer(CountLayer(count.clone()));
+
+        for i in 1..=3 {
+            let Counted(Counted(_tx)) = connect.call(()).await.unwrap();
+            assert_eq!(count.load(Ordering::SeqCst), i * 2);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+
+#[cfg(feature = "runtime")]
+use tokio::time::Sleep;
+#[cfg(feature = "runtime")]
+use tracing::debug;
+
+use crate::body::Body;
+use crate::common::{task, Future, Pin, Poll};
+use crate::Response;
+
+/// The timeouts of the responses received on a connection.
+///
+/// These need a timer, so are only set with the `runtime` feature.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Timeouts {
+    /// How long to wait for the head of a response.
+    #[cfg(feature = "runtime")]
+    pub(crate) header: Option<Duration>,
+    /// How long a response body can go without receiving a frame.
+    #[cfg(feature = "runtime")]
+    pub(crate) body_idle: Option<Duration>,
+}
+
+impl Timeouts {
+    /// Wraps a future waiting for the head of a response.
+    pub(crate) fn response_head<F>(&self, inner: F) -> ResponseHead<F> {
+        ResponseHead {
+            inner,
+            #[cfg(feature = "runtime")]
+            sleep: self
+                .header
+                .map(|timeout| Box::pin(tokio::time::sleep(timeout))),
+        }
+    }
+
+    /// Applies the body idle timeout to a received response.
+    pub(crate) fn response(
+        &self,
+        #[cfg_attr(not(feature = "runtime"), allow(unused_mut))] mut res: Response<Body>,
+    ) -> Response<Body> {
+        #[cfg(feature = "runtime")]
+        if let Some(timeout) = self.body_idle {
+            res.body_mut().idle_timeout(timeout);
+        }
+        res
+    }
+}
+
+/// A future failing if the head of a response doesn't arrive in time.
+///
+/// Resolves to `Err` if the timeout elapsed first, or the output of the inner
+/// future otherwise.
+pub(crate) struct ResponseHead<F> {
+    inner: F,
+    #[cfg(feature = "runtime")]
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl<F> Future for ResponseHead<F>
+where
+    F: Future + Unpin,
+{
+    type Output = Result<F::Output, crate::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(out) = Pin::new(&mut self.inner).poll(cx) {
+            return Poll::Ready(Ok(out));
+        }
+        #[cfg(feature = "runtime")]
+        if let Some(ref mut sleep) = self.sleep {
+            ready!(sleep.as_mut().poll(cx));
+            debug!("response header timeout elapsed");
+            return Poll::Ready(Err(crate::Error::new_response_header_timeout()));
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(all(test, feature = "runtime", feature = "http1"))]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::body::HttpBody;
+    use crate::client::conn::Builder;
+    use crate::{Body, Request};
+
+    #[tokio::test]
+    async fn response_header_timeout() {
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (mut tx, conn) = Builder::new()
+            .response_header_timeout(Duration::from_millis(10))
+            .handshake::<_, Body>(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+
+        let err = tx
+            .send_request(Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert!(err.is_response_header_timeout(), "{:?}", err);
+        assert!(err.is_timeout());
+        assert!(!err.is_body_idle_timeout());
+
+        let mut buf = [0; 1024];
+        let n = server.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"GET / HTTP/1.1\r\n"));
+    }
+
+    #[tokio::test]
+    async fn body_idle_timeout() {
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (mut tx, conn) = Builder::new()
+            .response_header_timeout(Duration::from_secs(1))
+            .body_idle_timeout(Duration::from_millis(50))
+            .handshake::<_, Body>(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+
+        let res = tx.send_request(Request::new(Body::empty()));
+        let mut buf = [0; 1024];
+        let _ = server.read(&mut buf).await.unwrap();
+        server
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\nhello")
+            .await
+            .unwrap();
+
+        // the header timeout doesn't apply to the body
+        let mut body = res.await.unwrap().into_body();
+        assert_eq!(body.data().await.unwrap().unwrap(), "hello");
+        let err = body.data().await.unwrap().unwrap_err();
+        assert!(err.is_body_idle_timeout(), "{:?}", err);
+        assert!(err.is_timeout());
+    }
+}
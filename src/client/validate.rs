@@ -0,0 +1,247 @@
+use std::fmt;
+use std::sync::Arc;
+
+use http::header;
+use http::{Method, StatusCode};
+use tracing::debug;
+
+use crate::proto::MessageHead;
+
+/// A requirement of RFC 9110 or RFC 9112 that a received response broke.
+///
+/// See [`Builder::http1_strict_response_validation`](super::conn::Builder::http1_strict_response_validation).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResponseViolation {
+    /// The status code is outside of `100..=599` (RFC 9110, section 15).
+    StatusOutOfRange,
+    /// A 1xx or 204 response has a `Content-Length` header (RFC 9110,
+    /// section 8.6).
+    ContentLengthWithoutContent,
+    /// A 1xx or 204 response has a `Transfer-Encoding` header (RFC 9112,
+    /// section 6.1).
+    TransferEncodingWithoutContent,
+    /// A response has both `Content-Length` and `Transfer-Encoding` headers
+    /// (RFC 9112, section 6.1).
+    ContentLengthWithTransferEncoding,
+    /// A response has several `Content-Length` values that differ, or one
+    /// that isn't a number (RFC 9110, section 8.6).
+    InvalidContentLength,
+    /// A 2xx response to a `CONNECT` request has a `Content-Length` or
+    /// `Transfer-Encoding` header (RFC 9110, section 9.3.6).
+    FramingOnConnect,
+    /// More bytes followed the head of a 204 or 304 response, which cannot
+    /// have content (RFC 9110, sections 15.3.5 and 15.4.5).
+    ContentOnNoContent,
+    /// A header line isn't a valid field, and was skipped (RFC 9112,
+    /// section 5). Only reported with
+    /// [`http1_ignore_invalid_headers_in_responses`](super::conn::Builder::http1_ignore_invalid_headers_in_responses),
+    /// else such a response fails the request.
+    InvalidHeader,
+}
+
+impl ResponseViolation {
+    /// A short, machine-readable name of this violation, like
+    /// `"status_out_of_range"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseViolation::StatusOutOfRange => "status_out_of_range",
+            ResponseViolation::ContentLengthWithoutContent => "content_length_without_content",
+            ResponseViolation::TransferEncodingWithoutContent => {
+                "transfer_encoding_without_content"
+            }
+            ResponseViolation::ContentLengthWithTransferEncoding => {
+                "content_length_with_transfer_encoding"
+            }
+            ResponseViolation::InvalidContentLength => "invalid_content_length",
+            ResponseViolation::FramingOnConnect => "framing_on_connect",
+            ResponseViolation::ContentOnNoContent => "content_on_no_content",
+            ResponseViolation::InvalidHeader => "invalid_header",
+        }
+    }
+}
+
+impl fmt::Display for ResponseViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+type ViolationCallback = dyn Fn(StatusCode, ResponseViolation) + Send + Sync;
+
+/// The callback told about violations of received responses.
+#[derive(Clone)]
+pub(crate) struct OnViolation(Arc<ViolationCallback>);
+
+impl OnViolation {
+    pub(crate) fn new<F>(f: F) -> OnViolation
+    where
+        F: Fn(StatusCode, ResponseViolation) + Send + Sync + 'static,
+    {
+        OnViolation(Arc::new(f))
+    }
+
+    fn report(&self, status: StatusCode, violation: ResponseViolation) {
+        debug!("response {} violates: {}", status, violation);
+        (self.0)(status, violation);
+    }
+
+    /// Checks a received response head, before its body is read.
+    ///
+    /// `trailing` is whether more bytes were already received after the
+    /// head, and `skipped_headers` how many invalid header lines of it were
+    /// skipped.
+    pub(crate) fn check(
+        &self,
+        head: &MessageHead<StatusCode>,
+        method: Option<&Method>,
+        trailing: bool,
+        skipped_headers: usize,
+    ) {
+        let status = head.subject;
+        let headers = &head.headers;
+        let code = status.as_u16();
+        let has_length = headers.contains_key(header::CONTENT_LENGTH);
+        let has_encoding = headers.contains_key(header::TRANSFER_ENCODING);
+
+        if code > 599 {
+            self.report(status, ResponseViolation::StatusOutOfRange);
+        }
+        if status.is_informational() || code == 204 {
+            if has_length {
+                self.report(status, ResponseViolation::ContentLengthWithoutContent);
+            }
+            if has_encoding {
+                self.report(status, ResponseViolation::TransferEncodingWithoutContent);
+            }
+        }
+        if has_length && has_encoding {
+            self.report(status, ResponseViolation::ContentLengthWithTransferEncoding);
+        }
+        if has_length && crate::headers::content_length_parse_all(headers).is_none() {
+            self.report(status, ResponseViolation::InvalidContentLength);
+        }
+        if method == Some(&Method::CONNECT) && status.is_success() && (has_length || has_encoding) {
+            self.report(status, ResponseViolation::FramingOnConnect);
+        }
+        if (code == 204 || code == 304) && trailing {
+            self.report(status, ResponseViolation::ContentOnNoContent);
+        }
+        for _ in 0..skipped_headers {
+            self.report(status, ResponseViolation::InvalidHeader);
+        }
+    }
+}
+
+impl fmt::Debug for OnViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnViolation").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn check(
+        head: &MessageHead<StatusCode>,
+        method: Option<&Method>,
+        trailing: bool,
+    ) -> Vec<ResponseViolation> {
+        check_skipped(head, method, trailing, 0)
+    }
+
+    fn check_skipped(
+        head: &MessageHead<StatusCode>,
+        method: Option<&Method>,
+        trailing: bool,
+        skipped_headers: usize,
+    ) -> Vec<ResponseViolation> {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let on_violation = {
+            let seen = seen.clone();
+            OnViolation::new(move |_, violation| seen.lock().unwrap().push(violation))
+        };
+        on_violation.check(head, method, trailing, skipped_headers);
+        let seen = seen.lock().unwrap().clone();
+        seen
+    }
+
+    fn head(status: u16, headers: &[(&str, &str)]) -> MessageHead<StatusCode> {
+        let mut head = MessageHead::default();
+        head.subject = StatusCode::from_u16(status).unwrap();
+        for (name, value) in headers {
+            head.headers.append(
+                header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        head
+    }
+
+    #[test]
+    fn valid_responses() {
+        let get = Some(&Method::GET);
+        assert_eq!(check(&head(200, &[("content-length", "5")]), get, true), []);
+        assert_eq!(
+            check(&head(200, &[("transfer-encoding", "chunked")]), get, false),
+            []
+        );
+        assert_eq!(check(&head(204, &[]), get, false), []);
+        assert_eq!(
+            check(&head(304, &[("content-length", "5")]), get, false),
+            []
+        );
+        assert_eq!(check(&head(200, &[]), Some(&Method::CONNECT), false), []);
+    }
+
+    #[test]
+    fn violations() {
+        use ResponseViolation::*;
+
+        let get = Some(&Method::GET);
+        assert_eq!(check(&head(600, &[]), get, false), [StatusOutOfRange]);
+        assert_eq!(
+            check(&head(204, &[("content-length", "0")]), get, false),
+            [ContentLengthWithoutContent]
+        );
+        assert_eq!(
+            check(&head(103, &[("transfer-encoding", "chunked")]), get, false),
+            [TransferEncodingWithoutContent]
+        );
+        assert_eq!(
+            check(
+                &head(
+                    200,
+                    &[("content-length", "5"), ("transfer-encoding", "chunked")]
+                ),
+                get,
+                false
+            ),
+            [ContentLengthWithTransferEncoding]
+        );
+        assert_eq!(
+            check(
+                &head(200, &[("content-length", "5"), ("content-length", "6")]),
+                get,
+                false
+            ),
+            [InvalidContentLength]
+        );
+        assert_eq!(
+            check(
+                &head(200, &[("content-length", "0")]),
+                Some(&Method::CONNECT),
+                false
+            ),
+            [FramingOnConnect]
+        );
+        assert_eq!(check(&head(304, &[]), get, true), [ContentOnNoContent]);
+        assert_eq!(
+            check_skipped(&head(200, &[]), get, false, 2),
+            [InvalidHeader, InvalidHeader]
+        );
+    }
+}
@@ -1,14 +1,12 @@
 use std::cell::RefCell;
-use std::fmt::{self, Write};
-use std::str;
 use std::time::{Duration, SystemTime};
 
 #[cfg(feature = "http2")]
 use http::header::HeaderValue;
-use httpdate::HttpDate;
 
-// "Sun, 06 Nov 1994 08:49:37 GMT".len()
-pub(crate) const DATE_VALUE_LENGTH: usize = 29;
+use crate::ext::date::{self, HTTP_DATE_LEN};
+
+pub(crate) const DATE_VALUE_LENGTH: usize = HTTP_DATE_LEN;
 
 #[cfg(feature = "http1")]
 pub(crate) fn extend(dst: &mut Vec<u8>) {
@@ -35,7 +33,6 @@ pub(crate) fn update_and_header_value() -> HeaderValue {
 
 struct CachedDate {
     bytes: [u8; DATE_VALUE_LENGTH],
-    pos: usize,
     next_update: SystemTime,
 }
 
@@ -45,7 +42,6 @@ impl CachedDate {
     fn new() -> Self {
         let mut cache = CachedDate {
             bytes: [0; DATE_VALUE_LENGTH],
-            pos: 0,
             next_update: SystemTime::now(),
         };
         cache.update(cache.next_update);
@@ -69,18 +65,8 @@ impl CachedDate {
     }
 
     fn render(&mut self, now: SystemTime) {
-        self.pos = 0;
-        let _ = write!(self, "{}", HttpDate::from(now));
-        debug_assert!(self.pos == DATE_VALUE_LENGTH);
-    }
-}
-
-impl fmt::Write for CachedDate {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        let len = s.len();
-        self.bytes[self.pos..self.pos + len].copy_from_slice(s.as_bytes());
-        self.pos += len;
-        Ok(())
+        let rendered = date::format(now, &mut self.bytes);
+        debug_assert!(rendered.is_some(), "system clock out of HTTP-date range");
     }
 }
 
@@ -0,0 +1,141 @@
+use http::header::{HeaderMap, HOST};
+use http::uri::{Authority, Uri};
+
+/// A set of hosts a server accepts requests for.
+///
+/// Entries are either exact host names (`example.com`), or wildcards
+/// matching any subdomain (`*.example.com`). An entry with a port only
+/// matches requests for that port, otherwise any port is accepted.
+#[derive(Clone, Debug)]
+pub(crate) struct AllowedHosts {
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    host: Pattern,
+    port: Option<u16>,
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    Exact(String),
+    // Stored with the leading dot, as in `.example.com`.
+    Subdomain(String),
+}
+
+impl AllowedHosts {
+    pub(crate) fn new<I, S>(hosts: I) -> AllowedHosts
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let entries = hosts
+            .into_iter()
+            .map(|host| Entry::parse(host.as_ref()))
+            .collect();
+        AllowedHosts { entries }
+    }
+
+    /// Checks the targeted host of a request.
+    ///
+    /// The authority of an absolute-form URI (or HTTP/2 `:authority`) is
+    /// used if present, and the `Host` header otherwise. Requests without
+    /// either are never allowed.
+    pub(crate) fn allows(&self, uri: &Uri, headers: &HeaderMap) -> bool {
+        if let Some(authority) = uri.authority() {
+            return self.allows_authority(authority);
+        }
+        match headers
+            .get(HOST)
+            .map(|host| Authority::try_from(host.as_bytes()))
+        {
+            Some(Ok(authority)) => self.allows_authority(&authority),
+            _ => false,
+        }
+    }
+
+    fn allows_authority(&self, authority: &Authority) -> bool {
+        // userinfo is not allowed in a `Host`
+        if authority.as_str().contains('@') {
+            return false;
+        }
+        let host = authority.host().trim_end_matches('.');
+        let port = authority.port_u16();
+        self.entries.iter().any(|entry| {
+            if entry.port.is_some() && entry.port != port {
+                return false;
+            }
+            match entry.host {
+                Pattern::Exact(ref exact) => host.eq_ignore_ascii_case(exact),
+                Pattern::Subdomain(ref suffix) => {
+                    host.len() > suffix.len()
+                        && host.as_bytes()[host.len() - suffix.len()..]
+                            .eq_ignore_ascii_case(suffix.as_bytes())
+                }
+            }
+        })
+    }
+}
+
+impl Entry {
+    fn parse(s: &str) -> Entry {
+        let s = s.trim();
+        let (host, port) = match s.rsplit_once(':') {
+            // a bare IPv6 address has more colons, and no port
+            Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+                (host, port.parse().ok())
+            }
+            _ => (s, None),
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let host = match host.strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') => Pattern::Subdomain(suffix.to_owned()),
+            _ => Pattern::Exact(host),
+        };
+        Entry { host, port }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn allows(hosts: &AllowedHosts, host: &'static str) -> bool {
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static(host));
+        hosts.allows(&Uri::from_static("/"), &headers)
+    }
+
+    #[test]
+    fn exact_and_wildcard() {
+        let hosts = AllowedHosts::new(["Example.com", "*.api.example.com", "[::1]:8080"]);
+
+        assert!(allows(&hosts, "example.com"));
+        assert!(allows(&hosts, "EXAMPLE.COM:443"));
+        assert!(allows(&hosts, "example.com."));
+        assert!(!allows(&hosts, "www.example.com"));
+        assert!(!allows(&hosts, "example.com.evil"));
+
+        assert!(allows(&hosts, "v1.api.example.com"));
+        assert!(allows(&hosts, "a.b.api.example.com"));
+        assert!(!allows(&hosts, "api.example.com"));
+        assert!(!allows(&hosts, "evilapi.example.com"));
+
+        assert!(allows(&hosts, "[::1]:8080"));
+        assert!(!allows(&hosts, "[::1]:8081"));
+        assert!(!allows(&hosts, "user@example.com"));
+    }
+
+    #[test]
+    fn absolute_form_wins_over_host() {
+        let hosts = AllowedHosts::new(["example.com"]);
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("example.com"));
+
+        assert!(!hosts.allows(&Uri::from_static("http://evil.local/"), &headers));
+        assert!(hosts.allows(&Uri::from_static("http://example.com/"), &HeaderMap::new()));
+        assert!(!hosts.allows(&Uri::from_static("/"), &HeaderMap::new()));
+    }
+}
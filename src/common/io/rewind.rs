@@ -39,9 +39,15 @@ impl<T> Rewind<T> {
         (self.inner, self.pre.unwrap_or_default())
     }
 
-    // pub(crate) fn get_mut(&mut self) -> &mut T {
-    //     &mut self.inner
-    // }
+    #[cfg(feature = "client")]
+    pub(crate) fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
 }
 
 impl<T> AsyncRead for Rewind<T>
@@ -14,10 +14,14 @@ pub(crate) mod date;
 pub(crate) mod drain;
 #[cfg(any(feature = "http1", feature = "http2", feature = "server"))]
 pub(crate) mod exec;
+#[cfg(all(feature = "server", any(feature = "http1", feature = "http2")))]
+pub(crate) mod host;
 pub(crate) mod io;
 #[cfg(all(feature = "client", any(feature = "http1", feature = "http2")))]
 mod lazy;
 mod never;
+#[cfg(feature = "runtime")]
+pub(crate) mod pace;
 #[cfg(any(
     feature = "stream",
     all(feature = "client", any(feature = "http1", feature = "http2"))
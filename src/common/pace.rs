@@ -0,0 +1,138 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+use std::time::Duration;
+
+use tokio::time::{Instant, Sleep};
+use tracing::trace;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// A token bucket pacing the chunks written to, or read from, a connection.
+///
+/// The bucket holds up to `burst` bytes, a second worth of them by default.
+/// A chunk is let through once any tokens are left, and may take more than
+/// are left, in which case the next chunk waits until the bucket is back in
+/// the positive.
+pub(crate) struct Pacer {
+    rate: i64,
+    burst: i64,
+    tokens: i64,
+    refilled: Instant,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Pacer {
+    #[cfg(all(feature = "client", feature = "http1"))]
+    pub(crate) fn new(bytes_per_sec: u64) -> Pacer {
+        Pacer::with_burst(bytes_per_sec, bytes_per_sec)
+    }
+
+    pub(crate) fn with_burst(bytes_per_sec: u64, burst: u64) -> Pacer {
+        debug_assert!(bytes_per_sec > 0 && burst > 0);
+        let rate = i64::try_from(bytes_per_sec).unwrap_or(i64::MAX);
+        let burst = i64::try_from(burst).unwrap_or(i64::MAX);
+        Pacer {
+            rate,
+            burst,
+            tokens: burst,
+            refilled: Instant::now(),
+            sleep: None,
+        }
+    }
+
+    /// Polls until the next chunk can be written.
+    #[cfg(all(feature = "client", feature = "http1"))]
+    pub(crate) fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<()> {
+        self.poll_available(cx, 1).map(|_| ())
+    }
+
+    /// Polls until `want` bytes can be let through, capped at the burst.
+    ///
+    /// Returns how many can be.
+    pub(crate) fn poll_available(
+        &mut self,
+        cx: &mut task::Context<'_>,
+        want: usize,
+    ) -> Poll<usize> {
+        let want = i64::try_from(want).unwrap_or(i64::MAX).clamp(1, self.burst);
+        loop {
+            if let Some(ref mut sleep) = self.sleep {
+                ready!(sleep.as_mut().poll(cx));
+                self.sleep = None;
+            }
+
+            self.refill(Instant::now());
+            if self.tokens >= want {
+                return Poll::Ready(usize::try_from(self.tokens).unwrap_or(usize::MAX));
+            }
+
+            let missing = (want - self.tokens) as u128;
+            let nanos = (missing * NANOS_PER_SEC).div_ceil(self.rate as u128);
+            let wait = Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX));
+            trace!("pacing, waiting {:?}", wait);
+            self.sleep = Some(Box::pin(tokio::time::sleep(wait)));
+        }
+    }
+
+    /// Takes the tokens of a chunk let through.
+    pub(crate) fn consume(&mut self, len: usize) {
+        let len = i64::try_from(len).unwrap_or(i64::MAX);
+        self.tokens = self.tokens.saturating_sub(len);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled);
+        let earned = elapsed.as_nanos() * self.rate as u128 / NANOS_PER_SEC;
+        if earned == 0 {
+            return;
+        }
+        let earned = i64::try_from(earned).unwrap_or(i64::MAX);
+        self.tokens = self.tokens.saturating_add(earned).min(self.burst);
+        self.refilled = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    #[cfg(all(feature = "client", feature = "http1"))]
+    async fn paces_to_rate() {
+        let mut pacer = Pacer::with_burst(1000, 1000);
+        let start = Instant::now();
+
+        // the bucket starts full
+        std::future::poll_fn(|cx| pacer.poll_ready(cx)).await;
+        pacer.consume(1000);
+        assert_eq!(Instant::now(), start);
+
+        // a chunk larger than the rate goes into debt
+        std::future::poll_fn(|cx| pacer.poll_ready(cx)).await;
+        assert_eq!(Instant::now() - start, Duration::from_millis(1));
+        pacer.consume(2000);
+
+        std::future::poll_fn(|cx| pacer.poll_ready(cx)).await;
+        assert_eq!(Instant::now() - start, Duration::from_millis(2001));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refills_up_to_burst() {
+        let mut pacer = Pacer::with_burst(1000, 100);
+        let start = Instant::now();
+        let available = std::future::poll_fn(|cx| pacer.poll_available(cx, 500)).await;
+        assert_eq!(available, 100);
+        pacer.consume(100);
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let available = std::future::poll_fn(|cx| pacer.poll_available(cx, 1)).await;
+        assert_eq!(available, 100);
+        pacer.consume(100);
+
+        // waits for the whole chunk
+        let available = std::future::poll_fn(|cx| pacer.poll_available(cx, 50)).await;
+        assert_eq!(available, 50);
+        assert_eq!(Instant::now() - start, Duration::from_millis(1050));
+    }
+}
@@ -7,6 +7,9 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 type Cause = Box<dyn StdError + Send + Sync>;
 
+#[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+use crate::client::conn::ConnContext;
+
 /// Represents errors that can occur handling HTTP streams.
 pub struct Error {
     inner: Box<ErrorImpl>,
@@ -15,6 +18,8 @@ pub struct Error {
 struct ErrorImpl {
     kind: Kind,
     cause: Option<Cause>,
+    #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+    context: Option<ConnContext>,
 }
 
 #[derive(Debug)]
@@ -47,8 +52,37 @@ pub(super) enum Kind {
     /// User took too long to send headers
     #[cfg(all(feature = "http1", feature = "server", feature = "runtime"))]
     HeaderTimeout,
+    /// A graceful shutdown didn't finish before its deadline.
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "server",
+        feature = "runtime"
+    ))]
+    ShutdownTimeout,
+    /// The head of a response didn't arrive in time.
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "client",
+        feature = "runtime"
+    ))]
+    ResponseHeaderTimeout,
+    /// A response body went too long without receiving data.
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "client",
+        feature = "runtime"
+    ))]
+    BodyIdleTimeout,
+    /// No request slot in a client's pool became free in time.
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "client",
+        feature = "runtime"
+    ))]
+    PoolExhausted,
+    /// A body was larger than its size limit.
+    BodyTooLarge,
     /// Error while reading a body from connection.
-    #[cfg(any(feature = "http1", feature = "http2", feature = "stream"))]
     Body,
     /// Error while writing a body to connection.
     #[cfg(any(feature = "http1", feature = "http2"))]
@@ -72,8 +106,16 @@ pub(super) enum Parse {
     #[cfg_attr(not(all(feature = "http1", feature = "server")), allow(unused))]
     UriTooLong,
     Header(Header),
+    #[cfg_attr(not(feature = "http1"), allow(unused))]
     TooLarge,
+    TooManyHeaders,
+    #[cfg_attr(not(feature = "http1"), allow(unused))]
+    HeaderValueTooLarge,
     Status,
+    #[cfg_attr(not(all(feature = "http1", feature = "server")), allow(unused))]
+    Misdirected,
+    #[cfg_attr(not(all(feature = "http1", feature = "client")), allow(unused))]
+    TooManyInformational,
     #[cfg_attr(debug_assertions, allow(unused))]
     Internal,
 }
@@ -90,6 +132,18 @@ pub(super) enum Header {
     TransferEncodingInvalid,
     #[cfg(feature = "http1")]
     TransferEncodingUnexpected,
+    #[cfg(feature = "http1")]
+    ContentLengthMultiple,
+    #[cfg(feature = "http1")]
+    ContentLengthMismatch,
+    #[cfg(all(feature = "http1", feature = "server"))]
+    ContentLengthWithTransferEncoding,
+    #[cfg(all(feature = "http1", feature = "server"))]
+    ObsFold,
+    #[cfg(all(feature = "http1", feature = "server"))]
+    BareCr,
+    #[cfg(all(feature = "http1", feature = "server"))]
+    WhitespaceBeforeColon,
 }
 
 #[derive(Debug)]
@@ -156,19 +210,57 @@ impl Error {
     }
 
     /// Returns true if this was an HTTP parse error caused by a message that was too large.
+    ///
+    /// This includes messages with too many headers, or too large header
+    /// values.
     pub fn is_parse_too_large(&self) -> bool {
         matches!(
             self.inner.kind,
-            Kind::Parse(Parse::TooLarge) | Kind::Parse(Parse::UriTooLong)
+            Kind::Parse(Parse::TooLarge)
+                | Kind::Parse(Parse::UriTooLong)
+                | Kind::Parse(Parse::TooManyHeaders)
+                | Kind::Parse(Parse::HeaderValueTooLarge)
         )
     }
 
+    /// Returns true if this was an HTTP parse error caused by a message with
+    /// more headers than allowed.
+    pub fn is_parse_too_many_headers(&self) -> bool {
+        matches!(self.inner.kind, Kind::Parse(Parse::TooManyHeaders))
+    }
+
+    /// Returns true if this was an HTTP parse error caused by a header value
+    /// larger than allowed.
+    pub fn is_parse_header_value_too_large(&self) -> bool {
+        matches!(self.inner.kind, Kind::Parse(Parse::HeaderValueTooLarge))
+    }
+
+    /// Returns true if this was an HTTP parse error caused by a message with
+    /// multiple `Content-Length` values, rejected by its
+    /// [`ContentLengthPolicy`](crate::ext::ContentLengthPolicy).
+    pub fn is_parse_conflicting_content_length(&self) -> bool {
+        #[cfg(feature = "http1")]
+        if let Kind::Parse(Parse::Header(
+            Header::ContentLengthMultiple | Header::ContentLengthMismatch,
+        )) = self.inner.kind
+        {
+            return true;
+        }
+        false
+    }
+
     /// Returns true if this was an HTTP parse error caused by an invalid response status code or
     /// reason phrase.
     pub fn is_parse_status(&self) -> bool {
         matches!(self.inner.kind, Kind::Parse(Parse::Status))
     }
 
+    /// Returns true if this was an HTTP parse error caused by a peer sending
+    /// more informational (1xx) responses than allowed before the final one.
+    pub fn is_parse_too_many_informational(&self) -> bool {
+        matches!(self.inner.kind, Kind::Parse(Parse::TooManyInformational))
+    }
+
     /// Returns true if this error was caused by user code.
     pub fn is_user(&self) -> bool {
         matches!(self.inner.kind, Kind::User(_))
@@ -204,6 +296,77 @@ impl Error {
         self.find_source::<TimedOut>().is_some()
     }
 
+    /// Returns true if the head of a response didn't arrive before the
+    /// client's response header timeout.
+    pub fn is_response_header_timeout(&self) -> bool {
+        #[cfg(all(
+            any(feature = "http1", feature = "http2"),
+            feature = "client",
+            feature = "runtime"
+        ))]
+        if let Kind::ResponseHeaderTimeout = self.inner.kind {
+            return true;
+        }
+        false
+    }
+
+    /// Returns true if a request waited longer than the client's pool wait
+    /// timeout for one of its limited request slots.
+    pub fn is_pool_exhausted(&self) -> bool {
+        #[cfg(all(
+            any(feature = "http1", feature = "http2"),
+            feature = "client",
+            feature = "runtime"
+        ))]
+        if let Kind::PoolExhausted = self.inner.kind {
+            return true;
+        }
+        false
+    }
+
+    /// Returns true if a client didn't send a complete request head before
+    /// the server's header read timeout.
+    pub fn is_header_read_timeout(&self) -> bool {
+        #[cfg(all(feature = "http1", feature = "server", feature = "runtime"))]
+        if let Kind::HeaderTimeout = self.inner.kind {
+            return true;
+        }
+        false
+    }
+
+    /// Returns true if a response body went longer than the client's body
+    /// idle timeout without receiving data.
+    pub fn is_body_idle_timeout(&self) -> bool {
+        #[cfg(all(
+            any(feature = "http1", feature = "http2"),
+            feature = "client",
+            feature = "runtime"
+        ))]
+        if let Kind::BodyIdleTimeout = self.inner.kind {
+            return true;
+        }
+        false
+    }
+
+    /// Returns true if a body was larger than its size limit.
+    ///
+    /// See [`Body::limit`](crate::Body::limit) for more.
+    pub fn is_body_too_large(&self) -> bool {
+        matches!(self.inner.kind, Kind::BodyTooLarge)
+    }
+
+    /// Returns the context of the client connection this error came from,
+    /// if one was set with
+    /// [`Builder::context`](crate::client::conn::Builder::context).
+    #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(any(feature = "http1", feature = "http2"), feature = "client")))
+    )]
+    pub fn connection_context(&self) -> Option<&ConnContext> {
+        self.inner.context.as_ref()
+    }
+
     /// Consumes the error, returning its cause.
     pub fn into_cause(self) -> Option<Box<dyn StdError + Send + Sync>> {
         self.inner.cause
@@ -211,7 +374,12 @@ impl Error {
 
     pub(super) fn new(kind: Kind) -> Error {
         Error {
-            inner: Box::new(ErrorImpl { kind, cause: None }),
+            inner: Box::new(ErrorImpl {
+                kind,
+                cause: None,
+                #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+                context: None,
+            }),
         }
     }
 
@@ -220,6 +388,16 @@ impl Error {
         self
     }
 
+    /// Attaches the context of the connection the error came from, unless
+    /// one was attached already.
+    #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+    pub(crate) fn with_context(mut self, context: Option<&ConnContext>) -> Error {
+        if self.inner.context.is_none() {
+            self.inner.context = context.cloned();
+        }
+        self
+    }
+
     #[cfg(any(all(feature = "http1", feature = "server"), feature = "ffi"))]
     pub(super) fn kind(&self) -> &Kind {
         &self.inner.kind
@@ -297,7 +475,6 @@ impl Error {
         Error::new(Kind::ChannelClosed)
     }
 
-    #[cfg(any(feature = "http1", feature = "http2", feature = "stream"))]
     pub(super) fn new_body<E: Into<Cause>>(cause: E) -> Error {
         Error::new(Kind::Body).with(cause)
     }
@@ -323,7 +500,47 @@ impl Error {
 
     #[cfg(all(feature = "http1", feature = "server", feature = "runtime"))]
     pub(super) fn new_header_timeout() -> Error {
-        Error::new(Kind::HeaderTimeout)
+        Error::new(Kind::HeaderTimeout).with(TimedOut)
+    }
+
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "server",
+        feature = "runtime"
+    ))]
+    pub(super) fn new_shutdown_timeout() -> Error {
+        Error::new(Kind::ShutdownTimeout).with(TimedOut)
+    }
+
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "client",
+        feature = "runtime"
+    ))]
+    pub(super) fn new_response_header_timeout() -> Error {
+        Error::new(Kind::ResponseHeaderTimeout).with(TimedOut)
+    }
+
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "client",
+        feature = "runtime"
+    ))]
+    pub(super) fn new_body_idle_timeout() -> Error {
+        Error::new(Kind::BodyIdleTimeout).with(TimedOut)
+    }
+
+    #[cfg(all(
+        any(feature = "http1", feature = "http2"),
+        feature = "client",
+        feature = "runtime"
+    ))]
+    pub(super) fn new_pool_exhausted() -> Error {
+        Error::new(Kind::PoolExhausted).with(TimedOut)
+    }
+
+    pub(super) fn new_body_too_large() -> Error {
+        Error::new(Kind::BodyTooLarge)
     }
 
     #[cfg(any(feature = "http1", feature = "http2"))]
@@ -425,8 +642,34 @@ impl Error {
             Kind::Parse(Parse::Header(Header::TransferEncodingUnexpected)) => {
                 "unexpected transfer-encoding parsed"
             }
+            #[cfg(feature = "http1")]
+            Kind::Parse(Parse::Header(Header::ContentLengthMultiple)) => {
+                "multiple content-length values parsed"
+            }
+            #[cfg(feature = "http1")]
+            Kind::Parse(Parse::Header(Header::ContentLengthMismatch)) => {
+                "differing content-length values parsed"
+            }
+            #[cfg(all(feature = "http1", feature = "server"))]
+            Kind::Parse(Parse::Header(Header::ContentLengthWithTransferEncoding)) => {
+                "both content-length and transfer-encoding parsed"
+            }
+            #[cfg(all(feature = "http1", feature = "server"))]
+            Kind::Parse(Parse::Header(Header::ObsFold)) => "obsolete line folding parsed",
+            #[cfg(all(feature = "http1", feature = "server"))]
+            Kind::Parse(Parse::Header(Header::BareCr)) => "bare CR parsed",
+            #[cfg(all(feature = "http1", feature = "server"))]
+            Kind::Parse(Parse::Header(Header::WhitespaceBeforeColon)) => {
+                "whitespace before header colon parsed"
+            }
             Kind::Parse(Parse::TooLarge) => "message head is too large",
+            Kind::Parse(Parse::TooManyHeaders) => "message head has too many headers",
+            Kind::Parse(Parse::HeaderValueTooLarge) => "header value is too large",
             Kind::Parse(Parse::Status) => "invalid HTTP status-code parsed",
+            Kind::Parse(Parse::Misdirected) => "request host is not allowed",
+            Kind::Parse(Parse::TooManyInformational) => {
+                "too many informational (1xx) responses received"
+            }
             Kind::Parse(Parse::Internal) => {
                 "internal error inside fluxio and/or its dependencies, please report"
             }
@@ -443,7 +686,31 @@ impl Error {
             Kind::Accept => "error accepting connection",
             #[cfg(all(feature = "http1", feature = "server", feature = "runtime"))]
             Kind::HeaderTimeout => "read header from client timeout",
-            #[cfg(any(feature = "http1", feature = "http2", feature = "stream"))]
+            #[cfg(all(
+                any(feature = "http1", feature = "http2"),
+                feature = "server",
+                feature = "runtime"
+            ))]
+            Kind::ShutdownTimeout => "graceful shutdown deadline elapsed",
+            #[cfg(all(
+                any(feature = "http1", feature = "http2"),
+                feature = "client",
+                feature = "runtime"
+            ))]
+            Kind::ResponseHeaderTimeout => "timed out waiting for the response head",
+            #[cfg(all(
+                any(feature = "http1", feature = "http2"),
+                feature = "client",
+                feature = "runtime"
+            ))]
+            Kind::BodyIdleTimeout => "timed out waiting for response body data",
+            #[cfg(all(
+                any(feature = "http1", feature = "http2"),
+                feature = "client",
+                feature = "runtime"
+            ))]
+            Kind::PoolExhausted => "timed out waiting for a free request slot in the pool",
+            Kind::BodyTooLarge => "body is larger than its size limit",
             Kind::Body => "error reading a body from connection",
             #[cfg(any(feature = "http1", feature = "http2"))]
             Kind::BodyWrite => "error writing a body to connection",
@@ -499,6 +766,10 @@ impl fmt::Debug for Error {
         if let Some(ref cause) = self.inner.cause {
             f.field(cause);
         }
+        #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+        if let Some(ref context) = self.inner.context {
+            f.field(context);
+        }
         f.finish()
     }
 }
@@ -506,10 +777,15 @@ impl fmt::Debug for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(ref cause) = self.inner.cause {
-            write!(f, "{}: {}", self.description(), cause)
+            write!(f, "{}: {}", self.description(), cause)?;
         } else {
-            f.write_str(self.description())
+            f.write_str(self.description())?;
         }
+        #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
+        if let Some(ref context) = self.inner.context {
+            write!(f, " (connection {})", context)?;
+        }
+        Ok(())
     }
 }
 
@@ -543,6 +819,14 @@ impl Parse {
     pub(crate) fn transfer_encoding_unexpected() -> Self {
         Parse::Header(Header::TransferEncodingUnexpected)
     }
+
+    pub(crate) fn content_length_multiple() -> Self {
+        Parse::Header(Header::ContentLengthMultiple)
+    }
+
+    pub(crate) fn content_length_mismatch() -> Self {
+        Parse::Header(Header::ContentLengthMismatch)
+    }
 }
 
 impl From<httparse::Error> for Parse {
@@ -553,7 +837,7 @@ impl From<httparse::Error> for Parse {
             | httparse::Error::NewLine
             | httparse::Error::Token => Parse::Header(Header::Token),
             httparse::Error::Status => Parse::Status,
-            httparse::Error::TooManyHeaders => Parse::TooLarge,
+            httparse::Error::TooManyHeaders => Parse::TooManyHeaders,
             httparse::Error::Version => Parse::Version,
         }
     }
@@ -0,0 +1,248 @@
+//! Content negotiation with `Accept`-style headers.
+//!
+//! The `Accept`, `Accept-Language`, and `Accept-Encoding` headers all share
+//! the same shape: a comma-separated list of values, each optionally
+//! weighted with a `q` parameter ([RFC 9110, Section 12.4.2][qvalue]).
+//!
+//! [qvalue]: https://www.rfc-editor.org/rfc/rfc9110#section-12.4.2
+//!
+//! # Example
+//!
+//! ```
+//! use fluxio::ext::accept::negotiate;
+//! use fluxio::header::HeaderValue;
+//!
+//! let accept = HeaderValue::from_static("text/*;q=0.5, application/json");
+//! let chosen = negotiate(&["text/html", "application/json"], Some(&accept));
+//! assert_eq!(chosen, Some("application/json"));
+//! ```
+
+use http::header::HeaderValue;
+
+/// The highest quality value, as thousandths.
+const MAX_QUALITY: u16 = 1000;
+
+/// One entry of an `Accept`-style header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QualityItem<'a> {
+    value: &'a str,
+    quality: u16,
+}
+
+impl<'a> QualityItem<'a> {
+    /// The value of this entry, without any parameters.
+    ///
+    /// For an `Accept` entry of `text/html;level=1;q=0.5`, this is
+    /// `text/html`.
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+
+    /// The weight of this entry in thousandths, from `0` to `1000`.
+    ///
+    /// Entries without a `q` parameter have a quality of `1000`.
+    pub fn quality(&self) -> u16 {
+        self.quality
+    }
+
+    /// Returns how specifically this entry matches `candidate`, if at all.
+    ///
+    /// Exact matches are the most specific, then media type (`text/*`) and
+    /// language prefix (`en` for `en-US`) matches, then `*` and `*/*`.
+    fn specificity(&self, candidate: &str) -> Option<u8> {
+        let value = self.value;
+        if value.eq_ignore_ascii_case(candidate) {
+            return Some(3);
+        }
+        if value == "*" || value == "*/*" {
+            return Some(1);
+        }
+        if let Some(ty) = value.strip_suffix("/*") {
+            if starts_with_ignore_case(candidate, ty) && candidate[ty.len()..].starts_with('/') {
+                return Some(2);
+            }
+        } else if !value.contains('/')
+            && starts_with_ignore_case(candidate, value)
+            && candidate[value.len()..].starts_with('-')
+        {
+            return Some(2);
+        }
+        None
+    }
+}
+
+/// Parses all the entries of one or more `Accept`-style header values.
+///
+/// Entries are returned highest quality first. Entries of equal quality
+/// keep the order they were sent in. Malformed entries are skipped.
+pub fn quality_list<'a, I>(values: I) -> Vec<QualityItem<'a>>
+where
+    I: IntoIterator<Item = &'a HeaderValue>,
+{
+    let mut items = Vec::new();
+    for value in values {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for entry in value.split(',') {
+            if let Some(item) = parse_entry(entry) {
+                items.push(item);
+            }
+        }
+    }
+    // sorting is stable, keeping the original order between equals
+    items.sort_by_key(|item| std::cmp::Reverse(item.quality));
+    items
+}
+
+/// Picks the best of the `available` values for an `Accept`-style header.
+///
+/// `available` should be listed in order of the server's preference, which
+/// is used to break ties. If the header is missing or empty, everything is
+/// acceptable, and the first available value is returned.
+///
+/// Returns `None` if none of the available values is acceptable, including
+/// when they were explicitly refused with `q=0`.
+pub fn negotiate<'s, 'a, I>(available: &[&'s str], values: I) -> Option<&'s str>
+where
+    I: IntoIterator<Item = &'a HeaderValue>,
+{
+    let items = quality_list(values);
+    if items.is_empty() {
+        return available.first().copied();
+    }
+
+    let mut best: Option<(&'s str, u16)> = None;
+    for &candidate in available {
+        // The most specific matching entry decides the quality.
+        let quality = items
+            .iter()
+            .filter_map(|item| item.specificity(candidate).map(|s| (s, item.quality)))
+            .max_by_key(|&(specificity, _)| specificity)
+            .map(|(_, quality)| quality)
+            .unwrap_or(0);
+
+        let is_better = match best {
+            Some((_, q)) => quality > q,
+            None => quality > 0,
+        };
+        if is_better {
+            best = Some((candidate, quality));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+fn parse_entry(entry: &str) -> Option<QualityItem<'_>> {
+    let mut parts = entry.split(';');
+    let value = parts.next()?.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut quality = MAX_QUALITY;
+    for param in parts {
+        let (name, val) = match param.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if name.trim().eq_ignore_ascii_case("q") {
+            quality = parse_qvalue(val.trim())?;
+        }
+    }
+
+    Some(QualityItem { value, quality })
+}
+
+/// Parses `qvalue = ( "0" [ "." 0*3DIGIT ] ) / ( "1" [ "." 0*3("0") ] )`.
+fn parse_qvalue(s: &str) -> Option<u16> {
+    let (int, frac) = match s.split_once('.') {
+        Some((int, frac)) => (int, frac),
+        None => (s, ""),
+    };
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut thousandths = 0;
+    for (i, b) in frac.bytes().enumerate() {
+        thousandths += u16::from(b - b'0') * [100, 10, 1][i];
+    }
+    match int {
+        "0" => Some(thousandths),
+        "1" if thousandths == 0 => Some(MAX_QUALITY),
+        _ => None,
+    }
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len() && s.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hv(s: &'static str) -> HeaderValue {
+        HeaderValue::from_static(s)
+    }
+
+    #[test]
+    fn quality_list_sorts_by_weight() {
+        let value = hv("da, en-gb;q=0.8, en;q=0.7, fr;Q=1.000, bad;q=2");
+        let list = quality_list(Some(&value));
+        let values = list
+            .iter()
+            .map(|item| (item.value(), item.quality()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![("da", 1000), ("fr", 1000), ("en-gb", 800), ("en", 700)]
+        );
+    }
+
+    #[test]
+    fn parse_qvalues() {
+        assert_eq!(parse_qvalue("0"), Some(0));
+        assert_eq!(parse_qvalue("0.5"), Some(500));
+        assert_eq!(parse_qvalue("0.125"), Some(125));
+        assert_eq!(parse_qvalue("1."), Some(1000));
+        assert_eq!(parse_qvalue("1.001"), None);
+        assert_eq!(parse_qvalue("0.1234"), None);
+        assert_eq!(parse_qvalue("-0"), None);
+    }
+
+    #[test]
+    fn negotiate_media_types() {
+        let accept = hv("text/*;q=0.5, application/json, */*;q=0.1");
+        assert_eq!(
+            negotiate(&["text/html", "application/json"], Some(&accept)),
+            Some("application/json")
+        );
+        assert_eq!(
+            negotiate(&["text/html", "image/png"], Some(&accept)),
+            Some("text/html")
+        );
+        assert_eq!(negotiate(&["image/png"], Some(&accept)), Some("image/png"));
+    }
+
+    #[test]
+    fn negotiate_languages() {
+        let accept = hv("fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5");
+        assert_eq!(negotiate(&["en-US", "fr-FR"], Some(&accept)), Some("fr-FR"));
+        assert_eq!(negotiate(&["de", "en-US"], Some(&accept)), Some("en-US"));
+    }
+
+    #[test]
+    fn negotiate_refused_and_missing() {
+        let accept = hv("gzip, identity;q=0");
+        assert_eq!(negotiate(&["identity"], Some(&accept)), None);
+        assert_eq!(negotiate(&["br", "gzip"], Some(&accept)), Some("gzip"));
+
+        // a more specific refusal wins over a wildcard
+        let accept = hv("*, br;q=0");
+        assert_eq!(negotiate(&["br", "gzip"], Some(&accept)), Some("gzip"));
+
+        assert_eq!(negotiate(&["br", "gzip"], None), Some("br"));
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+use std::sync::Arc;
+
+use http_body::SizeHint;
+
+/// How an outgoing HTTP/1 body whose length isn't known is framed.
+///
+/// By default, such a body is sent with `Transfer-Encoding: chunked`, or
+/// delimited by closing the connection for a response to an HTTP/1.0
+/// request. A callback set with `http1_body_framing` on a client or server
+/// builder can choose otherwise for each message, from the
+/// [`SizeHint`](crate::body::SizeHint) of its body.
+///
+/// # Example
+///
+/// Sending small bodies with a `Content-Length` to an upstream that can't
+/// read chunked requests:
+///
+/// ```
+/// # #[cfg(all(feature = "client", feature = "http1"))]
+/// # fn doc() {
+/// use fluxio::client::conn::Builder;
+/// use fluxio::ext::BodyFraming;
+///
+/// let mut builder = Builder::new();
+/// builder.http1_body_framing(|_hint| BodyFraming::Buffer(64 * 1024));
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BodyFraming {
+    /// Send the body with `Transfer-Encoding: chunked`.
+    ///
+    /// A response to an HTTP/1.0 request is still delimited by closing the
+    /// connection.
+    #[default]
+    Chunked,
+    /// Send the body delimited by closing the connection.
+    ///
+    /// Only a response can be delimited this way, so a request is sent
+    /// chunked instead.
+    CloseDelimited,
+    /// Buffer up to this many bytes of the body before sending it.
+    ///
+    /// If the body ends by then, it is sent with a `Content-Length`.
+    /// Otherwise, what was buffered is sent and the rest streamed as with
+    /// [`BodyFraming::Chunked`]. Trailers of a body sent with a
+    /// `Content-Length` are dropped.
+    Buffer(usize),
+}
+
+type Callback = dyn Fn(&SizeHint) -> BodyFraming + Send + Sync;
+
+/// The callback choosing how to frame a body of unknown length.
+#[derive(Clone)]
+pub(crate) struct BodyFramingFn(Arc<Callback>);
+
+impl BodyFramingFn {
+    pub(crate) fn new<F>(f: F) -> BodyFramingFn
+    where
+        F: Fn(&SizeHint) -> BodyFraming + Send + Sync + 'static,
+    {
+        BodyFramingFn(Arc::new(f))
+    }
+
+    pub(crate) fn call(&self, hint: &SizeHint) -> BodyFraming {
+        (self.0)(hint)
+    }
+}
+
+impl fmt::Debug for BodyFramingFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyFramingFn").finish()
+    }
+}
@@ -0,0 +1,336 @@
+use std::fmt;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+/// The extensions of a chunk of an HTTP/1 chunked body.
+///
+/// These are written after the size of a chunk, like `;ieof` or
+/// `; name="value"` ([RFC 9112, Section 7.1.1][rfc]), and kept as received,
+/// starting with the `;`.
+///
+/// # Receiving
+///
+/// The extensions of the chunk last returned by a body are available with
+/// [`Body::chunk_extensions`](crate::Body::chunk_extensions).
+///
+/// # Sending
+///
+/// Servers can emit extensions with a [`ChunkExtensionsHook`] in the
+/// extensions of a response.
+///
+/// [rfc]: https://www.rfc-editor.org/rfc/rfc9112#section-7.1.1
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ChunkExtensions(Bytes);
+
+/// An error converting bytes that aren't valid chunk extensions.
+///
+/// Chunk extensions must start with a `;`, and can contain horizontal tabs,
+/// spaces, visible US-ASCII characters, and bytes above `0x7F`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidChunkExtensions {
+    _priv: (),
+}
+
+impl ChunkExtensions {
+    /// Converts a static byte slice to chunk extensions.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the slice isn't valid chunk extensions. Used in a
+    /// `const`, this fails to compile instead.
+    pub const fn from_static(extensions: &'static [u8]) -> ChunkExtensions {
+        if !is_valid(extensions) {
+            panic!("invalid static chunk extensions");
+        }
+        ChunkExtensions(Bytes::from_static(extensions))
+    }
+
+    /// The bytes of these extensions, starting with the `;`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns an iterator over the names and values of these extensions.
+    ///
+    /// Whitespace around names and values is trimmed, and values are
+    /// returned as written, so quoted ones keep their quotes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::ext::ChunkExtensions;
+    ///
+    /// let extensions = ChunkExtensions::from_static(b"; ieof; name=\"a;b\"");
+    /// let mut iter = extensions.iter();
+    /// assert_eq!(iter.next(), Some((&b"ieof"[..], None)));
+    /// assert_eq!(iter.next(), Some((&b"name"[..], Some(&b"\"a;b\""[..]))));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], Option<&[u8]>)> {
+        split_unquoted(&self.0[1..]).filter_map(|extension| {
+            let (name, value) = match find_unquoted(extension, b'=') {
+                Some(i) => (&extension[..i], Some(trim(&extension[i + 1..]))),
+                None => (extension, None),
+            };
+            let name = trim(name);
+            (!name.is_empty()).then_some((name, value))
+        })
+    }
+}
+
+impl AsRef<[u8]> for ChunkExtensions {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ChunkExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ChunkExtensions")
+            .field(&String::from_utf8_lossy(&self.0))
+            .finish()
+    }
+}
+
+impl TryFrom<Bytes> for ChunkExtensions {
+    type Error = InvalidChunkExtensions;
+
+    fn try_from(extensions: Bytes) -> Result<ChunkExtensions, InvalidChunkExtensions> {
+        if is_valid(&extensions) {
+            Ok(ChunkExtensions(extensions))
+        } else {
+            Err(InvalidChunkExtensions { _priv: () })
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for ChunkExtensions {
+    type Error = InvalidChunkExtensions;
+
+    fn try_from(extensions: &[u8]) -> Result<ChunkExtensions, InvalidChunkExtensions> {
+        ChunkExtensions::try_from(Bytes::copy_from_slice(extensions))
+    }
+}
+
+impl TryFrom<Vec<u8>> for ChunkExtensions {
+    type Error = InvalidChunkExtensions;
+
+    fn try_from(extensions: Vec<u8>) -> Result<ChunkExtensions, InvalidChunkExtensions> {
+        ChunkExtensions::try_from(Bytes::from(extensions))
+    }
+}
+
+impl TryFrom<String> for ChunkExtensions {
+    type Error = InvalidChunkExtensions;
+
+    fn try_from(extensions: String) -> Result<ChunkExtensions, InvalidChunkExtensions> {
+        ChunkExtensions::try_from(Bytes::from(extensions))
+    }
+}
+
+impl fmt::Display for InvalidChunkExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid chunk extensions")
+    }
+}
+
+impl std::error::Error for InvalidChunkExtensions {}
+
+#[cfg(feature = "server")]
+type Hook = dyn Fn(&[u8]) -> Option<ChunkExtensions> + Send + Sync;
+
+/// A hook picking the extensions of each chunk of a response body.
+///
+/// When present in the extensions of a response sent by a server with a
+/// chunked body, the hook is called with the data of each chunk before it
+/// is written, and with an empty slice for the last chunk. The extensions it
+/// returns are written after the size of the chunk.
+///
+/// This is ignored for responses that aren't chunked, and by HTTP/2.
+///
+/// # Example
+///
+/// Marking the end of a body, like ICAP does:
+///
+/// ```
+/// use fluxio::ext::{ChunkExtensions, ChunkExtensionsHook};
+/// use fluxio::{Body, Response};
+///
+/// let mut res = Response::new(Body::from("preview"));
+/// res.extensions_mut().insert(ChunkExtensionsHook::new(|data| {
+///     data.is_empty()
+///         .then(|| ChunkExtensions::from_static(b"; ieof"))
+/// }));
+/// ```
+#[cfg(feature = "server")]
+#[derive(Clone)]
+pub struct ChunkExtensionsHook(Arc<Hook>);
+
+#[cfg(feature = "server")]
+impl ChunkExtensionsHook {
+    /// Creates a hook calling `f` with the data of each chunk.
+    pub fn new<F>(f: F) -> ChunkExtensionsHook
+    where
+        F: Fn(&[u8]) -> Option<ChunkExtensions> + Send + Sync + 'static,
+    {
+        ChunkExtensionsHook(Arc::new(f))
+    }
+
+    pub(crate) fn call(&self, data: &[u8]) -> Option<ChunkExtensions> {
+        (self.0)(data)
+    }
+}
+
+#[cfg(feature = "server")]
+impl fmt::Debug for ChunkExtensionsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkExtensionsHook").finish()
+    }
+}
+
+const fn is_valid(bytes: &[u8]) -> bool {
+    if bytes.is_empty() || bytes[0] != b';' {
+        return false;
+    }
+    let mut i = 1;
+    while i < bytes.len() {
+        let b = bytes[i];
+        // HTAB / SP / VCHAR / obs-text
+        if b != b'\t' && (b < b' ' || b == 0x7F) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Finds the first `delim` outside of a quoted string.
+fn find_unquoted(bytes: &[u8], delim: u8) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if quoted && b == b'\\' {
+            escaped = true;
+        } else if b == b'"' {
+            quoted = !quoted;
+        } else if b == delim && !quoted {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Splits `bytes` at each `;` outside of a quoted string.
+fn split_unquoted(mut bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        match find_unquoted(bytes, b';') {
+            Some(i) => {
+                let extension = &bytes[..i];
+                bytes = &bytes[i + 1..];
+                Some(extension)
+            }
+            None => {
+                done = true;
+                Some(bytes)
+            }
+        }
+    })
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&b| b != b' ' && b != b'\t')
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|&b| b != b' ' && b != b'\t')
+        .map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_extensions() {
+        assert!(ChunkExtensions::try_from(&b";a=1;b"[..]).is_ok());
+        assert!(ChunkExtensions::try_from(&b"a=1"[..]).is_err());
+        assert!(ChunkExtensions::try_from(&b""[..]).is_err());
+        assert!(ChunkExtensions::try_from(&b";a\r\n"[..]).is_err());
+    }
+
+    #[test]
+    fn iter_extensions() {
+        let extensions = ChunkExtensions::from_static(b";a = 1 ;;b;c=\"x\\\"=;\"; =d");
+        let all = extensions.iter().collect::<Vec<_>>();
+        assert_eq!(
+            all,
+            [
+                (&b"a"[..], Some(&b"1"[..])),
+                (&b"b"[..], None),
+                (&b"c"[..], Some(&b"\"x\\\"=;\""[..])),
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "client", feature = "server"))]
+    #[tokio::test]
+    async fn extensions_from_server_to_client() {
+        use std::convert::Infallible;
+
+        use crate::body::HttpBody;
+        use crate::server::conn::Http;
+        use crate::service::service_fn;
+        use crate::testing::duplex;
+        use crate::{Body, Request, Response};
+
+        let (client, server) = duplex(1024);
+        let svc = service_fn(|_req: Request<Body>| async {
+            let (mut tx, body) = Body::channel();
+            tokio::spawn(async move {
+                tx.send_data("foo".into()).await.unwrap();
+                tx.send_data("bar".into()).await.unwrap();
+            });
+            let mut res = Response::new(body);
+            res.extensions_mut()
+                .insert(ChunkExtensionsHook::new(|data| match data {
+                    b"" => Some(ChunkExtensions::from_static(b"; ieof")),
+                    b"foo" => Some(ChunkExtensions::from_static(b";n=1")),
+                    _ => None,
+                }));
+            Ok::<_, Infallible>(res)
+        });
+        tokio::spawn(Http::new().serve_connection(server, svc));
+
+        let (mut tx, conn) = crate::client::conn::handshake(client).await.unwrap();
+        tokio::spawn(conn);
+        let mut body = tx
+            .send_request(Request::new(Body::empty()))
+            .await
+            .unwrap()
+            .into_body();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let extensions = body.chunk_extensions().map(|e| e.as_bytes().to_vec());
+            chunks.push((chunk.unwrap(), extensions));
+        }
+        assert_eq!(
+            chunks,
+            [
+                ("foo".into(), Some(b";n=1".to_vec())),
+                ("bar".into(), None),
+                ("".into(), Some(b"; ieof".to_vec())),
+            ]
+        );
+    }
+}
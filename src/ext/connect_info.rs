@@ -0,0 +1,137 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::service::make::Conn;
+
+/// Details of the connection a request was received on.
+///
+/// This is found in the extensions of each request received by a
+/// [`Server`](crate::Server) built with
+/// [`connect_info(true)`](crate::server::Builder::connect_info), so that
+/// handlers can read them without threading them through the
+/// `MakeService`.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::ext::ConnectInfo;
+/// use fluxio::{Body, Request, Response};
+///
+/// async fn handle(req: Request<Body>) -> Result<Response<Body>, fluxio::Error> {
+///     let peer = req
+///         .extensions()
+///         .get::<ConnectInfo>()
+///         .and_then(ConnectInfo::remote_addr);
+///     Ok(Response::new(Body::from(format!("Hello, {:?}!", peer))))
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConnectInfo {
+    remote_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    tls: Option<Arc<TlsInfo>>,
+}
+
+/// The TLS details of a connection, found in its [`ConnectInfo`].
+#[derive(Clone, Debug)]
+pub struct TlsInfo {
+    server_name: Option<String>,
+    peer_certificate: Option<Bytes>,
+    alpn_protocol: Option<Bytes>,
+}
+
+impl ConnectInfo {
+    /// Collects the details of a connection.
+    ///
+    /// The connection is considered to use TLS if it reports any TLS
+    /// details.
+    pub fn from_conn(conn: &dyn Conn) -> ConnectInfo {
+        let tls = TlsInfo {
+            server_name: conn.tls_server_name().map(str::to_owned),
+            peer_certificate: conn.tls_peer_certificate().map(Bytes::copy_from_slice),
+            alpn_protocol: conn.alpn_protocol().map(Bytes::copy_from_slice),
+        };
+        let is_tls = tls.server_name.is_some()
+            || tls.peer_certificate.is_some()
+            || tls.alpn_protocol.is_some();
+        ConnectInfo {
+            remote_addr: conn.remote_addr(),
+            local_addr: conn.local_addr(),
+            tls: if is_tls { Some(Arc::new(tls)) } else { None },
+        }
+    }
+
+    /// Returns the address of the peer of the connection.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// Returns the local address of the connection.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Returns the TLS details of the connection, if it uses TLS.
+    pub fn tls(&self) -> Option<&TlsInfo> {
+        self.tls.as_deref()
+    }
+}
+
+impl TlsInfo {
+    /// Returns the server name the client asked for with TLS SNI.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    /// Returns the DER encoded certificate the client authenticated with,
+    /// if the connection uses mutual TLS.
+    pub fn peer_certificate(&self) -> Option<&[u8]> {
+        self.peer_certificate.as_deref()
+    }
+
+    /// Returns the protocol negotiated with ALPN, such as `b"h2"`.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_deref()
+    }
+}
+
+#[cfg(all(test, feature = "http1", feature = "tcp"))]
+mod tests {
+    use std::convert::Infallible;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::service::{make_service_fn, service_fn};
+    use crate::{Body, Request, Response, Server};
+
+    #[tokio::test]
+    async fn server_inserts_connect_info() {
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+                let info = req.extensions().get::<ConnectInfo>().unwrap();
+                assert!(info.tls().is_none());
+                let peer = info.remote_addr().unwrap().to_string();
+                Ok::<_, Infallible>(Response::new(Body::from(peer)))
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into())
+            .connect_info(true)
+            .serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\nconnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut res = Vec::new();
+        client.read_to_end(&mut res).await.unwrap();
+        let res = String::from_utf8(res).unwrap();
+        let peer = client.local_addr().unwrap().to_string();
+        assert!(res.ends_with(&peer), "{}", res);
+    }
+}
@@ -0,0 +1,39 @@
+/// How an HTTP/1 message with multiple `Content-Length` values is read.
+///
+/// The values may be in several `Content-Length` headers, or separated by
+/// commas in one. RFC 9110 allows a recipient to accept a list of identical
+/// values as that value, but peers disagreeing on which of differing values
+/// counts can be made to disagree on where a message ends.
+///
+/// Messages rejected by the policy fail with an error for which
+/// [`Error::is_parse_conflicting_content_length`] returns true. Servers
+/// respond to such requests with `400 Bad Request`.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "server", feature = "http1"))]
+/// # fn doc() {
+/// use fluxio::ext::ContentLengthPolicy;
+/// use fluxio::server::conn::Http;
+///
+/// let mut http = Http::new();
+/// http.http1_content_length_policy(ContentLengthPolicy::Reject);
+/// # }
+/// ```
+///
+/// [`Error::is_parse_conflicting_content_length`]: crate::Error::is_parse_conflicting_content_length
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContentLengthPolicy {
+    /// Reject messages with more than one value, even if they are equal.
+    Reject,
+    /// Use the first value, ignoring the others.
+    ///
+    /// Servers also leave the other `Content-Length` headers out of the
+    /// request.
+    FirstWins,
+    /// Accept multiple values if they are all equal, rejecting the message
+    /// otherwise.
+    #[default]
+    MergeIfEqual,
+}
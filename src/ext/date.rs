@@ -0,0 +1,211 @@
+//! Parsing and formatting HTTP dates without allocating.
+//!
+//! Headers such as `Date`, `Last-Modified`, `Expires` and
+//! `If-Modified-Since` carry an HTTP-date ([RFC 9110, Section 5.6.7][date]).
+//! Senders must use the IMF-fixdate format, while recipients must also
+//! accept the obsolete RFC 850 and asctime formats:
+//!
+//! ```text
+//! Sun, 06 Nov 1994 08:49:37 GMT    ; IMF-fixdate
+//! Sunday, 06-Nov-94 08:49:37 GMT   ; RFC 850
+//! Sun Nov  6 08:49:37 1994         ; asctime
+//! ```
+//!
+//! [date]: https://www.rfc-editor.org/rfc/rfc9110#section-5.6.7
+//!
+//! # Example
+//!
+//! ```
+//! use fluxio::ext::date;
+//! use fluxio::header::HeaderValue;
+//!
+//! let value = HeaderValue::from_static("Sunday, 06-Nov-94 08:49:37 GMT");
+//! let time = date::parse(value.as_bytes()).unwrap();
+//!
+//! let mut buf = [0; date::HTTP_DATE_LEN];
+//! assert_eq!(date::format(time, &mut buf), Some("Sun, 06 Nov 1994 08:49:37 GMT"));
+//! ```
+
+use std::fmt::{self, Write};
+use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use httpdate::HttpDate;
+
+/// The length of a date in the IMF-fixdate format.
+///
+/// This is `"Sun, 06 Nov 1994 08:49:37 GMT".len()`.
+pub const HTTP_DATE_LEN: usize = 29;
+
+/// The first second of the year 10000, which can't be formatted.
+const MAX_SECS: u64 = 253_402_300_800;
+
+/// Parses an HTTP-date in any of its three formats.
+///
+/// Surrounding whitespace is ignored. Returns `None` if `value` isn't a
+/// valid date, or is a date before 1970 or after 9999.
+pub fn parse(value: &[u8]) -> Option<SystemTime> {
+    let value = str::from_utf8(value).ok()?;
+    value.parse::<HttpDate>().ok().map(SystemTime::from)
+}
+
+/// Formats `time` as an IMF-fixdate into `buf`, returning it as a string.
+///
+/// Returns `None` if `time` is before 1970 or after 9999, which an
+/// HTTP-date can't represent. Sub-second precision is discarded.
+pub fn format(time: SystemTime, buf: &mut [u8; HTTP_DATE_LEN]) -> Option<&str> {
+    let secs = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    if secs >= MAX_SECS {
+        return None;
+    }
+
+    let mut writer = BufWriter { buf, pos: 0 };
+    write!(writer, "{}", HttpDate::from(time)).ok()?;
+    debug_assert_eq!(writer.pos, HTTP_DATE_LEN);
+    str::from_utf8(&writer.buf[..]).ok()
+}
+
+/// Sends a response without the `Date` header a server adds.
+///
+/// Servers add a `Date` header with the current time, formatted at most
+/// once per second, to responses that don't have one. A response setting
+/// its own `Date` header is sent with that one instead, and a response with
+/// this in its extensions is sent without any.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::ext::date::OmitDate;
+/// use fluxio::{Body, Response};
+///
+/// let mut res = Response::new(Body::empty());
+/// res.extensions_mut().insert(OmitDate);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OmitDate;
+
+struct BufWriter<'a> {
+    buf: &'a mut [u8; HTTP_DATE_LEN],
+    pos: usize,
+}
+
+impl fmt::Write for BufWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.pos + s.len();
+        if end > HTTP_DATE_LEN {
+            return Err(fmt::Error);
+        }
+        self.buf[self.pos..end].copy_from_slice(s.as_bytes());
+        self.pos = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[cfg(feature = "nightly")]
+    use test::Bencher;
+
+    use super::*;
+
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    fn nov_6_1994() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(784_111_777)
+    }
+
+    #[test]
+    fn parse_all_formats() {
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "  Sun, 06 Nov 1994 08:49:37 GMT ",
+        ] {
+            assert_eq!(parse(value.as_bytes()), Some(nov_6_1994()), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for value in [
+            &b""[..],
+            b"yesterday",
+            b"Sun, 06 Nov 1994 08:49:37 UTC",
+            b"Sun, 31 Feb 1994 08:49:37 GMT",
+            b"Sun, 06 Nov 1969 08:49:37 GMT",
+            b"Sun, 06 Nov 1994 08:49:37 GMT\xff",
+        ] {
+            assert_eq!(parse(value), None, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn format_roundtrip() {
+        let mut buf = [0; HTTP_DATE_LEN];
+        let time = nov_6_1994() + Duration::from_millis(500);
+        assert_eq!(
+            format(time, &mut buf),
+            Some("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+        assert_eq!(parse(&buf), Some(nov_6_1994()));
+
+        assert_eq!(
+            format(UNIX_EPOCH, &mut buf),
+            Some("Thu, 01 Jan 1970 00:00:00 GMT")
+        );
+    }
+
+    #[test]
+    fn format_out_of_range() {
+        let mut buf = [0; HTTP_DATE_LEN];
+        let before = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(format(before, &mut buf), None);
+
+        let last = UNIX_EPOCH + Duration::from_secs(MAX_SECS - 1);
+        assert_eq!(
+            format(last, &mut buf),
+            Some("Fri, 31 Dec 9999 23:59:59 GMT")
+        );
+        let after = UNIX_EPOCH + Duration::from_secs(MAX_SECS);
+        assert_eq!(format(after, &mut buf), None);
+    }
+
+    #[cfg(feature = "nightly")]
+    #[bench]
+    fn bench_parse(b: &mut Bencher) {
+        b.iter(|| parse(test::black_box(b"Sun, 06 Nov 1994 08:49:37 GMT")));
+    }
+
+    #[cfg(feature = "nightly")]
+    #[bench]
+    fn bench_parse_string(b: &mut Bencher) {
+        // The same parse, from an owned `String` as it would be with an
+        // allocating API.
+        b.iter(|| {
+            let value = String::from(test::black_box("Sun, 06 Nov 1994 08:49:37 GMT"));
+            httpdate::parse_http_date(&value).ok()
+        });
+    }
+
+    #[cfg(feature = "nightly")]
+    #[bench]
+    fn bench_format(b: &mut Bencher) {
+        let mut buf = [0; HTTP_DATE_LEN];
+        let now = SystemTime::now();
+        b.bytes = HTTP_DATE_LEN as u64;
+        b.iter(|| {
+            format(test::black_box(now), &mut buf);
+            test::black_box(&buf);
+        });
+    }
+
+    #[cfg(feature = "nightly")]
+    #[bench]
+    fn bench_format_string(b: &mut Bencher) {
+        let now = SystemTime::now();
+        b.bytes = HTTP_DATE_LEN as u64;
+        b.iter(|| httpdate::fmt_http_date(test::black_box(now)));
+    }
+}
@@ -0,0 +1,142 @@
+use tokio::sync::mpsc;
+
+/// Sends the `100 Continue` response a request is waiting for before sending
+/// its body.
+///
+/// A client sending `Expect: 100-continue` waits for the server to accept
+/// the request before sending its body, so the request can be rejected
+/// without transferring the body first.
+///
+/// This is found in the extensions of such requests when received on a
+/// connection with
+/// [`ExpectContinue::Defer`](crate::server::conn::ExpectContinue::Defer).
+/// Reading the body doesn't send `100 Continue` then, and a client may
+/// only send the body after waiting for a while.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::ext::Expect100;
+/// use fluxio::header::CONTENT_LENGTH;
+/// use fluxio::{Body, Request, Response, StatusCode};
+///
+/// async fn handle(req: Request<Body>) -> Result<Response<Body>, fluxio::Error> {
+///     let too_large = req
+///         .headers()
+///         .get(CONTENT_LENGTH)
+///         .and_then(|len| len.to_str().ok()?.parse::<u64>().ok())
+///         .map_or(true, |len| len > 1024 * 1024);
+///     if too_large {
+///         let mut res = Response::new(Body::empty());
+///         *res.status_mut() = StatusCode::PAYLOAD_TOO_LARGE;
+///         return Ok(res);
+///     }
+///     if let Some(expect) = req.extensions().get::<Expect100>() {
+///         expect.send_continue();
+///     }
+///     let body = fluxio::body::to_bytes(req.into_body()).await?;
+///     Ok(Response::new(Body::from(body)))
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Expect100 {
+    tx: mpsc::UnboundedSender<()>,
+}
+
+/// The receiving half of an [`Expect100`], polled by the connection.
+pub(crate) type Continue = mpsc::UnboundedReceiver<()>;
+
+impl Expect100 {
+    pub(crate) fn channel() -> (Expect100, Continue) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Expect100 { tx }, rx)
+    }
+
+    /// Sends the `100 Continue` response, accepting the request body.
+    ///
+    /// The response is written once the connection is polled again. Does
+    /// nothing if it was sent already, or if the final response was.
+    pub fn send_continue(&self) {
+        let _ = self.tx.send(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use futures_util::FutureExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::server::conn::{ExpectContinue, Http};
+    use crate::service::service_fn;
+    use crate::{Body, Request, Response};
+
+    const REQUEST: &[u8] =
+        b"POST / HTTP/1.1\r\nhost: example.com\r\nexpect: 100-continue\r\ncontent-length: 5\r\n\r\n";
+
+    #[tokio::test]
+    async fn defer_sends_continue_when_asked() {
+        let (handles, mut rx) = mpsc::unbounded_channel();
+        let svc = service_fn(move |req: Request<Body>| {
+            handles
+                .send(req.extensions().get::<Expect100>().unwrap().clone())
+                .unwrap();
+            async move {
+                let body = crate::body::to_bytes(req.into_body()).await?;
+                Ok::<_, crate::Error>(Response::new(Body::from(body)))
+            }
+        });
+
+        let mut http = Http::new();
+        http.http1_expect_continue(ExpectContinue::Defer);
+        let (mut client, io) = tokio::io::duplex(1024);
+        tokio::spawn(http.serve_connection(io, svc));
+
+        client.write_all(REQUEST).await.unwrap();
+        let expect = rx.recv().await.unwrap();
+        // reading the body doesn't send it
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        let mut buf = [0; 1024];
+        assert!(client.read(&mut buf).now_or_never().is_none());
+
+        expect.send_continue();
+        let n = client.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"HTTP/1.1 100 Continue\r\n\r\n");
+
+        client.write_all(b"hello").await.unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(buf[..n].ends_with(b"\r\n\r\nhello"));
+    }
+
+    #[tokio::test]
+    async fn reject_responds_without_service() {
+        let svc = service_fn(|_req: Request<Body>| async {
+            panic!("service called");
+            #[allow(unreachable_code)]
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        });
+
+        let mut http = Http::new();
+        http.http1_expect_continue(ExpectContinue::Reject);
+        let (mut client, io) = tokio::io::duplex(1024);
+        tokio::spawn(http.serve_connection(io, svc));
+
+        client.write_all(REQUEST).await.unwrap();
+        // the connection closes after the response
+        let mut res = Vec::new();
+        client.read_to_end(&mut res).await.unwrap();
+        let res = String::from_utf8(res).unwrap();
+        assert!(
+            res.starts_with("HTTP/1.1 417 Expectation Failed\r\n"),
+            "{}",
+            res
+        );
+        assert!(res.contains("\r\nconnection: close\r\n"), "{}", res);
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::sync::Arc;
+
+use http::{HeaderMap, StatusCode, Version};
+
+/// Sets a callback called with each informational (1xx) response received
+/// for this request, such as `103 Early Hints` or `102 Processing`.
+///
+/// The callback is called before the final response is returned, while the
+/// connection is reading it. It should return quickly, and copy whatever it
+/// needs to keep, since the response is only borrowed for the call.
+///
+/// This is supported for HTTP/1 connections. `101 Switching Protocols` is a
+/// final response instead, see [`upgrade`](crate::upgrade).
+///
+/// # Example
+///
+/// ```
+/// use fluxio::{Body, Request};
+///
+/// let mut req = Request::new(Body::empty());
+/// fluxio::ext::on_informational(&mut req, |res| {
+///     if res.status().as_u16() == 103 {
+///         for link in res.headers().get_all("link") {
+///             println!("preload hint: {:?}", link);
+///         }
+///     }
+/// });
+/// ```
+pub fn on_informational<B, F>(req: &mut http::Request<B>, callback: F)
+where
+    F: Fn(InformationalResponse<'_>) + Send + Sync + 'static,
+{
+    on_informational_raw(req, OnInformationalClosure(callback));
+}
+
+pub(crate) fn on_informational_raw<B, C>(req: &mut http::Request<B>, callback: C)
+where
+    C: OnInformationalCallback + Send + Sync + 'static,
+{
+    req.extensions_mut()
+        .insert(OnInformational(Arc::new(callback)));
+}
+
+/// An informational (1xx) response passed to [`on_informational`].
+pub struct InformationalResponse<'a>(&'a http::Response<()>);
+
+impl InformationalResponse<'_> {
+    /// The status code of the response, in the `1xx` range.
+    pub fn status(&self) -> StatusCode {
+        self.0.status()
+    }
+
+    /// The HTTP version of the response.
+    pub fn version(&self) -> Version {
+        self.0.version()
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        self.0.headers()
+    }
+}
+
+impl fmt::Debug for InformationalResponse<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InformationalResponse")
+            .field("status", &self.status())
+            .field("version", &self.version())
+            .field("headers", self.headers())
+            .finish()
+    }
+}
+
+pub(crate) trait OnInformationalCallback {
+    fn on_informational(&self, res: http::Response<()>);
+}
+
+/// The request extension holding the callback of [`on_informational`].
+#[derive(Clone)]
+pub(crate) struct OnInformational(Arc<dyn OnInformationalCallback + Send + Sync>);
+
+impl OnInformational {
+    pub(crate) fn call(&self, res: http::Response<()>) {
+        self.0.on_informational(res);
+    }
+}
+
+impl fmt::Debug for OnInformational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnInformational").finish()
+    }
+}
+
+struct OnInformationalClosure<F>(F);
+
+impl<F> OnInformationalCallback for OnInformationalClosure<F>
+where
+    F: Fn(InformationalResponse<'_>),
+{
+    fn on_informational(&self, res: http::Response<()>) {
+        (self.0)(InformationalResponse(&res));
+    }
+}
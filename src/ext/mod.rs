@@ -7,8 +7,74 @@ use http::header::{IntoHeaderName, ValueIter};
 use http::HeaderMap;
 #[cfg(feature = "ffi")]
 use std::collections::HashMap;
-#[cfg(feature = "http2")]
+#[cfg(any(feature = "http2", feature = "ffi"))]
 use std::fmt;
+#[cfg(feature = "ffi")]
+use std::sync::Arc;
+
+pub mod accept;
+#[cfg(feature = "http1")]
+mod body_framing;
+#[cfg(feature = "http1")]
+mod chunk_extensions;
+#[cfg(feature = "server")]
+mod connect_info;
+#[cfg(feature = "http1")]
+mod content_length;
+pub mod date;
+#[cfg(all(feature = "server", feature = "http1"))]
+mod expect;
+#[cfg(all(feature = "client", feature = "http1"))]
+mod informational;
+#[cfg(feature = "http1")]
+mod reason_phrase;
+#[cfg(feature = "client")]
+mod request_target;
+#[cfg(all(feature = "client", any(feature = "http1", feature = "http2")))]
+mod request_timings;
+pub mod sse;
+
+#[cfg(feature = "http1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+pub use self::body_framing::BodyFraming;
+#[cfg(feature = "http1")]
+pub(crate) use self::body_framing::BodyFramingFn;
+#[cfg(all(feature = "server", feature = "http1"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "server", feature = "http1"))))]
+pub use self::chunk_extensions::ChunkExtensionsHook;
+#[cfg(feature = "http1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+pub use self::chunk_extensions::{ChunkExtensions, InvalidChunkExtensions};
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub use self::connect_info::{ConnectInfo, TlsInfo};
+#[cfg(feature = "http1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+pub use self::content_length::ContentLengthPolicy;
+#[cfg(all(feature = "server", feature = "http1"))]
+pub(crate) use self::expect::Continue;
+#[cfg(all(feature = "server", feature = "http1"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "server", feature = "http1"))))]
+pub use self::expect::Expect100;
+#[cfg(all(feature = "client", feature = "http1"))]
+pub(crate) use self::informational::OnInformational;
+#[cfg(all(feature = "client", feature = "http1"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "client", feature = "http1"))))]
+pub use self::informational::{on_informational, InformationalResponse};
+#[cfg(feature = "ffi")]
+pub(crate) use self::informational::{on_informational_raw, OnInformationalCallback};
+#[cfg(feature = "http1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+pub use self::reason_phrase::{InvalidReasonPhrase, ReasonPhrase};
+#[cfg(feature = "client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client")))]
+pub use self::request_target::RequestTargetForm;
+#[cfg(all(feature = "client", any(feature = "http1", feature = "http2")))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "client", any(feature = "http1", feature = "http2"))))
+)]
+pub use self::request_timings::RequestTimings;
 
 #[cfg(feature = "http2")]
 /// Represents the `:protocol` pseudo-header used by
@@ -125,6 +191,33 @@ impl HeaderCaseMap {
     }
 }
 
+/// A callback inspecting the serialized head of a request right before it
+/// is written, returning whether to send it.
+#[cfg(feature = "ffi")]
+#[derive(Clone)]
+pub(crate) struct OnRequestHead(Arc<dyn Fn(&[u8]) -> bool + Send + Sync>);
+
+#[cfg(feature = "ffi")]
+impl OnRequestHead {
+    pub(crate) fn new<F>(callback: F) -> OnRequestHead
+    where
+        F: Fn(&[u8]) -> bool + Send + Sync + 'static,
+    {
+        OnRequestHead(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, head: &[u8]) -> bool {
+        (self.0)(head)
+    }
+}
+
+#[cfg(feature = "ffi")]
+impl fmt::Debug for OnRequestHead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OnRequestHead").finish()
+    }
+}
+
 #[cfg(feature = "ffi")]
 #[derive(Clone, Debug)]
 /// Hashmap<Headername, numheaders with that name>
@@ -220,3 +313,102 @@ impl OriginalHeaderOrder {
         self.entry_order.iter()
     }
 }
+
+/// The raw bytes of a received HTTP/1 message head.
+///
+/// If a request is parsed on a server connection whose option
+/// [`http1_headers_raw`] was set to true, this is stored in the request
+/// extensions. It holds the request line and header block exactly as they
+/// were received, including the final empty line, such as for verifying
+/// signatures computed over the original bytes, or debugging.
+///
+/// [`http1_headers_raw`]: crate::server::conn::Http::http1_headers_raw
+#[cfg(feature = "http1")]
+#[derive(Clone, Debug)]
+pub struct RawHeaders(Bytes);
+
+#[cfg(feature = "http1")]
+impl RawHeaders {
+    #[cfg(any(feature = "client", feature = "server"))]
+    pub(crate) fn new(raw: Bytes) -> RawHeaders {
+        RawHeaders(raw)
+    }
+
+    /// The received bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consumes this, returning the received bytes.
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+#[cfg(feature = "http1")]
+impl AsRef<[u8]> for RawHeaders {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// The raw bytes of an HTTP/1 request head, exactly as they were received.
+///
+/// If a request is parsed on a server connection whose option
+/// [`http1_preserve_raw_head`] was set to true, this is stored in the
+/// request extensions, together with a snapshot of the method, URI, version
+/// and headers those bytes decoded to.
+///
+/// When such a request is later sent on a client HTTP/1 connection, the raw
+/// bytes are written back out verbatim, preserving casing, order and
+/// whitespace, as long as the message still matches the snapshot. If
+/// anything about the head was modified, the request is serialized as usual.
+///
+/// [`http1_preserve_raw_head`]: crate::server::conn::Http::http1_preserve_raw_head
+#[cfg(all(feature = "http1", feature = "client", feature = "server"))]
+#[derive(Clone, Debug)]
+pub(crate) struct OriginalHead {
+    raw: Bytes,
+    method: http::Method,
+    uri: http::Uri,
+    version: http::Version,
+    headers: HeaderMap,
+}
+
+#[cfg(all(feature = "http1", feature = "client", feature = "server"))]
+impl OriginalHead {
+    pub(crate) fn new(
+        raw: Bytes,
+        method: http::Method,
+        uri: http::Uri,
+        version: http::Version,
+        headers: HeaderMap,
+    ) -> Self {
+        OriginalHead {
+            raw,
+            method,
+            uri,
+            version,
+            headers,
+        }
+    }
+
+    /// Returns the raw head if the given message is still what it decoded to.
+    pub(crate) fn raw_if_unchanged(
+        &self,
+        method: &http::Method,
+        uri: &http::Uri,
+        version: http::Version,
+        headers: &HeaderMap,
+    ) -> Option<&Bytes> {
+        if self.version == version
+            && self.method == method
+            && self.uri == *uri
+            && self.headers == *headers
+        {
+            Some(&self.raw)
+        } else {
+            None
+        }
+    }
+}
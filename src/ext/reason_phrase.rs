@@ -0,0 +1,180 @@
+use std::fmt;
+
+use bytes::Bytes;
+
+/// A reason phrase of an HTTP/1 response.
+///
+/// # Clients
+///
+/// For responses received by a client, this extension is present when the
+/// reason phrase differs from the canonical one of the status code, as
+/// returned by [`StatusCode::canonical_reason`](http::StatusCode::canonical_reason).
+///
+/// # Servers
+///
+/// When present in the extensions of a response sent by a server, this
+/// reason phrase is written instead of the canonical one. This is only
+/// needed for legacy clients expecting a particular phrase, since RFC 9112
+/// recommends clients ignore it.
+///
+/// HTTP/2 has no reason phrases, so this is ignored there.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::ext::ReasonPhrase;
+/// use fluxio::{Body, Response};
+///
+/// let mut res = Response::new(Body::empty());
+/// res.extensions_mut()
+///     .insert(ReasonPhrase::from_static(b"Very OK"));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ReasonPhrase(Bytes);
+
+/// An error converting bytes with a character not allowed in a reason phrase.
+///
+/// Reason phrases can contain horizontal tabs, spaces, visible US-ASCII
+/// characters, and bytes above `0x7F` ([RFC 9112, Section 4][rfc]).
+///
+/// [rfc]: https://www.rfc-editor.org/rfc/rfc9112#section-4
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidReasonPhrase {
+    bad_byte: u8,
+}
+
+impl ReasonPhrase {
+    /// Converts a static byte slice to a reason phrase.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the slice contains a byte not allowed in a reason
+    /// phrase. Used in a `const`, this fails to compile instead.
+    pub const fn from_static(reason: &'static [u8]) -> ReasonPhrase {
+        if find_invalid_byte(reason).is_some() {
+            panic!("invalid byte in static reason phrase");
+        }
+        ReasonPhrase(Bytes::from_static(reason))
+    }
+
+    /// The bytes of this reason phrase.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Converts bytes parsed from a response, which were already checked.
+    #[cfg(feature = "client")]
+    pub(crate) fn from_bytes_unchecked(reason: Bytes) -> ReasonPhrase {
+        ReasonPhrase(reason)
+    }
+}
+
+impl AsRef<[u8]> for ReasonPhrase {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ReasonPhrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReasonPhrase")
+            .field(&String::from_utf8_lossy(&self.0))
+            .finish()
+    }
+}
+
+impl TryFrom<Bytes> for ReasonPhrase {
+    type Error = InvalidReasonPhrase;
+
+    fn try_from(reason: Bytes) -> Result<ReasonPhrase, InvalidReasonPhrase> {
+        match find_invalid_byte(&reason) {
+            Some(bad_byte) => Err(InvalidReasonPhrase { bad_byte }),
+            None => Ok(ReasonPhrase(reason)),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for ReasonPhrase {
+    type Error = InvalidReasonPhrase;
+
+    fn try_from(reason: &[u8]) -> Result<ReasonPhrase, InvalidReasonPhrase> {
+        ReasonPhrase::try_from(Bytes::copy_from_slice(reason))
+    }
+}
+
+impl TryFrom<Vec<u8>> for ReasonPhrase {
+    type Error = InvalidReasonPhrase;
+
+    fn try_from(reason: Vec<u8>) -> Result<ReasonPhrase, InvalidReasonPhrase> {
+        ReasonPhrase::try_from(Bytes::from(reason))
+    }
+}
+
+impl TryFrom<String> for ReasonPhrase {
+    type Error = InvalidReasonPhrase;
+
+    fn try_from(reason: String) -> Result<ReasonPhrase, InvalidReasonPhrase> {
+        ReasonPhrase::try_from(Bytes::from(reason))
+    }
+}
+
+impl fmt::Display for InvalidReasonPhrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid byte in reason phrase: {:#04x}", self.bad_byte)
+    }
+}
+
+impl std::error::Error for InvalidReasonPhrase {}
+
+const fn is_valid_byte(b: u8) -> bool {
+    // HTAB / SP / VCHAR / obs-text
+    b == b'\t' || (b >= b' ' && b != 0x7F)
+}
+
+const fn find_invalid_byte(bytes: &[u8]) -> Option<u8> {
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if !is_valid_byte(b) {
+            return Some(b);
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_reason_phrases() {
+        const VERY_OK: ReasonPhrase = ReasonPhrase::from_static(b"Very OK");
+        assert_eq!(VERY_OK.as_bytes(), b"Very OK");
+
+        for reason in [&b""[..], b"Tab\tand obs-text \xE9", b"~!@#$%^&*()"] {
+            let phrase = ReasonPhrase::try_from(reason).unwrap();
+            assert_eq!(phrase.as_bytes(), reason);
+        }
+    }
+
+    #[test]
+    fn invalid_reason_phrases() {
+        for (reason, bad_byte) in [
+            (&b"Line\r\nInjected: 1"[..], b'\r'),
+            (b"Nul\0", 0),
+            (b"Del\x7F", 0x7F),
+        ] {
+            assert_eq!(
+                ReasonPhrase::try_from(reason),
+                Err(InvalidReasonPhrase { bad_byte })
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_static_invalid() {
+        let _ = ReasonPhrase::from_static(b"Bad\n");
+    }
+}
@@ -0,0 +1,31 @@
+/// The form of the target of an HTTP/1 request sent by a `Client`.
+///
+/// By default, the `Client` sends requests in origin-form
+/// (`GET /path HTTP/1.1`), or in absolute-form
+/// (`GET http://host/path HTTP/1.1`) on connections whose
+/// [`Connected`](crate::client::connect::Connected) was marked as proxied,
+/// unless the request is to an `https` URI. Inserting this in the extensions
+/// of a request picks the form regardless, such as for a forward proxy
+/// relaying requests it received in absolute-form.
+///
+/// `CONNECT` requests are always sent in authority-form
+/// (`CONNECT host:port HTTP/1.1`), and HTTP/2 requests aren't affected.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::ext::RequestTargetForm;
+/// use fluxio::{Body, Request};
+///
+/// let mut req = Request::get("http://example.com/path")
+///     .body(Body::empty())
+///     .unwrap();
+/// req.extensions_mut().insert(RequestTargetForm::Absolute);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestTargetForm {
+    /// Send only the path and query of the URI.
+    Origin,
+    /// Send the whole URI, with its scheme and authority.
+    Absolute,
+}
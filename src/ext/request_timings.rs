@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+/// How long sending a request took, and on which kind of connection.
+///
+/// The `Client` inserts this into the extensions of every response. The
+/// durations of establishing the connection are only known for the request
+/// that caused it to be established, and are `None` for requests on a reused
+/// pooled connection.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "runtime")]
+/// # async fn doc() -> fluxio::Result<()> {
+/// use fluxio::ext::RequestTimings;
+/// use fluxio::{Client, Uri};
+///
+/// let client = Client::new();
+/// let res = client.get(Uri::from_static("http://example.com")).await?;
+/// if let Some(timings) = res.extensions().get::<RequestTimings>() {
+///     println!(
+///         "reused = {}, ttfb = {:?}",
+///         timings.is_reused(),
+///         timings.time_to_first_byte()
+///     );
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestTimings {
+    dns: Option<Duration>,
+    connect: Option<Duration>,
+    tls: Option<Duration>,
+    time_to_first_byte: Duration,
+    reused: bool,
+}
+
+impl RequestTimings {
+    pub(crate) fn new(time_to_first_byte: Duration, reused: bool) -> RequestTimings {
+        RequestTimings {
+            dns: None,
+            connect: None,
+            tls: None,
+            time_to_first_byte,
+            reused,
+        }
+    }
+
+    pub(crate) fn with_connect(
+        mut self,
+        dns: Option<Duration>,
+        connect: Duration,
+        tls: Option<Duration>,
+    ) -> RequestTimings {
+        self.dns = dns;
+        self.connect = Some(connect);
+        self.tls = tls;
+        self
+    }
+
+    /// How long resolving the destination took.
+    ///
+    /// This is `None` if the connector didn't report it, such as when the
+    /// destination was an IP address.
+    pub fn dns(&self) -> Option<Duration> {
+        self.dns
+    }
+
+    /// How long the connector took to establish the connection, including
+    /// resolving the destination and any TLS handshake.
+    pub fn connect(&self) -> Option<Duration> {
+        self.connect
+    }
+
+    /// How long the TLS handshake took, if the connector reported it.
+    pub fn tls(&self) -> Option<Duration> {
+        self.tls
+    }
+
+    /// How long it took from sending the request to receiving the head of
+    /// the response.
+    pub fn time_to_first_byte(&self) -> Duration {
+        self.time_to_first_byte
+    }
+
+    /// Whether the request was sent on a connection that had been used
+    /// before.
+    pub fn is_reused(&self) -> bool {
+        self.reused
+    }
+}
@@ -0,0 +1,589 @@
+//! Server-Sent Events.
+//!
+//! An event stream is a `text/event-stream` body of events, as specified
+//! by the [HTML Living Standard][spec]. Clients parse a response body into
+//! [`Event`]s with an [`EventStream`], and servers write them into a
+//! response body with an [`SseBody`].
+//!
+//! [spec]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+//!
+//! # Example
+//!
+//! ```
+//! # async fn doc() -> fluxio::Result<()> {
+//! use fluxio::ext::sse::{Event, EventStream, SseBody};
+//!
+//! let (mut events, body) = SseBody::channel();
+//! tokio::spawn(async move {
+//!     events.send(&Event::new("hello").with_id("1")).await?;
+//!     events.send(&Event::new("world").with_event("greeting")).await
+//! });
+//!
+//! let mut stream = EventStream::new(body);
+//! while let Some(event) = stream.next_event().await {
+//!     let event = event?;
+//!     println!("{}: {}", event.event_type(), event.data());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http::header::{HeaderName, HeaderValue, ACCEPT};
+use http::Request;
+use http_body::Body as HttpBody;
+
+use crate::body::{Body, Sender};
+use crate::common::{task, Future, Pin, Poll};
+
+/// The media type of event streams.
+pub const CONTENT_TYPE: &str = "text/event-stream";
+
+const LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+
+const DEFAULT_MAX_LINE_SIZE: usize = 256 * 1024;
+const DEFAULT_MAX_EVENT_SIZE: usize = 1024 * 1024;
+
+/// An event of an event stream.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Creates an event with some data.
+    pub fn new(data: impl Into<String>) -> Event {
+        Event {
+            data: data.into(),
+            ..Event::default()
+        }
+    }
+
+    /// Sets the type of this event.
+    ///
+    /// Line breaks are replaced with spaces, as they can't be sent.
+    pub fn with_event(mut self, event: impl Into<String>) -> Event {
+        self.event = Some(single_line(event.into()));
+        self
+    }
+
+    /// Sets the id of this event.
+    ///
+    /// Line breaks are replaced with spaces, as they can't be sent.
+    pub fn with_id(mut self, id: impl Into<String>) -> Event {
+        self.id = Some(single_line(id.into()));
+        self
+    }
+
+    /// Sets the time clients should wait before reconnecting.
+    pub fn with_retry(mut self, retry: Duration) -> Event {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// The id of this event.
+    ///
+    /// For received events, this is the last id the stream sent, which may
+    /// be the id of an earlier event.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// The type of this event, `message` if it wasn't set.
+    pub fn event_type(&self) -> &str {
+        self.event.as_deref().unwrap_or("message")
+    }
+
+    /// The data of this event.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// The reconnection time sent along with this event, if any.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    #[cfg(feature = "ffi")]
+    pub(crate) fn into_data(self) -> String {
+        self.data
+    }
+
+    fn encode(&self) -> Bytes {
+        let mut buf = String::new();
+        if let Some(retry) = self.retry {
+            buf.push_str(&format!("retry: {}\n", retry.as_millis()));
+        }
+        if let Some(ref id) = self.id {
+            buf.push_str(&format!("id: {}\n", id));
+        }
+        if let Some(ref event) = self.event {
+            buf.push_str(&format!("event: {}\n", event));
+        }
+        let data = self.data.replace("\r\n", "\n").replace('\r', "\n");
+        for line in data.split('\n') {
+            buf.push_str("data: ");
+            buf.push_str(line);
+            buf.push('\n');
+        }
+        buf.push('\n');
+        Bytes::from(buf)
+    }
+}
+
+fn single_line(s: String) -> String {
+    if s.contains(['\r', '\n']) {
+        s.replace(['\r', '\n'], " ")
+    } else {
+        s
+    }
+}
+
+/// Parses a response body into events.
+///
+/// The stream keeps the state needed to reconnect once the body ends: the
+/// id of the last event, and the reconnection time asked by the server.
+/// Pass a new request to [`prepare_request`] before sending it, to resume
+/// the stream where it stopped.
+///
+/// Lines and events larger than the limits set with
+/// [`with_max_line_size`] and [`with_max_event_size`] fail the stream with
+/// an error, so a misbehaving server can't make it buffer without bound.
+///
+/// [`prepare_request`]: EventStream::prepare_request
+/// [`with_max_line_size`]: EventStream::with_max_line_size
+/// [`with_max_event_size`]: EventStream::with_max_event_size
+pub struct EventStream {
+    body: Body,
+    parser: Parser,
+    events: VecDeque<Event>,
+    error: Option<crate::Error>,
+    done: bool,
+}
+
+impl EventStream {
+    /// Parses the events of `body`.
+    pub fn new(body: Body) -> EventStream {
+        EventStream {
+            body,
+            parser: Parser::default(),
+            events: VecDeque::new(),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Sets the maximum size of a line of the stream, in bytes.
+    ///
+    /// Default is 256 KiB.
+    pub fn with_max_line_size(mut self, max: usize) -> EventStream {
+        self.parser.max_line_size = max;
+        self
+    }
+
+    /// Sets the maximum size of the data of an event, in bytes.
+    ///
+    /// Default is 1 MiB.
+    pub fn with_max_event_size(mut self, max: usize) -> EventStream {
+        self.parser.max_event_size = max;
+        self
+    }
+
+    /// Polls the next event of the stream.
+    pub fn poll_next_event(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<crate::Result<Event>>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            // The events before an error are still returned first.
+            if let Some(err) = self.error.take() {
+                return Poll::Ready(Some(Err(err)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+            match ready!(Pin::new(&mut self.body).poll_data(cx)) {
+                Some(Ok(chunk)) => {
+                    let events = &mut self.events;
+                    let fed = self.parser.feed(&chunk, |event| {
+                        events.push_back(event);
+                        ControlFlow::Continue(())
+                    });
+                    if let Err(err) = fed {
+                        self.done = true;
+                        self.error = Some(err);
+                    }
+                }
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                // An incomplete event at the end is discarded.
+                None => self.done = true,
+            }
+        }
+    }
+
+    /// Waits for the next event of the stream.
+    ///
+    /// Returns `None` once the body has ended.
+    pub fn next_event(&mut self) -> impl Future<Output = Option<crate::Result<Event>>> + '_ {
+        futures_util::future::poll_fn(move |cx| self.poll_next_event(cx))
+    }
+
+    /// The id of the last event received, to resume the stream from.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.last_event_id()
+    }
+
+    /// The time the server asked clients to wait before reconnecting.
+    pub fn retry(&self) -> Option<Duration> {
+        self.parser.retry
+    }
+
+    /// Prepares a request for this stream, to reconnect it.
+    ///
+    /// This sets the `Accept` header to `text/event-stream`, and the
+    /// `Last-Event-ID` header if an event id was received.
+    pub fn prepare_request<B>(&self, req: &mut Request<B>) {
+        let headers = req.headers_mut();
+        headers.insert(ACCEPT, HeaderValue::from_static(CONTENT_TYPE));
+        match self
+            .last_event_id()
+            .and_then(|id| HeaderValue::from_str(id).ok())
+        {
+            Some(id) => headers.insert(LAST_EVENT_ID, id),
+            None => headers.remove(LAST_EVENT_ID),
+        };
+    }
+}
+
+impl fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream")
+            .field("last_event_id", &self.last_event_id())
+            .field("retry", &self.retry())
+            .finish()
+    }
+}
+
+/// # Optional
+///
+/// This implementation requires enabling the `stream` feature in your
+/// `Cargo.toml`.
+#[cfg(feature = "stream")]
+impl futures_core::Stream for EventStream {
+    type Item = crate::Result<Event>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_event(cx)
+    }
+}
+
+/// Writes events into a response body.
+///
+/// The response should have a `Content-Type` of [`CONTENT_TYPE`]. Dropping
+/// the `SseBody` ends the body.
+#[derive(Debug)]
+pub struct SseBody {
+    tx: Sender,
+}
+
+impl SseBody {
+    /// Creates an `SseBody` and the response body it writes into.
+    pub fn channel() -> (SseBody, Body) {
+        let (tx, body) = Body::channel();
+        (SseBody { tx }, body)
+    }
+
+    /// Sends an event.
+    pub async fn send(&mut self, event: &Event) -> crate::Result<()> {
+        self.tx.send_data(event.encode()).await
+    }
+
+    /// Sends a comment, which clients ignore.
+    ///
+    /// Sending one periodically keeps idle connections from timing out.
+    pub async fn send_comment(&mut self, comment: &str) -> crate::Result<()> {
+        let comment = single_line(comment.to_owned());
+        self.tx
+            .send_data(Bytes::from(format!(": {}\n", comment)))
+            .await
+    }
+
+    /// Aborts the body, so the client sees an error instead of its end.
+    pub fn abort(self) {
+        self.tx.abort();
+    }
+}
+
+// ===== internal =====
+
+/// An incremental parser of event streams.
+pub(crate) struct Parser {
+    max_line_size: usize,
+    max_event_size: usize,
+    // The incomplete last line of the chunks so far.
+    line: Vec<u8>,
+    // A CR ended the last line, so a following LF is part of it.
+    skip_lf: bool,
+    started: bool,
+    data: String,
+    event: Option<String>,
+    event_retry: Option<Duration>,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Default for Parser {
+    fn default() -> Parser {
+        Parser {
+            max_line_size: DEFAULT_MAX_LINE_SIZE,
+            max_event_size: DEFAULT_MAX_EVENT_SIZE,
+            line: Vec::new(),
+            skip_lf: false,
+            started: false,
+            data: String::new(),
+            event: None,
+            event_retry: None,
+            last_event_id: None,
+            retry: None,
+        }
+    }
+}
+
+impl Parser {
+    /// Parses a chunk, calling `on_event` for every complete event, until it
+    /// breaks.
+    ///
+    /// Fails if a line or an event is larger than the limits.
+    pub(crate) fn feed<F>(&mut self, mut chunk: &[u8], mut on_event: F) -> crate::Result<()>
+    where
+        F: FnMut(Event) -> ControlFlow<()>,
+    {
+        while !chunk.is_empty() {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if chunk[0] == b'\n' {
+                    chunk = &chunk[1..];
+                    continue;
+                }
+            }
+            let end = chunk.iter().position(|&b| b == b'\r' || b == b'\n');
+            if self.line.len() + end.unwrap_or(chunk.len()) > self.max_line_size {
+                return Err(crate::Error::new_body("event stream line is too long"));
+            }
+            let end = match end {
+                Some(end) => end,
+                None => {
+                    self.line.extend_from_slice(chunk);
+                    return Ok(());
+                }
+            };
+            self.skip_lf = chunk[end] == b'\r';
+            let event = if self.line.is_empty() {
+                self.process_line(&chunk[..end])
+            } else {
+                self.line.extend_from_slice(&chunk[..end]);
+                let line = std::mem::take(&mut self.line);
+                self.process_line(&line)
+            };
+            if self.data.len() > self.max_event_size {
+                return Err(crate::Error::new_body("event stream event is too large"));
+            }
+            chunk = &chunk[end + 1..];
+            if let Some(event) = event {
+                if on_event(event).is_break() {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref().filter(|id| !id.is_empty())
+    }
+
+    fn process_line(&mut self, mut line: &[u8]) -> Option<Event> {
+        if !self.started {
+            self.started = true;
+            line = line.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(line);
+        }
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line[0] == b':' {
+            return None;
+        }
+        let (field, value) = match line.iter().position(|&b| b == b':') {
+            Some(colon) => {
+                let value = &line[colon + 1..];
+                (&line[..colon], value.strip_prefix(b" ").unwrap_or(value))
+            }
+            None => (line, &b""[..]),
+        };
+        let value = String::from_utf8_lossy(value);
+        match field {
+            b"event" => self.event = Some(value.into_owned()),
+            b"data" => {
+                self.data.push_str(&value);
+                self.data.push('\n');
+            }
+            b"id" if !value.contains('\0') => self.last_event_id = Some(value.into_owned()),
+            b"retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse() {
+                    let retry = Duration::from_millis(millis);
+                    self.retry = Some(retry);
+                    self.event_retry = Some(retry);
+                }
+            }
+            _ => (),
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event = self.event.take();
+        let retry = self.event_retry.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let mut data = std::mem::take(&mut self.data);
+        data.pop();
+        Some(Event {
+            id: self.last_event_id().map(ToOwned::to_owned),
+            event,
+            data,
+            retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&str]) -> Vec<Event> {
+        let mut parser = Parser::default();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            parser
+                .feed(chunk.as_bytes(), |event| {
+                    events.push(event);
+                    ControlFlow::Continue(())
+                })
+                .unwrap();
+        }
+        events
+    }
+
+    #[test]
+    fn parses_fields() {
+        let events = parse(&[
+            "\u{feff}: a comment\n",
+            "event: add\ndata: 1\ndata:2\nid: a\nretry: 500\n\n",
+            "data\n\n",
+            "id\ndata: no id\nunknown: field\n\n",
+            "event: ignored\n\n",
+            "data: incomplete",
+        ]);
+        assert_eq!(
+            events,
+            [
+                Event::new("1\n2")
+                    .with_event("add")
+                    .with_id("a")
+                    .with_retry(Duration::from_millis(500)),
+                Event::new("").with_id("a"),
+                Event::new("no id"),
+            ]
+        );
+        assert_eq!(events[2].event_type(), "message");
+    }
+
+    #[test]
+    fn line_endings_across_chunks() {
+        let events = parse(&["data: a\r", "\ndata: b\r\r", "data: c\n", "\n"]);
+        assert_eq!(events, [Event::new("a\nb"), Event::new("c")]);
+    }
+
+    fn cause(err: &crate::Error) -> String {
+        std::error::Error::source(err).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn limits_line_size() {
+        let body = Body::from("data: 1234\n\ndata: 12345\n\n");
+        let mut stream = EventStream::new(body).with_max_line_size(10);
+        let event = stream.next_event().await.unwrap().unwrap();
+        assert_eq!(event, Event::new("1234"));
+        let err = stream.next_event().await.unwrap().unwrap_err();
+        assert_eq!(cause(&err), "event stream line is too long");
+        assert!(stream.next_event().await.is_none());
+
+        // an unterminated line is limited as it comes in
+        let (mut tx, body) = Body::channel();
+        tokio::spawn(async move {
+            tx.send_data("data: 1234".into()).await.unwrap();
+            tx.send_data("5".into()).await.unwrap();
+        });
+        let mut stream = EventStream::new(body).with_max_line_size(10);
+        let err = stream.next_event().await.unwrap().unwrap_err();
+        assert_eq!(cause(&err), "event stream line is too long");
+    }
+
+    #[tokio::test]
+    async fn limits_event_size() {
+        let body = Body::from("data: 12\ndata: 3\n\ndata: 12\ndata: 34\n\n");
+        let mut stream = EventStream::new(body).with_max_event_size(5);
+        let event = stream.next_event().await.unwrap().unwrap();
+        assert_eq!(event, Event::new("12\n3"));
+        let err = stream.next_event().await.unwrap().unwrap_err();
+        assert_eq!(cause(&err), "event stream event is too large");
+        assert!(stream.next_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn roundtrip_and_reconnect() {
+        let (mut tx, body) = SseBody::channel();
+        let sent = [
+            Event::new("one\ntwo")
+                .with_id("1")
+                .with_retry(Duration::from_secs(3)),
+            Event::new("three").with_event("update\r\n"),
+        ];
+        let to_send = sent.clone();
+        tokio::spawn(async move {
+            for event in &to_send {
+                tx.send(event).await.unwrap();
+            }
+            tx.send_comment("keep-alive").await.unwrap();
+        });
+
+        let mut stream = EventStream::new(body);
+        let first = stream.next_event().await.unwrap().unwrap();
+        assert_eq!(first, sent[0]);
+        let second = stream.next_event().await.unwrap().unwrap();
+        assert_eq!(second.event_type(), "update  ");
+        assert_eq!(second.id(), Some("1"));
+        assert!(stream.next_event().await.is_none());
+
+        assert_eq!(stream.retry(), Some(Duration::from_secs(3)));
+        let mut req = Request::new(());
+        stream.prepare_request(&mut req);
+        assert_eq!(req.headers()[ACCEPT], CONTENT_TYPE);
+        assert_eq!(req.headers()["last-event-id"], "1");
+    }
+}
@@ -1,14 +1,23 @@
 use std::ffi::c_void;
-use std::mem::ManuallyDrop;
+use std::mem::{self, ManuallyDrop};
+use std::ops::ControlFlow;
+use std::pin::Pin;
 use std::ptr;
 use std::task::{Context, Poll};
 
+use bytes::BytesMut;
 use http::HeaderMap;
 use libc::{c_int, size_t};
 
-use super::task::{fluxio_context, fluxio_task, fluxio_task_return_type, AsTaskType};
+use super::error::fluxio_code;
+use super::io::{FLUXIO_IO_ERROR, FLUXIO_IO_PENDING};
+use super::task::{
+    fluxio_context, fluxio_task, fluxio_task_return_type, AsTaskType, FLUXIO_POLL_ERROR,
+    FLUXIO_POLL_PENDING, FLUXIO_POLL_READY,
+};
 use super::{UserDataPointer, FLUXIO_ITER_CONTINUE};
-use crate::body::{Body, Bytes, HttpBody as _};
+use crate::body::{Body, Buf, Bytes, HttpBody as _, Sender};
+use crate::ext::sse;
 
 /// A streaming HTTP body.
 pub struct fluxio_body(pub(super) Body);
@@ -16,6 +25,26 @@ pub struct fluxio_body(pub(super) Body);
 /// A buffer of bytes that is sent or received on a `fluxio_body`.
 pub struct fluxio_buf(pub(crate) Bytes);
 
+/// A growable buffer of bytes, finished into a `fluxio_buf`.
+pub struct fluxio_buf_builder(BytesMut);
+
+/// The sending half of a body created with `fluxio_body_channel`.
+pub struct fluxio_body_sender(Sender);
+
+/// A reader copying the data of a `fluxio_body` into buffers.
+pub struct fluxio_body_reader {
+    body: Body,
+    chunk: Bytes,
+}
+
+/// An event received on a Server-Sent Events body.
+pub struct fluxio_sse_event {
+    id: fluxio_buf,
+    event: fluxio_buf,
+    data: fluxio_buf,
+    retry: Option<u64>,
+}
+
 pub(crate) struct UserBody {
     data_func: fluxio_body_data_callback,
     userdata: *mut c_void,
@@ -25,6 +54,10 @@ pub(crate) struct UserBody {
 
 type fluxio_body_foreach_callback = extern "C" fn(*mut c_void, *const fluxio_buf) -> c_int;
 
+type fluxio_sse_event_callback = extern "C" fn(*mut c_void, *const fluxio_sse_event) -> c_int;
+
+type fluxio_buf_drop_callback = extern "C" fn(*mut c_void, *const u8, size_t);
+
 type fluxio_body_data_callback =
     extern "C" fn(*mut c_void, *mut fluxio_context<'_>, *mut *mut fluxio_buf) -> c_int;
 
@@ -65,6 +98,30 @@ ffi_fn! {
     } ?= ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Return a task that will collect all data of the body into a single
+    /// buffer.
+    ///
+    /// The task value is a `FLUXIO_TASK_BUF` with the data, or a
+    /// `FLUXIO_TASK_ERROR`. A body with more than `max_len` bytes of data
+    /// fails with the code `FLUXIO_BODY_TOO_LARGE`; pass `0` for no limit.
+    /// The buffer is preallocated from the length of the body, if known.
+    ///
+    /// This will consume the `fluxio_body *`, you shouldn't use it anymore or free it.
+    fn fluxio_body_collect(body: *mut fluxio_body, max_len: size_t) -> *mut fluxio_task {
+        let body = non_null!(Box::from_raw(body) ?= ptr::null_mut());
+
+        Box::into_raw(fluxio_task::boxed(async move {
+            let bytes = if max_len == 0 {
+                crate::body::to_bytes(body.0).await?
+            } else {
+                crate::body::to_bytes_limited(body.0, max_len as u64).await?
+            };
+            Ok::<_, crate::Error>(fluxio_buf(bytes))
+        }))
+    } ?= ptr::null_mut()
+}
+
 ffi_fn! {
     /// Return a task that will poll the body and execute the callback with each
     /// body chunk that is received.
@@ -92,6 +149,48 @@ ffi_fn! {
     } ?= ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Return a task that will poll the body as a Server-Sent Events stream,
+    /// and execute the callback with each event that is received.
+    ///
+    /// The `fluxio_sse_event` pointer is only a borrowed reference, it cannot
+    /// live outside the execution of the callback. You must copy any data you
+    /// wish to persist.
+    ///
+    /// The callback should return `FLUXIO_ITER_CONTINUE` to continue iterating
+    /// events as they are received, or `FLUXIO_ITER_BREAK` to cancel.
+    ///
+    /// A line longer than 256 KiB, or an event with more than 1 MiB of data,
+    /// fails the task with an error.
+    ///
+    /// This will consume the `fluxio_body *`, you shouldn't use it anymore or free it.
+    fn fluxio_body_foreach_event(body: *mut fluxio_body, func: fluxio_sse_event_callback, userdata: *mut c_void) -> *mut fluxio_task {
+        let mut body = non_null!(Box::from_raw(body) ?= ptr::null_mut());
+        let userdata = UserDataPointer(userdata);
+
+        Box::into_raw(fluxio_task::boxed(async move {
+            let userdata = &userdata;
+            let mut parser = sse::Parser::default();
+            while let Some(item) = body.0.data().await {
+                let chunk = item?;
+                let mut cancelled = false;
+                parser.feed(&chunk, |event| {
+                    if FLUXIO_ITER_CONTINUE == func(userdata.0, &fluxio_sse_event::new(event)) {
+                        ControlFlow::Continue(())
+                    } else {
+                        cancelled = true;
+                        ControlFlow::Break(())
+                    }
+                })?;
+                if cancelled {
+                    return Err(crate::Error::new_user_aborted_by_callback());
+                }
+            }
+            Ok(())
+        }))
+    } ?= ptr::null_mut()
+}
+
 ffi_fn! {
     /// Set userdata on this body, which will be passed to callback functions.
     fn fluxio_body_set_userdata(body: *mut fluxio_body, userdata: *mut c_void) {
@@ -126,6 +225,276 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Set the maximum size of the data of this body, in bytes.
+    ///
+    /// Reading more data fails with the code `FLUXIO_BODY_TOO_LARGE`, as
+    /// does reading a body declaring a larger length, such as with
+    /// `Content-Length`, before any of it is read.
+    fn fluxio_body_set_max_size(body: *mut fluxio_body, max: u64) -> fluxio_code {
+        let b = non_null!(&mut *body ?= fluxio_code::FLUXIO_INVALID_ARG);
+        b.0 = mem::replace(&mut b.0, Body::empty()).limit(max);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set the bounds of the length of the data of this body, in bytes.
+    ///
+    /// An `upper` of `UINT64_MAX` means the length has no upper bound. If
+    /// both are equal, the body is sent with a `Content-Length` header
+    /// instead of chunked encoding, and must have exactly that much data.
+    ///
+    /// Returns `FLUXIO_INVALID_ARG` if `upper` is less than `lower`.
+    fn fluxio_body_set_size_hint(body: *mut fluxio_body, lower: u64, upper: u64) -> fluxio_code {
+        let b = non_null!(&mut *body ?= fluxio_code::FLUXIO_INVALID_ARG);
+        if upper < lower {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
+        let upper = if upper == u64::MAX { None } else { Some(upper) };
+        b.0 = mem::replace(&mut b.0, Body::empty()).with_size_hint(lower, upper);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+// ===== Channel =====
+
+ffi_fn! {
+    /// Create a body whose data is sent with a `fluxio_body_sender`.
+    ///
+    /// The body buffers up to `capacity` buffers that were sent but not yet
+    /// received; a `capacity` of `0` is the same as `1`. The sending half is
+    /// written to `sender`, and must be freed with `fluxio_body_sender_free`
+    /// to end the body, or `fluxio_body_sender_abort` to abort it.
+    ///
+    /// Returns `NULL` if `capacity` is larger than `SIZE_MAX / 4`.
+    fn fluxio_body_channel(capacity: size_t, sender: *mut *mut fluxio_body_sender) -> *mut fluxio_body {
+        let out = non_null!(&mut *sender ?= ptr::null_mut());
+        if capacity > crate::body::MAX_CHANNEL_CAPACITY {
+            return ptr::null_mut();
+        }
+        let (tx, body) = Body::channel_with_capacity(capacity);
+        *out = Box::into_raw(Box::new(fluxio_body_sender(tx)));
+        Box::into_raw(Box::new(fluxio_body(body)))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Set the watermarks of the data buffered in the channel, in bytes.
+    ///
+    /// Once `high` bytes were sent but not yet received, the sender isn't
+    /// ready again until no more than `low` bytes remain buffered.
+    /// `fluxio_body_sender_try_send` only checks the capacity of the channel.
+    ///
+    /// Setting `high` to 0 disables the watermarks, which is the default.
+    /// Returns `FLUXIO_INVALID_ARG` if `low` is larger than `high`.
+    fn fluxio_body_sender_set_watermarks(sender: *mut fluxio_body_sender, low: size_t, high: size_t) -> fluxio_code {
+        let sender = non_null!(&mut *sender ?= fluxio_code::FLUXIO_INVALID_ARG);
+        if low > high {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
+        sender.0.set_watermarks(low, high);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Check whether the sender can send more data.
+    ///
+    /// Returns `FLUXIO_POLL_READY` if it can, or `FLUXIO_POLL_ERROR` if the
+    /// body was dropped. Otherwise, the waker of the `fluxio_context *` is
+    /// registered to be woken once it can, and `FLUXIO_POLL_PENDING` is
+    /// returned.
+    fn fluxio_body_sender_poll_ready(sender: *mut fluxio_body_sender, ctx: *mut fluxio_context<'_>) -> c_int {
+        let sender = non_null!(&mut *sender ?= FLUXIO_POLL_ERROR);
+        let cx = non_null!(&mut *ctx ?= FLUXIO_POLL_ERROR);
+        match sender.0.poll_ready(&mut cx.0) {
+            Poll::Ready(Ok(())) => FLUXIO_POLL_READY,
+            Poll::Ready(Err(_)) => FLUXIO_POLL_ERROR,
+            Poll::Pending => FLUXIO_POLL_PENDING,
+        }
+    } ?= FLUXIO_POLL_ERROR
+}
+
+ffi_fn! {
+    /// Try to send a buffer of data without waiting.
+    ///
+    /// On success, this consumes the `fluxio_buf *`. If the channel is full
+    /// or the body was dropped, `FLUXIO_ERROR` is returned, and the buffer is
+    /// still owned by the caller.
+    fn fluxio_body_sender_try_send(sender: *mut fluxio_body_sender, buf: *mut fluxio_buf) -> fluxio_code {
+        let sender = non_null!(&mut *sender ?= fluxio_code::FLUXIO_INVALID_ARG);
+        let chunk = non_null!(&*buf ?= fluxio_code::FLUXIO_INVALID_ARG).0.clone();
+        match sender.0.try_send_data(chunk) {
+            Ok(()) => {
+                drop(unsafe { Box::from_raw(buf) });
+                fluxio_code::FLUXIO_OK
+            }
+            Err(_) => fluxio_code::FLUXIO_ERROR,
+        }
+    }
+}
+
+ffi_fn! {
+    /// Return a task that sends a buffer of data once the sender is ready.
+    ///
+    /// The task value is `FLUXIO_TASK_EMPTY` once the data is sent, or
+    /// `FLUXIO_TASK_ERROR` if the body was dropped.
+    ///
+    /// This consumes the `fluxio_buf *`. It does not consume the
+    /// `fluxio_body_sender *`, but it MUST NOT be used or freed until the
+    /// related task completes.
+    fn fluxio_body_sender_send(sender: *mut fluxio_body_sender, buf: *mut fluxio_buf) -> *mut fluxio_task {
+        // This doesn't take ownership of the Sender, so don't allow destructor
+        let mut sender = ManuallyDrop::new(non_null!(Box::from_raw(sender) ?= ptr::null_mut()));
+        let buf = non_null!(Box::from_raw(buf) ?= ptr::null_mut());
+
+        Box::into_raw(fluxio_task::boxed(async move {
+            sender.0.send_data(buf.0).await
+        }))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Abort the body, so it ends with an error instead of normally.
+    ///
+    /// This consumes the `fluxio_body_sender *`, you shouldn't use it anymore
+    /// or free it.
+    fn fluxio_body_sender_abort(sender: *mut fluxio_body_sender) {
+        non_null!(Box::from_raw(sender) ?= ()).0.abort();
+    }
+}
+
+ffi_fn! {
+    /// Free a `fluxio_body_sender *`, ending the body normally.
+    fn fluxio_body_sender_free(sender: *mut fluxio_body_sender) {
+        drop(non_null!(Box::from_raw(sender) ?= ()));
+    }
+}
+
+// ===== Reader =====
+
+impl fluxio_body_reader {
+    fn poll_read(&mut self, cx: &mut Context<'_>, dst: &mut [u8]) -> Poll<crate::Result<usize>> {
+        while self.chunk.is_empty() {
+            match ready!(Pin::new(&mut self.body).poll_data(cx)) {
+                Some(chunk) => self.chunk = chunk?,
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+        let n = dst.len().min(self.chunk.len());
+        dst[..n].copy_from_slice(&self.chunk[..n]);
+        self.chunk.advance(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+ffi_fn! {
+    /// Create a reader of the data of a body.
+    ///
+    /// Instead of a `fluxio_buf *` per chunk, the reader copies the data into
+    /// buffers of your choosing, from within callbacks that are passed a
+    /// `fluxio_context *`, such as the data callback of another body.
+    ///
+    /// This will consume the `fluxio_body *`, you shouldn't use it anymore or free it.
+    fn fluxio_body_reader_new(body: *mut fluxio_body) -> *mut fluxio_body_reader {
+        let body = non_null!(Box::from_raw(body) ?= ptr::null_mut());
+        Box::into_raw(Box::new(fluxio_body_reader {
+            body: body.0,
+            chunk: Bytes::new(),
+        }))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Free a `fluxio_body_reader *`, and the body it reads.
+    fn fluxio_body_reader_free(reader: *mut fluxio_body_reader) {
+        drop(non_null!(Box::from_raw(reader) ?= ()));
+    }
+}
+
+ffi_fn! {
+    /// Read data of the body into `buf`.
+    ///
+    /// Returns the number of bytes copied into `buf`, which is `0` once the
+    /// body has no more data, or if `buf_len` is `0`.
+    ///
+    /// If no data is available yet, the waker of the `fluxio_context *` is
+    /// registered to be woken once there is, and `FLUXIO_IO_PENDING` is
+    /// returned. If reading the body fails, or `buf` is `NULL` while `buf_len`
+    /// isn't `0`, `FLUXIO_IO_ERROR` is returned.
+    fn fluxio_body_reader_read(reader: *mut fluxio_body_reader, ctx: *mut fluxio_context<'_>, buf: *mut u8, buf_len: size_t) -> size_t {
+        let reader = non_null!(&mut *reader ?= FLUXIO_IO_ERROR);
+        let cx = non_null!(&mut *ctx ?= FLUXIO_IO_ERROR);
+        if buf_len == 0 {
+            return 0;
+        }
+        let buf = non_null!(buf, std::slice::from_raw_parts_mut(buf, buf_len), FLUXIO_IO_ERROR);
+        match reader.poll_read(&mut cx.0, buf) {
+            Poll::Ready(Ok(n)) => n,
+            Poll::Ready(Err(_)) => FLUXIO_IO_ERROR,
+            Poll::Pending => FLUXIO_IO_PENDING,
+        }
+    } ?= FLUXIO_IO_ERROR
+}
+
+// ===== impl fluxio_sse_event =====
+
+impl fluxio_sse_event {
+    fn new(event: sse::Event) -> fluxio_sse_event {
+        fluxio_sse_event {
+            id: fluxio_buf(Bytes::copy_from_slice(event.id().unwrap_or("").as_bytes())),
+            event: fluxio_buf(Bytes::copy_from_slice(event.event_type().as_bytes())),
+            retry: event
+                .retry()
+                .map(|retry| u64::try_from(retry.as_millis()).unwrap_or(u64::MAX)),
+            data: fluxio_buf(Bytes::from(event.into_data())),
+        }
+    }
+}
+
+ffi_fn! {
+    /// Get the id of this event.
+    ///
+    /// This is the last id sent on the stream, which may be the id of an
+    /// earlier event. The buffer is empty if no id was sent.
+    ///
+    /// The `fluxio_buf` is borrowed from the event.
+    fn fluxio_sse_event_id(event: *const fluxio_sse_event) -> *const fluxio_buf {
+        &non_null!(&*event ?= ptr::null()).id
+    } ?= ptr::null()
+}
+
+ffi_fn! {
+    /// Get the type of this event, `message` if it wasn't set.
+    ///
+    /// The `fluxio_buf` is borrowed from the event.
+    fn fluxio_sse_event_type(event: *const fluxio_sse_event) -> *const fluxio_buf {
+        &non_null!(&*event ?= ptr::null()).event
+    } ?= ptr::null()
+}
+
+ffi_fn! {
+    /// Get the data of this event.
+    ///
+    /// The `fluxio_buf` is borrowed from the event.
+    fn fluxio_sse_event_data(event: *const fluxio_sse_event) -> *const fluxio_buf {
+        &non_null!(&*event ?= ptr::null()).data
+    } ?= ptr::null()
+}
+
+ffi_fn! {
+    /// Get the reconnection time sent along with this event, in milliseconds.
+    ///
+    /// Returns `-1` if the event didn't have one.
+    fn fluxio_sse_event_retry(event: *const fluxio_sse_event) -> i64 {
+        match non_null!(&*event ?= -1).retry {
+            Some(retry) => i64::try_from(retry).unwrap_or(i64::MAX),
+            None => -1,
+        }
+    }
+}
+
 // ===== impl UserBody =====
 
 impl UserBody {
@@ -195,6 +564,49 @@ ffi_fn! {
     } ?= ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Create a new `fluxio_buf *` borrowing the provided bytes, without
+    /// copying them.
+    ///
+    /// The bytes must stay valid and unchanged for as long as the program
+    /// runs, such as string literals or a file mapped for good.
+    ///
+    /// This returns `NULL` if `buf` is `NULL` and `len` isn't 0.
+    fn fluxio_buf_from_static(buf: *const u8, len: size_t) -> *mut fluxio_buf {
+        let bytes = if len == 0 {
+            Bytes::new()
+        } else {
+            Bytes::from_static(non_null!(buf, std::slice::from_raw_parts(buf, len), ptr::null_mut()))
+        };
+        Box::into_raw(Box::new(fluxio_buf(bytes)))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Create a new `fluxio_buf *` taking ownership of the provided bytes,
+    /// without copying them.
+    ///
+    /// The bytes must stay valid and unchanged until fluxio releases them, by
+    /// calling `drop_fn` with `userdata`, `buf` and `len`. That happens once
+    /// this buffer, every buffer cloned or sliced from it, and every body
+    /// made from them were freed, from whichever thread frees the last one.
+    ///
+    /// This returns `NULL` if `buf` is `NULL` and `len` isn't 0, without
+    /// calling `drop_fn`.
+    fn fluxio_buf_from_owned(buf: *const u8, len: size_t, drop_fn: fluxio_buf_drop_callback, userdata: *mut c_void) -> *mut fluxio_buf {
+        if buf.is_null() && len != 0 {
+            return ptr::null_mut();
+        }
+        let owner = OwnedBuf {
+            ptr: buf,
+            len,
+            drop_fn,
+            userdata: UserDataPointer(userdata),
+        };
+        Box::into_raw(Box::new(fluxio_buf(Bytes::from_owner(owner))))
+    } ?= ptr::null_mut()
+}
+
 ffi_fn! {
     /// Get a pointer to the bytes in this buffer.
     ///
@@ -222,8 +634,187 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Create a new `fluxio_buf *` sharing the bytes of this buffer.
+    ///
+    /// The bytes aren't copied, only a reference count is incremented, so
+    /// either buffer can be freed independently of the other.
+    fn fluxio_buf_clone(buf: *const fluxio_buf) -> *mut fluxio_buf {
+        let buf = non_null!(&*buf ?= ptr::null_mut());
+        Box::into_raw(Box::new(fluxio_buf(buf.0.clone())))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Create a new `fluxio_buf *` of `len` bytes of this buffer, starting at
+    /// `start`.
+    ///
+    /// The bytes aren't copied, so a part of a received chunk can be kept
+    /// after freeing the chunk, without keeping a copy of it.
+    ///
+    /// This returns `NULL` if the range isn't within the buffer.
+    fn fluxio_buf_slice(buf: *const fluxio_buf, start: size_t, len: size_t) -> *mut fluxio_buf {
+        let buf = non_null!(&*buf ?= ptr::null_mut());
+        match start.checked_add(len) {
+            Some(end) if end <= buf.0.len() => {
+                Box::into_raw(Box::new(fluxio_buf(buf.0.slice(start..end))))
+            }
+            _ => ptr::null_mut(),
+        }
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Create a body whose data is the bytes of this buffer.
+    ///
+    /// The bytes aren't copied, so a received chunk can be sent back out,
+    /// such as when proxying.
+    ///
+    /// This consumes the `fluxio_buf *`, you shouldn't use it anymore or free it.
+    fn fluxio_buf_into_body(buf: *mut fluxio_buf) -> *mut fluxio_body {
+        let buf = non_null!(Box::from_raw(buf) ?= ptr::null_mut());
+        Box::into_raw(Box::new(fluxio_body(Body::from(buf.0))))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Create a new, empty `fluxio_buf_builder *` with room for at least
+    /// `capacity` bytes.
+    ///
+    /// Bytes are appended with `fluxio_buf_builder_append`, and the builder is
+    /// turned into a `fluxio_buf *` with `fluxio_buf_builder_finish`, without
+    /// copying them again.
+    ///
+    /// This returns `NULL` if allocating a new builder fails.
+    fn fluxio_buf_builder_new(capacity: size_t) -> *mut fluxio_buf_builder {
+        Box::into_raw(Box::new(fluxio_buf_builder(BytesMut::with_capacity(capacity))))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Append a copy of the provided bytes to the builder.
+    ///
+    /// The `buf` argument can be freed or changed afterwards.
+    fn fluxio_buf_builder_append(builder: *mut fluxio_buf_builder, buf: *const u8, len: size_t) -> fluxio_code {
+        let builder = non_null!(&mut *builder ?= fluxio_code::FLUXIO_INVALID_ARG);
+        if len > 0 {
+            let slice = non_null!(buf, std::slice::from_raw_parts(buf, len), fluxio_code::FLUXIO_INVALID_ARG);
+            builder.0.extend_from_slice(slice);
+        }
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Reserve room for at least `additional` more bytes in the builder.
+    fn fluxio_buf_builder_reserve(builder: *mut fluxio_buf_builder, additional: size_t) -> fluxio_code {
+        let builder = non_null!(&mut *builder ?= fluxio_code::FLUXIO_INVALID_ARG);
+        builder.0.reserve(additional);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Get the number of bytes appended to the builder so far.
+    fn fluxio_buf_builder_len(builder: *const fluxio_buf_builder) -> size_t {
+        non_null!(&*builder ?= 0).0.len()
+    }
+}
+
+ffi_fn! {
+    /// Turn the builder into a `fluxio_buf *` holding the appended bytes.
+    ///
+    /// This consumes the `fluxio_buf_builder *`, you shouldn't use it anymore
+    /// or free it.
+    fn fluxio_buf_builder_finish(builder: *mut fluxio_buf_builder) -> *mut fluxio_buf {
+        let builder = non_null!(Box::from_raw(builder) ?= ptr::null_mut());
+        Box::into_raw(Box::new(fluxio_buf(builder.0.freeze())))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Free a builder, discarding the bytes appended to it.
+    fn fluxio_buf_builder_free(builder: *mut fluxio_buf_builder) {
+        drop(non_null!(Box::from_raw(builder) ?= ()));
+    }
+}
+
+/// Bytes owned by C code, released with its callback once unused.
+struct OwnedBuf {
+    ptr: *const u8,
+    len: size_t,
+    drop_fn: fluxio_buf_drop_callback,
+    userdata: UserDataPointer,
+}
+
+// The caller of `fluxio_buf_from_owned` promised the bytes stay valid and
+// unchanged until `drop_fn` is called, from any thread.
+unsafe impl Send for OwnedBuf {}
+unsafe impl Sync for OwnedBuf {}
+
+impl AsRef<[u8]> for OwnedBuf {
+    fn as_ref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for OwnedBuf {
+    fn drop(&mut self) {
+        (self.drop_fn)(self.userdata.0, self.ptr, self.len);
+    }
+}
+
 unsafe impl AsTaskType for fluxio_buf {
     fn as_task_type(&self) -> fluxio_task_return_type {
         fluxio_task_return_type::FLUXIO_TASK_BUF
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buf_from_owned_drops_once_unused() {
+        let data = b"hello world".to_vec();
+        let mut dropped = 0usize;
+
+        let buf = fluxio_buf_from_owned(
+            data.as_ptr(),
+            data.len(),
+            on_drop,
+            &mut dropped as *mut _ as *mut c_void,
+        );
+        let slice = fluxio_buf_slice(buf, 6, 5);
+        fluxio_buf_free(buf);
+        assert_eq!(dropped, 0);
+
+        unsafe {
+            assert_eq!((*slice).0.as_ref(), b"world");
+            // the bytes were never copied
+            assert_eq!(fluxio_buf_bytes(slice), data.as_ptr().add(6));
+        }
+        fluxio_buf_free(slice);
+        assert_eq!(dropped, 1);
+
+        extern "C" fn on_drop(dropped: *mut c_void, _: *const u8, len: size_t) {
+            assert_eq!(len, 11);
+            unsafe { *(dropped as *mut usize) += 1 };
+        }
+    }
+
+    #[test]
+    fn test_body_channel_rejects_huge_capacity() {
+        let mut sender = ptr::null_mut();
+        assert!(fluxio_body_channel(size_t::MAX, &mut sender).is_null());
+        assert!(sender.is_null());
+
+        let body = fluxio_body_channel(4, &mut sender);
+        assert!(!body.is_null());
+        fluxio_body_sender_free(sender);
+        fluxio_body_free(body);
+    }
+}
@@ -1,21 +1,45 @@
+use std::ffi::c_void;
+use std::net::SocketAddr;
 use std::ptr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use libc::c_int;
+use futures_util::future::{self, Either};
+use http::header::{HeaderValue, HOST};
+use http::uri::Authority;
+use http::{Method, Request, StatusCode, Uri};
+use libc::{c_int, size_t};
+use tracing::{debug, Instrument};
 
+#[cfg(feature = "decompress")]
+use crate::body::ContentEncoding;
 use crate::client::conn;
+use crate::client::HostPolicy;
+use crate::common::Future;
+use crate::ext::RequestTimings;
+use crate::hardened::Profile;
+use crate::metrics::MetricsSink;
+use crate::observer::{ConnectionInfo, ConnectionObserver};
 use crate::rt::Executor as _;
 
+use super::body::fluxio_buf;
 use super::error::fluxio_code;
-use super::http_types::{fluxio_request, fluxio_response};
+use super::http_types::{fluxio_request, fluxio_response, RequestSpan, UserExtensions};
 use super::io::fluxio_io;
 use super::task::{fluxio_executor, fluxio_task, fluxio_task_return_type, AsTaskType, WeakExec};
+use super::upgrade::fluxio_upgraded;
+use super::{UserDataPointer, FLUXIO_ITER_CONTINUE};
 
 /// An options builder to configure an HTTP client connection.
 pub struct fluxio_clientconn_options {
     builder: conn::Builder,
     /// Use a `Weak` to prevent cycles.
     exec: WeakExec,
+    /// The protocols offered with ALPN, if the version is picked from it.
+    alpn: Option<Vec<Vec<u8>>>,
+    interceptors: Vec<Interceptor>,
+    host: HostPolicy,
 }
 
 /// An HTTP client connection handle.
@@ -24,7 +48,72 @@ pub struct fluxio_clientconn_options {
 /// send multiple requests on a single connection, such as when HTTP/1
 /// keep-alive or HTTP/2 is used.
 pub struct fluxio_clientconn {
-    tx: conn::SendRequest<crate::Body>,
+    /// Shared with the tasks waiting for the connection to be ready.
+    tx: Arc<Mutex<conn::SendRequest<crate::Body>>>,
+    peer_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    interceptors: Arc<[Interceptor]>,
+    host: HostPolicy,
+    /// Whether a request was sent already.
+    sent: AtomicBool,
+}
+
+/// The kind of a metric passed to a `fluxio_metrics_callback`.
+#[repr(C)]
+pub enum fluxio_metric {
+    /// Bytes were read from the connection. The value is how many.
+    FLUXIO_METRIC_BYTES_READ,
+    /// Bytes were written to the connection. The value is how many.
+    FLUXIO_METRIC_BYTES_WRITTEN,
+    /// A response head was received. The value is the time since its request
+    /// was sent, in microseconds.
+    FLUXIO_METRIC_REQUEST_LATENCY,
+    /// The connection handshake completed. The value is how long it took, in
+    /// microseconds.
+    FLUXIO_METRIC_HANDSHAKE_DURATION,
+    /// An HTTP/2 stream was reset. The value is always `1`.
+    FLUXIO_METRIC_STREAM_RESET,
+}
+
+type fluxio_metrics_callback = extern "C" fn(*mut c_void, fluxio_metric, u64);
+
+struct MetricsCallback {
+    func: fluxio_metrics_callback,
+    data: UserDataPointer,
+}
+
+/// The kind of an event passed to a `fluxio_conn_event_callback`.
+#[repr(C)]
+pub enum fluxio_conn_event {
+    /// The connection was opened. The value is always `0`.
+    FLUXIO_CONN_EVENT_CONNECT,
+    /// The connection was closed. The value is `1` if it was closed because
+    /// of an error, and `0` otherwise.
+    FLUXIO_CONN_EVENT_CLOSE,
+    /// A request is about to be sent. The value is always `0`.
+    FLUXIO_CONN_EVENT_REQUEST_START,
+    /// A response head was received. The value is its status code.
+    FLUXIO_CONN_EVENT_RESPONSE_HEADERS,
+    /// A response body was fully received. The value is always `0`.
+    FLUXIO_CONN_EVENT_BODY_COMPLETE,
+}
+
+type fluxio_conn_event_callback = extern "C" fn(*mut c_void, fluxio_conn_event, u64, c_int);
+
+struct EventCallback {
+    func: fluxio_conn_event_callback,
+    data: UserDataPointer,
+}
+
+type fluxio_interceptor_request_callback = extern "C" fn(*mut c_void, *mut fluxio_request) -> c_int;
+
+type fluxio_interceptor_response_callback =
+    extern "C" fn(*mut c_void, *mut fluxio_response) -> c_int;
+
+struct Interceptor {
+    before_send: Option<fluxio_interceptor_request_callback>,
+    after_response: Option<fluxio_interceptor_response_callback>,
+    data: UserDataPointer,
 }
 
 // ===== impl fluxio_clientconn =====
@@ -37,10 +126,24 @@ ffi_fn! {
     ///
     /// The returned `fluxio_task *` must be polled with an executor until the
     /// handshake completes, at which point the value can be taken.
+    ///
+    /// If ALPN protocols were set with `fluxio_clientconn_options_alpn`, the
+    /// HTTP version is picked from the protocol the `io` negotiated.
     fn fluxio_clientconn_handshake(io: *mut fluxio_io, options: *mut fluxio_clientconn_options) -> *mut fluxio_task {
-        let options = non_null! { Box::from_raw(options) ?= ptr::null_mut() };
+        #[cfg_attr(not(all(feature = "http1", feature = "http2")), allow(unused_mut))]
+        let mut options = non_null! { Box::from_raw(options) ?= ptr::null_mut() };
         let io = non_null! { Box::from_raw(io) ?= ptr::null_mut() };
+        let (peer_addr, local_addr) = io.connected_addrs();
+
+        #[cfg(all(feature = "http1", feature = "http2"))]
+        if let Some(ref alpn) = options.alpn {
+            // Without an agreed protocol, servers speak HTTP/1.1.
+            let h2 = io.alpn() == Some(&b"h2"[..]) && alpn.iter().any(|proto| proto == b"h2");
+            options.builder.http2_negotiated(h2);
+        }
 
+        let interceptors = Arc::from(std::mem::take(&mut options.interceptors));
+        let host = options.host;
         Box::into_raw(fluxio_task::boxed(async move {
             options.builder.handshake::<_, crate::Body>(io)
                 .await
@@ -48,7 +151,14 @@ ffi_fn! {
                     options.exec.execute(Box::pin(async move {
                         let _ = conn.await;
                     }));
-                    fluxio_clientconn { tx }
+                    fluxio_clientconn {
+                        tx: Arc::new(Mutex::new(tx)),
+                        peer_addr,
+                        local_addr,
+                        interceptors,
+                        host,
+                        sent: AtomicBool::new(false),
+                    }
                 })
         }))
     } ?= std::ptr::null_mut()
@@ -59,22 +169,252 @@ ffi_fn! {
     ///
     /// Returns a task that needs to be polled until it is ready. When ready, the
     /// task yields a `fluxio_response *`.
+    ///
+    /// If the connection is closed already, such as after its idle timeout,
+    /// the task yields a `fluxio_error *` with the `FLUXIO_CONN_CLOSED` code.
+    ///
+    /// The interceptors added with `fluxio_clientconn_options_add_interceptor`
+    /// are called with the request right away.
     fn fluxio_clientconn_send(conn: *mut fluxio_clientconn, req: *mut fluxio_request) -> *mut fluxio_task {
-        let mut req = non_null! { Box::from_raw(req) ?= ptr::null_mut() };
-
-        // Update request with original-case map of headers
-        req.finalize_request();
+        let req = non_null! { Box::from_raw(req) ?= ptr::null_mut() };
+        let conn = non_null! { &mut *conn ?= ptr::null_mut() };
 
-        let fut = non_null! { &mut *conn ?= ptr::null_mut() }.tx.send_request(req.0);
+        Box::into_raw(fluxio_task::boxed(send(conn, req)))
+    } ?= std::ptr::null_mut()
+}
 
-        let fut = async move {
-            fut.await.map(fluxio_response::wrap)
+ffi_fn! {
+    /// Establish a tunnel through the client connection with a `CONNECT`
+    /// request.
+    ///
+    /// `authority` is the `host:port` to tunnel to, sent as the target and
+    /// `Host` header of the request, which has no body. The interceptors
+    /// added with `fluxio_clientconn_options_add_interceptor` are called as
+    /// with `fluxio_clientconn_send`.
+    ///
+    /// Returns a task that needs to be polled until it is ready. When ready,
+    /// the task yields a `fluxio_upgraded *` to read and write through the
+    /// tunnel, once a `2xx` response was received. A response with any other
+    /// status yields a `fluxio_error *` instead, as does an error sending the
+    /// request.
+    ///
+    /// Returns NULL if `authority` is not a valid authority.
+    fn fluxio_clientconn_connect_tunnel(conn: *mut fluxio_clientconn, authority: *const u8, authority_len: size_t) -> *mut fluxio_task {
+        let conn = non_null! { &mut *conn ?= ptr::null_mut() };
+        let bytes = unsafe {
+            std::slice::from_raw_parts(authority, authority_len as usize)
+        };
+        let authority = match Authority::try_from(bytes) {
+            Ok(authority) => authority,
+            Err(_) => return ptr::null_mut(),
         };
 
+        let host = HeaderValue::from_str(authority.as_str()).expect("authority is a valid header value");
+        let mut req = Request::new(crate::Body::empty());
+        *req.method_mut() = Method::CONNECT;
+        *req.uri_mut() = Uri::from(authority);
+        req.headers_mut().insert(HOST, host);
+
+        let fut = send(conn, Box::new(fluxio_request(req)));
+        Box::into_raw(fluxio_task::boxed(async move {
+            let mut resp = fut.await?;
+            let status = resp.0.status();
+            if !status.is_success() {
+                debug!("CONNECT tunnel refused with {}", status);
+                return Err(crate::Error::new_user_no_upgrade().with(format!("CONNECT responded with {}", status)));
+            }
+            let io = crate::upgrade::on(&mut resp.0).await?;
+            Ok(fluxio_upgraded::new(io))
+        }))
+    } ?= std::ptr::null_mut()
+}
+
+/// Sends a request on a client connection, calling its interceptors.
+fn send(
+    conn: &fluxio_clientconn,
+    mut req: Box<fluxio_request>,
+) -> impl Future<Output = crate::Result<fluxio_response>> + Send {
+    for interceptor in conn.interceptors.iter() {
+        if !interceptor.before_send(&mut req) {
+            let err = crate::Error::new_user_aborted_by_callback();
+            return Either::Left(future::err(err));
+        }
+    }
+
+    // Update request with original-case map of headers
+    req.finalize_request();
+
+    conn.host.apply(&mut req.0);
+
+    let span = req.0.extensions().get::<RequestSpan>().cloned();
+    let user_exts = req.0.extensions_mut().remove::<UserExtensions>();
+    if let Some(RequestSpan(ref span)) = span {
+        span.record("method", tracing::field::display(req.0.method()));
+        span.record("uri", tracing::field::display(req.0.uri()));
+    }
+
+    let mut tx = conn.tx.lock().unwrap();
+    let fut = if tx.is_closed() {
+        Either::Left(future::err(
+            crate::Error::new_closed().with("connection closed"),
+        ))
+    } else {
+        Either::Right(tx.send_request(req.0))
+    };
+    drop(tx);
+    let reused = conn.sent.swap(true, Ordering::Relaxed);
+    let sent_at = Instant::now();
+
+    let interceptors = conn.interceptors.clone();
+    let instrument = span
+        .as_ref()
+        .map_or_else(tracing::Span::none, |span| span.0.clone());
+    let fut = async move {
+        let mut resp = fluxio_response::wrap(fut.await?);
+        resp.0
+            .extensions_mut()
+            .insert(RequestTimings::new(sent_at.elapsed(), reused));
+        if let Some(span) = span {
+            resp.0.extensions_mut().insert(span);
+        }
+        if let Some(user_exts) = user_exts {
+            resp.0.extensions_mut().insert(user_exts);
+        }
+        for interceptor in interceptors.iter().rev() {
+            if !interceptor.after_response(&mut resp) {
+                return Err(crate::Error::new_user_aborted_by_callback());
+            }
+        }
+        Ok(resp)
+    }
+    .instrument(instrument);
+
+    Either::Right(fut)
+}
+
+ffi_fn! {
+    /// Returns a task that completes once the initial HTTP/2 SETTINGS were
+    /// exchanged with the peer.
+    ///
+    /// The handshake only sends the connection preface, so the peer's limits
+    /// may not be known yet when it completes. Awaiting this task before
+    /// sending a burst of requests makes sure they are.
+    ///
+    /// The task yields `FLUXIO_TASK_EMPTY` when complete, or a `fluxio_error *`
+    /// if the connection closed before that. On HTTP/1 connections, it
+    /// completes immediately.
+    ///
+    /// This does not consume the `fluxio_clientconn *`.
+    fn fluxio_clientconn_settings_exchanged(conn: *mut fluxio_clientconn) -> *mut fluxio_task {
+        let fut = non_null! { &mut *conn ?= ptr::null_mut() }
+            .tx
+            .lock()
+            .unwrap()
+            .settings_exchanged();
+
         Box::into_raw(fluxio_task::boxed(fut))
     } ?= std::ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Returns a task that completes once the connection is ready to send
+    /// another request.
+    ///
+    /// An HTTP/1 connection is ready once the previous response was
+    /// received, and an HTTP/2 connection once the peer's limit on
+    /// concurrent streams allows another one. Sending before that queues
+    /// the request, or fails if one is queued already.
+    ///
+    /// The task yields `FLUXIO_TASK_EMPTY` when ready, or a `fluxio_error *`
+    /// if the connection closed.
+    ///
+    /// This does not consume the `fluxio_clientconn *`.
+    fn fluxio_clientconn_poll_ready(conn: *mut fluxio_clientconn) -> *mut fluxio_task {
+        let tx = non_null! { &mut *conn ?= ptr::null_mut() }.tx.clone();
+        let fut = std::future::poll_fn(move |cx| tx.lock().unwrap().poll_ready(cx));
+
+        Box::into_raw(fluxio_task::boxed(fut))
+    } ?= std::ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Returns `1` if the connection is ready to send another request right
+    /// away, and `0` otherwise.
+    ///
+    /// See `fluxio_clientconn_poll_ready` to wait until it is.
+    fn fluxio_clientconn_is_ready(conn: *const fluxio_clientconn) -> c_int {
+        let conn = non_null! { &*conn ?= 0 };
+        conn.tx.lock().unwrap().is_ready() as c_int
+    }
+}
+
+ffi_fn! {
+    /// Returns `1` if the connection is closed, and no more requests can be
+    /// sent on it, and `0` otherwise.
+    fn fluxio_clientconn_is_closed(conn: *const fluxio_clientconn) -> c_int {
+        let conn = non_null! { &*conn ?= 0 };
+        conn.tx.lock().unwrap().is_closed() as c_int
+    }
+}
+
+ffi_fn! {
+    /// Returns a task that sends a PING, and completes once the peer
+    /// acknowledged it, to check that an HTTP/2 connection is still
+    /// responsive.
+    ///
+    /// If a PING is in flight already, such as one sent for keep-alive, this
+    /// waits for that one instead. The task doesn't time out on its own, so
+    /// it's typically raced against a `fluxio_timer_new` task.
+    ///
+    /// The task yields `FLUXIO_TASK_EMPTY` once acknowledged, or a
+    /// `fluxio_error *` if the connection closed first. On HTTP/1
+    /// connections, which have no PINGs, it fails right away.
+    ///
+    /// This does not consume the `fluxio_clientconn *`.
+    fn fluxio_clientconn_ping(conn: *mut fluxio_clientconn) -> *mut fluxio_task {
+        let pong = non_null! { &mut *conn ?= ptr::null_mut() }
+            .tx
+            .lock()
+            .unwrap()
+            .ping();
+
+        Box::into_raw(fluxio_task::boxed(async move { pong.await.map(|_rtt| ()) }))
+    } ?= std::ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Get the address of the peer of this connection, as set on its IO
+    /// transport with `fluxio_io_set_connected_info`.
+    ///
+    /// The address is formatted as `ip:port`, in a new `fluxio_buf *` that
+    /// must be freed with `fluxio_buf_free`. Returns `NULL` if the address
+    /// is unknown.
+    fn fluxio_clientconn_peer_addr(conn: *const fluxio_clientconn) -> *mut fluxio_buf {
+        let conn = non_null! { &*conn ?= ptr::null_mut() };
+        addr_buf(conn.peer_addr)
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Get the local address of this connection, as set on its IO transport
+    /// with `fluxio_io_set_connected_info`.
+    ///
+    /// The address is formatted as `ip:port`, in a new `fluxio_buf *` that
+    /// must be freed with `fluxio_buf_free`. Returns `NULL` if the address
+    /// is unknown.
+    fn fluxio_clientconn_local_addr(conn: *const fluxio_clientconn) -> *mut fluxio_buf {
+        let conn = non_null! { &*conn ?= ptr::null_mut() };
+        addr_buf(conn.local_addr)
+    } ?= ptr::null_mut()
+}
+
+fn addr_buf(addr: Option<SocketAddr>) -> *mut fluxio_buf {
+    match addr {
+        Some(addr) => Box::into_raw(Box::new(fluxio_buf(addr.to_string().into()))),
+        None => ptr::null_mut(),
+    }
+}
+
 ffi_fn! {
     /// Free a `fluxio_clientconn *`.
     fn fluxio_clientconn_free(conn: *mut fluxio_clientconn) {
@@ -98,6 +438,9 @@ ffi_fn! {
         Box::into_raw(Box::new(fluxio_clientconn_options {
             builder,
             exec: WeakExec::new(),
+            alpn: None,
+            interceptors: Vec::new(),
+            host: HostPolicy::Never,
         }))
     } ?= std::ptr::null_mut()
 }
@@ -166,6 +509,55 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Set the protocols offered with ALPN, so the HTTP version is picked
+    /// from the one the transport negotiated.
+    ///
+    /// The `protos` are in the ALPN wire format, each protocol prefixed by
+    /// its length, as also passed to TLS libraries, such as
+    /// `"\x02h2\x08http/1.1"`. Only `h2`, `http/1.1` and `http/1.0` are
+    /// supported.
+    ///
+    /// When handshaking, the connection uses HTTP/2 if the IO reported `h2`
+    /// with `fluxio_io_set_alpn`, and HTTP/1.1 otherwise, regardless of
+    /// `fluxio_clientconn_options_http2`.
+    ///
+    /// Returns `FLUXIO_INVALID_ARG` if the list is malformed or contains an
+    /// unsupported protocol, and `FLUXIO_FEATURE_NOT_ENABLED` if it contains
+    /// `h2` without HTTP/2 support.
+    fn fluxio_clientconn_options_alpn(opts: *mut fluxio_clientconn_options, protos: *const u8, len: size_t) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        if protos.is_null() {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
+        let protos = unsafe { std::slice::from_raw_parts(protos, len) };
+
+        let mut alpn = Vec::new();
+        let mut rest = protos;
+        while let Some((&proto_len, tail)) = rest.split_first() {
+            let proto_len = usize::from(proto_len);
+            if proto_len == 0 || proto_len > tail.len() {
+                return fluxio_code::FLUXIO_INVALID_ARG;
+            }
+            let (proto, tail) = tail.split_at(proto_len);
+            match proto {
+                b"http/1.1" | b"http/1.0" => (),
+                b"h2" if cfg!(feature = "http2") => (),
+                b"h2" => return fluxio_code::FLUXIO_FEATURE_NOT_ENABLED,
+                _ => return fluxio_code::FLUXIO_INVALID_ARG,
+            }
+            alpn.push(proto.to_vec());
+            rest = tail;
+        }
+        if alpn.is_empty() {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
+
+        opts.alpn = Some(alpn);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
 ffi_fn! {
     /// Set the whether to include a copy of the raw headers in responses
     /// received on this connection.
@@ -179,3 +571,878 @@ ffi_fn! {
         fluxio_code::FLUXIO_OK
     }
 }
+
+ffi_fn! {
+    /// Set the maximum number of informational (1xx) responses accepted
+    /// before the final response to a request.
+    ///
+    /// A server sending more fails the request with the code
+    /// `FLUXIO_TOO_MANY_INFORMATIONAL`. Default is 10.
+    fn fluxio_clientconn_options_max_informational(opts: *mut fluxio_clientconn_options, max: size_t) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.builder.http1_max_informational_responses(max);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set the maximum number of headers of a response.
+    ///
+    /// A server sending more fails the request with the code
+    /// `FLUXIO_TOO_MANY_HEADERS`. Default is 100.
+    fn fluxio_clientconn_options_max_headers(opts: *mut fluxio_clientconn_options, max: size_t) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.builder.http1_max_headers(max);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set the maximum size of a response header value, in bytes.
+    ///
+    /// A server sending a larger one fails the request with the code
+    /// `FLUXIO_HEADER_VALUE_TOO_LARGE`. Default is no limit other than the
+    /// size of the read buffer.
+    fn fluxio_clientconn_options_max_header_value_size(opts: *mut fluxio_clientconn_options, max: size_t) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.builder.http1_max_header_value_size(max);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set whether to accept obsolete line folding in response headers.
+    ///
+    /// Pass `0` to reject folded headers, `1` to accept them. If accepted,
+    /// each continuation line is unfolded into the header value, replacing
+    /// the line break with spaces, as RFC 7230 requires of user agents.
+    ///
+    /// Default is to reject them.
+    fn fluxio_clientconn_options_allow_obsolete_multiline_headers(opts: *mut fluxio_clientconn_options, enabled: c_int) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.builder.http1_allow_obsolete_multiline_headers_in_responses(enabled != 0);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set a callback for the metrics measured on this connection.
+    ///
+    /// The callback is called with the `userdata`, the kind of the metric,
+    /// and its value. It is called while the connection is being processed,
+    /// and so should return quickly.
+    fn fluxio_clientconn_options_metrics_callback(opts: *mut fluxio_clientconn_options, callback: fluxio_metrics_callback, userdata: *mut c_void) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.builder.metrics(Arc::new(MetricsCallback {
+            func: callback,
+            data: UserDataPointer(userdata),
+        }));
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set a callback for the lifecycle events of this connection.
+    ///
+    /// The callback is called with the `userdata`, the kind of the event,
+    /// the id of the connection, and a value depending on the event. It is
+    /// called while the connection is being processed, and so should return
+    /// quickly.
+    fn fluxio_clientconn_options_event_callback(opts: *mut fluxio_clientconn_options, callback: fluxio_conn_event_callback, userdata: *mut c_void) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.builder.observer(Arc::new(EventCallback {
+            func: callback,
+            data: UserDataPointer(userdata),
+        }));
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Add an interceptor to the requests sent on this connection, and their
+    /// responses.
+    ///
+    /// `before_send` is called with the `userdata` and each request right
+    /// before it is sent, and can change it like any other request, such as
+    /// setting headers or its URI. `after_response` is called with the
+    /// `userdata` and each response before the task sending its request
+    /// yields it. Either callback can be `NULL`.
+    ///
+    /// The callbacks should return `FLUXIO_ITER_CONTINUE`, or
+    /// `FLUXIO_ITER_BREAK` to fail the request with
+    /// `FLUXIO_ABORTED_BY_CALLBACK` instead. Requests aborted before being
+    /// sent leave the connection usable.
+    ///
+    /// Interceptors see requests in the order they were added, and responses
+    /// in the reverse order, so the first one added wraps all others.
+    ///
+    /// NOTE: The `fluxio_request *` and `fluxio_response *` are just
+    /// borrowed, and will not be valid after the callback finishes.
+    fn fluxio_clientconn_options_add_interceptor(opts: *mut fluxio_clientconn_options, before_send: Option<fluxio_interceptor_request_callback>, after_response: Option<fluxio_interceptor_response_callback>, userdata: *mut c_void) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.interceptors.push(Interceptor {
+            before_send,
+            after_response,
+            data: UserDataPointer(userdata),
+        });
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set which content codings of response bodies are decoded.
+    ///
+    /// The `flags` are a combination of the `FLUXIO_DECOMPRESS_` constants,
+    /// or `0` to leave bodies alone, which is the default. The enabled
+    /// codings are advertised in `Accept-Encoding` when a request doesn't
+    /// have one already, and the `Content-Encoding` and `Content-Length`
+    /// headers are removed from decoded responses.
+    fn fluxio_clientconn_options_decompress(opts: *mut fluxio_clientconn_options, flags: c_int) -> fluxio_code {
+        #[cfg(feature = "decompress")]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            let codings = [
+                (super::FLUXIO_DECOMPRESS_GZIP, ContentEncoding::Gzip),
+                (super::FLUXIO_DECOMPRESS_DEFLATE, ContentEncoding::Deflate),
+                (super::FLUXIO_DECOMPRESS_BR, ContentEncoding::Brotli),
+                (super::FLUXIO_DECOMPRESS_ZSTD, ContentEncoding::Zstd),
+            ];
+            let known = codings.iter().fold(0, |all, (flag, _)| all | flag);
+            if flags & !known != 0 {
+                return fluxio_code::FLUXIO_INVALID_ARG;
+            }
+            opts.builder.decompress(
+                codings
+                    .into_iter()
+                    .filter(|(flag, _)| flags & flag != 0)
+                    .map(|(_, coding)| coding),
+            );
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(feature = "decompress"))]
+        {
+            drop(opts);
+            drop(flags);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set whether a `Host` header is added to requests, derived from the
+    /// authority of their URI.
+    ///
+    /// The `mode` is one of the `FLUXIO_AUTO_HOST_` constants, and defaults
+    /// to `FLUXIO_AUTO_HOST_NEVER`. The host is kept as written, followed by
+    /// the port unless that is the default one of the scheme. Requests whose
+    /// URI has no authority, like `/path`, are left alone. Internationalized
+    /// domain names must be in their punycode form in the URI.
+    ///
+    /// This is applied after the interceptors' `before_send` callbacks.
+    fn fluxio_clientconn_options_auto_host(opts: *mut fluxio_clientconn_options, mode: c_int) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        opts.host = match mode {
+            super::FLUXIO_AUTO_HOST_NEVER => HostPolicy::Never,
+            super::FLUXIO_AUTO_HOST_IF_MISSING => HostPolicy::SetIfMissing,
+            super::FLUXIO_AUTO_HOST_OVERRIDE => HostPolicy::Override,
+            _ => return fluxio_code::FLUXIO_INVALID_ARG,
+        };
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set how long to wait for the head of a response, in milliseconds,
+    /// from when its request is sent.
+    ///
+    /// A request timing out fails with `FLUXIO_RESPONSE_HEADER_TIMEOUT`.
+    /// This doesn't limit how long the body takes to arrive, see
+    /// `fluxio_clientconn_options_body_idle_timeout`.
+    ///
+    /// Pass `0` to disable the timeout, which is the default.
+    fn fluxio_clientconn_options_response_header_timeout(opts: *mut fluxio_clientconn_options, timeout_ms: u64) -> fluxio_code {
+        #[cfg(feature = "runtime")]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.response_header_timeout(as_timeout(timeout_ms));
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(feature = "runtime"))]
+        {
+            drop(opts);
+            drop(timeout_ms);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set how long a response body can be waited on without receiving
+    /// data, in milliseconds.
+    ///
+    /// The timer starts each time the body is polled and no data is ready. If
+    /// it elapses, the body fails with `FLUXIO_BODY_IDLE_TIMEOUT`.
+    ///
+    /// Pass `0` to disable the timeout, which is the default.
+    fn fluxio_clientconn_options_body_idle_timeout(opts: *mut fluxio_clientconn_options, timeout_ms: u64) -> fluxio_code {
+        #[cfg(feature = "runtime")]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.body_idle_timeout(as_timeout(timeout_ms));
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(feature = "runtime"))]
+        {
+            drop(opts);
+            drop(timeout_ms);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set a rate limit, in bytes per second, for sending request bodies.
+    ///
+    /// Body chunks are paced with a token bucket holding a second worth of
+    /// bytes, so a large upload doesn't saturate a constrained uplink. Chunks
+    /// aren't split, so a chunk larger than the rate is sent at once, and
+    /// delays the next one instead. This only affects HTTP/1 connections.
+    ///
+    /// Pass `0` to disable the limit, which is the default.
+    fn fluxio_clientconn_options_upload_rate_limit(opts: *mut fluxio_clientconn_options, bytes_per_sec: u64) -> fluxio_code {
+        #[cfg(all(feature = "http1", feature = "runtime"))]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.http1_upload_rate_limit(Some(bytes_per_sec).filter(|&rate| rate > 0));
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(all(feature = "http1", feature = "runtime")))]
+        {
+            drop(opts);
+            drop(bytes_per_sec);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set an interval, in milliseconds, for sending HTTP/2 PING frames to
+    /// keep the connection alive.
+    ///
+    /// A PING is sent once no frame was received for this long. If it isn't
+    /// acknowledged within the timeout set with
+    /// `fluxio_clientconn_options_http2_keep_alive_timeout`, the connection
+    /// is closed.
+    ///
+    /// Pass `0` to disable keep-alive, which is the default.
+    fn fluxio_clientconn_options_http2_keep_alive_interval(opts: *mut fluxio_clientconn_options, interval_ms: u64) -> fluxio_code {
+        #[cfg(all(feature = "http2", feature = "runtime"))]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.http2_keep_alive_interval(as_timeout(interval_ms));
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(all(feature = "http2", feature = "runtime")))]
+        {
+            drop(opts);
+            drop(interval_ms);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set how long to wait for a keep-alive PING to be acknowledged, in
+    /// milliseconds, before closing the connection.
+    ///
+    /// Does nothing unless an interval is set with
+    /// `fluxio_clientconn_options_http2_keep_alive_interval`. The default is
+    /// 20 seconds, and `0` is an invalid argument.
+    fn fluxio_clientconn_options_http2_keep_alive_timeout(opts: *mut fluxio_clientconn_options, timeout_ms: u64) -> fluxio_code {
+        #[cfg(all(feature = "http2", feature = "runtime"))]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            match as_timeout(timeout_ms) {
+                Some(timeout) => {
+                    opts.builder.http2_keep_alive_timeout(timeout);
+                    fluxio_code::FLUXIO_OK
+                }
+                None => fluxio_code::FLUXIO_INVALID_ARG,
+            }
+        }
+
+        #[cfg(not(all(feature = "http2", feature = "runtime")))]
+        {
+            drop(opts);
+            drop(timeout_ms);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set whether HTTP/2 keep-alive PINGs are sent while no requests are in
+    /// flight.
+    ///
+    /// Pass `0` to only send them while requests are in flight, which is the
+    /// default, or `1` to also send them while the connection is idle.
+    fn fluxio_clientconn_options_http2_keep_alive_while_idle(opts: *mut fluxio_clientconn_options, enabled: c_int) -> fluxio_code {
+        #[cfg(all(feature = "http2", feature = "runtime"))]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.http2_keep_alive_while_idle(enabled != 0);
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(all(feature = "http2", feature = "runtime")))]
+        {
+            drop(opts);
+            drop(enabled);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set whether HTTP/1 connections are kept alive for more requests.
+    ///
+    /// If disabled, requests are sent with a `Connection: close` header, and
+    /// the connection closes once the first response was received.
+    ///
+    /// Pass `1` to keep connections alive, which is the default, or `0` to
+    /// disable it.
+    fn fluxio_clientconn_options_http1_keepalive(opts: *mut fluxio_clientconn_options, enabled: c_int) -> fluxio_code {
+        #[cfg(feature = "http1")]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.http1_keep_alive(enabled != 0);
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(feature = "http1"))]
+        {
+            drop(opts);
+            drop(enabled);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Set how long an HTTP/1 connection can go without a request in flight
+    /// before it is closed, in milliseconds.
+    ///
+    /// The timer starts when the connection is established, and when a
+    /// response and its body were fully received. Once the connection is
+    /// closed, sending a request fails with `FLUXIO_CONN_CLOSED`. This only
+    /// affects HTTP/1 connections.
+    ///
+    /// Pass `0` to disable the timeout, which is the default.
+    fn fluxio_clientconn_options_idle_timeout_ms(opts: *mut fluxio_clientconn_options, timeout_ms: u64) -> fluxio_code {
+        #[cfg(all(feature = "http1", feature = "runtime"))]
+        {
+            let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+            opts.builder.http1_idle_timeout(as_timeout(timeout_ms));
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(all(feature = "http1", feature = "runtime")))]
+        {
+            drop(opts);
+            drop(timeout_ms);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
+ffi_fn! {
+    /// Configure connections with a versioned profile of hardened defaults,
+    /// with stricter parsing, smaller limits and a response header timeout.
+    ///
+    /// Options set afterwards override the profile, and are counted by
+    /// `fluxio_clientconn_options_hardened_overridden`. Only version `1`
+    /// exists, others return `FLUXIO_INVALID_ARG`.
+    fn fluxio_clientconn_options_hardened(opts: *mut fluxio_clientconn_options, version: u32) -> fluxio_code {
+        let opts = non_null! { &mut *opts ?= fluxio_code::FLUXIO_INVALID_ARG };
+        let profile = match version {
+            1 => Profile::V1,
+            _ => return fluxio_code::FLUXIO_INVALID_ARG,
+        };
+        opts.builder.preset_hardened(profile);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Returns how many options of the hardened profile were set again since
+    /// it was applied with `fluxio_clientconn_options_hardened`, or `-1` if
+    /// no profile was applied.
+    fn fluxio_clientconn_options_hardened_overridden(opts: *const fluxio_clientconn_options) -> c_int {
+        let opts = non_null! { &*opts ?= -1 };
+        match opts.builder.hardened_posture() {
+            Some(posture) => posture.overridden().len() as c_int,
+            None => -1,
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+fn as_timeout(timeout_ms: u64) -> Option<Duration> {
+    match timeout_ms {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+// ===== impl Interceptor =====
+
+impl Interceptor {
+    /// Returns whether to send the request.
+    fn before_send(&self, req: &mut fluxio_request) -> bool {
+        self.before_send
+            .map_or(true, |func| func(self.data.0, req) == FLUXIO_ITER_CONTINUE)
+    }
+
+    /// Returns whether to yield the response.
+    fn after_response(&self, resp: &mut fluxio_response) -> bool {
+        self.after_response
+            .map_or(true, |func| func(self.data.0, resp) == FLUXIO_ITER_CONTINUE)
+    }
+}
+
+// ===== impl MetricsCallback =====
+
+impl MetricsCallback {
+    fn call(&self, metric: fluxio_metric, value: u64) {
+        (self.func)(self.data.0, metric, value);
+    }
+}
+
+fn as_micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+impl MetricsSink for MetricsCallback {
+    fn bytes_read(&self, bytes: u64) {
+        self.call(fluxio_metric::FLUXIO_METRIC_BYTES_READ, bytes);
+    }
+
+    fn bytes_written(&self, bytes: u64) {
+        self.call(fluxio_metric::FLUXIO_METRIC_BYTES_WRITTEN, bytes);
+    }
+
+    fn request_latency(&self, latency: Duration) {
+        self.call(
+            fluxio_metric::FLUXIO_METRIC_REQUEST_LATENCY,
+            as_micros(latency),
+        );
+    }
+
+    fn handshake_duration(&self, duration: Duration) {
+        self.call(
+            fluxio_metric::FLUXIO_METRIC_HANDSHAKE_DURATION,
+            as_micros(duration),
+        );
+    }
+
+    fn stream_reset(&self) {
+        self.call(fluxio_metric::FLUXIO_METRIC_STREAM_RESET, 1);
+    }
+}
+
+// ===== impl EventCallback =====
+
+impl EventCallback {
+    fn call(&self, event: fluxio_conn_event, conn: &ConnectionInfo, value: c_int) {
+        (self.func)(self.data.0, event, conn.id(), value);
+    }
+}
+
+impl ConnectionObserver for EventCallback {
+    fn on_connect(&self, conn: &ConnectionInfo) {
+        self.call(fluxio_conn_event::FLUXIO_CONN_EVENT_CONNECT, conn, 0);
+    }
+
+    fn on_connection_close(&self, conn: &ConnectionInfo, error: Option<&crate::Error>) {
+        self.call(
+            fluxio_conn_event::FLUXIO_CONN_EVENT_CLOSE,
+            conn,
+            error.is_some() as c_int,
+        );
+    }
+
+    fn on_request_start(&self, conn: &ConnectionInfo, _method: &Method, _uri: &Uri) {
+        self.call(fluxio_conn_event::FLUXIO_CONN_EVENT_REQUEST_START, conn, 0);
+    }
+
+    fn on_response_headers(&self, conn: &ConnectionInfo, status: StatusCode) {
+        self.call(
+            fluxio_conn_event::FLUXIO_CONN_EVENT_RESPONSE_HEADERS,
+            conn,
+            c_int::from(status.as_u16()),
+        );
+    }
+
+    fn on_body_complete(&self, conn: &ConnectionInfo) {
+        self.call(fluxio_conn_event::FLUXIO_CONN_EVENT_BODY_COMPLETE, conn, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::task::{
+        fluxio_executor_free, fluxio_executor_new, fluxio_executor_poll, fluxio_executor_push,
+        fluxio_task_free, fluxio_task_type, fluxio_task_value,
+    };
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clientconn_ready_and_closed() {
+        let (io, server) = tokio::io::duplex(1024);
+        let (tx, conn) = conn::Builder::new()
+            .handshake::<_, crate::Body>(io)
+            .await
+            .unwrap();
+        let conn_task = tokio::spawn(conn);
+        let mut conn = fluxio_clientconn {
+            tx: Arc::new(Mutex::new(tx)),
+            peer_addr: None,
+            local_addr: None,
+            interceptors: Vec::new().into(),
+            host: HostPolicy::Never,
+            sent: AtomicBool::new(false),
+        };
+
+        std::future::poll_fn(|cx| conn.tx.lock().unwrap().poll_ready(cx))
+            .await
+            .unwrap();
+        assert_eq!(fluxio_clientconn_is_ready(&conn), 1);
+        assert_eq!(fluxio_clientconn_is_closed(&conn), 0);
+
+        drop(server);
+        let _ = conn_task.await;
+        assert_eq!(fluxio_clientconn_is_closed(&conn), 1);
+
+        // sending fails with a distinct code
+        let exec = fluxio_executor_new();
+        let req = super::super::http_types::fluxio_request_new();
+        fluxio_executor_push(exec, fluxio_clientconn_send(&mut conn, req));
+        let task = fluxio_executor_poll(exec);
+        assert!(matches!(
+            fluxio_task_type(task),
+            fluxio_task_return_type::FLUXIO_TASK_ERROR
+        ));
+        let err = fluxio_task_value(task) as *mut super::super::error::fluxio_error;
+        assert!(matches!(
+            super::super::error::fluxio_error_code(err),
+            fluxio_code::FLUXIO_CONN_CLOSED
+        ));
+        super::super::error::fluxio_error_free(err);
+        fluxio_task_free(task);
+        fluxio_executor_free(exec);
+    }
+
+    #[tokio::test]
+    async fn test_clientconn_ping_http1() {
+        let (io, _server) = tokio::io::duplex(1024);
+        let (tx, conn) = conn::Builder::new()
+            .handshake::<_, crate::Body>(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+        let mut conn = fluxio_clientconn {
+            tx: Arc::new(Mutex::new(tx)),
+            peer_addr: None,
+            local_addr: None,
+            interceptors: Vec::new().into(),
+            host: HostPolicy::Never,
+            sent: AtomicBool::new(false),
+        };
+
+        // HTTP/1 has no PINGs
+        let exec = fluxio_executor_new();
+        fluxio_executor_push(exec, fluxio_clientconn_ping(&mut conn));
+        let task = fluxio_executor_poll(exec);
+        assert!(!task.is_null());
+        assert!(matches!(
+            fluxio_task_type(task),
+            fluxio_task_return_type::FLUXIO_TASK_ERROR
+        ));
+        fluxio_task_free(task);
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_options_alpn() {
+        let opts = fluxio_clientconn_options_new();
+        let set =
+            |protos: &[u8]| fluxio_clientconn_options_alpn(opts, protos.as_ptr(), protos.len());
+
+        assert!(matches!(set(b"\x08http/1.1"), fluxio_code::FLUXIO_OK));
+        assert_eq!(unsafe { &(*opts).alpn }, &Some(vec![b"http/1.1".to_vec()]));
+
+        for malformed in [&b""[..], b"\x00", b"\x09http/1.1", b"\x06spdy/3"] {
+            assert!(matches!(set(malformed), fluxio_code::FLUXIO_INVALID_ARG));
+        }
+        #[cfg(not(feature = "http2"))]
+        assert!(matches!(
+            set(b"\x02h2\x08http/1.1"),
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        ));
+
+        fluxio_clientconn_options_free(opts);
+    }
+
+    #[test]
+    fn test_options_http2_keep_alive() {
+        let opts = fluxio_clientconn_options_new();
+
+        #[cfg(all(feature = "http2", feature = "runtime"))]
+        {
+            assert!(matches!(
+                fluxio_clientconn_options_http2_keep_alive_interval(opts, 10_000),
+                fluxio_code::FLUXIO_OK
+            ));
+            assert!(matches!(
+                fluxio_clientconn_options_http2_keep_alive_timeout(opts, 0),
+                fluxio_code::FLUXIO_INVALID_ARG
+            ));
+        }
+        #[cfg(not(all(feature = "http2", feature = "runtime")))]
+        assert!(matches!(
+            fluxio_clientconn_options_http2_keep_alive_while_idle(opts, 1),
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        ));
+
+        fluxio_clientconn_options_free(opts);
+    }
+
+    #[test]
+    fn test_clientconn_options_hardened() {
+        let opts = fluxio_clientconn_options_new();
+        assert_eq!(fluxio_clientconn_options_hardened_overridden(opts), -1);
+        assert!(matches!(
+            fluxio_clientconn_options_hardened(opts, 2),
+            fluxio_code::FLUXIO_INVALID_ARG
+        ));
+
+        assert!(matches!(
+            fluxio_clientconn_options_hardened(opts, 1),
+            fluxio_code::FLUXIO_OK
+        ));
+        assert_eq!(fluxio_clientconn_options_hardened_overridden(opts), 0);
+        fluxio_clientconn_options_max_informational(opts, 20);
+        assert_eq!(fluxio_clientconn_options_hardened_overridden(opts), 1);
+
+        fluxio_clientconn_options_free(opts);
+    }
+
+    #[tokio::test]
+    async fn test_clientconn_send_head_vetoed() {
+        use tokio::io::AsyncReadExt;
+
+        use super::super::http_types::{fluxio_request_new, fluxio_request_on_head};
+        use super::super::FLUXIO_ITER_BREAK;
+
+        extern "C" fn veto(data: *mut c_void, head: *const u8, len: size_t) -> c_int {
+            let seen = unsafe { &mut *(data as *mut Vec<u8>) };
+            seen.extend_from_slice(unsafe { std::slice::from_raw_parts(head, len) });
+            FLUXIO_ITER_BREAK
+        }
+
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (tx, conn) = conn::Builder::new()
+            .handshake::<_, crate::Body>(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+        let mut conn = fluxio_clientconn {
+            tx: Arc::new(Mutex::new(tx)),
+            peer_addr: None,
+            local_addr: None,
+            interceptors: Vec::new().into(),
+            host: HostPolicy::Never,
+            sent: AtomicBool::new(false),
+        };
+
+        let mut seen = Vec::<u8>::new();
+        let req = fluxio_request_new();
+        fluxio_request_on_head(req, veto, &mut seen as *mut _ as *mut c_void);
+        let exec = fluxio_executor_new();
+        fluxio_executor_push(exec, fluxio_clientconn_send(&mut conn, req));
+        let task = loop {
+            let task = fluxio_executor_poll(exec);
+            if !task.is_null() {
+                break task;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert!(matches!(
+            fluxio_task_type(task),
+            fluxio_task_return_type::FLUXIO_TASK_ERROR
+        ));
+        let err = fluxio_task_value(task) as *mut super::super::error::fluxio_error;
+        assert!(matches!(
+            super::super::error::fluxio_error_code(err),
+            fluxio_code::FLUXIO_ABORTED_BY_CALLBACK
+        ));
+        super::super::error::fluxio_error_free(err);
+        fluxio_task_free(task);
+        fluxio_executor_free(exec);
+
+        assert!(seen.starts_with(b"GET / HTTP/1.1\r\n"), "{:?}", seen);
+        assert!(seen.ends_with(b"\r\n\r\n"), "{:?}", seen);
+        // nothing was written before the connection closed
+        let mut written = Vec::new();
+        server.read_to_end(&mut written).await.unwrap();
+        assert!(written.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_clientconn_interceptors() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use super::super::http_types::{
+            fluxio_headers_set, fluxio_request_headers, fluxio_request_new, fluxio_response_status,
+        };
+        use super::super::FLUXIO_ITER_BREAK;
+
+        extern "C" fn add_header(data: *mut c_void, req: *mut fluxio_request) -> c_int {
+            let calls = unsafe { &mut *(data as *mut Vec<&str>) };
+            calls.push("before_send");
+            let (name, value) = (b"x-intercepted", b"1");
+            let headers = fluxio_request_headers(req);
+            fluxio_headers_set(
+                headers,
+                name.as_ptr(),
+                name.len(),
+                value.as_ptr(),
+                value.len(),
+            );
+            FLUXIO_ITER_CONTINUE
+        }
+
+        extern "C" fn reject_server_error(data: *mut c_void, resp: *mut fluxio_response) -> c_int {
+            let calls = unsafe { &mut *(data as *mut Vec<&str>) };
+            calls.push("after_response");
+            if fluxio_response_status(resp) >= 500 {
+                FLUXIO_ITER_BREAK
+            } else {
+                FLUXIO_ITER_CONTINUE
+            }
+        }
+
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (tx, conn) = conn::Builder::new()
+            .handshake::<_, crate::Body>(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+        let mut calls = Vec::<&str>::new();
+        let mut conn = fluxio_clientconn {
+            tx: Arc::new(Mutex::new(tx)),
+            peer_addr: None,
+            local_addr: None,
+            interceptors: vec![Interceptor {
+                before_send: Some(add_header),
+                after_response: Some(reject_server_error),
+                data: UserDataPointer(&mut calls as *mut _ as *mut c_void),
+            }]
+            .into(),
+            host: HostPolicy::Never,
+            sent: AtomicBool::new(false),
+        };
+
+        let exec = fluxio_executor_new();
+        fluxio_executor_push(
+            exec,
+            fluxio_clientconn_send(&mut conn, fluxio_request_new()),
+        );
+
+        let mut buf = [0; 1024];
+        let n = server.read(&mut buf).await.unwrap();
+        let head = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(head.contains("\r\nx-intercepted: 1\r\n"), "{:?}", head);
+        server
+            .write_all(b"HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .unwrap();
+
+        let task = loop {
+            let task = fluxio_executor_poll(exec);
+            if !task.is_null() {
+                break task;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert!(matches!(
+            fluxio_task_type(task),
+            fluxio_task_return_type::FLUXIO_TASK_ERROR
+        ));
+        let err = fluxio_task_value(task) as *mut super::super::error::fluxio_error;
+        assert!(matches!(
+            super::super::error::fluxio_error_code(err),
+            fluxio_code::FLUXIO_ABORTED_BY_CALLBACK
+        ));
+        super::super::error::fluxio_error_free(err);
+        fluxio_task_free(task);
+        fluxio_executor_free(exec);
+
+        assert_eq!(calls, ["before_send", "after_response"]);
+    }
+
+    #[tokio::test]
+    async fn test_clientconn_auto_host() {
+        use tokio::io::AsyncReadExt;
+
+        use super::super::http_types::{fluxio_request_new, fluxio_request_set_uri};
+
+        let opts = fluxio_clientconn_options_new();
+        assert!(matches!(
+            fluxio_clientconn_options_auto_host(opts, 3),
+            fluxio_code::FLUXIO_INVALID_ARG
+        ));
+        assert!(matches!(
+            fluxio_clientconn_options_auto_host(opts, super::super::FLUXIO_AUTO_HOST_OVERRIDE),
+            fluxio_code::FLUXIO_OK
+        ));
+        fluxio_clientconn_options_free(opts);
+
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (tx, conn) = conn::Builder::new()
+            .handshake::<_, crate::Body>(io)
+            .await
+            .unwrap();
+        tokio::spawn(conn);
+        let mut conn = fluxio_clientconn {
+            tx: Arc::new(Mutex::new(tx)),
+            peer_addr: None,
+            local_addr: None,
+            interceptors: Vec::new().into(),
+            host: HostPolicy::SetIfMissing,
+            sent: AtomicBool::new(false),
+        };
+
+        let exec = fluxio_executor_new();
+        let req = fluxio_request_new();
+        let uri = b"http://Example.com:8080/path";
+        fluxio_request_set_uri(req, uri.as_ptr(), uri.len());
+        fluxio_executor_push(exec, fluxio_clientconn_send(&mut conn, req));
+        assert!(fluxio_executor_poll(exec).is_null());
+
+        let mut buf = [0; 1024];
+        let n = server.read(&mut buf).await.unwrap();
+        let head = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(
+            head.contains("\r\nhost: Example.com:8080\r\n"),
+            "{:?}",
+            head
+        );
+        fluxio_executor_free(exec);
+    }
+}
@@ -24,6 +24,22 @@ pub enum fluxio_code {
     FLUXIO_FEATURE_NOT_ENABLED,
     /// The peer sent an HTTP message that could not be parsed.
     FLUXIO_INVALID_PEER_MESSAGE,
+    /// The peer sent more informational (1xx) responses than allowed.
+    FLUXIO_TOO_MANY_INFORMATIONAL,
+    /// The head of a response didn't arrive before the response header
+    /// timeout.
+    FLUXIO_RESPONSE_HEADER_TIMEOUT,
+    /// A response body didn't receive data before the body idle timeout.
+    FLUXIO_BODY_IDLE_TIMEOUT,
+    /// The connection was closed, such as after its idle timeout, so no more
+    /// requests can be sent on it.
+    FLUXIO_CONN_CLOSED,
+    /// The peer sent a message with more headers than allowed.
+    FLUXIO_TOO_MANY_HEADERS,
+    /// The peer sent a header value larger than allowed.
+    FLUXIO_HEADER_VALUE_TOO_LARGE,
+    /// A body was larger than its maximum size.
+    FLUXIO_BODY_TOO_LARGE,
 }
 
 // ===== impl fluxio_error =====
@@ -31,12 +47,25 @@ pub enum fluxio_code {
 impl fluxio_error {
     fn code(&self) -> fluxio_code {
         use crate::error::Kind as ErrorKind;
-        use crate::error::User;
+        use crate::error::{Parse, User};
 
         match self.0.kind() {
+            ErrorKind::Parse(Parse::TooManyInformational) => {
+                fluxio_code::FLUXIO_TOO_MANY_INFORMATIONAL
+            }
+            ErrorKind::Parse(Parse::TooManyHeaders) => fluxio_code::FLUXIO_TOO_MANY_HEADERS,
+            ErrorKind::Parse(Parse::HeaderValueTooLarge) => {
+                fluxio_code::FLUXIO_HEADER_VALUE_TOO_LARGE
+            }
             ErrorKind::Parse(_) => fluxio_code::FLUXIO_INVALID_PEER_MESSAGE,
             ErrorKind::IncompleteMessage => fluxio_code::FLUXIO_UNEXPECTED_EOF,
             ErrorKind::User(User::AbortedByCallback) => fluxio_code::FLUXIO_ABORTED_BY_CALLBACK,
+            #[cfg(feature = "runtime")]
+            ErrorKind::ResponseHeaderTimeout => fluxio_code::FLUXIO_RESPONSE_HEADER_TIMEOUT,
+            #[cfg(feature = "runtime")]
+            ErrorKind::BodyIdleTimeout => fluxio_code::FLUXIO_BODY_IDLE_TIMEOUT,
+            ErrorKind::ChannelClosed => fluxio_code::FLUXIO_CONN_CLOSED,
+            ErrorKind::BodyTooLarge => fluxio_code::FLUXIO_BODY_TOO_LARGE,
             // TODO: add more variants
             _ => fluxio_code::FLUXIO_ERROR,
         }
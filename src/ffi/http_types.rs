@@ -1,12 +1,19 @@
 use bytes::Bytes;
 use libc::{c_int, size_t};
+use std::collections::HashMap;
 use std::ffi::c_void;
+use std::time::Duration;
 
 use super::body::{fluxio_body, fluxio_buf};
 use super::error::fluxio_code;
 use super::task::{fluxio_task_return_type, AsTaskType};
 use super::{UserDataPointer, FLUXIO_ITER_CONTINUE};
-use crate::ext::{HeaderCaseMap, OriginalHeaderOrder};
+#[cfg(feature = "compress")]
+use crate::body::ContentEncoding;
+use crate::ext::{
+    HeaderCaseMap, OnInformationalCallback, OnRequestHead, OriginalHeaderOrder, ReasonPhrase,
+    RequestTimings,
+};
 use crate::header::{HeaderName, HeaderValue};
 use crate::{Body, HeaderMap, Method, Request, Response, Uri};
 
@@ -16,33 +23,77 @@ pub struct fluxio_request(pub(super) Request<Body>);
 /// An HTTP response.
 pub struct fluxio_response(pub(super) Response<Body>);
 
+/// How long sending a request took, as returned by `fluxio_response_timings`.
+///
+/// Durations are in microseconds, or `-1` if unknown.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct fluxio_request_timings {
+    /// How long resolving the destination took.
+    pub dns_us: i64,
+    /// How long establishing the connection took.
+    pub connect_us: i64,
+    /// How long the TLS handshake took.
+    pub tls_us: i64,
+    /// How long it took from sending the request to receiving the head of
+    /// the response.
+    pub ttfb_us: i64,
+    /// `1` if the request was sent on a connection that had been used before,
+    /// `0` otherwise.
+    pub reused: c_int,
+}
+
 /// An HTTP header map.
 ///
 /// These can be part of a request or response.
+#[derive(Clone)]
 pub struct fluxio_headers {
     pub(super) headers: HeaderMap,
     orig_casing: HeaderCaseMap,
     orig_order: OriginalHeaderOrder,
 }
 
-#[derive(Debug)]
-pub(crate) struct ReasonPhrase(pub(crate) Bytes);
-
 pub(crate) struct RawHeaders(pub(crate) fluxio_buf);
 
-pub(crate) struct OnInformational {
+/// The tracing span of a request, shared with its response.
+#[derive(Clone)]
+pub(super) struct RequestSpan(pub(super) tracing::Span);
+
+/// The values attached to a request or response with
+/// `fluxio_request_set_extension` and `fluxio_response_set_extension`, by
+/// key.
+#[derive(Default)]
+pub(super) struct UserExtensions(HashMap<Box<[u8]>, UserExtension>);
+
+struct UserExtension {
+    value: UserDataPointer,
+    drop_fn: fluxio_extension_drop_callback,
+}
+
+struct OnInformational {
     func: fluxio_request_on_informational_callback,
     data: UserDataPointer,
+    span: Option<RequestSpan>,
 }
 
+#[cfg(feature = "compress")]
+#[derive(Clone, Copy)]
+struct Compression(ContentEncoding, u32);
+
 type fluxio_request_on_informational_callback = extern "C" fn(*mut c_void, *mut fluxio_response);
 
+type fluxio_request_on_head_callback = extern "C" fn(*mut c_void, *const u8, size_t) -> c_int;
+
+type fluxio_extension_drop_callback = extern "C" fn(*mut c_void);
+
 // ===== impl fluxio_request =====
 
 ffi_fn! {
     /// Construct a new HTTP request.
     fn fluxio_request_new() -> *mut fluxio_request {
-        Box::into_raw(Box::new(fluxio_request(Request::new(Body::empty()))))
+        let mut req = Request::new(Body::empty());
+        req.extensions_mut().insert(RequestSpan::new());
+        Box::into_raw(Box::new(fluxio_request(req)))
     } ?= std::ptr::null_mut()
 }
 
@@ -177,6 +228,47 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Set whether to compress the body of this request when it is sent.
+    ///
+    /// The encoding should be one of the `FLUXIO_COMPRESS_` constants, and
+    /// `FLUXIO_COMPRESS_NONE` turns compression back off. The level is
+    /// clamped to the range of the encoding, such as `0` to `9` for gzip.
+    ///
+    /// The `Content-Encoding` header is set when the request is sent, and
+    /// any `Content-Length` header is removed. A request that already has a
+    /// `Content-Encoding` header is sent as it is.
+    fn fluxio_request_set_compression(req: *mut fluxio_request, encoding: c_int, level: c_int) -> fluxio_code {
+        #[cfg(feature = "compress")]
+        {
+            let req = non_null!(&mut *req ?= fluxio_code::FLUXIO_INVALID_ARG);
+            let level = match u32::try_from(level) {
+                Ok(level) => level,
+                Err(_) => return fluxio_code::FLUXIO_INVALID_ARG,
+            };
+            let encoding = match encoding {
+                super::FLUXIO_COMPRESS_NONE => {
+                    req.0.extensions_mut().remove::<Compression>();
+                    return fluxio_code::FLUXIO_OK;
+                }
+                super::FLUXIO_COMPRESS_GZIP => ContentEncoding::Gzip,
+                super::FLUXIO_COMPRESS_DEFLATE => ContentEncoding::Deflate,
+                super::FLUXIO_COMPRESS_BR => ContentEncoding::Brotli,
+                super::FLUXIO_COMPRESS_ZSTD => ContentEncoding::Zstd,
+                _ => return fluxio_code::FLUXIO_INVALID_ARG,
+            };
+            req.0.extensions_mut().insert(Compression(encoding, level));
+            fluxio_code::FLUXIO_OK
+        }
+
+        #[cfg(not(feature = "compress"))]
+        {
+            let _ = (req, encoding, level);
+            fluxio_code::FLUXIO_FEATURE_NOT_ENABLED
+        }
+    }
+}
+
 ffi_fn! {
     /// Gets a reference to the HTTP headers of this request
     ///
@@ -219,16 +311,141 @@ ffi_fn! {
     /// be valid after the callback finishes. You must copy any data you wish
     /// to persist.
     fn fluxio_request_on_informational(req: *mut fluxio_request, callback: fluxio_request_on_informational_callback, data: *mut c_void) -> fluxio_code {
+        let req = non_null!(&mut *req ?= fluxio_code::FLUXIO_INVALID_ARG);
         let ext = OnInformational {
             func: callback,
             data: UserDataPointer(data),
+            span: req.0.extensions().get::<RequestSpan>().cloned(),
         };
+        crate::ext::on_informational_raw(&mut req.0, ext);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set a callback inspecting the head of the request right before it is
+    /// sent, and deciding whether to send it.
+    ///
+    /// The callback is passed the `void *` data pointer, and the request
+    /// head exactly as it will be written to the connection, including the
+    /// request line and the headers added when sending it. This allows
+    /// auditing or signing what is sent.
+    ///
+    /// The callback should return `FLUXIO_ITER_CONTINUE` to send the
+    /// request, or `FLUXIO_ITER_BREAK` to abort it. An aborted request
+    /// fails with `FLUXIO_ABORTED_BY_CALLBACK`, and closes the connection,
+    /// since nothing of it was written.
+    ///
+    /// This is only called on HTTP/1 connections, since HTTP/2 doesn't send
+    /// the head as text.
+    ///
+    /// NOTE: The head is just borrowed data, and will not be valid after the
+    /// callback finishes. You must copy any data you wish to persist.
+    fn fluxio_request_on_head(req: *mut fluxio_request, callback: fluxio_request_on_head_callback, data: *mut c_void) -> fluxio_code {
         let req = non_null!(&mut *req ?= fluxio_code::FLUXIO_INVALID_ARG);
+        let data = UserDataPointer(data);
+        let ext = OnRequestHead::new(move |head| {
+            let data = &data;
+            callback(data.0, head.as_ptr(), head.len()) == FLUXIO_ITER_CONTINUE
+        });
         req.0.extensions_mut().insert(ext);
         fluxio_code::FLUXIO_OK
     }
 }
 
+ffi_fn! {
+    /// Make a deep copy of this request.
+    ///
+    /// The method, URI, version, and headers (including their original
+    /// casing and order) are copied, as well as the body if its data is
+    /// fully buffered. The copy shares nothing with the original, so it can
+    /// be handed to another thread, and both can be sent or freed
+    /// independently.
+    ///
+    /// The informational (1xx) response and request head callbacks are not
+    /// copied, and the copy gets its own tracing span.
+    ///
+    /// Returns `NULL` if the body is a streaming body (such as one using
+    /// `fluxio_body_set_data_func`), since its data cannot be copied.
+    fn fluxio_request_clone(req: *const fluxio_request) -> *mut fluxio_request {
+        let req = non_null!(&*req ?= std::ptr::null_mut());
+        let body = match req.0.body().try_clone_buffered() {
+            Some(body) => body,
+            None => return std::ptr::null_mut(),
+        };
+
+        let mut copy = Request::new(body);
+        copy.extensions_mut().insert(RequestSpan::new());
+        *copy.method_mut() = req.0.method().clone();
+        *copy.uri_mut() = req.0.uri().clone();
+        *copy.version_mut() = req.0.version();
+        *copy.headers_mut() = req.0.headers().clone();
+        if let Some(headers) = req.0.extensions().get::<fluxio_headers>() {
+            copy.extensions_mut().insert(headers.clone());
+        }
+        #[cfg(feature = "compress")]
+        if let Some(compression) = req.0.extensions().get::<Compression>() {
+            copy.extensions_mut().insert(*compression);
+        }
+
+        Box::into_raw(Box::new(fluxio_request(copy)))
+    } ?= std::ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Get the id of the tracing span of this request.
+    ///
+    /// Each request gets a `request` span, entered by fluxio while sending
+    /// it. Its id can be added to logs written by the application, to
+    /// correlate them with fluxio's own logs. Responses to this request,
+    /// including informational (1xx) ones passed to callbacks, carry the same
+    /// id, see `fluxio_response_span_id`.
+    ///
+    /// Returns 0 if there is no span, such as when no tracing subscriber is
+    /// installed, or it isn't interested in this span.
+    fn fluxio_request_span_id(req: *const fluxio_request) -> u64 {
+        non_null!(&*req ?= 0).0.extensions().get::<RequestSpan>().map_or(0, RequestSpan::id)
+    }
+}
+
+ffi_fn! {
+    /// Attach a value to this request under a key.
+    ///
+    /// The value is an opaque pointer, owned by the request from now on:
+    /// `drop_fn` is called with it once the request or the response it is
+    /// moved to is freed, or when another value is set under the same key.
+    /// This allows middleware to pass data along with a request, without
+    /// keeping it in a global map.
+    ///
+    /// When the request is sent with `fluxio_clientconn_send`, its values
+    /// are moved to the response, see `fluxio_response_extension`. They are
+    /// not copied by `fluxio_request_clone`.
+    ///
+    /// Keys are compared byte by byte.
+    fn fluxio_request_set_extension(req: *mut fluxio_request, key: *const u8, key_len: size_t, value: *mut c_void, drop_fn: fluxio_extension_drop_callback) -> fluxio_code {
+        let req = non_null!(&mut *req ?= fluxio_code::FLUXIO_INVALID_ARG);
+        let key = non_null!(key, std::slice::from_raw_parts(key, key_len), fluxio_code::FLUXIO_INVALID_ARG);
+        let ext = UserExtension {
+            value: UserDataPointer(value),
+            drop_fn,
+        };
+        UserExtensions::get_or_default(req.0.extensions_mut()).insert(key, ext);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Get the value attached to this request under a key.
+    ///
+    /// Returns `NULL` if no value was set under the key. The value is still
+    /// owned by the request.
+    fn fluxio_request_extension(req: *const fluxio_request, key: *const u8, key_len: size_t) -> *mut c_void {
+        let req = non_null!(&*req ?= std::ptr::null_mut());
+        let key = non_null!(key, std::slice::from_raw_parts(key, key_len), std::ptr::null_mut());
+        UserExtensions::get(req.0.extensions(), key)
+    } ?= std::ptr::null_mut()
+}
+
 impl fluxio_request {
     pub(super) fn finalize_request(&mut self) {
         if let Some(headers) = self.0.extensions_mut().remove::<fluxio_headers>() {
@@ -236,6 +453,12 @@ impl fluxio_request {
             self.0.extensions_mut().insert(headers.orig_casing);
             self.0.extensions_mut().insert(headers.orig_order);
         }
+
+        #[cfg(feature = "compress")]
+        if let Some(Compression(encoding, level)) = self.0.extensions_mut().remove() {
+            let req = std::mem::replace(&mut self.0, Request::new(Body::empty()));
+            self.0 = crate::body::compress_request(req, encoding, level);
+        }
     }
 }
 
@@ -281,6 +504,30 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Set the reason-phrase of this response.
+    ///
+    /// The phrase is copied, and replaces the canonical reason of the status
+    /// code when the response is written on an HTTP/1 connection.
+    ///
+    /// Returns `FLUXIO_INVALID_ARG` if the phrase contains a byte not
+    /// allowed in a reason-phrase, such as a CR or LF. Tabs, spaces, visible
+    /// ASCII characters, and bytes above 0x7F are allowed.
+    fn fluxio_response_set_reason_phrase(resp: *mut fluxio_response, reason: *const u8, reason_len: size_t) -> fluxio_code {
+        let resp = non_null!(&mut *resp ?= fluxio_code::FLUXIO_INVALID_ARG);
+        let bytes = unsafe {
+            std::slice::from_raw_parts(reason, reason_len as usize)
+        };
+        match ReasonPhrase::try_from(bytes) {
+            Ok(reason) => {
+                resp.0.extensions_mut().insert(reason);
+                fluxio_code::FLUXIO_OK
+            }
+            Err(_) => fluxio_code::FLUXIO_INVALID_ARG,
+        }
+    }
+}
+
 ffi_fn! {
     /// Get a reference to the full raw headers of this response.
     ///
@@ -343,6 +590,63 @@ ffi_fn! {
     } ?= std::ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Get the id of the tracing span of the request this response is for.
+    ///
+    /// This is the same id returned by `fluxio_request_span_id` for the
+    /// request, or 0 if it had no span.
+    fn fluxio_response_span_id(resp: *const fluxio_response) -> u64 {
+        non_null!(&*resp ?= 0).0.extensions().get::<RequestSpan>().map_or(0, RequestSpan::id)
+    }
+}
+
+ffi_fn! {
+    /// Attach a value to this response under a key.
+    ///
+    /// This works like `fluxio_request_set_extension`: the response owns the
+    /// value, and `drop_fn` is called with it once the response is freed, or
+    /// when another value is set under the same key.
+    fn fluxio_response_set_extension(resp: *mut fluxio_response, key: *const u8, key_len: size_t, value: *mut c_void, drop_fn: fluxio_extension_drop_callback) -> fluxio_code {
+        let resp = non_null!(&mut *resp ?= fluxio_code::FLUXIO_INVALID_ARG);
+        let key = non_null!(key, std::slice::from_raw_parts(key, key_len), fluxio_code::FLUXIO_INVALID_ARG);
+        let ext = UserExtension {
+            value: UserDataPointer(value),
+            drop_fn,
+        };
+        UserExtensions::get_or_default(resp.0.extensions_mut()).insert(key, ext);
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Get the value attached to this response, or to the request it is
+    /// for, under a key.
+    ///
+    /// Returns `NULL` if no value was set under the key. The value is still
+    /// owned by the response.
+    fn fluxio_response_extension(resp: *const fluxio_response, key: *const u8, key_len: size_t) -> *mut c_void {
+        let resp = non_null!(&*resp ?= std::ptr::null_mut());
+        let key = non_null!(key, std::slice::from_raw_parts(key, key_len), std::ptr::null_mut());
+        UserExtensions::get(resp.0.extensions(), key)
+    } ?= std::ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Get how long sending the request of this response took.
+    ///
+    /// The durations of establishing the connection are never known for
+    /// connections handshaked with `fluxio_clientconn_handshake`, as the IO
+    /// is established by the caller. The durations are all `-1` if the
+    /// response wasn't received on a client connection.
+    fn fluxio_response_timings(resp: *const fluxio_response) -> fluxio_request_timings {
+        let resp = non_null!(&*resp ?= fluxio_request_timings::unknown());
+        match resp.0.extensions().get::<RequestTimings>() {
+            Some(timings) => fluxio_request_timings::new(timings),
+            None => fluxio_request_timings::unknown(),
+        }
+    } ?= fluxio_request_timings::unknown()
+}
+
 impl fluxio_response {
     pub(super) fn wrap(mut resp: Response<Body>) -> fluxio_response {
         let headers = std::mem::take(resp.headers_mut());
@@ -354,6 +658,10 @@ impl fluxio_response {
             .extensions_mut()
             .remove::<OriginalHeaderOrder>()
             .unwrap_or_else(OriginalHeaderOrder::default);
+        if let Some(raw) = resp.extensions_mut().remove::<crate::ext::RawHeaders>() {
+            resp.extensions_mut()
+                .insert(RawHeaders(fluxio_buf(raw.into_bytes())));
+        }
         resp.extensions_mut().insert(fluxio_headers {
             headers,
             orig_casing,
@@ -365,7 +673,7 @@ impl fluxio_response {
 
     fn reason_phrase(&self) -> &[u8] {
         if let Some(reason) = self.0.extensions().get::<ReasonPhrase>() {
-            return &reason.0;
+            return reason.as_bytes();
         }
 
         if let Some(reason) = self.0.status().canonical_reason() {
@@ -382,6 +690,79 @@ unsafe impl AsTaskType for fluxio_response {
     }
 }
 
+// ===== impl fluxio_request_timings =====
+
+impl fluxio_request_timings {
+    fn new(timings: &RequestTimings) -> fluxio_request_timings {
+        fn micros(duration: Option<Duration>) -> i64 {
+            duration.map_or(-1, |d| i64::try_from(d.as_micros()).unwrap_or(i64::MAX))
+        }
+
+        fluxio_request_timings {
+            dns_us: micros(timings.dns()),
+            connect_us: micros(timings.connect()),
+            tls_us: micros(timings.tls()),
+            ttfb_us: micros(Some(timings.time_to_first_byte())),
+            reused: timings.is_reused() as c_int,
+        }
+    }
+
+    fn unknown() -> fluxio_request_timings {
+        fluxio_request_timings {
+            dns_us: -1,
+            connect_us: -1,
+            tls_us: -1,
+            ttfb_us: -1,
+            reused: 0,
+        }
+    }
+}
+
+// ===== impl RequestSpan =====
+
+impl RequestSpan {
+    fn new() -> RequestSpan {
+        RequestSpan(tracing::debug_span!(
+            "request",
+            method = tracing::field::Empty,
+            uri = tracing::field::Empty,
+        ))
+    }
+
+    fn id(&self) -> u64 {
+        self.0.id().map_or(0, |id| id.into_u64())
+    }
+}
+
+// ===== impl UserExtensions =====
+
+impl UserExtensions {
+    fn get_or_default(ext: &mut http::Extensions) -> &mut UserExtensions {
+        if ext.get::<UserExtensions>().is_none() {
+            ext.insert(UserExtensions::default());
+        }
+
+        ext.get_mut::<UserExtensions>().unwrap()
+    }
+
+    fn get(ext: &http::Extensions, key: &[u8]) -> *mut c_void {
+        ext.get::<UserExtensions>()
+            .and_then(|exts| exts.0.get(key))
+            .map_or(std::ptr::null_mut(), |ext| ext.value.0)
+    }
+
+    fn insert(&mut self, key: &[u8], ext: UserExtension) {
+        // Replacing a value drops the previous one.
+        self.0.insert(key.into(), ext);
+    }
+}
+
+impl Drop for UserExtension {
+    fn drop(&mut self) {
+        (self.drop_fn)(self.value.0);
+    }
+}
+
 // ===== impl Headers =====
 
 type fluxio_headers_foreach_callback =
@@ -534,9 +915,12 @@ unsafe fn raw_name_value(
 
 // ===== impl OnInformational =====
 
-impl OnInformational {
-    pub(crate) fn call(&mut self, resp: Response<Body>) {
-        let mut resp = fluxio_response::wrap(resp);
+impl OnInformationalCallback for OnInformational {
+    fn on_informational(&self, resp: Response<()>) {
+        let mut resp = fluxio_response::wrap(resp.map(|()| Body::empty()));
+        if let Some(ref span) = self.span {
+            resp.0.extensions_mut().insert(span.clone());
+        }
         (self.func)(self.data.0, &mut resp);
     }
 }
@@ -594,6 +978,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_request_clone_copies_head() {
+        let req = fluxio_request_new();
+        let method = b"PUT";
+        fluxio_request_set_method(req, method.as_ptr(), method.len());
+        let uri = b"/upload";
+        fluxio_request_set_uri(req, uri.as_ptr(), uri.len());
+        let name = b"X-Token";
+        let value = b"abc";
+        fluxio_headers_add(
+            fluxio_request_headers(req),
+            name.as_ptr(),
+            name.len(),
+            value.as_ptr(),
+            value.len(),
+        );
+
+        let copy = fluxio_request_clone(req);
+        fluxio_request_free(req);
+
+        let mut copy = unsafe { Box::from_raw(copy) };
+        copy.finalize_request();
+        assert_eq!(copy.0.method(), Method::PUT);
+        assert_eq!(copy.0.uri(), "/upload");
+        assert_eq!(copy.0.headers()["x-token"], "abc");
+        let casing = copy.0.extensions().get::<HeaderCaseMap>().unwrap();
+        assert_eq!(
+            casing
+                .get_all_internal(&HeaderName::from_static("x-token"))
+                .collect::<Vec<_>>(),
+            vec![&Bytes::from("X-Token")]
+        );
+
+        // streaming bodies can't be copied
+        let body = crate::ffi::fluxio_body_new();
+        crate::ffi::fluxio_body_set_userdata(body, std::ptr::null_mut());
+        let req = fluxio_request_new();
+        fluxio_request_set_body(req, body);
+        assert!(fluxio_request_clone(req).is_null());
+        fluxio_request_free(req);
+    }
+
     #[cfg(all(feature = "http1", feature = "ffi"))]
     #[test]
     fn test_headers_foreach_order_preserved() {
@@ -657,4 +1083,104 @@ mod tests {
             FLUXIO_ITER_CONTINUE
         }
     }
+
+    #[test]
+    fn test_response_set_reason_phrase() {
+        let mut resp = fluxio_response(Response::new(Body::empty()));
+        assert_eq!(resp.reason_phrase(), b"OK");
+
+        let reason = b"Very OK";
+        assert!(matches!(
+            fluxio_response_set_reason_phrase(&mut resp, reason.as_ptr(), reason.len()),
+            fluxio_code::FLUXIO_OK
+        ));
+        assert_eq!(resp.reason_phrase(), b"Very OK");
+
+        let reason = b"OK\r\nX-Injected: 1";
+        assert!(matches!(
+            fluxio_response_set_reason_phrase(&mut resp, reason.as_ptr(), reason.len()),
+            fluxio_code::FLUXIO_INVALID_ARG
+        ));
+        assert_eq!(resp.reason_phrase(), b"Very OK");
+    }
+
+    #[test]
+    fn test_extensions_dropped_with_owner() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+        extern "C" fn drop_value(value: *mut c_void) {
+            DROPPED.fetch_add(value as usize, Ordering::SeqCst);
+        }
+
+        let key = b"tenant";
+        let req = fluxio_request_new();
+        assert!(fluxio_request_extension(req, key.as_ptr(), key.len()).is_null());
+        fluxio_request_set_extension(req, key.as_ptr(), key.len(), 1 as *mut c_void, drop_value);
+        assert_eq!(
+            fluxio_request_extension(req, key.as_ptr(), key.len()),
+            1 as *mut c_void
+        );
+
+        // replacing a value drops the previous one
+        fluxio_request_set_extension(req, key.as_ptr(), key.len(), 2 as *mut c_void, drop_value);
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+
+        // values move from a request to its response
+        let mut req = unsafe { Box::from_raw(req) };
+        let exts = req.0.extensions_mut().remove::<UserExtensions>().unwrap();
+        drop(req);
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+        let mut resp = fluxio_response::wrap(Response::new(Body::empty()));
+        resp.0.extensions_mut().insert(exts);
+        assert_eq!(
+            fluxio_response_extension(&resp, key.as_ptr(), key.len()),
+            2 as *mut c_void
+        );
+
+        fluxio_response_free(Box::into_raw(Box::new(resp)));
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_request_span_id() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        struct Spans(AtomicU64);
+
+        impl tracing::Subscriber for Spans {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(self.0.fetch_add(1, Ordering::SeqCst))
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        // without a subscriber, there is no span
+        let req = fluxio_request_new();
+        assert_eq!(fluxio_request_span_id(req), 0);
+        fluxio_request_free(req);
+
+        tracing::subscriber::with_default(Spans(AtomicU64::new(1)), || {
+            let req = fluxio_request_new();
+            let id = fluxio_request_span_id(req);
+            assert_ne!(id, 0);
+
+            let copy = fluxio_request_clone(req);
+            assert_ne!(fluxio_request_span_id(copy), 0);
+            assert_ne!(fluxio_request_span_id(copy), id);
+
+            fluxio_request_free(copy);
+            fluxio_request_free(req);
+        });
+    }
 }
@@ -1,29 +1,67 @@
 use std::ffi::c_void;
+use std::io::IoSlice;
+use std::mem::MaybeUninit;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use libc::size_t;
+use bytes::{Buf, BytesMut};
+use libc::{c_int, size_t};
 use tokio::io::{AsyncRead, AsyncWrite};
 
+use super::error::fluxio_code;
 use super::task::fluxio_context;
+use crate::testing::{self, ChaosIo};
 
-/// Sentinel value to return from a read or write callback that the operation
-/// is pending.
+/// Sentinel value to return from a read, write, flush or shutdown callback
+/// that the operation is pending.
 pub const FLUXIO_IO_PENDING: size_t = 0xFFFFFFFF;
-/// Sentinel value to return from a read or write callback that the operation
-/// has errored.
+/// Sentinel value to return from a read, write, flush or shutdown callback
+/// that the operation has errored.
 pub const FLUXIO_IO_ERROR: size_t = 0xFFFFFFFE;
 
 type fluxio_io_read_callback =
     extern "C" fn(*mut c_void, *mut fluxio_context<'_>, *mut u8, size_t) -> size_t;
 type fluxio_io_write_callback =
     extern "C" fn(*mut c_void, *mut fluxio_context<'_>, *const u8, size_t) -> size_t;
+type fluxio_io_cork_callback = extern "C" fn(*mut c_void, c_int);
+type fluxio_io_writev_callback =
+    extern "C" fn(*mut c_void, *mut fluxio_context<'_>, *const fluxio_iovec, size_t) -> size_t;
+type fluxio_io_flush_callback = extern "C" fn(*mut c_void, *mut fluxio_context<'_>) -> size_t;
+type fluxio_io_shutdown_callback = extern "C" fn(*mut c_void, *mut fluxio_context<'_>) -> size_t;
+
+/// The most buffers passed to a single call of a vectored write callback.
+const MAX_IOVECS: usize = 64;
+
+/// A buffer passed to a `fluxio_io_writev_callback`.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct fluxio_iovec {
+    /// The start of the buffer.
+    pub buf: *const u8,
+    /// The length of the buffer.
+    pub len: size_t,
+}
 
 /// An IO object used to represent a socket or similar concept.
 pub struct fluxio_io {
     read: fluxio_io_read_callback,
     write: fluxio_io_write_callback,
+    cork: Option<fluxio_io_cork_callback>,
+    writev: Option<fluxio_io_writev_callback>,
+    flush: Option<fluxio_io_flush_callback>,
+    shutdown: Option<fluxio_io_shutdown_callback>,
     userdata: *mut c_void,
+    corked: bool,
+    write_buf: BytesMut,
+    low_watermark: usize,
+    high_watermark: usize,
+    remote_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    alpn: Option<Vec<u8>>,
+    /// The Rust transport `userdata` points to, for the IO created with
+    /// `fluxio_io_duplex_pair` or `fluxio_io_chaos_wrap`.
+    transport: Option<Box<dyn Send>>,
 }
 
 ffi_fn! {
@@ -35,11 +73,98 @@ ffi_fn! {
         Box::into_raw(Box::new(fluxio_io {
             read: read_noop,
             write: write_noop,
+            cork: None,
+            writev: None,
+            flush: None,
+            shutdown: None,
             userdata: std::ptr::null_mut(),
+            corked: false,
+            write_buf: BytesMut::new(),
+            low_watermark: 0,
+            high_watermark: 0,
+            remote_addr: None,
+            local_addr: None,
+            alpn: None,
+            transport: None,
         }))
     } ?= std::ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Create a pair of IO transports connected to each other in memory.
+    ///
+    /// Bytes written to one transport can be read from the other, so that
+    /// tests can drive full request and response cycles without sockets,
+    /// such as by handshaking a client connection on `client` and serving
+    /// `server`. Each direction buffers up to `capacity` bytes, after which
+    /// writes are pending until the other end reads.
+    ///
+    /// The transports come with their user data and callbacks set, which
+    /// must not be changed. Once one end is freed, reads from the other end
+    /// reach the end of the stream, and writes to it error.
+    ///
+    /// Returns `FLUXIO_INVALID_ARG` if `capacity` is 0 or an out pointer is
+    /// `NULL`.
+    fn fluxio_io_duplex_pair(capacity: size_t, client: *mut *mut fluxio_io, server: *mut *mut fluxio_io) -> fluxio_code {
+        if capacity == 0 || client.is_null() || server.is_null() {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
+        let (one, two) = testing::duplex(capacity);
+        unsafe {
+            *client = rust_io(one);
+            *server = rust_io(two);
+        }
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Wrap an IO transport to inject faults into its reads and writes, to
+    /// exercise how connections handle them.
+    ///
+    /// The faults are picked with a pseudo-random generator seeded with
+    /// `seed`, so a failing schedule can be replayed by reusing its seed:
+    ///
+    /// - `partial_writes` is the probability that a write only writes part
+    ///   of its buffer,
+    /// - `delayed_reads` is the probability that a read is first pending,
+    ///   waking the task right away,
+    /// - `spurious_wakeups` is the probability that the task is woken when
+    ///   the transport is pending, although it is not ready,
+    /// - `eof_after` ends the stream once that many bytes were read. Pass
+    ///   `UINT64_MAX` to never end it early.
+    ///
+    /// Returns a new transport taking ownership of `io`, with the addresses
+    /// and ALPN protocol of `io`. Its user data and callbacks must not be
+    /// changed. Returns `NULL` if a probability is not between 0 and 1, in
+    /// which case `io` is not taken.
+    fn fluxio_io_chaos_wrap(io: *mut fluxio_io, seed: u64, partial_writes: f64, delayed_reads: f64, spurious_wakeups: f64, eof_after: u64) -> *mut fluxio_io {
+        if io.is_null() || ![partial_writes, delayed_reads, spurious_wakeups]
+            .iter()
+            .all(|p| (0.0..=1.0).contains(p))
+        {
+            return std::ptr::null_mut();
+        }
+        let inner = unsafe { Box::from_raw(io) };
+        let (remote_addr, local_addr) = inner.connected_addrs();
+        let alpn = inner.alpn.clone();
+
+        let mut chaos = ChaosIo::new(inner, seed)
+            .partial_writes(partial_writes)
+            .delayed_reads(delayed_reads)
+            .spurious_wakeups(spurious_wakeups);
+        if eof_after != u64::MAX {
+            chaos = chaos.eof_after(eof_after);
+        }
+        let io = rust_io(chaos);
+        let io_ref = unsafe { &mut *io };
+        io_ref.remote_addr = remote_addr;
+        io_ref.local_addr = local_addr;
+        io_ref.alpn = alpn;
+        io
+    } ?= std::ptr::null_mut()
+}
+
 ffi_fn! {
     /// Free an unused `fluxio_io *`.
     ///
@@ -100,6 +225,308 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Set the write watermarks of this IO transport, so small writes are
+    /// coalesced before reaching the write callback.
+    ///
+    /// When enabled, written data is buffered by fluxio until either `high`
+    /// bytes are buffered, or fluxio flushes the transport, such as at the
+    /// end of a message or a batch of HTTP/2 frames. Writes that are at least
+    /// `high` bytes long skip the buffer.
+    ///
+    /// Once the buffer is full, fluxio writes it out to the write callback,
+    /// and won't buffer more data until no more than `low` bytes remain.
+    ///
+    /// Setting `high` to 0 disables buffering, which is the default. Returns
+    /// `FLUXIO_INVALID_ARG` if `low` is larger than `high`.
+    fn fluxio_io_set_write_watermarks(io: *mut fluxio_io, low: size_t, high: size_t) -> fluxio_code {
+        let io = non_null!(&mut *io ?= fluxio_code::FLUXIO_INVALID_ARG);
+        if low > high {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
+        io.low_watermark = low;
+        io.high_watermark = high;
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set the cork function for this IO transport.
+    ///
+    /// The function is called with `1` before fluxio starts writing a batch
+    /// of data, and with `0` once the batch is complete and the transport is
+    /// flushed. This lets the transport hold back small writes, such as with
+    /// `TCP_CORK` or by collecting them into a single TLS record, and send
+    /// them out together when uncorked.
+    fn fluxio_io_set_cork(io: *mut fluxio_io, func: fluxio_io_cork_callback) {
+        non_null!(&mut *io ?= ()).cork = Some(func);
+    }
+}
+
+ffi_fn! {
+    /// Set the vectored write function for this IO transport.
+    ///
+    /// The function is called with an array of buffers and their count,
+    /// which should be written to the transport in order, like with `writev`.
+    /// The number of bytes written, across all buffers, should be the
+    /// return value, or `FLUXIO_IO_PENDING` and `FLUXIO_IO_ERROR` as with
+    /// the write function.
+    ///
+    /// When set, HTTP/1 connections queue large body chunks and pass them
+    /// along with the message head, instead of first copying them into a
+    /// single buffer for the write function. It is not used while write
+    /// watermarks are set, and must be set before the connection is
+    /// handshaked.
+    fn fluxio_io_set_writev(io: *mut fluxio_io, func: fluxio_io_writev_callback) {
+        non_null!(&mut *io ?= ()).writev = Some(func);
+    }
+}
+
+ffi_fn! {
+    /// Set the flush function for this IO transport.
+    ///
+    /// The function is called once fluxio has written out a batch of data,
+    /// such as a message or a set of HTTP/2 frames, so the transport can
+    /// send along anything it has buffered. It should return `0` once the
+    /// transport is flushed.
+    ///
+    /// If the flush can't complete yet, a waker should be claimed from the
+    /// `ctx` and registered, and the return value should be
+    /// `FLUXIO_IO_PENDING`. The function is then called again once woken.
+    /// If there is an irrecoverable error, `FLUXIO_IO_ERROR` should be the
+    /// return value.
+    ///
+    /// Without a flush function, flushing succeeds once fluxio's own write
+    /// buffer is written out.
+    fn fluxio_io_set_flush(io: *mut fluxio_io, func: fluxio_io_flush_callback) {
+        non_null!(&mut *io ?= ()).flush = Some(func);
+    }
+}
+
+ffi_fn! {
+    /// Set the shutdown function for this IO transport.
+    ///
+    /// The function is called when fluxio is done writing to the transport,
+    /// after it has been flushed, so the transport can close its write
+    /// side, such as sending a TLS `close_notify` alert. It should return
+    /// `0` once the shutdown is complete, or `FLUXIO_IO_PENDING` and
+    /// `FLUXIO_IO_ERROR` as with the flush function.
+    ///
+    /// Without a shutdown function, shutting down only flushes the
+    /// transport.
+    fn fluxio_io_set_shutdown(io: *mut fluxio_io, func: fluxio_io_shutdown_callback) {
+        non_null!(&mut *io ?= ()).shutdown = Some(func);
+    }
+}
+
+ffi_fn! {
+    /// Set the addresses of the connection this IO transport represents.
+    ///
+    /// Each address is text in the form `ip:port`, such as `192.0.2.7:443`
+    /// or `[2001:db8::7]:443`, and is not NUL-terminated. A `NULL` address
+    /// is left unknown. Once handshaked, the addresses can be read back
+    /// with `fluxio_clientconn_peer_addr` and `fluxio_clientconn_local_addr`,
+    /// like the info a Rust connector reports when connecting.
+    ///
+    /// Returns `FLUXIO_INVALID_ARG` if an address can't be parsed, in which
+    /// case neither address is changed.
+    fn fluxio_io_set_connected_info(io: *mut fluxio_io, remote: *const u8, remote_len: size_t, local: *const u8, local_len: size_t) -> fluxio_code {
+        let io = non_null!(&mut *io ?= fluxio_code::FLUXIO_INVALID_ARG);
+        let (remote_addr, local_addr) = match (
+            unsafe { parse_addr(remote, remote_len) },
+            unsafe { parse_addr(local, local_len) },
+        ) {
+            (Ok(remote), Ok(local)) => (remote, local),
+            _ => return fluxio_code::FLUXIO_INVALID_ARG,
+        };
+        io.remote_addr = remote_addr;
+        io.local_addr = local_addr;
+        fluxio_code::FLUXIO_OK
+    }
+}
+
+ffi_fn! {
+    /// Set the protocol this IO transport negotiated with ALPN, such as
+    /// `h2`, without a length prefix.
+    ///
+    /// Pass `NULL` if no protocol was agreed on, which is the default. This
+    /// is used by handshakes with `fluxio_clientconn_options_alpn` set.
+    fn fluxio_io_set_alpn(io: *mut fluxio_io, proto: *const u8, len: size_t) {
+        let io = non_null!(&mut *io ?= ());
+        io.alpn = if proto.is_null() {
+            None
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(proto, len) }.to_vec())
+        };
+    }
+}
+
+/// Parses an `ip:port` address passed over the C API, `NULL` being unknown.
+unsafe fn parse_addr(addr: *const u8, len: size_t) -> Result<Option<SocketAddr>, ()> {
+    if addr.is_null() {
+        return Ok(None);
+    }
+    let addr = std::slice::from_raw_parts(addr, len);
+    std::str::from_utf8(addr)
+        .ok()
+        .and_then(|addr| addr.parse().ok())
+        .map(Some)
+        .ok_or(())
+}
+
+impl fluxio_io {
+    /// The remote and local addresses set with `fluxio_io_set_connected_info`.
+    pub(super) fn connected_addrs(&self) -> (Option<SocketAddr>, Option<SocketAddr>) {
+        (self.remote_addr, self.local_addr)
+    }
+
+    /// The protocol set with `fluxio_io_set_alpn`.
+    #[cfg(all(feature = "http1", feature = "http2"))]
+    pub(super) fn alpn(&self) -> Option<&[u8]> {
+        self.alpn.as_deref()
+    }
+
+    fn set_corked(&mut self, corked: bool) {
+        if self.corked == corked {
+            return;
+        }
+        if let Some(cork) = self.cork {
+            cork(self.userdata, corked as c_int);
+            self.corked = corked;
+        }
+    }
+
+    fn poll_write_callback(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match (self.write)(
+            self.userdata,
+            fluxio_context::wrap(cx),
+            buf.as_ptr(),
+            buf.len(),
+        ) {
+            FLUXIO_IO_PENDING => Poll::Pending,
+            FLUXIO_IO_ERROR => Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "io error",
+            ))),
+            ok => Poll::Ready(Ok(ok)),
+        }
+    }
+
+    fn poll_callback(
+        &mut self,
+        cx: &mut Context<'_>,
+        func: Option<extern "C" fn(*mut c_void, *mut fluxio_context<'_>) -> size_t>,
+    ) -> Poll<std::io::Result<()>> {
+        let func = match func {
+            Some(func) => func,
+            None => return Poll::Ready(Ok(())),
+        };
+        match func(self.userdata, fluxio_context::wrap(cx)) {
+            FLUXIO_IO_PENDING => Poll::Pending,
+            FLUXIO_IO_ERROR => Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "io error",
+            ))),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Writes out buffered data until no more than `target` bytes remain.
+    fn poll_drain(&mut self, cx: &mut Context<'_>, target: usize) -> Poll<std::io::Result<()>> {
+        while self.write_buf.len() > target {
+            let buf = std::mem::take(&mut self.write_buf);
+            let res = self.poll_write_callback(cx, &buf);
+            self.write_buf = buf;
+            match ready!(res)? {
+                0 => return Poll::Ready(Err(std::io::ErrorKind::WriteZero.into())),
+                n => self.write_buf.advance(n),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Wraps a Rust transport, polling it from the callbacks.
+fn rust_io<T>(transport: T) -> *mut fluxio_io
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let mut transport = Box::new(transport);
+    let io = fluxio_io_new();
+    let io_ref = unsafe { &mut *io };
+    io_ref.userdata = &mut *transport as *mut T as *mut c_void;
+    io_ref.read = rust_read::<T>;
+    io_ref.write = rust_write::<T>;
+    io_ref.flush = Some(rust_flush::<T>);
+    io_ref.shutdown = Some(rust_shutdown::<T>);
+    io_ref.transport = Some(transport);
+    io
+}
+
+/// Maps the result of polling a Rust transport to a callback return.
+fn rust_ret(res: Poll<std::io::Result<usize>>) -> size_t {
+    match res {
+        Poll::Ready(Ok(n)) => n,
+        Poll::Ready(Err(_)) => FLUXIO_IO_ERROR,
+        Poll::Pending => FLUXIO_IO_PENDING,
+    }
+}
+
+/// cbindgen:ignore
+extern "C" fn rust_read<T: AsyncRead + Unpin>(
+    userdata: *mut c_void,
+    ctx: *mut fluxio_context<'_>,
+    buf: *mut u8,
+    buf_len: size_t,
+) -> size_t {
+    let transport = unsafe { &mut *(userdata as *mut T) };
+    let cx = unsafe { &mut (*ctx).0 };
+    let buf = unsafe { std::slice::from_raw_parts_mut(buf as *mut MaybeUninit<u8>, buf_len) };
+    let mut buf = tokio::io::ReadBuf::uninit(buf);
+    rust_ret(
+        Pin::new(transport)
+            .poll_read(cx, &mut buf)
+            .map_ok(|()| buf.filled().len()),
+    )
+}
+
+/// cbindgen:ignore
+extern "C" fn rust_write<T: AsyncWrite + Unpin>(
+    userdata: *mut c_void,
+    ctx: *mut fluxio_context<'_>,
+    buf: *const u8,
+    buf_len: size_t,
+) -> size_t {
+    let transport = unsafe { &mut *(userdata as *mut T) };
+    let cx = unsafe { &mut (*ctx).0 };
+    let buf = unsafe { std::slice::from_raw_parts(buf, buf_len) };
+    rust_ret(Pin::new(transport).poll_write(cx, buf))
+}
+
+/// cbindgen:ignore
+extern "C" fn rust_flush<T: AsyncWrite + Unpin>(
+    userdata: *mut c_void,
+    ctx: *mut fluxio_context<'_>,
+) -> size_t {
+    let transport = unsafe { &mut *(userdata as *mut T) };
+    let cx = unsafe { &mut (*ctx).0 };
+    rust_ret(Pin::new(transport).poll_flush(cx).map_ok(|()| 0))
+}
+
+/// cbindgen:ignore
+extern "C" fn rust_shutdown<T: AsyncWrite + Unpin>(
+    userdata: *mut c_void,
+    ctx: *mut fluxio_context<'_>,
+) -> size_t {
+    let transport = unsafe { &mut *(userdata as *mut T) };
+    let cx = unsafe { &mut (*ctx).0 };
+    rust_ret(Pin::new(transport).poll_shutdown(cx).map_ok(|()| 0))
+}
+
 /// cbindgen:ignore
 extern "C" fn read_noop(
     _userdata: *mut c_void,
@@ -152,10 +579,58 @@ impl AsyncWrite for fluxio_io {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<std::io::Result<usize>> {
-        let buf_ptr = buf.as_ptr();
-        let buf_len = buf.len();
+        let io = self.get_mut();
+        io.set_corked(true);
+
+        let high = io.high_watermark;
+        if high == 0 {
+            return io.poll_write_callback(cx, buf);
+        }
+        if io.write_buf.len() >= high {
+            ready!(io.poll_drain(cx, io.low_watermark))?;
+        }
+        if io.write_buf.is_empty() && buf.len() >= high {
+            return io.poll_write_callback(cx, buf);
+        }
+
+        let n = buf.len().min(high - io.write_buf.len());
+        io.write_buf.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        let writev = match self.writev {
+            Some(writev) if self.high_watermark == 0 => writev,
+            _ => {
+                let buf = bufs.iter().find(|buf| !buf.is_empty());
+                return self.poll_write(cx, buf.map_or(&[][..], |buf| &**buf));
+            }
+        };
+
+        let io = self.get_mut();
+        io.set_corked(true);
+
+        let mut iovecs = [fluxio_iovec {
+            buf: std::ptr::null(),
+            len: 0,
+        }; MAX_IOVECS];
+        let mut iovcnt = 0;
+        for (iovec, buf) in iovecs.iter_mut().zip(bufs) {
+            iovec.buf = buf.as_ptr();
+            iovec.len = buf.len();
+            iovcnt += 1;
+        }
 
-        match (self.write)(self.userdata, fluxio_context::wrap(cx), buf_ptr, buf_len) {
+        match writev(
+            io.userdata,
+            fluxio_context::wrap(cx),
+            iovecs.as_ptr(),
+            iovcnt,
+        ) {
             FLUXIO_IO_PENDING => Poll::Pending,
             FLUXIO_IO_ERROR => Poll::Ready(Err(std::io::Error::new(
                 std::io::ErrorKind::Other,
@@ -165,14 +640,301 @@ impl AsyncWrite for fluxio_io {
         }
     }
 
-    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
-        Poll::Ready(Ok(()))
+    fn is_write_vectored(&self) -> bool {
+        self.writev.is_some() && self.high_watermark == 0
     }
 
-    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<std::io::Result<()>> {
-        Poll::Ready(Ok(()))
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let io = self.get_mut();
+        ready!(io.poll_drain(cx, 0))?;
+        io.set_corked(false);
+        let flush = io.flush;
+        io.poll_callback(cx, flush)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        let io = self.get_mut();
+        let shutdown = io.shutdown;
+        io.poll_callback(cx, shutdown)
     }
 }
 
 unsafe impl Send for fluxio_io {}
 unsafe impl Sync for fluxio_io {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::task::noop_waker_ref;
+
+    extern "C" fn write_vec(
+        userdata: *mut c_void,
+        _: *mut fluxio_context<'_>,
+        buf: *const u8,
+        buf_len: size_t,
+    ) -> size_t {
+        let writes = unsafe { &mut *(userdata as *mut Vec<Vec<u8>>) };
+        let buf = unsafe { std::slice::from_raw_parts(buf, buf_len) };
+        writes.push(buf.to_vec());
+        buf_len
+    }
+
+    #[test]
+    fn test_write_watermarks_coalesce() {
+        let mut writes = Vec::<Vec<u8>>::new();
+        let io = fluxio_io_new();
+        fluxio_io_set_userdata(io, &mut writes as *mut _ as *mut c_void);
+        fluxio_io_set_write(io, write_vec);
+        assert!(matches!(
+            fluxio_io_set_write_watermarks(io, 4, 2),
+            fluxio_code::FLUXIO_INVALID_ARG
+        ));
+        assert!(matches!(
+            fluxio_io_set_write_watermarks(io, 0, 8),
+            fluxio_code::FLUXIO_OK
+        ));
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut io = unsafe { Box::from_raw(io) };
+        let mut write = |io: &mut fluxio_io, buf: &[u8]| match Pin::new(io).poll_write(&mut cx, buf)
+        {
+            Poll::Ready(Ok(n)) => n,
+            other => panic!("poll_write: {:?}", other),
+        };
+
+        assert_eq!(write(&mut io, b"ab"), 2);
+        assert_eq!(write(&mut io, b"cd"), 2);
+        assert!(writes.is_empty());
+
+        // filling up to the high watermark, and then draining it
+        assert_eq!(write(&mut io, b"efghij"), 4);
+        assert_eq!(write(&mut io, b"ij"), 2);
+        assert_eq!(writes, vec![b"abcdefgh".to_vec()]);
+
+        // large writes skip an empty buffer
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(Pin::new(&mut *io).poll_flush(&mut cx).is_ready());
+        assert_eq!(writes.last().unwrap(), b"ij");
+        let big = [b'x'; 10];
+        assert!(matches!(
+            Pin::new(&mut *io).poll_write(&mut cx, &big),
+            Poll::Ready(Ok(10))
+        ));
+        assert_eq!(writes.len(), 3);
+    }
+
+    extern "C" fn writev_vec(
+        userdata: *mut c_void,
+        _: *mut fluxio_context<'_>,
+        iovecs: *const fluxio_iovec,
+        iovcnt: size_t,
+    ) -> size_t {
+        let writes = unsafe { &mut *(userdata as *mut Vec<Vec<u8>>) };
+        let iovecs = unsafe { std::slice::from_raw_parts(iovecs, iovcnt) };
+        let mut write = Vec::new();
+        for iovec in iovecs {
+            write.extend_from_slice(unsafe { std::slice::from_raw_parts(iovec.buf, iovec.len) });
+        }
+        writes.push(write);
+        writes.last().unwrap().len()
+    }
+
+    #[test]
+    fn test_writev() {
+        let mut writes = Vec::<Vec<u8>>::new();
+        let io = fluxio_io_new();
+        fluxio_io_set_userdata(io, &mut writes as *mut _ as *mut c_void);
+        fluxio_io_set_write(io, write_vec);
+        let mut io = unsafe { Box::from_raw(io) };
+        assert!(!io.is_write_vectored());
+        fluxio_io_set_writev(&mut *io, writev_vec);
+        assert!(io.is_write_vectored());
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let bufs = [
+            IoSlice::new(b"hello "),
+            IoSlice::new(b""),
+            IoSlice::new(b"world"),
+        ];
+        assert!(matches!(
+            Pin::new(&mut *io).poll_write_vectored(&mut cx, &bufs),
+            Poll::Ready(Ok(11))
+        ));
+        assert_eq!(writes, vec![b"hello world".to_vec()]);
+
+        // watermarks fall back to buffering the first buffer
+        fluxio_io_set_write_watermarks(&mut *io, 0, 64);
+        assert!(!io.is_write_vectored());
+        assert!(matches!(
+            Pin::new(&mut *io).poll_write_vectored(&mut cx, &bufs),
+            Poll::Ready(Ok(6))
+        ));
+        assert_eq!(writes.len(), 1);
+    }
+
+    #[test]
+    fn test_set_connected_info() {
+        let io = fluxio_io_new();
+        let remote = b"[2001:db8::7]:443";
+        let local = b"192.0.2.1:50123";
+        assert!(matches!(
+            fluxio_io_set_connected_info(
+                io,
+                remote.as_ptr(),
+                remote.len(),
+                local.as_ptr(),
+                local.len()
+            ),
+            fluxio_code::FLUXIO_OK
+        ));
+        let io = unsafe { Box::from_raw(io) };
+        assert_eq!(
+            io.connected_addrs(),
+            (
+                Some("[2001:db8::7]:443".parse().unwrap()),
+                Some("192.0.2.1:50123".parse().unwrap())
+            )
+        );
+
+        // an invalid address changes neither
+        let io = Box::into_raw(io);
+        let bad = b"192.0.2.1";
+        assert!(matches!(
+            fluxio_io_set_connected_info(io, std::ptr::null(), 0, bad.as_ptr(), bad.len()),
+            fluxio_code::FLUXIO_INVALID_ARG
+        ));
+        let io = unsafe { Box::from_raw(io) };
+        assert!(io.connected_addrs().0.is_some());
+    }
+
+    struct Calls {
+        pending: bool,
+        calls: Vec<&'static str>,
+    }
+
+    extern "C" fn flush_calls(userdata: *mut c_void, _: *mut fluxio_context<'_>) -> size_t {
+        let calls = unsafe { &mut *(userdata as *mut Calls) };
+        calls.calls.push("flush");
+        if std::mem::take(&mut calls.pending) {
+            return FLUXIO_IO_PENDING;
+        }
+        0
+    }
+
+    extern "C" fn shutdown_calls(userdata: *mut c_void, _: *mut fluxio_context<'_>) -> size_t {
+        let calls = unsafe { &mut *(userdata as *mut Calls) };
+        calls.calls.push("shutdown");
+        FLUXIO_IO_ERROR
+    }
+
+    #[test]
+    fn test_flush_and_shutdown() {
+        let mut calls = Calls {
+            pending: true,
+            calls: Vec::new(),
+        };
+        let io = fluxio_io_new();
+        fluxio_io_set_userdata(io, &mut calls as *mut _ as *mut c_void);
+        let mut io = unsafe { Box::from_raw(io) };
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(matches!(
+            Pin::new(&mut *io).poll_shutdown(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert!(calls.calls.is_empty());
+
+        fluxio_io_set_flush(&mut *io, flush_calls);
+        fluxio_io_set_shutdown(&mut *io, shutdown_calls);
+        assert!(Pin::new(&mut *io).poll_flush(&mut cx).is_pending());
+        assert!(matches!(
+            Pin::new(&mut *io).poll_flush(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+        assert!(matches!(
+            Pin::new(&mut *io).poll_shutdown(&mut cx),
+            Poll::Ready(Err(_))
+        ));
+        assert_eq!(calls.calls, ["flush", "flush", "flush", "shutdown"]);
+    }
+
+    #[test]
+    fn test_duplex_pair() {
+        let mut client = std::ptr::null_mut();
+        let mut server = std::ptr::null_mut();
+        assert!(matches!(
+            fluxio_io_duplex_pair(0, &mut client, &mut server),
+            fluxio_code::FLUXIO_INVALID_ARG
+        ));
+        assert!(matches!(
+            fluxio_io_duplex_pair(4, &mut client, &mut server),
+            fluxio_code::FLUXIO_OK
+        ));
+        let mut client = unsafe { Box::from_raw(client) };
+        let mut server = unsafe { Box::from_raw(server) };
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(matches!(
+            Pin::new(&mut *client).poll_write(&mut cx, b"hello"),
+            Poll::Ready(Ok(4))
+        ));
+        assert!(Pin::new(&mut *client)
+            .poll_write(&mut cx, b"o")
+            .is_pending());
+
+        let mut buf = [0; 8];
+        let mut read_buf = tokio::io::ReadBuf::new(&mut buf);
+        assert!(matches!(
+            Pin::new(&mut *server).poll_read(&mut cx, &mut read_buf),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(read_buf.filled(), b"hell");
+        assert!(Pin::new(&mut *server)
+            .poll_read(&mut cx, &mut read_buf)
+            .is_pending());
+
+        // freeing an end ends the stream of the other
+        fluxio_io_free(Box::into_raw(client));
+        assert!(matches!(
+            Pin::new(&mut *server).poll_read(&mut cx, &mut read_buf),
+            Poll::Ready(Ok(()))
+        ));
+        assert_eq!(read_buf.filled().len(), 4);
+        assert!(matches!(
+            Pin::new(&mut *server).poll_write(&mut cx, b"x"),
+            Poll::Ready(Err(_))
+        ));
+    }
+
+    #[test]
+    fn test_chaos_wrap() {
+        let mut client = std::ptr::null_mut();
+        let mut server = std::ptr::null_mut();
+        fluxio_io_duplex_pair(64, &mut client, &mut server);
+        let addr = b"192.0.2.1:443";
+        fluxio_io_set_connected_info(client, addr.as_ptr(), addr.len(), std::ptr::null(), 0);
+
+        assert!(fluxio_io_chaos_wrap(client, 1, 1.5, 0.0, 0.0, 0).is_null());
+        let client = fluxio_io_chaos_wrap(client, 1, 0.0, 0.0, 0.0, 3);
+        assert!(!client.is_null());
+        let mut client = unsafe { Box::from_raw(client) };
+        let mut server = unsafe { Box::from_raw(server) };
+        assert!(client.connected_addrs().0.is_some());
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(Pin::new(&mut *server)
+            .poll_write(&mut cx, b"hello")
+            .is_ready());
+        let mut buf = [0; 8];
+        let mut read_buf = tokio::io::ReadBuf::new(&mut buf);
+        for _ in 0..2 {
+            assert!(matches!(
+                Pin::new(&mut *client).poll_read(&mut cx, &mut read_buf),
+                Poll::Ready(Ok(()))
+            ));
+        }
+        // the stream ends after 3 bytes
+        assert_eq!(read_buf.filled(), b"hel");
+    }
+}
@@ -53,6 +53,7 @@ mod error;
 mod http_types;
 mod io;
 mod task;
+mod upgrade;
 
 pub use self::body::*;
 pub use self::client::*;
@@ -60,6 +61,7 @@ pub use self::error::*;
 pub use self::http_types::*;
 pub use self::io::*;
 pub use self::task::*;
+pub use self::upgrade::*;
 
 /// Return in iter functions to continue iterating.
 pub const FLUXIO_ITER_CONTINUE: libc::c_int = 0;
@@ -76,6 +78,33 @@ pub const FLUXIO_HTTP_VERSION_1_1: libc::c_int = 11;
 /// The HTTP/2 version.
 pub const FLUXIO_HTTP_VERSION_2: libc::c_int = 20;
 
+/// Decode `gzip` response bodies.
+pub const FLUXIO_DECOMPRESS_GZIP: libc::c_int = 1;
+/// Decode `deflate` response bodies.
+pub const FLUXIO_DECOMPRESS_DEFLATE: libc::c_int = 1 << 1;
+/// Decode `br` response bodies.
+pub const FLUXIO_DECOMPRESS_BR: libc::c_int = 1 << 2;
+/// Decode `zstd` response bodies.
+pub const FLUXIO_DECOMPRESS_ZSTD: libc::c_int = 1 << 3;
+
+/// Don't compress the request body.
+pub const FLUXIO_COMPRESS_NONE: libc::c_int = 0;
+/// Compress the request body with `gzip`.
+pub const FLUXIO_COMPRESS_GZIP: libc::c_int = 1;
+/// Compress the request body with `deflate`.
+pub const FLUXIO_COMPRESS_DEFLATE: libc::c_int = 2;
+/// Compress the request body with `br`.
+pub const FLUXIO_COMPRESS_BR: libc::c_int = 3;
+/// Compress the request body with `zstd`.
+pub const FLUXIO_COMPRESS_ZSTD: libc::c_int = 4;
+
+/// Never add a `Host` header to requests.
+pub const FLUXIO_AUTO_HOST_NEVER: libc::c_int = 0;
+/// Add a `Host` header to requests that don't have one.
+pub const FLUXIO_AUTO_HOST_IF_MISSING: libc::c_int = 1;
+/// Always set the `Host` header of requests, replacing any they have.
+pub const FLUXIO_AUTO_HOST_OVERRIDE: libc::c_int = 2;
+
 struct UserDataPointer(*mut std::ffi::c_void);
 
 // We don't actually know anything about this pointer, it's up to the user
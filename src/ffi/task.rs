@@ -1,21 +1,42 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
 use std::ffi::c_void;
+use std::fmt::Write as _;
 use std::future::Future;
 use std::pin::Pin;
 use std::ptr;
+#[cfg(unix)]
+use std::sync::OnceLock;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     Arc, Mutex, Weak,
 };
-use std::task::{Context, Poll};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle, Thread};
+use std::time::{Duration, Instant};
 
 use futures_util::stream::{FuturesUnordered, Stream};
-use libc::c_int;
+use libc::{c_int, size_t};
 
 use super::error::fluxio_code;
-use super::UserDataPointer;
+use super::{UserDataPointer, FLUXIO_ITER_CONTINUE};
 
 type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
 type BoxAny = Box<dyn AsTaskType + Send + Sync>;
+/// The id, label and age of a task listed by `fluxio_executor_dump_tasks`.
+type PendingTask = (u64, Option<Cow<'static, str>>, Duration);
+
+type fluxio_executor_task_callback = extern "C" fn(*mut c_void, *mut fluxio_task);
+
+type fluxio_task_on_complete_callback = extern "C" fn(*mut c_void, *mut fluxio_task);
+
+type fluxio_executor_dump_callback =
+    extern "C" fn(*mut c_void, u64, *const u8, size_t, u64) -> c_int;
+
+/// The id of the next `fluxio_task` created.
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
 
 /// Return in a poll function to indicate it was ready.
 pub const FLUXIO_POLL_READY: c_int = 0;
@@ -44,31 +65,110 @@ pub struct fluxio_executor {
     /// a future, which would mean the driver's mutex is already locked.
     spawn_queue: Mutex<Vec<TaskFuture>>,
 
+    /// Tasks that completed while `fluxio_executor_block_on` waited for
+    /// another task, returned by the next polls.
+    completed: Mutex<VecDeque<Box<fluxio_task>>>,
+
+    /// The number of tasks pushed that haven't completed yet.
+    pending: AtomicUsize,
+
+    /// Set by `fluxio_executor_shutdown`, after which new tasks are dropped.
+    shutting_down: AtomicBool,
+
     /// This is used to track when a future calls `wake` while we are within
     /// `fluxio_executor::poll_next`.
     is_woken: Arc<ExecWaker>,
+
+    /// The timers of `fluxio_timer_new` tasks polled by this executor.
+    timers: Arc<Timers>,
 }
 
 #[derive(Clone)]
 pub(crate) struct WeakExec(Weak<fluxio_executor>);
 
-struct ExecWaker(AtomicBool);
+struct ExecWaker {
+    woken: AtomicBool,
+    /// The thread driving the executor with `fluxio_executor_run_in_thread`,
+    /// unparked when a task is woken or pushed.
+    runner: Mutex<Option<Thread>>,
+    /// The pipe of `fluxio_executor_fd`, signaled when a task is woken or
+    /// pushed. Created on first use, `None` if that failed.
+    #[cfg(unix)]
+    fd: OnceLock<Option<WakeFd>>,
+}
+
+/// A non-blocking pipe, readable once signaled until it is cleared.
+#[cfg(unix)]
+struct WakeFd {
+    read: c_int,
+    write: c_int,
+}
+
+/// Pending timers, ordered by deadline.
+///
+/// Timers register with the executor polling them, found in
+/// `CURRENT_TIMERS`, and the executor wakes them once their deadline passed.
+struct Timers {
+    heap: Mutex<BinaryHeap<Reverse<TimerEntry>>>,
+}
+
+struct TimerEntry {
+    deadline: Instant,
+    waker: Waker,
+}
+
+/// The future of a `fluxio_timer_new` task.
+struct Timer {
+    deadline: Instant,
+    registered: Option<Waker>,
+}
+
+thread_local! {
+    /// The timers of the executor polling tasks on this thread.
+    static CURRENT_TIMERS: RefCell<Option<Arc<Timers>>> = const { RefCell::new(None) };
+}
+
+/// The tasks handled by `fluxio_executor_shutdown`.
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct fluxio_executor_shutdown_stats {
+    /// The number of tasks that completed while shutting down.
+    pub completed: size_t,
+    /// The number of tasks that hadn't completed by the deadline, and were
+    /// canceled.
+    pub canceled: size_t,
+}
+
+/// A thread driving a `fluxio_executor`.
+pub struct fluxio_executor_thread {
+    stop: Arc<AtomicBool>,
+    exec: Arc<fluxio_executor>,
+    handle: JoinHandle<()>,
+}
 
 /// An async task.
 pub struct fluxio_task {
+    id: u64,
     future: BoxFuture<BoxAny>,
     output: Option<BoxAny>,
     userdata: UserDataPointer,
+    label: Option<Cow<'static, str>>,
+    on_complete: Option<(fluxio_task_on_complete_callback, UserDataPointer)>,
 }
 
 struct TaskFuture {
     task: Option<Box<fluxio_task>>,
+    /// When the task was pushed onto the executor.
+    spawned: Instant,
 }
 
 /// An async context for a task that contains the related waker.
-pub struct fluxio_context<'a>(Context<'a>);
+pub struct fluxio_context<'a>(pub(super) Context<'a>);
 
 /// A waker that is saved and used to waken a pending task.
+///
+/// Wakers can be sent to, woken and freed on any thread, not only the one
+/// polling the executor.
 pub struct fluxio_waker {
     waker: std::task::Waker,
 }
@@ -86,6 +186,8 @@ pub enum fluxio_task_return_type {
     FLUXIO_TASK_RESPONSE,
     /// The value of this task is `fluxio_buf *`.
     FLUXIO_TASK_BUF,
+    /// The value of this task is `fluxio_upgraded *`.
+    FLUXIO_TASK_UPGRADED,
 }
 
 pub(crate) unsafe trait AsTaskType {
@@ -103,7 +205,18 @@ impl fluxio_executor {
         Arc::new(fluxio_executor {
             driver: Mutex::new(FuturesUnordered::new()),
             spawn_queue: Mutex::new(Vec::new()),
-            is_woken: Arc::new(ExecWaker(AtomicBool::new(false))),
+            completed: Mutex::new(VecDeque::new()),
+            pending: AtomicUsize::new(0),
+            shutting_down: AtomicBool::new(false),
+            timers: Arc::new(Timers {
+                heap: Mutex::new(BinaryHeap::new()),
+            }),
+            is_woken: Arc::new(ExecWaker {
+                woken: AtomicBool::new(false),
+                runner: Mutex::new(None),
+                #[cfg(unix)]
+                fd: OnceLock::new(),
+            }),
         })
     }
 
@@ -111,14 +224,123 @@ impl fluxio_executor {
         WeakExec(Arc::downgrade(exec))
     }
 
-    fn spawn(&self, task: Box<fluxio_task>) {
-        self.spawn_queue
-            .lock()
-            .unwrap()
-            .push(TaskFuture { task: Some(task) });
+    /// Pushes a task, unless the executor is shutting down, in which case
+    /// it is dropped and this returns `false`.
+    fn spawn(&self, task: Box<fluxio_task>) -> bool {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            drop(task);
+            return false;
+        }
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.spawn_queue.lock().unwrap().push(TaskFuture {
+            task: Some(task),
+            spawned: Instant::now(),
+        });
+        self.is_woken.wake_driver();
+        true
+    }
+
+    /// Lists the id, label and age of each pending task.
+    ///
+    /// Returns `false` if the tasks in the driver couldn't be listed,
+    /// because it is being polled.
+    fn pending_tasks(&self) -> (Vec<PendingTask>, bool) {
+        let now = Instant::now();
+        let mut tasks = Vec::new();
+        let mut push = |task: &TaskFuture| {
+            if let Some(ref inner) = task.task {
+                let age = now.saturating_duration_since(task.spawned);
+                tasks.push((inner.id, inner.label.clone(), age));
+            }
+        };
+
+        self.spawn_queue.lock().unwrap().iter().for_each(&mut push);
+        // The driver is locked while polling, if this is called by a task.
+        let complete = match self.driver.try_lock() {
+            Ok(driver) => {
+                driver.iter().for_each(&mut push);
+                true
+            }
+            Err(_) => false,
+        };
+        (tasks, complete)
+    }
+
+    /// Writes a line for each pending task, with its id, label and age.
+    fn dump(&self) -> String {
+        let (tasks, complete) = self.pending_tasks();
+        let mut out = String::new();
+        for (id, label, age) in tasks {
+            let label = label.as_deref().unwrap_or("(unlabeled)");
+            let _ = writeln!(out, "#{} {}: pending for {:.3?}", id, label, age);
+        }
+        if !complete {
+            out.push_str("(tasks being polled are not listed)\n");
+        }
+        out
     }
 
     fn poll_next(&self) -> Option<Box<fluxio_task>> {
+        if let Some(task) = self.completed.lock().unwrap().pop_front() {
+            return Some(task);
+        }
+        self.poll_driver()
+    }
+
+    /// Polls until `task` completes, or the `timeout` elapses.
+    ///
+    /// The calling thread must be registered as the runner, to be unparked
+    /// when a task is woken.
+    fn block_on(
+        &self,
+        mut task: Box<fluxio_task>,
+        timeout: Option<Duration>,
+    ) -> Option<Box<fluxio_task>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        // The task is returned to the caller instead.
+        task.on_complete = None;
+        let id: *const fluxio_task = &*task;
+        if !self.spawn(task) {
+            return None;
+        }
+
+        loop {
+            while let Some(done) = self.poll_driver() {
+                if ptr::eq(&*done, id) {
+                    return Some(done);
+                }
+                self.completed.lock().unwrap().push_back(done);
+                // Polling returns it, so let a loop waiting on the fd know.
+                self.is_woken.wake_driver();
+            }
+
+            if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                return None;
+            }
+            self.park(deadline);
+        }
+    }
+
+    /// Parks the runner thread until a task is woken or pushed, the next
+    /// timer is due, or the `deadline` passed.
+    fn park(&self, deadline: Option<Instant>) {
+        let deadline = match (deadline, self.timers.next_deadline()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        // A wake or push since the executor was polled leaves the park token
+        // set, so this returns right away.
+        match deadline {
+            Some(deadline) => {
+                thread::park_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => thread::park(),
+        }
+    }
+
+    fn poll_driver(&self) -> Option<Box<fluxio_task>> {
+        let _timers = self.timers.enter();
+
         // Drain the queue first.
         self.drain_queue();
 
@@ -126,8 +348,18 @@ impl fluxio_executor {
         let mut cx = Context::from_waker(&waker);
 
         loop {
-            match Pin::new(&mut *self.driver.lock().unwrap()).poll_next(&mut cx) {
-                Poll::Ready(val) => return val,
+            let polled = Pin::new(&mut *self.driver.lock().unwrap()).poll_next(&mut cx);
+            if let Poll::Ready(Some(_)) = polled {
+                self.pending.fetch_sub(1, Ordering::SeqCst);
+            }
+            match polled {
+                Poll::Ready(Some(mut task)) => match task.on_complete.take() {
+                    // The driver isn't locked anymore, so the callback can
+                    // push new tasks, or free this one.
+                    Some((func, userdata)) => func(userdata.0, Box::into_raw(task)),
+                    None => return Some(task),
+                },
+                Poll::Ready(None) => return None,
                 Poll::Pending => {
                     // Check if any of the pending tasks tried to spawn
                     // some new tasks. If so, drain into the driver and loop.
@@ -135,9 +367,13 @@ impl fluxio_executor {
                         continue;
                     }
 
+                    // Wake the tasks of timers that are due, so they're
+                    // polled again below.
+                    self.timers.fire();
+
                     // If the driver called `wake` while we were polling,
                     // we should poll again immediately!
-                    if self.is_woken.0.swap(false, Ordering::SeqCst) {
+                    if self.is_woken.woken.swap(false, Ordering::SeqCst) {
                         continue;
                     }
 
@@ -161,11 +397,251 @@ impl fluxio_executor {
 
         true
     }
+
+    /// Stops taking new tasks, and polls the pending ones until they all
+    /// completed or the `deadline` passed, then drops those left.
+    ///
+    /// The tasks are only polled if the executor isn't driven by another
+    /// thread.
+    fn shutdown(&self, deadline: Option<Instant>) -> fluxio_executor_shutdown_stats {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        // Tasks that completed while blocking on another were never returned.
+        let returned = std::mem::take(&mut *self.completed.lock().unwrap());
+        let before = self.pending.load(Ordering::SeqCst);
+
+        let drive = {
+            let mut runner = self.is_woken.runner.lock().unwrap();
+            if runner.is_none() {
+                *runner = Some(thread::current());
+                true
+            } else {
+                false
+            }
+        };
+        if drive {
+            loop {
+                while let Some(task) = self.poll_driver() {
+                    drop(task);
+                }
+                if self.pending.load(Ordering::SeqCst) == 0
+                    || matches!(deadline, Some(deadline) if Instant::now() >= deadline)
+                {
+                    break;
+                }
+                self.park(deadline);
+            }
+            *self.is_woken.runner.lock().unwrap() = None;
+        }
+
+        // Dropping tasks may drop other tasks' wakers or spawn new tasks,
+        // so the locks are released first.
+        let queued = std::mem::take(&mut *self.spawn_queue.lock().unwrap());
+        let driven = std::mem::take(&mut *self.driver.lock().unwrap());
+        let canceled = queued.len() + driven.len();
+        let left = self.pending.fetch_sub(canceled, Ordering::SeqCst);
+        // Tasks pushed while draining can leave more pending than before.
+        let completed = returned.len() + before.saturating_sub(left);
+        drop((returned, queued, driven));
+
+        fluxio_executor_shutdown_stats {
+            completed,
+            canceled,
+        }
+    }
+
+    /// Drives the executor until `stop` is set, handing every completed task
+    /// to `func`.
+    fn run(
+        &self,
+        stop: &AtomicBool,
+        func: fluxio_executor_task_callback,
+        userdata: UserDataPointer,
+    ) {
+        while !stop.load(Ordering::SeqCst) {
+            match self.poll_next() {
+                Some(task) => func(userdata.0, Box::into_raw(task)),
+                None => self.park(None),
+            }
+        }
+    }
+}
+
+impl ExecWaker {
+    fn notify(&self) {
+        self.woken.store(true, Ordering::SeqCst);
+        self.wake_driver();
+    }
+
+    /// Lets whatever drives the executor know it should poll it again.
+    fn wake_driver(&self) {
+        if let Some(ref runner) = *self.runner.lock().unwrap() {
+            runner.unpark();
+        }
+        #[cfg(unix)]
+        if let Some(Some(fd)) = self.fd.get() {
+            fd.signal();
+        }
+    }
+}
+
+// ===== impl WakeFd =====
+
+#[cfg(unix)]
+impl WakeFd {
+    fn new() -> Option<WakeFd> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let fd = WakeFd {
+            read: fds[0],
+            write: fds[1],
+        };
+        for fd in fds {
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                if flags < 0
+                    || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0
+                    || libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) < 0
+                {
+                    return None;
+                }
+            }
+        }
+        Some(fd)
+    }
+
+    fn signal(&self) {
+        // A full pipe is readable already, so a failed write is fine.
+        let _ = unsafe { libc::write(self.write, [1u8].as_ptr() as *const c_void, 1) };
+    }
+
+    /// Empties the pipe, returning whether it was signaled.
+    fn clear(&self) -> bool {
+        let mut buf = [0u8; 64];
+        let mut signaled = false;
+        while unsafe { libc::read(self.read, buf.as_mut_ptr() as *mut c_void, buf.len()) } > 0 {
+            signaled = true;
+        }
+        signaled
+    }
+}
+
+#[cfg(unix)]
+impl Drop for WakeFd {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.read);
+            libc::close(self.write);
+        }
+    }
 }
 
 impl futures_util::task::ArcWake for ExecWaker {
     fn wake_by_ref(me: &Arc<ExecWaker>) {
-        me.0.store(true, Ordering::SeqCst);
+        me.notify();
+    }
+}
+
+// ===== impl Timers =====
+
+impl Timers {
+    /// Makes these the timers of `fluxio_timer_new` tasks polled on this
+    /// thread, until the guard is dropped.
+    fn enter(self: &Arc<Self>) -> impl Drop {
+        struct Exit(Option<Arc<Timers>>);
+
+        impl Drop for Exit {
+            fn drop(&mut self) {
+                CURRENT_TIMERS.with(|current| *current.borrow_mut() = self.0.take());
+            }
+        }
+
+        Exit(CURRENT_TIMERS.with(|current| current.borrow_mut().replace(self.clone())))
+    }
+
+    fn register(&self, deadline: Instant, waker: Waker) {
+        self.heap
+            .lock()
+            .unwrap()
+            .push(Reverse(TimerEntry { deadline, waker }));
+    }
+
+    /// Wakes the timers whose deadline passed.
+    fn fire(&self) {
+        let now = Instant::now();
+        let mut heap = self.heap.lock().unwrap();
+        while heap.peek().map_or(false, |next| next.0.deadline <= now) {
+            heap.pop().unwrap().0.waker.wake();
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.heap.lock().unwrap().peek().map(|next| next.0.deadline)
+    }
+}
+
+impl PartialEq for TimerEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for TimerEntry {}
+
+impl PartialOrd for TimerEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TimerEntry {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        if !matches!(self.registered, Some(ref waker) if waker.will_wake(cx.waker())) {
+            let registered = CURRENT_TIMERS.with(|current| match *current.borrow() {
+                Some(ref timers) => {
+                    timers.register(self.deadline, cx.waker().clone());
+                    true
+                }
+                None => false,
+            });
+            if !registered {
+                // Not polled by a `fluxio_executor`, nothing will wake the
+                // task when the deadline passes, so poll again.
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.registered = Some(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+// ===== impl fluxio_executor_thread =====
+
+impl fluxio_executor_thread {
+    fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        *self.exec.is_woken.runner.lock().unwrap() = None;
+        let handle = self.handle;
+        handle.thread().unpark();
+        // Joining from a completion callback would wait on ourselves; the
+        // thread exits once the callback returns instead.
+        if handle.thread().id() != thread::current().id() {
+            let _ = handle.join();
+        }
     }
 }
 
@@ -180,7 +656,9 @@ impl WeakExec {
 impl crate::rt::Executor<BoxFuture<()>> for WeakExec {
     fn execute(&self, fut: BoxFuture<()>) {
         if let Some(exec) = self.0.upgrade() {
-            exec.spawn(fluxio_task::boxed(fut));
+            let mut task = fluxio_task::boxed(fut);
+            task.label = Some(Cow::Borrowed("fluxio background task"));
+            exec.spawn(task);
         }
     }
 }
@@ -199,15 +677,50 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Shuts an executor down, giving its tasks until a deadline to complete,
+    /// and frees it.
+    ///
+    /// New tasks, including those pushed by the executor's tasks, are freed
+    /// instead of being pushed. The pending tasks are polled until all of
+    /// them completed, or `deadline_ms` milliseconds passed, after which the
+    /// rest are canceled and freed. A `deadline_ms` of `0` waits for all of
+    /// them. Completed tasks are freed, or passed to the callback set with
+    /// `fluxio_task_on_complete`, while canceled tasks never are.
+    ///
+    /// The calling thread sleeps while no task can make progress, so tasks
+    /// waiting on IO only complete if their wakers are called from other
+    /// threads meanwhile. If the executor is driven by
+    /// `fluxio_executor_run_in_thread`, the tasks aren't polled and are
+    /// canceled right away; stop the thread first.
+    ///
+    /// Returns how many tasks completed and were canceled. This is the
+    /// recommended way to free an executor, over `fluxio_executor_free`.
+    fn fluxio_executor_shutdown(exec: *const fluxio_executor, deadline_ms: u64) -> fluxio_executor_shutdown_stats {
+        let exec = non_null!(Arc::from_raw(exec) ?= fluxio_executor_shutdown_stats::default());
+        let deadline = if deadline_ms == 0 {
+            None
+        } else {
+            Some(Instant::now() + Duration::from_millis(deadline_ms))
+        };
+        exec.shutdown(deadline)
+    } ?= fluxio_executor_shutdown_stats::default()
+}
+
 ffi_fn! {
     /// Push a task onto the executor.
     ///
     /// The executor takes ownership of the task, it should not be accessed
     /// again unless returned back to the user with `fluxio_executor_poll`.
+    ///
+    /// Returns `FLUXIO_INVALID_ARG` if the executor is being shut down with
+    /// `fluxio_executor_shutdown`, in which case the task is freed.
     fn fluxio_executor_push(exec: *const fluxio_executor, task: *mut fluxio_task) -> fluxio_code {
         let exec = non_null!(&*exec ?= fluxio_code::FLUXIO_INVALID_ARG);
         let task = non_null!(Box::from_raw(task) ?= fluxio_code::FLUXIO_INVALID_ARG);
-        exec.spawn(task);
+        if !exec.spawn(task) {
+            return fluxio_code::FLUXIO_INVALID_ARG;
+        }
         fluxio_code::FLUXIO_OK
     }
 }
@@ -228,6 +741,296 @@ ffi_fn! {
     } ?= ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Print the tasks of the executor that haven't completed yet to a buffer.
+    ///
+    /// Each task gets a line with its id, from `fluxio_task_id`, its label,
+    /// set with `fluxio_task_set_label`, and how long ago it was pushed onto
+    /// the executor. Connection tasks
+    /// spawned by fluxio itself are labeled `fluxio background task`. This is
+    /// meant for diagnosing tasks that never complete, the format may change.
+    ///
+    /// If called from a callback of a task the executor is polling, only the
+    /// tasks pushed since the executor was last polled are listed.
+    ///
+    /// The `dst_len` value must be the maximum length that the buffer can
+    /// store.
+    ///
+    /// The return value is number of bytes that were written to `dst`, which
+    /// is `0` if `dst` is `NULL`.
+    fn fluxio_executor_dump(exec: *const fluxio_executor, dst: *mut u8, dst_len: size_t) -> size_t {
+        let exec = non_null!(&*exec ?= 0);
+        if dst.is_null() {
+            return 0;
+        }
+        let dst = unsafe {
+            std::slice::from_raw_parts_mut(dst, dst_len)
+        };
+        let dump = exec.dump();
+        let len = dump.len().min(dst.len());
+        dst[..len].copy_from_slice(&dump.as_bytes()[..len]);
+        len
+    }
+}
+
+ffi_fn! {
+    /// Calls `func` for each task of the executor that hasn't completed yet.
+    ///
+    /// The callback is passed `userdata`, the id of the task, its label and
+    /// the length of the label, and how many milliseconds ago the task was
+    /// pushed onto the executor. A task without a label is passed a `NULL`
+    /// label of length `0`. The label is only valid during the call.
+    ///
+    /// The callback should return `FLUXIO_ITER_CONTINUE` to keep iterating, or
+    /// `FLUXIO_ITER_BREAK` to stop. It may push new tasks, which aren't
+    /// listed.
+    ///
+    /// If called from a callback of a task the executor is polling, only the
+    /// tasks pushed since the executor was last polled are listed.
+    fn fluxio_executor_dump_tasks(exec: *const fluxio_executor, func: fluxio_executor_dump_callback, userdata: *mut c_void) {
+        let exec = non_null!(&*exec ?= ());
+        let (tasks, _) = exec.pending_tasks();
+        for (id, label, age) in tasks {
+            let (label_ptr, label_len) = match label {
+                Some(ref label) => (label.as_ptr(), label.len()),
+                None => (ptr::null(), 0),
+            };
+            let age_ms = age.as_millis().min(u64::MAX as u128) as u64;
+            if FLUXIO_ITER_CONTINUE != func(userdata, id, label_ptr, label_len, age_ms) {
+                return;
+            }
+        }
+    }
+}
+
+ffi_fn! {
+    /// Returns the number of tasks pushed onto the executor that haven't
+    /// completed yet.
+    ///
+    /// This includes the connection tasks spawned by fluxio itself. A count
+    /// that keeps growing in a long-running program usually means tasks are
+    /// never woken, or bodies are never consumed.
+    fn fluxio_executor_task_count(exec: *const fluxio_executor) -> size_t {
+        non_null!(&*exec ?= 0).pending.load(Ordering::SeqCst)
+    }
+}
+
+ffi_fn! {
+    /// Pushes a task onto the executor, and blocks the calling thread until
+    /// it completes.
+    ///
+    /// The executor drives all of its tasks meanwhile, such as the
+    /// connection tasks the pushed task depends on. The thread sleeps while
+    /// no task can make progress, instead of polling in a loop. Other tasks
+    /// that complete meanwhile are returned by the next calls to
+    /// `fluxio_executor_poll`.
+    ///
+    /// Returns the completed task, to be inspected with `fluxio_task_type`
+    /// and `fluxio_task_value`, and freed with `fluxio_task_free`. A
+    /// callback set with `fluxio_task_on_complete` is not called.
+    ///
+    /// If `timeout_ms` isn't `0` and the task hasn't completed once that many
+    /// milliseconds passed, this returns `NULL`. The task stays on the
+    /// executor, and is returned by `fluxio_executor_poll` once it completes.
+    ///
+    /// If the executor is driven by `fluxio_executor_run_in_thread`, or
+    /// blocked on by another thread, this returns `NULL` and the task is not
+    /// pushed.
+    fn fluxio_executor_block_on(exec: *const fluxio_executor, task: *mut fluxio_task, timeout_ms: u64) -> *mut fluxio_task {
+        let exec = non_null!(&*exec ?= ptr::null_mut());
+        if task.is_null() {
+            return ptr::null_mut();
+        }
+
+        {
+            let mut runner = exec.is_woken.runner.lock().unwrap();
+            if runner.is_some() {
+                return ptr::null_mut();
+            }
+            *runner = Some(thread::current());
+        }
+
+        let task = unsafe { Box::from_raw(task) };
+        let timeout = if timeout_ms == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(timeout_ms))
+        };
+        let done = exec.block_on(task, timeout);
+        *exec.is_woken.runner.lock().unwrap() = None;
+
+        match done {
+            Some(task) => Box::into_raw(task),
+            None => ptr::null_mut(),
+        }
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Returns how many milliseconds are left until the next timer created
+    /// with `fluxio_timer_new` is due, or `-1` if there are no timers.
+    ///
+    /// A loop polling the executor with `fluxio_executor_poll` should poll it
+    /// again at the latest after this timeout, such as by passing it to
+    /// `poll()` or `epoll_wait()`. A timer that is already due returns `0`.
+    fn fluxio_executor_next_timer(exec: *const fluxio_executor) -> c_int {
+        let exec = non_null!(&*exec ?= -1);
+        match exec.timers.next_deadline() {
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                // Round up, so the timer is due once the timeout elapsed.
+                let ms = left.as_nanos().div_ceil(1_000_000);
+                ms.min(c_int::MAX as u128) as c_int
+            }
+            None => -1,
+        }
+    } ?= -1
+}
+
+ffi_fn! {
+    /// Wakes the executor from any thread.
+    ///
+    /// The thread spawned with `fluxio_executor_run_in_thread` polls the
+    /// tasks again, and a `fluxio_executor_poll` that is in progress on
+    /// another thread polls them again before returning. This can be used
+    /// to interrupt an executor after changing state that tasks depend on
+    /// without holding their wakers, such as when integrating with a loop
+    /// waiting on a condition variable.
+    fn fluxio_executor_wake_external(exec: *const fluxio_executor) {
+        non_null!(&*exec ?= ()).is_woken.notify();
+    }
+}
+
+ffi_fn! {
+    /// Returns a file descriptor that is readable when the executor has tasks
+    /// to poll, so it can be added to an existing `poll()`, `epoll` or
+    /// `kqueue` loop.
+    ///
+    /// Once it is readable, call `fluxio_executor_fd_ready`, and then
+    /// `fluxio_executor_poll` until it returns `NULL`. Timers don't signal the
+    /// descriptor, so the loop should also wait at most
+    /// `fluxio_executor_next_timer` milliseconds.
+    ///
+    /// The descriptor is owned by the executor, and closed when it is freed.
+    /// It is created on the first call, readable right away, and the same
+    /// descriptor is returned by later calls. Returns `-1` if it couldn't be
+    /// created, or on platforms other than Unix.
+    fn fluxio_executor_fd(exec: *const fluxio_executor) -> c_int {
+        let exec = non_null!(&*exec ?= -1);
+
+        #[cfg(unix)]
+        {
+            let fd = exec.is_woken.fd.get_or_init(|| {
+                let fd = WakeFd::new()?;
+                // Tasks may have been pushed or woken already.
+                fd.signal();
+                Some(fd)
+            });
+            fd.as_ref().map_or(-1, |fd| fd.read)
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = exec;
+            -1
+        }
+    } ?= -1
+}
+
+ffi_fn! {
+    /// Clears the readiness of the descriptor returned by
+    /// `fluxio_executor_fd`, once it was readable.
+    ///
+    /// Returns `1` if the descriptor was signaled, meaning the executor
+    /// should be polled with `fluxio_executor_poll` until it returns `NULL`,
+    /// and `0` otherwise. Tasks woken from now on signal it again.
+    fn fluxio_executor_fd_ready(exec: *const fluxio_executor) -> c_int {
+        let exec = non_null!(&*exec ?= 0);
+
+        #[cfg(unix)]
+        {
+            match exec.is_woken.fd.get() {
+                Some(Some(fd)) => fd.clear() as c_int,
+                _ => 0,
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = exec;
+            0
+        }
+    } ?= 0
+}
+
+ffi_fn! {
+    /// Spawns a thread that drives the executor, so it doesn't have to be
+    /// polled with `fluxio_executor_poll`.
+    ///
+    /// Each task that completes is passed to `func`, on the spawned thread,
+    /// along with `userdata`. The callback takes ownership of the task, and
+    /// must free it with `fluxio_task_free` once its value was taken. The
+    /// thread sleeps while no task can make progress, and is woken when a
+    /// task is woken or pushed with `fluxio_executor_push`.
+    ///
+    /// While the thread runs, the executor must not be polled with
+    /// `fluxio_executor_poll`. Only one thread can drive an executor.
+    ///
+    /// Returns `NULL` if the thread could not be spawned, or the executor
+    /// is already driven by a thread. Stop the thread with
+    /// `fluxio_executor_thread_stop`.
+    fn fluxio_executor_run_in_thread(exec: *const fluxio_executor, func: fluxio_executor_task_callback, userdata: *mut c_void) -> *mut fluxio_executor_thread {
+        let exec = non_null!(exec, {
+            Arc::increment_strong_count(exec);
+            Arc::from_raw(exec)
+        }, ptr::null_mut());
+        let userdata = UserDataPointer(userdata);
+
+        let mut runner = exec.is_woken.runner.lock().unwrap();
+        if runner.is_some() {
+            return ptr::null_mut();
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let exec = exec.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("fluxio-executor".into())
+                .spawn(move || exec.run(&stop, func, userdata))
+        };
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(_) => return ptr::null_mut(),
+        };
+        *runner = Some(handle.thread().clone());
+        drop(runner);
+        // Tasks may have been pushed before the runner was registered.
+        handle.thread().unpark();
+
+        Box::into_raw(Box::new(fluxio_executor_thread {
+            stop,
+            exec,
+            handle,
+        }))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Stops a thread spawned with `fluxio_executor_run_in_thread`, and frees
+    /// it.
+    ///
+    /// This waits for a completion callback that is running to return, and
+    /// for the thread to exit. Tasks that haven't completed stay on the
+    /// executor, and it can be polled again afterwards.
+    ///
+    /// If called from a completion callback, the thread exits once the
+    /// callback returns, without waiting for it.
+    fn fluxio_executor_thread_stop(thread: *mut fluxio_executor_thread) {
+        non_null!(Box::from_raw(thread) ?= ()).stop();
+    }
+}
+
 // ===== impl fluxio_task =====
 
 impl fluxio_task {
@@ -237,9 +1040,12 @@ impl fluxio_task {
         F::Output: IntoDynTaskType + Send + Sync + 'static,
     {
         Box::new(fluxio_task {
+            id: NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed),
             future: Box::pin(async move { fut.await.into_dyn_task_type() }),
             output: None,
             userdata: UserDataPointer(ptr::null_mut()),
+            label: None,
+            on_complete: None,
         })
     }
 
@@ -306,6 +1112,17 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    /// Returns the id of this task.
+    ///
+    /// Every task gets a different id when created, which stays the same
+    /// until it is freed. Ids start at `1`, and `0` is returned for a `NULL`
+    /// task. They match the ids listed by `fluxio_executor_dump`.
+    fn fluxio_task_id(task: *const fluxio_task) -> u64 {
+        non_null!(&*task ?= 0).id
+    }
+}
+
 ffi_fn! {
     /// Set a user data pointer to be associated with this task.
     ///
@@ -327,6 +1144,56 @@ ffi_fn! {
     } ?= ptr::null_mut()
 }
 
+ffi_fn! {
+    /// Set a label for this task, listed by `fluxio_executor_dump`.
+    ///
+    /// The label is copied, and invalid UTF-8 replaced. Passing a `len` of `0`
+    /// removes the label.
+    fn fluxio_task_set_label(task: *mut fluxio_task, label: *const u8, len: size_t) {
+        let task = non_null!(&mut *task ?= ());
+        task.label = if len == 0 {
+            None
+        } else {
+            let label = unsafe { std::slice::from_raw_parts(label, len) };
+            Some(Cow::Owned(String::from_utf8_lossy(label).into_owned()))
+        };
+    }
+}
+
+ffi_fn! {
+    /// Set a callback called when this task completes, instead of the task
+    /// being returned by `fluxio_executor_poll`.
+    ///
+    /// The callback is called with `userdata` and the completed task, on the
+    /// thread polling the executor, once that thread isn't polling other
+    /// tasks anymore. It takes ownership of the task: the value can be taken
+    /// with `fluxio_task_value`, and the task must be freed with
+    /// `fluxio_task_free`. The callback may push new tasks onto the executor.
+    fn fluxio_task_on_complete(task: *mut fluxio_task, func: fluxio_task_on_complete_callback, userdata: *mut c_void) {
+        let task = non_null!(&mut *task ?= ());
+        task.on_complete = Some((func, UserDataPointer(userdata)));
+    }
+}
+
+ffi_fn! {
+    /// Creates a task that completes once `ms` milliseconds passed.
+    ///
+    /// The task is driven by the executor it's pushed onto, and its value is
+    /// `FLUXIO_TASK_EMPTY`. This can be used to build timeouts, or to do
+    /// something periodically, without a separate event loop. An executor
+    /// driven by `fluxio_executor_run_in_thread` or blocked on with
+    /// `fluxio_executor_block_on` wakes up for timers by itself. Loops
+    /// calling `fluxio_executor_poll` should use `fluxio_executor_next_timer`
+    /// to know when to poll again.
+    fn fluxio_timer_new(ms: u64) -> *mut fluxio_task {
+        let timer = Timer {
+            deadline: Instant::now() + Duration::from_millis(ms),
+            registered: None,
+        };
+        Box::into_raw(fluxio_task::boxed(timer))
+    } ?= ptr::null_mut()
+}
+
 // ===== impl AsTaskType =====
 
 unsafe impl AsTaskType for () {
@@ -393,6 +1260,16 @@ ffi_fn! {
 
 // ===== impl fluxio_waker =====
 
+ffi_fn! {
+    /// Clones a waker, such as to hand it to another thread.
+    ///
+    /// Both wakers wake the same task, and each must be woken or freed.
+    fn fluxio_waker_clone(waker: *const fluxio_waker) -> *mut fluxio_waker {
+        let waker = non_null!(&*waker ?= ptr::null_mut()).waker.clone();
+        Box::into_raw(Box::new(fluxio_waker { waker }))
+    } ?= ptr::null_mut()
+}
+
 ffi_fn! {
     /// Free a waker that hasn't been woken.
     fn fluxio_waker_free(waker: *mut fluxio_waker) {
@@ -410,3 +1287,316 @@ ffi_fn! {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_executor_dump() {
+        let exec = fluxio_executor_new();
+        let task = Box::into_raw(fluxio_task::boxed(futures_util::future::pending::<()>()));
+        fluxio_task_set_label(task, b"GET /slow".as_ptr(), 9);
+        fluxio_executor_push(exec, task);
+        let task = Box::into_raw(fluxio_task::boxed(futures_util::future::pending::<()>()));
+        fluxio_executor_push(exec, task);
+        assert!(fluxio_executor_poll(exec).is_null());
+
+        let mut buf = [0; 256];
+        let len = fluxio_executor_dump(exec, buf.as_mut_ptr(), buf.len());
+        let dump = std::str::from_utf8(&buf[..len]).unwrap();
+        assert_eq!(dump.lines().count(), 2, "{}", dump);
+        assert!(dump.contains("GET /slow: pending for "), "{}", dump);
+        assert!(dump.contains("(unlabeled): pending for "), "{}", dump);
+
+        // truncated to the buffer
+        assert_eq!(fluxio_executor_dump(exec, buf.as_mut_ptr(), 4), 4);
+        assert_eq!(fluxio_executor_dump(exec, ptr::null_mut(), 256), 0);
+
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_task_id_and_count() {
+        extern "C" fn dump_task(
+            userdata: *mut c_void,
+            id: u64,
+            label: *const u8,
+            label_len: size_t,
+            _age_ms: u64,
+        ) -> c_int {
+            let seen = unsafe { &mut *(userdata as *mut Vec<(u64, String)>) };
+            let label = if label.is_null() {
+                String::new()
+            } else {
+                let label = unsafe { std::slice::from_raw_parts(label, label_len) };
+                String::from_utf8(label.to_vec()).unwrap()
+            };
+            seen.push((id, label));
+            crate::ffi::FLUXIO_ITER_CONTINUE
+        }
+
+        let exec = fluxio_executor_new();
+        let slow = Box::into_raw(fluxio_task::boxed(futures_util::future::pending::<()>()));
+        fluxio_task_set_label(slow, b"GET /slow".as_ptr(), 9);
+        let slow_id = fluxio_task_id(slow);
+        let done = Box::into_raw(fluxio_task::boxed(async {}));
+        let done_id = fluxio_task_id(done);
+        assert_ne!(slow_id, 0);
+        assert_ne!(slow_id, done_id);
+
+        fluxio_executor_push(exec, slow);
+        fluxio_executor_push(exec, done);
+        assert_eq!(fluxio_executor_task_count(exec), 2);
+
+        let done = fluxio_executor_poll(exec);
+        assert_eq!(fluxio_task_id(done), done_id);
+        fluxio_task_free(done);
+        assert_eq!(fluxio_executor_task_count(exec), 1);
+
+        let mut seen = Vec::<(u64, String)>::new();
+        fluxio_executor_dump_tasks(exec, dump_task, &mut seen as *mut _ as *mut c_void);
+        assert_eq!(seen, [(slow_id, "GET /slow".to_owned())]);
+
+        let mut buf = [0; 256];
+        let len = fluxio_executor_dump(exec, buf.as_mut_ptr(), buf.len());
+        let dump = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(
+            dump.starts_with(&format!("#{} GET /slow: ", slow_id)),
+            "{}",
+            dump
+        );
+
+        fluxio_executor_free(exec);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_executor_fd() {
+        let exec = fluxio_executor_new();
+        let fd = fluxio_executor_fd(exec);
+        assert!(fd >= 0);
+        assert_eq!(fluxio_executor_fd(exec), fd);
+
+        // readable right away, once
+        assert_eq!(fluxio_executor_fd_ready(exec), 1);
+        assert_eq!(fluxio_executor_fd_ready(exec), 0);
+
+        let (tx, rx) = futures_channel::oneshot::channel::<()>();
+        let task = Box::into_raw(fluxio_task::boxed(async move {
+            let _ = rx.await;
+        }));
+        fluxio_executor_push(exec, task);
+        assert_eq!(fluxio_executor_fd_ready(exec), 1);
+        assert!(fluxio_executor_poll(exec).is_null());
+        // the first poll of a task may leave it signaled once more
+        fluxio_executor_fd_ready(exec);
+        assert!(fluxio_executor_poll(exec).is_null());
+        assert_eq!(fluxio_executor_fd_ready(exec), 0);
+
+        // waking the task signals it again
+        tx.send(()).unwrap();
+        assert_eq!(fluxio_executor_fd_ready(exec), 1);
+        let task = fluxio_executor_poll(exec);
+        assert!(!task.is_null());
+        fluxio_task_free(task);
+
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_executor_run_in_thread() {
+        use std::sync::mpsc;
+
+        extern "C" fn on_complete(userdata: *mut c_void, task: *mut fluxio_task) {
+            let tx = unsafe { &*(userdata as *const mpsc::Sender<usize>) };
+            tx.send(fluxio_task_userdata(task) as usize).unwrap();
+            fluxio_task_free(task);
+        }
+
+        let (tx, rx) = mpsc::channel::<usize>();
+        let (wake_tx, wake_rx) = futures_channel::oneshot::channel::<()>();
+
+        let exec = fluxio_executor_new();
+        let task = Box::into_raw(fluxio_task::boxed(async {}));
+        fluxio_task_set_userdata(task, 1 as *mut c_void);
+        fluxio_executor_push(exec, task);
+
+        let runner =
+            fluxio_executor_run_in_thread(exec, on_complete, &tx as *const _ as *mut c_void);
+        assert!(!runner.is_null());
+        assert_eq!(rx.recv_timeout(std::time::Duration::from_secs(5)), Ok(1));
+
+        // already driven by a thread
+        assert!(fluxio_executor_run_in_thread(exec, on_complete, ptr::null_mut()).is_null());
+
+        // a task woken from another thread
+        let task = Box::into_raw(fluxio_task::boxed(async move {
+            let _ = wake_rx.await;
+        }));
+        fluxio_task_set_userdata(task, 2 as *mut c_void);
+        fluxio_executor_push(exec, task);
+        wake_tx.send(()).unwrap();
+        assert_eq!(rx.recv_timeout(std::time::Duration::from_secs(5)), Ok(2));
+
+        fluxio_executor_thread_stop(runner);
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_executor_shutdown() {
+        let exec = fluxio_executor_new();
+        let done = Box::into_raw(fluxio_task::boxed(async {}));
+        fluxio_executor_push(exec, done);
+        let (tx, rx) = futures_channel::oneshot::channel::<()>();
+        let woken = Box::into_raw(fluxio_task::boxed(async move {
+            let _ = rx.await;
+        }));
+        fluxio_executor_push(exec, woken);
+        let stuck = Box::into_raw(fluxio_task::boxed(futures_util::future::pending::<()>()));
+        fluxio_executor_push(exec, stuck);
+
+        let waker = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            tx.send(()).unwrap();
+        });
+        let stats = fluxio_executor_shutdown(exec, 500);
+        assert_eq!(stats.completed, 2);
+        assert_eq!(stats.canceled, 1);
+        waker.join().unwrap();
+    }
+
+    #[test]
+    fn test_executor_block_on() {
+        let (tx, rx) = futures_channel::oneshot::channel::<()>();
+
+        let exec = fluxio_executor_new();
+        let other = Box::into_raw(fluxio_task::boxed(async {}));
+        fluxio_task_set_userdata(other, 1 as *mut c_void);
+        fluxio_executor_push(exec, other);
+
+        // wait for a task that is woken from another thread
+        let task = Box::into_raw(fluxio_task::boxed(async move {
+            let _ = rx.await;
+        }));
+        fluxio_task_set_userdata(task, 2 as *mut c_void);
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            tx.send(()).unwrap();
+        });
+        let done = fluxio_executor_block_on(exec, task, 5_000);
+        sender.join().unwrap();
+        assert_eq!(done, task);
+        fluxio_task_free(done);
+
+        // the other task is still returned by a poll
+        let done = fluxio_executor_poll(exec);
+        assert_eq!(fluxio_task_userdata(done) as usize, 1);
+        fluxio_task_free(done);
+        assert!(fluxio_executor_poll(exec).is_null());
+
+        // a timeout leaves the task on the executor
+        let task = Box::into_raw(fluxio_task::boxed(futures_util::future::pending::<()>()));
+        assert!(fluxio_executor_block_on(exec, task, 1).is_null());
+        let mut buf = [0; 256];
+        assert_ne!(fluxio_executor_dump(exec, buf.as_mut_ptr(), buf.len()), 0);
+
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_task_on_complete() {
+        extern "C" fn on_complete(userdata: *mut c_void, task: *mut fluxio_task) {
+            let completed = unsafe { &mut *(userdata as *mut Vec<usize>) };
+            completed.push(fluxio_task_userdata(task) as usize);
+            fluxio_task_free(task);
+        }
+
+        let mut completed = Vec::<usize>::new();
+        let exec = fluxio_executor_new();
+        for n in 1..=2 {
+            let task = Box::into_raw(fluxio_task::boxed(async {}));
+            fluxio_task_set_userdata(task, n as *mut c_void);
+            fluxio_task_on_complete(task, on_complete, &mut completed as *mut _ as *mut c_void);
+            fluxio_executor_push(exec, task);
+        }
+        let task = Box::into_raw(fluxio_task::boxed(async {}));
+        fluxio_task_set_userdata(task, 3 as *mut c_void);
+        fluxio_executor_push(exec, task);
+
+        // only the task without a callback is returned
+        let done = fluxio_executor_poll(exec);
+        assert_eq!(fluxio_task_userdata(done) as usize, 3);
+        fluxio_task_free(done);
+        assert!(fluxio_executor_poll(exec).is_null());
+
+        completed.sort();
+        assert_eq!(completed, [1, 2]);
+
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_timer() {
+        let exec = fluxio_executor_new();
+        assert_eq!(fluxio_executor_next_timer(exec), -1);
+
+        let long = fluxio_timer_new(60_000);
+        fluxio_task_set_userdata(long, 1 as *mut c_void);
+        fluxio_executor_push(exec, long);
+        let short = fluxio_timer_new(20);
+        fluxio_task_set_userdata(short, 2 as *mut c_void);
+        fluxio_executor_push(exec, short);
+
+        assert!(fluxio_executor_poll(exec).is_null());
+        let left = fluxio_executor_next_timer(exec);
+        assert!((1..=20).contains(&left), "next timer in {}ms", left);
+
+        let start = Instant::now();
+        std::thread::sleep(Duration::from_millis(left as u64));
+        let done = fluxio_executor_poll(exec);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(fluxio_task_userdata(done) as usize, 2);
+        assert!(matches!(
+            fluxio_task_type(done),
+            fluxio_task_return_type::FLUXIO_TASK_EMPTY
+        ));
+        fluxio_task_free(done);
+
+        // block_on wakes up for the timer by itself
+        let task = fluxio_timer_new(10);
+        let start = Instant::now();
+        let done = fluxio_executor_block_on(exec, task, 5_000);
+        assert_eq!(done, task);
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        fluxio_task_free(done);
+
+        fluxio_executor_free(exec);
+    }
+
+    #[test]
+    fn test_waker_clone_across_threads() {
+        use std::sync::atomic::AtomicUsize;
+
+        struct WakeCount(AtomicUsize);
+
+        impl futures_util::task::ArcWake for WakeCount {
+            fn wake_by_ref(me: &Arc<WakeCount>) {
+                me.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let count = Arc::new(WakeCount(AtomicUsize::new(0)));
+        let waker = Box::into_raw(Box::new(fluxio_waker {
+            waker: futures_util::task::waker(count.clone()),
+        }));
+
+        let clone = fluxio_waker_clone(waker) as usize;
+        std::thread::spawn(move || fluxio_waker_wake(clone as *mut fluxio_waker))
+            .join()
+            .unwrap();
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+
+        fluxio_waker_wake(waker);
+        assert_eq!(count.0.load(Ordering::SeqCst), 2);
+    }
+}
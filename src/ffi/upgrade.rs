@@ -0,0 +1,139 @@
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::ptr;
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures_util::future::poll_fn;
+use libc::size_t;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::body::fluxio_buf;
+use super::http_types::fluxio_response;
+use super::task::{fluxio_task, fluxio_task_return_type, AsTaskType};
+use crate::upgrade::Upgraded;
+
+/// An upgraded IO stream, such as after a CONNECT tunnel was established or
+/// a `101 Switching Protocols` response was received.
+pub struct fluxio_upgraded {
+    io: Upgraded,
+}
+
+// The upgraded IO is only ever accessed through a `*mut fluxio_upgraded`,
+// and C callers must not use it from multiple threads at once.
+unsafe impl Sync for fluxio_upgraded {}
+
+// ===== impl fluxio_upgraded =====
+
+impl fluxio_upgraded {
+    pub(super) fn new(io: Upgraded) -> fluxio_upgraded {
+        fluxio_upgraded { io }
+    }
+}
+
+ffi_fn! {
+    /// Take the upgraded connection of this response.
+    ///
+    /// This should be called on a response with a `101 Switching Protocols`
+    /// status, or a successful response to a `CONNECT` request.
+    ///
+    /// Returns a task that needs to be polled until it is ready. When ready,
+    /// the task yields a `fluxio_upgraded *`. If the connection could not be
+    /// upgraded, the task yields a `fluxio_error *` instead.
+    ///
+    /// This does not consume the `fluxio_response *`, but it can only be
+    /// called once per response.
+    fn fluxio_response_upgrade(resp: *mut fluxio_response) -> *mut fluxio_task {
+        let resp = non_null!(&mut *resp ?= ptr::null_mut());
+        let on_upgrade = crate::upgrade::on(&mut resp.0);
+
+        Box::into_raw(fluxio_task::boxed(async move {
+            on_upgrade.await.map(|io| fluxio_upgraded { io })
+        }))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Return a task that reads up to `max_len` bytes from the upgraded
+    /// connection.
+    ///
+    /// The task value may have different types depending on the outcome:
+    ///
+    /// - `FLUXIO_TASK_BUF`: Success, and some data was read.
+    /// - `FLUXIO_TASK_ERROR`: An error reading the data.
+    /// - `FLUXIO_TASK_EMPTY`: The connection has reached EOF.
+    ///
+    /// This does not consume the `fluxio_upgraded *`, so it may be used again.
+    /// However, it MUST NOT be used or freed until the related task completes.
+    fn fluxio_upgraded_read(upgraded: *mut fluxio_upgraded, max_len: size_t) -> *mut fluxio_task {
+        // This doesn't take ownership of the IO, so don't allow destructor
+        let mut upgraded = ManuallyDrop::new(non_null!(Box::from_raw(upgraded) ?= ptr::null_mut()));
+
+        Box::into_raw(fluxio_task::boxed(async move {
+            let mut buf = BytesMut::zeroed(max_len);
+            let n = poll_fn(|cx| {
+                let mut read_buf = ReadBuf::new(&mut buf);
+                Pin::new(&mut upgraded.io)
+                    .poll_read(cx, &mut read_buf)
+                    .map_ok(|()| read_buf.filled().len())
+            })
+            .await
+            .map_err(crate::Error::new_io)?;
+
+            if n == 0 && max_len != 0 {
+                return Ok(None);
+            }
+            buf.truncate(n);
+            Ok(Some(fluxio_buf(buf.freeze())))
+        }))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Return a task that writes all of the provided bytes to the upgraded
+    /// connection, and then flushes it.
+    ///
+    /// The bytes are copied, so the `buf` argument can be freed or changed
+    /// after this call returns.
+    ///
+    /// The task yields `FLUXIO_TASK_EMPTY` once all bytes were written, or a
+    /// `fluxio_error *` if the write failed.
+    ///
+    /// This does not consume the `fluxio_upgraded *`, so it may be used again.
+    /// However, it MUST NOT be used or freed until the related task completes.
+    fn fluxio_upgraded_write(upgraded: *mut fluxio_upgraded, buf: *const u8, len: size_t) -> *mut fluxio_task {
+        // This doesn't take ownership of the IO, so don't allow destructor
+        let mut upgraded = ManuallyDrop::new(non_null!(Box::from_raw(upgraded) ?= ptr::null_mut()));
+        let mut data = Bytes::copy_from_slice(unsafe {
+            std::slice::from_raw_parts(buf, len)
+        });
+
+        Box::into_raw(fluxio_task::boxed(async move {
+            let io = &mut upgraded.io;
+            while data.has_remaining() {
+                let n = poll_fn(|cx| Pin::new(&mut *io).poll_write(cx, data.chunk()))
+                    .await
+                    .map_err(crate::Error::new_io)?;
+                if n == 0 {
+                    return Err(crate::Error::new_io(std::io::ErrorKind::WriteZero.into()));
+                }
+                data.advance(n);
+            }
+            poll_fn(|cx| Pin::new(&mut *io).poll_flush(cx))
+                .await
+                .map_err(crate::Error::new_io)
+        }))
+    } ?= ptr::null_mut()
+}
+
+ffi_fn! {
+    /// Free a `fluxio_upgraded *`, closing the connection.
+    fn fluxio_upgraded_free(upgraded: *mut fluxio_upgraded) {
+        drop(non_null!(Box::from_raw(upgraded) ?= ()));
+    }
+}
+
+unsafe impl AsTaskType for fluxio_upgraded {
+    fn as_task_type(&self) -> fluxio_task_return_type {
+        fluxio_task_return_type::FLUXIO_TASK_UPGRADED
+    }
+}
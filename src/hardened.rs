@@ -0,0 +1,137 @@
+//! Hardened defaults, for deployments that need to show how they are
+//! configured.
+//!
+//! A [`Profile`] selects conservative values for the options bounding how
+//! much a peer can make a connection buffer, wait for, or accept, and turns
+//! off lenient parsing. It is applied with `preset_hardened` on
+//! [`server::conn::Http`], [`server::Builder`], [`client::conn::Builder`] and
+//! [`client::Builder`].
+//!
+//! Options set afterwards still override the profile, so relaxing one takes
+//! an explicit call. Each option of the profile set again afterwards is
+//! listed by the [`Posture`] of the builder, which can be checked or logged
+//! at startup.
+//!
+//! Profiles are versioned: the options a profile sets never change, and
+//! tightening them takes a new version.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(all(feature = "server", feature = "http1"))]
+//! # fn doc() {
+//! use fluxio::hardened::Profile;
+//! use fluxio::server::conn::Http;
+//!
+//! let mut http = Http::new();
+//! http.preset_hardened(Profile::V1)
+//!     .max_buf_size(1024 * 1024);
+//!
+//! let posture = http.hardened_posture().unwrap();
+//! assert_eq!(posture.overridden(), ["max_buf_size"]);
+//! # }
+//! ```
+//!
+//! [`server::conn::Http`]: crate::server::conn::Http
+//! [`server::Builder`]: crate::server::Builder
+//! [`client::conn::Builder`]: crate::client::conn::Builder
+//! [`client::Builder`]: crate::client::Builder
+
+/// A versioned set of hardened defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Profile {
+    /// The first hardened profile.
+    ///
+    /// For servers, this sets:
+    ///
+    /// - HTTP/1 read buffers of at most 64 KiB, which also bounds the size
+    ///   of request heads,
+    /// - a 30 second timeout for reading HTTP/1 request heads, with the
+    ///   `runtime` feature,
+    /// - no HTTP/1 half-closures,
+    /// - rejecting HTTP/1 requests with both `Content-Length` and
+    ///   `Transfer-Encoding`, obsolete line folding, bare CRs, or whitespace
+    ///   before a header colon,
+    /// - at most 100 concurrent streams per HTTP/2 connection, header lists
+    ///   of at most 16 KiB, and at most 10 streams reset by the client
+    ///   before being accepted, against "rapid reset" attacks.
+    ///
+    /// For clients, this sets:
+    ///
+    /// - no HTTP/0.9 responses, spaces after header names or obsolete
+    ///   multiline headers,
+    /// - HTTP/1 read buffers of at most 64 KiB, which also bounds the size
+    ///   of response heads,
+    /// - at most 5 informational responses before the final response,
+    /// - a 30 second timeout for receiving response heads, with the
+    ///   `runtime` feature.
+    V1,
+}
+
+/// The hardened profile applied to a builder, and the options of it set
+/// again afterwards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Posture {
+    profile: Profile,
+    overridden: Vec<&'static str>,
+}
+
+impl Posture {
+    pub(crate) fn new(profile: Profile) -> Posture {
+        Posture {
+            profile,
+            overridden: Vec::new(),
+        }
+    }
+
+    /// Returns the profile that was applied.
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    /// Returns the options of the profile set again since it was applied,
+    /// named after the methods setting them, in the order they were first
+    /// set.
+    ///
+    /// An option set again counts even if it was set to an equal or
+    /// stricter value.
+    pub fn overridden(&self) -> &[&'static str] {
+        &self.overridden
+    }
+
+    /// Returns whether all options of the profile are still as it set them.
+    pub fn is_hardened(&self) -> bool {
+        self.overridden.is_empty()
+    }
+
+    /// Records that `option` was set, if a profile was applied.
+    pub(crate) fn record(posture: &mut Option<Posture>, option: &'static str) {
+        if let Some(ref mut posture) = *posture {
+            if !posture.overridden.contains(&option) {
+                posture.overridden.push(option);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_each_option_once() {
+        let mut posture = None;
+        Posture::record(&mut posture, "max_buf_size");
+        assert_eq!(posture, None);
+
+        posture = Some(Posture::new(Profile::V1));
+        assert!(posture.as_ref().unwrap().is_hardened());
+        Posture::record(&mut posture, "max_buf_size");
+        Posture::record(&mut posture, "http1_half_close");
+        Posture::record(&mut posture, "max_buf_size");
+        let posture = posture.unwrap();
+        assert_eq!(posture.overridden(), ["max_buf_size", "http1_half_close"]);
+        assert!(!posture.is_hardened());
+    }
+}
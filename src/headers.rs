@@ -6,6 +6,11 @@ use http::HeaderMap;
 #[cfg(all(feature = "http2", feature = "client"))]
 use http::Method;
 
+#[cfg(feature = "http1")]
+use crate::error::Parse;
+#[cfg(feature = "http1")]
+use crate::ext::ContentLengthPolicy;
+
 #[cfg(feature = "http1")]
 pub(super) fn connection_keep_alive(value: &HeaderValue) -> bool {
     connection_has(value, "keep-alive")
@@ -64,6 +69,56 @@ pub(super) fn content_length_parse_all_values(values: ValueIter<'_, HeaderValue>
     return content_length;
 }
 
+/// Parses the `Content-Length` values of a received message, deciding
+/// between multiple values with `policy`.
+#[cfg(all(feature = "http1", feature = "client"))]
+pub(super) fn content_length_parse_with_policy(
+    headers: &HeaderMap,
+    policy: ContentLengthPolicy,
+) -> Result<Option<u64>, Parse> {
+    let mut content_length = None;
+    for h in headers.get_all(CONTENT_LENGTH) {
+        content_length_merge(&mut content_length, h, policy)?;
+    }
+    Ok(content_length)
+}
+
+/// Adds the lengths of one received `Content-Length` value, which may be a
+/// comma-separated list, to those seen before, deciding between multiple
+/// lengths with `policy`.
+#[cfg(feature = "http1")]
+pub(super) fn content_length_merge(
+    content_length: &mut Option<u64>,
+    value: &HeaderValue,
+    policy: ContentLengthPolicy,
+) -> Result<(), Parse> {
+    let line = value
+        .to_str()
+        .map_err(|_| Parse::content_length_invalid())?;
+    for v in line.split(',') {
+        let prev = match *content_length {
+            Some(prev) => prev,
+            None => {
+                let n = from_digits(v.trim().as_bytes());
+                *content_length = Some(n.ok_or_else(Parse::content_length_invalid)?);
+                continue;
+            }
+        };
+        match policy {
+            ContentLengthPolicy::Reject => return Err(Parse::content_length_multiple()),
+            // only the first length is kept
+            ContentLengthPolicy::FirstWins => return Ok(()),
+            ContentLengthPolicy::MergeIfEqual => {
+                let n = from_digits(v.trim().as_bytes());
+                if n.ok_or_else(Parse::content_length_invalid)? != prev {
+                    return Err(Parse::content_length_mismatch());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn from_digits(bytes: &[u8]) -> Option<u64> {
     // cannot use FromStr for u64, since it allows a signed prefix
     let mut result = 0u64;
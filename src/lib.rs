@@ -55,6 +55,12 @@
 //!   connectors and acceptors for TCP, and a default executor.
 //! - `tcp`: Enables convenient implementations over TCP (using tokio).
 //! - `stream`: Provides `futures::Stream` capabilities.
+//! - `sink`: Provides `futures::Sink` capabilities for body senders.
+//! - `tokio-util`: Provides `AsyncRead` and `AsyncWrite` adapters for bodies.
+//! - `compress`: Enables compressing request bodies with `gzip`, `deflate`,
+//!   `br` or `zstd`.
+//! - `decompress`: Enables transparently decoding compressed response bodies.
+//! - `testing`: Provides in-memory and fault-injecting transports for tests.
 //!
 //! [feature flags]: https://doc.rust-lang.org/cargo/reference/manifest.html#the-features-section
 #[doc(hidden)]
@@ -80,15 +86,21 @@ mod error;
 pub mod ext;
 #[cfg(test)]
 mod mock;
+pub mod redact;
 pub mod rt;
 pub mod service;
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
 pub mod upgrade;
 
 #[cfg(feature = "ffi")]
 pub mod ffi;
 
 cfg_proto! {
+    pub mod hardened;
     mod headers;
+    pub mod observer;
     mod proto;
 }
 
@@ -99,6 +111,8 @@ cfg_feature! {
     #[cfg(any(feature = "http1", feature = "http2"))]
     #[doc(no_inline)]
     pub use crate::client::Client;
+    #[cfg(any(feature = "http1", feature = "http2"))]
+    pub mod metrics;
 }
 
 cfg_feature! {
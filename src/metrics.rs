@@ -0,0 +1,510 @@
+//! Metrics of HTTP connections and requests.
+//!
+//! A [`MetricsSink`] can be set on a [`Client`](crate::Client) with
+//! [`Builder::metrics`](crate::client::Builder::metrics), or on a single
+//! connection with
+//! [`conn::Builder::metrics`](crate::client::conn::Builder::metrics). It is
+//! told about everything measured on the related connections, and can
+//! forward them to any metrics system.
+//!
+//! If no such system is in use, the [`Collector`] keeps counts and latency
+//! histograms in memory.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "tcp")]
+//! # fn doc() {
+//! use std::sync::Arc;
+//! use fluxio::metrics::Collector;
+//!
+//! let collector = Arc::new(Collector::new());
+//! let client = fluxio::Client::builder()
+//!     .metrics(collector.clone())
+//!     .build_http::<fluxio::Body>();
+//!
+//! // later...
+//! let snapshot = collector.snapshot();
+//! println!("{} bytes read", snapshot.bytes_read());
+//! # drop(client);
+//! # }
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "http2")]
+use std::io;
+#[cfg(feature = "http2")]
+use std::pin::Pin;
+#[cfg(feature = "http2")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "http2")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::client::conn::ConnContext;
+
+/// Receives the metrics measured on connections.
+///
+/// Every method has an empty default implementation, so a sink only needs to
+/// implement the ones it is interested in.
+///
+/// The methods are called in the middle of processing connections, and so
+/// should return quickly.
+pub trait MetricsSink: Send + Sync {
+    /// Called with the number of bytes read from a connection.
+    fn bytes_read(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// Called with the number of bytes written to a connection.
+    fn bytes_written(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// Called with the time from sending a request until its response head
+    /// was received.
+    fn request_latency(&self, latency: Duration) {
+        let _ = latency;
+    }
+
+    /// Called with the time a connection handshake took.
+    ///
+    /// For HTTP/2, this includes exchanging the connection preface.
+    fn handshake_duration(&self, duration: Duration) {
+        let _ = duration;
+    }
+
+    /// Called when a `Client` gets a connection for a request.
+    ///
+    /// `reused` is `true` if an existing connection from the pool was used,
+    /// and `false` if a new one was established.
+    fn pool_checkout(&self, reused: bool) {
+        let _ = reused;
+    }
+
+    /// Called when an HTTP/2 stream is reset.
+    fn stream_reset(&self) {}
+
+    /// Called with a new estimate of the bandwidth and round trip time of an
+    /// HTTP/2 connection.
+    ///
+    /// Estimates are only made with an adaptive flow control window, from the
+    /// data received between PINGs.
+    fn bandwidth_estimate(&self, estimate: BandwidthEstimate) {
+        let _ = estimate;
+    }
+
+    /// Called when the handshake of a connection with a [`ConnContext`]
+    /// starts, returning the sink for the metrics of that connection.
+    ///
+    /// Returning `None`, as by default, keeps using this sink. A sink can
+    /// return one labeling the metrics with the context instead.
+    fn for_context(&self, context: &ConnContext) -> Option<Arc<dyn MetricsSink>> {
+        let _ = context;
+        None
+    }
+}
+
+/// An estimate of the bandwidth and round trip time of a connection.
+///
+/// These are the values measured to size the adaptive flow control window of
+/// HTTP/2 connections, so applications adapting to the network, such as
+/// picking a video bitrate, can reuse them instead of probing separately.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BandwidthEstimate {
+    bandwidth: f64,
+    rtt: Duration,
+}
+
+/// A `MetricsSink` that collects metrics in memory.
+///
+/// Use [`Collector::snapshot`] to read the current values.
+#[derive(Debug, Default)]
+pub struct Collector {
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    request_latency: Histogram,
+    handshake_duration: Histogram,
+    pool_checkouts: AtomicU64,
+    pool_reuses: AtomicU64,
+    stream_resets: AtomicU64,
+    // The bits of the `f64`, and 0 until the first estimate.
+    bandwidth_bits: AtomicU64,
+    rtt_micros: AtomicU64,
+}
+
+/// The values of a [`Collector`] at some point in time.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    bytes_read: u64,
+    bytes_written: u64,
+    request_latency: HistogramSnapshot,
+    handshake_duration: HistogramSnapshot,
+    pool_checkouts: u64,
+    pool_reuses: u64,
+    stream_resets: u64,
+    bandwidth_estimate: Option<BandwidthEstimate>,
+}
+
+/// Upper bounds of the histogram buckets, in microseconds.
+const BUCKET_BOUNDS: [u64; 13] = [
+    1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 2_500_000,
+    5_000_000, 10_000_000,
+];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    // One more than the bounds, for everything slower.
+    buckets: [AtomicU64; 14],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+/// A histogram of durations.
+///
+/// Durations are counted in buckets ranging from 1 millisecond to 10
+/// seconds.
+#[derive(Clone, Debug)]
+pub struct HistogramSnapshot {
+    buckets: Vec<(Option<Duration>, u64)>,
+    count: u64,
+    sum: Duration,
+}
+
+// ===== impl Collector =====
+
+impl Collector {
+    /// Create a new `Collector` with all values at zero.
+    pub fn new() -> Collector {
+        Collector::default()
+    }
+
+    /// Read the current values.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            request_latency: self.request_latency.snapshot(),
+            handshake_duration: self.handshake_duration.snapshot(),
+            pool_checkouts: self.pool_checkouts.load(Ordering::Relaxed),
+            pool_reuses: self.pool_reuses.load(Ordering::Relaxed),
+            stream_resets: self.stream_resets.load(Ordering::Relaxed),
+            bandwidth_estimate: match self.bandwidth_bits.load(Ordering::Relaxed) {
+                0 => None,
+                bits => Some(BandwidthEstimate::new(
+                    f64::from_bits(bits),
+                    Duration::from_micros(self.rtt_micros.load(Ordering::Relaxed)),
+                )),
+            },
+        }
+    }
+}
+
+impl MetricsSink for Collector {
+    fn bytes_read(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn bytes_written(&self, bytes: u64) {
+        self.bytes_written.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn request_latency(&self, latency: Duration) {
+        self.request_latency.observe(latency);
+    }
+
+    fn handshake_duration(&self, duration: Duration) {
+        self.handshake_duration.observe(duration);
+    }
+
+    fn pool_checkout(&self, reused: bool) {
+        self.pool_checkouts.fetch_add(1, Ordering::Relaxed);
+        if reused {
+            self.pool_reuses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn stream_reset(&self) {
+        self.stream_resets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn bandwidth_estimate(&self, estimate: BandwidthEstimate) {
+        let micros = u64::try_from(estimate.rtt.as_micros()).unwrap_or(u64::MAX);
+        self.rtt_micros.store(micros, Ordering::Relaxed);
+        self.bandwidth_bits
+            .store(estimate.bandwidth.to_bits(), Ordering::Relaxed);
+    }
+}
+
+// ===== impl Snapshot =====
+
+impl Snapshot {
+    /// The total number of bytes read from connections.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// The total number of bytes written to connections.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// The latencies of requests, until their response head was received.
+    pub fn request_latency(&self) -> &HistogramSnapshot {
+        &self.request_latency
+    }
+
+    /// The durations of connection handshakes.
+    pub fn handshake_duration(&self) -> &HistogramSnapshot {
+        &self.handshake_duration
+    }
+
+    /// The number of times a `Client` got a connection for a request.
+    pub fn pool_checkouts(&self) -> u64 {
+        self.pool_checkouts
+    }
+
+    /// The number of pool checkouts that reused an existing connection.
+    pub fn pool_reuses(&self) -> u64 {
+        self.pool_reuses
+    }
+
+    /// The number of HTTP/2 streams that were reset.
+    pub fn stream_resets(&self) -> u64 {
+        self.stream_resets
+    }
+
+    /// The latest bandwidth estimate of any connection, if one was made.
+    pub fn bandwidth_estimate(&self) -> Option<BandwidthEstimate> {
+        self.bandwidth_estimate
+    }
+}
+
+// ===== impl BandwidthEstimate =====
+
+impl BandwidthEstimate {
+    pub(crate) fn new(bandwidth: f64, rtt: Duration) -> BandwidthEstimate {
+        BandwidthEstimate { bandwidth, rtt }
+    }
+
+    /// The estimated bandwidth, in bytes per second.
+    pub fn bandwidth(&self) -> f64 {
+        self.bandwidth
+    }
+
+    /// The estimated round trip time, as a moving average.
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+}
+
+// ===== impl Histogram =====
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        let idx = BUCKET_BOUNDS
+            .iter()
+            .position(|&bound| micros <= bound)
+            .unwrap_or(BUCKET_BOUNDS.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(idx, count)| {
+                let bound = BUCKET_BOUNDS.get(idx).copied().map(Duration::from_micros);
+                (bound, count.load(Ordering::Relaxed))
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::from_micros(self.sum_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl HistogramSnapshot {
+    /// The number of observed durations.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The sum of all observed durations.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// The mean of the observed durations, if there were any.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(
+            self.sum.as_micros() as u64 / self.count,
+        ))
+    }
+
+    /// The number of durations in each bucket.
+    ///
+    /// Each bucket is paired with its inclusive upper bound, and counts the
+    /// durations above the bound of the bucket before it. The last bucket has
+    /// no upper bound.
+    pub fn buckets(&self) -> &[(Option<Duration>, u64)] {
+        &self.buckets
+    }
+}
+
+// ===== internal =====
+
+/// A handle to the configured `MetricsSink`.
+#[derive(Clone)]
+pub(crate) struct Recorder(Arc<dyn MetricsSink>);
+
+impl Recorder {
+    pub(crate) fn new(sink: Arc<dyn MetricsSink>) -> Recorder {
+        Recorder(sink)
+    }
+}
+
+impl Deref for Recorder {
+    type Target = dyn MetricsSink;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder").finish()
+    }
+}
+
+/// An IO wrapper counting the bytes read and written.
+///
+/// HTTP/1 connections count bytes in their buffers instead, since they
+/// must be able to give back the original IO.
+#[cfg(feature = "http2")]
+pub(crate) struct MeteredIo<T> {
+    io: T,
+    metrics: Option<Recorder>,
+}
+
+#[cfg(feature = "http2")]
+impl<T> MeteredIo<T> {
+    pub(crate) fn new(io: T, metrics: Option<Recorder>) -> MeteredIo<T> {
+        MeteredIo { io, metrics }
+    }
+}
+
+#[cfg(feature = "http2")]
+impl<T: AsyncRead + Unpin> AsyncRead for MeteredIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut self.io).poll_read(cx, buf))?;
+        if let Some(ref metrics) = self.metrics {
+            metrics.bytes_read((buf.filled().len() - before) as u64);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "http2")]
+impl<T: AsyncWrite + Unpin> AsyncWrite for MeteredIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = ready!(Pin::new(&mut self.io).poll_write(cx, buf))?;
+        if let Some(ref metrics) = self.metrics {
+            metrics.bytes_written(n as u64);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let n = ready!(Pin::new(&mut self.io).poll_write_vectored(cx, bufs))?;
+        if let Some(ref metrics) = self.metrics {
+            metrics.bytes_written(n as u64);
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collector_counts_and_histograms() {
+        let collector = Collector::new();
+        collector.bytes_read(10);
+        collector.bytes_read(5);
+        collector.bytes_written(7);
+        collector.pool_checkout(false);
+        collector.pool_checkout(true);
+        collector.stream_reset();
+        collector.bandwidth_estimate(BandwidthEstimate::new(1_000.0, Duration::from_millis(20)));
+        collector.bandwidth_estimate(BandwidthEstimate::new(2_500.0, Duration::from_millis(40)));
+        collector.request_latency(Duration::from_micros(500));
+        collector.request_latency(Duration::from_millis(3));
+        collector.request_latency(Duration::from_secs(60));
+
+        let snapshot = collector.snapshot();
+        assert_eq!(snapshot.bytes_read(), 15);
+        assert_eq!(snapshot.bytes_written(), 7);
+        assert_eq!(snapshot.pool_checkouts(), 2);
+        assert_eq!(snapshot.pool_reuses(), 1);
+        assert_eq!(snapshot.stream_resets(), 1);
+        assert_eq!(
+            snapshot.bandwidth_estimate(),
+            Some(BandwidthEstimate::new(2_500.0, Duration::from_millis(40)))
+        );
+        assert_eq!(Collector::new().snapshot().bandwidth_estimate(), None);
+        assert_eq!(snapshot.handshake_duration().count(), 0);
+        assert_eq!(snapshot.handshake_duration().mean(), None);
+
+        let latency = snapshot.request_latency();
+        assert_eq!(latency.count(), 3);
+        assert_eq!(latency.sum(), Duration::from_micros(60_003_500));
+        let buckets = latency.buckets();
+        assert_eq!(buckets.len(), 14);
+        assert_eq!(buckets[0], (Some(Duration::from_millis(1)), 1));
+        assert_eq!(buckets[2], (Some(Duration::from_millis(5)), 1));
+        assert_eq!(buckets[13], (None, 1));
+    }
+}
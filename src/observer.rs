@@ -0,0 +1,322 @@
+//! Observing the lifecycle of HTTP connections.
+//!
+//! A [`ConnectionObserver`] is told when connections open and close, and
+//! about the progress of every request made on them. It can be set on a
+//! client with [`client::Builder::observer`], on a single client connection
+//! with [`client::conn::Builder::observer`], and on servers with
+//! [`server::conn::Http::observer`]. HTTP/2 client connections are not
+//! observed yet.
+//!
+//! Every observed connection also gets a `connection` [`tracing`] span,
+//! carrying its id and version. The events are logged at `DEBUG` level in
+//! that span, and the observer is called from within it, so anything it
+//! logs itself is attributed to the connection too.
+//!
+//! Requests are [redacted] according to [`ConnectionObserver::redaction`]
+//! before being logged or passed to the observer.
+//!
+//! [redacted]: crate::redact
+//! [`client::Builder::observer`]: crate::client::Builder::observer
+//! [`client::conn::Builder::observer`]: crate::client::conn::Builder::observer
+//! [`server::conn::Http::observer`]: crate::server::conn::Http::observer
+//! [`tracing`]: https://docs.rs/tracing
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "client")]
+//! # fn doc() {
+//! use std::sync::Arc;
+//! use fluxio::observer::{ConnectionInfo, ConnectionObserver};
+//! use fluxio::StatusCode;
+//!
+//! struct LogStatus;
+//!
+//! impl ConnectionObserver for LogStatus {
+//!     fn on_response_headers(&self, conn: &ConnectionInfo, status: StatusCode) {
+//!         println!("connection {}: {}", conn.id(), status);
+//!     }
+//! }
+//!
+//! let mut builder = fluxio::client::conn::Builder::new();
+//! builder.observer(Arc::new(LogStatus));
+//! # }
+//! # fn main() {}
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use http::{Method, StatusCode, Uri, Version};
+use tracing::{debug, debug_span, Span};
+
+use crate::redact::Redaction;
+
+/// Receives the lifecycle events of connections.
+///
+/// Every method has an empty default implementation, so an observer only
+/// needs to implement the ones it is interested in.
+///
+/// The methods are called in the middle of processing connections, and so
+/// should return quickly.
+pub trait ConnectionObserver: Send + Sync {
+    /// Called when a connection is opened.
+    fn on_connect(&self, conn: &ConnectionInfo) {
+        let _ = conn;
+    }
+
+    /// Called once when a connection is closed, with the error that closed
+    /// it, if any.
+    fn on_connection_close(&self, conn: &ConnectionInfo, error: Option<&crate::Error>) {
+        let _ = (conn, error);
+    }
+
+    /// Called when a request starts.
+    ///
+    /// For clients, this is when the request is about to be sent. For
+    /// servers, it is when the request head was received.
+    fn on_request_start(&self, conn: &ConnectionInfo, method: &Method, uri: &Uri) {
+        let _ = (conn, method, uri);
+    }
+
+    /// Called when the head of a response was received by a client, or is
+    /// about to be sent by a server.
+    fn on_response_headers(&self, conn: &ConnectionInfo, status: StatusCode) {
+        let _ = (conn, status);
+    }
+
+    /// Called when the body of a response was fully received by a client,
+    /// or fully sent by a server.
+    fn on_body_complete(&self, conn: &ConnectionInfo) {
+        let _ = conn;
+    }
+
+    /// The redaction to apply to requests before they are logged, or passed
+    /// to this observer.
+    ///
+    /// Default is to not redact anything.
+    fn redaction(&self) -> Option<&Redaction> {
+        None
+    }
+}
+
+/// Information about an observed connection.
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo {
+    id: u64,
+    version: Version,
+    is_server: bool,
+}
+
+impl ConnectionInfo {
+    /// An id of this connection, unique within the process.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The HTTP version of this connection.
+    ///
+    /// Servers detecting the version from the first bytes received report
+    /// HTTP/1.1 when connecting, and HTTP/2 in later events if the client
+    /// turned out to speak it.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Whether this is the server side of the connection.
+    pub fn is_server(&self) -> bool {
+        self.is_server
+    }
+}
+
+// ===== internal =====
+
+/// A handle to the configured `ConnectionObserver`.
+#[derive(Clone)]
+pub(crate) struct ObserverRef(Arc<dyn ConnectionObserver>);
+
+impl ObserverRef {
+    pub(crate) fn new(observer: Arc<dyn ConnectionObserver>) -> ObserverRef {
+        ObserverRef(observer)
+    }
+
+    /// Starts observing a new connection.
+    pub(crate) fn connect(&self, version: Version, is_server: bool) -> Observed {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+        let info = ConnectionInfo {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            version,
+            is_server,
+        };
+        let span = debug_span!("connection", id = info.id, version = ?info.version);
+        span.in_scope(|| {
+            debug!("connection open");
+            self.0.on_connect(&info);
+        });
+        Observed {
+            observer: self.0.clone(),
+            info,
+            span,
+            closed: AtomicBool::new(false),
+        }
+    }
+}
+
+impl fmt::Debug for ObserverRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObserverRef").finish()
+    }
+}
+
+/// An observed connection.
+///
+/// If it wasn't closed explicitly, dropping it reports the connection as
+/// closed without an error.
+pub(crate) struct Observed {
+    observer: Arc<dyn ConnectionObserver>,
+    info: ConnectionInfo,
+    span: Span,
+    closed: AtomicBool,
+}
+
+impl Observed {
+    #[cfg(all(feature = "server", feature = "http1", feature = "http2"))]
+    pub(crate) fn set_version(&mut self, version: Version) {
+        self.info.version = version;
+    }
+
+    pub(crate) fn request_start(&self, method: &Method, uri: &Uri) {
+        let redacted;
+        let uri = match self.observer.redaction() {
+            Some(redaction) => {
+                redacted = redaction.uri(uri);
+                &redacted
+            }
+            None => uri,
+        };
+        self.span.in_scope(|| {
+            debug!(%method, %uri, "request start");
+            self.observer.on_request_start(&self.info, method, uri);
+        });
+    }
+
+    pub(crate) fn response_headers(&self, status: StatusCode) {
+        self.span.in_scope(|| {
+            debug!(%status, "response headers");
+            self.observer.on_response_headers(&self.info, status);
+        });
+    }
+
+    pub(crate) fn body_complete(&self) {
+        self.span.in_scope(|| {
+            debug!("response body complete");
+            self.observer.on_body_complete(&self.info);
+        });
+    }
+
+    /// Reports the connection as closed, unless it already was.
+    pub(crate) fn close(&self, error: Option<&crate::Error>) {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.span.in_scope(|| {
+            match error {
+                Some(err) => debug!(error = %err, "connection closed"),
+                None => debug!("connection closed"),
+            }
+            self.observer.on_connection_close(&self.info, error);
+        });
+    }
+}
+
+impl Drop for Observed {
+    fn drop(&mut self) {
+        self.close(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+
+    impl ConnectionObserver for Events {
+        fn on_connect(&self, conn: &ConnectionInfo) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("connect {:?}", conn.version()));
+        }
+
+        fn on_connection_close(&self, _conn: &ConnectionInfo, error: Option<&crate::Error>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("close {}", error.is_some()));
+        }
+
+        fn on_request_start(&self, _conn: &ConnectionInfo, method: &Method, uri: &Uri) {
+            self.0.lock().unwrap().push(format!("{} {}", method, uri));
+        }
+    }
+
+    struct Redacting(Events, Redaction);
+
+    impl ConnectionObserver for Redacting {
+        fn on_request_start(&self, conn: &ConnectionInfo, method: &Method, uri: &Uri) {
+            self.0.on_request_start(conn, method, uri);
+        }
+
+        fn redaction(&self) -> Option<&Redaction> {
+            Some(&self.1)
+        }
+    }
+
+    #[test]
+    fn close_is_reported_once() {
+        let events = Arc::new(Events::default());
+        let observer = ObserverRef::new(events.clone());
+
+        let first = observer.connect(Version::HTTP_11, false);
+        let second = observer.connect(Version::HTTP_2, true);
+        assert!(second.info.id() > first.info.id());
+        assert!(second.info.is_server());
+
+        first.request_start(&Method::GET, &Uri::from_static("/"));
+        first.close(Some(&crate::Error::new_closed()));
+        drop(first);
+        drop(second);
+
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            [
+                "connect HTTP/1.1",
+                "connect HTTP/2.0",
+                "GET /",
+                "close true",
+                "close false",
+            ]
+        );
+    }
+
+    #[test]
+    fn requests_are_redacted() {
+        let observer = Arc::new(Redacting(
+            Events::default(),
+            Redaction::new().query_param("token"),
+        ));
+        let observed = ObserverRef::new(observer.clone()).connect(Version::HTTP_11, false);
+
+        observed.request_start(&Method::GET, &Uri::from_static("/a?token=secret&b=1"));
+
+        assert_eq!(
+            *(observer.0).0.lock().unwrap(),
+            ["GET /a?token=REDACTED&b=1"]
+        );
+    }
+}
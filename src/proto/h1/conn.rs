@@ -1,6 +1,8 @@
 use std::fmt;
 use std::io;
 use std::marker::PhantomData;
+#[cfg(feature = "server")]
+use std::sync::Arc;
 #[cfg(all(feature = "server", feature = "runtime"))]
 use std::time::Duration;
 
@@ -14,14 +16,29 @@ use tokio::time::Sleep;
 use tracing::{debug, error, trace};
 
 use super::io::Buffered;
+#[cfg(feature = "server")]
+use super::StrictParsing;
 use super::{Decoder, Encode, EncodedBuf, Encoder, Http1Transaction, ParseContext, Wants};
-use crate::body::DecodedLength;
+use crate::body::{DecodedLength, SizeHint};
+#[cfg(feature = "client")]
+use crate::client::validate::OnViolation;
+#[cfg(feature = "server")]
+use crate::common::host::AllowedHosts;
 use crate::common::{task, Pin, Poll, Unpin};
+use crate::ext::{BodyFraming, BodyFramingFn, ChunkExtensions, ContentLengthPolicy};
+#[cfg(feature = "client")]
+use crate::headers::connection_close;
 use crate::headers::connection_keep_alive;
+#[cfg(feature = "client")]
+use crate::metrics::Recorder;
 use crate::proto::{BodyLength, MessageHead};
 
 const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
 
+/// The default limit of 1xx responses to a single request.
+#[cfg(feature = "client")]
+const DEFAULT_MAX_INFORMATIONAL: usize = 10;
+
 /// This handles a connection, which will have been established over an
 /// `AsyncRead + AsyncWrite` (like a socket), and will likely include multiple
 /// `Transaction`s over HTTP.
@@ -62,14 +79,39 @@ where
                 preserve_header_order: false,
                 title_case_headers: false,
                 h09_responses: false,
-                #[cfg(feature = "ffi")]
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: DEFAULT_MAX_INFORMATIONAL,
+                #[cfg(feature = "client")]
+                informational_count: 0,
+                #[cfg(feature = "client")]
+                body_prefetch: 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
                 on_informational: None,
-                #[cfg(feature = "ffi")]
                 raw_headers: false,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: ContentLengthPolicy::default(),
+                body_framing: None,
                 notify_read: false,
                 reading: Reading::Init,
+                chunk_extensions: None,
                 writing: Writing::Init,
                 upgrade: None,
+                #[cfg(feature = "server")]
+                defer_continue: false,
+                #[cfg(feature = "server")]
+                date_header: true,
+                #[cfg(all(feature = "server", feature = "http2"))]
+                h2c_upgrade: false,
+                #[cfg(all(feature = "server", feature = "http2"))]
+                h2c_request: None,
                 // We assume a modern world where the remote speaks HTTP/1.1.
                 // If they tell us otherwise, we'll downgrade in `read_head`.
                 version: Version::HTTP_11,
@@ -91,11 +133,20 @@ where
         self.io.set_max_buf_size(max);
     }
 
+    pub(crate) fn set_min_buf_size(&mut self, min: usize) {
+        self.io.set_min_buf_size(min);
+    }
+
     #[cfg(feature = "client")]
     pub(crate) fn set_read_buf_exact_size(&mut self, sz: usize) {
         self.io.set_read_buf_exact_size(sz);
     }
 
+    #[cfg(feature = "client")]
+    pub(crate) fn set_metrics(&mut self, metrics: Recorder) {
+        self.io.set_metrics(metrics);
+    }
+
     pub(crate) fn set_write_strategy_flatten(&mut self) {
         self.io.set_write_strategy_flatten();
     }
@@ -123,6 +174,67 @@ where
         self.state.h09_responses = true;
     }
 
+    pub(crate) fn set_http1_max_headers(&mut self, max: usize) {
+        self.state.h1_max_headers = Some(max);
+    }
+
+    pub(crate) fn set_http1_max_header_value_size(&mut self, max: usize) {
+        self.state.h1_max_header_value_size = Some(max);
+    }
+
+    pub(crate) fn set_content_length_policy(&mut self, policy: ContentLengthPolicy) {
+        self.state.content_length_policy = policy;
+    }
+
+    pub(crate) fn set_body_framing(&mut self, body_framing: BodyFramingFn) {
+        self.state.body_framing = Some(body_framing);
+    }
+
+    /// How to frame an outgoing body of unknown length.
+    pub(crate) fn body_framing(&self, hint: &SizeHint) -> BodyFraming {
+        match self.state.body_framing {
+            Some(ref body_framing) => body_framing.call(hint),
+            None => BodyFraming::Chunked,
+        }
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn set_max_informational(&mut self, max: usize) {
+        self.state.max_informational = max;
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn set_body_prefetch(&mut self, max: usize) {
+        self.state.body_prefetch = max;
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn set_on_violation(&mut self, on_violation: OnViolation) {
+        self.state.on_violation = Some(on_violation);
+    }
+
+    /// How many bytes of an incoming body to read ahead of it being polled,
+    /// or `0` for the default of a single chunk.
+    #[cfg(feature = "client")]
+    pub(crate) fn body_prefetch(&self) -> usize {
+        self.state.body_prefetch
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn set_preserve_raw_head(&mut self) {
+        self.state.preserve_raw_head = true;
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn set_allowed_hosts(&mut self, allowed_hosts: Arc<AllowedHosts>) {
+        self.state.allowed_hosts = Some(allowed_hosts);
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn set_strict_parsing(&mut self, strict: StrictParsing) {
+        self.state.strict = strict;
+    }
+
     #[cfg(all(feature = "server", feature = "runtime"))]
     pub(crate) fn set_http1_header_read_timeout(&mut self, val: Duration) {
         self.state.h1_header_read_timeout = Some(val);
@@ -133,11 +245,30 @@ where
         self.state.allow_half_close = true;
     }
 
-    #[cfg(feature = "ffi")]
     pub(crate) fn set_raw_headers(&mut self, enabled: bool) {
         self.state.raw_headers = enabled;
     }
 
+    #[cfg(feature = "server")]
+    pub(crate) fn set_defer_continue(&mut self) {
+        self.state.defer_continue = true;
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn disable_date_header(&mut self) {
+        self.state.date_header = false;
+    }
+
+    #[cfg(all(feature = "server", feature = "http2"))]
+    pub(crate) fn set_h2c_upgrade(&mut self) {
+        self.state.h2c_upgrade = true;
+    }
+
+    #[cfg(all(feature = "server", feature = "http2"))]
+    pub(crate) fn take_h2c_request(&mut self) -> Option<Bytes> {
+        self.state.h2c_request.take()
+    }
+
     pub(crate) fn into_inner(self) -> (I, Bytes) {
         self.io.into_inner()
     }
@@ -190,6 +321,11 @@ where
         debug_assert!(self.can_read_head());
         trace!("Conn::read_head");
 
+        #[cfg(all(feature = "server", feature = "http2"))]
+        if self.state.h2c_request.is_some() {
+            return self.poll_read_h2c_preface(cx);
+        }
+
         let msg = match ready!(self.io.parse::<T>(
             cx,
             ParseContext {
@@ -206,16 +342,41 @@ where
                 #[cfg(feature = "ffi")]
                 preserve_header_order: self.state.preserve_header_order,
                 h09_responses: self.state.h09_responses,
-                #[cfg(feature = "ffi")]
+                preserve_raw_head: self.state.preserve_raw_head,
+                #[cfg(feature = "client")]
+                max_informational: self.state.max_informational,
+                #[cfg(feature = "client")]
+                informational_count: &mut self.state.informational_count,
+                #[cfg(feature = "client")]
+                on_violation: self.state.on_violation.as_ref(),
+                #[cfg(feature = "server")]
+                allowed_hosts: self.state.allowed_hosts.as_deref(),
+                #[cfg(feature = "server")]
+                strict: self.state.strict,
+                #[cfg(feature = "client")]
                 on_informational: &mut self.state.on_informational,
-                #[cfg(feature = "ffi")]
                 raw_headers: self.state.raw_headers,
+                h1_max_headers: self.state.h1_max_headers,
+                h1_max_header_value_size: self.state.h1_max_header_value_size,
+                content_length_policy: self.state.content_length_policy,
             }
         )) {
             Ok(msg) => msg,
             Err(e) => return self.on_read_head_error(e),
         };
 
+        #[cfg(all(feature = "server", feature = "http2"))]
+        if self.state.h2c_upgrade {
+            if let Some(request) = T::h2c_upgrade(&msg.head, msg.decode) {
+                debug!("switching protocols to h2c");
+                self.io
+                    .headers_buf()
+                    .extend_from_slice(crate::proto::h2::h2c::SWITCHING_PROTOCOLS);
+                self.state.h2c_request = Some(request);
+                return self.poll_read_h2c_preface(cx);
+            }
+        }
+
         // Note: don't deconstruct `msg` into local variables, it appears
         // the optimizer doesn't remove the extra copies.
 
@@ -224,8 +385,13 @@ where
         // Prevent accepting HTTP/0.9 responses after the initial one, if any.
         self.state.h09_responses = false;
 
+        #[cfg(feature = "client")]
+        {
+            self.state.informational_count = 0;
+        }
+
         // Drop any OnInformational callbacks, we're done there!
-        #[cfg(feature = "ffi")]
+        #[cfg(feature = "client")]
         {
             self.state.on_informational = None;
         }
@@ -258,6 +424,32 @@ where
         Poll::Ready(Some(Ok((msg.head, msg.decode, wants))))
     }
 
+    /// Reads the HTTP/2 preface the client sends after switching protocols
+    /// to h2c, so the connection can be handed to HTTP/2 with the request
+    /// that asked for it.
+    #[cfg(all(feature = "server", feature = "http2"))]
+    fn poll_read_h2c_preface<Z>(
+        &mut self,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<crate::Result<Z>>> {
+        loop {
+            match crate::proto::h2::h2c::preface_len(self.io.read_buf()) {
+                Ok(Some(_)) => return Poll::Ready(Some(Err(crate::Error::new_version_h2()))),
+                Ok(None) => (),
+                Err(()) => {
+                    debug!("h2c upgrade not followed by an HTTP/2 preface");
+                    self.state.close();
+                    return Poll::Ready(Some(Err(crate::Error::new_unexpected_message())));
+                }
+            }
+            if ready!(self.io.poll_read_from_io(cx)).map_err(crate::Error::new_io)? == 0 {
+                trace!("eof before h2c preface");
+                self.state.close();
+                return Poll::Ready(Some(Err(crate::Error::new_incomplete())));
+            }
+        }
+    }
+
     fn on_read_head_error<Z>(&mut self, e: crate::Error) -> Poll<Option<crate::Result<Z>>> {
         // If we are currently waiting on a message, then an empty
         // message should be reported as an error. If not, it is just
@@ -294,11 +486,13 @@ where
             Reading::Body(ref mut decoder) => {
                 match ready!(decoder.decode(cx, &mut self.io)) {
                     Ok(slice) => {
+                        self.state.chunk_extensions = decoder.take_chunk_extensions();
                         let (reading, chunk) = if decoder.is_eof() {
                             debug!("incoming body completed");
                             (
                                 Reading::KeepAlive,
-                                if !slice.is_empty() {
+                                // the last chunk is returned if it has extensions
+                                if !slice.is_empty() || self.state.chunk_extensions.is_some() {
                                     Some(Ok(slice))
                                 } else {
                                     None
@@ -322,8 +516,9 @@ where
                 }
             }
             Reading::Continue(ref decoder) => {
-                // Write the 100 Continue if not already responded...
-                if let Writing::Init = self.state.writing {
+                // Write the 100 Continue if not already responded, or left
+                // to the service...
+                if self.should_send_continue() {
                     trace!("automatically sending 100 Continue");
                     let cont = b"HTTP/1.1 100 Continue\r\n\r\n";
                     self.io.headers_buf().extend_from_slice(cont);
@@ -341,6 +536,11 @@ where
         ret
     }
 
+    /// Takes the extensions of the chunk last returned by `poll_read_body`.
+    pub(crate) fn take_chunk_extensions(&mut self) -> Option<ChunkExtensions> {
+        self.state.chunk_extensions.take()
+    }
+
     pub(crate) fn wants_read_again(&mut self) -> bool {
         let ret = self.state.notify_read;
         self.state.notify_read = false;
@@ -383,7 +583,13 @@ where
             return Poll::Ready(Err(crate::Error::new_unexpected_message()));
         }
 
-        let num_read = ready!(self.force_io_read(cx)).map_err(crate::Error::new_io)?;
+        let num_read = match self.force_io_read(cx) {
+            Poll::Ready(result) => result.map_err(crate::Error::new_io)?,
+            Poll::Pending => {
+                self.io.shrink_read_buf();
+                return Poll::Pending;
+            }
+        };
 
         if num_read == 0 {
             let ret = if self.should_error_on_eof() {
@@ -522,6 +728,50 @@ where
         }
     }
 
+    /// Writes a `103 Early Hints` response, unless the final response was
+    /// started, or the request is HTTP/1.0.
+    #[cfg(feature = "server")]
+    pub(crate) fn write_early_hints(&mut self, headers: &HeaderMap) {
+        if self.state.version != Version::HTTP_11 || !matches!(self.state.writing, Writing::Init) {
+            trace!("not sending early hints");
+            return;
+        }
+        super::role::encode_early_hints(
+            headers,
+            self.state.title_case_headers,
+            self.io.headers_buf(),
+        );
+    }
+
+    /// Writes a `100 Continue` response, once the service asked for it.
+    ///
+    /// This is also written if the body was read already, since the client
+    /// may still be waiting for it.
+    #[cfg(feature = "server")]
+    pub(crate) fn write_continue(&mut self) {
+        match self.state.reading {
+            Reading::Continue(ref decoder) => {
+                self.state.reading = Reading::Body(decoder.clone());
+            }
+            Reading::Body(..) => (),
+            _ => return,
+        }
+        if let Writing::Init = self.state.writing {
+            trace!("sending 100 Continue");
+            self.io
+                .headers_buf()
+                .extend_from_slice(b"HTTP/1.1 100 Continue\r\n\r\n");
+        }
+    }
+
+    fn should_send_continue(&self) -> bool {
+        #[cfg(feature = "server")]
+        if self.state.defer_continue {
+            return false;
+        }
+        matches!(self.state.writing, Writing::Init)
+    }
+
     pub(crate) fn write_full_msg(&mut self, head: MessageHead<T::Outgoing>, body: B) {
         if let Some(encoder) =
             self.encode_head(head, Some(BodyLength::Known(body.remaining() as u64)))
@@ -553,27 +803,53 @@ where
 
         self.enforce_version(&mut head);
 
+        // Let the server know the connection closes after the response.
+        #[cfg(feature = "client")]
+        if T::is_client()
+            && !self.state.wants_keep_alive()
+            && !head.headers.get(CONNECTION).is_some_and(connection_close)
+        {
+            head.headers
+                .insert(CONNECTION, HeaderValue::from_static("close"));
+        }
+
         let buf = self.io.headers_buf();
+        #[cfg(feature = "ffi")]
+        let orig_len = buf.len();
         match super::role::encode_headers::<T>(
             Encode {
                 head: &mut head,
                 body,
                 #[cfg(feature = "server")]
                 keep_alive: self.state.wants_keep_alive(),
+                #[cfg(feature = "server")]
+                date_header: self.state.date_header,
                 req_method: &mut self.state.method,
                 title_case_headers: self.state.title_case_headers,
             },
             buf,
         ) {
             Ok(encoder) => {
+                #[cfg(feature = "ffi")]
+                if let Some(on_head) = head.extensions.remove::<crate::ext::OnRequestHead>() {
+                    if !on_head.call(&buf[orig_len..]) {
+                        debug!("request head rejected by callback");
+                        buf.truncate(orig_len);
+                        self.state.error = Some(crate::Error::new_user_aborted_by_callback());
+                        // No response can follow the aborted request.
+                        self.state.close();
+                        return None;
+                    }
+                }
+
                 debug_assert!(self.state.cached_headers.is_none());
                 debug_assert!(head.headers.is_empty());
                 self.state.cached_headers = Some(head.headers);
 
-                #[cfg(feature = "ffi")]
+                #[cfg(feature = "client")]
                 {
                     self.state.on_informational =
-                        head.extensions.remove::<crate::ffi::OnInformational>();
+                        head.extensions.remove::<crate::ext::OnInformational>();
                 }
 
                 Some(encoder)
@@ -765,7 +1041,6 @@ where
         self.state.close_write();
     }
 
-    #[cfg(feature = "server")]
     pub(crate) fn disable_keep_alive(&mut self) {
         if self.state.is_idle() {
             trace!("disable_keep_alive; closing idle connection");
@@ -828,22 +1103,65 @@ struct State {
     preserve_header_order: bool,
     title_case_headers: bool,
     h09_responses: bool,
+    /// If set, the raw bytes of each parsed request head are kept so that
+    /// they can be re-emitted verbatim when proxied.
+    preserve_raw_head: bool,
+    /// The most 1xx informational responses accepted before the final
+    /// response to a request.
+    #[cfg(feature = "client")]
+    max_informational: usize,
+    /// The 1xx informational responses received for the current request.
+    #[cfg(feature = "client")]
+    informational_count: usize,
+    /// The bytes of a response body to read before it is polled.
+    #[cfg(feature = "client")]
+    body_prefetch: usize,
+    /// If set, told about received responses breaking a MUST of the RFCs.
+    #[cfg(feature = "client")]
+    on_violation: Option<OnViolation>,
+    #[cfg(feature = "server")]
+    allowed_hosts: Option<Arc<AllowedHosts>>,
+    /// Which ambiguous request heads are rejected.
+    #[cfg(feature = "server")]
+    strict: StrictParsing,
     /// If set, called with each 1xx informational response received for
     /// the current request. MUST be unset after a non-1xx response is
     /// received.
-    #[cfg(feature = "ffi")]
-    on_informational: Option<crate::ffi::OnInformational>,
-    #[cfg(feature = "ffi")]
+    #[cfg(feature = "client")]
+    on_informational: Option<crate::ext::OnInformational>,
     raw_headers: bool,
+    /// The most headers accepted in a message head, if not the default.
+    h1_max_headers: Option<usize>,
+    /// The largest header value accepted, in bytes.
+    h1_max_header_value_size: Option<usize>,
+    /// How messages with multiple `Content-Length` values are read.
+    content_length_policy: ContentLengthPolicy,
+    /// Chooses how outgoing bodies of unknown length are framed.
+    body_framing: Option<BodyFramingFn>,
     /// Set to true when the Dispatcher should poll read operations
     /// again. See the `maybe_notify` method for more.
     notify_read: bool,
     /// State of allowed reads
     reading: Reading,
+    /// The extensions of the chunk last returned by `poll_read_body`.
+    chunk_extensions: Option<ChunkExtensions>,
     /// State of allowed writes
     writing: Writing,
     /// An expected pending HTTP upgrade.
     upgrade: Option<crate::upgrade::Pending>,
+    /// Whether `100 Continue` is only sent when the service asks for it.
+    #[cfg(feature = "server")]
+    defer_continue: bool,
+    /// Whether responses without a `Date` header get one added.
+    #[cfg(feature = "server")]
+    date_header: bool,
+    /// Whether requests to upgrade to h2c are accepted.
+    #[cfg(all(feature = "server", feature = "http2"))]
+    h2c_upgrade: bool,
+    /// Once switched to h2c, the HTTP/2 frame replaying the request that
+    /// asked for it.
+    #[cfg(all(feature = "server", feature = "http2"))]
+    h2c_request: Option<Bytes>,
     /// Either HTTP/1.0 or 1.1 connection
     version: Version,
 }
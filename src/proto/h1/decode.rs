@@ -1,18 +1,24 @@
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+use std::mem;
 use std::usize;
 
 use bytes::Bytes;
 use tracing::{debug, trace};
 
 use crate::common::{task, Poll};
+use crate::ext::ChunkExtensions;
 
 use super::io::MemRead;
 use super::DecodedLength;
 
 use self::Kind::{Chunked, Eof, Length};
 
+/// The maximum size of the extensions of a chunk, since they are kept in
+/// memory until its data is read.
+const CHUNK_EXTENSIONS_LIMIT: usize = 16 * 1024;
+
 /// Decoders to handle different Transfer-Encodings.
 ///
 /// If a message body does not include a Transfer-Encoding, it *should*
@@ -20,6 +26,8 @@ use self::Kind::{Chunked, Eof, Length};
 #[derive(Clone, PartialEq)]
 pub(crate) struct Decoder {
     kind: Kind,
+    /// The extensions of the chunk being read, starting with the `;`.
+    extensions: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -69,18 +77,21 @@ impl Decoder {
     pub(crate) fn length(x: u64) -> Decoder {
         Decoder {
             kind: Kind::Length(x),
+            extensions: Vec::new(),
         }
     }
 
     pub(crate) fn chunked() -> Decoder {
         Decoder {
             kind: Kind::Chunked(ChunkedState::Size, 0),
+            extensions: Vec::new(),
         }
     }
 
     pub(crate) fn eof() -> Decoder {
         Decoder {
             kind: Kind::Eof(false),
+            extensions: Vec::new(),
         }
     }
 
@@ -98,6 +109,15 @@ impl Decoder {
         matches!(self.kind, Length(0) | Chunked(ChunkedState::End, _) | Eof(true))
     }
 
+    /// Takes the extensions of the chunk last decoded, if it had valid ones.
+    pub(crate) fn take_chunk_extensions(&mut self) -> Option<ChunkExtensions> {
+        if self.extensions.is_empty() {
+            return None;
+        }
+        let extensions = mem::take(&mut self.extensions);
+        ChunkExtensions::try_from(extensions).ok()
+    }
+
     pub(crate) fn decode<R: MemRead>(
         &mut self,
         cx: &mut task::Context<'_>,
@@ -129,7 +149,7 @@ impl Decoder {
                 loop {
                     let mut buf = None;
                     // advances the chunked state
-                    *state = ready!(state.step(cx, body, size, &mut buf))?;
+                    *state = ready!(state.step(cx, body, size, &mut self.extensions, &mut buf))?;
                     if *state == ChunkedState::End {
                         trace!("end of chunked");
                         return Poll::Ready(Ok(Bytes::new()));
@@ -185,13 +205,14 @@ impl ChunkedState {
         cx: &mut task::Context<'_>,
         body: &mut R,
         size: &mut u64,
+        extensions: &mut Vec<u8>,
         buf: &mut Option<Bytes>,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         use self::ChunkedState::*;
         match *self {
-            Size => ChunkedState::read_size(cx, body, size),
-            SizeLws => ChunkedState::read_size_lws(cx, body),
-            Extension => ChunkedState::read_extension(cx, body),
+            Size => ChunkedState::read_size(cx, body, size, extensions),
+            SizeLws => ChunkedState::read_size_lws(cx, body, extensions),
+            Extension => ChunkedState::read_extension(cx, body, extensions),
             SizeLf => ChunkedState::read_size_lf(cx, body, *size),
             Body => ChunkedState::read_body(cx, body, size, buf),
             BodyCr => ChunkedState::read_body_cr(cx, body),
@@ -207,6 +228,7 @@ impl ChunkedState {
         cx: &mut task::Context<'_>,
         rdr: &mut R,
         size: &mut u64,
+        extensions: &mut Vec<u8>,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         trace!("Read chunk hex size");
 
@@ -237,7 +259,11 @@ impl ChunkedState {
                 *size = or_overflow!(size.checked_add((b + 10 - b'A') as u64));
             }
             b'\t' | b' ' => return Poll::Ready(Ok(ChunkedState::SizeLws)),
-            b';' => return Poll::Ready(Ok(ChunkedState::Extension)),
+            b';' => {
+                extensions.clear();
+                extensions.push(b';');
+                return Poll::Ready(Ok(ChunkedState::Extension));
+            }
             b'\r' => return Poll::Ready(Ok(ChunkedState::SizeLf)),
             _ => {
                 return Poll::Ready(Err(io::Error::new(
@@ -251,12 +277,17 @@ impl ChunkedState {
     fn read_size_lws<R: MemRead>(
         cx: &mut task::Context<'_>,
         rdr: &mut R,
+        extensions: &mut Vec<u8>,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         trace!("read_size_lws");
         match byte!(rdr, cx) {
             // LWS can follow the chunk size, but no more digits can come
             b'\t' | b' ' => Poll::Ready(Ok(ChunkedState::SizeLws)),
-            b';' => Poll::Ready(Ok(ChunkedState::Extension)),
+            b';' => {
+                extensions.clear();
+                extensions.push(b';');
+                Poll::Ready(Ok(ChunkedState::Extension))
+            }
             b'\r' => Poll::Ready(Ok(ChunkedState::SizeLf)),
             _ => Poll::Ready(Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -267,10 +298,11 @@ impl ChunkedState {
     fn read_extension<R: MemRead>(
         cx: &mut task::Context<'_>,
         rdr: &mut R,
+        extensions: &mut Vec<u8>,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         trace!("read_extension");
-        // We don't care about extensions really at all. Just ignore them.
-        // They "end" at the next CRLF.
+        // Extensions are kept for the body to expose them. They "end" at the
+        // next CRLF.
         //
         // However, some implementations may not check for the CR, so to save
         // them from themselves, we reject extensions containing plain LF as
@@ -281,7 +313,14 @@ impl ChunkedState {
                 io::ErrorKind::InvalidData,
                 "invalid chunk extension contains newline",
             ))),
-            _ => Poll::Ready(Ok(ChunkedState::Extension)), // no supported extensions
+            _ if extensions.len() >= CHUNK_EXTENSIONS_LIMIT => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "chunk extensions over limit",
+            ))),
+            b => {
+                extensions.push(b);
+                Poll::Ready(Ok(ChunkedState::Extension))
+            }
         }
     }
     fn read_size_lf<R: MemRead>(
@@ -481,7 +520,7 @@ mod tests {
             let mut size = 0;
             loop {
                 let result =
-                    futures_util::future::poll_fn(|cx| state.step(cx, rdr, &mut size, &mut None))
+                    futures_util::future::poll_fn(|cx| state.step(cx, rdr, &mut size, &mut Vec::new(), &mut None))
                         .await;
                 let desc = format!("read_size failed for {:?}", s);
                 state = result.expect(desc.as_str());
@@ -498,7 +537,7 @@ mod tests {
             let mut size = 0;
             loop {
                 let result =
-                    futures_util::future::poll_fn(|cx| state.step(cx, rdr, &mut size, &mut None))
+                    futures_util::future::poll_fn(|cx| state.step(cx, rdr, &mut size, &mut Vec::new(), &mut None))
                         .await;
                 state = match result {
                     Ok(s) => s,
@@ -615,6 +654,37 @@ mod tests {
         assert_eq!(0, buf.len());
     }
 
+    #[tokio::test]
+    async fn test_read_chunked_extensions() {
+        let mut mock_buf = &b"3 ;a=1;b=\"x\"\r\nfoo\r\n3\r\nbar\r\n0;ieof\r\n\r\n"[..];
+        let mut decoder = Decoder::chunked();
+
+        let buf = decoder.decode_fut(&mut mock_buf).await.unwrap();
+        assert_eq!(buf, "foo");
+        let extensions = decoder.take_chunk_extensions().unwrap();
+        assert_eq!(extensions.as_bytes(), b";a=1;b=\"x\"");
+        assert_eq!(decoder.take_chunk_extensions(), None);
+
+        let buf = decoder.decode_fut(&mut mock_buf).await.unwrap();
+        assert_eq!(buf, "bar");
+        assert_eq!(decoder.take_chunk_extensions(), None);
+
+        let buf = decoder.decode_fut(&mut mock_buf).await.unwrap();
+        assert!(buf.is_empty() && decoder.is_eof());
+        let extensions = decoder.take_chunk_extensions().unwrap();
+        assert_eq!(extensions.as_bytes(), b";ieof");
+    }
+
+    #[tokio::test]
+    async fn test_read_chunked_extensions_over_limit() {
+        let mut chunk = b"1;".to_vec();
+        chunk.resize(CHUNK_EXTENSIONS_LIMIT + 10, b'a');
+        chunk.extend_from_slice(b"\r\nx\r\n");
+        let mut decoder = Decoder::chunked();
+        let e = decoder.decode_fut(&mut &chunk[..]).await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
     // perform an async read using a custom buffer size and causing a blocking
     // read at the specified byte
     async fn read_async(mut decoder: Decoder, content: &[u8], block_at: usize) -> String {
@@ -1,24 +1,50 @@
+use std::collections::VecDeque;
 use std::error::Error as StdError;
+#[cfg(all(feature = "client", feature = "runtime"))]
+use std::time::Duration;
 
 use bytes::{Buf, Bytes};
 use http::Request;
 use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(all(feature = "client", feature = "runtime"))]
+use tokio::time::Sleep;
 use tracing::{debug, trace};
 
 use super::{Http1Transaction, Wants};
 use crate::body::{Body, DecodedLength, HttpBody};
 use crate::common::{task, Future, Pin, Poll, Unpin};
-use crate::proto::{
-    BodyLength, Conn, Dispatched, MessageHead, RequestHead,
-};
+use crate::ext::BodyFraming;
+use crate::observer::Observed;
+use crate::proto::{BodyLength, Conn, Dispatched, MessageHead, RequestHead};
 use crate::upgrade::OnUpgrade;
 
-pub(crate) struct Dispatcher<D, Bs: HttpBody, I, T> {
+pub(crate) struct Dispatcher<D, Bs: HttpBody, I, T: Http1Transaction> {
     conn: Conn<I, Bs::Data, T>,
     dispatch: D,
     body_tx: Option<crate::body::Sender>,
     body_rx: Pin<Box<Option<Bs>>>,
+    /// The start of `body_rx`, buffered to frame it with a `Content-Length`
+    /// if it ends soon enough.
+    buffering: Option<Box<Buffering<MessageHead<T::Outgoing>, Bs::Data>>>,
     is_closing: bool,
+    #[cfg(all(feature = "client", feature = "runtime"))]
+    pacer: Option<super::Pacer>,
+    #[cfg(all(feature = "client", feature = "runtime"))]
+    idle_timeout: Option<Duration>,
+    /// Running while the connection is idle, if there is an idle timeout.
+    #[cfg(all(feature = "client", feature = "runtime"))]
+    idle_sleep: Option<Pin<Box<Sleep>>>,
+}
+
+/// A body buffered before its head is written, as chosen with
+/// `BodyFraming::Buffer`.
+struct Buffering<H, B> {
+    /// The head to write once the framing is known, or `None` once written.
+    head: Option<H>,
+    chunks: VecDeque<B>,
+    len: usize,
+    max: usize,
+    ended: bool,
 }
 
 pub(crate) trait Dispatch {
@@ -33,14 +59,48 @@ pub(crate) trait Dispatch {
     fn recv_msg(&mut self, msg: crate::Result<(Self::RecvItem, Body)>) -> crate::Result<()>;
     fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), ()>>;
     fn should_poll(&self) -> bool;
+    fn observed(&self) -> Option<&Observed>;
+    /// Returns the head of a response to write right away, instead of
+    /// receiving the message. Only asked about messages without a body.
+    fn respond_early(&mut self, _head: &Self::RecvItem) -> Option<Self::PollItem> {
+        None
+    }
+    /// Returns the headers of the next `103 Early Hints` response to write
+    /// before the message polled with `poll_msg`.
+    fn poll_early_hints(&mut self, _cx: &mut task::Context<'_>) -> Option<http::HeaderMap> {
+        None
+    }
+    /// Handles a message expecting `100 Continue` before sending its body,
+    /// returning the head of a response to reject it with instead of
+    /// receiving it.
+    fn expect_continue(&mut self, _head: &mut Self::RecvItem) -> Option<Self::PollItem> {
+        None
+    }
+    /// Returns whether to write `100 Continue` for the message being
+    /// handled, as deferred by `expect_continue`.
+    fn poll_continue(&mut self, _cx: &mut task::Context<'_>) -> bool {
+        false
+    }
 }
 
 cfg_server! {
+    use crate::ext::{ConnectInfo, Continue, Expect100};
+    use crate::server::conn::{CorsPolicy, ExpectContinue, HealthCheck, SendEarlyHints};
+    use crate::server::early_hints::EarlyHints;
     use crate::service::HttpService;
 
     pub(crate) struct Server<S: HttpService<B>, B> {
         in_flight: Pin<Box<Option<S::Future>>>,
         pub(crate) service: S,
+        observed: Option<Observed>,
+        health_check: Option<HealthCheck>,
+        cors: Option<CorsPolicy>,
+        send_early_hints: bool,
+        early_hints: Option<EarlyHints>,
+        expect_continue: ExpectContinue,
+        send_continue: Option<Continue>,
+        max_body_size: Option<u64>,
+        connect_info: Option<ConnectInfo>,
     }
 }
 
@@ -51,6 +111,7 @@ cfg_client! {
             #[pin]
             rx: ClientRx<B>,
             rx_closed: bool,
+            observed: Option<Observed>,
         }
     }
 
@@ -76,10 +137,29 @@ where
             dispatch,
             body_tx: None,
             body_rx: Box::pin(None),
+            buffering: None,
             is_closing: false,
+            #[cfg(all(feature = "client", feature = "runtime"))]
+            pacer: None,
+            #[cfg(all(feature = "client", feature = "runtime"))]
+            idle_timeout: None,
+            #[cfg(all(feature = "client", feature = "runtime"))]
+            idle_sleep: None,
         }
     }
 
+    /// Paces the body chunks written to this connection to a rate.
+    #[cfg(all(feature = "client", feature = "runtime"))]
+    pub(crate) fn set_body_rate_limit(&mut self, bytes_per_sec: u64) {
+        self.pacer = Some(super::Pacer::new(bytes_per_sec));
+    }
+
+    /// Closes this connection once no request was in flight for `timeout`.
+    #[cfg(all(feature = "client", feature = "runtime"))]
+    pub(crate) fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.idle_timeout = Some(timeout);
+    }
+
     #[cfg(feature = "server")]
     pub(crate) fn disable_keep_alive(&mut self) {
         self.conn.disable_keep_alive();
@@ -88,6 +168,13 @@ where
         }
     }
 
+    /// Takes the HTTP/2 frame replaying the request that switched this
+    /// connection to h2c.
+    #[cfg(all(feature = "server", feature = "http2"))]
+    pub(crate) fn take_h2c_request(&mut self) -> Option<Bytes> {
+        self.conn.take_h2c_request()
+    }
+
     pub(crate) fn into_inner(self) -> (I, Bytes, D) {
         let (io, buf) = self.conn.into_inner();
         (io, buf, self.dispatch)
@@ -117,7 +204,11 @@ where
         cx: &mut task::Context<'_>,
         should_shutdown: bool,
     ) -> Poll<crate::Result<Dispatched>> {
-        Poll::Ready(ready!(self.poll_inner(cx, should_shutdown)).or_else(|e| {
+        let result = ready!(self.poll_inner(cx, should_shutdown));
+        if let Some(observed) = self.dispatch.observed() {
+            observed.close(result.as_ref().err());
+        }
+        Poll::Ready(result.or_else(|e| {
             // An error means we're shutting down either way.
             // We just try to give the error to the user,
             // and close the connection with an Ok. If we
@@ -136,6 +227,9 @@ where
 
         ready!(self.poll_loop(cx))?;
 
+        #[cfg(all(feature = "client", feature = "runtime"))]
+        self.poll_idle_timeout(cx);
+
         if self.is_done() {
             if let Some(pending) = self.conn.pending_upgrade() {
                 self.conn.take_error()?;
@@ -180,6 +274,30 @@ where
         task::yield_now(cx).map(|never| match never {})
     }
 
+    #[cfg(all(feature = "client", feature = "runtime"))]
+    fn poll_idle_timeout(&mut self, cx: &mut task::Context<'_>) {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        let is_idle = self.conn.can_write_head()
+            && self.dispatch.should_poll()
+            && self.body_tx.is_none()
+            && self.body_rx.is_none();
+        if !is_idle {
+            self.idle_sleep = None;
+            return;
+        }
+
+        let sleep = self
+            .idle_sleep
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        if sleep.as_mut().poll(cx).is_ready() {
+            debug!("connection idle timeout elapsed, closing");
+            self.close();
+        }
+    }
+
     fn poll_read(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
         loop {
             if self.is_closing {
@@ -203,19 +321,26 @@ where
                         }
                     }
                     match self.conn.poll_read_body(cx) {
-                        Poll::Ready(Some(Ok(chunk))) => match body.try_send_data(chunk) {
-                            Ok(()) => {
-                                self.body_tx = Some(body);
-                            }
-                            Err(_canceled) => {
-                                if self.conn.can_read_body() {
-                                    trace!("body receiver dropped before eof, closing");
-                                    self.conn.close_read();
+                        Poll::Ready(Some(Ok(chunk))) => {
+                            match body.try_send_chunk(chunk, self.conn.take_chunk_extensions()) {
+                                Ok(()) => {
+                                    self.body_tx = Some(body);
+                                    // the last chunk ends the body
+                                    if !self.conn.can_read_body() {
+                                        Self::body_complete(&self.dispatch, true);
+                                    }
+                                }
+                                Err(_canceled) => {
+                                    if self.conn.can_read_body() {
+                                        trace!("body receiver dropped before eof, closing");
+                                        self.conn.close_read();
+                                    }
                                 }
                             }
-                        },
+                        }
                         Poll::Ready(None) => {
                             // just drop, the body will close automatically
+                            Self::body_complete(&self.dispatch, true);
                         }
                         Poll::Pending => {
                             self.body_tx = Some(body);
@@ -247,8 +372,30 @@ where
         // dispatch is ready for a message, try to read one
         match ready!(self.conn.poll_read_head(cx)) {
             Some(Ok((mut head, body_len, wants))) => {
+                let is_empty = body_len == DecodedLength::ZERO;
+                if is_empty && !wants.contains(Wants::UPGRADE) {
+                    if let Some(res) = self.dispatch.respond_early(&head) {
+                        self.conn.write_head(res, None);
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+                if wants.contains(Wants::EXPECT) {
+                    if let Some(res) = self.dispatch.expect_continue(&mut head) {
+                        // The client may send the body anyways, so it
+                        // can't be told apart from a next message.
+                        self.conn.close_read();
+                        self.conn.write_head(res, None);
+                        return Poll::Ready(Ok(()));
+                    }
+                }
                 let body = match body_len {
                     DecodedLength::ZERO => Body::empty(),
+                    #[cfg(feature = "client")]
+                    other if self.conn.body_prefetch() > 0 => {
+                        let (tx, rx) = Body::new_prefetch_channel(other, self.conn.body_prefetch());
+                        self.body_tx = Some(tx);
+                        rx
+                    }
                     other => {
                         let (tx, rx) = Body::new_channel(other, wants.contains(Wants::EXPECT));
                         self.body_tx = Some(tx);
@@ -262,6 +409,9 @@ where
                     head.extensions.insert(upgrade);
                 }
                 self.dispatch.recv_msg(Ok((head, body)))?;
+                if is_empty {
+                    Self::body_complete(&self.dispatch, true);
+                }
                 Poll::Ready(Ok(()))
             }
             Some(Err(err)) => {
@@ -294,6 +444,14 @@ where
                 && self.conn.can_write_head()
                 && self.dispatch.should_poll()
             {
+                #[cfg(feature = "server")]
+                while let Some(headers) = self.dispatch.poll_early_hints(cx) {
+                    self.conn.write_early_hints(&headers);
+                }
+                #[cfg(feature = "server")]
+                if self.dispatch.poll_continue(cx) {
+                    self.conn.write_continue();
+                }
                 if let Some(msg) = ready!(Pin::new(&mut self.dispatch).poll_msg(cx)) {
                     let (head, mut body) = msg.map_err(crate::Error::new_user_service)?;
 
@@ -302,7 +460,12 @@ where
                     // If so, we can skip a bit of bookkeeping that streaming
                     // bodies need to do.
                     if let Some(full) = crate::body::take_full_data(&mut body) {
+                        #[cfg(all(feature = "client", feature = "runtime"))]
+                        if let Some(ref mut pacer) = self.pacer {
+                            pacer.consume(full.remaining());
+                        }
                         self.conn.write_full_msg(head, full);
+                        Self::body_complete(&self.dispatch, false);
                         return Poll::Ready(Ok(()));
                     }
 
@@ -310,21 +473,41 @@ where
                         self.body_rx.set(None);
                         None
                     } else {
-                        let btype = body
-                            .size_hint()
-                            .exact()
-                            .map(BodyLength::Known)
-                            .or_else(|| Some(BodyLength::Unknown));
+                        let hint = body.size_hint();
+                        let btype = match hint.exact() {
+                            Some(len) => BodyLength::Known(len),
+                            None => match self.conn.body_framing(&hint) {
+                                BodyFraming::Chunked => BodyLength::Unknown,
+                                BodyFraming::CloseDelimited => BodyLength::CloseDelimited,
+                                BodyFraming::Buffer(max) => {
+                                    self.body_rx.set(Some(body));
+                                    self.buffering = Some(Box::new(Buffering {
+                                        head: Some(head),
+                                        chunks: VecDeque::new(),
+                                        len: 0,
+                                        max,
+                                        ended: false,
+                                    }));
+                                    continue;
+                                }
+                            },
+                        };
                         self.body_rx.set(Some(body));
-                        btype
+                        Some(btype)
                     };
+                    let is_empty = body_type.is_none();
                     self.conn.write_head(head, body_type);
+                    if is_empty {
+                        Self::body_complete(&self.dispatch, false);
+                    }
                 } else {
                     self.close();
                     return Poll::Ready(Ok(()));
                 }
             } else if !self.conn.can_buffer_body() {
                 ready!(self.poll_flush(cx))?;
+            } else if self.buffering.is_some() {
+                ready!(self.poll_buffering(cx))?;
             } else {
                 // A new scope is needed :(
                 if let (Some(mut body), clear_body) =
@@ -337,15 +520,25 @@ where
                             body.is_end_stream(),
                         );
                         *clear_body = true;
+                        Self::body_complete(&self.dispatch, false);
                         continue;
                     }
 
+                    #[cfg(all(feature = "client", feature = "runtime"))]
+                    if let Some(ref mut pacer) = self.pacer {
+                        ready!(pacer.poll_ready(cx));
+                    }
+
                     let item = ready!(body.as_mut().poll_data(cx));
                     if let Some(item) = item {
                         let chunk = item.map_err(|e| {
                             *clear_body = true;
                             crate::Error::new_user_body(e)
                         })?;
+                        #[cfg(all(feature = "client", feature = "runtime"))]
+                        if let Some(ref mut pacer) = self.pacer {
+                            pacer.consume(chunk.remaining());
+                        }
                         let eos = body.is_end_stream();
                         if eos {
                             *clear_body = true;
@@ -355,6 +548,7 @@ where
                             } else {
                                 self.conn.write_body_and_end(chunk);
                             }
+                            Self::body_complete(&self.dispatch, false);
                         } else {
                             if chunk.remaining() == 0 {
                                 trace!("discarding empty chunk");
@@ -365,6 +559,7 @@ where
                     } else {
                         *clear_body = true;
                         self.conn.end_body()?;
+                        Self::body_complete(&self.dispatch, false);
                     }
                 } else {
                     return Poll::Pending;
@@ -373,6 +568,65 @@ where
         }
     }
 
+    /// Buffers the start of a body until its framing is known, then writes
+    /// its head and what was buffered.
+    fn poll_buffering(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
+        let buffering = self.buffering.as_mut().expect("buffering a body");
+        if let Some(head) = buffering.head.take() {
+            let mut body = self.body_rx.as_mut().as_pin_mut().expect("buffered body");
+            let item = match body.as_mut().poll_data(cx) {
+                Poll::Ready(item) => item,
+                Poll::Pending => {
+                    buffering.head = Some(head);
+                    return Poll::Pending;
+                }
+            };
+            match item {
+                Some(Ok(chunk)) => {
+                    if chunk.remaining() != 0 {
+                        buffering.len += chunk.remaining();
+                        buffering.chunks.push_back(chunk);
+                    }
+                    buffering.ended = body.is_end_stream();
+                }
+                Some(Err(err)) => {
+                    self.buffering = None;
+                    self.body_rx.set(None);
+                    return Poll::Ready(Err(crate::Error::new_user_body(err)));
+                }
+                None => buffering.ended = true,
+            }
+
+            if buffering.ended {
+                trace!("buffered full body of {} bytes", buffering.len);
+                let len = buffering.len as u64;
+                self.conn.write_head(head, Some(BodyLength::Known(len)));
+            } else if buffering.len > buffering.max {
+                trace!("buffered body over {} bytes, streaming it", buffering.max);
+                self.conn.write_head(head, Some(BodyLength::Unknown));
+            } else {
+                buffering.head = Some(head);
+            }
+            return Poll::Ready(Ok(()));
+        }
+
+        match buffering.chunks.pop_front() {
+            Some(chunk) if self.conn.can_write_body() => self.conn.write_body(chunk),
+            _ => {
+                let ended = buffering.ended;
+                self.buffering = None;
+                if ended {
+                    if self.conn.can_write_body() {
+                        self.conn.end_body()?;
+                    }
+                    self.body_rx.set(None);
+                    Self::body_complete(&self.dispatch, false);
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
     fn poll_flush(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<()>> {
         self.conn.poll_flush(cx).map_err(|err| {
             debug!("error writing: {}", err);
@@ -380,6 +634,17 @@ where
         })
     }
 
+    /// Reports a response body as complete, if it was read by a client or
+    /// written by a server.
+    fn body_complete(dispatch: &D, incoming: bool) {
+        if incoming != T::is_client() {
+            return;
+        }
+        if let Some(observed) = dispatch.observed() {
+            observed.body_complete();
+        }
+    }
+
     fn close(&mut self) {
         self.is_closing = true;
         self.conn.close_read();
@@ -460,9 +725,51 @@ cfg_server! {
             Server {
                 in_flight: Box::pin(None),
                 service,
+                observed: None,
+                health_check: None,
+                cors: None,
+                send_early_hints: false,
+                early_hints: None,
+                expect_continue: ExpectContinue::default(),
+                send_continue: None,
+                max_body_size: None,
+                connect_info: None,
             }
         }
 
+        pub(crate) fn set_observed(&mut self, observed: Observed) {
+            self.observed = Some(observed);
+        }
+
+        pub(crate) fn set_health_check(&mut self, check: HealthCheck) {
+            self.health_check = Some(check);
+        }
+
+        pub(crate) fn set_cors(&mut self, cors: CorsPolicy) {
+            self.cors = Some(cors);
+        }
+
+        pub(crate) fn set_early_hints(&mut self) {
+            self.send_early_hints = true;
+        }
+
+        pub(crate) fn set_expect_continue(&mut self, policy: ExpectContinue) {
+            self.expect_continue = policy;
+        }
+
+        pub(crate) fn set_max_body_size(&mut self, max: u64) {
+            self.max_body_size = Some(max);
+        }
+
+        pub(crate) fn set_connect_info(&mut self, info: ConnectInfo) {
+            self.connect_info = Some(info);
+        }
+
+        #[cfg(feature = "http2")]
+        pub(crate) fn take_observed(&mut self) -> Option<Observed> {
+            self.observed.take()
+        }
+
         pub(crate) fn into_service(self) -> S {
             self.service
         }
@@ -489,6 +796,9 @@ cfg_server! {
             let mut this = self.as_mut();
             let ret = if let Some(ref mut fut) = this.in_flight.as_mut().as_pin_mut() {
                 let resp = ready!(fut.as_mut().poll(cx)?);
+                if let Some(ref observed) = this.observed {
+                    observed.response_headers(resp.status());
+                }
                 let (parts, body) = resp.into_parts();
                 let head = MessageHead {
                     version: parts.version,
@@ -503,17 +813,34 @@ cfg_server! {
 
             // Since in_flight finished, remove it
             this.in_flight.set(None);
+            // Early hints can't follow the final response.
+            this.early_hints = None;
+            this.send_continue = None;
             ret
         }
 
         fn recv_msg(&mut self, msg: crate::Result<(Self::RecvItem, Body)>) -> crate::Result<()> {
-            let (msg, body) = msg?;
+            let (msg, mut body) = msg?;
+            if let Some(ref observed) = self.observed {
+                observed.request_start(&msg.subject.0, &msg.subject.1);
+            }
+            if let Some(max) = self.max_body_size {
+                body = body.limit(max);
+            }
             let mut req = Request::new(body);
             *req.method_mut() = msg.subject.0;
             *req.uri_mut() = msg.subject.1;
             *req.headers_mut() = msg.headers;
             *req.version_mut() = msg.version;
             *req.extensions_mut() = msg.extensions;
+            if let Some(ref info) = self.connect_info {
+                req.extensions_mut().insert(info.clone());
+            }
+            if self.send_early_hints {
+                let (tx, rx) = SendEarlyHints::channel();
+                req.extensions_mut().insert(tx);
+                self.early_hints = Some(rx);
+            }
             let fut = self.service.call(req);
             self.in_flight.set(Some(fut));
             Ok(())
@@ -533,6 +860,76 @@ cfg_server! {
         fn should_poll(&self) -> bool {
             self.in_flight.is_some()
         }
+
+        fn poll_early_hints(&mut self, cx: &mut task::Context<'_>) -> Option<http::HeaderMap> {
+            match self.early_hints.as_mut()?.poll_recv(cx) {
+                Poll::Ready(Some(headers)) => Some(headers),
+                Poll::Ready(None) => {
+                    self.early_hints = None;
+                    None
+                }
+                Poll::Pending => None,
+            }
+        }
+
+        fn expect_continue(&mut self, head: &mut Self::RecvItem) -> Option<Self::PollItem> {
+            match self.expect_continue {
+                ExpectContinue::Automatic => None,
+                ExpectContinue::Defer => {
+                    let (tx, rx) = Expect100::channel();
+                    head.extensions.insert(tx);
+                    self.send_continue = Some(rx);
+                    None
+                }
+                ExpectContinue::Reject => {
+                    debug!("rejecting request expecting 100 Continue");
+                    let mut headers = http::HeaderMap::new();
+                    headers.insert(
+                        http::header::CONNECTION,
+                        http::HeaderValue::from_static("close"),
+                    );
+                    Some(MessageHead {
+                        subject: http::StatusCode::EXPECTATION_FAILED,
+                        headers,
+                        ..Default::default()
+                    })
+                }
+            }
+        }
+
+        fn poll_continue(&mut self, cx: &mut task::Context<'_>) -> bool {
+            match self.send_continue.as_mut().map(|rx| rx.poll_recv(cx)) {
+                Some(Poll::Ready(sent)) => {
+                    self.send_continue = None;
+                    sent.is_some()
+                }
+                _ => false,
+            }
+        }
+
+        fn respond_early(&mut self, head: &Self::RecvItem) -> Option<Self::PollItem> {
+            if let Some(ref check) = self.health_check {
+                if let Some(status) = check.respond(&head.subject.0, &head.subject.1) {
+                    debug!("answering health check with {}", status);
+                    return Some(MessageHead {
+                        subject: status,
+                        ..Default::default()
+                    });
+                }
+            }
+            let cors = self.cors.as_ref()?;
+            let (status, headers) = cors.preflight(&head.subject.0, &head.headers)?;
+            debug!("answering CORS preflight with {}", status);
+            Some(MessageHead {
+                subject: status,
+                headers,
+                ..Default::default()
+            })
+        }
+
+        fn observed(&self) -> Option<&Observed> {
+            self.observed.as_ref()
+        }
     }
 }
 
@@ -545,8 +942,13 @@ cfg_client! {
                 callback: None,
                 rx,
                 rx_closed: false,
+                observed: None,
             }
         }
+
+        pub(crate) fn set_observed(&mut self, observed: Observed) {
+            self.observed = Some(observed);
+        }
     }
 
     impl<B> Dispatch for Client<B>
@@ -573,6 +975,9 @@ cfg_client! {
                             Poll::Ready(None)
                         }
                         Poll::Pending => {
+                            if let Some(ref observed) = this.observed {
+                                observed.request_start(req.method(), req.uri());
+                            }
                             let (parts, body) = req.into_parts();
                             let head = RequestHead {
                                 version: parts.version,
@@ -599,6 +1004,9 @@ cfg_client! {
             match msg {
                 Ok((msg, body)) => {
                     if let Some(cb) = self.callback.take() {
+                        if let Some(ref observed) = self.observed {
+                            observed.response_headers(msg.subject);
+                        }
                         let res = msg.into_response(body);
                         cb.send(Ok(res));
                         Ok(())
@@ -647,6 +1055,10 @@ cfg_client! {
         fn should_poll(&self) -> bool {
             self.callback.is_none()
         }
+
+        fn observed(&self) -> Option<&Observed> {
+            self.observed.as_ref()
+        }
     }
 }
 
@@ -719,6 +1131,149 @@ mod tests {
         assert!(!tx.is_ready());
     }
 
+    #[tokio::test]
+    async fn client_response_body_prefetch() {
+        use futures_util::FutureExt;
+
+        let _ = pretty_env_logger::try_init();
+
+        for (prefetch, chunks) in [(0, 1), (1024, 2)] {
+            let io = tokio_test::io::Builder::new()
+                .write(b"GET / HTTP/1.1\r\n\r\n")
+                .read(b"HTTP/1.1 200 OK\r\ncontent-length: 12\r\n\r\nhello ")
+                .read(b"world!")
+                .build();
+
+            let (mut tx, rx) = crate::client::dispatch::channel();
+            let mut conn = Conn::<_, bytes::Bytes, ClientTransaction>::new(io);
+            conn.set_body_prefetch(prefetch);
+            let mut dispatcher = tokio_test::task::spawn(Dispatcher::new(Client::new(rx), conn));
+
+            // First poll is needed to allow tx to send...
+            assert!(dispatcher.poll().is_pending());
+
+            let mut res_rx = tx
+                .try_send(crate::Request::new(crate::Body::empty()))
+                .unwrap();
+            let res = loop {
+                let _ = dispatcher.poll();
+                if let Some(res) = (&mut res_rx).now_or_never() {
+                    break res.unwrap().expect("response");
+                }
+                tokio::task::yield_now().await;
+            };
+            // give the connection a chance to read the rest of the body
+            for _ in 0..4 {
+                let _ = dispatcher.poll();
+                tokio::task::yield_now().await;
+            }
+
+            // only count what was read without polling the connection again
+            let mut body = res.into_body();
+            let mut buffered = 0;
+            while let Some(Some(chunk)) = body.data().now_or_never() {
+                chunk.unwrap();
+                buffered += 1;
+            }
+            assert_eq!(buffered, chunks, "prefetch {}", prefetch);
+
+            // let the connection read everything
+            drop(body);
+            let _ = dispatcher.poll();
+        }
+    }
+
+    #[tokio::test]
+    async fn client_body_framing_buffer() {
+        use crate::ext::{BodyFraming, BodyFramingFn};
+
+        let _ = pretty_env_logger::try_init();
+
+        let cases: [(usize, &[u8]); 2] = [
+            (
+                16,
+                b"POST / HTTP/1.1\r\ncontent-length: 11\r\n\r\nhello world",
+            ),
+            (
+                4,
+                b"POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+                  6\r\nhello \r\n5\r\nworld\r\n0\r\n\r\n",
+            ),
+        ];
+        for (max, written) in cases {
+            let io = tokio_test::io::Builder::new()
+                .write(written)
+                .read(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .build();
+
+            let (mut tx, rx) = crate::client::dispatch::channel();
+            let mut conn = Conn::<_, bytes::Bytes, ClientTransaction>::new(io);
+            conn.set_body_framing(BodyFramingFn::new(move |hint| {
+                assert_eq!(hint.exact(), None);
+                BodyFraming::Buffer(max)
+            }));
+            let dispatcher = Dispatcher::new(Client::new(rx), conn);
+            let _dispatcher = tokio::spawn(dispatcher);
+
+            let (mut body_tx, body) = crate::Body::channel();
+            let req = crate::Request::builder().method("POST").body(body).unwrap();
+            let res_rx = tx.try_send(req).unwrap();
+            body_tx.send_data("hello ".into()).await.unwrap();
+            body_tx.send_data("world".into()).await.unwrap();
+            drop(body_tx);
+
+            res_rx.await.unwrap().expect("response");
+        }
+    }
+
+    #[tokio::test]
+    async fn client_on_informational() {
+        use futures_util::FutureExt;
+        use std::sync::{Arc, Mutex};
+
+        let _ = pretty_env_logger::try_init();
+
+        let io = tokio_test::io::Builder::new()
+            .write(b"GET / HTTP/1.1\r\n\r\n")
+            .read(b"HTTP/1.1 103 Early Hints\r\nlink: </style.css>\r\n\r\n")
+            .read(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .build();
+
+        let (mut tx, rx) = crate::client::dispatch::channel();
+        let conn = Conn::<_, bytes::Bytes, ClientTransaction>::new(io);
+        let mut dispatcher = tokio_test::task::spawn(Dispatcher::new(Client::new(rx), conn));
+
+        // First poll is needed to allow tx to send...
+        assert!(dispatcher.poll().is_pending());
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut req = crate::Request::new(crate::Body::empty());
+        {
+            let seen = seen.clone();
+            crate::ext::on_informational(&mut req, move |res| {
+                let link = res.headers()["link"].clone();
+                seen.lock().unwrap().push((res.status(), link));
+            });
+        }
+        let mut res_rx = tx.try_send(req).unwrap();
+        let res = loop {
+            let _ = dispatcher.poll();
+            if let Some(res) = (&mut res_rx).now_or_never() {
+                break res.unwrap().expect("response");
+            }
+            tokio::task::yield_now().await;
+        };
+
+        assert_eq!(res.status(), crate::StatusCode::OK);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(
+                crate::StatusCode::from_u16(103).unwrap(),
+                crate::header::HeaderValue::from_static("</style.css>")
+            )]
+        );
+    }
+
     #[tokio::test]
     async fn body_empty_chunks_ignored() {
         let _ = pretty_env_logger::try_init();
@@ -747,4 +1302,134 @@ mod tests {
         // If it is, it will trigger an assertion.
         assert!(dispatcher.poll().is_pending());
     }
+
+    #[tokio::test]
+    async fn client_observer_events() {
+        use crate::observer::{ConnectionInfo, ConnectionObserver, ObserverRef};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Events(Mutex<Vec<String>>);
+
+        impl ConnectionObserver for Events {
+            fn on_connect(&self, _conn: &ConnectionInfo) {
+                self.0.lock().unwrap().push("connect".into());
+            }
+
+            fn on_connection_close(&self, _conn: &ConnectionInfo, error: Option<&crate::Error>) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("close {}", error.is_some()));
+            }
+
+            fn on_request_start(
+                &self,
+                _conn: &ConnectionInfo,
+                method: &http::Method,
+                uri: &http::Uri,
+            ) {
+                self.0.lock().unwrap().push(format!("{} {}", method, uri));
+            }
+
+            fn on_response_headers(&self, _conn: &ConnectionInfo, status: http::StatusCode) {
+                self.0.lock().unwrap().push(status.to_string());
+            }
+
+            fn on_body_complete(&self, _conn: &ConnectionInfo) {
+                self.0.lock().unwrap().push("body complete".into());
+            }
+        }
+
+        let _ = pretty_env_logger::try_init();
+
+        let io = tokio_test::io::Builder::new()
+            .write(b"GET / HTTP/1.1\r\n\r\n")
+            .read(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello")
+            .build();
+
+        let events = Arc::new(Events::default());
+        let (mut tx, rx) = crate::client::dispatch::channel();
+        let conn = Conn::<_, bytes::Bytes, ClientTransaction>::new(io);
+        let mut client = Client::new(rx);
+        client
+            .set_observed(ObserverRef::new(events.clone()).connect(http::Version::HTTP_11, false));
+        let dispatcher = tokio::spawn(Dispatcher::new(client, conn));
+
+        let res = tx
+            .try_send(crate::Request::new(crate::Body::empty()))
+            .unwrap()
+            .await
+            .expect("dispatch dropped")
+            .expect("response");
+        let body = crate::body::to_bytes(res.into_body()).await.expect("body");
+        assert_eq!(body, "hello");
+
+        drop(tx);
+        dispatcher.await.unwrap().expect("dispatcher");
+
+        assert_eq!(
+            *events.0.lock().unwrap(),
+            ["connect", "GET /", "200 OK", "body complete", "close false",]
+        );
+    }
+
+    #[tokio::test]
+    async fn client_keep_alive_disabled() {
+        let _ = pretty_env_logger::try_init();
+
+        let io = tokio_test::io::Builder::new()
+            .write(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n")
+            .read(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .build();
+
+        let (mut tx, rx) = crate::client::dispatch::channel();
+        let mut conn = Conn::<_, bytes::Bytes, ClientTransaction>::new(io);
+        conn.disable_keep_alive();
+        let dispatcher = tokio::spawn(Dispatcher::new(Client::new(rx), conn));
+
+        let res = tx
+            .try_send(crate::Request::new(crate::Body::empty()))
+            .unwrap();
+        res.await.expect("response");
+        assert!(matches!(
+            dispatcher.await.unwrap(),
+            Ok(Dispatched::Shutdown)
+        ));
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test(start_paused = true)]
+    async fn client_idle_timeout() {
+        use tokio::io::AsyncWriteExt;
+        use tokio::time::Instant;
+
+        let _ = pretty_env_logger::try_init();
+
+        let (io, mut server) = tokio::io::duplex(1024);
+        let (mut tx, rx) = crate::client::dispatch::channel();
+        let conn = Conn::<_, bytes::Bytes, ClientTransaction>::new(io);
+        let mut dispatcher = Dispatcher::new(Client::new(rx), conn);
+        dispatcher.set_idle_timeout(Duration::from_secs(5));
+        let dispatcher = tokio::spawn(dispatcher);
+
+        // the timer doesn't run while a request is in flight
+        let res = tx
+            .try_send(crate::Request::new(crate::Body::empty()))
+            .unwrap();
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        server
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .unwrap();
+        res.await.expect("response");
+
+        let idle_since = Instant::now();
+        assert!(matches!(
+            dispatcher.await.unwrap(),
+            Ok(Dispatched::Shutdown)
+        ));
+        assert!(idle_since.elapsed() >= Duration::from_secs(5));
+        assert!(tx.is_closed());
+    }
 }
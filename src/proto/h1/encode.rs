@@ -3,17 +3,24 @@ use std::io::IoSlice;
 
 use bytes::buf::{Chain, Take};
 use bytes::Buf;
+#[cfg(feature = "server")]
+use bytes::{Bytes, BytesMut};
 use tracing::trace;
 
 use super::io::WriteBuf;
+#[cfg(feature = "server")]
+use crate::ext::{ChunkExtensions, ChunkExtensionsHook};
 
 type StaticBuf = &'static [u8];
 
 /// Encoders to handle different Transfer-Encodings.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub(crate) struct Encoder {
     kind: Kind,
     is_last: bool,
+    /// Picks the extensions of each chunk, if chunked.
+    #[cfg(feature = "server")]
+    chunk_extensions: Option<ChunkExtensionsHook>,
 }
 
 #[derive(Debug)]
@@ -46,6 +53,8 @@ enum BufKind<B> {
     Limited(Take<B>),
     Chunked(Chain<Chain<ChunkSize, B>, StaticBuf>),
     ChunkedEnd(StaticBuf),
+    #[cfg(feature = "server")]
+    ChunkedWithExtensions(Chain<Chain<Bytes, Bytes>, Bytes>),
 }
 
 impl Encoder {
@@ -53,6 +62,8 @@ impl Encoder {
         Encoder {
             kind,
             is_last: false,
+            #[cfg(feature = "server")]
+            chunk_extensions: None,
         }
     }
     pub(crate) fn chunked() -> Encoder {
@@ -82,6 +93,15 @@ impl Encoder {
         self.is_last
     }
 
+    /// Calls `hook` for the extensions of each chunk, if chunked.
+    #[cfg(feature = "server")]
+    pub(crate) fn with_chunk_extensions(mut self, hook: ChunkExtensionsHook) -> Self {
+        if self.kind == Kind::Chunked {
+            self.chunk_extensions = Some(hook);
+        }
+        self
+    }
+
     pub(crate) fn is_close_delimited(&self) -> bool {
         match self.kind {
             #[cfg(feature = "server")]
@@ -93,6 +113,10 @@ impl Encoder {
     pub(crate) fn end<B>(&self) -> Result<Option<EncodedBuf<B>>, NotEof> {
         match self.kind {
             Kind::Length(0) => Ok(None),
+            #[cfg(feature = "server")]
+            Kind::Chunked if self.chunk_extensions.is_some() => Ok(Some(EncodedBuf {
+                kind: self.chunk_with_extensions(Bytes::new(), true),
+            })),
             Kind::Chunked => Ok(Some(EncodedBuf {
                 kind: BufKind::ChunkedEnd(b"0\r\n\r\n"),
             })),
@@ -110,6 +134,11 @@ impl Encoder {
         debug_assert!(len > 0, "encode() called with empty buf");
 
         let kind = match self.kind {
+            #[cfg(feature = "server")]
+            Kind::Chunked if self.chunk_extensions.is_some() => {
+                trace!("encoding chunked {}B with extensions", len);
+                self.chunk_with_extensions(msg, false)
+            }
             Kind::Chunked => {
                 trace!("encoding chunked {}B", len);
                 let buf = ChunkSize::new(len)
@@ -145,6 +174,14 @@ impl Encoder {
         debug_assert!(len > 0, "encode() called with empty buf");
 
         match self.kind {
+            #[cfg(feature = "server")]
+            Kind::Chunked if self.chunk_extensions.is_some() => {
+                trace!("encoding chunked {}B with extensions", len);
+                dst.buffer(EncodedBuf {
+                    kind: self.chunk_with_extensions(msg, true),
+                });
+                !self.is_last
+            }
             Kind::Chunked => {
                 trace!("encoding chunked {}B", len);
                 let buf = ChunkSize::new(len)
@@ -200,6 +237,12 @@ impl Encoder {
         );
 
         match self.kind {
+            #[cfg(feature = "server")]
+            Kind::Chunked if self.chunk_extensions.is_some() => {
+                dst.buffer(EncodedBuf {
+                    kind: self.chunk_with_extensions(msg, true),
+                });
+            }
             Kind::Chunked => {
                 let len = msg.remaining();
                 trace!("encoding chunked {}B", len);
@@ -215,6 +258,40 @@ impl Encoder {
     }
 }
 
+#[cfg(feature = "server")]
+impl Encoder {
+    /// Encodes a chunk with the extensions picked by the hook, followed by
+    /// the last chunk if `last`.
+    fn chunk_with_extensions<D: Buf, B>(&self, mut data: D, last: bool) -> BufKind<B> {
+        let hook = self
+            .chunk_extensions
+            .as_ref()
+            .expect("chunk_with_extensions without hook");
+        let data = data.copy_to_bytes(data.remaining());
+        let mut head = BytesMut::new();
+        let mut tail = BytesMut::new();
+        if !data.is_empty() {
+            write_chunk_size(&mut head, data.len(), hook.call(&data));
+            tail.extend_from_slice(b"\r\n");
+        }
+        if last {
+            write_chunk_size(&mut tail, 0, hook.call(&[]));
+            tail.extend_from_slice(b"\r\n");
+        }
+        BufKind::ChunkedWithExtensions(head.freeze().chain(data).chain(tail.freeze()))
+    }
+}
+
+#[cfg(feature = "server")]
+fn write_chunk_size(dst: &mut BytesMut, len: usize, extensions: Option<ChunkExtensions>) {
+    use std::fmt::Write;
+    write!(dst, "{:X}", len).expect("writing to BytesMut cannot fail");
+    if let Some(extensions) = extensions {
+        dst.extend_from_slice(extensions.as_bytes());
+    }
+    dst.extend_from_slice(b"\r\n");
+}
+
 impl<B> Buf for EncodedBuf<B>
 where
     B: Buf,
@@ -226,6 +303,8 @@ where
             BufKind::Limited(ref b) => b.remaining(),
             BufKind::Chunked(ref b) => b.remaining(),
             BufKind::ChunkedEnd(ref b) => b.remaining(),
+            #[cfg(feature = "server")]
+            BufKind::ChunkedWithExtensions(ref b) => b.remaining(),
         }
     }
 
@@ -236,6 +315,8 @@ where
             BufKind::Limited(ref b) => b.chunk(),
             BufKind::Chunked(ref b) => b.chunk(),
             BufKind::ChunkedEnd(ref b) => b.chunk(),
+            #[cfg(feature = "server")]
+            BufKind::ChunkedWithExtensions(ref b) => b.chunk(),
         }
     }
 
@@ -246,6 +327,8 @@ where
             BufKind::Limited(ref mut b) => b.advance(cnt),
             BufKind::Chunked(ref mut b) => b.advance(cnt),
             BufKind::ChunkedEnd(ref mut b) => b.advance(cnt),
+            #[cfg(feature = "server")]
+            BufKind::ChunkedWithExtensions(ref mut b) => b.advance(cnt),
         }
     }
 
@@ -256,6 +339,8 @@ where
             BufKind::Limited(ref b) => b.chunks_vectored(dst),
             BufKind::Chunked(ref b) => b.chunks_vectored(dst),
             BufKind::ChunkedEnd(ref b) => b.chunks_vectored(dst),
+            #[cfg(feature = "server")]
+            BufKind::ChunkedWithExtensions(ref b) => b.chunks_vectored(dst),
         }
     }
 }
@@ -391,6 +476,27 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "server")]
+    #[test]
+    fn chunked_with_extensions() {
+        use crate::ext::{ChunkExtensions, ChunkExtensionsHook};
+
+        let hook = ChunkExtensionsHook::new(|data| match data {
+            b"" => Some(ChunkExtensions::from_static(b";ieof")),
+            b"foo" => Some(ChunkExtensions::from_static(b";a=1")),
+            _ => None,
+        });
+        let mut encoder = Encoder::chunked().with_chunk_extensions(hook);
+        let mut dst = Vec::new();
+
+        dst.put(encoder.encode(b"foo".as_ref()));
+        dst.put(encoder.encode(b"bar".as_ref()));
+        assert_eq!(dst, b"3;a=1\r\nfoo\r\n3\r\nbar\r\n");
+
+        dst.put(encoder.end::<&[u8]>().unwrap().unwrap());
+        assert_eq!(dst, b"3;a=1\r\nfoo\r\n3\r\nbar\r\n0;ieof\r\n\r\n");
+    }
+
     #[test]
     fn length() {
         let max_len = 8;
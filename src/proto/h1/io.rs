@@ -17,6 +17,8 @@ use tracing::{debug, trace};
 use super::{Http1Transaction, ParseContext, ParsedMessage};
 use crate::common::buf::BufList;
 use crate::common::{task, Pin, Poll};
+#[cfg(feature = "client")]
+use crate::metrics::Recorder;
 
 /// The initial buffer size allocated before trying to read from IO.
 pub(crate) const INIT_BUFFER_SIZE: usize = 8192;
@@ -24,6 +26,9 @@ pub(crate) const INIT_BUFFER_SIZE: usize = 8192;
 /// The minimum value that can be set to max buffer size.
 pub(crate) const MINIMUM_MAX_BUFFER_SIZE: usize = INIT_BUFFER_SIZE;
 
+/// The minimum value that can be set to min buffer size.
+pub(crate) const MINIMUM_MIN_BUFFER_SIZE: usize = 64;
+
 /// The default maximum read buffer size. If the buffer gets this big and
 /// a message is still not complete, a `TooLarge` error is triggered.
 // Note: if this changes, update server::conn::Http::max_buf_size docs.
@@ -39,6 +44,8 @@ const MAX_BUF_LIST_BUFFERS: usize = 16;
 pub(crate) struct Buffered<T, B> {
     flush_pipeline: bool,
     io: T,
+    #[cfg(feature = "client")]
+    metrics: Option<Recorder>,
     read_blocked: bool,
     read_buf: BytesMut,
     read_buf_strategy: ReadStrategy,
@@ -72,6 +79,8 @@ where
         Buffered {
             flush_pipeline: false,
             io,
+            #[cfg(feature = "client")]
+            metrics: None,
             read_blocked: false,
             read_buf: BytesMut::with_capacity(0),
             read_buf_strategy: ReadStrategy::default(),
@@ -98,11 +107,27 @@ where
         self.write_buf.max_buf_size = max;
     }
 
+    /// Sets the size of the first read, and the smallest that reads shrink
+    /// to. Must be called after `set_max_buf_size`.
+    pub(crate) fn set_min_buf_size(&mut self, min: usize) {
+        assert!(
+            min >= MINIMUM_MIN_BUFFER_SIZE,
+            "The min_buf_size cannot be smaller than {}.",
+            MINIMUM_MIN_BUFFER_SIZE,
+        );
+        self.read_buf_strategy = ReadStrategy::with_bounds(min, self.read_buf_strategy.max());
+    }
+
     #[cfg(feature = "client")]
     pub(crate) fn set_read_buf_exact_size(&mut self, sz: usize) {
         self.read_buf_strategy = ReadStrategy::Exact(sz);
     }
 
+    #[cfg(feature = "client")]
+    pub(crate) fn set_metrics(&mut self, metrics: Recorder) {
+        self.metrics = Some(metrics);
+    }
+
     pub(crate) fn set_write_strategy_flatten(&mut self) {
         // this should always be called only at construction time,
         // so this assert is here to catch myself
@@ -197,10 +222,23 @@ where
                     #[cfg(feature = "ffi")]
                     preserve_header_order: parse_ctx.preserve_header_order,
                     h09_responses: parse_ctx.h09_responses,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: parse_ctx.preserve_raw_head,
+                    #[cfg(feature = "client")]
+                    max_informational: parse_ctx.max_informational,
+                    #[cfg(feature = "client")]
+                    informational_count: parse_ctx.informational_count,
+                    #[cfg(feature = "client")]
+                    on_violation: parse_ctx.on_violation,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: parse_ctx.allowed_hosts,
+                    #[cfg(feature = "server")]
+                    strict: parse_ctx.strict,
+                    #[cfg(feature = "client")]
                     on_informational: parse_ctx.on_informational,
-                    #[cfg(feature = "ffi")]
                     raw_headers: parse_ctx.raw_headers,
+                    h1_max_headers: parse_ctx.h1_max_headers,
+                    h1_max_header_value_size: parse_ctx.h1_max_header_value_size,
+                    content_length_policy: parse_ctx.content_length_policy,
                 },
             )? {
                 Some(msg) => {
@@ -243,7 +281,15 @@ where
                     }
                 }
             }
-            if ready!(self.poll_read_from_io(cx)).map_err(crate::Error::new_io)? == 0 {
+            let n = match self.poll_read_from_io(cx) {
+                Poll::Ready(result) => result.map_err(crate::Error::new_io)?,
+                Poll::Pending => {
+                    // waiting for a new message
+                    self.shrink_read_buf();
+                    return Poll::Pending;
+                }
+            };
+            if n == 0 {
                 trace!("parse eof");
                 return Poll::Ready(Err(crate::Error::new_incomplete()));
             }
@@ -274,6 +320,10 @@ where
                     self.read_buf.advance_mut(n);
                 }
                 self.read_buf_strategy.record(n);
+                #[cfg(feature = "client")]
+                if let Some(ref metrics) = self.metrics {
+                    metrics.bytes_read(n as u64);
+                }
                 Poll::Ready(Ok(n))
             }
             Poll::Pending => {
@@ -284,6 +334,26 @@ where
         }
     }
 
+    /// Releases the read buffer while waiting for a new message, if it
+    /// grew larger than the smallest read, and starts reading with the
+    /// smallest read size again.
+    ///
+    /// This keeps idle keep-alive connections from holding on to buffers
+    /// grown for earlier, larger messages.
+    pub(crate) fn shrink_read_buf(&mut self) {
+        if !self.read_buf.is_empty() {
+            return;
+        }
+        self.read_buf_strategy.reset();
+        if self.read_buf.capacity() > self.read_buf_strategy.next() {
+            trace!(
+                "releasing idle read buffer of {} bytes",
+                self.read_buf.capacity()
+            );
+            self.read_buf = BytesMut::new();
+        }
+    }
+
     pub(crate) fn into_inner(self) -> (T, Bytes) {
         (self.io, self.read_buf.freeze())
     }
@@ -318,6 +388,10 @@ where
                 // `poll_write_buf` comes back, the manual advance will need to leave!
                 self.write_buf.advance(n);
                 debug!("flushed {} bytes", n);
+                #[cfg(feature = "client")]
+                if let Some(ref metrics) = self.metrics {
+                    metrics.bytes_written(n as u64);
+                }
                 if self.write_buf.remaining() == 0 {
                     break;
                 } else if n == 0 {
@@ -340,6 +414,10 @@ where
         loop {
             let n = ready!(Pin::new(&mut self.io).poll_write(cx, self.write_buf.headers.chunk()))?;
             debug!("flushed {} bytes", n);
+            #[cfg(feature = "client")]
+            if let Some(ref metrics) = self.metrics {
+                metrics.bytes_written(n as u64);
+            }
             self.write_buf.headers.advance(n);
             if self.write_buf.headers.remaining() == 0 {
                 self.write_buf.headers.reset();
@@ -390,6 +468,7 @@ enum ReadStrategy {
     Adaptive {
         decrease_now: bool,
         next: usize,
+        min: usize,
         max: usize,
     },
     #[cfg(feature = "client")]
@@ -398,9 +477,15 @@ enum ReadStrategy {
 
 impl ReadStrategy {
     fn with_max(max: usize) -> ReadStrategy {
+        ReadStrategy::with_bounds(INIT_BUFFER_SIZE, max)
+    }
+
+    fn with_bounds(min: usize, max: usize) -> ReadStrategy {
+        let min = cmp::min(min, max);
         ReadStrategy::Adaptive {
             decrease_now: false,
-            next: INIT_BUFFER_SIZE,
+            next: min,
+            min,
             max,
         }
     }
@@ -426,8 +511,8 @@ impl ReadStrategy {
             ReadStrategy::Adaptive {
                 ref mut decrease_now,
                 ref mut next,
+                min,
                 max,
-                ..
             } => {
                 if bytes_read >= *next {
                     *next = cmp::min(incr_power_of_two(*next), max);
@@ -436,7 +521,7 @@ impl ReadStrategy {
                     let decr_to = prev_power_of_two(*next);
                     if bytes_read < decr_to {
                         if *decrease_now {
-                            *next = cmp::max(decr_to, INIT_BUFFER_SIZE);
+                            *next = cmp::max(decr_to, min);
                             *decrease_now = false;
                         } else {
                             // Decreasing is a two "record" process.
@@ -454,6 +539,23 @@ impl ReadStrategy {
             ReadStrategy::Exact(_) => (),
         }
     }
+
+    /// Starts over from the smallest read size.
+    fn reset(&mut self) {
+        match *self {
+            ReadStrategy::Adaptive {
+                ref mut decrease_now,
+                ref mut next,
+                min,
+                ..
+            } => {
+                *next = min;
+                *decrease_now = false;
+            }
+            #[cfg(feature = "client")]
+            ReadStrategy::Exact(_) => (),
+        }
+    }
 }
 
 fn incr_power_of_two(n: usize) -> usize {
@@ -745,10 +847,23 @@ mod tests {
                 #[cfg(feature = "ffi")]
                 preserve_header_order: false,
                 h09_responses: false,
-                #[cfg(feature = "ffi")]
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
                 on_informational: &mut None,
-                #[cfg(feature = "ffi")]
                 raw_headers: false,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: Default::default(),
             };
             assert!(buffered
                 .parse::<ClientTransaction>(cx, parse_ctx)
@@ -870,6 +985,60 @@ mod tests {
         fuzz(::std::usize::MAX);
     }
 
+    #[test]
+    fn read_strategy_adaptive_min() {
+        let mut strategy = ReadStrategy::with_bounds(1024, 16384);
+        assert_eq!(strategy.next(), 1024);
+
+        strategy.record(1024);
+        strategy.record(2048);
+        assert_eq!(strategy.next(), 4096);
+
+        for _ in 0..10 {
+            strategy.record(1);
+        }
+        assert_eq!(strategy.next(), 1024, "doesn't decrement under minimum");
+
+        strategy.record(1024);
+        strategy.reset();
+        assert_eq!(strategy.next(), 1024, "reset goes back to minimum");
+
+        let strategy = ReadStrategy::with_bounds(32768, 16384);
+        assert_eq!(strategy.next(), 16384, "minimum is capped at maximum");
+    }
+
+    #[test]
+    fn shrink_read_buf_releases_large_idle_buffer() {
+        let mock = Mock::new().build();
+        let mut buffered = Buffered::<_, Cursor<Vec<u8>>>::new(mock);
+        buffered.set_min_buf_size(1024);
+        buffered.read_buf_strategy.record(1024);
+        buffered.read_buf_strategy.record(2048);
+
+        buffered.read_buf.reserve(4096);
+        buffered.read_buf.extend_from_slice(b"GET");
+        buffered.shrink_read_buf();
+        assert!(
+            buffered.read_buf.capacity() >= 4096,
+            "doesn't release a partial message"
+        );
+        assert_eq!(buffered.read_buf_strategy.next(), 4096);
+
+        buffered.read_buf.clear();
+        buffered.shrink_read_buf();
+        assert_eq!(buffered.read_buf.capacity(), 0);
+        assert_eq!(buffered.read_buf_strategy.next(), 1024);
+
+        buffered.read_buf.reserve(512);
+        let cap = buffered.read_buf.capacity();
+        buffered.shrink_read_buf();
+        assert_eq!(
+            buffered.read_buf.capacity(),
+            cap,
+            "keeps a buffer no larger than the minimum"
+        );
+    }
+
     #[test]
     #[should_panic]
     #[cfg(debug_assertions)] // needs to trigger a debug_assert
@@ -984,6 +1153,35 @@ mod tests {
         assert_eq!(buffered.write_buf.queue.bufs_cnt(), 0);
     }
 
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn metrics_count_bytes() {
+        use crate::metrics::Collector;
+        use std::sync::Arc;
+
+        let mock = Mock::new()
+            .read(b"HTTP/1.1 200 OK\r\n")
+            .write(b"hello ")
+            .write(b"world")
+            .build();
+
+        let collector = Arc::new(Collector::new());
+        let mut buffered = Buffered::<_, Cursor<Vec<u8>>>::new(mock);
+        buffered.write_buf.set_strategy(WriteStrategy::Queue);
+        buffered.set_metrics(Recorder::new(collector.clone()));
+
+        futures_util::future::poll_fn(|cx| buffered.poll_read_from_io(cx))
+            .await
+            .expect("read");
+        buffered.headers_buf().extend(b"hello ");
+        buffered.buffer(Cursor::new(b"world".to_vec()));
+        buffered.flush().await.expect("flush");
+
+        let snapshot = collector.snapshot();
+        assert_eq!(snapshot.bytes_read(), 17);
+        assert_eq!(snapshot.bytes_written(), 11);
+    }
+
     // #[cfg(feature = "nightly")]
     // #[bench]
     // fn bench_write_buf_flatten_buffer_chunk(b: &mut Bencher) {
@@ -1,6 +1,8 @@
 #[cfg(all(feature = "server", feature = "runtime"))]
 use std::{pin::Pin, time::Duration};
 
+#[cfg(all(feature = "server", feature = "http2"))]
+use bytes::Bytes;
 use bytes::BytesMut;
 use http::{HeaderMap, Method};
 use httparse::ParserConfig;
@@ -8,14 +10,21 @@ use httparse::ParserConfig;
 use tokio::time::Sleep;
 
 use crate::body::DecodedLength;
+#[cfg(feature = "client")]
+use crate::client::validate::OnViolation;
+#[cfg(feature = "server")]
+use crate::common::host::AllowedHosts;
+use crate::ext::ContentLengthPolicy;
 use crate::proto::{BodyLength, MessageHead};
 
 pub(crate) use self::conn::Conn;
 pub(crate) use self::decode::Decoder;
 pub(crate) use self::dispatch::Dispatcher;
 pub(crate) use self::encode::{EncodedBuf, Encoder};
+#[cfg(all(feature = "client", feature = "runtime"))]
+pub(crate) use crate::common::pace::Pacer;
 //TODO: move out of h1::io
-pub(crate) use self::io::MINIMUM_MAX_BUFFER_SIZE;
+pub(crate) use self::io::{MINIMUM_MAX_BUFFER_SIZE, MINIMUM_MIN_BUFFER_SIZE};
 
 mod conn;
 mod decode;
@@ -58,6 +67,13 @@ pub(crate) trait Http1Transaction {
     }
 
     fn update_date() {}
+
+    /// If `head` asks to upgrade the connection to h2c, returns the HTTP/2
+    /// frame replaying it once switched.
+    #[cfg(all(feature = "server", feature = "http2"))]
+    fn h2c_upgrade(_head: &MessageHead<Self::Incoming>, _decode: DecodedLength) -> Option<Bytes> {
+        None
+    }
 }
 
 /// Result newtype for Http1Transaction::parse.
@@ -86,10 +102,46 @@ pub(crate) struct ParseContext<'a> {
     #[cfg(feature = "ffi")]
     preserve_header_order: bool,
     h09_responses: bool,
-    #[cfg(feature = "ffi")]
-    on_informational: &'a mut Option<crate::ffi::OnInformational>,
-    #[cfg(feature = "ffi")]
+    preserve_raw_head: bool,
+    #[cfg(feature = "client")]
+    max_informational: usize,
+    #[cfg(feature = "client")]
+    informational_count: &'a mut usize,
+    #[cfg(feature = "client")]
+    on_violation: Option<&'a OnViolation>,
+    #[cfg(feature = "server")]
+    allowed_hosts: Option<&'a AllowedHosts>,
+    #[cfg(feature = "server")]
+    strict: StrictParsing,
+    #[cfg(feature = "client")]
+    on_informational: &'a mut Option<crate::ext::OnInformational>,
     raw_headers: bool,
+    h1_max_headers: Option<usize>,
+    h1_max_header_value_size: Option<usize>,
+    content_length_policy: ContentLengthPolicy,
+}
+
+/// Which request heads a server rejects for being ambiguous, since peers
+/// such as proxies might parse them differently, allowing requests to be
+/// smuggled past them.
+#[cfg(feature = "server")]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct StrictParsing {
+    /// Reject requests with both `Content-Length` and `Transfer-Encoding`.
+    pub(crate) content_length_with_transfer_encoding: bool,
+    /// Reject header values continued on the next line.
+    pub(crate) obs_fold: bool,
+    /// Reject CRs not followed by a LF.
+    pub(crate) bare_cr: bool,
+    /// Reject whitespace between a header name and its colon.
+    pub(crate) whitespace_before_colon: bool,
+}
+
+#[cfg(feature = "server")]
+impl StrictParsing {
+    fn scans_head(&self) -> bool {
+        self.obs_fold || self.bare_cr || self.whitespace_before_colon
+    }
 }
 
 /// Passed to Http1Transaction::encode
@@ -98,6 +150,8 @@ pub(crate) struct Encode<'a, T> {
     body: Option<BodyLength>,
     #[cfg(feature = "server")]
     keep_alive: bool,
+    #[cfg(feature = "server")]
+    date_header: bool,
     req_method: &'a mut Option<Method>,
     title_case_headers: bool,
 }
@@ -1,7 +1,7 @@
 use std::fmt::{self, Write};
 use std::mem::MaybeUninit;
 
-#[cfg(any(test, feature = "server", feature = "ffi"))]
+#[cfg(any(test, feature = "client", feature = "server"))]
 use bytes::Bytes;
 use bytes::BytesMut;
 #[cfg(feature = "server")]
@@ -15,11 +15,25 @@ use tracing::{debug, error, trace, trace_span, warn};
 use crate::body::DecodedLength;
 #[cfg(feature = "server")]
 use crate::common::date;
+#[cfg(feature = "server")]
+use crate::error::Header;
 use crate::error::Parse;
+#[cfg(feature = "server")]
+use crate::ext::date::OmitDate;
+#[cfg(feature = "server")]
+use crate::ext::ChunkExtensionsHook;
+#[cfg(any(feature = "client", test))]
+use crate::ext::ContentLengthPolicy;
 use crate::ext::HeaderCaseMap;
+#[cfg(all(feature = "client", feature = "server"))]
+use crate::ext::OriginalHead;
 #[cfg(feature = "ffi")]
 use crate::ext::OriginalHeaderOrder;
+#[cfg(any(feature = "client", feature = "server"))]
+use crate::ext::{RawHeaders, ReasonPhrase};
 use crate::headers;
+#[cfg(feature = "server")]
+use crate::proto::h1::StrictParsing;
 use crate::proto::h1::{
     Encode, Encoder, Http1Transaction, ParseContext, ParseResult, ParsedMessage,
 };
@@ -80,7 +94,7 @@ where
     if !*ctx.h1_header_read_timeout_running {
         if let Some(h1_header_read_timeout) = ctx.h1_header_read_timeout {
             let deadline = Instant::now() + h1_header_read_timeout;
-
+            *ctx.h1_header_read_timeout_running = true;
             match ctx.h1_header_read_timeout_fut {
                 Some(h1_header_read_timeout_fut) => {
                     debug!("resetting h1 header read timeout timer");
@@ -142,14 +156,25 @@ impl Http1Transaction for Server {
             // SAFETY: We can go safely from MaybeUninit array to array of MaybeUninit
             MaybeUninit::uninit().assume_init()
         };
+        let mut many_headers_indices = Vec::new();
+        let headers_indices = header_slots(
+            &mut headers_indices,
+            &mut many_headers_indices,
+            ctx.h1_max_headers,
+        );
         {
             /* SAFETY: it is safe to go from MaybeUninit array to array of MaybeUninit */
             let mut headers: [MaybeUninit<httparse::Header<'_>>; MAX_HEADERS] =
                 unsafe { MaybeUninit::uninit().assume_init() };
+            let mut many_headers = Vec::new();
+            let headers = header_slots(&mut headers, &mut many_headers, ctx.h1_max_headers);
             trace!(bytes = buf.len(), "Request.parse");
+            if ctx.strict.scans_head() {
+                check_strict_head(buf, &ctx.strict)?;
+            }
             let mut req = httparse::Request::new(&mut []);
             let bytes = buf.as_ref();
-            match req.parse_with_uninit_headers(bytes, &mut headers) {
+            match req.parse_with_uninit_headers(bytes, headers) {
                 Ok(httparse::Status::Complete(parsed_len)) => {
                     trace!("Request.parse Complete({})", parsed_len);
                     len = parsed_len;
@@ -171,7 +196,12 @@ impl Http1Transaction for Server {
                         Version::HTTP_10
                     };
 
-                    record_header_indices(bytes, &req.headers, &mut headers_indices)?;
+                    record_header_indices(
+                        bytes,
+                        &req.headers,
+                        headers_indices,
+                        ctx.h1_max_header_value_size,
+                    )?;
                     headers_len = req.headers.len();
                 }
                 Ok(httparse::Status::Partial) => return Ok(None),
@@ -206,6 +236,7 @@ impl Http1Transaction for Server {
         let mut decoder = DecodedLength::ZERO;
         let mut expect_continue = false;
         let mut con_len = None;
+        let mut has_con_len = false;
         let mut is_te = false;
         let mut is_te_chunked = false;
         let mut wants_upgrade = subject.0 == Method::CONNECT;
@@ -231,7 +262,7 @@ impl Http1Transaction for Server {
             // SAFETY: array is valid up to `headers_len`
             let header = unsafe { &*header.as_ptr() };
             let name = header_name!(&slice[header.name.0..header.name.1]);
-            let value = header_value!(slice.slice(header.value.0..header.value.1));
+            let mut value = header_value!(slice.slice(header.value.0..header.value.1));
 
             match name {
                 header::TRANSFER_ENCODING => {
@@ -252,24 +283,33 @@ impl Http1Transaction for Server {
                     }
                 }
                 header::CONTENT_LENGTH => {
+                    has_con_len = true;
                     if is_te {
                         continue;
                     }
-                    let len = headers::content_length_parse(&value)
-                        .ok_or_else(Parse::content_length_invalid)?;
-                    if let Some(prev) = con_len {
-                        if prev != len {
-                            debug!(
-                                "multiple Content-Length headers with different values: [{}, {}]",
-                                prev, len,
-                            );
-                            return Err(Parse::content_length_invalid());
+                    let is_first = con_len.is_none();
+                    match headers::content_length_merge(
+                        &mut con_len,
+                        &value,
+                        ctx.content_length_policy,
+                    ) {
+                        Ok(()) => (),
+                        Err(e) => {
+                            debug!("invalid Content-Length: {:?}", value);
+                            return Err(e);
                         }
+                    }
+                    if !is_first {
                         // we don't need to append this secondary length
                         continue;
                     }
-                    decoder = DecodedLength::checked_new(len)?;
-                    con_len = Some(len);
+                    if let Some(len) = con_len {
+                        decoder = DecodedLength::checked_new(len)?;
+                        // a list such as `10, 10` is replaced with the chosen length
+                        if value.as_bytes().contains(&b',') {
+                            value = HeaderValue::from(len);
+                        }
+                    }
                 }
                 header::CONNECTION => {
                     // keep_alive was previously set to default for Version
@@ -312,6 +352,24 @@ impl Http1Transaction for Server {
             return Err(Parse::transfer_encoding_invalid());
         }
 
+        if is_te && has_con_len && ctx.strict.content_length_with_transfer_encoding {
+            debug!("request with both content-length and transfer-encoding headers");
+            return Err(Parse::Header(Header::ContentLengthWithTransferEncoding));
+        }
+
+        // A proxy keeping raw heads must not forward both framings, so the
+        // Content-Length is dropped, as RFC 9112 asks of intermediaries.
+        if is_te && has_con_len && ctx.preserve_raw_head {
+            headers.remove(header::CONTENT_LENGTH);
+        }
+
+        if let Some(allowed_hosts) = ctx.allowed_hosts {
+            if !allowed_hosts.allows(&subject.1, &headers) {
+                debug!("request for a host that is not allowed");
+                return Err(Parse::Misdirected);
+            }
+        }
+
         let mut extensions = http::Extensions::default();
 
         if let Some(header_case_map) = header_case_map {
@@ -323,6 +381,24 @@ impl Http1Transaction for Server {
             extensions.insert(header_order);
         }
 
+        // Only keep the raw head if every header line made it into the map,
+        // otherwise re-emitting it could forward something we ignored (like
+        // a secondary Content-Length, or one next to a Transfer-Encoding).
+        #[cfg(feature = "client")]
+        if ctx.preserve_raw_head && headers.len() == headers_len {
+            extensions.insert(OriginalHead::new(
+                slice.clone(),
+                subject.0.clone(),
+                subject.1.clone(),
+                version,
+                headers.clone(),
+            ));
+        }
+
+        if ctx.raw_headers {
+            extensions.insert(RawHeaders::new(slice));
+        }
+
         *ctx.req_method = Some(subject.0.clone());
 
         Ok(Some(ParsedMessage {
@@ -377,7 +453,11 @@ impl Http1Transaction for Server {
 
         let init_cap = 30 + msg.head.headers.len() * AVERAGE_HEADER_SIZE;
         dst.reserve(init_cap);
-        if msg.head.version == Version::HTTP_11 && msg.head.subject == StatusCode::OK {
+        let custom_reason = msg.head.extensions.get::<ReasonPhrase>();
+        if msg.head.version == Version::HTTP_11
+            && msg.head.subject == StatusCode::OK
+            && custom_reason.is_none()
+        {
             extend(dst, b"HTTP/1.1 200 OK\r\n");
         } else {
             match msg.head.version {
@@ -393,19 +473,25 @@ impl Http1Transaction for Server {
             extend(dst, msg.head.subject.as_str().as_bytes());
             extend(dst, b" ");
             // a reason MUST be written, as many parsers will expect it.
-            extend(
-                dst,
-                msg.head
-                    .subject
-                    .canonical_reason()
-                    .unwrap_or("<none>")
-                    .as_bytes(),
-            );
+            match custom_reason {
+                Some(reason) => extend(dst, reason.as_bytes()),
+                None => extend(
+                    dst,
+                    msg.head
+                        .subject
+                        .canonical_reason()
+                        .unwrap_or("<none>")
+                        .as_bytes(),
+                ),
+            }
             extend(dst, b"\r\n");
         }
 
         let orig_headers;
         let extensions = std::mem::take(&mut msg.head.extensions);
+        if extensions.get::<OmitDate>().is_some() {
+            msg.date_header = false;
+        }
         let orig_headers = match extensions.get::<HeaderCaseMap>() {
             None if msg.title_case_headers => {
                 orig_headers = HeaderCaseMap::default();
@@ -425,6 +511,10 @@ impl Http1Transaction for Server {
         } else {
             Self::encode_headers_with_lower_case(msg, dst, is_last, orig_len, wrote_len)?
         };
+        let encoder = match extensions.get::<ChunkExtensionsHook>() {
+            Some(hook) => encoder.with_chunk_extensions(hook.clone()),
+            None => encoder,
+        };
 
         ret.map(|()| encoder)
     }
@@ -436,8 +526,13 @@ impl Http1Transaction for Server {
             | Kind::Parse(Parse::Header(_))
             | Kind::Parse(Parse::Uri)
             | Kind::Parse(Parse::Version) => StatusCode::BAD_REQUEST,
-            Kind::Parse(Parse::TooLarge) => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            Kind::Parse(Parse::TooLarge)
+            | Kind::Parse(Parse::TooManyHeaders)
+            | Kind::Parse(Parse::HeaderValueTooLarge) => {
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+            }
             Kind::Parse(Parse::UriTooLong) => StatusCode::URI_TOO_LONG,
+            Kind::Parse(Parse::Misdirected) => StatusCode::MISDIRECTED_REQUEST,
             _ => return None,
         };
 
@@ -454,6 +549,11 @@ impl Http1Transaction for Server {
     fn update_date() {
         date::update();
     }
+
+    #[cfg(feature = "http2")]
+    fn h2c_upgrade(head: &RequestHead, decode: DecodedLength) -> Option<Bytes> {
+        crate::proto::h2::h2c::upgrade_request(head, decode)
+    }
 }
 
 #[cfg(feature = "server")]
@@ -621,7 +721,7 @@ impl Server {
         };
 
         let mut encoder = Encoder::length(0);
-        let mut wrote_date = false;
+        let mut wrote_date = !msg.date_header;
         let mut cur_name = None;
         let mut is_name_written = false;
         let mut must_write_chunked = false;
@@ -694,7 +794,7 @@ impl Server {
                             }
                             continue 'headers;
                         }
-                        Some(BodyLength::Unknown) => {
+                        Some(BodyLength::Unknown) | Some(BodyLength::CloseDelimited) => {
                             // The HttpBody impl didn't know how long the
                             // body is, but a length header was included.
                             // We have to parse the value to return our
@@ -740,7 +840,7 @@ impl Server {
                             // - The header says the length is `0`.
                             // - This is a response to a `HEAD` request.
                             if msg.req_method == &Some(Method::HEAD) {
-                                debug_assert_eq!(encoder, Encoder::length(0));
+                                debug_assert!(encoder.is_eof());
                             } else {
                                 if value.as_bytes() != b"0" {
                                     warn!(
@@ -842,6 +942,7 @@ impl Server {
                         Encoder::chunked()
                     }
                 }
+                Some(BodyLength::CloseDelimited) => Encoder::close_delimited(),
                 None | Some(BodyLength::Known(0)) => {
                     if Server::can_have_implicit_zero_content_length(
                         msg.req_method,
@@ -895,6 +996,57 @@ impl Server {
     }
 }
 
+/// Rejects the ambiguous lines of a request head that `strict` is set to,
+/// before it is parsed, since the parser would either accept them or reject
+/// them as generic invalid headers.
+#[cfg(feature = "server")]
+fn check_strict_head(buf: &[u8], strict: &StrictParsing) -> Result<(), Parse> {
+    let mut rest = buf;
+    let mut is_request_line = true;
+    loop {
+        let (line, complete) = match rest.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                let line = &rest[..i];
+                rest = &rest[i + 1..];
+                (line, true)
+            }
+            None => (rest, false),
+        };
+        // A CR at the end of a partial line may still be followed by a LF.
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        if strict.bare_cr && line.contains(&b'\r') {
+            debug!("request head with a bare CR");
+            return Err(Parse::Header(Header::BareCr));
+        }
+        if complete && line.is_empty() {
+            if is_request_line {
+                // empty lines before the request line are skipped
+                continue;
+            }
+            return Ok(());
+        }
+        if !is_request_line {
+            if strict.obs_fold && matches!(line.first(), Some(b' ' | b'\t')) {
+                debug!("request head with obsolete line folding");
+                return Err(Parse::Header(Header::ObsFold));
+            }
+            let name = line.split(|&b| b == b':').next().unwrap_or(line);
+            if strict.whitespace_before_colon
+                && name.len() < line.len()
+                && matches!(name.last(), Some(b' ' | b'\t'))
+            {
+                debug!("request head with whitespace before a header colon");
+                return Err(Parse::Header(Header::WhitespaceBeforeColon));
+            }
+        }
+        if !complete {
+            return Ok(());
+        }
+        is_request_line = false;
+    }
+}
+
 #[cfg(feature = "server")]
 trait HeaderNameWriter {
     fn write_full_header_line(
@@ -928,25 +1080,29 @@ impl Http1Transaction for Client {
                 // SAFETY: We can go safely from MaybeUninit array to array of MaybeUninit
                 MaybeUninit::uninit().assume_init()
             };
+            let mut many_headers_indices = Vec::new();
+            let headers_indices = header_slots(
+                &mut headers_indices,
+                &mut many_headers_indices,
+                ctx.h1_max_headers,
+            );
             let (len, status, reason, version, headers_len) = {
                 // SAFETY: We can go safely from MaybeUninit array to array of MaybeUninit
                 let mut headers: [MaybeUninit<httparse::Header<'_>>; MAX_HEADERS] =
                     unsafe { MaybeUninit::uninit().assume_init() };
+                let mut many_headers = Vec::new();
+                let headers = header_slots(&mut headers, &mut many_headers, ctx.h1_max_headers);
                 trace!(bytes = buf.len(), "Response.parse");
                 let mut res = httparse::Response::new(&mut []);
                 let bytes = buf.as_ref();
-                match ctx.h1_parser_config.parse_response_with_uninit_headers(
-                    &mut res,
-                    bytes,
-                    &mut headers,
-                ) {
+                match ctx
+                    .h1_parser_config
+                    .parse_response_with_uninit_headers(&mut res, bytes, headers)
+                {
                     Ok(httparse::Status::Complete(len)) => {
                         trace!("Response.parse Complete({})", len);
                         let status = StatusCode::from_u16(res.code.unwrap())?;
 
-                        #[cfg(not(feature = "ffi"))]
-                        let reason = ();
-                        #[cfg(feature = "ffi")]
                         let reason = {
                             let reason = res.reason.unwrap();
                             // Only save the reason phrase if it isnt the canonical reason
@@ -962,7 +1118,12 @@ impl Http1Transaction for Client {
                         } else {
                             Version::HTTP_10
                         };
-                        record_header_indices(bytes, &res.headers, &mut headers_indices)?;
+                        record_header_indices(
+                            bytes,
+                            &res.headers,
+                            headers_indices,
+                            ctx.h1_max_header_value_size,
+                        )?;
                         let headers_len = res.headers.len();
                         (len, status, reason, version, headers_len)
                     }
@@ -970,9 +1131,6 @@ impl Http1Transaction for Client {
                     Err(httparse::Error::Version) if ctx.h09_responses => {
                         trace!("Response.parse accepted HTTP/0.9 response");
 
-                        #[cfg(not(feature = "ffi"))]
-                        let reason = ();
-                        #[cfg(feature = "ffi")]
                         let reason = None;
 
                         (0, StatusCode::OK, reason, Version::HTTP_09, 0)
@@ -983,6 +1141,17 @@ impl Http1Transaction for Client {
 
             let mut slice = buf.split_to(len);
 
+            let skipped_headers = if ctx.on_violation.is_some() {
+                // SAFETY: array is valid up to `headers_len`
+                let headers = unsafe {
+                    &*(&headers_indices[..headers_len] as *const [MaybeUninit<HeaderIndices>]
+                        as *const [HeaderIndices])
+                };
+                skipped_header_lines(&slice, headers)
+            } else {
+                0
+            };
+
             if ctx
                 .h1_parser_config
                 .obsolete_multiline_headers_in_responses_are_allowed()
@@ -1058,16 +1227,12 @@ impl Http1Transaction for Client {
                 extensions.insert(header_order);
             }
 
-            #[cfg(feature = "ffi")]
             if let Some(reason) = reason {
-                extensions.insert(crate::ffi::ReasonPhrase(reason));
+                extensions.insert(ReasonPhrase::from_bytes_unchecked(reason));
             }
-            #[cfg(not(feature = "ffi"))]
-            drop(reason);
 
-            #[cfg(feature = "ffi")]
             if ctx.raw_headers {
-                extensions.insert(crate::ffi::RawHeaders(crate::ffi::hyper_buf(slice)));
+                extensions.insert(RawHeaders::new(slice));
             }
 
             let head = MessageHead {
@@ -1076,7 +1241,18 @@ impl Http1Transaction for Client {
                 headers,
                 extensions,
             };
-            if let Some((decode, is_upgrade)) = Client::decoder(&head, ctx.req_method)? {
+            if let Some(on_violation) = ctx.on_violation {
+                on_violation.check(
+                    &head,
+                    ctx.req_method.as_ref(),
+                    !buf.is_empty(),
+                    skipped_headers,
+                );
+            }
+
+            if let Some((decode, is_upgrade)) =
+                Client::decoder(&head, ctx.req_method, ctx.content_length_policy)?
+            {
                 return Ok(Some(ParsedMessage {
                     head,
                     decode,
@@ -1088,10 +1264,18 @@ impl Http1Transaction for Client {
                 }));
             }
 
-            #[cfg(feature = "ffi")]
+            *ctx.informational_count += 1;
+            if *ctx.informational_count > ctx.max_informational {
+                debug!(
+                    "exceeded max of {} informational responses",
+                    ctx.max_informational
+                );
+                return Err(Parse::TooManyInformational);
+            }
+
             if head.subject.is_informational() {
                 if let Some(callback) = ctx.on_informational {
-                    callback.call(head.into_response(crate::Body::empty()));
+                    callback.call(head.into_response(()));
                 }
             }
 
@@ -1114,6 +1298,23 @@ impl Http1Transaction for Client {
 
         let body = Client::set_length(msg.head, msg.body);
 
+        // A request forwarded untouched from a server connection that kept
+        // its raw head can be written back out byte-for-byte.
+        #[cfg(feature = "server")]
+        if let Some(raw) = msg.head.extensions.get::<OriginalHead>().and_then(|orig| {
+            orig.raw_if_unchanged(
+                &msg.head.subject.0,
+                &msg.head.subject.1,
+                msg.head.version,
+                &msg.head.headers,
+            )
+        }) {
+            trace!("Client::encode using original raw head");
+            extend(dst, raw);
+            msg.head.headers.clear();
+            return Ok(body);
+        }
+
         let init_cap = 30 + msg.head.headers.len() * AVERAGE_HEADER_SIZE;
         dst.reserve(init_cap);
 
@@ -1170,6 +1371,7 @@ impl Client {
     fn decoder(
         inc: &MessageHead<StatusCode>,
         method: &mut Option<Method>,
+        content_length_policy: ContentLengthPolicy,
     ) -> Result<Option<(DecodedLength, bool)>, Parse> {
         // According to https://tools.ietf.org/html/rfc7230#section-3.3.3
         // 1. HEAD responses, and Status 1xx, 204, and 304 cannot have a body.
@@ -1220,19 +1422,33 @@ impl Client {
                 trace!("not chunked, read till eof");
                 Ok(Some((DecodedLength::CLOSE_DELIMITED, false)))
             }
-        } else if let Some(len) = headers::content_length_parse_all(&inc.headers) {
+        } else if let Some(len) =
+            headers::content_length_parse_with_policy(&inc.headers, content_length_policy)?
+        {
             Ok(Some((DecodedLength::checked_new(len)?, false)))
-        } else if inc.headers.contains_key(header::CONTENT_LENGTH) {
-            debug!("illegal Content-Length header");
-            Err(Parse::content_length_invalid())
         } else {
             trace!("neither Transfer-Encoding nor Content-Length");
             Ok(Some((DecodedLength::CLOSE_DELIMITED, false)))
         }
     }
     fn set_length(head: &mut RequestHead, body: Option<BodyLength>) -> Encoder {
+        // A CONNECT request has no content: once it is sent, the connection
+        // is the tunnel (RFC 9110, Section 9.3.6).
+        if head.subject.0 == Method::CONNECT {
+            if !matches!(body, None | Some(BodyLength::Known(0))) {
+                warn!("ignoring body of CONNECT request");
+            }
+            head.headers.remove(header::TRANSFER_ENCODING);
+            head.headers.remove(header::CONTENT_LENGTH);
+            return Encoder::length(0);
+        }
+
         let body = if let Some(body) = body {
-            body
+            // A request can't be delimited by closing the connection.
+            match body {
+                BodyLength::CloseDelimited => BodyLength::Unknown,
+                body => body,
+            }
         } else {
             head.headers.remove(header::TRANSFER_ENCODING);
             return Encoder::length(0);
@@ -1382,10 +1598,64 @@ struct HeaderIndices {
     value: (usize, usize),
 }
 
+/// Counts the lines of a response head that are not part of a parsed
+/// header, having been skipped as invalid by httparse.
+#[cfg(feature = "client")]
+fn skipped_header_lines(head: &[u8], headers: &[HeaderIndices]) -> usize {
+    let mut headers = headers.iter().peekable();
+    let mut skipped = 0;
+    // the status line is never a header
+    let mut pos = match head.iter().position(|&b| b == b'\n') {
+        Some(i) => i + 1,
+        None => return 0,
+    };
+    while pos < head.len() {
+        let end = head[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(head.len(), |i| pos + i + 1);
+        let line = &head[pos..end];
+        if line != b"\r\n" && line != b"\n" {
+            while let Some(h) = headers.peek() {
+                if h.value.1 >= pos {
+                    break;
+                }
+                headers.next();
+            }
+            // a line starting inside a header is one of its continuations
+            match headers.peek() {
+                Some(h) if h.name.0 <= pos => (),
+                _ => skipped += 1,
+            }
+        }
+        pos = end;
+    }
+    skipped
+}
+
+/// Returns the slots for parsing up to `max` headers, or the default number
+/// of them, taken from `stack` if they fit in it, or else allocated in
+/// `heap`.
+fn header_slots<'a, T: Copy>(
+    stack: &'a mut [MaybeUninit<T>; MAX_HEADERS],
+    heap: &'a mut Vec<MaybeUninit<T>>,
+    max: Option<usize>,
+) -> &'a mut [MaybeUninit<T>] {
+    match max {
+        Some(max) if max > MAX_HEADERS => {
+            heap.resize(max, MaybeUninit::uninit());
+            heap
+        }
+        Some(max) => &mut stack[..max],
+        None => stack,
+    }
+}
+
 fn record_header_indices(
     bytes: &[u8],
     headers: &[httparse::Header<'_>],
     indices: &mut [MaybeUninit<HeaderIndices>],
+    max_value_size: Option<usize>,
 ) -> Result<(), crate::error::Parse> {
     let bytes_ptr = bytes.as_ptr() as usize;
 
@@ -1394,6 +1664,13 @@ fn record_header_indices(
             debug!("header name larger than 64kb: {:?}", header.name);
             return Err(crate::error::Parse::TooLarge);
         }
+        match max_value_size {
+            Some(max) if header.value.len() > max => {
+                debug!("header value larger than {} bytes: {:?}", max, header.name);
+                return Err(crate::error::Parse::HeaderValueTooLarge);
+            }
+            _ => (),
+        }
         let name_start = header.name.as_ptr() as usize - bytes_ptr;
         let name_end = name_start + header.name.len();
         let value_start = header.value.as_ptr() as usize - bytes_ptr;
@@ -1438,6 +1715,18 @@ fn write_headers_title_case(headers: &HeaderMap, dst: &mut Vec<u8>) {
     }
 }
 
+/// Writes a `103 Early Hints` response with these headers.
+#[cfg(feature = "server")]
+pub(super) fn encode_early_hints(headers: &HeaderMap, title_case_headers: bool, dst: &mut Vec<u8>) {
+    extend(dst, b"HTTP/1.1 103 Early Hints\r\n");
+    if title_case_headers {
+        write_headers_title_case(headers, dst);
+    } else {
+        write_headers(headers, dst);
+    }
+    extend(dst, b"\r\n");
+}
+
 fn write_headers(headers: &HeaderMap, dst: &mut Vec<u8>) {
     for (name, value) in headers {
         extend(dst, name.as_str().as_bytes());
@@ -1508,6 +1797,7 @@ mod tests {
     use bytes::BytesMut;
 
     use super::*;
+    use crate::common::host::AllowedHosts;
 
     #[test]
     fn test_parse_request() {
@@ -1530,10 +1820,23 @@ mod tests {
                 #[cfg(feature = "ffi")]
                 preserve_header_order: false,
                 h09_responses: false,
-                #[cfg(feature = "ffi")]
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
                 on_informational: &mut None,
-                #[cfg(feature = "ffi")]
                 raw_headers: false,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: Default::default(),
             },
         )
         .unwrap()
@@ -1547,6 +1850,323 @@ mod tests {
         assert_eq!(method, Some(crate::Method::GET));
     }
 
+    #[test]
+    fn test_parse_request_raw_headers() {
+        let head = "GET /echo HTTP/1.1\r\nHOST:  hyper.rs\r\nx-sig: a\r\n\r\n";
+        let mut raw = BytesMut::from(head);
+        let msg = Server::parse(
+            &mut raw,
+            ParseContext {
+                cached_headers: &mut None,
+                req_method: &mut None,
+                h1_parser_config: Default::default(),
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout: None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_fut: &mut None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_running: &mut false,
+                preserve_header_case: false,
+                #[cfg(feature = "ffi")]
+                preserve_header_order: false,
+                h09_responses: false,
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
+                on_informational: &mut None,
+                raw_headers: true,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: Default::default(),
+            },
+        )
+        .unwrap()
+        .unwrap();
+        let raw_headers = msg.head.extensions.get::<RawHeaders>().unwrap();
+        assert_eq!(raw_headers.as_bytes(), head.as_bytes());
+    }
+
+    #[test]
+    fn test_parse_request_allowed_hosts() {
+        let allowed_hosts = AllowedHosts::new(["hyper.rs", "*.hyper.rs"]);
+        let parse = |s: &str| {
+            let mut raw = BytesMut::from(s);
+            Server::parse(
+                &mut raw,
+                ParseContext {
+                    cached_headers: &mut None,
+                    req_method: &mut None,
+                    h1_parser_config: Default::default(),
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout: None,
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout_fut: &mut None,
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout_running: &mut false,
+                    preserve_header_case: false,
+                    #[cfg(feature = "ffi")]
+                    preserve_header_order: false,
+                    h09_responses: false,
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    allowed_hosts: Some(&allowed_hosts),
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
+                    on_informational: &mut None,
+                    raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
+                },
+            )
+            .map(|msg| msg.unwrap().head.subject.1)
+        };
+
+        assert!(parse("GET / HTTP/1.1\r\nHost: www.hyper.rs\r\n\r\n").is_ok());
+        assert!(parse("GET http://hyper.rs/ HTTP/1.1\r\nHost: evil.local\r\n\r\n").is_ok());
+        for req in &[
+            "GET / HTTP/1.1\r\nHost: evil.local\r\n\r\n",
+            "GET http://evil.local/ HTTP/1.1\r\nHost: hyper.rs\r\n\r\n",
+            "GET / HTTP/1.0\r\n\r\n",
+        ] {
+            match parse(req) {
+                Err(Parse::Misdirected) => (),
+                other => panic!("{:?} should be misdirected, got {:?}", req, other),
+            }
+        }
+
+        let err = crate::Error::new(crate::error::Kind::Parse(Parse::Misdirected));
+        let head = Server::on_error(&err).unwrap();
+        assert_eq!(head.subject, StatusCode::MISDIRECTED_REQUEST);
+    }
+
+    #[test]
+    fn test_parse_request_strict() {
+        let parse = |s: &str, strict: StrictParsing| {
+            let mut raw = BytesMut::from(s);
+            Server::parse(
+                &mut raw,
+                ParseContext {
+                    cached_headers: &mut None,
+                    req_method: &mut None,
+                    h1_parser_config: Default::default(),
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout: None,
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout_fut: &mut None,
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout_running: &mut false,
+                    preserve_header_case: false,
+                    #[cfg(feature = "ffi")]
+                    preserve_header_order: false,
+                    h09_responses: false,
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    allowed_hosts: None,
+                    strict,
+                    #[cfg(feature = "client")]
+                    on_informational: &mut None,
+                    raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
+                },
+            )
+            .map(|msg| msg.map(|msg| msg.decode))
+        };
+        let all = StrictParsing {
+            content_length_with_transfer_encoding: true,
+            obs_fold: true,
+            bare_cr: true,
+            whitespace_before_colon: true,
+        };
+
+        // the checks are each toggled
+        let cl_te = "POST / HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert_eq!(
+            parse(cl_te, StrictParsing::default()).unwrap(),
+            Some(DecodedLength::CHUNKED)
+        );
+        assert!(matches!(
+            parse(cl_te, all),
+            Err(Parse::Header(Header::ContentLengthWithTransferEncoding))
+        ));
+        assert!(matches!(
+            parse("GET / HTTP/1.1\r\nA: b\r\n c\r\n\r\n", all),
+            Err(Parse::Header(Header::ObsFold))
+        ));
+        assert!(matches!(
+            parse("GET / HTTP/1.1\r\nA: b\rc\r\n\r\n", all),
+            Err(Parse::Header(Header::BareCr))
+        ));
+        assert!(matches!(
+            parse("GET / HTTP/1.1\r\nA : b\r\n\r\n", all),
+            Err(Parse::Header(Header::WhitespaceBeforeColon))
+        ));
+        assert!(matches!(
+            parse(
+                "GET / HTTP/1.1\r\nA: b\r\n c\r\n\r\n",
+                StrictParsing::default()
+            ),
+            Err(Parse::Header(Header::Token))
+        ));
+
+        // valid and partial heads pass, and bodies are not scanned
+        assert!(parse(
+            "\r\nPOST / HTTP/1.1\r\nA: b\r\nContent-Length: 5\r\n\r\n\r\n b:",
+            all
+        )
+        .unwrap()
+        .is_some());
+        assert!(parse("GET / HTTP/1.1\r\nA: b\r", all).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_content_length_policy() {
+        let parse_req = |s: &str, content_length_policy| {
+            let mut raw = BytesMut::from(s);
+            Server::parse(
+                &mut raw,
+                ParseContext {
+                    cached_headers: &mut None,
+                    req_method: &mut None,
+                    h1_parser_config: Default::default(),
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout: None,
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout_fut: &mut None,
+                    #[cfg(feature = "runtime")]
+                    h1_header_read_timeout_running: &mut false,
+                    preserve_header_case: false,
+                    #[cfg(feature = "ffi")]
+                    preserve_header_order: false,
+                    h09_responses: false,
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    allowed_hosts: None,
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
+                    on_informational: &mut None,
+                    raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy,
+                },
+            )
+            .map(|msg| {
+                let msg = msg.unwrap();
+                let lengths = msg.head.headers.get_all("content-length");
+                (
+                    msg.decode,
+                    lengths
+                        .iter()
+                        .map(|v| v.to_str().unwrap().to_owned())
+                        .collect::<Vec<_>>(),
+                )
+            })
+        };
+        let decode_res = |values: &[&str], policy| {
+            let mut head = MessageHead::<StatusCode>::default();
+            for value in values {
+                head.headers
+                    .append("content-length", value.parse().unwrap());
+            }
+            Client::decoder(&head, &mut Some(Method::GET), policy).map(|decode| decode.unwrap().0)
+        };
+
+        let same = "POST / HTTP/1.1\r\ncontent-length: 10\r\ncontent-length: 10\r\n\r\n";
+        let differing = "POST / HTTP/1.1\r\ncontent-length: 10\r\ncontent-length: 11\r\n\r\n";
+        let same_list = "POST / HTTP/1.1\r\ncontent-length: 10, 10\r\n\r\n";
+        let differing_list = "POST / HTTP/1.1\r\ncontent-length: 10, 11\r\n\r\n";
+
+        // merging equal values is the default
+        assert_eq!(
+            parse_req(same, ContentLengthPolicy::MergeIfEqual).unwrap(),
+            (DecodedLength::new(10), vec!["10".to_owned()])
+        );
+        assert!(matches!(
+            parse_req(differing, ContentLengthPolicy::MergeIfEqual),
+            Err(Parse::Header(Header::ContentLengthMismatch))
+        ));
+        assert_eq!(
+            parse_req(same_list, ContentLengthPolicy::MergeIfEqual).unwrap(),
+            (DecodedLength::new(10), vec!["10".to_owned()])
+        );
+        assert!(matches!(
+            parse_req(differing_list, ContentLengthPolicy::MergeIfEqual),
+            Err(Parse::Header(Header::ContentLengthMismatch))
+        ));
+        assert_eq!(
+            decode_res(&["10, 10"], ContentLengthPolicy::MergeIfEqual).unwrap(),
+            DecodedLength::new(10)
+        );
+        assert!(matches!(
+            decode_res(&["10", "11"], ContentLengthPolicy::MergeIfEqual),
+            Err(Parse::Header(Header::ContentLengthMismatch))
+        ));
+
+        assert!(matches!(
+            parse_req(same, ContentLengthPolicy::Reject),
+            Err(Parse::Header(Header::ContentLengthMultiple))
+        ));
+        assert!(matches!(
+            parse_req(same_list, ContentLengthPolicy::Reject),
+            Err(Parse::Header(Header::ContentLengthMultiple))
+        ));
+        assert!(matches!(
+            decode_res(&["10, 10"], ContentLengthPolicy::Reject),
+            Err(Parse::Header(Header::ContentLengthMultiple))
+        ));
+        assert_eq!(
+            decode_res(&["10"], ContentLengthPolicy::Reject).unwrap(),
+            DecodedLength::new(10)
+        );
+
+        assert_eq!(
+            parse_req(differing, ContentLengthPolicy::FirstWins).unwrap(),
+            (DecodedLength::new(10), vec!["10".to_owned()])
+        );
+        assert_eq!(
+            parse_req(differing_list, ContentLengthPolicy::FirstWins).unwrap(),
+            (DecodedLength::new(10), vec!["10".to_owned()])
+        );
+        assert_eq!(
+            decode_res(&["10", "bogus"], ContentLengthPolicy::FirstWins).unwrap(),
+            DecodedLength::new(10)
+        );
+
+        let err = crate::Error::new(crate::error::Kind::Parse(Parse::content_length_mismatch()));
+        assert!(err.is_parse_conflicting_content_length());
+        assert_eq!(
+            Server::on_error(&err).unwrap().subject,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
     #[test]
     fn test_parse_response() {
         let _ = pretty_env_logger::try_init();
@@ -1565,10 +2185,23 @@ mod tests {
             #[cfg(feature = "ffi")]
             preserve_header_order: false,
             h09_responses: false,
-            #[cfg(feature = "ffi")]
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
-            #[cfg(feature = "ffi")]
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         let msg = Client::parse(&mut raw, ctx).unwrap().unwrap();
         assert_eq!(raw.len(), 0);
@@ -1595,10 +2228,23 @@ mod tests {
             #[cfg(feature = "ffi")]
             preserve_header_order: false,
             h09_responses: false,
-            #[cfg(feature = "ffi")]
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
-            #[cfg(feature = "ffi")]
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         Server::parse(&mut raw, ctx).unwrap_err();
     }
@@ -1623,10 +2269,23 @@ mod tests {
             #[cfg(feature = "ffi")]
             preserve_header_order: false,
             h09_responses: true,
-            #[cfg(feature = "ffi")]
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
-            #[cfg(feature = "ffi")]
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         let msg = Client::parse(&mut raw, ctx).unwrap().unwrap();
         assert_eq!(raw, H09_RESPONSE);
@@ -1653,44 +2312,237 @@ mod tests {
             #[cfg(feature = "ffi")]
             preserve_header_order: false,
             h09_responses: false,
-            #[cfg(feature = "ffi")]
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
-            #[cfg(feature = "ffi")]
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         Client::parse(&mut raw, ctx).unwrap_err();
         assert_eq!(raw, H09_RESPONSE);
     }
 
-    const RESPONSE_WITH_WHITESPACE_BETWEEN_HEADER_NAME_AND_COLON: &'static str =
-        "HTTP/1.1 200 OK\r\nAccess-Control-Allow-Credentials : true\r\n\r\n";
-
     #[test]
-    fn test_parse_allow_response_with_spaces_before_colons() {
-        use httparse::ParserConfig;
-
+    fn test_parse_response_max_informational() {
         let _ = pretty_env_logger::try_init();
-        let mut raw = BytesMut::from(RESPONSE_WITH_WHITESPACE_BETWEEN_HEADER_NAME_AND_COLON);
-        let mut h1_parser_config = ParserConfig::default();
-        h1_parser_config.allow_spaces_after_header_name_in_responses(true);
-        let ctx = ParseContext {
-            cached_headers: &mut None,
-            req_method: &mut Some(crate::Method::GET),
-            h1_parser_config,
-            #[cfg(feature = "runtime")]
-            h1_header_read_timeout: None,
-            #[cfg(feature = "runtime")]
-            h1_header_read_timeout_fut: &mut None,
-            #[cfg(feature = "runtime")]
-            h1_header_read_timeout_running: &mut false,
-            preserve_header_case: false,
-            #[cfg(feature = "ffi")]
-            preserve_header_order: false,
-            h09_responses: false,
+        const CONTINUE: &str = "HTTP/1.1 100 Continue\r\n\r\n";
+        fn parse(raw: &mut BytesMut, max: usize, count: &mut usize) -> ParseResult<StatusCode> {
+            let ctx = ParseContext {
+                cached_headers: &mut None,
+                req_method: &mut Some(crate::Method::GET),
+                h1_parser_config: Default::default(),
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout: None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_fut: &mut None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_running: &mut false,
+                preserve_header_case: false,
+                #[cfg(feature = "ffi")]
+                preserve_header_order: false,
+                h09_responses: false,
+                preserve_raw_head: false,
+                max_informational: max,
+                informational_count: count,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
+                on_informational: &mut None,
+                raw_headers: false,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: Default::default(),
+            };
+            Client::parse(raw, ctx)
+        }
+
+        let ok = format!("{}{}HTTP/1.1 200 OK\r\n\r\n", CONTINUE, CONTINUE);
+        let msg = parse(&mut BytesMut::from(&ok[..]), 2, &mut 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg.head.subject, StatusCode::OK);
+        let err = parse(&mut BytesMut::from(&ok[..]), 1, &mut 0).unwrap_err();
+        assert!(matches!(err, Parse::TooManyInformational));
+
+        // The count carries over responses split across reads.
+        let mut count = 0;
+        let mut raw = BytesMut::from(CONTINUE);
+        assert!(parse(&mut raw, 1, &mut count).unwrap().is_none());
+        assert_eq!(count, 1);
+        raw.extend_from_slice(CONTINUE.as_bytes());
+        parse(&mut raw, 1, &mut count).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_max_headers() {
+        let _ = pretty_env_logger::try_init();
+        fn head(start: &str, count: usize) -> BytesMut {
+            let mut head = String::from(start);
+            for i in 0..count {
+                head.push_str(&format!("x-{}: {}\r\n", i, "v".repeat(i)));
+            }
+            head.push_str("\r\n");
+            BytesMut::from(&head[..])
+        }
+        fn parse_request(
+            count: usize,
+            max_headers: Option<usize>,
+            max_value_size: Option<usize>,
+        ) -> ParseResult<RequestLine> {
+            let mut raw = head("GET / HTTP/1.1\r\n", count);
+            let ctx = ParseContext {
+                cached_headers: &mut None,
+                req_method: &mut None,
+                h1_parser_config: Default::default(),
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout: None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_fut: &mut None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_running: &mut false,
+                preserve_header_case: false,
+                #[cfg(feature = "ffi")]
+                preserve_header_order: false,
+                h09_responses: false,
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
+                on_informational: &mut None,
+                raw_headers: false,
+                h1_max_headers: max_headers,
+                h1_max_header_value_size: max_value_size,
+                content_length_policy: Default::default(),
+            };
+            Server::parse(&mut raw, ctx)
+        }
+        fn parse_response(
+            count: usize,
+            max_headers: Option<usize>,
+            max_value_size: Option<usize>,
+        ) -> ParseResult<StatusCode> {
+            let mut raw = head("HTTP/1.1 200 OK\r\n", count);
+            let ctx = ParseContext {
+                cached_headers: &mut None,
+                req_method: &mut Some(crate::Method::GET),
+                h1_parser_config: Default::default(),
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout: None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_fut: &mut None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_running: &mut false,
+                preserve_header_case: false,
+                #[cfg(feature = "ffi")]
+                preserve_header_order: false,
+                h09_responses: false,
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
+                on_informational: &mut None,
+                raw_headers: false,
+                h1_max_headers: max_headers,
+                h1_max_header_value_size: max_value_size,
+                content_length_policy: Default::default(),
+            };
+            Client::parse(&mut raw, ctx)
+        }
+
+        let msg = parse_request(100, None, None).unwrap().unwrap();
+        assert_eq!(msg.head.headers.len(), 100);
+        let err = parse_request(101, None, None).unwrap_err();
+        assert!(matches!(err, Parse::TooManyHeaders));
+        parse_request(10, Some(10), None).unwrap().unwrap();
+        let err = parse_request(11, Some(10), None).unwrap_err();
+        assert!(matches!(err, Parse::TooManyHeaders));
+        // more headers than fit on the stack
+        let msg = parse_response(150, Some(150), None).unwrap().unwrap();
+        assert_eq!(msg.head.headers.len(), 150);
+        let err = parse_response(151, Some(150), None).unwrap_err();
+        assert!(matches!(err, Parse::TooManyHeaders));
+
+        parse_request(11, None, Some(10)).unwrap().unwrap();
+        let err = parse_request(12, None, Some(10)).unwrap_err();
+        assert!(matches!(err, Parse::HeaderValueTooLarge));
+        let err = parse_response(12, None, Some(10)).unwrap_err();
+        assert!(matches!(err, Parse::HeaderValueTooLarge));
+    }
+
+    const RESPONSE_WITH_WHITESPACE_BETWEEN_HEADER_NAME_AND_COLON: &'static str =
+        "HTTP/1.1 200 OK\r\nAccess-Control-Allow-Credentials : true\r\n\r\n";
+
+    #[test]
+    fn test_parse_allow_response_with_spaces_before_colons() {
+        use httparse::ParserConfig;
+
+        let _ = pretty_env_logger::try_init();
+        let mut raw = BytesMut::from(RESPONSE_WITH_WHITESPACE_BETWEEN_HEADER_NAME_AND_COLON);
+        let mut h1_parser_config = ParserConfig::default();
+        h1_parser_config.allow_spaces_after_header_name_in_responses(true);
+        let ctx = ParseContext {
+            cached_headers: &mut None,
+            req_method: &mut Some(crate::Method::GET),
+            h1_parser_config,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout: None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_fut: &mut None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_running: &mut false,
+            preserve_header_case: false,
             #[cfg(feature = "ffi")]
+            preserve_header_order: false,
+            h09_responses: false,
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
-            #[cfg(feature = "ffi")]
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         let msg = Client::parse(&mut raw, ctx).unwrap().unwrap();
         assert_eq!(raw.len(), 0);
@@ -1718,14 +2570,186 @@ mod tests {
             #[cfg(feature = "ffi")]
             preserve_header_order: false,
             h09_responses: false,
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
+            on_informational: &mut None,
+            raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
+        };
+        Client::parse(&mut raw, ctx).unwrap_err();
+    }
+
+    const RESPONSE_WITH_OBSOLETE_LINE_FOLDING: &'static str =
+        "HTTP/1.1 200 OK\r\nX-Folded: first\r\n second\r\n\tthird\r\nX-Next: ok\r\n\r\n";
+
+    #[test]
+    fn test_parse_allow_response_with_obsolete_line_folding() {
+        use httparse::ParserConfig;
+
+        let _ = pretty_env_logger::try_init();
+        let mut raw = BytesMut::from(RESPONSE_WITH_OBSOLETE_LINE_FOLDING);
+        let mut h1_parser_config = ParserConfig::default();
+        h1_parser_config.allow_obsolete_multiline_headers_in_responses(true);
+        let ctx = ParseContext {
+            cached_headers: &mut None,
+            req_method: &mut Some(crate::Method::GET),
+            h1_parser_config,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout: None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_fut: &mut None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_running: &mut false,
+            preserve_header_case: false,
             #[cfg(feature = "ffi")]
+            preserve_header_order: false,
+            h09_responses: false,
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
+            raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
+        };
+        let msg = Client::parse(&mut raw, ctx).unwrap().unwrap();
+        assert_eq!(raw.len(), 0);
+        assert_eq!(msg.head.headers.len(), 2);
+        assert_eq!(msg.head.headers["X-Folded"], "first   second  \tthird");
+        assert_eq!(msg.head.headers["X-Next"], "ok");
+    }
+
+    #[test]
+    fn test_parse_reject_response_with_obsolete_line_folding() {
+        let _ = pretty_env_logger::try_init();
+        let mut raw = BytesMut::from(RESPONSE_WITH_OBSOLETE_LINE_FOLDING);
+        let ctx = ParseContext {
+            cached_headers: &mut None,
+            req_method: &mut Some(crate::Method::GET),
+            h1_parser_config: Default::default(),
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout: None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_fut: &mut None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_running: &mut false,
+            preserve_header_case: false,
             #[cfg(feature = "ffi")]
+            preserve_header_order: false,
+            h09_responses: false,
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
+            on_informational: &mut None,
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         Client::parse(&mut raw, ctx).unwrap_err();
     }
 
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_parse_ignore_invalid_headers_in_response() {
+        use std::sync::{Arc, Mutex};
+
+        use httparse::ParserConfig;
+
+        use crate::client::validate::OnViolation;
+        use crate::client::ResponseViolation;
+
+        let _ = pretty_env_logger::try_init();
+        let mut raw = BytesMut::from(
+            "HTTP/1.1 200 OK\r\nX-Good: a\r\nBad Header\r\nX-Folded: b\r\n c\r\n: empty\r\nX-Last: d\r\n\r\n",
+        );
+        let mut h1_parser_config = ParserConfig::default();
+        h1_parser_config
+            .allow_obsolete_multiline_headers_in_responses(true)
+            .ignore_invalid_headers_in_responses(true);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let on_violation = {
+            let seen = seen.clone();
+            OnViolation::new(move |_, violation| seen.lock().unwrap().push(violation))
+        };
+        let ctx = ParseContext {
+            cached_headers: &mut None,
+            req_method: &mut Some(crate::Method::GET),
+            h1_parser_config,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout: None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_fut: &mut None,
+            #[cfg(feature = "runtime")]
+            h1_header_read_timeout_running: &mut false,
+            preserve_header_case: false,
+            #[cfg(feature = "ffi")]
+            preserve_header_order: false,
+            h09_responses: false,
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: Some(&on_violation),
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
+            on_informational: &mut None,
+            raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
+        };
+        let msg = Client::parse(&mut raw, ctx).unwrap().unwrap();
+        assert_eq!(raw.len(), 0);
+        assert_eq!(msg.head.headers.len(), 3);
+        assert_eq!(msg.head.headers["X-Good"], "a");
+        assert_eq!(msg.head.headers["X-Folded"], "b   c");
+        assert_eq!(msg.head.headers["X-Last"], "d");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                ResponseViolation::InvalidHeader,
+                ResponseViolation::InvalidHeader
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_preserve_header_case_in_request() {
         let mut raw =
@@ -1744,10 +2768,23 @@ mod tests {
             #[cfg(feature = "ffi")]
             preserve_header_order: false,
             h09_responses: false,
-            #[cfg(feature = "ffi")]
+            preserve_raw_head: false,
+            #[cfg(feature = "client")]
+            max_informational: usize::MAX,
+            #[cfg(feature = "client")]
+            informational_count: &mut 0,
+            #[cfg(feature = "client")]
+            on_violation: None,
+            #[cfg(feature = "server")]
+            allowed_hosts: None,
+            #[cfg(feature = "server")]
+            strict: Default::default(),
+            #[cfg(feature = "client")]
             on_informational: &mut None,
-            #[cfg(feature = "ffi")]
             raw_headers: false,
+            h1_max_headers: None,
+            h1_max_header_value_size: None,
+            content_length_policy: Default::default(),
         };
         let parsed_message = Server::parse(&mut raw, ctx).unwrap().unwrap();
         let orig_headers = parsed_message
@@ -1791,10 +2828,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 },
             )
             .expect("parse ok")
@@ -1819,10 +2869,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 },
             )
             .expect_err(comment)
@@ -2056,10 +3119,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 }
             )
             .expect("parse ok")
@@ -2084,10 +3160,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 },
             )
             .expect("parse ok")
@@ -2112,10 +3201,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 },
             )
             .expect_err("parse should err")
@@ -2381,6 +3483,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_client_request_encode_connect() {
+        use crate::proto::BodyLength;
+
+        let mut head = MessageHead {
+            subject: RequestLine(Method::CONNECT, "example.com:443".parse().unwrap()),
+            ..Default::default()
+        };
+        head.headers
+            .insert("content-length", HeaderValue::from_static("5"));
+
+        let mut vec = Vec::new();
+        let encoder = Client::encode(
+            Encode {
+                head: &mut head,
+                body: Some(BodyLength::Known(5)),
+                keep_alive: true,
+                date_header: true,
+                req_method: &mut None,
+                title_case_headers: false,
+            },
+            &mut vec,
+        )
+        .unwrap();
+
+        assert!(encoder.is_eof());
+        assert_eq!(vec, b"CONNECT example.com:443 HTTP/1.1\r\n\r\n");
+    }
+
     #[test]
     fn test_client_request_encode_title_case() {
         use crate::proto::BodyLength;
@@ -2399,6 +3530,7 @@ mod tests {
                 head: &mut head,
                 body: Some(BodyLength::Known(10)),
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut None,
                 title_case_headers: true,
             },
@@ -2430,6 +3562,7 @@ mod tests {
                 head: &mut head,
                 body: Some(BodyLength::Known(10)),
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut None,
                 title_case_headers: false,
             },
@@ -2464,6 +3597,7 @@ mod tests {
                 head: &mut head,
                 body: Some(BodyLength::Known(10)),
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut None,
                 title_case_headers: true,
             },
@@ -2478,6 +3612,94 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_client_request_encode_preserved_raw_head() {
+        use crate::proto::BodyLength;
+
+        fn parse(raw: &str) -> MessageHead<RequestLine> {
+            let mut raw = BytesMut::from(raw);
+            let ctx = ParseContext {
+                cached_headers: &mut None,
+                req_method: &mut None,
+                h1_parser_config: Default::default(),
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout: None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_fut: &mut None,
+                #[cfg(feature = "runtime")]
+                h1_header_read_timeout_running: &mut false,
+                preserve_header_case: false,
+                #[cfg(feature = "ffi")]
+                preserve_header_order: false,
+                h09_responses: false,
+                preserve_raw_head: true,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
+                on_informational: &mut None,
+                raw_headers: false,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: Default::default(),
+            };
+            Server::parse(&mut raw, ctx).unwrap().unwrap().head
+        }
+
+        fn encode(head: &mut MessageHead<RequestLine>) -> Vec<u8> {
+            let mut vec = Vec::new();
+            Client::encode(
+                Encode {
+                    head,
+                    body: Some(BodyLength::Known(3)),
+                    keep_alive: true,
+                    date_header: true,
+                    req_method: &mut None,
+                    title_case_headers: false,
+                },
+                &mut vec,
+            )
+            .unwrap();
+            vec
+        }
+
+        let raw = "POST /echo HTTP/1.1\r\nhOsT:   hyper.rs  \r\nContent-Length:3\r\n\r\n";
+
+        let mut head = parse(raw);
+        assert_eq!(encode(&mut head), raw.as_bytes());
+
+        // any modification falls back to regular serialization
+        let mut head = parse(raw);
+        head.headers
+            .insert("x-forwarded", HeaderValue::from_static("1"));
+        assert_eq!(
+            encode(&mut head),
+            b"POST /echo HTTP/1.1\r\nhost: hyper.rs\r\ncontent-length: 3\r\nx-forwarded: 1\r\n\r\n"
+                .as_ref(),
+        );
+
+        // ignored duplicate Content-Length headers are never re-emitted
+        let head = parse("POST / HTTP/1.1\r\nContent-Length: 3\r\ncontent-length: 3\r\n\r\n");
+        assert!(head.extensions.get::<OriginalHead>().is_none());
+
+        // both framings are never forwarded together
+        let mut head =
+            parse("POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n");
+        assert!(head.extensions.get::<OriginalHead>().is_none());
+        assert!(!head.headers.contains_key("content-length"));
+        assert_eq!(
+            encode(&mut head),
+            b"POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n".as_ref(),
+        );
+    }
+
     #[test]
     fn test_server_encode_connect_method() {
         let mut head = MessageHead::default();
@@ -2488,6 +3710,7 @@ mod tests {
                 head: &mut head,
                 body: None,
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut Some(Method::CONNECT),
                 title_case_headers: false,
             },
@@ -2498,6 +3721,42 @@ mod tests {
         assert!(encoder.is_last());
     }
 
+    #[test]
+    fn test_server_encode_date_header() {
+        fn encode(head: &mut MessageHead<StatusCode>, date_header: bool) -> String {
+            let mut vec = Vec::new();
+            Server::encode(
+                Encode {
+                    head,
+                    body: None,
+                    keep_alive: true,
+                    date_header,
+                    req_method: &mut None,
+                    title_case_headers: false,
+                },
+                &mut vec,
+            )
+            .unwrap();
+            String::from_utf8(vec).unwrap()
+        }
+
+        let mut head = MessageHead::default();
+        assert!(encode(&mut head, true).contains("\r\ndate: "));
+        assert_eq!(
+            encode(&mut head, false),
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n"
+        );
+
+        head.extensions.insert(OmitDate);
+        assert!(!encode(&mut head, true).contains("date: "));
+
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        head.headers.insert(header::DATE, date.parse().unwrap());
+        let encoded = encode(&mut head, true);
+        assert!(encoded.contains(&format!("\r\ndate: {}\r\n", date)));
+        assert_eq!(encoded.matches("date: ").count(), 1);
+    }
+
     #[test]
     fn test_server_response_encode_title_case() {
         use crate::proto::BodyLength;
@@ -2517,6 +3776,7 @@ mod tests {
                 head: &mut head,
                 body: Some(BodyLength::Known(10)),
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut None,
                 title_case_headers: true,
             },
@@ -2530,6 +3790,54 @@ mod tests {
         assert_eq!(&vec[..expected_response.len()], &expected_response[..]);
     }
 
+    #[test]
+    fn test_server_response_encode_close_delimited() {
+        use crate::proto::BodyLength;
+
+        let mut head = MessageHead::default();
+        let mut vec = Vec::new();
+        let encoder = Server::encode(
+            Encode {
+                head: &mut head,
+                body: Some(BodyLength::CloseDelimited),
+                keep_alive: true,
+                date_header: false,
+                req_method: &mut Some(Method::GET),
+                title_case_headers: false,
+            },
+            &mut vec,
+        )
+        .unwrap();
+
+        assert!(encoder.is_close_delimited());
+        assert_eq!(vec, b"HTTP/1.1 200 OK\r\n\r\n");
+    }
+
+    #[test]
+    fn test_server_response_encode_reason_phrase() {
+        use crate::proto::BodyLength;
+
+        let mut head = MessageHead::default();
+        head.extensions
+            .insert(ReasonPhrase::from_static(b"Very OK"));
+
+        let mut vec = Vec::new();
+        Server::encode(
+            Encode {
+                head: &mut head,
+                body: Some(BodyLength::Known(0)),
+                keep_alive: true,
+                date_header: true,
+                req_method: &mut None,
+                title_case_headers: false,
+            },
+            &mut vec,
+        )
+        .unwrap();
+
+        assert!(vec.starts_with(b"HTTP/1.1 200 Very OK\r\n"), "{:?}", vec);
+    }
+
     #[test]
     fn test_server_response_encode_orig_case() {
         use crate::proto::BodyLength;
@@ -2551,6 +3859,7 @@ mod tests {
                 head: &mut head,
                 body: Some(BodyLength::Known(10)),
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut None,
                 title_case_headers: false,
             },
@@ -2585,6 +3894,7 @@ mod tests {
                 head: &mut head,
                 body: Some(BodyLength::Known(10)),
                 keep_alive: true,
+                date_header: true,
                 req_method: &mut None,
                 title_case_headers: true,
             },
@@ -2617,10 +3927,23 @@ mod tests {
                 #[cfg(feature = "ffi")]
                 preserve_header_order: false,
                 h09_responses: false,
-                #[cfg(feature = "ffi")]
+                preserve_raw_head: false,
+                #[cfg(feature = "client")]
+                max_informational: usize::MAX,
+                #[cfg(feature = "client")]
+                informational_count: &mut 0,
+                #[cfg(feature = "client")]
+                on_violation: None,
+                #[cfg(feature = "server")]
+                allowed_hosts: None,
+                #[cfg(feature = "server")]
+                strict: Default::default(),
+                #[cfg(feature = "client")]
                 on_informational: &mut None,
-                #[cfg(feature = "ffi")]
                 raw_headers: false,
+                h1_max_headers: None,
+                h1_max_header_value_size: None,
+                content_length_policy: Default::default(),
             },
         )
         .expect("parse ok")
@@ -2709,10 +4032,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 },
             )
             .unwrap()
@@ -2757,10 +4093,23 @@ mod tests {
                     #[cfg(feature = "ffi")]
                     preserve_header_order: false,
                     h09_responses: false,
-                    #[cfg(feature = "ffi")]
+                    preserve_raw_head: false,
+                    #[cfg(feature = "client")]
+                    max_informational: usize::MAX,
+                    #[cfg(feature = "client")]
+                    informational_count: &mut 0,
+                    #[cfg(feature = "client")]
+                    on_violation: None,
+                    #[cfg(feature = "server")]
+                    allowed_hosts: None,
+                    #[cfg(feature = "server")]
+                    strict: Default::default(),
+                    #[cfg(feature = "client")]
                     on_informational: &mut None,
-                    #[cfg(feature = "ffi")]
                     raw_headers: false,
+                    h1_max_headers: None,
+                    h1_max_header_value_size: None,
+                    content_length_policy: Default::default(),
                 },
             )
             .unwrap()
@@ -2801,6 +4150,7 @@ mod tests {
                     head: &mut head,
                     body: Some(BodyLength::Known(10)),
                     keep_alive: true,
+                    date_header: true,
                     req_method: &mut Some(Method::GET),
                     title_case_headers: false,
                 },
@@ -2829,6 +4179,7 @@ mod tests {
                     head: &mut head,
                     body: Some(BodyLength::Known(10)),
                     keep_alive: true,
+                    date_header: true,
                     req_method: &mut Some(Method::GET),
                     title_case_headers: false,
                 },
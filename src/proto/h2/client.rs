@@ -9,6 +9,7 @@ use futures_util::stream::StreamExt as _;
 use h2::client::{Builder, SendRequest};
 use http::{Method, StatusCode};
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::watch;
 use tracing::{debug, trace, warn};
 
 use super::{ping, H2Upgraded, PipeToSendStream, SendBuf};
@@ -16,6 +17,7 @@ use crate::body::HttpBody;
 use crate::common::{exec::Exec, task, Future, Never, Pin, Poll};
 use crate::ext::Protocol;
 use crate::headers;
+use crate::metrics::{MeteredIo, Recorder};
 use crate::proto::h2::UpgradedSendStream;
 use crate::proto::Dispatched;
 use crate::upgrade::Upgraded;
@@ -53,6 +55,7 @@ pub(crate) struct Config {
     pub(crate) keep_alive_while_idle: bool,
     pub(crate) max_concurrent_reset_streams: Option<usize>,
     pub(crate) max_send_buffer_size: usize,
+    pub(crate) metrics: Option<Recorder>,
 }
 
 impl Default for Config {
@@ -70,6 +73,7 @@ impl Default for Config {
             keep_alive_while_idle: false,
             max_concurrent_reset_streams: None,
             max_send_buffer_size: DEFAULT_MAX_SEND_BUF_SIZE,
+            metrics: None,
         }
     }
 }
@@ -101,6 +105,7 @@ fn new_ping_config(config: &Config) -> ping::Config {
         keep_alive_timeout: config.keep_alive_timeout,
         #[cfg(feature = "runtime")]
         keep_alive_while_idle: config.keep_alive_while_idle,
+        metrics: config.metrics.clone(),
     }
 }
 
@@ -115,6 +120,7 @@ where
     B: HttpBody,
     B::Data: Send + 'static,
 {
+    let io = MeteredIo::new(io, config.metrics.clone());
     let (h2_tx, mut conn) = new_builder(config)
         .handshake::<_, SendBuf<B::Data>>(io)
         .await
@@ -135,41 +141,52 @@ where
 
     let ping_config = new_ping_config(&config);
 
-    let (conn, ping) = if ping_config.is_enabled() {
-        let pp = conn.ping_pong().expect("conn.ping_pong");
-        let (recorder, mut ponger) = ping::channel(pp, ping_config);
+    // The peer's preface starts with its SETTINGS, so once a PING sent right
+    // after ours is acknowledged, the peer's settings are in effect.
+    let (settings_tx, settings_rx) = watch::channel(false);
+
+    let is_ping_enabled = ping_config.is_enabled();
+    let pp = conn.ping_pong().expect("conn.ping_pong");
+    let (recorder, mut ponger) = ping::channel(pp, ping_config);
+    ponger.send_initial_ping(settings_tx);
+    let pinger = recorder.pinger();
+    // Streams only need to record frames for BDP or keep-alive.
+    let ping = if is_ping_enabled {
+        recorder
+    } else {
+        ping::disabled()
+    };
 
-        let conn = future::poll_fn(move |cx| {
-            match ponger.poll(cx) {
-                Poll::Ready(ping::Ponged::SizeUpdate(wnd)) => {
-                    conn.set_target_window_size(wnd);
-                    conn.set_initial_window_size(wnd)?;
-                }
-                #[cfg(feature = "runtime")]
-                Poll::Ready(ping::Ponged::KeepAliveTimedOut) => {
-                    debug!("connection keep-alive timed out");
-                    return Poll::Ready(Ok(()));
-                }
-                Poll::Pending => {}
+    let conn = future::poll_fn(move |cx| {
+        match ponger.poll(cx) {
+            Poll::Ready(ping::Ponged::SizeUpdate(wnd)) => {
+                conn.set_target_window_size(wnd);
+                conn.set_initial_window_size(wnd)?;
             }
+            #[cfg(feature = "runtime")]
+            Poll::Ready(ping::Ponged::KeepAliveTimedOut) => {
+                debug!("connection keep-alive timed out");
+                return Poll::Ready(Ok(()));
+            }
+            Poll::Pending => {}
+        }
 
-            Pin::new(&mut conn).poll(cx)
-        });
-        (Either::Left(conn), recorder)
-    } else {
-        (Either::Right(conn), ping::disabled())
-    };
+        Pin::new(&mut conn).poll(cx)
+    });
     let conn = conn.map_err(|e| debug!("connection error: {}", e));
 
     exec.execute(conn_task(conn, conn_drop_rx, cancel_tx));
 
     Ok(ClientTask {
         ping,
+        pinger,
         conn_drop_ref,
         conn_eof,
         executor: exec,
         h2_tx,
+        metrics: config.metrics.clone(),
         req_rx,
+        settings_rx,
     })
 }
 
@@ -198,11 +215,14 @@ where
     B: HttpBody,
 {
     ping: ping::Recorder,
+    pinger: ping::Pinger,
     conn_drop_ref: ConnDropRef,
     conn_eof: ConnEof,
     executor: Exec,
     h2_tx: SendRequest<SendBuf<B::Data>>,
+    metrics: Option<Recorder>,
     req_rx: ClientRx<B>,
+    settings_rx: watch::Receiver<bool>,
 }
 
 impl<B> ClientTask<B>
@@ -212,6 +232,17 @@ where
     pub(crate) fn is_extended_connect_protocol_enabled(&self) -> bool {
         self.h2_tx.is_extended_connect_protocol_enabled()
     }
+
+    /// Returns a receiver that becomes `true` once the initial SETTINGS
+    /// were exchanged with the peer.
+    pub(crate) fn settings_exchanged(&self) -> watch::Receiver<bool> {
+        self.settings_rx.clone()
+    }
+
+    /// Returns a handle sending PINGs on this connection.
+    pub(crate) fn pinger(&self) -> ping::Pinger {
+        self.pinger.clone()
+    }
 }
 
 impl<B> Future for ClientTask<B>
@@ -256,6 +287,7 @@ where
                     let is_connect = req.method() == Method::CONNECT;
                     let eos = body.is_end_stream();
                     let ping = self.ping.clone();
+                    let metrics = self.metrics.clone();
 
                     if is_connect {
                         if headers::content_length_parse_all(req.headers())
@@ -330,6 +362,9 @@ where
                                     warn!("h2 connect response with non-zero body not supported");
 
                                     send_stream.send_reset(h2::Reason::INTERNAL_ERROR);
+                                    if let Some(ref metrics) = metrics {
+                                        metrics.stream_reset();
+                                    }
                                     return Err((
                                         crate::Error::new_h2(h2::Reason::INTERNAL_ERROR.into()),
                                         None,
@@ -362,6 +397,11 @@ where
                         Err(err) => {
                             ping.ensure_not_timed_out().map_err(|e| (e, None))?;
 
+                            if err.is_reset() {
+                                if let Some(ref metrics) = metrics {
+                                    metrics.stream_reset();
+                                }
+                            }
                             debug!("client response error: {}", err);
                             Err((crate::Error::new_h2(err), None))
                         }
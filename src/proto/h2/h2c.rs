@@ -0,0 +1,267 @@
+//! Upgrades of HTTP/1.1 connections to HTTP/2 over cleartext ("h2c").
+//!
+//! A client asks to upgrade with a request carrying `Upgrade: h2c` and an
+//! `HTTP2-Settings` header, as described in [RFC 7540 section 3.2]. The
+//! server replies with `101 Switching Protocols`, after which the client
+//! sends the HTTP/2 connection preface, and the request that asked for the
+//! upgrade is answered as stream 1.
+//!
+//! The `h2` server can't be handed such a stream, so the request is replayed
+//! to it as a HEADERS frame, as if the client had sent it right after its
+//! preface.
+//!
+//! [RFC 7540 section 3.2]: https://httpwg.org/specs/rfc7540.html#discover-http
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http::header::{self, HeaderName, HeaderValue};
+use http::{HeaderMap, Method, Version};
+use tracing::debug;
+
+use crate::body::DecodedLength;
+use crate::proto::RequestHead;
+
+/// The response accepting an upgrade to h2c.
+pub(crate) const SWITCHING_PROTOCOLS: &[u8] =
+    b"HTTP/1.1 101 Switching Protocols\r\nconnection: Upgrade\r\nupgrade: h2c\r\n\r\n";
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const FRAME_HEADER_LEN: usize = 9;
+// The SETTINGS_MAX_FRAME_SIZE until the client acknowledged the server's
+// SETTINGS.
+const DEFAULT_MAX_FRAME_SIZE: usize = 16_384;
+
+const HTTP2_SETTINGS: HeaderName = HeaderName::from_static("http2-settings");
+
+const FRAME_TYPE_HEADERS: u8 = 0x1;
+const FRAME_TYPE_SETTINGS: u8 = 0x4;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+
+/// If `head` asks to upgrade to h2c, returns the HEADERS frame replaying it
+/// as stream 1.
+///
+/// Requests with a body are served with HTTP/1 instead, since their body
+/// would have to be read before switching protocols.
+pub(crate) fn upgrade_request(head: &RequestHead, decode: DecodedLength) -> Option<Bytes> {
+    if head.version != Version::HTTP_11
+        || decode != DecodedLength::ZERO
+        || head.subject.0 == Method::CONNECT
+        || !has_token(&head.headers, header::UPGRADE, "h2c")
+        || !has_token(&head.headers, header::CONNECTION, "upgrade")
+        || !has_token(&head.headers, header::CONNECTION, "http2-settings")
+        || head.headers.get_all(HTTP2_SETTINGS).iter().count() != 1
+    {
+        return None;
+    }
+
+    let uri = &head.subject.1;
+    let authority = match head.headers.get(header::HOST) {
+        Some(host) => host.as_bytes(),
+        None => uri.authority().map_or(&b""[..], |a| a.as_str().as_bytes()),
+    };
+    let path = match uri.path_and_query() {
+        Some(path) => path.as_str(),
+        None if head.subject.0 == Method::OPTIONS => "*",
+        None => "/",
+    };
+
+    let mut block = BytesMut::new();
+    encode_field(&mut block, b":method", head.subject.0.as_str().as_bytes());
+    encode_field(&mut block, b":scheme", b"http");
+    encode_field(&mut block, b":authority", authority);
+    encode_field(&mut block, b":path", path.as_bytes());
+    for (name, value) in &head.headers {
+        if !is_connection_header(&head.headers, name, value) {
+            encode_field(&mut block, name.as_str().as_bytes(), value.as_bytes());
+        }
+    }
+    if block.len() > DEFAULT_MAX_FRAME_SIZE {
+        debug!("h2c upgrade request headers too large for one frame");
+        return None;
+    }
+
+    let mut frame = BytesMut::with_capacity(FRAME_HEADER_LEN + block.len());
+    frame.put_uint(block.len() as u64, 3);
+    frame.put_u8(FRAME_TYPE_HEADERS);
+    frame.put_u8(FLAG_END_STREAM | FLAG_END_HEADERS);
+    frame.put_u32(1);
+    frame.put(block);
+    Some(frame.freeze())
+}
+
+/// Returns the length of the client's connection preface at the start of
+/// `buf`, including its SETTINGS frame, once all of it was read.
+///
+/// Returns an error if `buf` doesn't start with a preface.
+pub(crate) fn preface_len(buf: &[u8]) -> Result<Option<usize>, ()> {
+    let magic = buf.len().min(PREFACE.len());
+    if buf[..magic] != PREFACE[..magic] {
+        return Err(());
+    }
+    let header = match buf.get(PREFACE.len()..PREFACE.len() + FRAME_HEADER_LEN) {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    if header[3] != FRAME_TYPE_SETTINGS {
+        return Err(());
+    }
+    let len = (header[0] as usize) << 16 | (header[1] as usize) << 8 | header[2] as usize;
+    let total = PREFACE.len() + FRAME_HEADER_LEN + len;
+    Ok(if buf.len() >= total {
+        Some(total)
+    } else {
+        None
+    })
+}
+
+/// Inserts the `request` frame after the client's preface in `read`, the
+/// bytes read since switching protocols.
+pub(crate) fn replay_request(read: Bytes, request: Bytes) -> Bytes {
+    let at = match preface_len(&read) {
+        Ok(Some(at)) => at,
+        _ => unreachable!("replay_request without a preface"),
+    };
+    let mut buf = BytesMut::with_capacity(read.len() + request.len());
+    buf.put(&read[..at]);
+    buf.put(request);
+    buf.put(&read[at..]);
+    buf.freeze()
+}
+
+fn has_token(headers: &HeaderMap, name: HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|t| t.trim().eq_ignore_ascii_case(token))
+}
+
+/// Whether a header can't be sent with HTTP/2, being about the HTTP/1
+/// connection.
+fn is_connection_header(headers: &HeaderMap, name: &HeaderName, value: &HeaderValue) -> bool {
+    if *name == header::TE {
+        return value != "trailers";
+    }
+    *name == header::CONNECTION
+        || *name == header::HOST
+        || *name == header::TRANSFER_ENCODING
+        || *name == header::UPGRADE
+        || *name == HTTP2_SETTINGS
+        || name == "keep-alive"
+        || name == "proxy-connection"
+        || has_token(headers, header::CONNECTION, name.as_str())
+}
+
+/// Encodes a "Literal Header Field without Indexing — New Name", which
+/// leaves the HPACK dynamic table of the server untouched.
+fn encode_field(dst: &mut BytesMut, name: &[u8], value: &[u8]) {
+    dst.put_u8(0);
+    encode_string(dst, name);
+    encode_string(dst, value);
+}
+
+/// Encodes a string literal without Huffman coding.
+fn encode_string(dst: &mut BytesMut, s: &[u8]) {
+    // an integer with a 7-bit prefix, from RFC 7541 section 5.1
+    let mut len = s.len();
+    if len < 0x7f {
+        dst.put_u8(len as u8);
+    } else {
+        dst.put_u8(0x7f);
+        len -= 0x7f;
+        while len >= 0x80 {
+            dst.put_u8((len & 0x7f) as u8 | 0x80);
+            len >>= 7;
+        }
+        dst.put_u8(len as u8);
+    }
+    dst.put_slice(s);
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Uri;
+
+    use super::*;
+    use crate::proto::RequestLine;
+
+    fn head(headers: &[(&'static str, &'static str)]) -> RequestHead {
+        let mut head = RequestHead {
+            version: Version::HTTP_11,
+            subject: RequestLine(Method::GET, Uri::from_static("/a?b")),
+            ..Default::default()
+        };
+        for &(name, value) in headers {
+            head.headers.append(name, HeaderValue::from_static(value));
+        }
+        head
+    }
+
+    #[test]
+    fn upgrade_request_replays_head() {
+        let head = head(&[
+            ("host", "example.com"),
+            ("connection", "Upgrade, HTTP2-Settings"),
+            ("upgrade", "h2c"),
+            ("http2-settings", "AAMAAABkAARAAAAAAAIAAAAA"),
+            ("accept", "*/*"),
+        ]);
+        let frame = upgrade_request(&head, DecodedLength::ZERO).unwrap();
+
+        assert_eq!(frame[..6], [0, 0, frame.len() as u8 - 9, 0x1, 0x5, 0]);
+        assert_eq!(frame[6..9], [0, 0, 1]);
+        let mut expected = BytesMut::new();
+        encode_field(&mut expected, b":method", b"GET");
+        encode_field(&mut expected, b":scheme", b"http");
+        encode_field(&mut expected, b":authority", b"example.com");
+        encode_field(&mut expected, b":path", b"/a?b");
+        encode_field(&mut expected, b"accept", b"*/*");
+        assert_eq!(frame[9..], expected[..]);
+    }
+
+    #[test]
+    fn upgrade_request_requires_settings_and_no_body() {
+        let headers = [
+            ("connection", "upgrade, http2-settings"),
+            ("upgrade", "h2c"),
+            ("http2-settings", ""),
+        ];
+        assert!(upgrade_request(&head(&headers), DecodedLength::ZERO).is_some());
+        assert!(upgrade_request(&head(&headers), DecodedLength::new(1)).is_none());
+        assert!(upgrade_request(&head(&headers[..2]), DecodedLength::ZERO).is_none());
+        assert!(upgrade_request(&head(&headers[1..]), DecodedLength::ZERO).is_none());
+    }
+
+    #[test]
+    fn encode_string_long_length() {
+        let mut dst = BytesMut::new();
+        encode_string(&mut dst, &[b'a'; 300]);
+        // 300 - 127 = 173 = 0b1_0101101
+        assert_eq!(dst[..3], [0x7f, 0xad, 0x01]);
+        assert_eq!(dst.len(), 303);
+    }
+
+    #[test]
+    fn replays_after_preface() {
+        let mut read = PREFACE.to_vec();
+        assert_eq!(preface_len(&read), Ok(None));
+        read.extend_from_slice(&[0, 0, 6, 0x4, 0, 0, 0, 0, 0]);
+        assert_eq!(preface_len(&read), Ok(None));
+        read.extend_from_slice(&[0, 3, 0, 0, 0, 100]);
+        assert_eq!(preface_len(&read), Ok(Some(39)));
+        read.extend_from_slice(b"rest");
+        assert_eq!(preface_len(b"GET / HTTP/1.1\r\n"), Err(()));
+
+        let replayed = replay_request(read.into(), Bytes::from_static(b"frame"));
+        assert_eq!(
+            replayed[..39],
+            {
+                let mut start = PREFACE.to_vec();
+                start.extend_from_slice(&[0, 0, 6, 0x4, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 100]);
+                start
+            }[..]
+        );
+        assert_eq!(replayed[39..], b"framerest"[..]);
+    }
+}
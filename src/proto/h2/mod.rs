@@ -26,6 +26,9 @@ cfg_server! {
     pub(crate) use self::server::Server;
 }
 
+#[cfg(all(feature = "http1", feature = "server"))]
+pub(crate) mod h2c;
+
 /// Default initial stream window size defined in HTTP2 spec.
 pub(crate) const SPEC_WINDOW_SIZE: u32 = 65_535;
 
@@ -5,7 +5,8 @@
 /// 1. Adaptive flow control using BDP
 /// 2. Connection keep-alive
 ///
-/// Both cases are optional.
+/// Both cases are optional. Clients may also send a PING to check that the
+/// peer is still responsive, with a `Pinger`.
 ///
 /// # BDP Algorithm
 ///
@@ -25,32 +26,40 @@ use std::fmt;
 use std::future::Future;
 #[cfg(feature = "runtime")]
 use std::pin::Pin;
+#[cfg(feature = "client")]
+use std::sync::Weak;
 use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 use std::time::Duration;
 #[cfg(not(feature = "runtime"))]
 use std::time::Instant;
 
+#[cfg(feature = "client")]
+use futures_channel::oneshot;
 use h2::{Ping, PingPong};
+use tokio::sync::watch;
 #[cfg(feature = "runtime")]
 use tokio::time::{Instant, Sleep};
 use tracing::{debug, trace};
 
+#[cfg(feature = "client")]
+use crate::metrics::BandwidthEstimate;
+
 type WindowSize = u32;
 
 pub(super) fn disabled() -> Recorder {
     Recorder { shared: None }
 }
 
+/// Creates the ping state of a connection.
+///
+/// Servers only need this if `config` is enabled, while clients always use
+/// it, for the initial PING and those of a `Pinger`.
 pub(super) fn channel(ping_pong: PingPong, config: Config) -> (Recorder, Ponger) {
-    debug_assert!(
-        config.is_enabled(),
-        "ping channel requires bdp or keep-alive config",
-    );
-
     let bdp = config.bdp_initial_window.map(|wnd| Bdp {
         bdp: wnd,
         max_bandwidth: 0.0,
+        bandwidth: 0.0,
         rtt: 0.0,
         ping_delay: Duration::from_millis(100),
         stable_count: 0,
@@ -83,6 +92,12 @@ pub(super) fn channel(ping_pong: PingPong, config: Config) -> (Recorder, Ponger)
         ping_pong,
         ping_sent_at: None,
         next_bdp_at,
+        #[cfg(feature = "client")]
+        waiters: Vec::new(),
+        #[cfg(feature = "client")]
+        is_closed: false,
+        #[cfg(feature = "client")]
+        estimate: None,
     }));
 
     (
@@ -93,6 +108,9 @@ pub(super) fn channel(ping_pong: PingPong, config: Config) -> (Recorder, Ponger)
             bdp,
             #[cfg(feature = "runtime")]
             keep_alive,
+            initial: None,
+            #[cfg(feature = "client")]
+            metrics: config.metrics,
             shared,
         },
     )
@@ -111,6 +129,9 @@ pub(super) struct Config {
     /// If true, sends pings even when there are no active streams.
     #[cfg(feature = "runtime")]
     pub(super) keep_alive_while_idle: bool,
+    /// Told about each bandwidth estimate of the BDP algorithm.
+    #[cfg(feature = "client")]
+    pub(super) metrics: Option<crate::metrics::Recorder>,
 }
 
 #[derive(Clone)]
@@ -118,10 +139,25 @@ pub(crate) struct Recorder {
     shared: Option<Arc<Mutex<Shared>>>,
 }
 
+/// Sends PINGs on behalf of the user, to check the peer is responsive.
+///
+/// This doesn't keep the connection state alive, so it doesn't count as a
+/// user of the connection for `keep_alive_while_idle`.
+#[cfg(feature = "client")]
+#[derive(Clone, Debug)]
+pub(crate) struct Pinger {
+    shared: Weak<Mutex<Shared>>,
+}
+
 pub(super) struct Ponger {
     bdp: Option<Bdp>,
     #[cfg(feature = "runtime")]
     keep_alive: Option<KeepAlive>,
+    /// If `Some`, the first PING is outstanding, and this notified once it
+    /// is acknowledged.
+    initial: Option<watch::Sender<bool>>,
+    #[cfg(feature = "client")]
+    metrics: Option<crate::metrics::Recorder>,
     shared: Arc<Mutex<Shared>>,
 }
 
@@ -145,6 +181,19 @@ struct Shared {
 
     #[cfg(feature = "runtime")]
     is_keep_alive_timed_out: bool,
+
+    // user pings
+    /// Notified with the round trip time once the PING in flight is
+    /// acknowledged.
+    #[cfg(feature = "client")]
+    waiters: Vec<oneshot::Sender<Duration>>,
+    /// Set once the `Ponger` is dropped, as no PING will be acknowledged
+    /// anymore.
+    #[cfg(feature = "client")]
+    is_closed: bool,
+    /// The latest estimate of the BDP algorithm.
+    #[cfg(feature = "client")]
+    estimate: Option<BandwidthEstimate>,
 }
 
 struct Bdp {
@@ -152,6 +201,8 @@ struct Bdp {
     bdp: u32,
     /// Largest bandwidth we've seen so far.
     max_bandwidth: f64,
+    /// Bandwidth of the latest sample, in bytes per second.
+    bandwidth: f64,
     /// Round trip time in seconds
     rtt: f64,
     /// Delay the next ping by this amount.
@@ -262,6 +313,14 @@ impl Recorder {
         }
     }
 
+    /// Returns a `Pinger` for the connection of this recorder.
+    #[cfg(feature = "client")]
+    pub(super) fn pinger(&self) -> Pinger {
+        Pinger {
+            shared: self.shared.as_ref().map(Arc::downgrade).unwrap_or_default(),
+        }
+    }
+
     /// If the incoming stream is already closed, convert self into
     /// a disabled reporter.
     #[cfg(feature = "client")]
@@ -289,9 +348,50 @@ impl Recorder {
     }
 }
 
+// ===== impl Pinger =====
+
+#[cfg(feature = "client")]
+impl Pinger {
+    /// Sends a PING, unless one is in flight already, and returns a receiver
+    /// of the round trip time once it is acknowledged.
+    ///
+    /// The receiver is canceled if the connection closes first.
+    pub(crate) fn ping(&self) -> oneshot::Receiver<Duration> {
+        let (tx, rx) = oneshot::channel();
+        if let Some(shared) = self.shared.upgrade() {
+            let mut locked = shared.lock().unwrap();
+            if !locked.is_closed {
+                if !locked.is_ping_sent() {
+                    locked.send_ping();
+                }
+                // Sending fails once the connection is closing.
+                if locked.is_ping_sent() {
+                    locked.waiters.push(tx);
+                }
+            }
+        }
+        rx
+    }
+
+    /// Returns the latest bandwidth estimate, if BDP is enabled and a sample
+    /// was taken.
+    pub(crate) fn bandwidth_estimate(&self) -> Option<BandwidthEstimate> {
+        let shared = self.shared.upgrade()?;
+        let locked = shared.lock().unwrap();
+        locked.estimate
+    }
+}
+
 // ===== impl Ponger =====
 
 impl Ponger {
+    /// Sends a PING right away, and notifies `acked` once it is acknowledged.
+    #[cfg(feature = "client")]
+    pub(super) fn send_initial_ping(&mut self, acked: watch::Sender<bool>) {
+        self.shared.lock().unwrap().send_ping();
+        self.initial = Some(acked);
+    }
+
     pub(super) fn poll(&mut self, cx: &mut task::Context<'_>) -> Poll<Ponged> {
         let now = Instant::now();
         let mut locked = self.shared.lock().unwrap();
@@ -320,6 +420,11 @@ impl Ponger {
                 let rtt = now - start;
                 trace!("recv pong");
 
+                #[cfg(feature = "client")]
+                for waiter in locked.waiters.drain(..) {
+                    let _ = waiter.send(rtt);
+                }
+
                 #[cfg(feature = "runtime")]
                 {
                     if let Some(ref mut ka) = self.keep_alive {
@@ -328,13 +433,27 @@ impl Ponger {
                     }
                 }
 
-                if let Some(ref mut bdp) = self.bdp {
+                if let Some(acked) = self.initial.take() {
+                    let _ = acked.send(true);
+                    // The initial ping wasn't sent for a BDP sample.
+                    if locked.bytes.is_some() {
+                        locked.bytes = Some(0);
+                    }
+                } else if let Some(ref mut bdp) = self.bdp {
                     let bytes = locked.bytes.expect("bdp enabled implies bytes");
                     locked.bytes = Some(0); // reset
                     trace!("received BDP ack; bytes = {}, rtt = {:?}", bytes, rtt);
 
                     let update = bdp.calculate(bytes, rtt);
                     locked.next_bdp_at = Some(now + bdp.ping_delay);
+                    #[cfg(feature = "client")]
+                    {
+                        let estimate = bdp.estimate();
+                        locked.estimate = Some(estimate);
+                        if let Some(ref metrics) = self.metrics {
+                            metrics.bandwidth_estimate(estimate);
+                        }
+                    }
                     if let Some(update) = update {
                         return Poll::Ready(Ponged::SizeUpdate(update));
                     }
@@ -342,6 +461,9 @@ impl Ponger {
             }
             Poll::Ready(Err(e)) => {
                 debug!("pong error: {}", e);
+                self.initial = None;
+                #[cfg(feature = "client")]
+                locked.waiters.clear();
             }
             Poll::Pending => {
                 #[cfg(feature = "runtime")]
@@ -367,6 +489,15 @@ impl Ponger {
     }
 }
 
+#[cfg(feature = "client")]
+impl Drop for Ponger {
+    fn drop(&mut self) {
+        let mut locked = self.shared.lock().unwrap();
+        locked.is_closed = true;
+        locked.waiters.clear();
+    }
+}
+
 // ===== impl Shared =====
 
 impl Shared {
@@ -406,12 +537,6 @@ const BDP_LIMIT: usize = 1024 * 1024 * 16;
 
 impl Bdp {
     fn calculate(&mut self, bytes: usize, rtt: Duration) -> Option<WindowSize> {
-        // No need to do any math if we're at the limit.
-        if self.bdp as usize == BDP_LIMIT {
-            self.stabilize_delay();
-            return None;
-        }
-
         // average the rtt
         let rtt = seconds(rtt);
         if self.rtt == 0.0 {
@@ -425,6 +550,13 @@ impl Bdp {
         // calculate the current bandwidth
         let bw = (bytes as f64) / (self.rtt * 1.5);
         trace!("current bandwidth = {:.1}B/s", bw);
+        self.bandwidth = bw;
+
+        // No need to grow the window if we're at the limit.
+        if self.bdp as usize == BDP_LIMIT {
+            self.stabilize_delay();
+            return None;
+        }
 
         if bw < self.max_bandwidth {
             // not a faster bandwidth, so don't update
@@ -449,6 +581,11 @@ impl Bdp {
         }
     }
 
+    #[cfg(feature = "client")]
+    fn estimate(&self) -> BandwidthEstimate {
+        BandwidthEstimate::new(self.bandwidth, Duration::from_secs_f64(self.rtt))
+    }
+
     fn stabilize_delay(&mut self) {
         if self.ping_delay < Duration::from_secs(10) {
             self.stable_count += 1;
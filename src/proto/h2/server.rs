@@ -1,5 +1,6 @@
 use std::error::Error as StdError;
 use std::marker::Unpin;
+use std::sync::Arc;
 #[cfg(feature = "runtime")]
 use std::time::Duration;
 
@@ -15,8 +16,10 @@ use super::{ping, PipeToSendStream, SendBuf};
 use crate::body::HttpBody;
 use crate::common::exec::ConnStreamExec;
 use crate::common::{date, task, Future, Pin, Poll};
-use crate::ext::Protocol;
+use crate::ext::date::OmitDate;
+use crate::ext::{ConnectInfo, Protocol};
 use crate::headers;
+use crate::observer::Observed;
 use crate::proto::h2::ping::Recorder;
 use crate::proto::h2::{H2Upgraded, UpgradedSendStream};
 use crate::proto::Dispatched;
@@ -46,12 +49,15 @@ pub(crate) struct Config {
     pub(crate) max_frame_size: u32,
     pub(crate) enable_connect_protocol: bool,
     pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) max_pending_accept_reset_streams: Option<usize>,
     #[cfg(feature = "runtime")]
     pub(crate) keep_alive_interval: Option<Duration>,
     #[cfg(feature = "runtime")]
     pub(crate) keep_alive_timeout: Duration,
     pub(crate) max_send_buffer_size: usize,
     pub(crate) max_header_list_size: u32,
+    pub(crate) auto_date_header: bool,
+    pub(crate) connect_info: Option<ConnectInfo>,
 }
 
 impl Default for Config {
@@ -63,12 +69,15 @@ impl Default for Config {
             max_frame_size: DEFAULT_MAX_FRAME_SIZE,
             enable_connect_protocol: false,
             max_concurrent_streams: None,
+            max_pending_accept_reset_streams: None,
             #[cfg(feature = "runtime")]
             keep_alive_interval: None,
             #[cfg(feature = "runtime")]
             keep_alive_timeout: Duration::from_secs(20),
             max_send_buffer_size: DEFAULT_MAX_SEND_BUF_SIZE,
             max_header_list_size: DEFAULT_SETTINGS_MAX_HEADER_LIST_SIZE,
+            auto_date_header: true,
+            connect_info: None,
         }
     }
 }
@@ -82,6 +91,7 @@ pin_project! {
         exec: E,
         service: S,
         state: State<T, B>,
+        observed: Option<Arc<Observed>>,
     }
 }
 
@@ -91,6 +101,8 @@ where
 {
     Handshaking {
         ping_config: ping::Config,
+        auto_date_header: bool,
+        connect_info: Option<ConnectInfo>,
         hs: Handshake<T, SendBuf<B::Data>>,
     },
     Serving(Serving<T, B>),
@@ -104,6 +116,8 @@ where
     ping: Option<(ping::Recorder, ping::Ponger)>,
     conn: Connection<T, SendBuf<B::Data>>,
     closing: Option<crate::Error>,
+    auto_date_header: bool,
+    connect_info: Option<ConnectInfo>,
 }
 
 impl<T, S, B, E> Server<T, S, B, E>
@@ -114,7 +128,13 @@ where
     B: HttpBody + 'static,
     E: ConnStreamExec<S::Future, B>,
 {
-    pub(crate) fn new(io: T, service: S, config: &Config, exec: E) -> Server<T, S, B, E> {
+    pub(crate) fn new(
+        io: T,
+        service: S,
+        config: &Config,
+        exec: E,
+        observed: Option<Observed>,
+    ) -> Server<T, S, B, E> {
         let mut builder = h2::server::Builder::default();
         builder
             .initial_window_size(config.initial_stream_window_size)
@@ -125,6 +145,9 @@ where
         if let Some(max) = config.max_concurrent_streams {
             builder.max_concurrent_streams(max);
         }
+        if let Some(max) = config.max_pending_accept_reset_streams {
+            builder.max_pending_accept_reset_streams(max);
+        }
         if config.enable_connect_protocol {
             builder.enable_connect_protocol();
         }
@@ -146,15 +169,20 @@ where
             // idle, so it can more aggresively close dead connections.
             #[cfg(feature = "runtime")]
             keep_alive_while_idle: true,
+            #[cfg(feature = "client")]
+            metrics: None,
         };
 
         Server {
             exec,
             state: State::Handshaking {
                 ping_config,
+                auto_date_header: config.auto_date_header,
+                connect_info: config.connect_info.clone(),
                 hs: handshake,
             },
             service,
+            observed: observed.map(Arc::new),
         }
     }
 
@@ -189,12 +217,31 @@ where
     type Output = crate::Result<Dispatched>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        let me = &mut *self;
+        let result = ready!(self.poll_inner(cx));
+        if let Some(ref observed) = self.observed {
+            observed.close(result.as_ref().err());
+        }
+        Poll::Ready(result)
+    }
+}
+
+impl<T, S, B, E> Server<T, S, B, E>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    S: HttpService<Body, ResBody = B>,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+    B: HttpBody + 'static,
+    E: ConnStreamExec<S::Future, B>,
+{
+    fn poll_inner(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Result<Dispatched>> {
+        let me = self;
         loop {
             let next = match me.state {
                 State::Handshaking {
                     ref mut hs,
                     ref ping_config,
+                    auto_date_header,
+                    ref connect_info,
                 } => {
                     let mut conn = ready!(Pin::new(hs).poll(cx).map_err(crate::Error::new_h2))?;
                     let ping = if ping_config.is_enabled() {
@@ -207,10 +254,17 @@ where
                         ping,
                         conn,
                         closing: None,
+                        auto_date_header,
+                        connect_info: connect_info.clone(),
                     })
                 }
                 State::Serving(ref mut srv) => {
-                    ready!(srv.poll_server(cx, &mut me.service, &mut me.exec))?;
+                    ready!(srv.poll_server(
+                        cx,
+                        &mut me.service,
+                        &mut me.exec,
+                        me.observed.as_ref()
+                    ))?;
                     return Poll::Ready(Ok(Dispatched::Shutdown));
                 }
                 State::Closed => {
@@ -234,6 +288,7 @@ where
         cx: &mut task::Context<'_>,
         service: &mut S,
         exec: &mut E,
+        observed: Option<&Arc<Observed>>,
     ) -> Poll<crate::Result<()>>
     where
         S: HttpService<Body, ResBody = B>,
@@ -322,7 +377,19 @@ where
                             req.extensions_mut().insert(Protocol::from_inner(protocol));
                         }
 
-                        let fut = H2Stream::new(service.call(req), connect_parts, respond);
+                        if let Some(ref info) = self.connect_info {
+                            req.extensions_mut().insert(info.clone());
+                        }
+                        if let Some(observed) = observed {
+                            observed.request_start(req.method(), req.uri());
+                        }
+                        let fut = H2Stream::new(
+                            service.call(req),
+                            connect_parts,
+                            respond,
+                            self.auto_date_header,
+                            observed.cloned(),
+                        );
                         exec.execute_h2stream(fut);
                     }
                     Some(Err(e)) => {
@@ -376,8 +443,10 @@ pin_project! {
         B: HttpBody,
     {
         reply: SendResponse<SendBuf<B::Data>>,
+        auto_date_header: bool,
         #[pin]
         state: H2StreamState<F, B>,
+        observed: Option<Arc<Observed>>,
     }
 }
 
@@ -413,10 +482,14 @@ where
         fut: F,
         connect_parts: Option<ConnectParts>,
         respond: SendResponse<SendBuf<B::Data>>,
+        auto_date_header: bool,
+        observed: Option<Arc<Observed>>,
     ) -> H2Stream<F, B> {
         H2Stream {
             reply: respond,
+            auto_date_header,
             state: H2StreamState::Service { fut, connect_parts },
+            observed,
         }
     }
 }
@@ -471,14 +544,19 @@ where
                         }
                     };
 
+                    if let Some(ref observed) = *me.observed {
+                        observed.response_headers(res.status());
+                    }
                     let (head, body) = res.into_parts();
                     let mut res = ::http::Response::from_parts(head, ());
                     super::strip_connection_headers(res.headers_mut(), false);
 
                     // set Date header if it isn't already set...
-                    res.headers_mut()
-                        .entry(::http::header::DATE)
-                        .or_insert_with(date::update_and_header_value);
+                    if *me.auto_date_header && res.extensions().get::<OmitDate>().is_none() {
+                        res.headers_mut()
+                            .entry(::http::header::DATE)
+                            .or_insert_with(date::update_and_header_value);
+                    }
 
                     if let Some(connect_parts) = connect_parts.take() {
                         if res.status().is_success() {
@@ -515,11 +593,18 @@ where
                         }
                     } else {
                         reply!(me, res, true);
+                        if let Some(ref observed) = *me.observed {
+                            observed.body_complete();
+                        }
                         return Poll::Ready(Ok(()));
                     }
                 }
                 H2StreamStateProj::Body { pipe } => {
-                    return pipe.poll(cx);
+                    ready!(pipe.poll(cx))?;
+                    if let Some(ref observed) = *me.observed {
+                        observed.body_complete();
+                    }
+                    return Poll::Ready(Ok(()));
                 }
             };
             me.state.set(next);
@@ -48,6 +48,8 @@ pub(crate) enum BodyLength {
     Known(u64),
     /// Transfer-Encoding: chunked (if h1)
     Unknown,
+    /// Delimited by closing the connection, if a response
+    CloseDelimited,
 }
 
 /// Status of when a Disaptcher future completes.
@@ -10,3 +10,10 @@ pub trait Executor<Fut> {
     /// Place the future into the executor to be run.
     fn execute(&self, fut: Fut);
 }
+
+#[cfg(feature = "runtime")]
+mod throttle;
+
+#[cfg(feature = "runtime")]
+#[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+pub use self::throttle::{Throttle, Throttled};
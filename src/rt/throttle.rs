@@ -0,0 +1,265 @@
+use std::fmt;
+use std::io::{self, IoSlice};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::pace::Pacer;
+
+/// The rate limits of a [`Throttled`] transport.
+///
+/// Each direction is limited separately, with a token bucket refilling at
+/// `bytes_per_sec` and holding up to `burst` bytes. A direction without a
+/// limit is passed through as is.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::rt::Throttle;
+///
+/// // 64 KiB/s down, 16 KiB/s up, each allowed a burst of 4 KiB
+/// let throttle = Throttle::new()
+///     .read(64 * 1024, 4 * 1024)
+///     .write(16 * 1024, 4 * 1024);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Throttle {
+    read: Option<Limit>,
+    write: Option<Limit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Limit {
+    bytes_per_sec: u64,
+    burst: u64,
+}
+
+/// A transport with its reads and writes rate limited.
+///
+/// Reads and writes wait until the limit of their direction allows them,
+/// or at least as many bytes as its burst, and are shortened to what it
+/// allows.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "tcp")]
+/// # async fn doc() -> std::io::Result<()> {
+/// use fluxio::rt::{Throttle, Throttled};
+/// use tokio::net::TcpStream;
+///
+/// let stream = TcpStream::connect("127.0.0.1:8080").await?;
+/// let stream = Throttled::new(stream, Throttle::new().read(1024, 1024));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Throttled<T> {
+    io: T,
+    read: Option<Pacer>,
+    write: Option<Pacer>,
+}
+
+impl Throttle {
+    /// Creates limits that let everything through.
+    pub fn new() -> Throttle {
+        Throttle::default()
+    }
+
+    /// Limits reads to `bytes_per_sec`, allowing bursts of up to `burst`
+    /// bytes.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `bytes_per_sec` or `burst` is `0`.
+    pub fn read(mut self, bytes_per_sec: u64, burst: u64) -> Throttle {
+        self.read = Some(Limit::new(bytes_per_sec, burst));
+        self
+    }
+
+    /// Limits writes to `bytes_per_sec`, allowing bursts of up to `burst`
+    /// bytes.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `bytes_per_sec` or `burst` is `0`.
+    pub fn write(mut self, bytes_per_sec: u64, burst: u64) -> Throttle {
+        self.write = Some(Limit::new(bytes_per_sec, burst));
+        self
+    }
+}
+
+impl Limit {
+    fn new(bytes_per_sec: u64, burst: u64) -> Limit {
+        assert!(bytes_per_sec > 0, "throttle rate must be at least 1");
+        assert!(burst > 0, "throttle burst must be at least 1");
+        Limit {
+            bytes_per_sec,
+            burst,
+        }
+    }
+
+    fn pacer(self) -> Pacer {
+        Pacer::with_burst(self.bytes_per_sec, self.burst)
+    }
+}
+
+impl<T> Throttled<T> {
+    /// Wraps `io`, limiting it to `throttle`.
+    pub fn new(io: T, throttle: Throttle) -> Throttled<T> {
+        Throttled {
+            io,
+            read: throttle.read.map(Limit::pacer),
+            write: throttle.write.map(Limit::pacer),
+        }
+    }
+
+    /// Returns a reference to the wrapped transport.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the wrapped transport.
+    ///
+    /// Reading or writing through it bypasses the limits.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Consumes this, returning the wrapped transport.
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Throttled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttled")
+            .field("io", &self.io)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> AsyncRead for Throttled<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        let pacer = match me.read {
+            Some(ref mut pacer) => pacer,
+            None => return Pin::new(&mut me.io).poll_read(cx, buf),
+        };
+        let allowed = ready!(pacer.poll_available(cx, buf.remaining())).min(buf.remaining());
+        let n = {
+            let mut limited = buf.take(allowed);
+            ready!(Pin::new(&mut me.io).poll_read(cx, &mut limited))?;
+            limited.filled().len()
+        };
+        // SAFETY: the transport initialized the `n` bytes it read.
+        unsafe {
+            buf.assume_init(n);
+        }
+        buf.advance(n);
+        pacer.consume(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncWrite for Throttled<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let pacer = match me.write {
+            Some(ref mut pacer) => pacer,
+            None => return Pin::new(&mut me.io).poll_write(cx, buf),
+        };
+        let allowed = ready!(pacer.poll_available(cx, buf.len())).min(buf.len());
+        let n = ready!(Pin::new(&mut me.io).poll_write(cx, &buf[..allowed]))?;
+        pacer.consume(n);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        if self.write.is_none() {
+            return Pin::new(&mut self.io).poll_write_vectored(cx, bufs);
+        }
+        let buf = bufs
+            .iter()
+            .find(|buf| !buf.is_empty())
+            .map_or(&[][..], |buf| &**buf);
+        self.poll_write(cx, buf)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.write.is_none() && self.io.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::time::Instant;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn throttles_each_direction() {
+        let (client, mut server) = tokio::io::duplex(8192);
+        let mut io = Throttled::new(client, Throttle::new().write(1000, 500));
+        let start = Instant::now();
+
+        // 500 bytes of burst, then 1000 more at 1000 bytes per second
+        io.write_all(&[0; 1500]).await.unwrap();
+        assert_eq!(Instant::now() - start, Duration::from_secs(1));
+
+        // reads aren't limited
+        server.write_all(&[0; 4096]).await.unwrap();
+        let mut buf = [0; 4096];
+        io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(Instant::now() - start, Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reads_shortened_to_burst() {
+        let (client, mut server) = tokio::io::duplex(8192);
+        let mut io = Throttled::new(client, Throttle::new().read(100, 10));
+        server.write_all(&[0; 100]).await.unwrap();
+
+        let mut buf = [0; 100];
+        assert_eq!(io.read(&mut buf).await.unwrap(), 10);
+        let start = Instant::now();
+        assert_eq!(io.read(&mut buf).await.unwrap(), 10);
+        assert_eq!(Instant::now() - start, Duration::from_millis(100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_rate_panics() {
+        let _ = Throttle::new().read(0, 1);
+    }
+}
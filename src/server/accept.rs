@@ -5,16 +5,29 @@
 //! - The [`Accept`](Accept) trait used to asynchronously accept incoming
 //!   connections.
 //! - Utilities like `poll_fn` to ease creating a custom `Accept`.
+//! - [`with_handshake`] to run a handshake on each accepted connection, such
+//!   as a TLS one.
+//!
+//! Besides [`AddrIncoming`](crate::server::conn::AddrIncoming), `Accept` is
+//! implemented for the receivers of tokio's `mpsc` channels, to serve
+//! connections made in memory such as with `tokio::io::duplex`, and for
+//! tokio's `UnixListener` with the `tcp` feature on Unix.
+
+use std::convert::Infallible;
+use std::error::Error as StdError;
+use std::fmt;
 
 #[cfg(feature = "stream")]
 use futures_core::Stream;
-#[cfg(feature = "stream")]
 use pin_project_lite::pin_project;
+use tokio::sync::mpsc;
+use tracing::debug;
 
 use crate::common::{
     task::{self, Poll},
-    Pin,
+    Future, Pin,
 };
+use crate::ext::ConnectInfo;
 
 /// Asynchronously accept incoming connections.
 pub trait Accept {
@@ -28,6 +41,17 @@ pub trait Accept {
         self: Pin<&mut Self>,
         cx: &mut task::Context<'_>,
     ) -> Poll<Option<Result<Self::Conn, Self::Error>>>;
+
+    /// Returns the details of an accepted connection, inserted into its
+    /// requests by servers built with
+    /// [`connect_info(true)`](crate::server::Builder::connect_info).
+    ///
+    /// Returns `None` by default. Acceptors adding TLS can return the
+    /// details of the handshake, such as with [`ConnectInfo::from_conn`].
+    fn connect_info(conn: &Self::Conn) -> Option<ConnectInfo> {
+        let _ = conn;
+        None
+    }
 }
 
 /// Create an `Accept` with a polling function.
@@ -109,3 +133,215 @@ where
 
     FromStream { stream }
 }
+
+/// Run a handshake on each connection accepted by `incoming`, such as a TLS
+/// one, yielding the connections it returns.
+///
+/// Handshakes run concurrently, so that a slow client doesn't hold up the
+/// others. A failed handshake only drops its connection, and doesn't stop
+/// the server. Handshakes have no timeout of their own, so the function
+/// should usually add one, such as with `tokio::time::timeout`.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "tcp")]
+/// # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// use std::time::Duration;
+///
+/// use fluxio::server::accept;
+/// use fluxio::server::conn::{AddrIncoming, AddrStream};
+///
+/// # async fn tls_accept(io: AddrStream) -> std::io::Result<AddrStream> { Ok(io) }
+/// let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into())?;
+/// let incoming = accept::with_handshake(incoming, |io| async move {
+///     // A TLS acceptor would run its handshake here.
+///     tokio::time::timeout(Duration::from_secs(10), tls_accept(io)).await?
+/// });
+/// # let _ = incoming;
+/// # Ok(())
+/// # }
+/// ```
+pub fn with_handshake<A, F, Fut, IO, E>(incoming: A, handshake: F) -> WithHandshake<A, F, Fut>
+where
+    A: Accept,
+    F: FnMut(A::Conn) -> Fut,
+    Fut: Future<Output = Result<IO, E>>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    WithHandshake {
+        incoming,
+        handshake,
+        pending: Vec::new(),
+        incoming_done: false,
+    }
+}
+
+pin_project! {
+    /// An `Accept` running a handshake on each accepted connection, returned
+    /// from [`with_handshake`].
+    pub struct WithHandshake<A, F, Fut> {
+        #[pin]
+        incoming: A,
+        handshake: F,
+        pending: Vec<Pin<Box<Fut>>>,
+        incoming_done: bool,
+    }
+}
+
+impl<A, F, Fut, IO, E> Accept for WithHandshake<A, F, Fut>
+where
+    A: Accept,
+    F: FnMut(A::Conn) -> Fut,
+    Fut: Future<Output = Result<IO, E>>,
+    E: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Conn = IO;
+    type Error = A::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let mut me = self.project();
+        while !*me.incoming_done {
+            match me.incoming.as_mut().poll_accept(cx) {
+                Poll::Ready(Some(Ok(conn))) => me.pending.push(Box::pin((me.handshake)(conn))),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => *me.incoming_done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        let mut i = 0;
+        while i < me.pending.len() {
+            match me.pending[i].as_mut().poll(cx) {
+                Poll::Ready(Ok(io)) => {
+                    drop(me.pending.swap_remove(i));
+                    return Poll::Ready(Some(Ok(io)));
+                }
+                Poll::Ready(Err(e)) => {
+                    debug!("connection handshake error: {}", e.into());
+                    drop(me.pending.swap_remove(i));
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+
+        if *me.incoming_done && me.pending.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<A, F, Fut> fmt::Debug for WithHandshake<A, F, Fut>
+where
+    A: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithHandshake")
+            .field("incoming", &self.incoming)
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+/// Accepts the connections sent on the channel, such as ones made in memory
+/// with `tokio::io::duplex`.
+impl<IO> Accept for mpsc::Receiver<IO> {
+    type Conn = IO;
+    type Error = Infallible;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        self.get_mut().poll_recv(cx).map(|conn| conn.map(Ok))
+    }
+}
+
+/// Accepts the connections sent on the channel, such as ones made in memory
+/// with `tokio::io::duplex`.
+impl<IO> Accept for mpsc::UnboundedReceiver<IO> {
+    type Conn = IO;
+    type Error = Infallible;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        self.get_mut().poll_recv(cx).map(|conn| conn.map(Ok))
+    }
+}
+
+#[cfg(all(unix, feature = "tcp"))]
+#[cfg_attr(docsrs, doc(cfg(all(unix, feature = "tcp"))))]
+impl Accept for tokio::net::UnixListener {
+    type Conn = tokio::net::UnixStream;
+    type Error = std::io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        tokio::net::UnixListener::poll_accept(self.get_mut(), cx)
+            .map(|res| Some(res.map(|(io, _)| io)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn with_handshake_skips_failed_handshakes() {
+        let mut conns = vec![Ok(3), Ok(2), Ok(1)];
+        let incoming =
+            poll_fn(move |_cx| Poll::Ready(conns.pop().map(|conn: io::Result<u8>| conn)));
+        let mut incoming = Box::pin(with_handshake(incoming, |conn| async move {
+            if conn == 2 {
+                Err(io::Error::new(io::ErrorKind::Other, "bad handshake"))
+            } else {
+                Ok(conn * 10)
+            }
+        }));
+
+        let mut accepted = Vec::new();
+        while let Some(conn) = std::future::poll_fn(|cx| incoming.as_mut().poll_accept(cx)).await {
+            accepted.push(conn.unwrap());
+        }
+        accepted.sort_unstable();
+        assert_eq!(accepted, [10, 30]);
+    }
+
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[tokio::test]
+    async fn serves_connections_sent_on_channel() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use crate::service::{make_service_fn, service_fn};
+        use crate::{Body, Request, Response, Server};
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::from("in memory")))
+            }))
+        });
+        tokio::spawn(Server::builder(rx).serve(make_svc));
+
+        let (mut client, server) = tokio::io::duplex(1024);
+        tx.send(server).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\nconnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut res = Vec::new();
+        client.read_to_end(&mut res).await.unwrap();
+        assert!(res.ends_with(b"\r\n\r\nin memory"), "{:?}", res);
+    }
+}
@@ -51,11 +51,18 @@ use std::marker::PhantomData;
 #[cfg(all(any(feature = "http1", feature = "http2"), feature = "runtime"))]
 use std::time::Duration;
 
+#[cfg(all(any(feature = "http1", feature = "http2"), feature = "runtime"))]
+use tokio::time::{Instant, Sleep};
+
+#[cfg(feature = "http1")]
+use crate::body::SizeHint;
 #[cfg(feature = "http2")]
 use crate::common::io::Rewind;
 #[cfg(all(feature = "http1", feature = "http2"))]
 use crate::error::{Kind, Parse};
 #[cfg(feature = "http1")]
+use crate::ext::{BodyFraming, BodyFramingFn, ContentLengthPolicy};
+#[cfg(feature = "http1")]
 use crate::upgrade::Upgraded;
 
 cfg_feature! {
@@ -63,14 +70,19 @@ cfg_feature! {
 
   use std::error::Error as StdError;
   use std::fmt;
+  use std::sync::Arc;
 
   use bytes::Bytes;
   use pin_project_lite::pin_project;
   use tokio::io::{AsyncRead, AsyncWrite};
-  use tracing::trace;
+  use tracing::{debug, trace};
 
   pub use super::server::Connecting;
   use crate::body::{Body, HttpBody};
+  use crate::common::host::AllowedHosts;
+  use crate::ext::ConnectInfo;
+  use crate::hardened::{Posture, Profile};
+  use crate::observer::{ConnectionObserver, ObserverRef};
   use crate::common::{task, Future, Pin, Poll, Unpin};
   #[cfg(not(all(feature = "http1", feature = "http2")))]
   use crate::common::Never;
@@ -81,6 +93,26 @@ cfg_feature! {
   pub(super) use self::upgrades::UpgradeableConnection;
 }
 
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub use super::cors::CorsPolicy;
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub use super::early_hints::SendEarlyHints;
+#[cfg(all(any(feature = "http1", feature = "http2"), feature = "runtime"))]
+pub use super::graceful::{GracefulConnection, GracefulShutdown, Watched};
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub use super::health::HealthCheck;
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub use super::live::{LiveConfig, ShedPolicy};
+
+#[cfg(all(feature = "http1", feature = "http2"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "http2"))))]
+pub mod auto;
+#[cfg(feature = "http1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+pub mod http1;
+#[cfg(feature = "http2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+pub mod http2;
 #[cfg(feature = "tcp")]
 pub use super::tcp::{AddrIncoming, AddrStream};
 
@@ -99,14 +131,60 @@ pub struct Http<E = Exec> {
     h1_keep_alive: bool,
     h1_title_case_headers: bool,
     h1_preserve_header_case: bool,
+    h1_preserve_raw_head: bool,
+    h1_headers_raw: bool,
+    #[cfg(feature = "http1")]
+    h1_expect_continue: ExpectContinue,
+    #[cfg(feature = "http1")]
+    h1_max_headers: Option<usize>,
+    #[cfg(feature = "http1")]
+    h1_max_header_value_size: Option<usize>,
+    #[cfg(feature = "http1")]
+    h1_strict: proto::h1::StrictParsing,
+    #[cfg(feature = "http1")]
+    h1_content_length_policy: ContentLengthPolicy,
+    #[cfg(feature = "http1")]
+    h1_body_framing: Option<BodyFramingFn>,
     #[cfg(all(feature = "http1", feature = "runtime"))]
     h1_header_read_timeout: Option<Duration>,
     h1_writev: Option<bool>,
     #[cfg(feature = "http2")]
     h2_builder: proto::h2::server::Config,
+    #[cfg(all(feature = "http1", feature = "http2"))]
+    h2c_upgrade: bool,
     mode: ConnectionMode,
     max_buf_size: Option<usize>,
+    min_buf_size: Option<usize>,
     pipeline_flush: bool,
+    allowed_hosts: Option<Arc<AllowedHosts>>,
+    early_hints: bool,
+    auto_date_header: bool,
+    max_request_body_size: Option<u64>,
+    connect_info: Option<ConnectInfo>,
+    pub(super) health_check: Option<HealthCheck>,
+    cors: Option<CorsPolicy>,
+    observer: Option<ObserverRef>,
+    pub(super) live_config: Option<LiveConfig>,
+    hardened: Option<Posture>,
+}
+
+/// How HTTP/1 requests sending `Expect: 100-continue` are handled.
+///
+/// Such a client waits for a `100 Continue` response before sending the
+/// request body, so a request can be validated before its body is accepted.
+#[cfg(feature = "http1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExpectContinue {
+    /// Send `100 Continue` once the service starts reading the body.
+    #[default]
+    Automatic,
+    /// Only send `100 Continue` when the service asks for it with the
+    /// [`Expect100`](crate::ext::Expect100) in the request extensions.
+    Defer,
+    /// Respond with `417 Expectation Failed` and close the connection,
+    /// without calling the service.
+    Reject,
 }
 
 /// The internal mode of HTTP protocol which indicates the behavior when a parse error occurs.
@@ -137,9 +215,16 @@ pin_project! {
   {
       pub(super) conn: Option<ProtoServer<T, S::ResBody, S, E>>,
       fallback: Fallback<E>,
+      shutdown_deadline: ShutdownDeadline,
   }
 }
 
+#[cfg(all(any(feature = "http1", feature = "http2"), feature = "runtime"))]
+type ShutdownDeadline = Option<Pin<Box<Sleep>>>;
+
+#[cfg(all(any(feature = "http1", feature = "http2"), not(feature = "runtime")))]
+type ShutdownDeadline = ();
+
 #[cfg(feature = "http1")]
 type Http1Dispatcher<T, B, S> =
     proto::h1::Dispatcher<proto::h1::dispatch::Server<S, Body>, B, T, proto::ServerTransaction>;
@@ -238,14 +323,41 @@ impl Http {
             h1_keep_alive: true,
             h1_title_case_headers: false,
             h1_preserve_header_case: false,
+            h1_preserve_raw_head: false,
+            h1_headers_raw: false,
+            #[cfg(feature = "http1")]
+            h1_expect_continue: ExpectContinue::default(),
+            #[cfg(feature = "http1")]
+            h1_max_headers: None,
+            #[cfg(feature = "http1")]
+            h1_max_header_value_size: None,
+            #[cfg(feature = "http1")]
+            h1_strict: Default::default(),
+            #[cfg(feature = "http1")]
+            h1_content_length_policy: ContentLengthPolicy::default(),
+            #[cfg(feature = "http1")]
+            h1_body_framing: None,
             #[cfg(all(feature = "http1", feature = "runtime"))]
             h1_header_read_timeout: None,
             h1_writev: None,
             #[cfg(feature = "http2")]
             h2_builder: Default::default(),
+            #[cfg(all(feature = "http1", feature = "http2"))]
+            h2c_upgrade: false,
             mode: ConnectionMode::default(),
             max_buf_size: None,
+            min_buf_size: None,
             pipeline_flush: false,
+            allowed_hosts: None,
+            early_hints: false,
+            auto_date_header: true,
+            max_request_body_size: None,
+            connect_info: None,
+            health_check: None,
+            cors: None,
+            observer: None,
+            live_config: None,
+            hardened: None,
         }
     }
 }
@@ -280,6 +392,7 @@ impl<E> Http<E> {
     #[cfg(feature = "http1")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
     pub fn http1_half_close(&mut self, val: bool) -> &mut Self {
+        Posture::record(&mut self.hardened, "http1_half_close");
         self.h1_half_close = val;
         self
     }
@@ -327,13 +440,240 @@ impl<E> Http<E> {
         self
     }
 
+    /// Set whether to keep the raw bytes of each received request head.
+    ///
+    /// This is meant for transparent proxies and security tooling. The raw
+    /// head is stored in a private extension on the `Request`. If that
+    /// `Request` is then sent, unmodified, over an HTTP/1 client connection,
+    /// the original request line and header block are written back out
+    /// byte-for-byte, including casing, ordering and whitespace. As soon as
+    /// the method, URI, version or headers are changed, the request is
+    /// serialized normally instead, so it is usually useful to combine this
+    /// with [`http1_preserve_header_case`](Http::http1_preserve_header_case).
+    ///
+    /// Chunk sizes of a chunked body are not preserved. A request with both
+    /// `Content-Length` and `Transfer-Encoding` is never written back out
+    /// raw, and its `Content-Length` header is removed.
+    ///
+    /// This has no effect unless the `client` feature is enabled too.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_preserve_raw_head(&mut self, enabled: bool) -> &mut Self {
+        self.h1_preserve_raw_head = enabled;
+        self
+    }
+
+    /// Set whether to store the raw bytes of each received request head in a
+    /// [`RawHeaders`](crate::ext::RawHeaders) extension on the `Request`.
+    ///
+    /// This is meant for verifying signatures computed over the exact bytes
+    /// sent by a client, and for debugging proxies. Unlike
+    /// [`http1_preserve_raw_head`](Http::http1_preserve_raw_head), the bytes
+    /// are only exposed, never written back out.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_headers_raw(&mut self, enabled: bool) -> &mut Self {
+        self.h1_headers_raw = enabled;
+        self
+    }
+
+    /// Set how requests sending `Expect: 100-continue` are handled.
+    ///
+    /// See [`ExpectContinue`] for the choices.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is [`ExpectContinue::Automatic`].
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_expect_continue(&mut self, policy: ExpectContinue) -> &mut Self {
+        self.h1_expect_continue = policy;
+        self
+    }
+
+    /// Set the maximum number of headers of a request.
+    ///
+    /// Requests with more headers are rejected with `431 Request Header
+    /// Fields Too Large`, and the connection is closed with an error for
+    /// which [`is_parse_too_many_headers`](crate::Error::is_parse_too_many_headers)
+    /// is true.
+    ///
+    /// Up to 100 headers are parsed into memory on the stack, and more into
+    /// memory allocated for each request.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is 100.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_headers(&mut self, max: usize) -> &mut Self {
+        self.h1_max_headers = Some(max);
+        self
+    }
+
+    /// Set the maximum size of a request header value, in bytes.
+    ///
+    /// Requests with a larger header value are rejected with `431 Request
+    /// Header Fields Too Large`, and the connection is closed with an error
+    /// for which
+    /// [`is_parse_header_value_too_large`](crate::Error::is_parse_header_value_too_large)
+    /// is true.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is no limit other than the size of the read buffer.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_header_value_size(&mut self, max: usize) -> &mut Self {
+        self.h1_max_header_value_size = Some(max);
+        self
+    }
+
+    /// Set how requests with multiple `Content-Length` values are read.
+    ///
+    /// See [`ContentLengthPolicy`] for the choices.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is [`ContentLengthPolicy::MergeIfEqual`].
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_content_length_policy(&mut self, policy: ContentLengthPolicy) -> &mut Self {
+        self.h1_content_length_policy = policy;
+        self
+    }
+
+    /// Set a callback choosing how response bodies of unknown length are
+    /// framed.
+    ///
+    /// The callback is called with the size hint of each such body. See
+    /// [`BodyFraming`] for the choices, such as delimiting them by closing
+    /// the connection, or buffering small ones to send them with a
+    /// `Content-Length`.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is [`BodyFraming::Chunked`].
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_body_framing<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&SizeHint) -> BodyFraming + Send + Sync + 'static,
+    {
+        self.h1_body_framing = Some(BodyFramingFn::new(f));
+        self
+    }
+
+    /// Set whether to reject requests with both a `Content-Length` and a
+    /// `Transfer-Encoding` header.
+    ///
+    /// Such requests are otherwise read with `Transfer-Encoding`, ignoring
+    /// `Content-Length`, but a proxy in front of the server that uses
+    /// `Content-Length` instead would disagree on where the request ends,
+    /// letting a second request be smuggled past it.
+    ///
+    /// Rejected requests get a `400 Bad Request` response, and the
+    /// connection is closed with a parse error.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_content_length_with_transfer_encoding(
+        &mut self,
+        enabled: bool,
+    ) -> &mut Self {
+        Posture::record(
+            &mut self.hardened,
+            "http1_reject_content_length_with_transfer_encoding",
+        );
+        self.h1_strict.content_length_with_transfer_encoding = enabled;
+        self
+    }
+
+    /// Set whether to reject requests with obsolete line folding, header
+    /// values continued on a line starting with whitespace.
+    ///
+    /// Such requests are always invalid, but with this they are rejected
+    /// before being parsed, with an error describing the line folding.
+    ///
+    /// Rejected requests get a `400 Bad Request` response, and the
+    /// connection is closed with a parse error.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_obs_fold(&mut self, enabled: bool) -> &mut Self {
+        Posture::record(&mut self.hardened, "http1_reject_obs_fold");
+        self.h1_strict.obs_fold = enabled;
+        self
+    }
+
+    /// Set whether to reject requests with a CR that is not followed by a
+    /// LF in their head.
+    ///
+    /// Such requests are always invalid, but with this they are rejected
+    /// before being parsed, with an error describing the bare CR.
+    ///
+    /// Rejected requests get a `400 Bad Request` response, and the
+    /// connection is closed with a parse error.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_bare_cr(&mut self, enabled: bool) -> &mut Self {
+        Posture::record(&mut self.hardened, "http1_reject_bare_cr");
+        self.h1_strict.bare_cr = enabled;
+        self
+    }
+
+    /// Set whether to reject requests with whitespace between a header name
+    /// and its colon.
+    ///
+    /// Such requests are always invalid, but with this they are rejected
+    /// before being parsed, with an error describing the whitespace.
+    ///
+    /// Rejected requests get a `400 Bad Request` response, and the
+    /// connection is closed with a parse error.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_whitespace_before_colon(&mut self, enabled: bool) -> &mut Self {
+        Posture::record(&mut self.hardened, "http1_reject_whitespace_before_colon");
+        self.h1_strict.whitespace_before_colon = enabled;
+        self
+    }
+
     /// Set a timeout for reading client request headers. If a client does not
     /// transmit the entire header within this time, the connection is closed.
     ///
+    /// This protects against clients sending heads slowly to hold on to
+    /// connections ("slowloris"). The timer starts with the first bytes of
+    /// each request head, and the connection fails with an error for which
+    /// [`Error::is_header_read_timeout`](crate::Error::is_header_read_timeout)
+    /// and `is_timeout` are true.
+    ///
     /// Default is None.
     #[cfg(all(feature = "http1", feature = "runtime"))]
     #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "runtime"))))]
     pub fn http1_header_read_timeout(&mut self, read_timeout: Duration) -> &mut Self {
+        Posture::record(&mut self.hardened, "http1_header_read_timeout");
         self.h1_header_read_timeout = Some(read_timeout);
         self
     }
@@ -375,6 +715,26 @@ impl<E> Http<E> {
         self
     }
 
+    /// Sets whether HTTP/1.1 requests asking to upgrade the connection to
+    /// HTTP/2 over cleartext, with `Upgrade: h2c`, are honored.
+    ///
+    /// The server then replies with `101 Switching Protocols`, and serves
+    /// the connection with HTTP/2, answering the request that asked for it
+    /// as stream 1. Requests with a body aren't upgraded, and are served
+    /// with HTTP/1 instead. The client's `HTTP2-Settings` are not applied,
+    /// since it repeats them in the SETTINGS frame it sends after the
+    /// connection preface.
+    ///
+    /// This has no effect if either HTTP/1 or HTTP/2 is required.
+    ///
+    /// Default is false.
+    #[cfg(all(feature = "http1", feature = "http2"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "http2"))))]
+    pub fn http2_upgrade(&mut self, enabled: bool) -> &mut Self {
+        self.h2c_upgrade = enabled;
+        self
+    }
+
     /// Sets the [`SETTINGS_INITIAL_WINDOW_SIZE`][spec] option for HTTP2
     /// stream-level flow control.
     ///
@@ -452,6 +812,7 @@ impl<E> Http<E> {
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_max_concurrent_streams(&mut self, max: impl Into<Option<u32>>) -> &mut Self {
+        Posture::record(&mut self.hardened, "http2_max_concurrent_streams");
         self.h2_builder.max_concurrent_streams = max.into();
         self
     }
@@ -525,10 +886,33 @@ impl<E> Http<E> {
     #[cfg(feature = "http2")]
     #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
     pub fn http2_max_header_list_size(&mut self, max: u32) -> &mut Self {
+        Posture::record(&mut self.hardened, "http2_max_header_list_size");
         self.h2_builder.max_header_list_size = max;
         self
     }
 
+    /// Sets the maximum number of streams a client can reset before they
+    /// were accepted, pending to be accepted.
+    ///
+    /// This bounds the work a client can cause by opening and resetting
+    /// streams right away, as in "rapid reset" attacks. Passing `None` uses
+    /// the default of `h2`, currently 20.
+    ///
+    /// See the documentation of [`h2::server::Builder::max_pending_accept_reset_streams`]
+    /// for more details.
+    ///
+    /// [`h2::server::Builder::max_pending_accept_reset_streams`]: https://docs.rs/h2/server/struct.Builder.html#method.max_pending_accept_reset_streams
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_pending_accept_reset_streams(
+        &mut self,
+        max: impl Into<Option<usize>>,
+    ) -> &mut Self {
+        Posture::record(&mut self.hardened, "http2_max_pending_accept_reset_streams");
+        self.h2_builder.max_pending_accept_reset_streams = max.into();
+        self
+    }
+
     /// Set the maximum buffer size for the connection.
     ///
     /// Default is ~400kb.
@@ -543,10 +927,37 @@ impl<E> Http<E> {
             max >= proto::h1::MINIMUM_MAX_BUFFER_SIZE,
             "the max_buf_size cannot be smaller than the minimum that h1 specifies."
         );
+        Posture::record(&mut self.hardened, "max_buf_size");
         self.max_buf_size = Some(max);
         self
     }
 
+    /// Set the minimum buffer size for the connection.
+    ///
+    /// The read buffer starts at this size, grows with larger messages up
+    /// to the maximum buffer size, and shrinks back to it with smaller ones.
+    /// Once a connection waits for a new request, such as an idle keep-alive
+    /// connection, a read buffer grown beyond this size is released.
+    ///
+    /// Lowering it reduces the memory held by many mostly idle connections,
+    /// at the cost of more reads for larger requests.
+    ///
+    /// Default is 8kb.
+    ///
+    /// # Panics
+    ///
+    /// The minimum value allowed is 64. This method panics if the passed `min` is less than the minimum.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn min_buf_size(&mut self, min: usize) -> &mut Self {
+        assert!(
+            min >= proto::h1::MINIMUM_MIN_BUFFER_SIZE,
+            "the min_buf_size cannot be smaller than the minimum that h1 specifies."
+        );
+        self.min_buf_size = Some(min);
+        self
+    }
+
     /// Aggregates flushes to better support pipelined responses.
     ///
     /// Experimental, may have bugs.
@@ -557,6 +968,234 @@ impl<E> Http<E> {
         self
     }
 
+    /// Only accept requests for the given hosts.
+    ///
+    /// Each entry is either an exact host name, such as `example.com`, or a
+    /// wildcard matching any of its subdomains, such as `*.example.com`.
+    /// Entries with a port, such as `example.com:8443`, only match requests
+    /// for that port. Host names are compared case-insensitively.
+    ///
+    /// The target of each request is taken from its absolute-form URI, and
+    /// otherwise from the `Host` header. Requests for any other host, or
+    /// without a target host at all, are answered with `421 Misdirected
+    /// Request` before reaching the service, and the connection is closed.
+    /// This protects against DNS rebinding and `Host` header attacks.
+    ///
+    /// Note that this setting does not affect HTTP/2 yet, so servers that
+    /// rely on it should not enable HTTP/2.
+    ///
+    /// Default is to accept any host.
+    pub fn allowed_hosts<I, S>(&mut self, hosts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed_hosts = Some(Arc::new(AllowedHosts::new(hosts)));
+        self
+    }
+
+    /// Set whether requests carry a [`SendEarlyHints`] in their extensions,
+    /// to send `103 Early Hints` responses before the final response.
+    ///
+    /// Note that this setting does not affect HTTP/2 yet.
+    ///
+    /// Default is `false`.
+    pub fn early_hints(&mut self, enabled: bool) -> &mut Self {
+        self.early_hints = enabled;
+        self
+    }
+
+    /// Set whether responses without a `Date` header get one with the
+    /// current time.
+    ///
+    /// The date is formatted at most once per second. A service can still
+    /// send its own `Date` header, or send a response without one by
+    /// inserting [`OmitDate`](crate::ext::date::OmitDate) in its extensions.
+    ///
+    /// Default is `true`.
+    pub fn auto_date_header(&mut self, enabled: bool) -> &mut Self {
+        #[cfg(feature = "http2")]
+        {
+            self.h2_builder.auto_date_header = enabled;
+        }
+        self.auto_date_header = enabled;
+        self
+    }
+
+    /// Set the maximum size of request bodies, in bytes.
+    ///
+    /// Request bodies are limited with [`Body::limit`](crate::Body::limit):
+    /// reading more of one fails with an error for which
+    /// [`Error::is_body_too_large`](crate::Error::is_body_too_large) is
+    /// true, and a request declaring a larger `Content-Length` fails before
+    /// any of its body is read. The service decides how to respond, such as
+    /// with `413 Payload Too Large`.
+    ///
+    /// Note that this setting does not affect HTTP/2 yet.
+    ///
+    /// Default is no limit.
+    pub fn max_request_body_size(&mut self, max: u64) -> &mut Self {
+        self.max_request_body_size = Some(max);
+        self
+    }
+
+    /// Answer health checks on the connection, before they reach the service.
+    ///
+    /// See [`HealthCheck`] for which requests are answered, and how.
+    ///
+    /// Note that this setting does not affect HTTP/2 yet.
+    ///
+    /// Default is to pass every request to the service.
+    pub fn health_check(&mut self, check: HealthCheck) -> &mut Self {
+        self.health_check = Some(check);
+        self
+    }
+
+    /// Answer CORS preflight requests on the connection, following a policy.
+    ///
+    /// See [`CorsPolicy`] for which requests are answered, and how.
+    ///
+    /// Note that this setting does not affect HTTP/2 yet.
+    ///
+    /// Default is to pass every request to the service.
+    pub fn cors(&mut self, policy: CorsPolicy) -> &mut Self {
+        self.cors = Some(policy);
+        self
+    }
+
+    /// Configure connections for a server exposed to untrusted clients.
+    ///
+    /// This tightens the limits bounding how much a single client can make
+    /// the server buffer or wait for:
+    ///
+    /// - HTTP/1 read buffers of at most 64 KiB, which also bounds the size
+    ///   of request heads,
+    /// - a 30 second timeout for reading HTTP/1 request heads, with the
+    ///   `runtime` feature,
+    /// - no HTTP/1 half-closures,
+    /// - at most 100 concurrent streams per HTTP/2 connection, and header
+    ///   lists of at most 16 KiB.
+    ///
+    /// It also rejects HTTP/1 requests with both `Content-Length` and
+    /// `Transfer-Encoding`, obsolete line folding, bare CRs, or whitespace
+    /// before a header colon, which proxies may parse differently.
+    ///
+    /// Options set afterwards override these.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use fluxio::server::conn::Http;
+    ///
+    /// let mut http = Http::new();
+    /// http.preset_strict_server()
+    ///     .allowed_hosts(["example.com"]);
+    /// ```
+    pub fn preset_strict_server(&mut self) -> &mut Self {
+        #[cfg(feature = "http1")]
+        self.max_buf_size(64 * 1024)
+            .http1_half_close(false)
+            .http1_reject_content_length_with_transfer_encoding(true)
+            .http1_reject_obs_fold(true)
+            .http1_reject_bare_cr(true)
+            .http1_reject_whitespace_before_colon(true);
+        #[cfg(all(feature = "http1", feature = "runtime"))]
+        self.http1_header_read_timeout(Duration::from_secs(30));
+        #[cfg(feature = "http2")]
+        self.http2_max_concurrent_streams(100)
+            .http2_max_header_list_size(16 * 1024);
+        self
+    }
+
+    /// Configure connections for the accepting side of a proxy, forwarding
+    /// requests as faithfully as possible.
+    ///
+    /// This preserves the original case of HTTP/1 header names, so they can
+    /// be forwarded as received, and supports HTTP/1 half-closures, since
+    /// the proxy can't know whether the origin server will still respond.
+    ///
+    /// Options set afterwards override these.
+    pub fn preset_lenient_proxy(&mut self) -> &mut Self {
+        #[cfg(feature = "http1")]
+        self.http1_preserve_header_case(true).http1_half_close(true);
+        self
+    }
+
+    /// Configure connections to use as little memory as possible, such as on
+    /// embedded devices, at the cost of throughput.
+    ///
+    /// This sets:
+    ///
+    /// - HTTP/1 read buffers of at most 8 KiB, the smallest allowed, which
+    ///   also bounds the size of request heads,
+    /// - HTTP/2 flow control windows of 64 KiB per stream and 128 KiB per
+    ///   connection, instead of an adaptive window,
+    /// - HTTP/2 frames of at most 16 KiB, write buffers of at most 16 KiB per
+    ///   stream, at most 16 concurrent streams, and header lists of at most
+    ///   16 KiB.
+    ///
+    /// Options set afterwards override these.
+    pub fn preset_low_memory(&mut self) -> &mut Self {
+        #[cfg(feature = "http1")]
+        self.max_buf_size(proto::h1::MINIMUM_MAX_BUFFER_SIZE);
+        #[cfg(feature = "http2")]
+        self.http2_initial_stream_window_size(64 * 1024)
+            .http2_initial_connection_window_size(128 * 1024)
+            .http2_max_frame_size(16 * 1024)
+            .http2_max_send_buf_size(16 * 1024)
+            .http2_max_concurrent_streams(16)
+            .http2_max_header_list_size(16 * 1024);
+        self
+    }
+
+    /// Configure connections with a [hardened profile](crate::hardened).
+    ///
+    /// See [`Profile`] for the options set by each profile. Setting one of
+    /// them again afterwards overrides it, and is listed by
+    /// [`hardened_posture`](Http::hardened_posture).
+    ///
+    /// Options of `h2` that aren't set by the profile keep the defaults of
+    /// the `h2` version in use.
+    pub fn preset_hardened(&mut self, profile: Profile) -> &mut Self {
+        match profile {
+            Profile::V1 => {
+                // V1 is the strict server preset, against rapid resets too.
+                self.preset_strict_server();
+                #[cfg(feature = "http2")]
+                self.http2_max_pending_accept_reset_streams(10);
+            }
+        }
+        self.hardened = Some(Posture::new(profile));
+        self
+    }
+
+    /// Returns the hardened profile applied with
+    /// [`preset_hardened`](Http::preset_hardened), and the options of it
+    /// overridden since.
+    pub fn hardened_posture(&self) -> Option<&Posture> {
+        self.hardened.as_ref()
+    }
+
+    /// Set an observer for the lifecycle events of connections.
+    ///
+    /// See the [`observer`](crate::observer) module for more.
+    pub fn observer(&mut self, observer: Arc<dyn ConnectionObserver>) -> &mut Self {
+        self.observer = Some(ObserverRef::new(observer));
+        self
+    }
+
+    /// Take options from a config that can be changed while serving.
+    ///
+    /// See [`LiveConfig`] for the options, and when changes take effect.
+    ///
+    /// Default is to only use the options set on this `Http`.
+    pub fn live_config(&mut self, config: LiveConfig) -> &mut Self {
+        // The live config can override the profile at any time.
+        Posture::record(&mut self.hardened, "live_config");
+        self.live_config = Some(config);
+        self
+    }
+
     /// Set the executor used to spawn background tasks.
     ///
     /// Default uses implicit default (like `tokio::spawn`).
@@ -567,15 +1206,52 @@ impl<E> Http<E> {
             h1_keep_alive: self.h1_keep_alive,
             h1_title_case_headers: self.h1_title_case_headers,
             h1_preserve_header_case: self.h1_preserve_header_case,
+            h1_preserve_raw_head: self.h1_preserve_raw_head,
+            h1_headers_raw: self.h1_headers_raw,
+            #[cfg(feature = "http1")]
+            h1_expect_continue: self.h1_expect_continue,
+            #[cfg(feature = "http1")]
+            h1_max_headers: self.h1_max_headers,
+            #[cfg(feature = "http1")]
+            h1_max_header_value_size: self.h1_max_header_value_size,
+            #[cfg(feature = "http1")]
+            h1_strict: self.h1_strict,
+            #[cfg(feature = "http1")]
+            h1_content_length_policy: self.h1_content_length_policy,
+            #[cfg(feature = "http1")]
+            h1_body_framing: self.h1_body_framing,
             #[cfg(all(feature = "http1", feature = "runtime"))]
             h1_header_read_timeout: self.h1_header_read_timeout,
             h1_writev: self.h1_writev,
             #[cfg(feature = "http2")]
             h2_builder: self.h2_builder,
+            #[cfg(all(feature = "http1", feature = "http2"))]
+            h2c_upgrade: self.h2c_upgrade,
             mode: self.mode,
             max_buf_size: self.max_buf_size,
+            min_buf_size: self.min_buf_size,
             pipeline_flush: self.pipeline_flush,
+            allowed_hosts: self.allowed_hosts,
+            early_hints: self.early_hints,
+            auto_date_header: self.auto_date_header,
+            max_request_body_size: self.max_request_body_size,
+            connect_info: self.connect_info,
+            health_check: self.health_check,
+            cors: self.cors,
+            observer: self.observer,
+            live_config: self.live_config,
+            hardened: self.hardened,
+        }
+    }
+
+    /// Sets the details of the connection about to be served, inserted into
+    /// each of its requests.
+    pub(super) fn set_connect_info(&mut self, info: ConnectInfo) {
+        #[cfg(feature = "http2")]
+        {
+            self.h2_builder.connect_info = Some(info.clone());
         }
+        self.connect_info = Some(info);
     }
 
     /// Bind a connection together with a [`Service`](crate::service::Service).
@@ -631,8 +1307,41 @@ impl<E> Http<E> {
                 if self.h1_preserve_header_case {
                     conn.set_preserve_header_case();
                 }
+                if self.h1_preserve_raw_head {
+                    conn.set_preserve_raw_head();
+                }
+                if !self.auto_date_header {
+                    conn.disable_date_header();
+                }
+                conn.set_raw_headers(self.h1_headers_raw);
+                if self.h1_expect_continue == ExpectContinue::Defer {
+                    conn.set_defer_continue();
+                }
+                if let Some(max) = self.h1_max_headers {
+                    conn.set_http1_max_headers(max);
+                }
+                if let Some(max) = self.h1_max_header_value_size {
+                    conn.set_http1_max_header_value_size(max);
+                }
+                conn.set_strict_parsing(self.h1_strict);
+                conn.set_content_length_policy(self.h1_content_length_policy);
+                if let Some(ref body_framing) = self.h1_body_framing {
+                    conn.set_body_framing(body_framing.clone());
+                }
+                #[cfg(feature = "http2")]
+                if self.h2c_upgrade && self.mode == ConnectionMode::Fallback {
+                    conn.set_h2c_upgrade();
+                }
+                if let Some(ref allowed_hosts) = self.allowed_hosts {
+                    conn.set_allowed_hosts(allowed_hosts.clone());
+                }
                 #[cfg(all(feature = "http1", feature = "runtime"))]
-                if let Some(header_read_timeout) = self.h1_header_read_timeout {
+                if let Some(header_read_timeout) = self
+                    .live_config
+                    .as_ref()
+                    .and_then(LiveConfig::http1_header_read_timeout)
+                    .or(self.h1_header_read_timeout)
+                {
                     conn.set_http1_header_read_timeout(header_read_timeout);
                 }
                 if let Some(writev) = self.h1_writev {
@@ -643,10 +1352,37 @@ impl<E> Http<E> {
                     }
                 }
                 conn.set_flush_pipeline(self.pipeline_flush);
-                if let Some(max) = self.max_buf_size {
+                if let Some(max) = self
+                    .live_config
+                    .as_ref()
+                    .and_then(LiveConfig::max_buf_size)
+                    .or(self.max_buf_size)
+                {
                     conn.set_max_buf_size(max);
                 }
-                let sd = proto::h1::dispatch::Server::new(service);
+                if let Some(min) = self.min_buf_size {
+                    conn.set_min_buf_size(min);
+                }
+                let mut sd = proto::h1::dispatch::Server::new(service);
+                if let Some(ref check) = self.health_check {
+                    sd.set_health_check(check.clone());
+                }
+                if let Some(ref cors) = self.cors {
+                    sd.set_cors(cors.clone());
+                }
+                if self.early_hints {
+                    sd.set_early_hints();
+                }
+                sd.set_expect_continue(self.h1_expect_continue);
+                if let Some(max) = self.max_request_body_size {
+                    sd.set_max_body_size(max);
+                }
+                if let Some(ref info) = self.connect_info {
+                    sd.set_connect_info(info.clone());
+                }
+                if let Some(ref observer) = self.observer {
+                    sd.set_observed(observer.connect(http::Version::HTTP_11, true));
+                }
                 ProtoServer::H1 {
                     h1: proto::h1::Dispatcher::new(sd, conn),
                 }
@@ -663,14 +1399,24 @@ impl<E> Http<E> {
             #[cfg(feature = "http2")]
             ConnectionMode::H2Only => {
                 let rewind_io = Rewind::new(io);
-                let h2 =
-                    proto::h2::Server::new(rewind_io, service, &self.h2_builder, self.exec.clone());
+                let observed = self
+                    .observer
+                    .as_ref()
+                    .map(|observer| observer.connect(http::Version::HTTP_2, true));
+                let h2 = proto::h2::Server::new(
+                    rewind_io,
+                    service,
+                    &self.h2_builder,
+                    self.exec.clone(),
+                    observed,
+                );
                 ProtoServer::H2 { h2 }
             }
         };
 
         Connection {
             conn: Some(proto),
+            shutdown_deadline: Default::default(),
             #[cfg(all(feature = "http1", feature = "http2"))]
             fallback: if self.mode == ConnectionMode::Fallback {
                 Fallback::ToHttp2(self.h2_builder.clone(), self.exec.clone())
@@ -724,6 +1470,42 @@ where
         }
     }
 
+    /// Start a graceful shutdown process for this connection, aborting it
+    /// if it hasn't finished after `deadline`.
+    ///
+    /// Like with [`graceful_shutdown`](Connection::graceful_shutdown), HTTP/1
+    /// connections close after the response in progress, and HTTP/2
+    /// connections send a `GOAWAY` and finish their open streams. Once the
+    /// deadline elapses, the `Connection` future resolves with an error for
+    /// which [`Error::is_timeout`](crate::Error::is_timeout) is true, and
+    /// dropping it closes the connection.
+    ///
+    /// Calling this again can only bring the deadline forward.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn graceful_shutdown_with_deadline(mut self: Pin<&mut Self>, deadline: Duration) {
+        self.as_mut().graceful_shutdown();
+
+        let deadline = Instant::now() + deadline;
+        match self.shutdown_deadline {
+            Some(ref sleep) if sleep.deadline() <= deadline => (),
+            _ => self.shutdown_deadline = Some(Box::pin(tokio::time::sleep_until(deadline))),
+        }
+    }
+
+    #[cfg(feature = "runtime")]
+    fn poll_shutdown_deadline(&mut self, cx: &mut task::Context<'_>) -> Poll<crate::Error> {
+        match self.shutdown_deadline {
+            Some(ref mut sleep) => {
+                ready!(sleep.as_mut().poll(cx));
+                debug!("graceful shutdown deadline elapsed, aborting connection");
+                self.shutdown_deadline = None;
+                Poll::Ready(crate::Error::new_shutdown_timeout())
+            }
+            None => Poll::Pending,
+        }
+    }
+
     /// Return the inner IO object, and additional information.
     ///
     /// If the IO object has been "rewound" the io will not contain those bytes rewound.
@@ -773,6 +1555,11 @@ where
         S::Future: Unpin,
         B: Unpin,
     {
+        #[cfg(feature = "runtime")]
+        if let Poll::Ready(err) = self.poll_shutdown_deadline(cx) {
+            return Poll::Ready(Err(err));
+        }
+
         loop {
             match *self.conn.as_mut().unwrap() {
                 #[cfg(feature = "http1")]
@@ -831,19 +1618,38 @@ where
         trace!("Trying to upgrade connection to h2");
         let conn = self.conn.take();
 
-        let (io, read_buf, dispatch) = match conn.unwrap() {
-            ProtoServer::H1 { h1, .. } => h1.into_inner(),
+        let (io, read_buf, mut dispatch, h2c_request) = match conn.unwrap() {
+            ProtoServer::H1 { mut h1, .. } => {
+                let h2c_request = h1.take_h2c_request();
+                let (io, read_buf, dispatch) = h1.into_inner();
+                (io, read_buf, dispatch, h2c_request)
+            }
             ProtoServer::H2 { .. } => {
                 panic!("h2 cannot into_inner");
             }
         };
         let mut rewind_io = Rewind::new(io);
-        rewind_io.rewind(read_buf);
+        rewind_io.rewind(match h2c_request {
+            // answer the request that asked to switch as stream 1
+            Some(request) => proto::h2::h2c::replay_request(read_buf, request),
+            None => read_buf,
+        });
         let (builder, exec) = match self.fallback {
             Fallback::ToHttp2(ref builder, ref exec) => (builder, exec),
             Fallback::Http1Only => unreachable!("upgrade_h2 with Fallback::Http1Only"),
         };
-        let h2 = proto::h2::Server::new(rewind_io, dispatch.into_service(), builder, exec.clone());
+        // the connection is still the same one, now known to be HTTP/2
+        let observed = dispatch.take_observed().map(|mut observed| {
+            observed.set_version(http::Version::HTTP_2);
+            observed
+        });
+        let h2 = proto::h2::Server::new(
+            rewind_io,
+            dispatch.into_service(),
+            builder,
+            exec.clone(),
+            observed,
+        );
 
         debug_assert!(self.conn.is_none());
         self.conn = Some(ProtoServer::H2 { h2 });
@@ -873,6 +1679,11 @@ where
     type Output = crate::Result<()>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        #[cfg(feature = "runtime")]
+        if let Poll::Ready(err) = self.poll_shutdown_deadline(cx) {
+            return Poll::Ready(Err(err));
+        }
+
         loop {
             match ready!(Pin::new(self.conn.as_mut().unwrap()).poll(cx)) {
                 Ok(done) => {
@@ -999,6 +1810,16 @@ mod upgrades {
         pub fn graceful_shutdown(mut self: Pin<&mut Self>) {
             Pin::new(&mut self.inner).graceful_shutdown()
         }
+
+        /// Start a graceful shutdown process for this connection, aborting
+        /// it if it hasn't finished after `deadline`.
+        ///
+        /// See [`Connection::graceful_shutdown_with_deadline`].
+        #[cfg(feature = "runtime")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+        pub fn graceful_shutdown_with_deadline(mut self: Pin<&mut Self>, deadline: Duration) {
+            Pin::new(&mut self.inner).graceful_shutdown_with_deadline(deadline)
+        }
     }
 
     impl<I, B, S, E> Future for UpgradeableConnection<I, S, E>
@@ -1013,6 +1834,11 @@ mod upgrades {
         type Output = crate::Result<()>;
 
         fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+            #[cfg(feature = "runtime")]
+            if let Poll::Ready(err) = self.inner.poll_shutdown_deadline(cx) {
+                return Poll::Ready(Err(err));
+            }
+
             loop {
                 match ready!(Pin::new(self.inner.conn.as_mut().unwrap()).poll(cx)) {
                     Ok(proto::Dispatched::Shutdown) => return Poll::Ready(Ok(())),
@@ -1048,3 +1874,101 @@ mod upgrades {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_strict_server() {
+        let mut http = Http::new();
+        #[cfg(feature = "http1")]
+        http.http1_half_close(true);
+        http.preset_strict_server();
+
+        #[cfg(feature = "http1")]
+        {
+            assert_eq!(http.max_buf_size, Some(64 * 1024));
+            assert!(!http.h1_half_close);
+            assert!(http.h1_strict.content_length_with_transfer_encoding);
+            assert!(http.h1_strict.obs_fold);
+            assert!(http.h1_strict.bare_cr);
+            assert!(http.h1_strict.whitespace_before_colon);
+        }
+        #[cfg(all(feature = "http1", feature = "runtime"))]
+        assert_eq!(http.h1_header_read_timeout, Some(Duration::from_secs(30)));
+        #[cfg(feature = "http2")]
+        {
+            assert_eq!(http.h2_builder.max_concurrent_streams, Some(100));
+            assert_eq!(http.h2_builder.max_header_list_size, 16 * 1024);
+        }
+    }
+
+    #[test]
+    fn preset_hardened_is_strict() {
+        let mut strict = Http::new();
+        strict.preset_strict_server();
+        let mut hardened = Http::new();
+        hardened.preset_hardened(Profile::V1);
+
+        #[cfg(feature = "http1")]
+        {
+            assert_eq!(hardened.max_buf_size, strict.max_buf_size);
+            assert!(hardened.h1_strict.content_length_with_transfer_encoding);
+            assert!(hardened.h1_strict.obs_fold);
+            assert!(hardened.h1_strict.bare_cr);
+            assert!(hardened.h1_strict.whitespace_before_colon);
+        }
+        #[cfg(all(feature = "http1", feature = "runtime"))]
+        assert_eq!(
+            hardened.h1_header_read_timeout,
+            strict.h1_header_read_timeout
+        );
+        #[cfg(feature = "http2")]
+        assert_eq!(
+            hardened.h2_builder.max_pending_accept_reset_streams,
+            Some(10)
+        );
+        assert!(hardened.hardened_posture().unwrap().is_hardened());
+
+        #[cfg(feature = "http1")]
+        {
+            hardened.http1_reject_bare_cr(false);
+            assert_eq!(
+                hardened.hardened_posture().unwrap().overridden(),
+                ["http1_reject_bare_cr"]
+            );
+        }
+    }
+
+    #[test]
+    fn preset_lenient_proxy() {
+        let mut http = Http::new();
+        http.preset_lenient_proxy();
+
+        #[cfg(feature = "http1")]
+        {
+            assert!(http.h1_preserve_header_case);
+            assert!(http.h1_half_close);
+        }
+    }
+
+    #[test]
+    fn preset_low_memory() {
+        let mut http = Http::new();
+        http.preset_low_memory();
+
+        #[cfg(feature = "http1")]
+        assert_eq!(http.max_buf_size, Some(proto::h1::MINIMUM_MAX_BUFFER_SIZE));
+        #[cfg(feature = "http2")]
+        {
+            assert!(!http.h2_builder.adaptive_window);
+            assert_eq!(http.h2_builder.initial_stream_window_size, 64 * 1024);
+            assert_eq!(http.h2_builder.initial_conn_window_size, 128 * 1024);
+            assert_eq!(http.h2_builder.max_frame_size, 16 * 1024);
+            assert_eq!(http.h2_builder.max_send_buffer_size, 16 * 1024);
+            assert_eq!(http.h2_builder.max_concurrent_streams, Some(16));
+            assert_eq!(http.h2_builder.max_header_list_size, 16 * 1024);
+        }
+    }
+}
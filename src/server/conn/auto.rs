@@ -0,0 +1,272 @@
+//! Server connections speaking HTTP/1 or HTTP/2, as chosen by the client.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use bytes::Bytes;
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tracing::trace;
+
+use super::{http1, http2, ConnectionMode, Http};
+use crate::body::{Body, HttpBody};
+use crate::common::exec::ConnStreamExec;
+use crate::common::io::Rewind;
+use crate::common::{task, Future, Pin, Poll};
+use crate::service::HttpService;
+
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// A configuration for serving connections with either HTTP/1 or HTTP/2.
+///
+/// The first bytes of each connection are read to pick the protocol:
+/// connections starting with the HTTP/2 connection preface are served with
+/// HTTP/2, and all others with HTTP/1. This allows serving both without TLS
+/// and ALPN, such as gRPC clients using h2c with prior knowledge next to
+/// HTTP/1 clients.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::rt::Executor;
+/// use fluxio::server::conn::auto;
+///
+/// # #[derive(Clone)]
+/// # struct TokioExecutor;
+/// # impl<F: std::future::Future + Send + 'static> Executor<F> for TokioExecutor
+/// # where F::Output: Send + 'static {
+/// #     fn execute(&self, fut: F) { tokio::spawn(fut); }
+/// # }
+/// let mut builder = auto::Builder::new(TokioExecutor);
+/// builder.http1().keep_alive(false);
+/// builder.http2().max_concurrent_streams(100);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Builder<E> {
+    http1: http1::Builder,
+    http2: http2::Builder<E>,
+}
+
+impl<E> Builder<E> {
+    /// Creates a configuration with the default options, spawning HTTP/2
+    /// streams on `exec`.
+    pub fn new(exec: E) -> Builder<E> {
+        Builder {
+            http1: http1::Builder::new(),
+            http2: http2::Builder::new(exec),
+        }
+    }
+
+    /// The options of connections served with HTTP/1.
+    pub fn http1(&mut self) -> &mut http1::Builder {
+        &mut self.http1
+    }
+
+    /// The options of connections served with HTTP/2.
+    pub fn http2(&mut self) -> &mut http2::Builder<E> {
+        &mut self.http2
+    }
+
+    /// Bind a connection together with a [`Service`](crate::service::Service).
+    ///
+    /// This returns a Future that must be polled in order for HTTP to be
+    /// driven on the connection. It first reads until the HTTP/2 preface
+    /// was either received or ruled out, and the bytes read are then served
+    /// as part of the connection.
+    pub fn serve_connection<S, I, Bd>(&self, io: I, service: S) -> Connection<I, S, E>
+    where
+        S: HttpService<Body, ResBody = Bd>,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Bd: HttpBody + 'static,
+        Bd::Error: Into<Box<dyn StdError + Send + Sync>>,
+        I: AsyncRead + AsyncWrite + Unpin,
+        E: ConnStreamExec<S::Future, Bd>,
+    {
+        Connection {
+            state: State::ReadVersion {
+                io: Some(io),
+                service: Some(service),
+                protocol: Some(self.protocol()),
+                buf: [0; 24],
+                filled: 0,
+                shutdown: false,
+            },
+        }
+    }
+
+    /// Combines the HTTP/1 and HTTP/2 options.
+    fn protocol(&self) -> Http<E>
+    where
+        E: Clone,
+    {
+        let h1 = &self.http1.inner;
+        let mut http = self.http2.inner.clone();
+        http.h1_half_close = h1.h1_half_close;
+        http.h1_keep_alive = h1.h1_keep_alive;
+        http.h1_title_case_headers = h1.h1_title_case_headers;
+        http.h1_preserve_header_case = h1.h1_preserve_header_case;
+        http.h1_preserve_raw_head = h1.h1_preserve_raw_head;
+        http.h1_headers_raw = h1.h1_headers_raw;
+        http.h1_expect_continue = h1.h1_expect_continue;
+        http.h1_max_headers = h1.h1_max_headers;
+        http.h1_max_header_value_size = h1.h1_max_header_value_size;
+        http.h1_strict = h1.h1_strict;
+        http.h1_content_length_policy = h1.h1_content_length_policy;
+        #[cfg(feature = "runtime")]
+        {
+            http.h1_header_read_timeout = h1.h1_header_read_timeout;
+        }
+        http.h1_writev = h1.h1_writev;
+        http.max_buf_size = h1.max_buf_size;
+        http.min_buf_size = h1.min_buf_size;
+        http.pipeline_flush = h1.pipeline_flush;
+        http.auto_date_header = h1.auto_date_header;
+        http.max_request_body_size = h1.max_request_body_size;
+        http
+    }
+}
+
+pin_project! {
+    /// A future binding a connection with a Service, serving it with the
+    /// protocol the client picked.
+    ///
+    /// Polling this future will drive HTTP forward.
+    #[must_use = "futures do nothing unless polled"]
+    pub struct Connection<I, S, E>
+    where
+        S: HttpService<Body>,
+    {
+        state: State<I, S, E>,
+    }
+}
+
+enum State<I, S, E>
+where
+    S: HttpService<Body>,
+{
+    ReadVersion {
+        io: Option<I>,
+        service: Option<S>,
+        protocol: Option<Http<E>>,
+        buf: [u8; 24],
+        filled: usize,
+        shutdown: bool,
+    },
+    Serving(super::Connection<Rewind<I>, S, E>),
+}
+
+impl<I, B, S, E> Connection<I, S, E>
+where
+    S: HttpService<Body, ResBody = B>,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+    I: AsyncRead + AsyncWrite + Unpin,
+    B: HttpBody + 'static,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    E: ConnStreamExec<S::Future, B>,
+{
+    /// Start a graceful shutdown process for this connection.
+    ///
+    /// A connection still being read to pick the protocol is closed right
+    /// away, since no request was received on it yet.
+    ///
+    /// This `Connection` should continue to be polled until shutdown
+    /// can finish.
+    pub fn graceful_shutdown(self: Pin<&mut Self>) {
+        match *self.project().state {
+            State::ReadVersion {
+                ref mut shutdown, ..
+            } => *shutdown = true,
+            State::Serving(ref mut conn) => Pin::new(conn).graceful_shutdown(),
+        }
+    }
+}
+
+impl<I, B, S, E> Future for Connection<I, S, E>
+where
+    S: HttpService<Body, ResBody = B>,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+    I: AsyncRead + AsyncWrite + Unpin + 'static,
+    B: HttpBody + 'static,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    E: ConnStreamExec<S::Future, B>,
+{
+    type Output = crate::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let state = self.project().state;
+        loop {
+            match *state {
+                State::ReadVersion {
+                    ref mut io,
+                    ref mut service,
+                    ref mut protocol,
+                    ref mut buf,
+                    ref mut filled,
+                    shutdown,
+                } => {
+                    if shutdown {
+                        return Poll::Ready(Ok(()));
+                    }
+                    let is_h2 = ready!(poll_read_version(
+                        io.as_mut().expect("polled after complete"),
+                        buf,
+                        filled,
+                        cx
+                    ))
+                    .map_err(crate::Error::new_io)?;
+                    trace!("connection preface read, is HTTP/2: {}", is_h2);
+
+                    let io = Rewind::new_buffered(
+                        io.take().unwrap(),
+                        Bytes::copy_from_slice(&buf[..*filled]),
+                    );
+                    let mut protocol = protocol.take().unwrap();
+                    protocol.mode = if is_h2 {
+                        ConnectionMode::H2Only
+                    } else {
+                        ConnectionMode::H1Only
+                    };
+                    let conn = protocol.serve_connection(io, service.take().unwrap());
+                    *state = State::Serving(conn);
+                }
+                State::Serving(ref mut conn) => return Pin::new(conn).poll(cx),
+            }
+        }
+    }
+}
+
+impl<I, S, E> fmt::Debug for Connection<I, S, E>
+where
+    S: HttpService<Body>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection").finish()
+    }
+}
+
+/// Reads until the HTTP/2 preface was either received, returning `true`,
+/// or ruled out.
+fn poll_read_version<I>(
+    io: &mut I,
+    buf: &mut [u8; 24],
+    filled: &mut usize,
+    cx: &mut task::Context<'_>,
+) -> Poll<std::io::Result<bool>>
+where
+    I: AsyncRead + Unpin,
+{
+    while *filled < H2_PREFACE.len() {
+        let mut read_buf = ReadBuf::new(&mut buf[*filled..]);
+        ready!(Pin::new(&mut *io).poll_read(cx, &mut read_buf))?;
+        let n = read_buf.filled().len();
+        if n == 0 {
+            // Let HTTP/1 handle the closed connection.
+            return Poll::Ready(Ok(false));
+        }
+        *filled += n;
+        if buf[..*filled] != H2_PREFACE[..*filled] {
+            return Poll::Ready(Ok(false));
+        }
+    }
+    Poll::Ready(Ok(true))
+}
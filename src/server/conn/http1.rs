@@ -0,0 +1,407 @@
+//! HTTP/1 server connections.
+
+use std::error::Error as StdError;
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{Connection, ExpectContinue, Http};
+use crate::body::{Body, HttpBody};
+use crate::common::exec::{ConnStreamExec, Exec};
+use crate::service::HttpService;
+
+/// A configuration for serving HTTP/1 connections.
+///
+/// Unlike [`Http`], this never serves HTTP/2, and is available without the
+/// `http2` feature, so servers only speaking HTTP/1 don't depend on `h2`.
+///
+/// # Example
+///
+/// ```
+/// # use fluxio::{Body, Request, Response};
+/// # use fluxio::service::service_fn;
+/// # use tokio::io::{AsyncRead, AsyncWrite};
+/// use fluxio::server::conn::http1;
+///
+/// # async fn run<I>(io: I)
+/// # where
+/// #     I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+/// # {
+/// let service = service_fn(|_req: Request<Body>| async {
+///     Ok::<_, std::convert::Infallible>(Response::new(Body::from("Hello World")))
+/// });
+///
+/// let conn = http1::Builder::new()
+///     .keep_alive(true)
+///     .serve_connection(io, service);
+/// if let Err(err) = conn.await {
+///     eprintln!("server connection error: {}", err);
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Builder {
+    pub(super) inner: Http,
+}
+
+impl Builder {
+    /// Creates a configuration with the default options.
+    pub fn new() -> Builder {
+        let mut inner = Http::new();
+        inner.http1_only(true);
+        Builder { inner }
+    }
+
+    /// Set whether connections should support half-closures.
+    ///
+    /// See [`Http::http1_half_close`].
+    ///
+    /// Default is `false`.
+    pub fn half_close(&mut self, val: bool) -> &mut Self {
+        self.inner.http1_half_close(val);
+        self
+    }
+
+    /// Enables or disables keep-alive.
+    ///
+    /// Default is `true`.
+    pub fn keep_alive(&mut self, val: bool) -> &mut Self {
+        self.inner.http1_keep_alive(val);
+        self
+    }
+
+    /// Set whether to write header names as title case.
+    ///
+    /// Default is `false`.
+    pub fn title_case_headers(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_title_case_headers(enabled);
+        self
+    }
+
+    /// Set whether to support preserving original header cases.
+    ///
+    /// See [`Http::http1_preserve_header_case`].
+    ///
+    /// Default is `false`.
+    pub fn preserve_header_case(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_preserve_header_case(enabled);
+        self
+    }
+
+    /// Set whether to keep the raw bytes of each received request head.
+    ///
+    /// See [`Http::http1_preserve_raw_head`].
+    ///
+    /// Default is `false`.
+    pub fn preserve_raw_head(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_preserve_raw_head(enabled);
+        self
+    }
+
+    /// Set whether to store the raw bytes of each received request head in a
+    /// [`RawHeaders`](crate::ext::RawHeaders) extension on the `Request`.
+    ///
+    /// Default is `false`.
+    pub fn headers_raw(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_headers_raw(enabled);
+        self
+    }
+
+    /// Set how requests sending `Expect: 100-continue` are handled.
+    ///
+    /// Default is [`ExpectContinue::Automatic`].
+    pub fn expect_continue(&mut self, policy: ExpectContinue) -> &mut Self {
+        self.inner.http1_expect_continue(policy);
+        self
+    }
+
+    /// Set the maximum number of headers of a request.
+    ///
+    /// See [`Http::http1_max_headers`](super::Http::http1_max_headers) for
+    /// more.
+    ///
+    /// Default is 100.
+    pub fn max_headers(&mut self, max: usize) -> &mut Self {
+        self.inner.http1_max_headers(max);
+        self
+    }
+
+    /// Set the maximum size of a request header value, in bytes.
+    ///
+    /// Default is no limit other than the size of the read buffer.
+    pub fn max_header_value_size(&mut self, max: usize) -> &mut Self {
+        self.inner.http1_max_header_value_size(max);
+        self
+    }
+
+    /// Set how requests with multiple `Content-Length` values are read.
+    ///
+    /// See [`ContentLengthPolicy`](crate::ext::ContentLengthPolicy) for the
+    /// choices.
+    ///
+    /// Default is `ContentLengthPolicy::MergeIfEqual`.
+    pub fn content_length_policy(&mut self, policy: crate::ext::ContentLengthPolicy) -> &mut Self {
+        self.inner.http1_content_length_policy(policy);
+        self
+    }
+
+    /// Set a callback choosing how response bodies of unknown length are
+    /// framed.
+    ///
+    /// See [`BodyFraming`](crate::ext::BodyFraming) for the choices.
+    ///
+    /// Default is `BodyFraming::Chunked`.
+    pub fn body_framing<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&crate::body::SizeHint) -> crate::ext::BodyFraming + Send + Sync + 'static,
+    {
+        self.inner.http1_body_framing(f);
+        self
+    }
+
+    /// Set whether to reject requests with both a `Content-Length` and a
+    /// `Transfer-Encoding` header.
+    ///
+    /// See [`Http::http1_reject_content_length_with_transfer_encoding`](super::Http::http1_reject_content_length_with_transfer_encoding)
+    /// for more.
+    ///
+    /// Default is false.
+    pub fn reject_content_length_with_transfer_encoding(&mut self, enabled: bool) -> &mut Self {
+        self.inner
+            .http1_reject_content_length_with_transfer_encoding(enabled);
+        self
+    }
+
+    /// Set whether to reject requests with obsolete line folding.
+    ///
+    /// See [`Http::http1_reject_obs_fold`](super::Http::http1_reject_obs_fold)
+    /// for more.
+    ///
+    /// Default is false.
+    pub fn reject_obs_fold(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_reject_obs_fold(enabled);
+        self
+    }
+
+    /// Set whether to reject requests with a CR that is not followed by a
+    /// LF in their head.
+    ///
+    /// See [`Http::http1_reject_bare_cr`](super::Http::http1_reject_bare_cr)
+    /// for more.
+    ///
+    /// Default is false.
+    pub fn reject_bare_cr(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_reject_bare_cr(enabled);
+        self
+    }
+
+    /// Set whether to reject requests with whitespace between a header name
+    /// and its colon.
+    ///
+    /// See [`Http::http1_reject_whitespace_before_colon`](super::Http::http1_reject_whitespace_before_colon)
+    /// for more.
+    ///
+    /// Default is false.
+    pub fn reject_whitespace_before_colon(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http1_reject_whitespace_before_colon(enabled);
+        self
+    }
+
+    /// Set a timeout for reading client request headers. If a client does not
+    /// transmit the entire header within this time, the connection is closed.
+    ///
+    /// Default is None.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn header_read_timeout(&mut self, read_timeout: Duration) -> &mut Self {
+        self.inner.http1_header_read_timeout(read_timeout);
+        self
+    }
+
+    /// Set whether to try to use vectored writes, or always flatten into a
+    /// single buffer.
+    ///
+    /// See [`Http::http1_writev`].
+    ///
+    /// Default is `auto`.
+    pub fn writev(&mut self, val: bool) -> &mut Self {
+        self.inner.http1_writev(val);
+        self
+    }
+
+    /// Set the maximum buffer size for the connection.
+    ///
+    /// Default is ~400kb.
+    ///
+    /// # Panics
+    ///
+    /// The minimum value allowed is 8192. This method panics if the passed `max` is less than the minimum.
+    pub fn max_buf_size(&mut self, max: usize) -> &mut Self {
+        self.inner.max_buf_size(max);
+        self
+    }
+
+    /// Set the minimum buffer size for the connection.
+    ///
+    /// See [`Http::min_buf_size`] for more.
+    ///
+    /// Default is 8kb.
+    ///
+    /// # Panics
+    ///
+    /// The minimum value allowed is 64. This method panics if the passed `min` is less than the minimum.
+    pub fn min_buf_size(&mut self, min: usize) -> &mut Self {
+        self.inner.min_buf_size(min);
+        self
+    }
+
+    /// Aggregates flushes to better support pipelined responses.
+    ///
+    /// Experimental, may have bugs.
+    ///
+    /// Default is false.
+    pub fn pipeline_flush(&mut self, enabled: bool) -> &mut Self {
+        self.inner.pipeline_flush(enabled);
+        self
+    }
+
+    /// Set whether responses without a `Date` header get one with the
+    /// current time.
+    ///
+    /// See [`Http::auto_date_header`] for more.
+    pub fn auto_date_header(&mut self, enabled: bool) -> &mut Self {
+        self.inner.auto_date_header(enabled);
+        self
+    }
+
+    /// Set the maximum size of request bodies, in bytes.
+    ///
+    /// See [`Http::max_request_body_size`] for more.
+    pub fn max_request_body_size(&mut self, max: u64) -> &mut Self {
+        self.inner.max_request_body_size(max);
+        self
+    }
+
+    /// Bind a connection together with a [`Service`](crate::service::Service).
+    ///
+    /// This returns a Future that must be polled in order for HTTP to be
+    /// driven on the connection.
+    pub fn serve_connection<S, I, Bd>(&self, io: I, service: S) -> Connection<I, S>
+    where
+        S: HttpService<Body, ResBody = Bd>,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Bd: HttpBody + 'static,
+        Bd::Error: Into<Box<dyn StdError + Send + Sync>>,
+        I: AsyncRead + AsyncWrite + Unpin,
+        Exec: ConnStreamExec<S::Future, Bd>,
+    {
+        self.inner.serve_connection(io, service)
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::service::service_fn;
+    use crate::{Request, Response};
+
+    #[tokio::test]
+    async fn serves_http1() {
+        let svc = service_fn(|_req: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::from("hello")))
+        });
+        let (mut client, io) = tokio::io::duplex(1024);
+        let conn = Builder::new()
+            .keep_alive(false)
+            .title_case_headers(true)
+            .serve_connection(io, svc);
+        tokio::spawn(conn);
+
+        client
+            .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .unwrap();
+        // keep-alive is disabled, so the connection closes after the response
+        let mut res = Vec::new();
+        client.read_to_end(&mut res).await.unwrap();
+        let res = String::from_utf8(res).unwrap();
+        assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+        assert!(res.contains("\r\nContent-Length: 5\r\n"), "{}", res);
+        assert!(res.ends_with("\r\n\r\nhello"), "{}", res);
+    }
+
+    #[cfg(feature = "runtime")]
+    #[tokio::test]
+    async fn header_read_timeout_closes_slow_clients() {
+        use std::time::Duration;
+
+        tokio::time::pause();
+        let svc = service_fn(|_req: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        });
+
+        let mut builder = Builder::new();
+        builder.header_read_timeout(Duration::from_secs(5));
+        let (mut client, io) = tokio::io::duplex(1024);
+        let conn = tokio::spawn(builder.serve_connection(io, svc));
+
+        client.write_all(b"GET / HTTP/1.1\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        client.write_all(b"host: example.com\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+
+        let err = conn.await.unwrap().unwrap_err();
+        assert!(err.is_header_read_timeout(), "{:?}", err);
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn max_request_body_size_fails_larger_bodies() {
+        let svc = service_fn(|req: Request<Body>| async {
+            let mut res = Response::new(Body::empty());
+            match crate::body::to_bytes(req.into_body()).await {
+                Ok(body) => *res.body_mut() = Body::from(body),
+                Err(err) if err.is_body_too_large() => {
+                    *res.status_mut() = crate::StatusCode::PAYLOAD_TOO_LARGE
+                }
+                Err(err) => panic!("{:?}", err),
+            }
+            Ok::<_, Infallible>(res)
+        });
+
+        let mut builder = Builder::new();
+        builder.keep_alive(false).max_request_body_size(5);
+        for (req, status) in [
+            ("content-length: 5\r\n\r\nhello", "200 OK"),
+            ("content-length: 6\r\n\r\n", "413 Payload Too Large"),
+            (
+                "transfer-encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n",
+                "413 Payload Too Large",
+            ),
+        ] {
+            let (mut client, io) = tokio::io::duplex(1024);
+            tokio::spawn(builder.serve_connection(io, svc.clone()));
+            let req = format!("POST / HTTP/1.1\r\nhost: example.com\r\n{}", req);
+            client.write_all(req.as_bytes()).await.unwrap();
+            let mut res = Vec::new();
+            client.read_to_end(&mut res).await.unwrap();
+            let res = String::from_utf8(res).unwrap();
+            assert!(
+                res.starts_with(&format!("HTTP/1.1 {}\r\n", status)),
+                "{}",
+                res
+            );
+        }
+    }
+}
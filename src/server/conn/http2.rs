@@ -0,0 +1,173 @@
+//! HTTP/2 server connections.
+
+use std::error::Error as StdError;
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{Connection, Http};
+use crate::body::{Body, HttpBody};
+use crate::common::exec::ConnStreamExec;
+use crate::service::HttpService;
+
+/// A configuration for serving HTTP/2 connections.
+///
+/// Unlike [`Http`], this never serves HTTP/1, expecting each connection to
+/// start with the HTTP/2 preface, such as after negotiating `h2` with ALPN.
+///
+/// The executor spawns a task for each stream of a connection.
+#[derive(Clone, Debug)]
+pub struct Builder<E> {
+    pub(super) inner: Http<E>,
+}
+
+impl<E> Builder<E> {
+    /// Creates a configuration with the default options, spawning streams
+    /// on `exec`.
+    pub fn new(exec: E) -> Builder<E> {
+        let mut inner = Http::new().with_executor(exec);
+        inner.http2_only(true);
+        Builder { inner }
+    }
+
+    /// Sets the [`SETTINGS_INITIAL_WINDOW_SIZE`][spec] option for stream-level
+    /// flow control.
+    ///
+    /// Passing `None` will do nothing.
+    ///
+    /// If not set, fluxio will use a default.
+    ///
+    /// [spec]: https://http2.github.io/http2-spec/#SETTINGS_INITIAL_WINDOW_SIZE
+    pub fn initial_stream_window_size(&mut self, sz: impl Into<Option<u32>>) -> &mut Self {
+        self.inner.http2_initial_stream_window_size(sz);
+        self
+    }
+
+    /// Sets the max connection-level flow control.
+    ///
+    /// Passing `None` will do nothing.
+    ///
+    /// If not set, fluxio will use a default.
+    pub fn initial_connection_window_size(&mut self, sz: impl Into<Option<u32>>) -> &mut Self {
+        self.inner.http2_initial_connection_window_size(sz);
+        self
+    }
+
+    /// Sets whether to use an adaptive flow control.
+    ///
+    /// Enabling this will override the limits set in
+    /// `initial_stream_window_size` and `initial_connection_window_size`.
+    pub fn adaptive_window(&mut self, enabled: bool) -> &mut Self {
+        self.inner.http2_adaptive_window(enabled);
+        self
+    }
+
+    /// Sets the maximum frame size to use.
+    ///
+    /// Passing `None` will do nothing.
+    ///
+    /// If not set, fluxio will use a default.
+    pub fn max_frame_size(&mut self, sz: impl Into<Option<u32>>) -> &mut Self {
+        self.inner.http2_max_frame_size(sz);
+        self
+    }
+
+    /// Sets the [`SETTINGS_MAX_CONCURRENT_STREAMS`][spec] option.
+    ///
+    /// Default is no limit (`std::u32::MAX`). Passing `None` will do nothing.
+    ///
+    /// [spec]: https://http2.github.io/http2-spec/#SETTINGS_MAX_CONCURRENT_STREAMS
+    pub fn max_concurrent_streams(&mut self, max: impl Into<Option<u32>>) -> &mut Self {
+        self.inner.http2_max_concurrent_streams(max);
+        self
+    }
+
+    /// Sets an interval for Ping frames should be sent to keep a connection
+    /// alive.
+    ///
+    /// Pass `None` to disable keep-alive.
+    ///
+    /// Default is currently disabled.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn keep_alive_interval(&mut self, interval: impl Into<Option<Duration>>) -> &mut Self {
+        self.inner.http2_keep_alive_interval(interval);
+        self
+    }
+
+    /// Sets a timeout for receiving an acknowledgement of the keep-alive ping.
+    ///
+    /// If the ping is not acknowledged within the timeout, the connection will
+    /// be closed. Does nothing if `keep_alive_interval` is disabled.
+    ///
+    /// Default is 20 seconds.
+    #[cfg(feature = "runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "runtime")))]
+    pub fn keep_alive_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.inner.http2_keep_alive_timeout(timeout);
+        self
+    }
+
+    /// Sets the maximum number of streams a client can reset before they
+    /// were accepted, pending to be accepted.
+    ///
+    /// See [`Http::http2_max_pending_accept_reset_streams`] for more.
+    pub fn max_pending_accept_reset_streams(&mut self, max: impl Into<Option<usize>>) -> &mut Self {
+        self.inner.http2_max_pending_accept_reset_streams(max);
+        self
+    }
+
+    /// Set the maximum write buffer size for each stream.
+    ///
+    /// Default is currently ~400KB, but may change.
+    ///
+    /// # Panics
+    ///
+    /// The value must be no larger than `u32::MAX`.
+    pub fn max_send_buf_size(&mut self, max: usize) -> &mut Self {
+        self.inner.http2_max_send_buf_size(max);
+        self
+    }
+
+    /// Enables the [extended CONNECT protocol].
+    ///
+    /// [extended CONNECT protocol]: https://datatracker.ietf.org/doc/html/rfc8441#section-4
+    pub fn enable_connect_protocol(&mut self) -> &mut Self {
+        self.inner.http2_enable_connect_protocol();
+        self
+    }
+
+    /// Sets the max size of received header frames.
+    ///
+    /// Default is currently ~16MB, but may change.
+    pub fn max_header_list_size(&mut self, max: u32) -> &mut Self {
+        self.inner.http2_max_header_list_size(max);
+        self
+    }
+
+    /// Set whether responses without a `Date` header get one with the
+    /// current time.
+    ///
+    /// See [`Http::auto_date_header`] for more.
+    pub fn auto_date_header(&mut self, enabled: bool) -> &mut Self {
+        self.inner.auto_date_header(enabled);
+        self
+    }
+
+    /// Bind a connection together with a [`Service`](crate::service::Service).
+    ///
+    /// This returns a Future that must be polled in order for HTTP to be
+    /// driven on the connection.
+    pub fn serve_connection<S, I, Bd>(&self, io: I, service: S) -> Connection<I, S, E>
+    where
+        S: HttpService<Body, ResBody = Bd>,
+        S::Error: Into<Box<dyn StdError + Send + Sync>>,
+        Bd: HttpBody + 'static,
+        Bd::Error: Into<Box<dyn StdError + Send + Sync>>,
+        I: AsyncRead + AsyncWrite + Unpin,
+        E: ConnStreamExec<S::Future, Bd>,
+    {
+        self.inner.serve_connection(io, service)
+    }
+}
@@ -0,0 +1,502 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use http::{Method, StatusCode};
+
+/// A [CORS] policy, answering preflight requests on the connection itself.
+///
+/// `OPTIONS` requests with `Origin` and `Access-Control-Request-Method`
+/// headers are preflights, and are answered before reaching the service:
+/// with `204 No Content` and the `Access-Control-Allow-*` headers if the
+/// policy allows the request, or `403 Forbidden` without them otherwise.
+/// The `Vary` header is always set, so caches keep answers for different
+/// origins apart.
+///
+/// Responses to the actual requests need CORS headers too, which the
+/// service adds with [`apply`](CorsPolicy::apply).
+///
+/// Clones share their policy, so are cheap to make.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use fluxio::header::CONTENT_TYPE;
+/// use fluxio::server::conn::{CorsPolicy, Http};
+/// use fluxio::Method;
+///
+/// let cors = CorsPolicy::new()
+///     .allow_origin("https://app.example.com")
+///     .allow_methods([Method::PUT, Method::DELETE])
+///     .allow_headers([CONTENT_TYPE])
+///     .max_age(Duration::from_secs(600));
+///
+/// let mut http = Http::new();
+/// http.cors(cors);
+/// ```
+///
+/// [CORS]: https://fetch.spec.whatwg.org/#http-cors-protocol
+#[derive(Clone, Debug)]
+pub struct CorsPolicy {
+    inner: Arc<Policy>,
+}
+
+#[derive(Clone, Debug)]
+struct Policy {
+    origins: Option<Vec<String>>,
+    methods: Vec<Method>,
+    headers: Option<Vec<HeaderName>>,
+    expose_headers: Vec<HeaderName>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl CorsPolicy {
+    /// Creates a policy allowing no origins.
+    ///
+    /// The CORS-safelisted methods `GET`, `HEAD` and `POST` are always
+    /// allowed.
+    pub fn new() -> CorsPolicy {
+        CorsPolicy {
+            inner: Arc::new(Policy {
+                origins: Some(Vec::new()),
+                methods: vec![Method::GET, Method::HEAD, Method::POST],
+                headers: Some(Vec::new()),
+                expose_headers: Vec::new(),
+                credentials: false,
+                max_age: None,
+            }),
+        }
+    }
+
+    /// Allows requests from an origin, like `"https://example.com"`.
+    ///
+    /// Origins are compared exactly, so must not have a trailing slash.
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> CorsPolicy {
+        let policy = Arc::make_mut(&mut self.inner);
+        if let Some(ref mut origins) = policy.origins {
+            origins.push(origin.into());
+        }
+        self
+    }
+
+    /// Allows requests from any origin.
+    ///
+    /// This answers with `Access-Control-Allow-Origin: *`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if credentials are allowed, as any site could then make
+    /// requests with the user's credentials and read the responses.
+    pub fn allow_any_origin(mut self) -> CorsPolicy {
+        let policy = Arc::make_mut(&mut self.inner);
+        assert!(
+            !policy.credentials,
+            "credentials can't be allowed for any origin"
+        );
+        policy.origins = None;
+        self
+    }
+
+    /// Allows requests with these methods, in addition to `GET`, `HEAD`
+    /// and `POST`.
+    pub fn allow_methods<I>(mut self, methods: I) -> CorsPolicy
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        let policy = Arc::make_mut(&mut self.inner);
+        for method in methods {
+            if !policy.methods.contains(&method) {
+                policy.methods.push(method);
+            }
+        }
+        self
+    }
+
+    /// Allows requests with these headers.
+    pub fn allow_headers<I>(mut self, headers: I) -> CorsPolicy
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        let policy = Arc::make_mut(&mut self.inner);
+        if let Some(ref mut allowed) = policy.headers {
+            allowed.extend(headers);
+        }
+        self
+    }
+
+    /// Allows requests with any headers.
+    pub fn allow_any_header(mut self) -> CorsPolicy {
+        Arc::make_mut(&mut self.inner).headers = None;
+        self
+    }
+
+    /// Lets scripts read these response headers, with
+    /// `Access-Control-Expose-Headers`.
+    pub fn expose_headers<I>(mut self, headers: I) -> CorsPolicy
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        Arc::make_mut(&mut self.inner)
+            .expose_headers
+            .extend(headers);
+        self
+    }
+
+    /// Sets whether requests can include credentials, such as cookies.
+    ///
+    /// Default is false.
+    ///
+    /// # Panics
+    ///
+    /// Panics if enabled while any origin is allowed, as credentials are
+    /// only allowed for the origins listed with
+    /// [`allow_origin`](CorsPolicy::allow_origin).
+    pub fn allow_credentials(mut self, enabled: bool) -> CorsPolicy {
+        let policy = Arc::make_mut(&mut self.inner);
+        assert!(
+            !enabled || policy.origins.is_some(),
+            "credentials can't be allowed for any origin"
+        );
+        policy.credentials = enabled;
+        self
+    }
+
+    /// Sets how long browsers can cache the answer to a preflight, with
+    /// `Access-Control-Max-Age`.
+    ///
+    /// Default is to leave it to the browser, which caches answers for 5
+    /// seconds.
+    pub fn max_age(mut self, max_age: Duration) -> CorsPolicy {
+        Arc::make_mut(&mut self.inner).max_age = Some(max_age);
+        self
+    }
+
+    /// Adds the CORS headers for the response to an actual request.
+    ///
+    /// The `Access-Control-Allow-Origin` header, and the credentials and
+    /// exposed headers, are only set if the origin of the request is
+    /// allowed. `Vary: Origin` is added unless any origin is allowed,
+    /// keeping the existing `Vary` headers.
+    pub fn apply(&self, request_headers: &HeaderMap, response_headers: &mut HeaderMap) {
+        let policy = &*self.inner;
+        if !policy.answers_any_origin() {
+            response_headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+        let origin = match request_headers.get(header::ORIGIN) {
+            Some(origin) if policy.allows_origin(origin) => origin,
+            _ => return,
+        };
+        policy.insert_origin(origin, response_headers);
+        if !policy.expose_headers.is_empty() {
+            response_headers.insert(
+                header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                join(policy.expose_headers.iter().map(HeaderName::as_str)),
+            );
+        }
+    }
+
+    /// Returns the answer to a request, if it is a preflight.
+    pub(crate) fn preflight(
+        &self,
+        method: &Method,
+        headers: &HeaderMap,
+    ) -> Option<(StatusCode, HeaderMap)> {
+        if method != Method::OPTIONS {
+            return None;
+        }
+        let origin = headers.get(header::ORIGIN)?;
+        let request_method = headers.get(header::ACCESS_CONTROL_REQUEST_METHOD)?;
+        let request_headers = headers.get(header::ACCESS_CONTROL_REQUEST_HEADERS);
+
+        let policy = &*self.inner;
+        let mut res = HeaderMap::new();
+        res.insert(
+            header::VARY,
+            HeaderValue::from_static(
+                "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+            ),
+        );
+        if !policy.allows_origin(origin)
+            || !policy.allows_method(request_method)
+            || request_headers.is_some_and(|names| !policy.allows_headers(names))
+        {
+            return Some((StatusCode::FORBIDDEN, res));
+        }
+
+        policy.insert_origin(origin, &mut res);
+        res.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            join(policy.methods.iter().map(Method::as_str)),
+        );
+        let allow_headers = match policy.headers {
+            Some(ref allowed) if !allowed.is_empty() => {
+                Some(join(allowed.iter().map(HeaderName::as_str)))
+            }
+            Some(_) => None,
+            None => request_headers.cloned(),
+        };
+        if let Some(allow_headers) = allow_headers {
+            res.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
+        }
+        if let Some(max_age) = policy.max_age {
+            res.insert(header::ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+        }
+        Some((StatusCode::NO_CONTENT, res))
+    }
+}
+
+impl Default for CorsPolicy {
+    fn default() -> CorsPolicy {
+        CorsPolicy::new()
+    }
+}
+
+impl Policy {
+    fn allows_origin(&self, origin: &HeaderValue) -> bool {
+        match self.origins {
+            Some(ref origins) => origins
+                .iter()
+                .any(|allowed| allowed.as_bytes() == origin.as_bytes()),
+            None => true,
+        }
+    }
+
+    fn allows_method(&self, method: &HeaderValue) -> bool {
+        self.methods
+            .iter()
+            .any(|allowed| allowed.as_str().as_bytes() == method.as_bytes())
+    }
+
+    fn allows_headers(&self, names: &HeaderValue) -> bool {
+        let allowed = match self.headers {
+            Some(ref allowed) => allowed,
+            None => return true,
+        };
+        let names = match names.to_str() {
+            Ok(names) => names,
+            Err(_) => return false,
+        };
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .all(|name| {
+                allowed
+                    .iter()
+                    .any(|allowed| allowed.as_str().eq_ignore_ascii_case(name))
+            })
+    }
+
+    fn answers_any_origin(&self) -> bool {
+        self.origins.is_none()
+    }
+
+    fn insert_origin(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+        let allow_origin = if self.answers_any_origin() {
+            HeaderValue::from_static("*")
+        } else {
+            origin.clone()
+        };
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+}
+
+fn join<'a>(items: impl Iterator<Item = &'a str>) -> HeaderValue {
+    let joined = items.collect::<Vec<_>>().join(", ");
+    HeaderValue::try_from(joined).expect("tokens are valid header values")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preflight(
+        cors: &CorsPolicy,
+        origin: &'static str,
+        method: &'static str,
+        names: Option<&'static str>,
+    ) -> (StatusCode, HeaderMap) {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ORIGIN, HeaderValue::from_static(origin));
+        headers.insert(
+            header::ACCESS_CONTROL_REQUEST_METHOD,
+            HeaderValue::from_static(method),
+        );
+        if let Some(names) = names {
+            headers.insert(
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                HeaderValue::from_static(names),
+            );
+        }
+        cors.preflight(&Method::OPTIONS, &headers).unwrap()
+    }
+
+    #[test]
+    fn preflight_allowed() {
+        let cors = CorsPolicy::new()
+            .allow_origin("https://a.example")
+            .allow_methods([Method::PUT])
+            .allow_headers([header::CONTENT_TYPE, HeaderName::from_static("x-token")])
+            .allow_credentials(true)
+            .max_age(Duration::from_secs(600));
+
+        let (status, headers) = preflight(
+            &cors,
+            "https://a.example",
+            "PUT",
+            Some("X-Token, content-type"),
+        );
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://a.example"
+        );
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_METHODS],
+            "GET, HEAD, POST, PUT"
+        );
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            "content-type, x-token"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+        assert!(headers[header::VARY]
+            .to_str()
+            .unwrap()
+            .starts_with("Origin"));
+    }
+
+    #[test]
+    fn preflight_forbidden() {
+        let cors = CorsPolicy::new().allow_origin("https://a.example");
+
+        for (origin, method, names) in [
+            ("https://b.example", "GET", None),
+            ("https://a.example/", "GET", None),
+            ("https://a.example", "DELETE", None),
+            ("https://a.example", "GET", Some("x-token")),
+        ] {
+            let (status, headers) = preflight(&cors, origin, method, names);
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+            assert!(headers.contains_key(header::VARY));
+        }
+
+        // not a preflight
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://a.example"),
+        );
+        assert!(cors.preflight(&Method::OPTIONS, &headers).is_none());
+        headers.insert(
+            header::ACCESS_CONTROL_REQUEST_METHOD,
+            HeaderValue::from_static("GET"),
+        );
+        assert!(cors.preflight(&Method::GET, &headers).is_none());
+    }
+
+    #[test]
+    fn any_origin() {
+        let cors = CorsPolicy::new().allow_any_origin().allow_any_header();
+        let (status, headers) = preflight(&cors, "https://b.example", "GET", Some("x-anything"));
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS], "x-anything");
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    }
+
+    #[test]
+    #[should_panic(expected = "credentials can't be allowed for any origin")]
+    fn any_origin_with_credentials() {
+        let _ = CorsPolicy::new().allow_any_origin().allow_credentials(true);
+    }
+
+    #[test]
+    #[should_panic(expected = "credentials can't be allowed for any origin")]
+    fn credentials_with_any_origin() {
+        let _ = CorsPolicy::new().allow_credentials(true).allow_any_origin();
+    }
+
+    #[test]
+    fn apply() {
+        let cors = CorsPolicy::new()
+            .allow_origin("https://a.example")
+            .expose_headers([header::ETAG]);
+        let mut req = HeaderMap::new();
+        req.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://a.example"),
+        );
+
+        let mut res = HeaderMap::new();
+        res.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+        cors.apply(&req, &mut res);
+        assert_eq!(
+            res[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://a.example"
+        );
+        assert_eq!(res[header::ACCESS_CONTROL_EXPOSE_HEADERS], "etag");
+        assert_eq!(res.get_all(header::VARY).iter().count(), 2);
+
+        req.insert(
+            header::ORIGIN,
+            HeaderValue::from_static("https://b.example"),
+        );
+        let mut res = HeaderMap::new();
+        cors.apply(&req, &mut res);
+        assert!(!res.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!(res[header::VARY], "Origin");
+    }
+
+    #[cfg(feature = "http1")]
+    #[tokio::test]
+    async fn answered_before_service() {
+        use std::convert::Infallible;
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use crate::server::conn::Http;
+        use crate::service::service_fn;
+        use crate::{Body, Request, Response};
+
+        let mut http = Http::new();
+        http.cors(CorsPolicy::new().allow_origin("https://a.example"));
+
+        let svc = service_fn(|req: Request<Body>| async move {
+            assert_ne!(req.method(), Method::OPTIONS);
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        });
+        let (mut client, io) = tokio::io::duplex(1024);
+        tokio::spawn(http.serve_connection(io, svc));
+
+        client
+            .write_all(
+                b"OPTIONS /items HTTP/1.1\r\n\
+                  host: api.example\r\n\
+                  origin: https://a.example\r\n\
+                  access-control-request-method: POST\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut buf = [0; 1024];
+        let n = client.read(&mut buf).await.unwrap();
+        let res = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(res.starts_with("HTTP/1.1 204 No Content\r\n"), "{:?}", res);
+        assert!(
+            res.contains("access-control-allow-origin: https://a.example\r\n"),
+            "{:?}",
+            res
+        );
+    }
+}
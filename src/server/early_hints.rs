@@ -0,0 +1,109 @@
+use http::HeaderMap;
+use tokio::sync::mpsc;
+
+/// Sends `103 Early Hints` responses ahead of the final response to a
+/// request.
+///
+/// Early hints let a client start fetching resources, such as stylesheets
+/// announced with `Link` headers, while the server is still preparing the
+/// final response ([RFC 8297]).
+///
+/// This is found in the extensions of each request received on an HTTP/1
+/// connection with [`Http::early_hints`](super::conn::Http::early_hints)
+/// enabled.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::header::{HeaderMap, HeaderValue, LINK};
+/// use fluxio::server::conn::SendEarlyHints;
+/// use fluxio::{Body, Request, Response};
+///
+/// async fn handle(req: Request<Body>) -> Result<Response<Body>, fluxio::Error> {
+///     if let Some(hints) = req.extensions().get::<SendEarlyHints>() {
+///         let mut headers = HeaderMap::new();
+///         headers.insert(LINK, HeaderValue::from_static("</style.css>; rel=preload; as=style"));
+///         let _ = hints.send(headers);
+///     }
+///     // ... prepare the final response
+///     Ok(Response::new(Body::from("<link rel=stylesheet href=/style.css>")))
+/// }
+/// ```
+///
+/// [RFC 8297]: https://www.rfc-editor.org/rfc/rfc8297
+#[derive(Clone, Debug)]
+pub struct SendEarlyHints {
+    tx: mpsc::UnboundedSender<HeaderMap>,
+}
+
+/// The receiving half of a [`SendEarlyHints`], polled by the connection.
+pub(crate) type EarlyHints = mpsc::UnboundedReceiver<HeaderMap>;
+
+impl SendEarlyHints {
+    pub(crate) fn channel() -> (SendEarlyHints, EarlyHints) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (SendEarlyHints { tx }, rx)
+    }
+
+    /// Sends a `103 Early Hints` response with these headers.
+    ///
+    /// The response is written before the final response, once the
+    /// connection is polled again. HTTP/1.0 clients don't receive early
+    /// hints, as they don't support informational responses.
+    ///
+    /// Returns the headers back if the final response was already sent, or
+    /// the connection closed.
+    pub fn send(&self, headers: HeaderMap) -> Result<(), HeaderMap> {
+        self.tx.send(headers).map_err(|err| err.0)
+    }
+}
+
+#[cfg(all(test, feature = "http1"))]
+mod tests {
+    use std::convert::Infallible;
+
+    use http::header::{HeaderValue, LINK};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::server::conn::Http;
+    use crate::service::service_fn;
+    use crate::{Body, Request, Response};
+
+    #[tokio::test]
+    async fn sent_before_response() {
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let released = std::sync::Mutex::new(Some(released));
+        let svc = service_fn(move |req: Request<Body>| {
+            let hints = req.extensions().get::<SendEarlyHints>().unwrap().clone();
+            let released = released.lock().unwrap().take().unwrap();
+            async move {
+                let mut headers = HeaderMap::new();
+                headers.insert(LINK, HeaderValue::from_static("</style.css>"));
+                hints.send(headers).unwrap();
+                released.await.unwrap();
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }
+        });
+
+        let mut http = Http::new();
+        http.early_hints(true);
+        let (mut client, io) = tokio::io::duplex(1024);
+        tokio::spawn(http.serve_connection(io, svc));
+
+        client
+            .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .unwrap();
+        let mut buf = [0; 1024];
+        let n = client.read(&mut buf).await.unwrap();
+        assert_eq!(
+            &buf[..n],
+            b"HTTP/1.1 103 Early Hints\r\nlink: </style.css>\r\n\r\n"
+        );
+
+        release.send(()).unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+    }
+}
@@ -0,0 +1,274 @@
+//! Gracefully shutting down many server connections at once.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::time::Duration;
+
+use pin_project_lite::pin_project;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::debug;
+
+use super::conn::{Connection, UpgradeableConnection};
+use crate::body::{Body, HttpBody};
+use crate::common::exec::ConnStreamExec;
+use crate::common::{task, Future, Pin, Poll};
+use crate::service::HttpService;
+
+/// Coordinates the graceful shutdown of a set of server connections.
+///
+/// Connections are tracked by wrapping them with [`watch`], and polling the
+/// returned future in their place. Calling [`shutdown`] starts a graceful
+/// shutdown of all of them, with a deadline after which the ones still
+/// running are aborted.
+///
+/// [`watch`]: GracefulShutdown::watch
+/// [`shutdown`]: GracefulShutdown::shutdown
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "http1", feature = "tcp"))]
+/// # async fn run(
+/// #     listener: tokio::net::TcpListener,
+/// #     stop: impl std::future::Future<Output = ()>,
+/// # ) -> std::io::Result<()> {
+/// use std::convert::Infallible;
+/// use std::time::Duration;
+/// use fluxio::server::conn::{GracefulShutdown, Http};
+/// use fluxio::service::service_fn;
+/// use fluxio::{Body, Response};
+///
+/// let http = Http::new();
+/// let graceful = GracefulShutdown::new();
+///
+/// tokio::pin!(stop);
+/// loop {
+///     tokio::select! {
+///         accepted = listener.accept() => {
+///             let (io, _addr) = accepted?;
+///             let svc = service_fn(|_req| async {
+///                 Ok::<_, Infallible>(Response::new(Body::from("Hello World!")))
+///             });
+///             tokio::spawn(graceful.watch(http.serve_connection(io, svc)));
+///         }
+///         _ = &mut stop => break,
+///     }
+/// }
+///
+/// graceful.shutdown(Duration::from_secs(30)).await;
+/// # Ok(())
+/// # }
+/// # fn main() {}
+/// ```
+pub struct GracefulShutdown {
+    tx: watch::Sender<Option<Instant>>,
+}
+
+impl GracefulShutdown {
+    /// Creates a new `GracefulShutdown`, without any connections.
+    pub fn new() -> GracefulShutdown {
+        let (tx, _) = watch::channel(None);
+        GracefulShutdown { tx }
+    }
+
+    /// Tracks a connection, to shut it down with the others.
+    ///
+    /// The returned future must be polled instead of the connection. If this
+    /// `GracefulShutdown` is dropped without calling `shutdown`, the
+    /// connection keeps running normally.
+    pub fn watch<C: GracefulConnection>(&self, conn: C) -> Watched<C> {
+        let mut rx = self.tx.subscribe();
+        Watched {
+            conn,
+            shutdown: Some(Box::pin(async move {
+                // An error means the `GracefulShutdown` was dropped instead.
+                let deadline = match rx.changed().await {
+                    Ok(()) => *rx.borrow(),
+                    Err(_) => None,
+                };
+                (deadline, rx)
+            })),
+            rx: None,
+        }
+    }
+
+    /// The number of watched connections that are still running.
+    pub fn connections(&self) -> usize {
+        self.tx.receiver_count()
+    }
+
+    /// Starts a graceful shutdown of all the watched connections.
+    ///
+    /// The returned future resolves once all of them have finished. The
+    /// ones that are still running after `deadline` are aborted, as with
+    /// [`Connection::graceful_shutdown_with_deadline`].
+    pub fn shutdown(self, deadline: Duration) -> impl Future<Output = ()> {
+        debug!(
+            "gracefully shutting down {} connection(s)",
+            self.connections()
+        );
+        let _ = self.tx.send(Some(Instant::now() + deadline));
+        async move { self.tx.closed().await }
+    }
+}
+
+impl Default for GracefulShutdown {
+    fn default() -> GracefulShutdown {
+        GracefulShutdown::new()
+    }
+}
+
+impl fmt::Debug for GracefulShutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GracefulShutdown")
+            .field("connections", &self.connections())
+            .finish()
+    }
+}
+
+type ShutdownSignal = Pin<Box<dyn Future<Output = (Option<Instant>, Receiver)> + Send + Sync>>;
+
+type Receiver = watch::Receiver<Option<Instant>>;
+
+pin_project! {
+    /// A connection watched by a [`GracefulShutdown`].
+    #[must_use = "futures do nothing unless polled"]
+    pub struct Watched<C> {
+        #[pin]
+        conn: C,
+        // Owns the receiver until shutdown starts, counting this connection
+        // as running until it is dropped.
+        shutdown: Option<ShutdownSignal>,
+        rx: Option<Receiver>,
+    }
+}
+
+impl<C: GracefulConnection> Future for Watched<C> {
+    type Output = crate::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let mut me = self.project();
+        if let Some(ref mut shutdown) = me.shutdown {
+            if let Poll::Ready((deadline, rx)) = shutdown.as_mut().poll(cx) {
+                *me.shutdown = None;
+                *me.rx = Some(rx);
+                if let Some(deadline) = deadline {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    me.conn.as_mut().graceful_shutdown_with_deadline(remaining);
+                }
+            }
+        }
+        me.conn.poll(cx)
+    }
+}
+
+impl<C> fmt::Debug for Watched<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watched").finish()
+    }
+}
+
+/// A connection that can be watched by a [`GracefulShutdown`].
+///
+/// This is implemented by [`Connection`], and its upgradeable version.
+pub trait GracefulConnection: Future<Output = crate::Result<()>> + sealed::Sealed {
+    /// Start a graceful shutdown process for this connection, aborting it
+    /// if it hasn't finished after `deadline`.
+    fn graceful_shutdown_with_deadline(self: Pin<&mut Self>, deadline: Duration);
+}
+
+impl<I, B, S, E> GracefulConnection for Connection<I, S, E>
+where
+    S: HttpService<Body, ResBody = B>,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+    I: AsyncRead + AsyncWrite + Unpin + 'static,
+    B: HttpBody + 'static,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    E: ConnStreamExec<S::Future, B>,
+{
+    fn graceful_shutdown_with_deadline(self: Pin<&mut Self>, deadline: Duration) {
+        Connection::graceful_shutdown_with_deadline(self, deadline)
+    }
+}
+
+impl<I, B, S, E> GracefulConnection for UpgradeableConnection<I, S, E>
+where
+    S: HttpService<Body, ResBody = B>,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    B: HttpBody + 'static,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+    E: ConnStreamExec<S::Future, B>,
+{
+    fn graceful_shutdown_with_deadline(self: Pin<&mut Self>, deadline: Duration) {
+        UpgradeableConnection::graceful_shutdown_with_deadline(self, deadline)
+    }
+}
+
+mod sealed {
+    use super::{Body, Connection, HttpService, UpgradeableConnection};
+
+    pub trait Sealed {}
+
+    impl<I, S: HttpService<Body>, E> Sealed for Connection<I, S, E> {}
+
+    impl<I, S: HttpService<Body>, E> Sealed for UpgradeableConnection<I, S, E> {}
+}
+
+#[cfg(all(test, feature = "http1"))]
+mod tests {
+    use std::convert::Infallible;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::server::conn::Http;
+    use crate::service::service_fn;
+    use crate::{Request, Response};
+
+    #[tokio::test]
+    async fn shutdown_aborts_after_deadline() {
+        tokio::time::pause();
+
+        let graceful = GracefulShutdown::new();
+        let http = Http::new();
+
+        // answers a request immediately
+        let (mut idle, io) = tokio::io::duplex(1024);
+        let svc = service_fn(|_req: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        });
+        let idle_conn = tokio::spawn(graceful.watch(http.serve_connection(io, svc)));
+
+        // never answers
+        let (mut stuck, io) = tokio::io::duplex(1024);
+        let svc = service_fn(|_req: Request<Body>| {
+            futures_util::future::pending::<Result<Response<Body>, Infallible>>()
+        });
+        let stuck_conn = tokio::spawn(graceful.watch(http.serve_connection(io, svc)));
+
+        for io in [&mut idle, &mut stuck] {
+            io.write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n")
+                .await
+                .unwrap();
+        }
+        let mut buf = [0; 1024];
+        let n = idle.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert_eq!(graceful.connections(), 2);
+
+        graceful.shutdown(Duration::from_secs(5)).await;
+
+        idle_conn
+            .await
+            .unwrap()
+            .expect("idle connection closes cleanly");
+        let err = stuck_conn
+            .await
+            .unwrap()
+            .expect_err("stuck connection aborted");
+        assert!(err.is_timeout());
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use http::{Method, StatusCode, Uri};
+
+/// A health check endpoint answered by the connection itself.
+///
+/// `GET` and `HEAD` requests for the configured path, without a body, are
+/// answered with `200 OK`, or `503 Service Unavailable` while draining or
+/// not ready, before reaching the service. Load balancers probing it get a
+/// fast and consistent answer, even while the service is saturated. Only
+/// HTTP/1 connections answer it for now.
+///
+/// Clones share their state, so a clone can be kept to call
+/// [`set_draining`](HealthCheck::set_draining) once the check was passed to
+/// [`Http::health_check`](super::conn::Http::health_check).
+///
+/// # Example
+///
+/// ```
+/// use fluxio::server::conn::{HealthCheck, Http};
+///
+/// let health = HealthCheck::new("/healthz");
+///
+/// let mut http = Http::new();
+/// http.health_check(health.clone());
+///
+/// // Before shutting down, let load balancers take this instance out of
+/// // rotation.
+/// health.set_draining(true);
+/// ```
+#[derive(Clone)]
+pub struct HealthCheck {
+    path: Arc<str>,
+    draining: Arc<AtomicBool>,
+    ready: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl HealthCheck {
+    /// Creates a health check answering requests for `path`, like
+    /// `"/healthz"`.
+    ///
+    /// The path is compared exactly, ignoring the query.
+    pub fn new(path: impl Into<String>) -> HealthCheck {
+        HealthCheck {
+            path: path.into().into(),
+            draining: Arc::new(AtomicBool::new(false)),
+            ready: None,
+        }
+    }
+
+    /// Sets a function called for each health check, answered with
+    /// `503 Service Unavailable` if it returns `false`.
+    ///
+    /// This is called on the connection's task, so it must return quickly,
+    /// such as by reading a flag the application updates.
+    pub fn ready_when<F>(mut self, ready: F) -> HealthCheck
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.ready = Some(Arc::new(ready));
+        self
+    }
+
+    /// Sets whether the server is draining, answering health checks with
+    /// `503 Service Unavailable` while it is.
+    ///
+    /// [`Server::with_graceful_shutdown`](crate::server::Server::with_graceful_shutdown)
+    /// sets this once its signal resolves. When shutting down connections
+    /// with a [`GracefulShutdown`](super::conn::GracefulShutdown) instead,
+    /// this should be set first, giving load balancers time to stop sending
+    /// new requests.
+    pub fn set_draining(&self, draining: bool) {
+        self.draining.store(draining, Ordering::SeqCst);
+    }
+
+    /// Returns whether the server is draining.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Returns the status to answer a request with, if it is a health check.
+    pub(crate) fn respond(&self, method: &Method, uri: &Uri) -> Option<StatusCode> {
+        if (method != Method::GET && method != Method::HEAD) || uri.path() != &*self.path {
+            return None;
+        }
+        let ready = match self.ready {
+            Some(ref ready) => ready(),
+            None => true,
+        };
+        if ready && !self.is_draining() {
+            Some(StatusCode::OK)
+        } else {
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        }
+    }
+}
+
+impl fmt::Debug for HealthCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthCheck")
+            .field("path", &self.path)
+            .field("draining", &self.is_draining())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respond() {
+        let check = HealthCheck::new("/healthz");
+        let uri = |s: &str| s.parse::<Uri>().unwrap();
+
+        assert_eq!(
+            check.respond(&Method::GET, &uri("/healthz")),
+            Some(StatusCode::OK)
+        );
+        assert_eq!(
+            check.respond(&Method::HEAD, &uri("/healthz?verbose")),
+            Some(StatusCode::OK)
+        );
+        assert_eq!(check.respond(&Method::POST, &uri("/healthz")), None);
+        assert_eq!(check.respond(&Method::GET, &uri("/healthz/")), None);
+        assert_eq!(check.respond(&Method::GET, &uri("/")), None);
+
+        check.clone().set_draining(true);
+        assert_eq!(
+            check.respond(&Method::GET, &uri("/healthz")),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+    }
+
+    #[test]
+    fn ready_when() {
+        let ready = Arc::new(AtomicBool::new(false));
+        let check = HealthCheck::new("/ready").ready_when({
+            let ready = ready.clone();
+            move || ready.load(Ordering::SeqCst)
+        });
+        let uri = "/ready".parse::<Uri>().unwrap();
+
+        assert_eq!(
+            check.respond(&Method::GET, &uri),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        ready.store(true, Ordering::SeqCst);
+        assert_eq!(check.respond(&Method::GET, &uri), Some(StatusCode::OK));
+    }
+
+    #[cfg(feature = "http1")]
+    #[tokio::test]
+    async fn answered_before_service() {
+        use std::convert::Infallible;
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        use crate::server::conn::Http;
+        use crate::service::service_fn;
+        use crate::{Body, Request, Response};
+
+        let check = HealthCheck::new("/healthz");
+        let mut http = Http::new();
+        http.health_check(check.clone());
+
+        // a saturated service, never answering
+        let svc = service_fn(|_req: Request<Body>| {
+            futures_util::future::pending::<Result<Response<Body>, Infallible>>()
+        });
+        let (mut client, io) = tokio::io::duplex(1024);
+        tokio::spawn(http.serve_connection(io, svc));
+
+        let mut buf = [0; 1024];
+        client
+            .write_all(b"GET /healthz HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert!(
+            buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"),
+            "{:?}",
+            &buf[..n]
+        );
+
+        // the connection is kept alive
+        check.set_draining(true);
+        client
+            .write_all(b"HEAD /healthz HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .unwrap();
+        let n = client.read(&mut buf).await.unwrap();
+        assert!(
+            buf[..n].starts_with(b"HTTP/1.1 503 Service Unavailable\r\n"),
+            "{:?}",
+            &buf[..n]
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(all(feature = "http1", feature = "runtime"))]
+use std::time::Duration;
+
+use futures_util::task::AtomicWaker;
+
+use crate::common::{task, Poll};
+
+/// Server options that can be changed while the server is running.
+///
+/// Clones share their state, so a clone can be kept to change the options
+/// once the config was passed to
+/// [`Http::live_config`](super::conn::Http::live_config). This lets an
+/// operator raise or lower limits in place, such as during an incident,
+/// without restarting listeners.
+///
+/// Options set here take precedence over the matching options of
+/// [`Http`](super::conn::Http). Connection limits apply to connections
+/// accepted afterwards by a [`Server`](crate::Server), and the other options
+/// to connections served afterwards. Connections already being served keep
+/// the options they started with.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::server::conn::{Http, LiveConfig, ShedPolicy};
+///
+/// let live = LiveConfig::new();
+/// live.set_max_connections(Some(10_000));
+///
+/// let mut http = Http::new();
+/// http.live_config(live.clone());
+///
+/// // Later, to shed load during an incident...
+/// live.set_max_connections(Some(2_000));
+/// live.set_shed_policy(ShedPolicy::Close);
+/// ```
+#[derive(Clone)]
+pub struct LiveConfig {
+    shared: Arc<Shared>,
+}
+
+/// What a [`Server`](crate::Server) does with new connections once it
+/// reached its [maximum](LiveConfig::set_max_connections).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShedPolicy {
+    /// Stop accepting connections until one of the served connections
+    /// closes, leaving new ones waiting in the listener's backlog.
+    #[default]
+    Pause,
+    /// Accept connections and close them right away, without reading from
+    /// them, so clients fail fast and can retry elsewhere.
+    Close,
+}
+
+#[derive(Debug)]
+struct Shared {
+    // 0 means no value for each of these.
+    max_connections: AtomicUsize,
+    max_buf_size: AtomicUsize,
+    header_read_timeout_nanos: AtomicU64,
+    shed_policy: AtomicU8,
+    active: AtomicUsize,
+    // The accept loop waiting for a connection to close.
+    accept_waker: AtomicWaker,
+}
+
+/// A connection counted against the maximum, until dropped.
+#[derive(Debug)]
+pub(crate) struct ConnGuard {
+    shared: Arc<Shared>,
+}
+
+impl LiveConfig {
+    /// Creates a config without any option set.
+    pub fn new() -> LiveConfig {
+        LiveConfig {
+            shared: Arc::new(Shared {
+                max_connections: AtomicUsize::new(0),
+                max_buf_size: AtomicUsize::new(0),
+                header_read_timeout_nanos: AtomicU64::new(0),
+                shed_policy: AtomicU8::new(ShedPolicy::Pause as u8),
+                active: AtomicUsize::new(0),
+                accept_waker: AtomicWaker::new(),
+            }),
+        }
+    }
+
+    /// Sets the maximum number of connections a server serves at once.
+    ///
+    /// Once reached, new connections are handled according to the
+    /// [`ShedPolicy`]. Lowering the maximum below the number of connections
+    /// being served doesn't close any of them.
+    ///
+    /// Default is `None`, for no maximum.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `max` is `Some(0)`.
+    pub fn set_max_connections(&self, max: Option<usize>) {
+        assert_ne!(max, Some(0), "max_connections must be at least 1");
+        self.shared
+            .max_connections
+            .store(max.unwrap_or(0), Ordering::SeqCst);
+        // a raised maximum may let a paused server accept again
+        self.shared.accept_waker.wake();
+    }
+
+    /// Returns the maximum number of connections a server serves at once.
+    pub fn max_connections(&self) -> Option<usize> {
+        match self.shared.max_connections.load(Ordering::SeqCst) {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
+    /// Sets what a server does with new connections once it reached its
+    /// maximum.
+    ///
+    /// Default is [`ShedPolicy::Pause`].
+    pub fn set_shed_policy(&self, policy: ShedPolicy) {
+        self.shared
+            .shed_policy
+            .store(policy as u8, Ordering::SeqCst);
+        self.shared.accept_waker.wake();
+    }
+
+    /// Returns what a server does with new connections once it reached its
+    /// maximum.
+    pub fn shed_policy(&self) -> ShedPolicy {
+        if self.shared.shed_policy.load(Ordering::SeqCst) == ShedPolicy::Close as u8 {
+            ShedPolicy::Close
+        } else {
+            ShedPolicy::Pause
+        }
+    }
+
+    /// Returns the number of connections being served by servers using this
+    /// config.
+    pub fn active_connections(&self) -> usize {
+        self.shared.active.load(Ordering::SeqCst)
+    }
+
+    /// Sets the maximum buffer size of new connections, overriding
+    /// [`Http::max_buf_size`](super::conn::Http::max_buf_size).
+    ///
+    /// Default is `None`, using the option of `Http`.
+    ///
+    /// # Panics
+    ///
+    /// The minimum value allowed is 8192. This method panics if the passed
+    /// `max` is less than the minimum.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn set_max_buf_size(&self, max: Option<usize>) {
+        if let Some(max) = max {
+            assert!(
+                max >= crate::proto::h1::MINIMUM_MAX_BUFFER_SIZE,
+                "the max_buf_size cannot be smaller than the minimum that h1 specifies."
+            );
+        }
+        self.shared
+            .max_buf_size
+            .store(max.unwrap_or(0), Ordering::SeqCst);
+    }
+
+    /// Returns the maximum buffer size of new connections, if set.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn max_buf_size(&self) -> Option<usize> {
+        match self.shared.max_buf_size.load(Ordering::SeqCst) {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
+    /// Sets the timeout for reading request heads of new connections,
+    /// overriding
+    /// [`Http::http1_header_read_timeout`](super::conn::Http::http1_header_read_timeout).
+    ///
+    /// Default is `None`, using the option of `Http`.
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "runtime"))))]
+    pub fn set_http1_header_read_timeout(&self, timeout: Option<Duration>) {
+        let nanos = timeout.map_or(0, |timeout| {
+            u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX).max(1)
+        });
+        self.shared
+            .header_read_timeout_nanos
+            .store(nanos, Ordering::SeqCst);
+    }
+
+    /// Returns the timeout for reading request heads of new connections, if
+    /// set.
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "runtime"))))]
+    pub fn http1_header_read_timeout(&self) -> Option<Duration> {
+        match self.shared.header_read_timeout_nanos.load(Ordering::SeqCst) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// Polls until a server can accept a new connection, returning `false`
+    /// if it should close the connection instead.
+    pub(crate) fn poll_capacity(&self, cx: &mut task::Context<'_>) -> Poll<bool> {
+        if self.has_capacity() {
+            return Poll::Ready(true);
+        }
+        if self.shed_policy() == ShedPolicy::Close {
+            return Poll::Ready(false);
+        }
+        self.shared.accept_waker.register(cx.waker());
+        // check again, a connection may have closed before registering
+        if self.has_capacity() {
+            Poll::Ready(true)
+        } else if self.shed_policy() == ShedPolicy::Close {
+            Poll::Ready(false)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Counts a new connection.
+    pub(crate) fn acquire(&self) -> ConnGuard {
+        self.shared.active.fetch_add(1, Ordering::SeqCst);
+        ConnGuard {
+            shared: self.shared.clone(),
+        }
+    }
+
+    fn has_capacity(&self) -> bool {
+        match self.max_connections() {
+            Some(max) => self.active_connections() < max,
+            None => true,
+        }
+    }
+}
+
+impl Default for LiveConfig {
+    fn default() -> LiveConfig {
+        LiveConfig::new()
+    }
+}
+
+impl fmt::Debug for LiveConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiveConfig")
+            .field("max_connections", &self.max_connections())
+            .field("shed_policy", &self.shed_policy())
+            .field("active_connections", &self.active_connections())
+            .finish()
+    }
+}
+
+impl Drop for ConnGuard {
+    fn drop(&mut self) {
+        self.shared.active.fetch_sub(1, Ordering::SeqCst);
+        self.shared.accept_waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_connections() {
+        let live = LiveConfig::new();
+        let a = live.acquire();
+        let b = live.acquire();
+        assert_eq!(live.active_connections(), 2);
+        assert!(live.has_capacity());
+
+        live.set_max_connections(Some(2));
+        assert!(!live.has_capacity());
+        drop(a);
+        assert!(live.has_capacity());
+        let c = live.acquire();
+
+        // lowering the maximum keeps the connections being served
+        live.set_max_connections(Some(1));
+        assert_eq!(live.active_connections(), 2);
+        drop((b, c));
+        assert_eq!(live.active_connections(), 0);
+        assert!(live.has_capacity());
+    }
+
+    #[cfg(all(feature = "http1", feature = "runtime"))]
+    #[tokio::test]
+    async fn server_sheds_connections() {
+        use std::convert::Infallible;
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+        use tokio::sync::mpsc;
+
+        use crate::server::accept;
+        use crate::service::{make_service_fn, service_fn};
+        use crate::{Body, Request, Response, Server};
+
+        let live = LiveConfig::new();
+        live.set_max_connections(Some(1));
+        live.set_shed_policy(ShedPolicy::Close);
+
+        let (conns, mut rx) = mpsc::unbounded_channel::<DuplexStream>();
+        let incoming =
+            accept::poll_fn(move |cx| rx.poll_recv(cx).map(|io| io.map(Ok::<_, Infallible>)));
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(|_req: Request<Body>| async {
+                Ok::<_, Infallible>(Response::new(Body::from("ok")))
+            }))
+        });
+        let server = Server::builder(incoming)
+            .http1_only(true)
+            .live_config(live.clone())
+            .serve(make_svc);
+        tokio::spawn(server);
+
+        let connect = || {
+            let (client, server) = tokio::io::duplex(1024);
+            conns.send(server).unwrap();
+            client
+        };
+        let mut buf = [0; 1024];
+
+        let mut first = connect();
+        first
+            .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .unwrap();
+        let n = first.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert_eq!(live.active_connections(), 1);
+
+        // the first connection is kept alive, so the second one is shed
+        let mut second = connect();
+        assert_eq!(second.read(&mut buf).await.unwrap(), 0);
+
+        // until the maximum is raised
+        live.set_max_connections(Some(2));
+        let mut third = connect();
+        third
+            .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\n\r\n")
+            .await
+            .unwrap();
+        let n = third.read(&mut buf).await.unwrap();
+        assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert_eq!(live.active_connections(), 2);
+    }
+}
@@ -161,9 +161,19 @@ cfg_feature! {
     pub(crate) mod server;
     pub use self::server::Builder;
 
+    mod cors;
+    pub(crate) mod early_hints;
+    mod health;
+    mod live;
     mod shutdown;
 }
 
+cfg_feature! {
+    #![all(any(feature = "http1", feature = "http2"), feature = "runtime")]
+
+    mod graceful;
+}
+
 cfg_feature! {
     #![not(any(feature = "http1", feature = "http2"))]
 
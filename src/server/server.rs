@@ -2,12 +2,13 @@ use std::error::Error as StdError;
 use std::fmt;
 #[cfg(feature = "tcp")]
 use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::sync::Arc;
 #[cfg(any(feature = "tcp", feature = "http1"))]
 use std::time::Duration;
 
 use pin_project_lite::pin_project;
 use tokio::io::{AsyncRead, AsyncWrite};
-use tracing::trace;
+use tracing::{debug, trace};
 
 use super::accept::Accept;
 #[cfg(all(feature = "tcp"))]
@@ -16,9 +17,13 @@ use crate::body::{Body, HttpBody};
 use crate::common::exec::Exec;
 use crate::common::exec::{ConnStreamExec, NewSvcExec};
 use crate::common::{task, Future, Pin, Poll, Unpin};
+use crate::observer::ConnectionObserver;
 // Renamed `Http` as `Http_` for now so that people upgrading don't see an
 // error that `fluxio::server::Http` is private...
-use super::conn::{Connection, Http as Http_, UpgradeableConnection};
+use super::conn::{
+    Connection, CorsPolicy, HealthCheck, Http as Http_, LiveConfig, UpgradeableConnection,
+};
+use super::live::ConnGuard;
 use super::shutdown::{Graceful, GracefulWatcher};
 use crate::service::{HttpService, MakeServiceRef};
 
@@ -36,6 +41,7 @@ pin_project! {
         incoming: I,
         make_service: S,
         protocol: Http_<E>,
+        connect_info: bool,
     }
 }
 
@@ -45,6 +51,7 @@ pin_project! {
 pub struct Builder<I, E = Exec> {
     incoming: I,
     protocol: Http_<E>,
+    connect_info: bool,
 }
 
 // ===== impl Server =====
@@ -56,6 +63,7 @@ impl<I> Server<I, ()> {
         Builder {
             incoming,
             protocol: Http_::new(),
+            connect_info: false,
         }
     }
 }
@@ -162,7 +170,7 @@ where
         self: Pin<&mut Self>,
         cx: &mut task::Context<'_>,
     ) -> Poll<Option<crate::Result<Connecting<IO, S::Future, E>>>> {
-        let me = self.project();
+        let mut me = self.project();
         match ready!(me.make_service.poll_ready_ref(cx)) {
             Ok(()) => (),
             Err(e) => {
@@ -171,19 +179,42 @@ where
             }
         }
 
-        if let Some(item) = ready!(me.incoming.poll_accept(cx)) {
+        loop {
+            let shed = match me.protocol.live_config {
+                Some(ref live) => !ready!(live.poll_capacity(cx)),
+                None => false,
+            };
+            let item = match ready!(me.incoming.as_mut().poll_accept(cx)) {
+                Some(item) => item,
+                None => return Poll::Ready(None),
+            };
             let io = item.map_err(crate::Error::new_accept)?;
+            if shed {
+                debug!("max connections reached, closing accepted connection");
+                drop(io);
+                continue;
+            }
+            let guard = me.protocol.live_config.as_ref().map(LiveConfig::acquire);
             let new_fut = me.make_service.make_service_ref(&io);
-            Poll::Ready(Some(Ok(Connecting {
+            let mut protocol = me.protocol.clone();
+            if *me.connect_info {
+                if let Some(info) = I::connect_info(&io) {
+                    protocol.set_connect_info(info);
+                }
+            }
+            return Poll::Ready(Some(Ok(Connecting {
                 future: new_fut,
                 io: Some(io),
-                protocol: me.protocol.clone(),
-            })))
-        } else {
-            Poll::Ready(None)
+                protocol,
+                guard,
+            })));
         }
     }
 
+    pub(super) fn health_check(&self) -> Option<&HealthCheck> {
+        self.protocol.health_check.as_ref()
+    }
+
     pub(super) fn poll_watch<W>(
         mut self: Pin<&mut Self>,
         cx: &mut task::Context<'_>,
@@ -240,7 +271,11 @@ impl<I, E> Builder<I, E> {
     ///
     /// For a more convenient constructor, see [`Server::bind`](Server::bind).
     pub fn new(incoming: I, protocol: Http_<E>) -> Self {
-        Builder { incoming, protocol }
+        Builder {
+            incoming,
+            protocol,
+            connect_info: false,
+        }
     }
 
     /// Sets whether to use keep-alive for HTTP/1 connections.
@@ -278,6 +313,19 @@ impl<I, E> Builder<I, E> {
         self
     }
 
+    /// Set the minimum buffer size.
+    ///
+    /// Read buffers start at this size, and ones grown beyond it are released
+    /// while connections wait for a new request.
+    ///
+    /// Default is 8kb.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_min_buf_size(mut self, val: usize) -> Self {
+        self.protocol.min_buf_size(val);
+        self
+    }
+
     // Sets whether to bunch up HTTP/1 writes until the read buffer is empty.
     //
     // This isn't really desirable in most cases, only really being useful in
@@ -340,6 +388,171 @@ impl<I, E> Builder<I, E> {
         self
     }
 
+    /// Set whether to keep the raw bytes of each received request head.
+    ///
+    /// If such a `Request` is forwarded unmodified over an HTTP/1 client
+    /// connection, its head is written back out byte-for-byte.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_preserve_raw_head(mut self, val: bool) -> Self {
+        self.protocol.http1_preserve_raw_head(val);
+        self
+    }
+
+    /// Set whether to store the raw bytes of each received request head in a
+    /// [`RawHeaders`](crate::ext::RawHeaders) extension on the `Request`.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_headers_raw(mut self, val: bool) -> Self {
+        self.protocol.http1_headers_raw(val);
+        self
+    }
+
+    /// Set how requests sending `Expect: 100-continue` are handled.
+    ///
+    /// See [`ExpectContinue`](crate::server::conn::ExpectContinue) for the
+    /// choices.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is `ExpectContinue::Automatic`.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_expect_continue(mut self, policy: super::conn::ExpectContinue) -> Self {
+        self.protocol.http1_expect_continue(policy);
+        self
+    }
+
+    /// Set the maximum number of headers of a request.
+    ///
+    /// Requests with more headers are rejected with `431 Request Header
+    /// Fields Too Large`.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is 100.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_headers(mut self, max: usize) -> Self {
+        self.protocol.http1_max_headers(max);
+        self
+    }
+
+    /// Set the maximum size of a request header value, in bytes.
+    ///
+    /// Requests with a larger header value are rejected with `431 Request
+    /// Header Fields Too Large`.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is no limit other than the size of the read buffer.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_max_header_value_size(mut self, max: usize) -> Self {
+        self.protocol.http1_max_header_value_size(max);
+        self
+    }
+
+    /// Set how requests with multiple `Content-Length` values are read.
+    ///
+    /// See [`ContentLengthPolicy`](crate::ext::ContentLengthPolicy) for the
+    /// choices.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is `ContentLengthPolicy::MergeIfEqual`.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_content_length_policy(mut self, policy: crate::ext::ContentLengthPolicy) -> Self {
+        self.protocol.http1_content_length_policy(policy);
+        self
+    }
+
+    /// Set a callback choosing how response bodies of unknown length are
+    /// framed.
+    ///
+    /// The callback is called with the size hint of each such body. See
+    /// [`BodyFraming`](crate::ext::BodyFraming) for the choices, such as delimiting them by closing
+    /// the connection, or buffering small ones to send them with a
+    /// `Content-Length`.
+    ///
+    /// Note that this setting does not affect HTTP/2.
+    ///
+    /// Default is `BodyFraming::Chunked`.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_body_framing<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&crate::body::SizeHint) -> crate::ext::BodyFraming + Send + Sync + 'static,
+    {
+        self.protocol.http1_body_framing(f);
+        self
+    }
+
+    /// Set whether to reject requests with both a `Content-Length` and a
+    /// `Transfer-Encoding` header.
+    ///
+    /// See [`Http::http1_reject_content_length_with_transfer_encoding`](crate::server::conn::Http::http1_reject_content_length_with_transfer_encoding)
+    /// for more.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_content_length_with_transfer_encoding(mut self, enabled: bool) -> Self {
+        self.protocol
+            .http1_reject_content_length_with_transfer_encoding(enabled);
+        self
+    }
+
+    /// Set whether to reject requests with obsolete line folding.
+    ///
+    /// See [`Http::http1_reject_obs_fold`](crate::server::conn::Http::http1_reject_obs_fold)
+    /// for more.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_obs_fold(mut self, enabled: bool) -> Self {
+        self.protocol.http1_reject_obs_fold(enabled);
+        self
+    }
+
+    /// Set whether to reject requests with a CR that is not followed by a
+    /// LF in their head.
+    ///
+    /// See [`Http::http1_reject_bare_cr`](crate::server::conn::Http::http1_reject_bare_cr)
+    /// for more.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_bare_cr(mut self, enabled: bool) -> Self {
+        self.protocol.http1_reject_bare_cr(enabled);
+        self
+    }
+
+    /// Set whether to reject requests with whitespace between a header name
+    /// and its colon.
+    ///
+    /// See [`Http::http1_reject_whitespace_before_colon`](crate::server::conn::Http::http1_reject_whitespace_before_colon)
+    /// for more.
+    ///
+    /// Default is false.
+    #[cfg(feature = "http1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http1")))]
+    pub fn http1_reject_whitespace_before_colon(mut self, enabled: bool) -> Self {
+        self.protocol.http1_reject_whitespace_before_colon(enabled);
+        self
+    }
+
     /// Set a timeout for reading client request headers. If a client does not
     /// transmit the entire header within this time, the connection is closed.
     ///
@@ -371,6 +584,19 @@ impl<I, E> Builder<I, E> {
         self
     }
 
+    /// Sets whether HTTP/1.1 requests asking to upgrade the connection to
+    /// HTTP/2 over cleartext, with `Upgrade: h2c`, are honored.
+    ///
+    /// See [`Http::http2_upgrade`](crate::server::conn::Http::http2_upgrade).
+    ///
+    /// Default is `false`.
+    #[cfg(all(feature = "http1", feature = "http2"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "http1", feature = "http2"))))]
+    pub fn http2_upgrade(mut self, val: bool) -> Self {
+        self.protocol.http2_upgrade(val);
+        self
+    }
+
     /// Sets the [`SETTINGS_INITIAL_WINDOW_SIZE`][spec] option for HTTP2
     /// stream-level flow control.
     ///
@@ -436,6 +662,18 @@ impl<I, E> Builder<I, E> {
         self
     }
 
+    /// Sets the maximum number of streams a client can reset before they
+    /// were accepted, pending to be accepted.
+    ///
+    /// See [`Http::http2_max_pending_accept_reset_streams`](crate::server::conn::Http::http2_max_pending_accept_reset_streams)
+    /// for more.
+    #[cfg(feature = "http2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http2")))]
+    pub fn http2_max_pending_accept_reset_streams(mut self, max: impl Into<Option<usize>>) -> Self {
+        self.protocol.http2_max_pending_accept_reset_streams(max);
+        self
+    }
+
     /// Sets an interval for HTTP2 Ping frames should be sent to keep a
     /// connection alive.
     ///
@@ -493,6 +731,154 @@ impl<I, E> Builder<I, E> {
         self
     }
 
+    /// Only accept requests for the given hosts, answering others with
+    /// `421 Misdirected Request`.
+    ///
+    /// See [`Http::allowed_hosts`](crate::server::conn::Http::allowed_hosts)
+    /// for the format of the entries.
+    ///
+    /// Default is to accept any host.
+    pub fn allowed_hosts<H, S>(mut self, hosts: H) -> Self
+    where
+        H: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.protocol.allowed_hosts(hosts);
+        self
+    }
+
+    /// Set whether requests carry a
+    /// [`SendEarlyHints`](crate::server::conn::SendEarlyHints) in their
+    /// extensions, to send `103 Early Hints` responses before the final
+    /// response.
+    ///
+    /// Note that this setting does not affect HTTP/2 yet.
+    ///
+    /// Default is `false`.
+    pub fn early_hints(mut self, enabled: bool) -> Self {
+        self.protocol.early_hints(enabled);
+        self
+    }
+
+    /// Set whether responses without a `Date` header get one with the
+    /// current time.
+    ///
+    /// See [`Http::auto_date_header`](crate::server::conn::Http::auto_date_header)
+    /// for more.
+    ///
+    /// Default is `true`.
+    pub fn auto_date_header(mut self, enabled: bool) -> Self {
+        self.protocol.auto_date_header(enabled);
+        self
+    }
+
+    /// Set the maximum size of request bodies, in bytes.
+    ///
+    /// See [`Http::max_request_body_size`](crate::server::conn::Http::max_request_body_size)
+    /// for more.
+    ///
+    /// Default is no limit.
+    pub fn max_request_body_size(mut self, max: u64) -> Self {
+        self.protocol.max_request_body_size(max);
+        self
+    }
+
+    /// Set whether each request gets a [`ConnectInfo`] extension with the
+    /// details of its connection.
+    ///
+    /// The details are those returned by [`Accept::connect_info`] for the
+    /// connection, such as the remote address of connections accepted by
+    /// [`AddrIncoming`]. Requests of connections it returns `None` for get
+    /// no extension.
+    ///
+    /// Default is `false`.
+    ///
+    /// [`ConnectInfo`]: crate::ext::ConnectInfo
+    /// [`AddrIncoming`]: crate::server::conn::AddrIncoming
+    pub fn connect_info(mut self, enabled: bool) -> Self {
+        self.connect_info = enabled;
+        self
+    }
+
+    /// Answer health checks on the connection, before they reach the service.
+    ///
+    /// The check starts draining once the signal passed to
+    /// [`Server::with_graceful_shutdown`] resolves.
+    ///
+    /// See [`HealthCheck`](crate::server::conn::HealthCheck) for more.
+    pub fn health_check(mut self, check: HealthCheck) -> Self {
+        self.protocol.health_check(check);
+        self
+    }
+
+    /// Answer CORS preflight requests on the connection, following a policy.
+    ///
+    /// See [`CorsPolicy`](crate::server::conn::CorsPolicy) for more.
+    pub fn cors(mut self, policy: CorsPolicy) -> Self {
+        self.protocol.cors(policy);
+        self
+    }
+
+    /// Configure connections for a server exposed to untrusted clients.
+    ///
+    /// See [`Http::preset_strict_server`](crate::server::conn::Http::preset_strict_server)
+    /// for the options set.
+    pub fn preset_strict_server(mut self) -> Self {
+        self.protocol.preset_strict_server();
+        self
+    }
+
+    /// Configure connections for the accepting side of a proxy.
+    ///
+    /// See [`Http::preset_lenient_proxy`](crate::server::conn::Http::preset_lenient_proxy)
+    /// for the options set.
+    pub fn preset_lenient_proxy(mut self) -> Self {
+        self.protocol.preset_lenient_proxy();
+        self
+    }
+
+    /// Configure connections to use as little memory as possible.
+    ///
+    /// See [`Http::preset_low_memory`](crate::server::conn::Http::preset_low_memory)
+    /// for the options set.
+    pub fn preset_low_memory(mut self) -> Self {
+        self.protocol.preset_low_memory();
+        self
+    }
+
+    /// Configure connections with a [hardened profile](crate::hardened).
+    ///
+    /// See [`Http::preset_hardened`](crate::server::conn::Http::preset_hardened)
+    /// for more.
+    pub fn preset_hardened(mut self, profile: crate::hardened::Profile) -> Self {
+        self.protocol.preset_hardened(profile);
+        self
+    }
+
+    /// Returns the hardened profile applied with
+    /// [`preset_hardened`](Builder::preset_hardened), and the options of it
+    /// overridden since.
+    pub fn hardened_posture(&self) -> Option<&crate::hardened::Posture> {
+        self.protocol.hardened_posture()
+    }
+
+    /// Set an observer for the lifecycle events of connections.
+    ///
+    /// See the [`observer`](crate::observer) module for more.
+    pub fn observer(mut self, observer: Arc<dyn ConnectionObserver>) -> Self {
+        self.protocol.observer(observer);
+        self
+    }
+
+    /// Take options from a config that can be changed while serving, such
+    /// as the maximum number of connections.
+    ///
+    /// See [`LiveConfig`](crate::server::conn::LiveConfig) for more.
+    pub fn live_config(mut self, config: LiveConfig) -> Self {
+        self.protocol.live_config(config);
+        self
+    }
+
     /// Sets the `Executor` to deal with connection tasks.
     ///
     /// Default is `tokio::spawn`.
@@ -500,6 +886,7 @@ impl<I, E> Builder<I, E> {
         Builder {
             incoming: self.incoming,
             protocol: self.protocol.with_executor(executor),
+            connect_info: self.connect_info,
         }
     }
 
@@ -549,6 +936,7 @@ impl<I, E> Builder<I, E> {
             incoming: self.incoming,
             make_service,
             protocol: self.protocol.clone(),
+            connect_info: self.connect_info,
         }
     }
 }
@@ -635,7 +1023,7 @@ pub(crate) mod new_svc {
     use tokio::io::{AsyncRead, AsyncWrite};
     use tracing::debug;
 
-    use super::{Connecting, Watcher};
+    use super::{ConnGuard, Connecting, Watcher};
     use crate::body::{Body, HttpBody};
     use crate::common::exec::ConnStreamExec;
     use crate::common::{task, Future, Pin, Poll, Unpin};
@@ -657,6 +1045,8 @@ pub(crate) mod new_svc {
         pub struct NewSvcTask<I, N, S: HttpService<Body>, E, W: Watcher<I, S, E>> {
             #[pin]
             state: State<I, N, S, E, W>,
+            // Counts the connection against the maximum until it's done.
+            _guard: Option<ConnGuard>,
         }
     }
 
@@ -676,8 +1066,9 @@ pub(crate) mod new_svc {
     }
 
     impl<I, N, S: HttpService<Body>, E, W: Watcher<I, S, E>> NewSvcTask<I, N, S, E, W> {
-        pub(super) fn new(connecting: Connecting<I, N, E>, watcher: W) -> Self {
+        pub(super) fn new(mut connecting: Connecting<I, N, E>, watcher: W) -> Self {
             NewSvcTask {
+                _guard: connecting.guard.take(),
                 state: State::Connecting {
                     connecting,
                     watcher,
@@ -753,6 +1144,7 @@ pin_project! {
         future: F,
         io: Option<I>,
         protocol: Http_<E>,
+        guard: Option<ConnGuard>,
     }
 }
 
@@ -75,6 +75,9 @@ where
                     } => match signal.poll(cx) {
                         Poll::Ready(()) => {
                             debug!("signal received, starting graceful shutdown");
+                            if let Some(check) = server.health_check() {
+                                check.set_draining(true);
+                            }
                             let sig = drain.take().expect("drain channel").0;
                             State::Draining {
                                 draining: sig.drain(),
@@ -8,6 +8,7 @@ use tokio::time::Sleep;
 use tracing::{debug, error, trace};
 
 use crate::common::{task, Future, Pin, Poll};
+use crate::ext::ConnectInfo;
 
 #[allow(unreachable_pub)] // https://github.com/rust-lang/rust/issues/57411
 pub use self::addr_stream::AddrStream;
@@ -165,6 +166,10 @@ impl Accept for AddrIncoming {
         let result = ready!(self.poll_next_(cx));
         Poll::Ready(Some(result))
     }
+
+    fn connect_info(conn: &AddrStream) -> Option<ConnectInfo> {
+        Some(ConnectInfo::from_conn(conn))
+    }
 }
 
 /// This function defines errors that are per-connection. Which basically
@@ -203,6 +208,7 @@ mod addr_stream {
     use tokio::net::TcpStream;
 
     use crate::common::{task, Pin, Poll};
+    use crate::service::make::Conn;
 
     pin_project_lite::pin_project! {
         /// A transport returned yieled by `AddrIncoming`.
@@ -258,6 +264,16 @@ mod addr_stream {
         }
     }
 
+    impl Conn for AddrStream {
+        fn remote_addr(&self) -> Option<SocketAddr> {
+            Some(self.remote_addr)
+        }
+
+        fn local_addr(&self) -> Option<SocketAddr> {
+            Some(self.local_addr)
+        }
+    }
+
     impl AsyncRead for AddrStream {
         #[inline]
         fn poll_read(
@@ -1,106 +1,22 @@
+//! Making a `Service` for each connection.
+//!
+//! A server calls a `MakeService` with each connection it accepts, to make
+//! the `Service` responding to the requests of that connection.
+//!
+//! - [`make_service_fn`] makes services with an `async` function of the
+//!   connection's transport.
+//! - [`make_conn_service_fn`] makes services with a plain function of the
+//!   [`Conn`] information of the connection, such as its remote address or
+//!   negotiated protocol.
+
 use std::error::Error as StdError;
 use std::fmt;
+use std::future::{ready, Ready};
+use std::net::SocketAddr;
 
-use tokio::io::{AsyncRead, AsyncWrite};
-
-use super::{HttpService, Service};
-use crate::body::HttpBody;
+use super::Service;
 use crate::common::{task, Future, Poll};
 
-// The same "trait alias" as tower::MakeConnection, but inlined to reduce
-// dependencies.
-#[allow(dead_code)]
-pub trait MakeConnection<Target>: self::sealed::Sealed<(Target,)> {
-    type Connection: AsyncRead + AsyncWrite;
-    type Error;
-    type Future: Future<Output = Result<Self::Connection, Self::Error>>;
-
-    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>>;
-    fn make_connection(&mut self, target: Target) -> Self::Future;
-}
-
-impl<S, Target> self::sealed::Sealed<(Target,)> for S where S: Service<Target> {}
-
-impl<S, Target> MakeConnection<Target> for S
-where
-    S: Service<Target>,
-    S::Response: AsyncRead + AsyncWrite,
-{
-    type Connection = S::Response;
-    type Error = S::Error;
-    type Future = S::Future;
-
-    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Service::poll_ready(self, cx)
-    }
-
-    fn make_connection(&mut self, target: Target) -> Self::Future {
-        Service::call(self, target)
-    }
-}
-
-// Just a sort-of "trait alias" of `MakeService`, not to be implemented
-// by anyone, only used as bounds.
-#[allow(dead_code)]
-pub trait MakeServiceRef<Target, ReqBody>: self::sealed::Sealed<(Target, ReqBody)> {
-    type ResBody: HttpBody;
-    type Error: Into<Box<dyn StdError + Send + Sync>>;
-    type Service: HttpService<ReqBody, ResBody = Self::ResBody, Error = Self::Error>;
-    type MakeError: Into<Box<dyn StdError + Send + Sync>>;
-    type Future: Future<Output = Result<Self::Service, Self::MakeError>>;
-
-    // Acting like a #[non_exhaustive] for associated types of this trait.
-    //
-    // Basically, no one outside of fluxio should be able to set this type
-    // or declare bounds on it, so it should prevent people from creating
-    // trait objects or otherwise writing code that requires using *all*
-    // of the associated types.
-    //
-    // Why? So we can add new associated types to this alias in the future,
-    // if necessary.
-    type __DontNameMe: self::sealed::CantImpl;
-
-    fn poll_ready_ref(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::MakeError>>;
-
-    fn make_service_ref(&mut self, target: &Target) -> Self::Future;
-}
-
-impl<T, Target, E, ME, S, F, IB, OB> MakeServiceRef<Target, IB> for T
-where
-    T: for<'a> Service<&'a Target, Error = ME, Response = S, Future = F>,
-    E: Into<Box<dyn StdError + Send + Sync>>,
-    ME: Into<Box<dyn StdError + Send + Sync>>,
-    S: HttpService<IB, ResBody = OB, Error = E>,
-    F: Future<Output = Result<S, ME>>,
-    IB: HttpBody,
-    OB: HttpBody,
-{
-    type Error = E;
-    type Service = S;
-    type ResBody = OB;
-    type MakeError = ME;
-    type Future = F;
-
-    type __DontNameMe = self::sealed::CantName;
-
-    fn poll_ready_ref(&mut self, cx: &mut task::Context<'_>) -> Poll<Result<(), Self::MakeError>> {
-        self.poll_ready(cx)
-    }
-
-    fn make_service_ref(&mut self, target: &Target) -> Self::Future {
-        self.call(target)
-    }
-}
-
-impl<T, Target, S, B1, B2> self::sealed::Sealed<(Target, B1)> for T
-where
-    T: for<'a> Service<&'a Target, Response = S>,
-    S: HttpService<B1, ResBody = B2>,
-    B1: HttpBody,
-    B2: HttpBody,
-{
-}
-
 /// Create a `MakeService` from a function.
 ///
 /// # Example
@@ -176,15 +92,154 @@ impl<F> fmt::Debug for MakeServiceFn<F> {
     }
 }
 
-#[allow(dead_code)]
-mod sealed {
-    pub trait Sealed<X> {}
+/// Information about a connection, passed to the function of
+/// [`make_conn_service_fn`].
+///
+/// This is implemented for the transports of fluxio, such as
+/// [`AddrStream`](crate::server::conn::AddrStream). Transports adding TLS
+/// can implement it to expose what was negotiated in the handshake. Each
+/// method returns `None` by default.
+pub trait Conn {
+    /// Returns the address of the peer of the connection.
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        None
+    }
 
-    #[allow(unreachable_pub)] // This is intentional.
-    pub trait CantImpl {}
+    /// Returns the local address of the connection.
+    fn local_addr(&self) -> Option<SocketAddr> {
+        None
+    }
 
-    #[allow(missing_debug_implementations)]
-    pub enum CantName {}
+    /// Returns the server name the client asked for with TLS SNI.
+    fn tls_server_name(&self) -> Option<&str> {
+        None
+    }
 
-    impl CantImpl for CantName {}
+    /// Returns the DER encoded certificate the client authenticated with,
+    /// if the connection uses mutual TLS.
+    fn tls_peer_certificate(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// Returns the protocol negotiated with ALPN, such as `b"h2"`.
+    fn alpn_protocol(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+#[cfg(feature = "tcp")]
+impl Conn for tokio::net::TcpStream {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr().ok()
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        tokio::net::TcpStream::local_addr(self).ok()
+    }
+}
+
+/// Create a `MakeService` from a function of the [`Conn`] information of
+/// each connection.
+///
+/// Unlike [`make_service_fn`], the function returns the service right away,
+/// so it doesn't need an `async` block, and it is passed the connection as
+/// a `&dyn Conn`, so it needs no type annotations.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "runtime")]
+/// # async fn run() {
+/// use std::convert::Infallible;
+/// use fluxio::{Body, Request, Response, Server};
+/// use fluxio::service::make::make_conn_service_fn;
+/// use fluxio::service::service_fn;
+///
+/// let addr = ([127, 0, 0, 1], 3000).into();
+///
+/// let make_svc = make_conn_service_fn(|conn| {
+///     let remote_addr = conn.remote_addr();
+///     Ok::<_, Infallible>(service_fn(move |_: Request<Body>| async move {
+///         Ok::<_, Infallible>(
+///             Response::new(Body::from(format!("Hello, {:?}!", remote_addr)))
+///         )
+///     }))
+/// });
+///
+/// // Then bind and serve...
+/// let server = Server::bind(&addr)
+///     .serve(make_svc);
+///
+/// // Finally, spawn `server` onto an Executor...
+/// if let Err(e) = server.await {
+///     eprintln!("server error: {}", e);
+/// }
+/// # }
+/// # fn main() {}
+/// ```
+pub fn make_conn_service_fn<F, Svc, MkErr>(f: F) -> MakeConnServiceFn<F>
+where
+    F: FnMut(&dyn Conn) -> Result<Svc, MkErr>,
+{
+    MakeConnServiceFn { f }
+}
+
+/// `MakeService` returned from [`make_conn_service_fn`]
+#[derive(Clone, Copy)]
+pub struct MakeConnServiceFn<F> {
+    f: F,
+}
+
+impl<'t, F, Target, Svc, MkErr> Service<&'t Target> for MakeConnServiceFn<F>
+where
+    F: FnMut(&dyn Conn) -> Result<Svc, MkErr>,
+    Target: Conn,
+    MkErr: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Error = MkErr;
+    type Response = Svc;
+    type Future = Ready<Result<Svc, MkErr>>;
+
+    fn poll_ready(&mut self, _cx: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, target: &'t Target) -> Self::Future {
+        ready((self.f)(target))
+    }
+}
+
+impl<F> fmt::Debug for MakeConnServiceFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MakeConnServiceFn").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    struct TlsConn;
+
+    impl Conn for TlsConn {
+        fn remote_addr(&self) -> Option<SocketAddr> {
+            Some(([10, 0, 0, 1], 443).into())
+        }
+
+        fn alpn_protocol(&self) -> Option<&[u8]> {
+            Some(b"h2")
+        }
+    }
+
+    #[tokio::test]
+    async fn make_conn_service_fn_passes_conn() {
+        let mut make_svc = make_conn_service_fn(|conn| {
+            Ok::<_, Infallible>((conn.remote_addr(), conn.alpn_protocol().map(<[u8]>::to_vec)))
+        });
+        let svc = make_svc.call(&TlsConn).await.unwrap();
+        assert_eq!(svc.0, Some(([10, 0, 0, 1], 443).into()));
+        assert_eq!(svc.1.as_deref(), Some(&b"h2"[..]));
+    }
 }
@@ -37,17 +37,18 @@
 
 pub use tower_service::Service;
 
+mod alias;
 mod http;
-mod make;
+pub mod make;
 #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
 mod oneshot;
 mod util;
 
-pub(super) use self::http::HttpService;
 #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
-pub(super) use self::make::MakeConnection;
+pub(super) use self::alias::MakeConnection;
 #[cfg(all(any(feature = "http1", feature = "http2"), feature = "server"))]
-pub(super) use self::make::MakeServiceRef;
+pub(super) use self::alias::MakeServiceRef;
+pub(super) use self::http::HttpService;
 #[cfg(all(any(feature = "http1", feature = "http2"), feature = "client"))]
 pub(super) use self::oneshot::{oneshot, Oneshot};
 
@@ -0,0 +1,250 @@
+//! Utilities for testing code using fluxio.
+//!
+//! [`duplex`] creates a pair of connected in-memory transports, so that a
+//! test can serve a connection on one end and drive it from the other,
+//! through full request and response cycles, without sockets. [`ChaosIo`]
+//! wraps a transport to inject faults, to exercise the error paths of
+//! connections.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(all(feature = "server", feature = "http1"))]
+//! # async fn run() {
+//! use std::convert::Infallible;
+//!
+//! use fluxio::server::conn::Http;
+//! use fluxio::service::service_fn;
+//! use fluxio::{Body, Request, Response};
+//! use tokio::io::{AsyncReadExt, AsyncWriteExt};
+//!
+//! let (mut client, server) = fluxio::testing::duplex(1024);
+//! let svc = service_fn(|_: Request<Body>| async {
+//!     Ok::<_, Infallible>(Response::new(Body::from("hello")))
+//! });
+//! tokio::spawn(Http::new().serve_connection(server, svc));
+//!
+//! client
+//!     .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\nconnection: close\r\n\r\n")
+//!     .await
+//!     .unwrap();
+//! let mut res = Vec::new();
+//! client.read_to_end(&mut res).await.unwrap();
+//! assert!(res.ends_with(b"\r\n\r\nhello"));
+//! # }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::{task, Pin, Poll};
+
+pub use self::chaos::ChaosIo;
+
+mod chaos;
+
+/// Creates a pair of connected in-memory transports.
+///
+/// Bytes written to one end can be read from the other. Each direction
+/// buffers up to `capacity` bytes, after which writes wait until the other
+/// end reads. Once an end is shut down or dropped, reads from the other end
+/// reach the end of the stream, and once an end is dropped, writes to it
+/// from the other end fail with `BrokenPipe`.
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0.
+pub fn duplex(capacity: usize) -> (Duplex, Duplex) {
+    assert!(capacity > 0, "duplex capacity must be larger than 0");
+    let one = Arc::new(Mutex::new(Pipe::new(capacity)));
+    let two = Arc::new(Mutex::new(Pipe::new(capacity)));
+    (
+        Duplex {
+            read: one.clone(),
+            write: two.clone(),
+        },
+        Duplex {
+            read: two,
+            write: one,
+        },
+    )
+}
+
+/// One end of an in-memory transport, returned from [`duplex`].
+pub struct Duplex {
+    read: Arc<Mutex<Pipe>>,
+    write: Arc<Mutex<Pipe>>,
+}
+
+/// The bytes written in one direction, not read yet.
+struct Pipe {
+    buf: BytesMut,
+    capacity: usize,
+    /// Whether the writing end was shut down or dropped.
+    write_closed: bool,
+    /// Whether the reading end was dropped.
+    read_closed: bool,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+}
+
+impl Pipe {
+    fn new(capacity: usize) -> Pipe {
+        Pipe {
+            buf: BytesMut::new(),
+            capacity,
+            write_closed: false,
+            read_closed: false,
+            read_waker: None,
+            write_waker: None,
+        }
+    }
+
+    fn wake_reader(&mut self) {
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn wake_writer(&mut self) {
+        if let Some(waker) = self.write_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl AsyncRead for Duplex {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut pipe = self.read.lock().unwrap();
+        if pipe.buf.has_remaining() {
+            let n = pipe.buf.len().min(buf.remaining());
+            buf.put_slice(&pipe.buf[..n]);
+            pipe.buf.advance(n);
+            pipe.wake_writer();
+            Poll::Ready(Ok(()))
+        } else if pipe.write_closed {
+            Poll::Ready(Ok(()))
+        } else {
+            pipe.read_waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl AsyncWrite for Duplex {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = self.write.lock().unwrap();
+        if pipe.read_closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        if pipe.write_closed {
+            return Poll::Ready(Err(io::Error::other("write after shutdown")));
+        }
+        let n = buf.len().min(pipe.capacity - pipe.buf.len());
+        if n == 0 && !buf.is_empty() {
+            pipe.write_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        pipe.buf.extend_from_slice(&buf[..n]);
+        pipe.wake_reader();
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let mut pipe = self.write.lock().unwrap();
+        pipe.write_closed = true;
+        pipe.wake_reader();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for Duplex {
+    fn drop(&mut self) {
+        let mut pipe = self.write.lock().unwrap();
+        pipe.write_closed = true;
+        pipe.wake_reader();
+        drop(pipe);
+
+        let mut pipe = self.read.lock().unwrap();
+        pipe.read_closed = true;
+        pipe.wake_writer();
+    }
+}
+
+impl fmt::Debug for Duplex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Duplex").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_wait_for_reads() {
+        let (mut one, mut two) = duplex(4);
+
+        assert_eq!(one.write(b"hello").await.unwrap(), 4);
+        let mut buf = [0; 8];
+        let write = tokio::spawn(async move {
+            one.write_all(b"o world").await.unwrap();
+            one
+        });
+        two.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello wo");
+
+        // dropping an end ends the stream after the buffered bytes
+        drop(write.await.unwrap());
+        let mut rest = Vec::new();
+        two.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"rld");
+        let err = two.write(b"x").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[cfg(all(feature = "client", feature = "server", feature = "http1"))]
+    #[tokio::test]
+    async fn request_response_cycle() {
+        use std::convert::Infallible;
+
+        use crate::server::conn::Http;
+        use crate::service::service_fn;
+        use crate::{body, Body, Request, Response};
+
+        let (client, server) = duplex(64);
+        let svc = service_fn(|req: Request<Body>| async move {
+            let body = body::to_bytes(req.into_body()).await.unwrap();
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        });
+        tokio::spawn(Http::new().serve_connection(server, svc));
+
+        let (mut tx, conn) = crate::client::conn::handshake(client).await.unwrap();
+        tokio::spawn(conn);
+        let req = Request::post("/echo")
+            .body(Body::from(vec![b'x'; 1000]))
+            .unwrap();
+        let res = tx.send_request(req).await.unwrap();
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body.len(), 1000);
+    }
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::common::{task, Pin, Poll};
+
+/// A transport injecting faults into the reads and writes of another, to
+/// exercise how connections handle them.
+///
+/// The faults are picked with a pseudo-random generator seeded with the
+/// seed passed to [`ChaosIo::new`], so a failing schedule can be replayed
+/// by reusing its seed. Each kind of fault is disabled until enabled with
+/// its method:
+///
+/// - [`partial_writes`](ChaosIo::partial_writes) writes only part of a
+///   buffer,
+/// - [`delayed_reads`](ChaosIo::delayed_reads) returns `Pending` from a read
+///   once before reading,
+/// - [`spurious_wakeups`](ChaosIo::spurious_wakeups) wakes the task when the
+///   transport returns `Pending`, without it being ready,
+/// - [`eof_after`](ChaosIo::eof_after) ends the stream in the middle.
+///
+/// # Example
+///
+/// ```
+/// use fluxio::testing::{duplex, ChaosIo};
+///
+/// let (client, _server) = duplex(1024);
+/// let client = ChaosIo::new(client, 42)
+///     .partial_writes(0.5)
+///     .delayed_reads(0.25)
+///     .eof_after(100);
+/// ```
+pub struct ChaosIo<T> {
+    inner: T,
+    rng: u64,
+    partial_writes: f64,
+    delayed_reads: f64,
+    spurious_wakeups: f64,
+    eof_after: Option<u64>,
+    read_delayed: bool,
+    bytes_read: u64,
+}
+
+impl<T> ChaosIo<T> {
+    /// Wraps a transport, picking faults with a generator seeded with
+    /// `seed`.
+    pub fn new(inner: T, seed: u64) -> ChaosIo<T> {
+        ChaosIo {
+            inner,
+            rng: seed,
+            partial_writes: 0.0,
+            delayed_reads: 0.0,
+            spurious_wakeups: 0.0,
+            eof_after: None,
+            read_delayed: false,
+            bytes_read: 0,
+        }
+    }
+
+    /// Sets the probability that a write only writes part of its buffer,
+    /// but at least one byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn partial_writes(mut self, probability: f64) -> Self {
+        self.partial_writes = check_probability(probability);
+        self
+    }
+
+    /// Sets the probability that a read first returns `Pending`, waking the
+    /// task right away so that it is retried.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn delayed_reads(mut self, probability: f64) -> Self {
+        self.delayed_reads = check_probability(probability);
+        self
+    }
+
+    /// Sets the probability that the task is woken when the transport
+    /// returns `Pending`, although it is not ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between 0 and 1.
+    pub fn spurious_wakeups(mut self, probability: f64) -> Self {
+        self.spurious_wakeups = check_probability(probability);
+        self
+    }
+
+    /// Ends the stream once `bytes` were read, as if the peer closed the
+    /// connection.
+    pub fn eof_after(mut self, bytes: u64) -> Self {
+        self.eof_after = Some(bytes);
+        self
+    }
+
+    /// Returns a reference to the wrapped transport.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped transport.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped transport.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// The next value of a splitmix64 generator.
+    fn next_u64(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns `true` with the given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// Wakes the task with the configured probability, if `poll` is
+    /// `Pending`.
+    fn maybe_wake<R>(&mut self, cx: &mut task::Context<'_>, poll: Poll<R>) -> Poll<R> {
+        if poll.is_pending() && self.chance(self.spurious_wakeups) {
+            cx.waker().wake_by_ref();
+        }
+        poll
+    }
+}
+
+fn check_probability(probability: f64) -> f64 {
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "probability must be between 0 and 1"
+    );
+    probability
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ChaosIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let remaining = match this.eof_after {
+            Some(eof_after) if this.bytes_read >= eof_after => return Poll::Ready(Ok(())),
+            Some(eof_after) => eof_after - this.bytes_read,
+            None => u64::MAX,
+        };
+        if !this.read_delayed && this.chance(this.delayed_reads) {
+            this.read_delayed = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let limit = buf
+            .remaining()
+            .min(remaining.try_into().unwrap_or(usize::MAX));
+        let mut limited = buf.take(limit);
+        let poll = Pin::new(&mut this.inner).poll_read(cx, &mut limited);
+        let poll = this.maybe_wake(cx, poll);
+        ready!(poll)?;
+        this.read_delayed = false;
+
+        let n = limited.filled().len();
+        // Safety: the bytes were initialized by the read into `limited`.
+        unsafe { buf.assume_init(n) };
+        buf.advance(n);
+        this.bytes_read += n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ChaosIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let mut len = buf.len();
+        if len > 1 && this.chance(this.partial_writes) {
+            len = 1 + (this.next_u64() % (len as u64 - 1)) as usize;
+        }
+        let poll = Pin::new(&mut this.inner).poll_write(cx, &buf[..len]);
+        this.maybe_wake(cx, poll)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_flush(cx);
+        this.maybe_wake(cx, poll)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_shutdown(cx);
+        this.maybe_wake(cx, poll)
+    }
+}
+
+impl<T> fmt::Debug for ChaosIo<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaosIo")
+            .field("partial_writes", &self.partial_writes)
+            .field("delayed_reads", &self.delayed_reads)
+            .field("spurious_wakeups", &self.spurious_wakeups)
+            .field("eof_after", &self.eof_after)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::testing::duplex;
+
+    #[tokio::test]
+    async fn faults_are_seeded() {
+        let lens = |seed| async move {
+            let (client, _server) = duplex(1024);
+            let mut client = ChaosIo::new(client, seed).partial_writes(1.0);
+            let mut lens = Vec::new();
+            for _ in 0..8 {
+                lens.push(client.write(&[0; 100]).await.unwrap());
+            }
+            lens
+        };
+        let first = lens(7).await;
+        assert!(first.iter().all(|&n| (1..100).contains(&n)), "{:?}", first);
+        assert_eq!(first, lens(7).await);
+        assert_ne!(first, lens(8).await);
+    }
+
+    #[tokio::test]
+    async fn eof_in_the_middle() {
+        let (client, mut server) = duplex(1024);
+        let mut client = ChaosIo::new(client, 1)
+            .delayed_reads(0.5)
+            .spurious_wakeups(1.0)
+            .eof_after(5);
+        server.write_all(b"hello world").await.unwrap();
+
+        let mut buf = Vec::new();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello");
+    }
+
+    #[cfg(all(feature = "client", feature = "server", feature = "http1"))]
+    #[tokio::test]
+    async fn connections_survive_partial_writes_and_delays() {
+        use std::convert::Infallible;
+
+        use crate::server::conn::Http;
+        use crate::service::service_fn;
+        use crate::{body, Body, Request, Response};
+
+        let (client, server) = duplex(64);
+        let server = ChaosIo::new(server, 3)
+            .partial_writes(0.5)
+            .delayed_reads(0.5)
+            .spurious_wakeups(0.5);
+        let svc = service_fn(|req: Request<Body>| async move {
+            let body = body::to_bytes(req.into_body()).await.unwrap();
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        });
+        tokio::spawn(Http::new().serve_connection(server, svc));
+
+        let client = ChaosIo::new(client, 4).partial_writes(0.5);
+        let (mut tx, conn) = crate::client::conn::handshake(client).await.unwrap();
+        tokio::spawn(conn);
+        let req = Request::post("/echo")
+            .body(Body::from(vec![b'x'; 1000]))
+            .unwrap();
+        let res = tx.send_request(req).await.unwrap();
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body.len(), 1000);
+    }
+
+    #[cfg(all(feature = "client", feature = "http1"))]
+    #[tokio::test]
+    async fn eof_in_the_middle_of_a_response() {
+        use crate::{Body, Request};
+
+        let (client, mut server) = duplex(1024);
+        let client = ChaosIo::new(client, 5).eof_after(43);
+        let (mut tx, conn) = crate::client::conn::handshake(client).await.unwrap();
+        tokio::spawn(conn);
+        tokio::spawn(async move {
+            let mut buf = [0; 1024];
+            let _ = server.read(&mut buf).await;
+            server
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\nhello")
+                .await
+                .unwrap();
+            // keep the transport open, only the chaos ends it
+            let _ = server.read(&mut buf).await;
+        });
+
+        let res = tx.send_request(Request::new(Body::empty())).await.unwrap();
+        let err = crate::body::to_bytes(res.into_body()).await.unwrap_err();
+        let cause = std::error::Error::source(&err)
+            .and_then(|cause| cause.downcast_ref::<io::Error>())
+            .unwrap();
+        assert_eq!(cause.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}